@@ -141,7 +141,7 @@ impl RawDataSource for ACTDataLoader {
             }
         }
         if !prefs.is_empty() { btl.add(prefs.clone()); }
-        Ok(ElectionData{ metadata, atl:vec![], atl_types: vec![], atl_transfer_values: vec![], btl:btl.to_btls(), btl_types: vec![], btl_transfer_values: vec![], informal:0 })
+        Ok(ElectionData{ metadata, atl:vec![], atl_types: vec![], atl_transfer_values: vec![], btl:btl.to_btls(), btl_types: vec![], btl_transfer_values: vec![], atl_annotations: vec![], btl_annotations: vec![], informal:0 })
     }
 
     fn read_raw_data_best_quality(&self, electorate: &str) -> anyhow::Result<ElectionData> {
@@ -179,6 +179,7 @@ impl RawDataSource for ACTDataLoader {
             enrolment: None,
             secondary_vacancies: None,
             excluded: self.excluded_candidates(electorate),
+            withdrawn_before_count: vec![],
             tie_resolutions : self.ec_decisions(electorate),
         })
     }
@@ -259,6 +260,7 @@ impl ACTDataLoader {
                     atl_allowed: false,
                     candidates: vec![],
                     tickets: vec![],
+                    official_atl_votes: None,
                 })
             }
         }
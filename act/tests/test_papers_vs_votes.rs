@@ -0,0 +1,60 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that ballot papers and (fractional) votes are both tracked in every count of an ACT count with transfer values less than 1.
+
+use act::ACT2021;
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use stv::ballot_paper::BTL;
+use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+
+#[test]
+fn test_papers_vs_votes() {
+    let vote_data = ElectionData {
+        metadata: ElectionMetadata {
+            candidates: vec![
+                Candidate::from_name("A"),
+                Candidate::from_name("B"),
+                Candidate::from_name("C"),
+                Candidate::from_name("D"),
+            ],
+            vacancies: Some(NumberOfCandidates(2)),
+            ..Default::default()
+        },
+        btl: vec![
+            BTL{ candidates: vec![CandidateIndex(0),CandidateIndex(1)], n: 62 },
+            BTL{ candidates: vec![CandidateIndex(0),CandidateIndex(2)], n: 39 },
+            BTL{ candidates: vec![CandidateIndex(1)], n: 30 },
+            BTL{ candidates: vec![CandidateIndex(2)], n: 35 },
+            BTL{ candidates: vec![CandidateIndex(3)], n: 25 },
+        ],
+        ..Default::default()
+    };
+    let transcript = vote_data.distribute_preferences::<ACT2021>(&mut Randomness::ReverseDonkeyVote);
+    // Papers are never lost to rounding, so every paper is accounted for in every count, unlike votes.
+    for count in &transcript.counts {
+        assert_eq!(count.status.papers.candidate.len(),4);
+        assert!(count.status.atl_papers.is_some());
+        let total : usize = count.status.papers.candidate.iter().map(|p|p.0).sum::<usize>()+count.status.papers.exhausted.0;
+        assert_eq!(total,191);
+    }
+    // quota is 64, so A's surplus of 37 is distributed over 101 papers, with transfer value 37/101.
+    let after_surplus = transcript.count(CountIndex(1));
+    assert_eq!(after_surplus.status.papers.candidate[1],BallotPaperCount(92));
+    assert_eq!("52.712871",after_surplus.status.tallies.candidate[1].to_string());
+    assert_eq!(after_surplus.paper_vote_ratio(CandidateIndex(1)),Some(52.712871/92.0));
+    assert_eq!(after_surplus.paper_vote_ratio(CandidateIndex(3)),Some(1.0));
+    // A keeps a quota of votes but none of the papers, which have all been transferred.
+    assert_eq!(after_surplus.status.papers.candidate[0],BallotPaperCount(0));
+    assert_eq!(after_surplus.paper_vote_ratio(CandidateIndex(0)),None);
+    // D's 25 papers and C's 74 exhaust, but 39 of C's papers came from A and carry only the transfer value.
+    let last = transcript.counts.last().unwrap();
+    assert_eq!(last.status.papers.exhausted,BallotPaperCount(99));
+    assert_eq!("74.287128",last.status.tallies.exhausted.to_string());
+}
@@ -0,0 +1,66 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the transfer value ledger on a small contest counted with the ACT's fixed precision rules.
+
+use act::ACT2021;
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NameFormat, NumberOfCandidates};
+use stv::ballot_paper::BTL;
+use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+use stv::transfer_value::{DecimalRounding, TransferValueFormat};
+use stv::transfer_value_ledger::write_transfer_value_ledger_csv;
+
+#[test]
+fn test_transfer_value_ledger() {
+    let vote_data = ElectionData {
+        metadata: ElectionMetadata {
+            candidates: vec![
+                Candidate::from_name("A"),
+                Candidate::from_name("B"),
+                Candidate::from_name("C"),
+                Candidate::from_name("D"),
+            ],
+            vacancies: Some(NumberOfCandidates(2)),
+            ..Default::default()
+        },
+        btl: vec![
+            BTL{ candidates: vec![CandidateIndex(0),CandidateIndex(1)], n: 62 },
+            BTL{ candidates: vec![CandidateIndex(0),CandidateIndex(2)], n: 39 },
+            BTL{ candidates: vec![CandidateIndex(1)], n: 30 },
+            BTL{ candidates: vec![CandidateIndex(2)], n: 35 },
+            BTL{ candidates: vec![CandidateIndex(3)], n: 25 },
+        ],
+        ..Default::default()
+    };
+    let transcript = vote_data.distribute_preferences::<ACT2021>(&mut Randomness::ReverseDonkeyVote);
+    // quota is 191/3+1 = 64, so A has a surplus of 37 over 101 continuing ballots.
+    let ledger = transcript.transfer_value_ledger();
+    assert_eq!(1,ledger.len());
+    let entry = &ledger[0];
+    assert_eq!(CountIndex(1),entry.count);
+    assert_eq!(Some(CandidateIndex(0)),entry.source_candidate);
+    assert_eq!("37",entry.surplus.to_string());
+    assert_eq!("101",entry.votes.to_string());
+    assert_eq!(BallotPaperCount(101),entry.continuing_ballots);
+    assert_eq!("37",entry.numerator);
+    assert_eq!("101",entry.denominator);
+    // 62*37/101 = 22.7128712..., rounded down to 6 decimal places.
+    assert_eq!("52.712871",transcript.count(CountIndex(1)).status.tallies.candidate[1].to_string());
+    let mut csv : Vec<u8> = vec![];
+    write_transfer_value_ledger_csv(&ledger,Some(&vote_data.metadata),NameFormat::AsStored,TransferValueFormat::Exact,&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines : Vec<&str> = csv.lines().collect();
+    assert_eq!(2,lines.len());
+    assert_eq!("2,A,37,101,,1,,101,101,37/101,37,101,SurplusOverContinuingBallotsLimitedToPriorTransferValue",lines[1]);
+    // same thing with decimal transfer values. 37/101 = 0.3663366...
+    let mut csv : Vec<u8> = vec![];
+    write_transfer_value_ledger_csv(&ledger,Some(&vote_data.metadata),NameFormat::AsStored,TransferValueFormat::Decimal{places:6,rounding:DecimalRounding::Nearest},&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!("2,A,37,101,,1.000000,,101,101,0.366337,37,101,SurplusOverContinuingBallotsLimitedToPriorTransferValue",csv.lines().nth(1).unwrap());
+}
@@ -0,0 +1,52 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Rules for Cambridge, Massachusetts city council and school committee elections, which use
+//! the Cincinnati method for surplus transfers: rather than transferring all ballots at a fractional
+//! transfer value, every nth ballot of the last parcel is transferred whole.
+
+use stv::ballot_pile::{BallotPaperCount, DoNotSplitByCountNumber};
+use stv::preference_distribution::{BigRational, LastParcelUse, PreferenceDistributionRules, SurplusTransferMethod, TransferValueMethod, WhenToDoElectCandidateClauseChecking};
+use stv::tie_resolution::MethodOfTieResolution;
+use stv::transfer_value::{convert_usize_to_rational, round_rational_down_to_usize, TransferValue};
+
+pub mod parse;
+
+/// My understanding of the Cambridge rules, as far as the surplus transfer goes.
+///
+/// This does not model some other details of the Cambridge count, in particular that in the first
+/// count ballots for a candidate who has already reached the quota go straight to their next preference,
+/// and that all candidates with fewer than 50 votes are excluded together after the first count.
+pub struct CambridgeMA {}
+
+impl PreferenceDistributionRules for CambridgeMA {
+    type Tally = usize;
+    type SplitByNumber = DoNotSplitByCountNumber;
+
+    /// Surplus ballots are drawn from the ballots that made the candidate reach the quota.
+    fn use_last_parcel_for_surplus_distribution() -> LastParcelUse { LastParcelUse::LiterallyLast }
+    /// Not really used, as whole ballots are transferred.
+    fn transfer_value_method() -> TransferValueMethod { TransferValueMethod::SurplusOverBallots }
+    fn convert_tally_to_rational(tally: Self::Tally) -> BigRational { convert_usize_to_rational(tally) }
+    fn convert_rational_to_tally_after_applying_transfer_value(rational: BigRational) -> Self::Tally { round_rational_down_to_usize(rational) }
+    fn make_transfer_value(surplus: usize, ballots: BallotPaperCount) -> TransferValue { TransferValue::from_surplus(surplus,ballots) }
+    fn use_transfer_value(transfer_value: &TransferValue, ballots: BallotPaperCount) -> usize { transfer_value.mul_rounding_down(ballots) }
+    fn surplus_distribution_subdivisions() -> SurplusTransferMethod { SurplusTransferMethod::CincinnatiEveryNth }
+    fn sort_exclusions_by_transfer_value() -> bool { false }
+    /// Ties are resolved by lot.
+    fn resolve_ties_elected_one_of_last_two() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_elected_by_quota() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_elected_all_remaining() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_choose_lowest_candidate_for_exclusion() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn check_elected_if_in_middle_of_surplus_distribution() -> bool { true }
+    fn check_elected_if_in_middle_of_exclusion() -> bool { true }
+    fn finish_all_counts_in_elimination_when_all_elected() -> bool { false }
+    fn finish_all_surplus_distributions_when_all_elected() -> bool { false }
+    fn when_to_check_if_just_two_standing_for_shortcut_election() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::Never }
+    fn when_to_check_if_all_remaining_should_get_elected() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::AfterCheckingQuota }
+    fn when_to_check_if_top_few_have_overwhelming_votes() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::Never }
+    fn name() -> String { "CambridgeMA".to_string() }
+}
@@ -0,0 +1,79 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Parse Cambridge ballot data.
+//!
+//! This is a CSV file of ballot images, one ballot per row, in the order in which the ballots were counted,
+//! which matters for the Cincinnati method. The first row is a header. Columns whose header is a positive integer
+//! contain the candidate ranked at that position (blank if none); other columns, such as a ballot identifier or precinct, are ignored.
+//!
+//! Candidates are listed in alphabetical order. A candidate ranked more than once only counts at their highest ranking.
+//! A ballot with no candidates ranked is informal.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use stv::ballot_metadata::{Candidate, CandidateIndex, DataSource, ElectionMetadata, ElectionName, NumberOfCandidates};
+use stv::ballot_paper::BTL;
+use stv::election_data::ElectionData;
+use stv::parse_util::ParseError;
+
+pub fn parse<P:AsRef<Path>>(path:P,vacancies:NumberOfCandidates) -> Result<ElectionData,ParseError> {
+    let file = ParseError::open(path.as_ref())?;
+    let filename = path.as_ref().file_name().and_then(|s|s.to_str()).unwrap_or("");
+    parse_reader(file,filename,vacancies)
+}
+
+/// Like [parse], but read from something other than a file. The filename is used for the election name and source.
+pub fn parse_reader<R:Read>(reader:R,filename:&str,vacancies:NumberOfCandidates) -> Result<ElectionData,ParseError> {
+    let mut csv = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+    let headers = csv.headers().map_err(|e|ParseError::MalformedMetadata{line_number:1,reason:e.to_string()})?.clone();
+    let mut rank_columns : Vec<(usize,usize)> = headers.iter().enumerate().filter_map(|(column,h)|h.trim().parse::<usize>().ok().filter(|&rank|rank>0).map(|rank|(rank,column))).collect();
+    if rank_columns.is_empty() { return Err(ParseError::MalformedMetadata{line_number:1,reason:"No rank columns (with a positive integer heading) found".to_string()}); }
+    rank_columns.sort();
+    let mut rankings : Vec<Vec<String>> = vec![];
+    for (row,record) in csv.records().enumerate() {
+        let record = record.map_err(|e|ParseError::MalformedPreferenceLine{line_number:row+2,reason:e.to_string()})?;
+        rankings.push(rank_columns.iter().filter_map(|&(_,column)|record.get(column)).map(|name|name.trim()).filter(|name|!name.is_empty()).map(|name|name.to_string()).collect());
+    }
+    let mut names : Vec<&String> = rankings.iter().flatten().collect();
+    names.sort();
+    names.dedup();
+    let lookup : HashMap<&String,CandidateIndex> = names.iter().enumerate().map(|(i,&name)|(name,CandidateIndex(i))).collect();
+    let mut btl : Vec<BTL> = vec![];
+    let mut informal = 0;
+    for ranking in &rankings {
+        let mut candidates : Vec<CandidateIndex> = vec![];
+        for name in ranking {
+            let candidate = lookup[name];
+            if !candidates.contains(&candidate) { candidates.push(candidate); }
+        }
+        if candidates.is_empty() { informal+=1; }
+        else if let Some(last) = btl.last_mut().filter(|last|last.candidates==candidates) { last.n+=1; } // merge consecutive identical ballots, keeping the order.
+        else { btl.push(BTL{candidates,n:1}); }
+    }
+    let metadata = ElectionMetadata{
+        name: ElectionName {
+            year: "".to_string(),
+            authority: "Cambridge Election Commission".to_string(),
+            name: filename.to_string(),
+            electorate: "".to_string(),
+            modifications: vec![],
+            comment: None,
+        },
+        candidates: names.iter().map(|name|Candidate::from_name(name)).collect(),
+        parties: vec![],
+        source: vec![DataSource{url:"".to_string(),files:vec![filename.to_string()],comments:None}],
+        results: None,
+        vacancies: Some(vacancies),
+        enrolment: None,
+        secondary_vacancies: None,
+        excluded: vec![],
+        withdrawn_before_count: vec![],
+        tie_resolutions: Default::default(),
+    };
+    Ok(ElectionData{ metadata, atl: vec![], atl_types: vec![], atl_transfer_values: vec![], btl, btl_types: vec![], btl_transfer_values: vec![], atl_annotations: vec![], btl_annotations: vec![], informal })
+}
@@ -0,0 +1,78 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test the Cincinnati method surplus transfer and the Cambridge ballot data parser.
+
+use cambridge::CambridgeMA;
+use cambridge::parse::parse_reader;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::election_data::ElectionData;
+use stv::parse_util::FileFinder;
+use stv::random_util::Randomness;
+
+/// Ten ballots for 2 vacancies, so the quota is 4. Alice has 6 first preferences, a surplus of 2.
+/// Her ballots in order are (Bob, Carol, Carol, Bob, Dave, Carol) after her, so every third ballot gives 2 to Carol.
+const BALLOTS : &str = "Ballot,Precinct,1,2,3
+1,1-1,Alice,Bob,
+2,1-1,Alice,Carol,
+3,1-1,Bob,,
+4,1-1,Alice,Carol,
+5,1-2,Alice,Bob,Bob
+6,1-2,Carol,,
+7,1-2,Alice,Dave,
+8,2-1,Alice,Carol,
+9,2-1,Dave,,
+10,2-1,Bob,,
+11,2-1,,,
+";
+
+fn parse(ballots:&str) -> ElectionData { parse_reader(ballots.as_bytes(),"test.csv",NumberOfCandidates(2)).unwrap() }
+
+#[test]
+fn test_parse() {
+    let data = parse(BALLOTS);
+    assert_eq!(vec!["Alice","Bob","Carol","Dave"],data.metadata.candidates.iter().map(|c|c.name.as_str()).collect::<Vec<_>>());
+    assert_eq!(10,data.num_votes());
+    assert_eq!(1,data.informal);
+    assert_eq!(Some(NumberOfCandidates(2)),data.metadata.vacancies);
+    // order is kept, and a repeated ranking ignored.
+    assert_eq!(vec![CandidateIndex(0),CandidateIndex(1)],data.btl[0].candidates);
+    assert_eq!(vec![CandidateIndex(0),CandidateIndex(1)],data.btl[4].candidates);
+    assert_eq!(vec![CandidateIndex(1)],data.btl[9].candidates);
+    assert!(parse_reader("Ballot,Precinct\n1,1-1\n".as_bytes(),"bad.csv",NumberOfCandidates(2)).is_err());
+}
+
+#[test]
+fn test_every_nth_surplus_transfer() {
+    let transcript = parse(BALLOTS).distribute_preferences::<CambridgeMA>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(vec![6,2,1,1],transcript.counts[0].status.tallies.candidate);
+    assert_eq!(vec![4,2,3,1],transcript.counts[1].status.tallies.candidate);
+    assert_eq!(vec![CandidateIndex(0),CandidateIndex(2)],transcript.elected);
+}
+
+#[test]
+fn test_every_nth_skips_exhausted_ballots() {
+    // Alice's third ballot now has no next preference, so the next one, for Bob, is taken instead, changing the result.
+    let transcript = parse(&BALLOTS.replace("4,1-1,Alice,Carol,","4,1-1,Alice,,")).distribute_preferences::<CambridgeMA>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(vec![4,3,2,1],transcript.counts[1].status.tallies.candidate);
+    assert_eq!(vec![CandidateIndex(0),CandidateIndex(1)],transcript.elected);
+}
+
+/// Count the published ballots for the 2023 Cambridge City Council election, converted to the CSV format read by [parse_reader]
+/// and kept in the data repository, and check the nine elected against the official result.
+/// This needs the data repository, like the tests of the Australian jurisdictions against official results.
+#[test]
+fn test_2023_city_council_matches_official_result() {
+    let path = FileFinder::find_ec_data_repository().find_raw_data_file("CityCouncil2023.csv","Cambridge/2023","https://www.cambridgema.gov/election").unwrap();
+    let data = cambridge::parse::parse(&path,NumberOfCandidates(9)).unwrap();
+    let transcript = data.distribute_preferences::<CambridgeMA>(&mut Randomness::ReverseDonkeyVote);
+    let official = ["Azeem","McGovern","Nolan","Pickett","Siddiqui","Simmons","Toner","Wilson","Zusy"];
+    let elected : Vec<&str> = transcript.elected.iter().map(|c|data.metadata.candidates[c.0].name.as_str()).collect();
+    assert_eq!(official.len(),elected.len());
+    for surname in official {
+        assert!(elected.iter().any(|name|name.contains(surname)),"{} should be elected, but elected were {:?}",surname,elected);
+    }
+}
@@ -330,6 +330,7 @@ function RenderTranscript(full_transcript,render_div) {
             if (count.created_transfer_value.original_transfer_value) title+=" original transfer value : "+count.created_transfer_value.original_transfer_value;
             if (count.created_transfer_value.multiplied_transfer_value) title+=" common multiple "+count.created_transfer_value.multiplied_transfer_value;
             if (count.created_transfer_value.excluded_exhausted_tally) title+=" exhausted tally "+count.created_transfer_value.excluded_exhausted_tally;
+            if (count.created_transfer_value.denominator_policy_applied) title+=" zero or negative denominator handled by "+count.created_transfer_value.denominator_policy_applied;
             tv_td.title=title;
         }
         fullSpanTD("CountAction").innerText=count.reason==="FirstPreferenceCount"?"First Preference Count":count.reason.hasOwnProperty("ExcessDistribution")?"Surplus distribution for "+cname(count.reason.ExcessDistribution):"Exclusion of "+count.reason.Elimination.map(cname).join(" & "); // TODO prettify
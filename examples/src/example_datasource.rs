@@ -14,6 +14,7 @@ use anyhow::anyhow;
 use stv::ballot_metadata::{CandidateIndex, ElectionMetadata, ElectionName, NumberOfCandidates};
 use stv::datasource_description::{AssociatedRules, Copyright, ElectionDataSource};
 use stv::election_data::ElectionData;
+use stv::file_format::load_str_with_migration;
 use stv::official_dop_transcript::OfficialDistributionOfPreferencesTranscript;
 use stv::parse_util::{FileFinder, KnowsAboutRawMarkings, MissingFile, RawDataSource};
 use stv::tie_resolution::TieResolutionsMadeByEC;
@@ -201,7 +202,7 @@ impl <T:SimpleExampleFromText> RawDataSource for ExampleDataLoader<T> {
 
     fn read_raw_data(&self, electorate: &str) -> anyhow::Result<ElectionData> {
         let json = T::get_raw_data_as_string(electorate)?;
-        let data : ElectionData = serde_json::from_str(json)?;
+        let data : ElectionData = load_str_with_migration(json)?;
         Ok(data)
     }
 
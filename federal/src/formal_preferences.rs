@@ -0,0 +1,134 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Read the AEC's per-ballot Senate "formal preferences" file directly, without needing any of the other AEC files.
+//!
+//! From 2019 this file has one column per box on the ballot paper. The first six columns are
+//! `State,Division,Vote Collection Point Name,Vote Collection Point ID,Batch No,Paper No`. Then there is one
+//! column per above the line box, headed like `A:Party Name`, followed by one column per candidate, headed like
+//! `A:SURNAME Given Names` (or `UG:SURNAME Given Names` for ungrouped candidates). The headings are enough to
+//! reconstruct the candidates and groups. The 2016 file has all the preferences in a single column and does not
+//! name the candidates, so it can't be read this way; use [crate::parse::FederalDataLoader] for it.
+//!
+//! Each paper is interpreted from its raw markings in the same way as [crate::parse::FederalDataLoader], including
+//! the savings provisions of the Commonwealth Electoral Act 1918 as applied by the AEC:
+//! * A `*`, `/` or `X` is treated as a 1.
+//! * A below the line vote is formal if it has at least 6 consecutive preferences starting at 1, and then takes precedence over any above the line marks.
+//! * Otherwise an above the line vote is formal if it has a first preference, using consecutive preferences from 1.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use anyhow::anyhow;
+use csv::StringRecord;
+use stv::ballot_metadata::{Candidate, CandidateIndex, DataSource, ElectionMetadata, ElectionName, NumberOfCandidates, Party, PartyIndex};
+use stv::ballot_paper::{parse_marking, GapPolicy, RawBallotMarkings, UniqueVoteBuilderMultipleTypes};
+use stv::election_data::ElectionData;
+use crate::parse::vote_type_of_collection_point;
+
+/// The number of columns before the first ballot paper box.
+const NUM_NON_PREFERENCE_COLUMNS : usize = 6;
+/// The column containing the vote collection point name, used to determine the vote type.
+const COLLECTION_POINT_COLUMN : usize = 2;
+/// The minimum number of above the line preferences for a formal vote, after the savings provisions.
+const MIN_ATL_PREFERENCES : usize = 1;
+/// The minimum number of below the line preferences for a formal vote, after the savings provisions.
+const MIN_BTL_PREFERENCES : usize = 6;
+
+/// Read a formal preferences file, either the .zip file downloaded from the AEC or the .csv file inside it.
+/// See the [module documentation](crate::formal_preferences) for the format.
+pub fn read_formal_preferences_file(path:&Path,name:ElectionName,vacancies:NumberOfCandidates,gap_policy:GapPolicy) -> anyhow::Result<ElectionData> {
+    let mut data = if path.extension().map(|e|e.eq_ignore_ascii_case("zip")).unwrap_or(false) {
+        let mut zipfile = zip::ZipArchive::new(File::open(path)?)?;
+        let contents = zipfile.by_index(0)?;
+        read_formal_preferences_csv(contents,name,vacancies,gap_policy)?
+    } else {
+        read_formal_preferences_csv(File::open(path)?,name,vacancies,gap_policy)?
+    };
+    data.metadata.source.push(DataSource{
+        url: "https://results.aec.gov.au/".to_string(),
+        files: vec![path.file_name().map(|f|f.to_string_lossy().to_string()).unwrap_or_default()],
+        comments: None
+    });
+    Ok(data)
+}
+
+/// Read the csv contents of a formal preferences file.
+/// See the [module documentation](crate::formal_preferences) for the format.
+pub fn read_formal_preferences_csv<R:Read>(reader:R,name:ElectionName,vacancies:NumberOfCandidates,gap_policy:GapPolicy) -> anyhow::Result<ElectionData> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headings = reader.headers()?.clone();
+    if headings.len()>5 && &headings[5]=="Preferences" { return Err(anyhow!("This is the 2016 format formal preferences file, which does not name the candidates. Use FederalDataLoader instead.")); }
+    if headings.len()<=NUM_NON_PREFERENCE_COLUMNS || &headings[COLLECTION_POINT_COLUMN]!="Vote Collection Point Name" { return Err(anyhow!("Not a recognised AEC formal preferences file.")); }
+    let metadata = metadata_from_headings(&headings,name,vacancies)?;
+    let parties_that_can_get_atls : Vec<PartyIndex> = (0..metadata.parties.len()).filter(|&i|metadata.parties[i].atl_allowed).map(PartyIndex).collect();
+    let mut builder = UniqueVoteBuilderMultipleTypes::default();
+    let mut markings = Vec::with_capacity(headings.len()-NUM_NON_PREFERENCE_COLUMNS);
+    for record in reader.records() {
+        let record = record?;
+        if record[0].starts_with("---") { continue; } // skip dummy heading "underlines" if there.
+        markings.clear();
+        markings.extend(record.iter().skip(NUM_NON_PREFERENCE_COLUMNS).map(parse_marking));
+        let raw = RawBallotMarkings::new(&parties_that_can_get_atls,&markings);
+        builder.add_vote(raw.interpret_vote_with_gap_policy(MIN_ATL_PREFERENCES,MIN_BTL_PREFERENCES,gap_policy),vote_type_of_collection_point(&record[COLLECTION_POINT_COLUMN]));
+    }
+    Ok(builder.into_election_data(metadata))
+}
+
+/// Work out the groups and candidates from the column headings.
+/// The above the line columns are those before the first candidate column; a candidate column is recognised as
+/// either being ungrouped, or having a group id already seen.
+fn metadata_from_headings(headings:&StringRecord,name:ElectionName,vacancies:NumberOfCandidates) -> anyhow::Result<ElectionMetadata> {
+    let mut atl_boxes : Vec<(&str,&str)> = vec![]; // group id, party name.
+    let mut parties : Vec<Party> = vec![];
+    let mut candidates : Vec<Candidate> = vec![];
+    for heading in headings.iter().skip(NUM_NON_PREFERENCE_COLUMNS) {
+        let (group_id,text) = heading.split_once(':').ok_or_else(||anyhow!("Column heading {} is not of the form group:name",heading))?;
+        if candidates.is_empty() && group_id!="UG" && !atl_boxes.iter().any(|(g,_)|*g==group_id) {
+            atl_boxes.push((group_id,text));
+        } else {
+            if parties.last().map(|p|p.column_id!=group_id).unwrap_or(true) {
+                if parties.iter().any(|p|p.column_id==group_id) { return Err(anyhow!("Candidates in group {} are not in adjacent columns",group_id)); }
+                parties.push(Party{ column_id: group_id.to_string(), name: String::new(), abbreviation: None, atl_allowed: false, candidates: vec![], tickets: vec![], official_atl_votes: None });
+            }
+            let party_index = PartyIndex(parties.len()-1);
+            let party = &mut parties[party_index.0];
+            party.candidates.push(CandidateIndex(candidates.len()));
+            candidates.push(Candidate{ name: surname_comma_given_names(text), party: Some(party_index), position: Some(party.candidates.len()), ec_id: None });
+        }
+    }
+    // The above the line boxes need to be in the same order as the groups.
+    let mut next_party = 0;
+    for (group_id,party_name) in atl_boxes {
+        let offset = parties[next_party..].iter().position(|p|p.column_id==group_id).ok_or_else(||anyhow!("Above the line box for group {} does not match any group of candidates in order",group_id))?;
+        next_party+=offset;
+        parties[next_party].name=party_name.to_string();
+        parties[next_party].atl_allowed=true;
+        next_party+=1;
+    }
+    Ok(ElectionMetadata{
+        name,
+        candidates,
+        parties,
+        source: vec![],
+        results: None,
+        vacancies: Some(vacancies),
+        enrolment: None,
+        secondary_vacancies: None,
+        excluded: vec![],
+        withdrawn_before_count: vec![],
+        tie_resolutions: Default::default(),
+    })
+}
+
+/// The headings give names as "SURNAME Given Names", where the surname may have multiple words. Convert to "SURNAME, Given Names".
+/// Surnames like McDONALD are not all upper case, so the first word is always taken as part of the surname.
+fn surname_comma_given_names(name:&str) -> String {
+    let words : Vec<&str> = name.split_whitespace().collect();
+    let surname_words = words.iter().take_while(|w|!w.chars().any(|c|c.is_lowercase())).count().max(1);
+    if surname_words>=words.len() { name.to_string() } else { words[..surname_words].join(" ")+", "+&words[surname_words..].join(" ") }
+}
@@ -14,6 +14,7 @@ use stv::tie_resolution::MethodOfTieResolution;
 
 pub mod parse;
 pub mod parse2013;
+pub mod formal_preferences;
 
 /// Many variants on the federal rules are used, partly due to legislation changes
 /// and partly due to deviations between what the AEC did and the legislation.
@@ -448,8 +449,11 @@ mod tests {
     use stv::compare_rules::CompareRules;
     use crate::{FederalRulesUsed2013, FederalRulesUsed2016, FederalRulesPre2021, FederalRulesUsed2019};
     use stv::compare_transcripts::DifferenceBetweenTranscripts::{DifferentCandidatesElected, CandidatesOrderedDifferentWay,Same};
-    use stv::compare_transcripts::DifferentCandidateLists;
+    use stv::compare_transcripts::{first_detailed_difference, DetailedDifference, DetailedDifferenceKind, DifferentCandidateLists};
+    use stv::preference_distribution::distribute_preferences;
+    use stv::random_util::Randomness;
     use stv::ballot_metadata::CandidateIndex;
+    use stv::distribution_of_preferences_transcript::CountIndex;
 
     #[test]
     fn example() -> anyhow::Result<()>{
@@ -465,6 +469,13 @@ mod tests {
         assert_eq!(comp.results[index("AEC2019","AEC2013")],DifferentCandidatesElected(DifferentCandidateLists{ list1: vec![CandidateIndex(0),CandidateIndex(2),CandidateIndex(6),CandidateIndex(5),CandidateIndex(4),CandidateIndex(3)], list2: vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(6),CandidateIndex(5),CandidateIndex(4),CandidateIndex(3)] }));
         assert_eq!(comp.results[index("AEC2019","AEC2016")],CandidatesOrderedDifferentWay(DifferentCandidateLists{ list1: vec![CandidateIndex(0),CandidateIndex(2),CandidateIndex(6),CandidateIndex(5),CandidateIndex(4),CandidateIndex(3)], list2: vec![CandidateIndex(0),CandidateIndex(2),CandidateIndex(3),CandidateIndex(4),CandidateIndex(5),CandidateIndex(6)] }));
         assert_eq!(comp.results[index("FederalPre2021","AEC2013")],Same);
+        // where AEC2016 and AEC2013 first diverge.
+        let transcript2016 = distribute_preferences::<FederalRulesUsed2016>(&data,data.metadata.vacancies.unwrap(),&data.metadata.excluded.iter().cloned().collect(),&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+        let transcript2013 = distribute_preferences::<FederalRulesUsed2013>(&data,data.metadata.vacancies.unwrap(),&data.metadata.excluded.iter().cloned().collect(),&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+        // the first exclusion is already different, as the 2013 rules use bulk exclusion.
+        assert_eq!(first_detailed_difference(&transcript2016,&transcript2013),Some(DetailedDifference{ count: CountIndex(1), kind: DetailedDifferenceKind::NotContinuing }));
+        let transcript_pre2021 = distribute_preferences::<FederalRulesPre2021>(&data,data.metadata.vacancies.unwrap(),&data.metadata.excluded.iter().cloned().collect(),&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+        assert_eq!(first_detailed_difference(&transcript_pre2021,&transcript2013),None);
         Ok(())
     }
 }
@@ -9,7 +9,7 @@ use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 use std::fs::File;
 use stv::ballot_metadata::{ElectionName, Candidate, CandidateIndex, PartyIndex, ElectionMetadata, DataSource, NumberOfCandidates};
-use stv::ballot_paper::{RawBallotMarking, parse_marking, RawBallotMarkings, UniqueVoteBuilderMultipleTypes};
+use stv::ballot_paper::{RawBallotMarking, parse_marking, RawBallotMarkings, UniqueVoteBuilderMultipleTypes, GapPolicy};
 use std::collections::{HashMap};
 use csv::{StringRecord, StringRecordsIntoIter};
 use zip::ZipArchive;
@@ -48,6 +48,15 @@ pub fn get_federal_data_loader_2022(finder:&FileFinder) -> FederalDataLoader {
 
 
 
+/// The vote type used by ConcreteSTV for a vote collection point name in an AEC formal preferences file, or None for an ordinary vote.
+pub fn vote_type_of_collection_point(collection_point:&str) -> Option<&'static str> {
+    if collection_point.starts_with("PROVISIONAL") { Some("PROVISIONAL") }
+    else if collection_point.starts_with("PRE_POLL") { Some("PRE_POLL") }
+    else if collection_point.starts_with("POSTAL") { Some("POSTAL") }
+    else if collection_point.starts_with("ABSENT") { Some("ABSENT") }
+    else {None}
+}
+
 pub struct FederalDataSource {}
 
 impl ElectionDataSource for FederalDataSource {
@@ -75,6 +84,8 @@ pub struct FederalDataLoader {
     double_dissolution : bool,
     page_url : String,
     election_number : usize,
+    /// How to interpret ballots with a gap in their numbering.
+    gap_policy : GapPolicy,
 }
 
 impl RawDataSource for FederalDataLoader {
@@ -139,13 +150,7 @@ impl RawDataSource for FederalDataLoader {
             _ => {
                 let mut builder = UniqueVoteBuilderMultipleTypes::default();
                 let callback = |markings:&RawBallotMarkings,_meta:&[(&str,&str)]| {
-                    let collection_point = _meta[1].1;
-                    let vote_type = if collection_point.starts_with("PROVISIONAL") { Some("PROVISIONAL") }
-                    else if collection_point.starts_with("PRE_POLL") { Some("PRE_POLL") }
-                    else if collection_point.starts_with("POSTAL") { Some("POSTAL") }
-                    else if collection_point.starts_with("ABSENT") { Some("ABSENT") }
-                    else {None};
-                    builder.add_vote(markings.interpret_vote(1,6),vote_type);
+                    builder.add_vote(markings.interpret_vote_with_gap_policy(1,6,self.gap_policy),vote_type_of_collection_point(_meta[1].1));
                 };
                 let metadata = self.iterate_over_raw_markings(state,callback)?;
                 Ok(builder.into_election_data(metadata))
@@ -184,6 +189,7 @@ impl RawDataSource for FederalDataLoader {
             enrolment: None,
             secondary_vacancies: if vacancies==NumberOfCandidates(12) { Some(NumberOfCandidates(6)) } else {None},
             excluded: self.excluded_candidates(state),
+            withdrawn_before_count: vec![],
             tie_resolutions : self.ec_decisions(state),
         })
     }
@@ -250,6 +256,11 @@ impl RawDataSource for FederalDataLoader {
         }
     }
 
+    fn set_gap_policy(&mut self,gap_policy:GapPolicy) -> anyhow::Result<()> {
+        self.gap_policy=gap_policy;
+        Ok(())
+    }
+
 }
 
 impl CanReadRawMarkings for FederalDataLoader {
@@ -289,6 +300,7 @@ impl FederalDataLoader {
             double_dissolution,
             page_url: page_url.to_string(),
             election_number,
+            gap_policy: GapPolicy::default(),
         }
     }
 
@@ -324,8 +336,8 @@ impl FederalDataLoader {
         let preferences_zip_file = self.find_raw_data_file(&filename)?;
         println!("Parsing {}",&preferences_zip_file.to_string_lossy());
         metadata.source[0].files.push(filename);
-        let (btl,informal) = read_btl_votes2013(&metadata, &preferences_zip_file, 1)?; // The 2013 formality rules are quite complex. I am assuming the AEC has applied them already to all with a 1 vote. This is a dubious assumption as there are some without a 1 vote. However since we don't get all the informal votes, it is hard to check formality properly.
-        Ok(ElectionData{ metadata, atl:ticket_votes, atl_types: vec![], atl_transfer_values: vec![], btl, btl_types: vec![], btl_transfer_values: vec![], informal })
+        let (btl,informal) = read_btl_votes2013(&metadata, &preferences_zip_file, 1,self.gap_policy)?; // The 2013 formality rules are quite complex. I am assuming the AEC has applied them already to all with a 1 vote. This is a dubious assumption as there are some without a 1 vote. However since we don't get all the informal votes, it is hard to check formality properly.
+        Ok(ElectionData{ metadata, atl:ticket_votes, atl_types: vec![], atl_transfer_values: vec![], btl, btl_types: vec![], btl_transfer_values: vec![], atl_annotations: vec![], btl_annotations: vec![], informal })
     }
 
 }
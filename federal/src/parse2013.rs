@@ -15,7 +15,7 @@ use stv::parse_util::{CandidateAndGroupInformationBuilder, skip_first_line_of_fi
 use std::path::Path;
 use stv::ballot_metadata::{Candidate, PartyIndex, CandidateIndex, ElectionMetadata};
 use std::collections::HashMap;
-use stv::ballot_paper::{ATL, BTL, RawBallotMarking, RawBallotMarkings, UniqueBTLBuilder};
+use stv::ballot_paper::{ATL, BTL, GapPolicy, RawBallotMarking, RawBallotMarkings, UniqueBTLBuilder};
 use std::iter::FromIterator;
 use anyhow::anyhow;
 use std::fs::File;
@@ -145,7 +145,7 @@ pub(crate) fn read_ticket_votes2013(metadata:&ElectionMetadata,path:&Path, state
     Ok(res)
 }
 
-pub(crate) fn read_btl_votes2013(metadata:&ElectionMetadata,path:&Path,min_btl_prefs_needed:usize) -> anyhow::Result<(Vec<BTL>,usize)> {
+pub(crate) fn read_btl_votes2013(metadata:&ElectionMetadata,path:&Path,min_btl_prefs_needed:usize,gap_policy:GapPolicy) -> anyhow::Result<(Vec<BTL>,usize)> {
     // let mut res = vec![];
     let mut builder = UniqueBTLBuilder::default();
     let mut informal : usize = 0;
@@ -171,7 +171,7 @@ pub(crate) fn read_btl_votes2013(metadata:&ElectionMetadata,path:&Path,min_btl_p
                     let paper = record[3].parse::<usize>()?;
                     if last!=Some((batch,paper)) {
                         if last.is_some() { // save existing paper.
-                            if let Some(btl) = (RawBallotMarkings{ atl: &[], btl: &btl_markings, atl_parties: &[] }).interpret_vote_as_btl(min_btl_prefs_needed) {
+                            if let Some(btl) = (RawBallotMarkings{ atl: &[], btl: &btl_markings, atl_parties: &[] }).interpret_vote_as_btl_with_gap_policy(min_btl_prefs_needed,gap_policy) {
                                 builder.add_vote(btl);
                             } else { informal+=1;}
                             btl_markings.clear();
@@ -203,7 +203,7 @@ pub(crate) fn read_btl_votes2013(metadata:&ElectionMetadata,path:&Path,min_btl_p
                     btl_markings[candidate.0]=preference;
                 }
                 // save existing paper.
-                if let Some(btl) = (RawBallotMarkings{ atl: &[], btl: &btl_markings, atl_parties: &[] }).interpret_vote_as_btl(min_btl_prefs_needed) {
+                if let Some(btl) = (RawBallotMarkings{ atl: &[], btl: &btl_markings, atl_parties: &[] }).interpret_vote_as_btl_with_gap_policy(min_btl_prefs_needed,gap_policy) {
                     builder.add_vote(btl);
                 } else { informal+=1;}
                 return Ok((builder.to_btls(),informal))
@@ -0,0 +1,64 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that a rules configuration file describing the FederalPre2021 rules counts the same as [FederalRulesPre2021],
+//! and that configurations that can't be honoured, or rules used without a configuration, are errors.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use federal::FederalRulesPre2021;
+use stv::ballot_metadata::CandidateIndex;
+use stv::compare_transcripts::{compare_transcripts, DifferenceBetweenTranscripts};
+use stv::configurable_rules::ConfigurableRules;
+use stv::election_data::ElectionData;
+use stv::file_format::load_with_migration;
+use stv::preference_distribution::distribute_preferences;
+use stv::random_util::Randomness;
+
+#[test]
+fn test_configured_federal_pre2021_same_as_compiled() {
+    let rules = ConfigurableRules::load(Path::new("tests/FederalPre2021.toml")).unwrap();
+    assert_eq!(rules.name,"FederalPre2021");
+    assert_eq!(rules.decimal_places,0);
+    // round trip through JSON, the other supported format.
+    let rules = Arc::new(ConfigurableRules::from_json_str(&serde_json::to_string(&rules).unwrap()).unwrap());
+    for example in ["MultipleExclusionOrdering.stv","MultipleExclusionRounding.stv"] {
+        let data : ElectionData = load_with_migration(File::open(Path::new("../examples").join(example)).unwrap()).unwrap();
+        let excluded : HashSet<CandidateIndex> = data.metadata.excluded.iter().cloned().collect();
+        let vacancies = data.metadata.vacancies.unwrap();
+        let compiled = distribute_preferences::<FederalRulesPre2021>(&data,vacancies,&excluded,&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+        let configured = rules.distribute_preferences(&data,vacancies,&excluded,&data.metadata.tie_resolutions,&mut Randomness::ReverseDonkeyVote,Default::default());
+        assert_eq!(compare_transcripts(&compiled,&configured),DifferenceBetweenTranscripts::Same,"{}",example);
+        assert_eq!(compiled.elected,configured.elected);
+        assert_eq!(configured.rules,"FederalPre2021");
+    }
+}
+
+#[test]
+fn test_rules_config_rejects_unknown_field() {
+    let text = std::fs::read_to_string("tests/FederalPre2021.toml").unwrap()+"\nnot_a_rule = true\n";
+    assert!(ConfigurableRules::from_toml_str(&text).is_err());
+}
+
+#[test]
+fn test_rules_config_rejects_custom_timing() {
+    let text = std::fs::read_to_string("tests/FederalPre2021.toml").unwrap().replace("when_to_check_if_top_few_have_overwhelming_votes = \"Never\"","when_to_check_if_top_few_have_overwhelming_votes = \"Custom\"");
+    let error = ConfigurableRules::from_toml_str(&text).unwrap_err();
+    assert!(error.to_string().contains("when_to_check_if_top_few_have_overwhelming_votes"),"{}",error);
+}
+
+#[test]
+fn test_rules_only_available_inside_with() {
+    assert!(ConfigurableRules::current_shared().is_err());
+    let rules = Arc::new(ConfigurableRules::load(Path::new("tests/FederalPre2021.toml")).unwrap());
+    let name = rules.with(||ConfigurableRules::current_shared().unwrap().name.clone());
+    assert_eq!(name,"FederalPre2021");
+    // not visible on another thread, or once with returns.
+    rules.with(||assert!(std::thread::spawn(||ConfigurableRules::current_shared().is_err()).join().unwrap()));
+    assert!(ConfigurableRules::current_shared().is_err());
+}
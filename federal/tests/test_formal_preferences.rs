@@ -0,0 +1,88 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Check reading the AEC formal preferences file directly.
+
+use std::collections::HashSet;
+use federal::formal_preferences::{read_formal_preferences_csv, read_formal_preferences_file};
+use federal::FederalRulesUsed2019;
+use federal::parse::get_federal_data_loader_2022;
+use stv::ballot_metadata::{CandidateIndex, ElectionName, NumberOfCandidates, PartyIndex};
+use stv::ballot_paper::GapPolicy;
+use stv::first_preferences::first_preferences;
+use stv::parse_util::{FileFinder, RawDataSource};
+use stv::random_util::Randomness;
+use stv::tie_resolution::TieResolutionsMadeByEC;
+
+/// A small file in the 2019+ format, with two groups and two ungrouped candidates.
+const FORMAL_PREFERENCES : &str = "State,Division,Vote Collection Point Name,Vote Collection Point ID,Batch No,Paper No,A:Party One,B:Party Two,A:SMITH Jane,A:DI NATALE Richard,A:McDONALD Ann,B:BROWN Bob,B:GREEN Sue,UG:JONES Tom,UG:LEE Kim
+------------,------------,------------,------------,------------,------------,------------,------------,------------,------------,------------,------------,------------,------------,------------
+ACT,Bean,Belconnen,1,1,1,1,2,,,,,,,
+ACT,Bean,POSTAL 1,2,1,2,,*,,,,,,,
+ACT,Bean,PRE_POLL Civic,3,1,3,1,,6,5,4,3,2,1,
+ACT,Bean,Belconnen,1,1,4,,1,1,2,3,4,5,,
+ACT,Bean,Belconnen,1,1,5,,,1,2,3,4,5,,
+ACT,Bean,Belconnen,1,1,6,/,,,,,,,,
+";
+
+fn name() -> ElectionName {
+    ElectionName{ year: "2022".to_string(), authority: "AEC".to_string(), name: "Federal Senate".to_string(), electorate: "ACT".to_string(), ..Default::default() }
+}
+
+#[test]
+fn test_read_formal_preferences() {
+    let data = read_formal_preferences_csv(FORMAL_PREFERENCES.as_bytes(),name(),NumberOfCandidates(2),GapPolicy::TruncateAtGap).unwrap();
+    let metadata = &data.metadata;
+    assert_eq!(metadata.candidates.iter().map(|c|c.name.as_str()).collect::<Vec<_>>(),vec!["SMITH, Jane","DI NATALE, Richard","McDONALD, Ann","BROWN, Bob","GREEN, Sue","JONES, Tom","LEE, Kim"]);
+    assert_eq!(metadata.parties.len(),3);
+    assert_eq!(metadata.parties[0].name,"Party One");
+    assert!(metadata.parties[1].atl_allowed);
+    assert_eq!(metadata.parties[2].column_id,"UG");
+    assert!(!metadata.parties[2].atl_allowed);
+    assert_eq!(metadata.parties[2].candidates,vec![CandidateIndex(5),CandidateIndex(6)]);
+    assert_eq!(metadata.candidates[4].position,Some(2));
+    // Paper 5 has only 5 below the line preferences and nothing above the line.
+    assert_eq!(data.informal,1);
+    assert_eq!(data.num_votes(),5);
+    // Paper 3 is formal below the line with 6 preferences, overriding the above the line mark.
+    assert_eq!(data.btl.len(),1);
+    assert_eq!(data.btl[0].candidates,vec![CandidateIndex(5),CandidateIndex(4),CandidateIndex(3),CandidateIndex(2),CandidateIndex(1),CandidateIndex(0)]);
+    assert_eq!(data.btl_types[0].vote_type,"PRE_POLL");
+    // Paper 4 falls back to above the line, and papers 2 and 6 use the "*" and "/" savings provisions.
+    let atl_b_only = data.atl.iter().filter(|a|a.parties==vec![PartyIndex(1)]).map(|a|a.n).sum::<usize>();
+    assert_eq!(atl_b_only,2);
+    assert!(data.atl.iter().any(|a|a.parties==vec![PartyIndex(0)]));
+    assert!(data.atl.iter().any(|a|a.parties==vec![PartyIndex(0),PartyIndex(1)]));
+    assert_eq!(data.atl_types[0].vote_type,"POSTAL");
+}
+
+#[test]
+fn test_2016_format_rejected() {
+    let old = "ElectorateNm,VoteCollectionPointNm,VoteCollectionPointId,BatchNo,PaperNo,Preferences\nBean,Belconnen,1,1,1,\"1,2,,,\"\n";
+    assert!(read_formal_preferences_csv(old.as_bytes(),name(),NumberOfCandidates(2),GapPolicy::TruncateAtGap).is_err());
+}
+
+/// Read the published 2022 ACT Senate formal preferences file from the data repository, and check the first preferences
+/// against the first count of the AEC's official distribution of preferences. This needs the data repository,
+/// like the tests in test_federal.rs.
+#[test]
+fn test_2022_act_first_preferences_match_official() {
+    let loader = get_federal_data_loader_2022(&FileFinder::find_ec_data_repository());
+    let path = loader.find_raw_data_file("aec-senate-formalpreferences-27966-ACT.zip").unwrap();
+    let vacancies = loader.candidates_to_be_elected("ACT");
+    let data = read_formal_preferences_file(&path,loader.name("ACT"),vacancies,GapPolicy::default()).unwrap();
+    // Candidate names are matched against the official transcript, so this also checks the column headings were parsed correctly.
+    let official = loader.read_official_dop_transcript(&data.metadata).unwrap();
+    let official_quota = official.quota.as_ref().unwrap();
+    assert_eq!(data.num_votes(),official_quota.papers.0);
+    let first = first_preferences::<FederalRulesUsed2019>(&data,vacancies,&HashSet::default(),&TieResolutionsMadeByEC::default(),None,&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(first.quota.unwrap().quota as f64,official_quota.quota);
+    let official_first = official.counts[0].vote_total.as_ref().unwrap();
+    for (candidate,&tally) in first.tallies.candidate.iter().enumerate() {
+        assert_eq!(tally as f64,official_first.candidate[candidate],"First preferences for {}",data.metadata.candidates[candidate].name);
+    }
+}
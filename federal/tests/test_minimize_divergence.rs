@@ -0,0 +1,38 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check finding a small dataset on which two sets of rules elect different candidates.
+
+use std::fs::File;
+use federal::{FederalRulesPost2021, FederalRulesPost2021Manual};
+use stv::compare_rules::{minimize_divergence_dataset, rules_elect_different_candidates};
+use stv::election_data::ElectionData;
+use stv::file_format::load_with_migration;
+
+fn papers(data:&ElectionData) -> usize { data.atl.iter().map(|v|v.n).sum::<usize>()+data.btl.iter().map(|v|v.n).sum::<usize>() }
+
+#[test]
+fn test_minimize_divergence_dataset() {
+    // This differs between computer and manual counting after 2021 due to the handling of bulk exclusion.
+    let data : ElectionData = load_with_migration(File::open("../examples/MultipleExclusionOrdering.stv").unwrap()).unwrap();
+    assert!(rules_elect_different_candidates::<FederalRulesPost2021,FederalRulesPost2021Manual>(&data));
+    let minimized = minimize_divergence_dataset::<FederalRulesPost2021,FederalRulesPost2021Manual>(&data);
+    assert!(rules_elect_different_candidates::<FederalRulesPost2021,FederalRulesPost2021Manual>(&minimized));
+    assert!(minimized.btl.len()<=data.btl.len());
+    assert!(papers(&minimized)<papers(&data),"{} papers",papers(&minimized));
+    assert_eq!(minimized.metadata.candidates.len(),data.metadata.candidates.len());
+    assert_eq!(minimized.metadata.vacancies,data.metadata.vacancies);
+    // No single vote can be removed.
+    for vote in 0..minimized.btl.len() {
+        let mut smaller = minimized.clone();
+        smaller.btl.remove(vote);
+        assert!(!rules_elect_different_candidates::<FederalRulesPost2021,FederalRulesPost2021Manual>(&smaller),"Could remove vote {}",vote);
+    }
+    // Nothing to do if the rules agree.
+    let same = minimize_divergence_dataset::<FederalRulesPost2021,FederalRulesPost2021>(&data);
+    assert_eq!(papers(&same),papers(&data));
+    assert_eq!(same.metadata.name.modifications,data.metadata.name.modifications);
+}
@@ -0,0 +1,61 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that the federal rules give the same transcript for each of the example datasets regardless of the order of the ballots,
+//! and regardless of HashMap iteration order (which differs between HashMaps even in the same process).
+
+use std::collections::HashSet;
+use std::fs::File;
+use federal::{FederalRulesPost2021, FederalRulesPre2021, FederalRulesUsed2013, FederalRulesUsed2016, FederalRulesUsed2019};
+use stv::election_data::ElectionData;
+use stv::file_format::load_with_migration;
+use stv::order_independence::check_order_independent;
+use stv::preference_distribution::{distribute_preferences_with_extractors, PreferenceDistributionRules};
+use stv::random_util::Randomness;
+
+const SEEDS : [u64;5] = [1,2,3,4,5];
+
+fn assert_order_independent<Rules:PreferenceDistributionRules>(data:&ElectionData) {
+    if let Err(problem) = check_order_independent::<Rules>(data,&SEEDS) { panic!("{}",problem); }
+}
+
+fn examples() -> Vec<ElectionData> {
+    let mut paths : Vec<_> = std::fs::read_dir("../examples").unwrap().map(|e|e.unwrap().path()).filter(|p|p.extension().map(|e|e=="stv").unwrap_or(false)).collect();
+    paths.sort();
+    paths.iter().map(|p|load_with_migration(File::open(p).unwrap()).unwrap()).collect()
+}
+
+#[test]
+fn test_examples_order_independent() {
+    for data in examples() {
+        assert_order_independent::<FederalRulesUsed2013>(&data);
+        assert_order_independent::<FederalRulesUsed2016>(&data);
+        assert_order_independent::<FederalRulesUsed2019>(&data);
+        assert_order_independent::<FederalRulesPre2021>(&data);
+        assert_order_independent::<FederalRulesPost2021>(&data);
+    }
+}
+
+/// Count twice, including the list of votes in the transcript, and check that the JSON is byte for byte identical.
+fn assert_deterministic<Rules:PreferenceDistributionRules>(data:&ElectionData) {
+    let count = || {
+        let excluded : HashSet<_> = data.metadata.excluded.iter().cloned().collect();
+        let transcript = distribute_preferences_with_extractors::<Rules>(data,data.metadata.vacancies.unwrap(),&excluded,&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote,&[],true);
+        serde_json::to_string(&transcript).unwrap()
+    };
+    assert!(count()==count(),"{} gives different transcripts when counted twice with {}",data.metadata.name.human_readable_name(),Rules::name());
+}
+
+#[test]
+fn test_examples_deterministic() {
+    for data in examples() {
+        assert_deterministic::<FederalRulesUsed2013>(&data);
+        assert_deterministic::<FederalRulesUsed2016>(&data);
+        assert_deterministic::<FederalRulesUsed2019>(&data);
+        assert_deterministic::<FederalRulesPre2021>(&data);
+        assert_deterministic::<FederalRulesPost2021>(&data);
+    }
+}
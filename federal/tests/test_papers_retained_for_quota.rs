@@ -0,0 +1,53 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the ballot papers retained by an elected candidate in a federal (Gregory) surplus distribution.
+
+use std::collections::HashSet;
+use federal::FederalRulesPost2021;
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use stv::ballot_paper::BTL;
+use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::ReasonForCount;
+use stv::election_data::ElectionData;
+use stv::preference_distribution::distribute_preferences;
+use stv::random_util::Randomness;
+
+/// Candidates A, B, C, 2 vacancies. A gets 14 first preferences; 7 go on to B, 4 to C and 3 exhaust.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: ElectionMetadata {
+            candidates: vec![Candidate::from_name("A"),Candidate::from_name("B"),Candidate::from_name("C")],
+            vacancies: Some(NumberOfCandidates(2)),
+            ..Default::default()
+        },
+        btl: vec![
+            BTL{ candidates: vec![CandidateIndex(0),CandidateIndex(1)], n: 7 },
+            BTL{ candidates: vec![CandidateIndex(0),CandidateIndex(2)], n: 4 },
+            BTL{ candidates: vec![CandidateIndex(0)], n: 3 },
+            BTL{ candidates: vec![CandidateIndex(1)], n: 3 },
+            BTL{ candidates: vec![CandidateIndex(2)], n: 5 },
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_gregory_surplus_papers_retained() {
+    let data = make_data();
+    let transcript = distribute_preferences::<FederalRulesPost2021>(&data,NumberOfCandidates(2),&HashSet::new(),&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,8);
+    assert_eq!(transcript.counts[0].papers_retained_for_quota(),None);
+    let surplus = &transcript.counts[1];
+    assert!(matches!(surplus.reason,ReasonForCount::ExcessDistribution(CandidateIndex(0))));
+    // The surplus of 6 is transferred at 6/14 = 3/7, so B gets 3 votes from 7 papers, C 1 from 4 and exhausted 1 from 3.
+    // The rest, 14-5=9 papers, stay with A: its quota of 8, and 1 lost to rounding.
+    let set_aside = surplus.set_aside_for_quota.as_ref().unwrap();
+    assert_eq!(set_aside.candidate,vec![BallotPaperCount(0),BallotPaperCount(4),BallotPaperCount(3)]);
+    assert_eq!(set_aside.exhausted,BallotPaperCount(2));
+    assert_eq!(surplus.papers_retained_for_quota(),Some(BallotPaperCount(9)));
+    assert_eq!(surplus.status.tallies.candidate,vec![8,6,6]);
+}
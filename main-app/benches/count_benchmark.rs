@@ -0,0 +1,203 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Benchmarks of counting performance on synthetic, deterministically generated elections.
+//! These do not need the EC data repository, unlike federal/benches/parse_benchmark.rs.
+//!
+//! Run with `cargo bench -p main-app --bench count_benchmark` and then check against the
+//! committed baseline with `cargo run --release --bin check_benchmark_regression`.
+//! The benchmark ids must match the keys in benches/baseline.json, apart from count_irv_preflib which
+//! is only run if the environment variable PREFLIB_IRV_FILE names a preflib file, such as one of the Irish elections.
+
+use std::collections::HashSet;
+use criterion::{criterion_group, criterion_main, Criterion};
+use federal::FederalRulesPost2021;
+use margin::choose_votes::ChooseVotesOptions;
+use margin::find_outcome_changes::find_outcome_changes;
+use nsw::{NSWECLocalGov2021, SimpleIRVAnyDifferenceBreaksTies, SimpleIRVBatchExclusion};
+use rand::Rng;
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha20Rng;
+use rand_chacha::rand_core::SeedableRng;
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, ElectionName, NumberOfCandidates, Party, PartyIndex};
+use stv::ballot_paper::{ATL, BTL};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::PreferenceDistributionRules;
+use stv::random_util::Randomness;
+
+/// Description of a synthetic election. Parties have a popularity that decays geometrically,
+/// so there are a few big parties and a long tail of small ones, roughly like real elections.
+struct SyntheticElection {
+    name : &'static str,
+    seed : u64,
+    parties : usize,
+    candidates_per_party : usize,
+    vacancies : usize,
+    /// number of distinct ATL votes (0 means no ATL voting).
+    distinct_atl : usize,
+    /// number of distinct BTL votes.
+    distinct_btl : usize,
+    /// maximum number of ballots with a given distinct vote.
+    max_multiplicity : usize,
+    /// maximum number of preferences marked on a BTL vote (0 means all).
+    max_btl_preferences : usize,
+}
+
+impl SyntheticElection {
+    fn make(&self) -> ElectionData {
+        let mut rng = ChaCha20Rng::seed_from_u64(self.seed);
+        let num_candidates = self.parties*self.candidates_per_party;
+        let candidates : Vec<Candidate> = (0..num_candidates).map(|c|Candidate{name:format!("Candidate {}",c),party:Some(PartyIndex(c/self.candidates_per_party)),position:Some(1+c%self.candidates_per_party),ec_id:None}).collect();
+        let parties : Vec<Party> = (0..self.parties).map(|p|Party{
+            column_id: format!("G{}",p),
+            name: format!("Party {}",p),
+            abbreviation: None,
+            atl_allowed: self.distinct_atl>0,
+            candidates: (p*self.candidates_per_party..(p+1)*self.candidates_per_party).map(CandidateIndex).collect(),
+            tickets: vec![],
+            official_atl_votes: None
+        }).collect();
+        let popularity : Vec<f64> = (0..self.parties).map(|p|0.7f64.powi(p as i32)).collect();
+        let total_popularity : f64 = popularity.iter().sum();
+        let choose_party = |rng:&mut ChaCha20Rng,excluding:&[usize]| -> usize {
+            loop {
+                let mut r = rng.gen::<f64>()*total_popularity;
+                for (p,&w) in popularity.iter().enumerate() {
+                    if r<w { if !excluding.contains(&p) { return p; } else { break; } }
+                    r-=w;
+                }
+            }
+        };
+        let mut atl = vec![];
+        for _ in 0..self.distinct_atl {
+            let len = rng.gen_range(1..=self.parties.min(6));
+            let mut chosen : Vec<usize> = vec![];
+            while chosen.len()<len { let p = choose_party(&mut rng,&chosen); chosen.push(p); }
+            atl.push(ATL{ parties: chosen.into_iter().map(PartyIndex).collect(), n: rng.gen_range(1..=self.max_multiplicity), ticket_index: None });
+        }
+        let mut btl = vec![];
+        for _ in 0..self.distinct_btl {
+            let max_len = if self.max_btl_preferences==0 { num_candidates } else { self.max_btl_preferences.min(num_candidates) };
+            let len = rng.gen_range(1..=max_len);
+            // mostly follow party lines, with some candidates shuffled in.
+            let mut chosen_parties : Vec<usize> = vec![];
+            let mut candidates_in_vote : Vec<CandidateIndex> = vec![];
+            while candidates_in_vote.len()<len && chosen_parties.len()<self.parties {
+                let p = choose_party(&mut rng,&chosen_parties);
+                chosen_parties.push(p);
+                let mut party_candidates : Vec<CandidateIndex> = (p*self.candidates_per_party..(p+1)*self.candidates_per_party).map(CandidateIndex).collect();
+                if rng.gen_bool(0.2) { party_candidates.shuffle(&mut rng); }
+                candidates_in_vote.extend(party_candidates);
+            }
+            candidates_in_vote.truncate(len);
+            btl.push(BTL{ candidates: candidates_in_vote, n: rng.gen_range(1..=self.max_multiplicity) });
+        }
+        ElectionData {
+            metadata: ElectionMetadata {
+                name: ElectionName {
+                    year: "".to_string(),
+                    authority: "Synthetic".to_string(),
+                    name: self.name.to_string(),
+                    electorate: "".to_string(),
+                    modifications: vec![],
+                    comment: Some(format!("Generated for benchmarking with seed {}",self.seed))
+                },
+                candidates,
+                parties,
+                source: vec![],
+                results: None,
+                vacancies: Some(NumberOfCandidates(self.vacancies)),
+                enrolment: None,
+                secondary_vacancies: None,
+                excluded: vec![],
+                withdrawn_before_count: vec![],
+                tie_resolutions: Default::default()
+            },
+            atl,
+            atl_types: vec![],
+            atl_transfer_values: vec![],
+            btl,
+            btl_types: vec![],
+            btl_transfer_values: vec![],
+            atl_annotations: vec![],
+            btl_annotations: vec![],
+            informal: 0
+        }
+    }
+}
+
+/// Something the size of a federal senate contest in a territory, with a couple of states' worth of structure.
+const FEDERAL_SMALL : SyntheticElection = SyntheticElection{ name: "federal_small", seed: 1, parties: 12, candidates_per_party: 2, vacancies: 2, distinct_atl: 3000, distinct_btl: 5000, max_multiplicity: 40, max_btl_preferences: 0 };
+/// Something like a big NSW local government election, with many candidates and only BTL votes.
+const NSW_LGE_BIG : SyntheticElection = SyntheticElection{ name: "nsw_lge_big", seed: 2, parties: 15, candidates_per_party: 5, vacancies: 15, distinct_atl: 0, distinct_btl: 40000, max_multiplicity: 10, max_btl_preferences: 30 };
+/// A smaller federal style contest for the (much slower) search for outcome changes.
+const FEDERAL_MARGIN : SyntheticElection = SyntheticElection{ name: "federal_margin", seed: 4, parties: 6, candidates_per_party: 2, vacancies: 2, distinct_atl: 500, distinct_btl: 1000, max_multiplicity: 40, max_btl_preferences: 0 };
+/// A single seat IRV election, like a lower house seat.
+const IRV_SINGLE_SEAT : SyntheticElection = SyntheticElection{ name: "irv_single_seat", seed: 3, parties: 9, candidates_per_party: 1, vacancies: 1, distinct_atl: 0, distinct_btl: 20000, max_multiplicity: 20, max_btl_preferences: 0 };
+/// A single seat IRV election with a very large field, most of whom get very few votes.
+const IRV_LARGE_FIELD : SyntheticElection = SyntheticElection{ name: "irv_large_field", seed: 5, parties: 300, candidates_per_party: 1, vacancies: 1, distinct_atl: 0, distinct_btl: 20000, max_multiplicity: 20, max_btl_preferences: 20 };
+
+fn count<Rules:PreferenceDistributionRules>(data:&ElectionData) {
+    data.distribute_preferences::<Rules>(&mut Randomness::ReverseDonkeyVote);
+}
+
+fn count_federal_small(c: &mut Criterion) {
+    let data = FEDERAL_SMALL.make();
+    c.bench_function("count_federal_small", |b| b.iter(|| count::<FederalRulesPost2021>(&data)));
+}
+
+fn count_nsw_lge_big(c: &mut Criterion) {
+    let data = NSW_LGE_BIG.make();
+    c.bench_function("count_nsw_lge_big", |b| b.iter(|| count::<NSWECLocalGov2021>(&data)));
+}
+
+fn count_irv_single_seat(c: &mut Criterion) {
+    let data = IRV_SINGLE_SEAT.make();
+    c.bench_function("count_irv_single_seat", |b| b.iter(|| count::<SimpleIRVAnyDifferenceBreaksTies>(&data)));
+}
+
+/// Compare IRV with and without batch exclusion, checking they elect the same candidate.
+fn bench_irv_batching(c: &mut Criterion,name:&str,data:&ElectionData) {
+    let unbatched = data.distribute_preferences::<SimpleIRVAnyDifferenceBreaksTies>(&mut Randomness::ReverseDonkeyVote).elected;
+    let batched = data.distribute_preferences::<SimpleIRVBatchExclusion>(&mut Randomness::ReverseDonkeyVote).elected;
+    assert_eq!(unbatched,batched,"Batch exclusion changed the winner of {}",name);
+    c.bench_function(name, |b| b.iter(|| count::<SimpleIRVAnyDifferenceBreaksTies>(data)));
+    c.bench_function(&format!("{}_batched",name), |b| b.iter(|| count::<SimpleIRVBatchExclusion>(data)));
+}
+
+fn count_irv_large_field(c: &mut Criterion) {
+    bench_irv_batching(c,"count_irv_large_field",&IRV_LARGE_FIELD.make());
+}
+
+/// Real data, counted as a single seat IRV election, if PREFLIB_IRV_FILE is set. E.g. the preflib Irish election data.
+fn count_irv_preflib(c: &mut Criterion) {
+    let Some(path) = std::env::var_os("PREFLIB_IRV_FILE") else { println!("Skipping count_irv_preflib as PREFLIB_IRV_FILE is not set."); return; };
+    let mut data = preflib::parse(&path).expect("Could not read PREFLIB_IRV_FILE");
+    data.metadata.vacancies = Some(NumberOfCandidates(1));
+    bench_irv_batching(c,"count_irv_preflib",&data);
+}
+
+fn find_outcome_changes_federal_margin(c: &mut Criterion) {
+    let mut data = FEDERAL_MARGIN.make();
+    // find_outcome_changes compares against the official results, as would be present in a real .stv file.
+    data.metadata.results = Some(data.distribute_preferences::<FederalRulesPost2021>(&mut Randomness::ReverseDonkeyVote).elected);
+    let options = ChooseVotesOptions{
+        allow_atl: true,
+        allow_first_pref: true,
+        allow_verifiable: true,
+        ballot_types_considered_unverifiable: HashSet::default(),
+        allow_additions: false,
+        allow_from: None,
+        allow_to: None
+    };
+    let mut group = c.benchmark_group("find_outcome_changes");
+    group.sample_size(10);
+    group.bench_function("federal_margin", |b| b.iter(|| find_outcome_changes::<FederalRulesPost2021>(&data,&options,false,None)));
+    group.finish();
+}
+
+criterion_group!(benches, count_federal_small, count_nsw_lge_big, count_irv_single_seat, count_irv_large_field, count_irv_preflib, find_outcome_changes_federal_margin);
+criterion_main!(benches);
@@ -0,0 +1,79 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use anyhow::anyhow;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser)]
+#[clap(version = "0.3", author = "Andrew Conway", name="ConcreteSTV")]
+/// Compare the results of `cargo bench -p main-app --bench count_benchmark` against committed baseline times,
+/// failing if any benchmark has become too much slower.
+struct Opts {
+    /// The file containing the baseline times.
+    #[clap(long,value_parser,default_value=concat!(env!("CARGO_MANIFEST_DIR"),"/benches/baseline.json"))]
+    baseline : PathBuf,
+
+    /// The directory criterion writes its results to.
+    #[clap(long,value_parser,default_value="target/criterion")]
+    criterion : PathBuf,
+
+    /// Rather than checking, replace the times in the baseline file with the current results.
+    #[clap(long)]
+    update : bool,
+}
+
+/// The contents of benches/baseline.json
+#[derive(Serialize,Deserialize)]
+struct Baseline {
+    comment : String,
+    /// A benchmark fails if it takes more than this multiple of its baseline time.
+    max_slowdown : f64,
+    /// Mean time, in nanoseconds, for each criterion benchmark id.
+    mean_ns : BTreeMap<String,f64>,
+}
+
+#[derive(Deserialize)]
+struct CriterionEstimate { point_estimate : f64 }
+#[derive(Deserialize)]
+struct CriterionEstimates { mean : CriterionEstimate }
+
+/// Get the mean time in nanoseconds of the most recent run of the given benchmark.
+fn latest_mean_ns(criterion_dir:&Path,id:&str) -> anyhow::Result<f64> {
+    let path = criterion_dir.join(id).join("new").join("estimates.json");
+    let file = File::open(&path).map_err(|e|anyhow!("Could not open {} : {}. Have the benchmarks been run?",path.display(),e))?;
+    let estimates : CriterionEstimates = serde_json::from_reader(file)?;
+    Ok(estimates.mean.point_estimate)
+}
+
+fn main() -> anyhow::Result<()> {
+    let opt : Opts = Opts::parse();
+    let mut baseline : Baseline = serde_json::from_reader(File::open(&opt.baseline)?)?;
+    let mut failures = 0;
+    for (id,baseline_ns) in baseline.mean_ns.iter_mut() {
+        let latest = latest_mean_ns(&opt.criterion,id)?;
+        let ratio = latest / *baseline_ns;
+        if opt.update {
+            println!("{} : {:.3} ms (was {:.3} ms)",id,latest/1e6,*baseline_ns/1e6);
+            *baseline_ns=latest.round();
+        } else {
+            let ok = ratio<=baseline.max_slowdown;
+            if !ok { failures+=1; }
+            println!("{} : {:.3} ms, baseline {:.3} ms, ratio {:.2} {}",id,latest/1e6,*baseline_ns/1e6,ratio,if ok {"OK"} else {"TOO SLOW"});
+        }
+    }
+    if opt.update {
+        serde_json::to_writer_pretty(File::create(&opt.baseline)?,&baseline)?;
+        println!("Updated {}",opt.baseline.display());
+    } else if failures>0 {
+        return Err(anyhow!("{} benchmark(s) took more than {} times their baseline",failures,baseline.max_slowdown));
+    }
+    Ok(())
+}
@@ -0,0 +1,38 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+use clap::{Parser};
+use std::path::PathBuf;
+use main_app::compare_different_rules::BatchRulesComparison;
+use main_app::rules::Rules;
+
+#[derive(Parser)]
+#[clap(version = "0.3", author = "Andrew Conway", name="ConcreteSTV")]
+/// Count every .stv file in a directory under several rules, and summarise how often the choice of rules
+/// changes the outcome compared to the first rule.
+struct Opts {
+    /// The directory containing .stv files
+    #[clap(value_parser)]
+    directory : PathBuf,
+
+    /// The rules to compare, separated by commas. The first is the reference against which the others are compared.
+    /// The default is the different interpretations of the federal legislation.
+    #[clap(long,value_parser,value_delimiter=',',default_value="AEC2013,AEC2016,AEC2019,FederalPre2021,FederalPost2021,FederalPost2021Manual")]
+    rules : Vec<Rules>,
+
+    /// Whether the output should be JSON rather than a human readable table.
+    #[clap(long)]
+    json: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let opt : Opts = Opts::parse();
+    let comparison = BatchRulesComparison::create_for_directory(&opt.directory,&opt.rules)?;
+    if opt.json { println!("{}",serde_json::to_string(&comparison)?) }
+    else { print!("{}",comparison) }
+    Ok(())
+}
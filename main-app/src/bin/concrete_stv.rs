@@ -8,19 +8,30 @@
 use clap::{Parser};
 use std::path::PathBuf;
 use std::fs::File;
+use std::str::FromStr;
+use std::sync::Arc;
 use main_app::ModifyStvFileOptions;
-use main_app::rules::Rules;
+use anyhow::anyhow;
+use main_app::rules::{count_simple_with_configurable_rules, PossibleTranscripts, Rules, RulesCountOptions};
+use stv::ballot_metadata::{CandidateIndex, NameFormat};
+use stv::configurable_rules::ConfigurableRules;
+use stv::distribution_of_preferences_transcript::CountStopped;
+use stv::compare_transcripts::{compare_elected_with_official, DeltasInCandidateLists, DifferenceBetweenTranscripts, pretty_print_candidate_list};
 use stv::extract_votes_in_pile::ExtractionRequest;
-use stv::random_util::Randomness;
+use stv::file_format::{load_with_migration, save_with_version};
+use stv::random_util::{parse_hex_beacon, Randomness};
+use stv::transfer_value::{DecimalRounding, TransferValueFormat};
 
 #[derive(Parser)]
-#[clap(version = "0.3", author = "Andrew Conway", name="ConcreteSTV")]
+#[clap(version = "0.3", author = "Andrew Conway", name="ConcreteSTV",allow_missing_positional=true)]
 /// Count STV elections using a variety of rules including good approximations to
 /// those used by various electoral commissions on various elections.
 struct Opts {
     /// The counting rules to use.
     /// Currently supported AEC2013, AEC2016, AEC2019, FederalPre2021, FederalPost2021, FederalPost2021Manual, ACTPre2020, ACT2020, ACT2021, NSWLocalGov2021, NSWECLocalGov2021, NSWECRandomLGE2012, NSWECRandomLGE2016, NSWECRandomLGE2017, NSWECRandomLC2015, NSWECRandomLC2019, Vic2018, WA2008
-    rules : Rules,
+    /// Omit if --rules-config is given.
+    #[clap(required_unless_present="rules_config")]
+    rules : Option<Rules>,
 
     /// The name of the .stv (or .vchange) file to get votes from
     #[clap(value_parser)]
@@ -43,12 +54,20 @@ struct Opts {
     #[clap(short, long,value_parser)]
     seed : Option<u64>,
 
+    /// Resolve random ties with a pseudo random number generator seeded from a published randomness beacon value,
+    /// such as a drand or NIST beacon round, given in hexadecimal. The value is recorded in the transcript so anyone
+    /// can check the random tie resolutions by repeating the count.
+    #[clap(long,conflicts_with="seed")]
+    beacon : Option<String>,
+
     /// It is possible to extract the particular votes at some point in the transcript. The
     /// general format for this is --extract what_to_extract;what_to_do_with_it, where
     ///
-    /// what_to_extract can currently only be UsedToElectACT:candidate_number where candidate_number
+    /// what_to_extract can be UsedToElectACT:candidate_number where candidate_number
     /// is an integer 0 to the number of candidates-1 and will extract the votes used to elect the
-    /// candidate according to the ACT casual vacancies legislation.
+    /// candidate according to the ACT casual vacancies legislation, or StateAtEndOfCount:count_index
+    /// where count_index is 0 for the first count, and will extract the continuing candidates and the
+    /// ballot papers they hold at that point, for resuming the count (with the quota fixed).
     ///
     /// what_to_do_with_it can currently only be file:file_name where file_name is the name of a .stv
     /// file that you want to store the extracted votes in.
@@ -60,18 +79,267 @@ struct Opts {
     /// not do this, flag makes it be done.
     #[clap(long)]
     include_list_of_votes_in_transcript:bool,
+
+    /// An optional CSV file to write a ledger of every transfer value created during the count to.
+    /// This is a flat table, one row per transfer value, intended for auditors wanting to check the
+    /// arithmetic independently.
+    #[clap(long,value_parser)]
+    transfer_value_ledger : Option<PathBuf>,
+
+    /// If set, transfer values in the ledger are written as decimals with this many places
+    /// instead of as exact fractions.
+    #[clap(long)]
+    transfer_value_decimals : Option<u32>,
+
+    /// How to round transfer values written as decimals : down, up or nearest. Default down.
+    #[clap(long,default_value="down")]
+    transfer_value_rounding : DecimalRounding,
+
+    /// How candidate names are written in the transfer value ledger : AsStored (default), LastFirst (SMITH, John) or FirstLast (John SMITH).
+    #[clap(long,default_value="AsStored")]
+    name_format : NameFormat,
+
+    /// Merge consecutive counts excluding candidates with no votes, in which nothing is transferred, into a single count.
+    /// This makes the transcript easier to read when there are many such candidates. Later counts keep their original numbers
+    /// as their count names. Default is to keep every count as done.
+    #[clap(long)]
+    merge_vacuous_exclusions : bool,
+
+    /// The format of the transcript file : json (default), the .transcript format read by other ConcreteSTV tools,
+    /// or aec-text, human readable text in the style of the distribution of preferences reports published by the AEC,
+    /// for side by side comparison with official results, or parquet, a table of the tally and papers of each candidate
+    /// at each count, for loading into data analysis tools. The default file name for aec-text ends in .txt, and for parquet in .parquet.
+    #[clap(long,default_value="json")]
+    format : TranscriptFormat,
+
+    /// After counting, print whether the candidates elected (and their order) match the official
+    /// results stored in the .stv file, and if not, how they differ.
+    #[clap(long)]
+    compare_official : bool,
+
+    /// After counting, print any count in which a transfer value was created that is greater than the transfer value
+    /// of the papers it was applied to. This should not happen for most rules, and indicates a bug or an unusual interaction of rules.
+    #[clap(long)]
+    check_transfer_values : bool,
+
+    /// Instead of built in rules, use rules described by a TOML (if the file name ends in .toml) or JSON file.
+    /// See stv::configurable_rules for the format. The name field of the file is used in place of the rules name in the default transcript file name.
+    #[clap(long,value_parser,conflicts_with="rules")]
+    rules_config : Option<PathBuf>,
+
+    /// Stop the count and fail, without writing a transcript, at a tie that the legislation provides no procedure for
+    /// and that is not resolved by the tie resolutions in the .stv file, rather than resolving it randomly (or by --seed).
+    /// This forces a resolution to be supplied for such ties. Ties the legislation says to resolve by lot are resolved as usual.
+    #[clap(long)]
+    strict_ties : bool,
+}
+
+/// How the transcript file is written. See [Opts::format].
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+enum TranscriptFormat {
+    Json,
+    AecText,
+    Parquet,
+}
+
+impl FromStr for TranscriptFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(TranscriptFormat::Json),
+            "aec-text" | "aectext" | "aec_text" => Ok(TranscriptFormat::AecText),
+            "parquet" => Ok(TranscriptFormat::Parquet),
+            _ => Err(format!("Unknown transcript format {}, expecting json, aec-text or parquet",s)),
+        }
+    }
+}
+
+#[derive(Parser)]
+#[clap(version = "0.3", author = "Andrew Conway", name="ConcreteSTV")]
+/// Check that a .stv file is well formed, without counting it. Usage : concrete_stv validate file.stv
+/// Also checks the above the line votes for each party against any official numbers in the file.
+/// Exits with a non-zero status if there are fatal problems.
+struct ValidateOpts {
+    /// The name of the .stv (or .vchange) file to check
+    #[clap(value_parser)]
+    votes : PathBuf,
+
+    #[clap(flatten)]
+    input_options : ModifyStvFileOptions,
+}
+
+fn validate(opt:ValidateOpts) -> anyhow::Result<()> {
+    let votes = opt.input_options.get_data(&opt.votes,false)?;
+    let report = votes.validate();
+    print!("{}",report);
+    for warning in votes.validate_atl_reconstruction() { println!("Warning : {}",warning); }
+    if report.has_fatal_problems() { std::process::exit(1); }
+    Ok(())
+}
+
+#[derive(Parser)]
+#[clap(version = "0.3", author = "Andrew Conway", name="ConcreteSTV")]
+/// Report the differences between two versions of the data for a contest, such as when the ballots are republished.
+/// Usage : concrete_stv diff-data old.stv new.stv
+struct DiffDataOpts {
+    /// The name of the older .stv (or .vchange) file
+    #[clap(value_parser)]
+    old : PathBuf,
+
+    /// The name of the newer .stv (or .vchange) file
+    #[clap(value_parser)]
+    new : PathBuf,
+
+    /// Applied to both files.
+    #[clap(flatten)]
+    input_options : ModifyStvFileOptions,
+
+    /// Whether the output should be JSON rather than human readable text.
+    #[clap(long)]
+    json: bool,
+}
+
+fn diff_data(opt:DiffDataOpts) -> anyhow::Result<()> {
+    let old = opt.input_options.get_data(&opt.old,false)?;
+    let new = opt.input_options.get_data(&opt.new,false)?;
+    let diff = old.diff(&new);
+    if opt.json { println!("{}",serde_json::to_string(&diff)?) }
+    else { print!("{}",diff) }
+    Ok(())
+}
+
+#[derive(Parser)]
+#[clap(version = "0.3", author = "Andrew Conway", name="ConcreteSTV")]
+/// Extract the ballot papers that elected a candidate, as a new .stv file with the transfer values at which the candidate received them.
+/// Usage : concrete_stv trace result.transcript --candidate X
+/// The transcript must have been made with --include-list-of-votes-in-transcript. This is a best effort attribution
+/// (the papers held when elected); see stv::trace_votes for its limitations.
+struct TraceOpts {
+    /// The name of the .transcript file to trace
+    #[clap(value_parser)]
+    transcript : PathBuf,
+
+    /// The candidate to trace; either a candidate number (starting from 0) or a name.
+    #[clap(long)]
+    candidate : String,
+
+    /// The .stv file to write the ballots to. If not specified, defaults to transcript_trace_X.stv where transcript and X are from above.
+    #[clap(short, long,value_parser)]
+    out : Option<PathBuf>,
+}
+
+fn trace(opt:TraceOpts) -> anyhow::Result<()> {
+    let transcript : PossibleTranscripts = load_with_migration(File::open(&opt.transcript)?)?;
+    let metadata = transcript.metadata();
+    let who = match opt.candidate.parse::<usize>() {
+        Ok(index) if index<metadata.candidates.len() => CandidateIndex(index),
+        Ok(index) => return Err(anyhow!("No candidate number {}; there are {} candidates",index,metadata.candidates.len())),
+        Err(_) => metadata.find_candidate_by_name(&opt.candidate)?,
+    };
+    let votes = transcript.votes_electing(who)?;
+    let out = opt.out.unwrap_or_else(||{
+        let stem = opt.transcript.file_stem().map(|s|s.to_string_lossy().to_string()).unwrap_or_default();
+        opt.transcript.with_file_name(format!("{}_trace_{}.stv",stem,who))
+    });
+    save_with_version(File::create(&out)?,&votes)?;
+    println!("Wrote {} ballot papers electing {} to {}",votes.num_votes(),metadata.candidate(who).name,out.display());
+    Ok(())
+}
+
+#[derive(Parser)]
+#[clap(version = "0.3", author = "Andrew Conway", name="ConcreteSTV")]
+/// Step through a count one surplus distribution or exclusion at a time, for teaching or debugging.
+/// Usage : concrete_stv step file.stv --rules R
+/// Commands are read from standard input; type help for a list.
+struct StepOpts {
+    /// The name of the .stv (or .vchange) file to get votes from
+    #[clap(value_parser)]
+    votes : PathBuf,
+
+    /// The counting rules to use.
+    #[clap(long)]
+    rules : Rules,
+
+    #[clap(flatten)]
+    input_options : ModifyStvFileOptions,
+
+    /// How random ties are done. If specified, the seed for a pseudo random number generator.
+    /// If not specified, then reverse donkey vote is used.
+    #[clap(short, long,value_parser)]
+    seed : Option<u64>,
+}
+
+fn step(opt:StepOpts) -> anyhow::Result<()> {
+    let votes = opt.input_options.get_data(&opt.votes,false)?;
+    let vacancies = votes.metadata.vacancies.ok_or_else(||anyhow!("Need to specify number of vacancies"))?;
+    let mut randomness : Randomness = opt.seed.into();
+    println!("{}",stv::count_stepper::HELP);
+    opt.rules.step_interactively(&votes,vacancies,&votes.metadata.excluded.iter().cloned().collect(),&votes.metadata.tie_resolutions,&mut randomness,std::io::stdin().lock(),std::io::stdout().lock())?;
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
+    if std::env::args_os().nth(1).map(|a|a=="validate").unwrap_or(false) {
+        return validate(ValidateOpts::parse_from(std::env::args_os().skip(1)));
+    }
+    if std::env::args_os().nth(1).map(|a|a=="diff-data").unwrap_or(false) {
+        return diff_data(DiffDataOpts::parse_from(std::env::args_os().skip(1)));
+    }
+    if std::env::args_os().nth(1).map(|a|a=="step").unwrap_or(false) {
+        return step(StepOpts::parse_from(std::env::args_os().skip(1)));
+    }
+    if std::env::args_os().nth(1).map(|a|a=="trace").unwrap_or(false) {
+        return trace(TraceOpts::parse_from(std::env::args_os().skip(1)));
+    }
     let opt : Opts = Opts::parse();
 
     let votes = opt.input_options.get_data(&opt.votes,opt.verbose)?;
-    let transcript_file = opt.input_options.result_file_name(&opt.votes,opt.transcript.as_ref(),".transcript",&opt.rules);
-    let mut randomness : Randomness = opt.seed.into();
-    let transcript = opt.rules.count_simple(&votes,opt.verbose,&mut randomness,&opt.extract,opt.include_list_of_votes_in_transcript)?;
+    let extension = match opt.format { TranscriptFormat::Json => ".transcript", TranscriptFormat::AecText => ".txt", TranscriptFormat::Parquet => ".parquet" };
+    let configured_rules = opt.rules_config.as_ref().map(|path|ConfigurableRules::load(path).map(Arc::new)).transpose()?;
+    let beacon = opt.beacon.as_ref().map(|hex|parse_hex_beacon(hex)).transpose().map_err(|e|anyhow!(e))?;
+    let mut randomness : Randomness = if let Some(beacon) = &beacon { Randomness::from_beacon(beacon) } else { opt.seed.into() };
+    let count_options = RulesCountOptions{ print_progress_to_stdout: opt.verbose, extractors: &opt.extract, include_list_of_votes_in_transcript: opt.include_list_of_votes_in_transcript, strict_ties: opt.strict_ties, ..Default::default() };
+    let (rules_name,mut transcript) = match (&opt.rules,&configured_rules) {
+        (_,Some(configured)) => (configured.name.clone(),count_simple_with_configurable_rules(configured,&votes,&mut randomness,count_options)?),
+        (Some(rules),None) => (rules.to_string(),rules.count_simple_with_options(&votes,&mut randomness,count_options)?),
+        (None,None) => return Err(anyhow!("Need to specify rules or --rules-config")), // prevented by clap.
+    };
+    if let Some(beacon) = &beacon { transcript.set_randomness_beacon(beacon.iter().map(|b|format!("{:02x}",b)).collect()); }
+    if let Some(CountStopped::UnresolvedTie(tie)) = transcript.stopped() { return Err(tie.clone().into()) }
+    if opt.merge_vacuous_exclusions { transcript.merge_vacuous_exclusions(); }
+    let transcript_file = opt.input_options.result_file_name(&opt.votes,opt.transcript.as_ref(),extension,&rules_name);
 
     if let Some(parent) = transcript_file.parent() { std::fs::create_dir_all(parent)? }
-    serde_json::to_writer(File::create(&transcript_file)?,&transcript)?;
+    match opt.format {
+        TranscriptFormat::Json => save_with_version(File::create(&transcript_file)?,&transcript)?,
+        TranscriptFormat::AecText => std::fs::write(&transcript_file,transcript.to_aec_text())?,
+        TranscriptFormat::Parquet => transcript.write_parquet(File::create(&transcript_file)?)?,
+    }
+    if let Some(ledger_file) = &opt.transfer_value_ledger {
+        let format = match opt.transfer_value_decimals {
+            Some(places) => TransferValueFormat::Decimal{places,rounding:opt.transfer_value_rounding},
+            None => TransferValueFormat::Exact,
+        };
+        transcript.write_transfer_value_ledger_csv(opt.name_format,format,File::create(ledger_file)?)?;
+    }
+    if opt.check_transfer_values {
+        let anomalies = transcript.transfer_value_anomalies();
+        if anomalies.is_empty() { println!("No transfer value anomalies."); }
+        for anomaly in anomalies { println!("Transfer value anomaly : {}",anomaly); }
+    }
+    if opt.compare_official {
+        let names = |candidates:&[CandidateIndex]| pretty_print_candidate_list(candidates,&votes.metadata);
+        match compare_elected_with_official(transcript.elected(),&votes.metadata) {
+            None => println!("No official results are available to compare with."),
+            Some(DifferenceBetweenTranscripts::Same) => println!("Matches official results : same candidates elected in the same order."),
+            Some(DifferenceBetweenTranscripts::CandidatesOrderedDifferentWay(lists)) => println!("Same candidates elected as official results, but in a different order. Computed {} official {}",names(&lists.list1),names(&lists.list2)),
+            Some(DifferenceBetweenTranscripts::DifferentCandidatesElected(lists)) => {
+                let deltas : DeltasInCandidateLists = lists.into();
+                println!("DIFFERENT candidates elected from official results. Only computed {} only official {} both {}",names(&deltas.list1only),names(&deltas.list2only),names(&deltas.common));
+            }
+            Some(other) => println!("{}",other), // not produced by compare_elected_with_official.
+        }
+    }
 
     Ok(())
 }
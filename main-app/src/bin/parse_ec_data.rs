@@ -13,7 +13,9 @@ use std::path::PathBuf;
 use std::fs::File;
 use std::io::stdout;
 use stv::ballot_metadata::CandidateIndex;
+use stv::ballot_paper::GapPolicy;
 use stv::parse_util::FileFinder;
+use stv::file_format::save_with_version;
 use stv::tie_resolution::{TieResolutionAtom, TieResolutionsMadeByEC};
 
 #[derive(Parser)]
@@ -52,15 +54,24 @@ struct Opts {
     /// This flag may be used multiple times for multiple tie resolutions.
     #[clap(long,value_parser=main_app::try_parse_candidate_list)]
     tie : Vec<TieResolutionAtom>,
+
+    /// How to treat a ballot with a gap in its numbering, such as 1,2,4,5.
+    /// TruncateAtGap (the default) uses preferences up to the gap, Informal makes the ballot informal, and IgnoreGap
+    /// uses all the numbers in order. Only supported for elections where ConcreteSTV interprets the raw markings (AEC, VIC).
+    #[clap(long)]
+    gap_policy : Option<GapPolicy>,
 }
 
 
 fn main() -> anyhow::Result<()> {
     let opt: Opts = Opts::parse();
     let finder : FileFinder = opt.src.clone().unwrap_or_else(||FileFinder::default());
-    let mut res = opt.election.load(&opt.electorate,&finder)?;
+    let mut res = match opt.gap_policy {
+        None => opt.election.load(&opt.electorate,&finder)?,
+        Some(gap_policy) => opt.election.load_with_gap_policy(&opt.electorate,&finder,gap_policy)?,
+    };
     if !opt.tie.is_empty() {
-        res.metadata.tie_resolutions=TieResolutionsMadeByEC{ tie_resolutions: opt.tie };
+        res.metadata.tie_resolutions=TieResolutionsMadeByEC{ tie_resolutions: opt.tie, default_policies: vec![] };
     }
     if let Some(exclude) = opt.exclude {
         res.metadata.excluded=exclude; //.iter().map(|&e|CandidateIndex(e)).collect();
@@ -71,6 +82,6 @@ fn main() -> anyhow::Result<()> {
         None => Box::new(stdout()),
         Some(path) => Box::new(File::create(path)?),
     };
-    serde_json::to_writer(out,&res)?;
+    save_with_version(out,&res)?;
     Ok(())
 }
\ No newline at end of file
@@ -8,6 +8,7 @@ use clap::Parser;
 use std::path::PathBuf;
 use std::fs::File;
 use stv::election_data::ElectionData;
+use stv::file_format::load_with_migration;
 //use anyhow::anyhow;
 use stv::distribution_of_preferences_transcript::{TranscriptWithMetadata, ReasonForCount};
 use stv::ballot_metadata::CandidateIndex;
@@ -17,6 +18,7 @@ use std::ops::Sub;
 use stv::fixed_precision_decimal::FixedPrecisionDecimal;
 use std::str::FromStr;
 use std::fmt::{Debug, Display};
+use stv::transfer_value::{DecimalRounding, TransferValueFormat};
 
 #[derive(Parser)]
 #[clap(version = "0.1", author = "Andrew Conway", name="ConcreteSTV")]
@@ -34,6 +36,10 @@ struct Opts {
     /// An optional list of candidate numbers (starting counting at 0) to restrict the table to.
     #[clap(short, long,value_delimiter=',')]
     candidates : Option<Vec<usize>>,
+
+    /// If set, show transfer values as decimals with this many places (rounded to nearest) rather than exact fractions.
+    #[clap(long)]
+    transfer_value_decimals : Option<u32>,
 }
 
 fn  possibly_blank<T:ToString+Zero>(t:T) -> String {
@@ -49,6 +55,10 @@ where <T as Sub>::Output: ToString
 }
 
 fn print_transcript<T:Copy+ToString+Eq+Ord+Sub<Output=T>+Clone+Display+FromStr+Zero+Debug>(transcript:TranscriptWithMetadata<T>,opt:&Opts) {
+    let tv_format = match opt.transfer_value_decimals {
+        Some(places) => TransferValueFormat::Decimal{places,rounding:DecimalRounding::Nearest},
+        None => TransferValueFormat::Exact,
+    };
     let use_candidate = |c:CandidateIndex|{ opt.candidates.is_none() || opt.candidates.as_ref().unwrap().contains(&c.0)};
 
     let separate_row_for_paper_deltas = false;
@@ -95,10 +105,8 @@ fn print_transcript<T:Copy+ToString+Eq+Ord+Sub<Output=T>+Clone+Display+FromStr+Z
         let count = &transcript.transcript.counts[count_no];
         let is_excluded_next_count = |candidate:CandidateIndex| count_no+1<transcript.transcript.counts.len()&&transcript.transcript.counts[count_no+1].not_continuing.contains(&candidate)&&!transcript.transcript.elected.contains(&candidate);
         let get_tv = || {
-            if let Some(tv) = &count.created_transfer_value {
-                tv.transfer_value.to_string()
-            } else if let Some(tv) = count.portion.transfer_value.as_ref() {
-                tv.to_string()
+            if let Some(tv) = count.created_transfer_value.as_ref().map(|tv|&tv.transfer_value).or(count.portion.transfer_value.as_ref()) {
+                tv_format.format(tv)
             } else { "".to_string() }
         };
         let status = &count.status;
@@ -259,7 +267,7 @@ fn main() -> anyhow::Result<()> {
     let opt : Opts = Opts::parse();
 
 
-    if let Ok(votes) = { let file = File::open(&opt.file)?; serde_json::from_reader::<_,ElectionData>(file) } {
+    if let Ok(votes) = { let file = File::open(&opt.file)?; load_with_migration::<ElectionData,_>(file) } {
         let metadata = &votes.metadata;
         println!("{}",r"\begin{tabular}{|l|l|}");
         println!("{}",r"\hline");
@@ -270,9 +278,9 @@ fn main() -> anyhow::Result<()> {
         println!("{}",r"\hline");
         println!("{}",r"\end{tabular}");
         // we have some votes, make a latex table out of it.
-    } else if let Ok(transcript) = { let file = File::open(&opt.file)?; serde_json::from_reader::<_,TranscriptWithMetadata<usize>>(file) } {
+    } else if let Ok(transcript) = { let file = File::open(&opt.file)?; load_with_migration::<TranscriptWithMetadata<usize>,_>(file) } {
         print_transcript(transcript,&opt);
-    } else if let Ok(transcript) = { let file = File::open(&opt.file)?; serde_json::from_reader::<_,TranscriptWithMetadata<FixedPrecisionDecimal<6>>>(file) } {
+    } else if let Ok(transcript) = { let file = File::open(&opt.file)?; load_with_migration::<TranscriptWithMetadata<FixedPrecisionDecimal<6>>,_>(file) } {
         print_transcript(transcript,&opt);
     } else {
         println!("The input file was neither a valid .stv nor .transcript file, so I'm giving up and going to a corner to sulk.");
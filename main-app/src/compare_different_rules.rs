@@ -9,13 +9,19 @@
 //! * First divide them into groups according to which candidates end up elected.
 //! * Next divide each group into subgroups according to the order of electing candidates
 //! * Next divide each group into subgroups that have different values at a particular count or different numbers of counts.
+//!
+//! There is also a batch comparison [BatchRulesComparison] over all the .stv files in a directory, summarising
+//! for each contest how much the transcript under each rule differs from that under a reference rule.
 
 
 use std::fmt;
+use std::fs::File;
+use std::path::Path;
 use serde::{Deserialize, Serialize};
 use stv::ballot_metadata::{CandidateIndex, ElectionMetadata};
 use stv::compare_transcripts::DifferenceBetweenTranscripts;
 use stv::election_data::ElectionData;
+use stv::file_format::load_with_migration;
 use stv::random_util::Randomness;
 use crate::rules::{PossibleTranscripts, Rules};
 
@@ -122,4 +128,96 @@ impl RulesComparisonGroups {
 
     pub fn has_different_winners(&self) -> bool { self.groups.len()>1 }
     pub fn has_different_orders(&self) -> bool { self.groups.iter().any(|g|g.subgroups.len()>1) }
-}
\ No newline at end of file
+}
+
+/// How the count of one contest under each of several rules compared to the count under the first (reference) rule.
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct ContestRulesComparison {
+    /// The name of the file the contest was loaded from, used as the key for the contest.
+    pub contest : String,
+    pub metadata : ElectionMetadata,
+    /// For each rule after the reference rule, the difference between its transcript and the reference rule's transcript.
+    pub differences : Vec<(String,DifferenceBetweenTranscripts)>,
+}
+
+impl ContestRulesComparison {
+    pub fn create(contest:String,data:&ElectionData,rules:&[Rules]) -> anyhow::Result<Self> {
+        let mut differences = vec![];
+        if let Some((reference_rule,other_rules)) = rules.split_first() {
+            let reference = reference_rule.count_simple(data,false,&mut Randomness::ReverseDonkeyVote,&[],false)?;
+            for rule in other_rules {
+                let transcript = rule.count_simple(data,false,&mut Randomness::ReverseDonkeyVote,&[],false)?;
+                differences.push((rule.to_string(),reference.compare_transcripts(&transcript)));
+            }
+        }
+        Ok(ContestRulesComparison{contest,metadata:data.metadata.clone(),differences})
+    }
+
+    /// The most serious difference from the reference rule over all the rules.
+    pub fn most_serious_difference(&self) -> DifferenceBetweenTranscripts {
+        self.differences.iter().map(|(_,d)|d).max_by_key(|d|d.seriousness()).cloned().unwrap_or(DifferenceBetweenTranscripts::Same)
+    }
+
+    /// The rules that produced a difference of the same seriousness as [Self::most_serious_difference].
+    pub fn rules_with_most_serious_difference(&self) -> Vec<String> {
+        let worst = self.most_serious_difference().seriousness();
+        if worst==0 { return vec![]; }
+        self.differences.iter().filter(|(_,d)|d.seriousness()==worst).map(|(r,_)|r.clone()).collect()
+    }
+}
+
+/// A comparison of several rules over a batch of contests, typically all the .stv files in a directory.
+/// The first rule is the reference rule against which the others are compared.
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct BatchRulesComparison {
+    pub rules : Vec<String>,
+    pub contests : Vec<ContestRulesComparison>,
+}
+
+impl BatchRulesComparison {
+    /// Compare the rules on every .stv file in the given directory, in alphabetical order of file name.
+    pub fn create_for_directory(directory:&Path,rules:&[Rules]) -> anyhow::Result<Self> {
+        let mut paths = vec![];
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.extension().map(|e|e=="stv").unwrap_or(false) { paths.push(path); }
+        }
+        paths.sort();
+        let mut contests = vec![];
+        for path in paths {
+            let data : ElectionData = load_with_migration(File::open(&path)?)?;
+            let contest = path.file_name().map(|f|f.to_string_lossy().to_string()).unwrap_or_default();
+            contests.push(ContestRulesComparison::create(contest,&data,rules)?);
+        }
+        Ok(BatchRulesComparison{rules:rules.iter().map(|r|r.to_string()).collect(),contests})
+    }
+
+    /// For each category of [DifferenceBetweenTranscripts], from most to least serious, the number of contests with that as the most serious difference.
+    pub fn summary(&self) -> Vec<(&'static str,usize)> {
+        let mut res : Vec<(u8,&'static str,usize)> = vec![];
+        for contest in &self.contests {
+            let difference = contest.most_serious_difference();
+            match res.iter_mut().find(|(_,category,_)|*category==difference.category()) {
+                Some((_,_,n)) => *n+=1,
+                None => res.push((difference.seriousness(),difference.category(),1)),
+            }
+        }
+        res.sort_by_key(|(seriousness,_,_)|u8::MAX-seriousness);
+        res.into_iter().map(|(_,category,n)|(category,n)).collect()
+    }
+}
+
+impl fmt::Display for BatchRulesComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f,"Comparing {} against reference rule {}",self.rules.get(1..).unwrap_or(&[]).join(" "),self.rules.first().map(|s|s.as_str()).unwrap_or(""))?;
+        writeln!(f,"Contest\tMost serious difference\tRules")?;
+        for contest in &self.contests {
+            writeln!(f,"{}\t{}\t{}",contest.contest,contest.most_serious_difference().category(),contest.rules_with_most_serious_difference().join(" "))?;
+        }
+        writeln!(f)?;
+        for (category,n) in self.summary() {
+            writeln!(f,"{}\t{} of {} contests",category,n,self.contests.len())?;
+        }
+        Ok(())
+    }
+}
@@ -7,6 +7,7 @@
 
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
+use stv::ballot_paper::GapPolicy;
 use stv::election_data::ElectionData;
 use federal::parse::{get_federal_data_loader_2013, get_federal_data_loader_2016, get_federal_data_loader_2019, get_federal_data_loader_2022};
 use stv::parse_util::{RawDataSource, FileFinder};
@@ -74,20 +75,33 @@ impl Display for ECDataSource {
 
 impl ECDataSource {
 
+    /// Get the loader for this election.
+    pub fn loader(&self,finder:&FileFinder) -> anyhow::Result<Box<dyn RawDataSource>> {
+        Ok(match self {
+            ECDataSource::AEC2013 => Box::new(get_federal_data_loader_2013(finder)),
+            ECDataSource::AEC2016 => Box::new(get_federal_data_loader_2016(finder)),
+            ECDataSource::AEC2019 => Box::new(get_federal_data_loader_2019(finder)),
+            ECDataSource::AEC2022 => Box::new(get_federal_data_loader_2022(finder)),
+            ECDataSource::ACT2008 => Box::new(get_act_data_loader_2008(finder)?),
+            ECDataSource::ACT2012 => Box::new(get_act_data_loader_2012(finder)?),
+            ECDataSource::ACT2016 => Box::new(get_act_data_loader_2016(finder)?),
+            ECDataSource::ACT2020 => Box::new(get_act_data_loader_2020(finder)?),
+            ECDataSource::NSWLG2021 => Box::new(get_nsw_lge_data_loader_2021(finder)?),
+            ECDataSource::VIC2014 => Box::new(get_vic_data_loader_2014(finder)?),
+            ECDataSource::VIC2018 => Box::new(get_vic_data_loader_2018(finder)?),
+            ECDataSource::VIC2022 => Box::new(get_vic_data_loader_2022(finder)?),
+        })
+    }
+
     pub fn load(&self,electorate:&String,finder:&FileFinder) -> anyhow::Result<ElectionData> {
-        match self {
-            ECDataSource::AEC2013 => get_federal_data_loader_2013(finder).read_raw_data_checking_electorate_valid(electorate),
-            ECDataSource::AEC2016 => get_federal_data_loader_2016(finder).read_raw_data_checking_electorate_valid(electorate),
-            ECDataSource::AEC2019 => get_federal_data_loader_2019(finder).read_raw_data_checking_electorate_valid(electorate),
-            ECDataSource::AEC2022 => get_federal_data_loader_2022(finder).read_raw_data_checking_electorate_valid(electorate),
-            ECDataSource::ACT2008 => get_act_data_loader_2008(finder)?.read_raw_data_checking_electorate_valid(electorate),
-            ECDataSource::ACT2012 => get_act_data_loader_2012(finder)?.read_raw_data_checking_electorate_valid(electorate),
-            ECDataSource::ACT2016 => get_act_data_loader_2016(finder)?.read_raw_data_checking_electorate_valid(electorate),
-            ECDataSource::ACT2020 => get_act_data_loader_2020(finder)?.read_raw_data_checking_electorate_valid(electorate),
-            ECDataSource::NSWLG2021 => get_nsw_lge_data_loader_2021(finder)?.read_raw_data_checking_electorate_valid(electorate),
-            ECDataSource::VIC2014 => get_vic_data_loader_2014(finder)?.read_raw_data_checking_electorate_valid(electorate),
-            ECDataSource::VIC2018 => get_vic_data_loader_2018(finder)?.read_raw_data_checking_electorate_valid(electorate),
-            ECDataSource::VIC2022 => get_vic_data_loader_2022(finder)?.read_raw_data_checking_electorate_valid(electorate),
-        }
+        self.loader(finder)?.read_raw_data_checking_electorate_valid(electorate)
+    }
+
+    /// Like [Self::load], but with a specified treatment of ballots with a gap in their numbering.
+    /// This is an error for elections where the EC provides preference lists rather than raw markings.
+    pub fn load_with_gap_policy(&self,electorate:&String,finder:&FileFinder,gap_policy:GapPolicy) -> anyhow::Result<ElectionData> {
+        let mut loader = self.loader(finder)?;
+        loader.set_gap_policy(gap_policy)?;
+        loader.read_raw_data_checking_electorate_valid(electorate)
     }
 }
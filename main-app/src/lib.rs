@@ -9,6 +9,7 @@
 
 use clap::Args;
 use std::collections::HashSet;
+use std::fmt::Display;
 use std::fs::File;
 use std::num::ParseIntError;
 use std::path::PathBuf;
@@ -18,39 +19,106 @@ use margin::choose_votes::ChooseVotesOptions;
 use margin::find_outcome_changes::find_outcome_changes;
 use margin::record_changes::ElectionChanges;
 use margin::vote_changes::{VoteChange, VoteChanges};
-use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::ballot_metadata::{CandidateIndex, ElectionMetadata, NumberOfCandidates};
 use stv::ballot_pile::BallotPaperCount;
 use stv::distribution_of_preferences_transcript::CountIndex;
 use stv::election_data::ElectionData;
+use stv::file_format::load_with_migration;
 use stv::preference_distribution::PreferenceDistributionRules;
-use stv::tie_resolution::{TieResolutionAtom, TieResolutionExplicitDecision, TieResolutionExplicitDecisionInCount, TieResolutionsMadeByEC, TieResolutionUsage};
-use crate::rules::Rules;
+use stv::tie_resolution::{TieResolutionAtom, TieResolutionExplicitDecision, TieResolutionExplicitDecisionInCount, TieResolutionUsage};
 
 pub mod rules;
 pub mod ec_data_source;
 pub mod compare_different_rules;
+pub mod seat_stability;
 
 
-/// Utility that is helpful for parsing tie resolution description string in clap a Vec<Vec<CandidateIndex>>.
-pub fn try_parse_candidate_list(s:&str) -> anyhow::Result<TieResolutionAtom> {
-    fn string_to_candidate_list(s:&str) -> Result<Vec<CandidateIndex>,ParseIntError> {
-        s.split('/').map(|s|s.trim().parse::<CandidateIndex>()).collect()
-    }
+/// Parse the options that may follow the candidate lists in a tie resolution string, separated by colons.
+/// Each is either a count number (starting at 1) or a TieResolutionUsage.
+fn parse_tie_resolution_options<'a>(colon_options:impl Iterator<Item=&'a str>) -> anyhow::Result<(Option<TieResolutionUsage>,Option<CountIndex>)> {
     let mut came_up_in : Option<CountIndex> = None;
     let mut usage : Option<TieResolutionUsage> = None;
-    let mut split_colon = s.split(':');
-    let main_list = split_colon.next().ok_or_else(||anyhow!("Empty tie string"))?;
-    for colon_option in split_colon {
+    for colon_option in colon_options {
         if let Ok(parsed_usage) = TieResolutionUsage::from_str(colon_option) { usage=Some(parsed_usage); }
         else if let Ok(count) = usize::from_str(colon_option) {
             if count>0 { came_up_in=Some(CountIndex(count-1))} else { return Err(anyhow!("The first count is 1, not 0.")) }
         } else { return Err(anyhow!("Do not understand option {}",colon_option)); }
     }
+    Ok((usage,came_up_in))
+}
+
+/// Utility that is helpful for parsing tie resolution description string in clap a Vec<Vec<CandidateIndex>>.
+pub fn try_parse_candidate_list(s:&str) -> anyhow::Result<TieResolutionAtom> {
+    fn string_to_candidate_list(s:&str) -> Result<Vec<CandidateIndex>,ParseIntError> {
+        s.split('/').map(|s|s.trim().parse::<CandidateIndex>()).collect()
+    }
+    let mut split_colon = s.split(':');
+    let main_list = split_colon.next().ok_or_else(||anyhow!("Empty tie string"))?;
+    let (usage,came_up_in) = parse_tie_resolution_options(split_colon)?;
     let mut increasing_favour : Vec<Vec<CandidateIndex>> = vec![];
     for same_level in main_list.split(',') {
         increasing_favour.push(string_to_candidate_list(same_level)?);
     }
-    Ok(TieResolutionAtom::ExplicitDecision(TieResolutionExplicitDecisionInCount{ decision: TieResolutionExplicitDecision { increasing_favour, usage }, came_up_in }))
+    Ok(TieResolutionAtom::ExplicitDecision(TieResolutionExplicitDecisionInCount{ decision: TieResolutionExplicitDecision { increasing_favour, usage, candidate_ids: vec![] }, came_up_in }))
+}
+
+/// A tie resolution like that produced by [try_parse_candidate_list], but with candidates specified by name.
+/// The names can only be converted to indices once the election metadata has been loaded, so this
+/// is kept as names until [TieResolutionByName::resolve] is called.
+#[derive(Clone,Debug)]
+pub struct TieResolutionByName {
+    /// Like TieResolutionExplicitDecision::increasing_favour, but names rather than indices.
+    increasing_favour : Vec<Vec<String>>,
+    usage : Option<TieResolutionUsage>,
+    came_up_in : Option<CountIndex>,
+}
+
+impl TieResolutionByName {
+    /// Convert the names to candidate indices. Fails if a name is unknown or matches more than one candidate.
+    pub fn resolve(&self,metadata:&ElectionMetadata) -> anyhow::Result<TieResolutionAtom> {
+        let mut increasing_favour : Vec<Vec<CandidateIndex>> = vec![];
+        for same_level in &self.increasing_favour {
+            increasing_favour.push(same_level.iter().map(|name|metadata.find_candidate_by_name(name)).collect::<Result<Vec<_>,_>>()?);
+        }
+        Ok(TieResolutionAtom::ExplicitDecision(TieResolutionExplicitDecisionInCount{ decision: TieResolutionExplicitDecision { increasing_favour, usage:self.usage, candidate_ids: vec![] }, came_up_in:self.came_up_in }))
+    }
+}
+
+/// Split s by separator, except when the separator is inside double quotes.
+fn split_outside_quotes(s:&str,separator:char) -> Vec<&str> {
+    let mut res = vec![];
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (pos,c) in s.char_indices() {
+        if c=='"' { in_quotes=!in_quotes; }
+        else if c==separator && !in_quotes {
+            res.push(&s[start..pos]);
+            start=pos+c.len_utf8();
+        }
+    }
+    res.push(&s[start..]);
+    res
+}
+
+/// Utility for parsing a tie resolution description string in clap where candidates are given by name.
+/// The format is the same as for [try_parse_candidate_list], except names are used instead of indices.
+/// As names often contain commas, a name may be enclosed in double quotes.
+pub fn try_parse_candidate_name_list(s:&str) -> anyhow::Result<TieResolutionByName> {
+    let split_colon = split_outside_quotes(s,':');
+    let main_list = split_colon[0];
+    let (usage,came_up_in) = parse_tie_resolution_options(split_colon[1..].iter().map(|o|o.trim()))?;
+    let mut increasing_favour : Vec<Vec<String>> = vec![];
+    for same_level in split_outside_quotes(main_list,',') {
+        let mut names : Vec<String> = vec![];
+        for name in split_outside_quotes(same_level,'/') {
+            let name = name.trim();
+            let name = name.strip_prefix('"').and_then(|n|n.strip_suffix('"')).unwrap_or(name);
+            if name.is_empty() { return Err(anyhow!("Empty candidate name in tie resolution {}",s)); }
+            names.push(name.to_string());
+        }
+        increasing_favour.push(names);
+    }
+    Ok(TieResolutionByName{increasing_favour,usage,came_up_in})
 }
 
 /// Options that pertain to what ballots are to be considered for changing
@@ -104,6 +172,13 @@ pub struct ChangeOptions {
 }
 
 
+/// The same as the command line defaults.
+impl Default for ChangeOptions {
+    fn default() -> Self {
+        ChangeOptions{allow_atl:vec![true],allow_first:vec![true],allow_verifiable:vec![true],unverifiable:vec![],disallow_additions:false,allow_from:None,allow_to:None,just_try:None}
+    }
+}
+
 impl ChangeOptions {
     fn find_changes<Rules:PreferenceDistributionRules>(&self,data:&ElectionData,verbose:bool) -> anyhow::Result<ElectionChanges<Rules::Tally>> {
         let just_try = self.just_try.as_ref().map(|v|VoteChanges{changes:v.iter().map(|c|VoteChange{vote_value:Rules::Tally::from(BallotPaperCount(c.vote_value as usize)),from:c.from,to:c.to}).collect()});
@@ -139,6 +214,18 @@ pub struct ModifyStvFileOptions {
     #[clap(short, long, value_delimiter=',')]
     exclude : Option<Vec<CandidateIndex>>,
 
+    /// Exclude all the candidates in a party (group). The party may be given by its index (starting counting at zero),
+    /// its name, its abbreviation, or its column id (e.g. `B`). This may be used multiple times. The party's candidates
+    /// are added to any excluded candidates from `--exclude` or the .stv file.
+    #[clap(long)]
+    exclude_party : Vec<String>,
+
+    /// An optional list of candidates who withdrew before the count, in the same format as --exclude.
+    /// Unlike excluded candidates, votes only for withdrawn candidates are not counted at all, even for
+    /// the quota. If specified, this overrides any withdrawn candidates specified in the .stv file.
+    #[clap(long, value_delimiter=',')]
+    withdraw : Option<Vec<CandidateIndex>>,
+
     /// If a .vchange file is used for input instead of a .stv file, one of the vote manipulations in it can be applied first, specified here. 1 means the first one in the file, 2 the second, etc.
     /// This can be used to prove an upper bound on the margin.
     #[clap(short, long)]
@@ -173,6 +260,16 @@ pub struct ModifyStvFileOptions {
     #[clap(long,value_parser=try_parse_candidate_list)]
     tie : Vec<TieResolutionAtom>,
 
+    /// Like `--tie`, but with candidates specified by name rather than by index. As candidate names often
+    /// contain commas, names may be surrounded by double quotes, so `--tie-by-name '"Smith, J","Jones, K"'`
+    /// indicates that Jones, K should be favoured over Smith, J. A name may be either the name as stored
+    /// in the .stv file, or with the part before the comma moved to the end, e.g. `J Smith`.
+    ///
+    /// Names are checked after the .stv file is loaded; it is an error if a name does not match exactly
+    /// one candidate. These are applied after any `--tie` arguments.
+    #[clap(long,value_parser=try_parse_candidate_name_list)]
+    tie_by_name : Vec<TieResolutionByName>,
+
 }
 
 impl ModifyStvFileOptions {
@@ -188,17 +285,29 @@ impl ModifyStvFileOptions {
                     vchange.changes[modification_number_1_based-1].ballots.apply_to_votes(&vchange.original,verbose)
                 } else { vchange.original }
             } else {
-                serde_json::from_reader(file)?
+                load_with_migration(file)?
             }
         };
 
         if let Some(vacancies) = self.vacancies { votes.metadata.vacancies=Some(vacancies); }
         if let Some(ineligible) = self.exclude.as_ref() { votes.metadata.excluded = ineligible.clone(); }
-        if !self.tie.is_empty() { votes.metadata.tie_resolutions=TieResolutionsMadeByEC{tie_resolutions:self.tie.clone()}; }
+        for party in &self.exclude_party {
+            let party = votes.metadata.find_party(party)?;
+            let candidates = votes.metadata.party(party).candidates.clone();
+            for candidate in candidates {
+                if !votes.metadata.excluded.contains(&candidate) { votes.metadata.excluded.push(candidate); }
+            }
+        }
+        if let Some(withdrawn) = self.withdraw.as_ref() { votes.metadata.withdrawn_before_count = withdrawn.clone(); }
+        if !(self.tie.is_empty() && self.tie_by_name.is_empty()) {
+            let mut tie_resolutions = self.tie.clone();
+            for by_name in &self.tie_by_name { tie_resolutions.push(by_name.resolve(&votes.metadata)?); }
+            votes.metadata.tie_resolutions.tie_resolutions=tie_resolutions; // keep any default policies.
+        }
         Ok(votes)
     }
 
-    pub fn result_file_name(&self,input_path:&PathBuf,explicit_out_path:Option<&PathBuf>,extension:&str,rules:&Rules) -> PathBuf {
+    pub fn result_file_name(&self,input_path:&PathBuf,explicit_out_path:Option<&PathBuf>,extension:&str,rules:&impl Display) -> PathBuf {
         match explicit_out_path {
             None => {
                 let votename = input_path.file_name().map(|o|o.to_string_lossy()).unwrap_or_default();
@@ -212,3 +321,18 @@ impl ModifyStvFileOptions {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use stv::tie_resolution::TieResolutionUsage;
+    use crate::try_parse_candidate_name_list;
+
+    #[test]
+    fn test_parse_candidate_name_list() {
+        let parsed = try_parse_candidate_name_list("\"Smith, J\"/Brown,\"Jones, K\":3:Exclusion").unwrap();
+        assert_eq!(vec![vec!["Smith, J".to_string(),"Brown".to_string()],vec!["Jones, K".to_string()]],parsed.increasing_favour);
+        assert_eq!(Some(TieResolutionUsage::Exclusion),parsed.usage);
+        assert_eq!(Some(2),parsed.came_up_in.map(|c|c.0));
+        assert!(try_parse_candidate_name_list("Smith,,Jones").is_err());
+    }
+}
@@ -5,25 +5,42 @@
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
 
+use std::io::{BufRead, Write};
 use std::str::FromStr;
 use stv::election_data::ElectionData;
 use stv::tie_resolution::TieResolutionsMadeByEC;
-use stv::distribution_of_preferences_transcript::TranscriptWithMetadata;
-use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::distribution_of_preferences_transcript::{CountIndex, CountStopped, TranscriptWithMetadata};
+use stv::ballot_metadata::{CandidateIndex, ElectionMetadata, NameFormat, NumberOfCandidates};
+use stv::ballot_pile::BallotPaperCount;
 use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use federal::{FederalRulesUsed2013, FederalRulesUsed2019, FederalRulesUsed2016, FederalRulesPre2021, FederalRulesPost2021, FederalRulesPost2021Manual};
-use stv::preference_distribution::{distribute_preferences_with_extractors};
+use stv::preference_distribution::{distribute_preferences_with_options, CountOptions};
 use std::fmt::{Display, Formatter};
 use anyhow::anyhow;
 use act::{ACTPre2020, ACT2020, ACT2021};
 use stv::fixed_precision_decimal::FixedPrecisionDecimal;
+use stv::runtime_decimal::RuntimeDecimal;
+use stv::configurable_rules::ConfigurableRules;
 use serde::{Serialize,Deserialize};
 use margin::record_changes::ElectionChanges;
-use nsw::{NSWECLocalGov2021, NSWECLocalGov2021Literal, NSWLocalCouncilLegislation2021MyGuessAtHighlyAmbiguousLegislation, SimpleIRVAnyDifferenceBreaksTies};
+use nsw::{NSWECLocalGov2021, NSWECLocalGov2021Literal, NSWLocalCouncilLegislation2021MyGuessAtHighlyAmbiguousLegislation, SimpleIRVAnyDifferenceBreaksTies, SimpleIRVBatchExclusion, SimpleIRVContinueToTwoCandidates};
 use nsw::nsw_random_rules::{NSWECRandomLC2015, NSWECRandomLC2019, NSWECRandomLGE2012, NSWECRandomLGE2016, NSWECRandomLGE2017};
+use stv::aec_text::transcript_to_aec_text;
+use stv::parquet_export::write_transcript_parquet;
 use stv::compare_transcripts::{compare_transcripts, DifferenceBetweenTranscripts};
 use stv::extract_votes_in_pile::ExtractionRequest;
+use stv::first_preferences::{first_preferences, FirstPreferences};
+use stv::non_election_reasons::NonElectionReason;
+use stv::exhaustion_curve::{ExhaustionBreakdown, ExhaustionCurve};
+use stv::final_seat::FinalSeatAnalysis;
+use stv::trace_votes::{votes_electing, TraceVotesError};
+use stv::count_stepper::step_interactively;
 use stv::random_util::Randomness;
+use stv::transfer_value::TransferValueFormat;
+use stv::transfer_value_ledger::{write_transfer_value_ledger_csv, TVAnomaly};
+use stv::file_format::VersionedFile;
 use vic::Vic2018LegislativeCouncil;
 use wa::WALegislativeCouncil;
 use crate::ChangeOptions;
@@ -50,6 +67,8 @@ pub enum Rules {
     Vic2018,
     WA2008,
     IRV,
+    IRVContinueToTwo,
+    IRVBatch,
 }
 
 impl FromStr for Rules {
@@ -77,6 +96,8 @@ impl FromStr for Rules {
             "Vic2018" => Ok(Rules::Vic2018),
             "WA2008" => Ok(Rules::WA2008),
             "IRV" => Ok(Rules::IRV),
+            "IRVContinueToTwo" => Ok(Rules::IRVContinueToTwo),
+            "IRVBatch" => Ok(Rules::IRVBatch),
             _ => Err("No such rule supported")
         }
     }
@@ -105,6 +126,8 @@ impl Display for Rules {
             Rules::Vic2018 => "Vic2018",
             Rules::WA2008 => "WA2008",
             Rules::IRV => "IRV",
+            Rules::IRVContinueToTwo => "IRVContinueToTwo",
+            Rules::IRVBatch => "IRVBatch",
         };
         f.write_str(s)
     }
@@ -113,36 +136,48 @@ impl Display for Rules {
 impl Rules {
 
     pub fn count_simple(&self, data:&ElectionData, verbose:bool,randomness:&mut Randomness,extractors:&[ExtractionRequest],include_list_of_votes_in_transcript:bool) -> anyhow::Result<PossibleTranscripts> {
-        Ok(self.count(data,data.metadata.vacancies.ok_or_else(||anyhow!("Need to specify number of vacancies"))?,&data.metadata.excluded.iter().cloned().collect(),&data.metadata.tie_resolutions,None,verbose,randomness,extractors,include_list_of_votes_in_transcript))
+        self.count_simple_with_options(data,randomness,RulesCountOptions{ print_progress_to_stdout: verbose, extractors, include_list_of_votes_in_transcript, ..Default::default() })
+    }
+
+    /// Like [Self::count_simple], but with the variations given in `options`.
+    pub fn count_simple_with_options(&self, data:&ElectionData,randomness:&mut Randomness,options:RulesCountOptions<'_>) -> anyhow::Result<PossibleTranscripts> {
+        Ok(self.count_with_options(data,data.metadata.vacancies.ok_or_else(||anyhow!("Need to specify number of vacancies"))?,&data.metadata.excluded.iter().cloned().collect(),&data.metadata.tie_resolutions,randomness,options))
     }
 
     pub fn count(&self,data: &ElectionData,candidates_to_be_elected : NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:& TieResolutionsMadeByEC,vote_types : Option<&[String]>,print_progress_to_stdout:bool,randomness:&mut Randomness,extractors:&[ExtractionRequest],include_list_of_votes_in_transcript:bool) -> PossibleTranscripts {
+        self.count_with_options(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,RulesCountOptions{ vote_types, print_progress_to_stdout, extractors, include_list_of_votes_in_transcript, ..Default::default() })
+    }
+
+    /// Like [Self::count], but with the variations given in `options`.
+    pub fn count_with_options(&self,data: &ElectionData,candidates_to_be_elected : NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:& TieResolutionsMadeByEC,randomness:&mut Randomness,options:RulesCountOptions<'_>) -> PossibleTranscripts {
         let transcript = match self {
-            Rules::AEC2013 => distribute_preferences_with_extractors::<FederalRulesUsed2013>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::AEC2016 => distribute_preferences_with_extractors::<FederalRulesUsed2016>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::AEC2019 => distribute_preferences_with_extractors::<FederalRulesUsed2019>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::FederalPre2021 => distribute_preferences_with_extractors::<FederalRulesPre2021>(data, candidates_to_be_elected, excluded_candidates, ec_resolutions, vote_types, print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::FederalPost2021 => distribute_preferences_with_extractors::<FederalRulesPost2021>(data, candidates_to_be_elected, excluded_candidates, ec_resolutions, vote_types, print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::FederalPost2021Manual => distribute_preferences_with_extractors::<FederalRulesPost2021Manual>(data, candidates_to_be_elected, excluded_candidates, ec_resolutions, vote_types, print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::ACTPre2020 => distribute_preferences_with_extractors::<ACTPre2020>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::NSWLocalGov2021 => distribute_preferences_with_extractors::<NSWLocalCouncilLegislation2021MyGuessAtHighlyAmbiguousLegislation>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::NSWECLocalGov2021 => distribute_preferences_with_extractors::<NSWECLocalGov2021>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
+            Rules::AEC2013 => distribute_preferences_with_options::<FederalRulesUsed2013>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::AEC2016 => distribute_preferences_with_options::<FederalRulesUsed2016>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::AEC2019 => distribute_preferences_with_options::<FederalRulesUsed2019>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::FederalPre2021 => distribute_preferences_with_options::<FederalRulesPre2021>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::FederalPost2021 => distribute_preferences_with_options::<FederalRulesPost2021>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::FederalPost2021Manual => distribute_preferences_with_options::<FederalRulesPost2021Manual>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::ACTPre2020 => distribute_preferences_with_options::<ACTPre2020>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::NSWLocalGov2021 => distribute_preferences_with_options::<NSWLocalCouncilLegislation2021MyGuessAtHighlyAmbiguousLegislation>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::NSWECLocalGov2021 => distribute_preferences_with_options::<NSWECLocalGov2021>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
             Rules::NSWECLocalGov2021Literal => {
-                let transcript = distribute_preferences_with_extractors::<NSWECLocalGov2021Literal>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript);
+                let transcript = distribute_preferences_with_options::<NSWECLocalGov2021Literal>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options());
                 return PossibleTranscripts::SignedIntegers(TranscriptWithMetadata{ metadata: data.metadata.clone(), transcript })
             },
-            Rules::NSWECRandomLGE2012 => distribute_preferences_with_extractors::<NSWECRandomLGE2012>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::NSWECRandomLGE2016 => distribute_preferences_with_extractors::<NSWECRandomLGE2016>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::NSWECRandomLGE2017 => distribute_preferences_with_extractors::<NSWECRandomLGE2017>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::NSWECRandomLC2015 => distribute_preferences_with_extractors::<NSWECRandomLC2015>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::NSWECRandomLC2019 => distribute_preferences_with_extractors::<NSWECRandomLC2019>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::Vic2018 => distribute_preferences_with_extractors::<Vic2018LegislativeCouncil>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::WA2008 => distribute_preferences_with_extractors::<WALegislativeCouncil>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-            Rules::IRV => distribute_preferences_with_extractors::<SimpleIRVAnyDifferenceBreaksTies>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
+            Rules::NSWECRandomLGE2012 => distribute_preferences_with_options::<NSWECRandomLGE2012>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::NSWECRandomLGE2016 => distribute_preferences_with_options::<NSWECRandomLGE2016>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::NSWECRandomLGE2017 => distribute_preferences_with_options::<NSWECRandomLGE2017>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::NSWECRandomLC2015 => distribute_preferences_with_options::<NSWECRandomLC2015>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::NSWECRandomLC2019 => distribute_preferences_with_options::<NSWECRandomLC2019>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::Vic2018 => distribute_preferences_with_options::<Vic2018LegislativeCouncil>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::WA2008 => distribute_preferences_with_options::<WALegislativeCouncil>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::IRV => distribute_preferences_with_options::<SimpleIRVAnyDifferenceBreaksTies>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::IRVContinueToTwo => distribute_preferences_with_options::<SimpleIRVContinueToTwoCandidates>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+            Rules::IRVBatch => distribute_preferences_with_options::<SimpleIRVBatchExclusion>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
             _ => { // handle 6 digit transcripts.
                 let transcript = match self {
-                    Rules::ACT2020 => distribute_preferences_with_extractors::<ACT2020>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
-                    Rules::ACT2021 => distribute_preferences_with_extractors::<ACT2021>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,extractors,include_list_of_votes_in_transcript),
+                    Rules::ACT2020 => distribute_preferences_with_options::<ACT2020>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
+                    Rules::ACT2021 => distribute_preferences_with_options::<ACT2021>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options.count_options()),
                     _ => panic!("Case not handled.")
                 };
                 return PossibleTranscripts::SixDigitDecimals(TranscriptWithMetadata{ metadata: data.metadata.clone(), transcript })
@@ -151,6 +186,63 @@ impl Rules {
         PossibleTranscripts::Integers(TranscriptWithMetadata{ metadata: data.metadata.clone(), transcript })
     }
 
+    /// Just the first preference count and quota, much faster than a full count.
+    pub fn first_preferences(&self,data: &ElectionData,candidates_to_be_elected : NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:& TieResolutionsMadeByEC,vote_types : Option<&[String]>,randomness:&mut Randomness) -> PossibleFirstPreferences {
+        match self {
+            Rules::AEC2013 => PossibleFirstPreferences::Integers(first_preferences::<FederalRulesUsed2013>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::AEC2016 => PossibleFirstPreferences::Integers(first_preferences::<FederalRulesUsed2016>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::AEC2019 => PossibleFirstPreferences::Integers(first_preferences::<FederalRulesUsed2019>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::FederalPre2021 => PossibleFirstPreferences::Integers(first_preferences::<FederalRulesPre2021>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::FederalPost2021 => PossibleFirstPreferences::Integers(first_preferences::<FederalRulesPost2021>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::FederalPost2021Manual => PossibleFirstPreferences::Integers(first_preferences::<FederalRulesPost2021Manual>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::ACTPre2020 => PossibleFirstPreferences::Integers(first_preferences::<ACTPre2020>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::ACT2020 => PossibleFirstPreferences::SixDigitDecimals(first_preferences::<ACT2020>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::ACT2021 => PossibleFirstPreferences::SixDigitDecimals(first_preferences::<ACT2021>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::NSWLocalGov2021 => PossibleFirstPreferences::Integers(first_preferences::<NSWLocalCouncilLegislation2021MyGuessAtHighlyAmbiguousLegislation>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::NSWECLocalGov2021 => PossibleFirstPreferences::Integers(first_preferences::<NSWECLocalGov2021>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::NSWECLocalGov2021Literal => PossibleFirstPreferences::SignedIntegers(first_preferences::<NSWECLocalGov2021Literal>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::NSWECRandomLGE2012 => PossibleFirstPreferences::Integers(first_preferences::<NSWECRandomLGE2012>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::NSWECRandomLGE2016 => PossibleFirstPreferences::Integers(first_preferences::<NSWECRandomLGE2016>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::NSWECRandomLGE2017 => PossibleFirstPreferences::Integers(first_preferences::<NSWECRandomLGE2017>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::NSWECRandomLC2015 => PossibleFirstPreferences::Integers(first_preferences::<NSWECRandomLC2015>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::NSWECRandomLC2019 => PossibleFirstPreferences::Integers(first_preferences::<NSWECRandomLC2019>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::Vic2018 => PossibleFirstPreferences::Integers(first_preferences::<Vic2018LegislativeCouncil>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::WA2008 => PossibleFirstPreferences::Integers(first_preferences::<WALegislativeCouncil>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::IRV => PossibleFirstPreferences::Integers(first_preferences::<SimpleIRVAnyDifferenceBreaksTies>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::IRVContinueToTwo => PossibleFirstPreferences::Integers(first_preferences::<SimpleIRVContinueToTwoCandidates>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+            Rules::IRVBatch => PossibleFirstPreferences::Integers(first_preferences::<SimpleIRVBatchExclusion>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,randomness)),
+        }
+    }
+
+    /// Count, stepping through the count under control of commands read from input. See [stv::count_stepper].
+    pub fn step_interactively<R:BufRead,W:Write>(&self,data: &ElectionData,candidates_to_be_elected : NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:& TieResolutionsMadeByEC,randomness:&mut Randomness,input:R,output:W) -> std::io::Result<()> {
+        match self {
+            Rules::AEC2013 => { step_interactively::<FederalRulesUsed2013,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::AEC2016 => { step_interactively::<FederalRulesUsed2016,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::AEC2019 => { step_interactively::<FederalRulesUsed2019,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::FederalPre2021 => { step_interactively::<FederalRulesPre2021,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::FederalPost2021 => { step_interactively::<FederalRulesPost2021,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::FederalPost2021Manual => { step_interactively::<FederalRulesPost2021Manual,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::ACTPre2020 => { step_interactively::<ACTPre2020,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::ACT2020 => { step_interactively::<ACT2020,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::ACT2021 => { step_interactively::<ACT2021,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::NSWLocalGov2021 => { step_interactively::<NSWLocalCouncilLegislation2021MyGuessAtHighlyAmbiguousLegislation,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::NSWECLocalGov2021 => { step_interactively::<NSWECLocalGov2021,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::NSWECLocalGov2021Literal => { step_interactively::<NSWECLocalGov2021Literal,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::NSWECRandomLGE2012 => { step_interactively::<NSWECRandomLGE2012,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::NSWECRandomLGE2016 => { step_interactively::<NSWECRandomLGE2016,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::NSWECRandomLGE2017 => { step_interactively::<NSWECRandomLGE2017,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::NSWECRandomLC2015 => { step_interactively::<NSWECRandomLC2015,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::NSWECRandomLC2019 => { step_interactively::<NSWECRandomLC2019,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::Vic2018 => { step_interactively::<Vic2018LegislativeCouncil,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::WA2008 => { step_interactively::<WALegislativeCouncil,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::IRV => { step_interactively::<SimpleIRVAnyDifferenceBreaksTies,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::IRVContinueToTwo => { step_interactively::<SimpleIRVContinueToTwoCandidates,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+            Rules::IRVBatch => { step_interactively::<SimpleIRVBatchExclusion,_,_>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,input,output)?; }
+        }
+        Ok(())
+    }
+
     pub fn find_changes(&self,data:&ElectionData,options:&ChangeOptions,verbose:bool) -> anyhow::Result<PossibleChanges> {
         Ok(match self {
             Rules::AEC2013 => PossibleChanges::Integers(options.find_changes::<FederalRulesUsed2013>(data,verbose)?),
@@ -168,6 +260,8 @@ impl Rules {
             Rules::Vic2018 => PossibleChanges::Integers(options.find_changes::<Vic2018LegislativeCouncil>(data,verbose)?),
             Rules::WA2008 => PossibleChanges::Integers(options.find_changes::<WALegislativeCouncil>(data,verbose)?),
             Rules::IRV => PossibleChanges::Integers(options.find_changes::<SimpleIRVAnyDifferenceBreaksTies>(data,verbose)?),
+            Rules::IRVContinueToTwo => PossibleChanges::Integers(options.find_changes::<SimpleIRVContinueToTwoCandidates>(data,verbose)?),
+            Rules::IRVBatch => PossibleChanges::Integers(options.find_changes::<SimpleIRVBatchExclusion>(data,verbose)?),
             Rules::NSWECRandomLGE2012 => PossibleChanges::Integers(options.find_changes::<NSWECRandomLGE2012>(data, verbose)?),
             Rules::NSWECRandomLGE2016 => PossibleChanges::Integers(options.find_changes::<NSWECRandomLGE2016>(data, verbose)?),
             Rules::NSWECRandomLGE2017 => PossibleChanges::Integers(options.find_changes::<NSWECRandomLGE2017>(data, verbose)?),
@@ -178,6 +272,34 @@ impl Rules {
 
 }
 
+/// Like [Rules::count_simple], but for rules read from a configuration file rather than built in.
+pub fn count_simple_with_configurable_rules(rules:&Arc<ConfigurableRules>,data:&ElectionData,randomness:&mut Randomness,options:RulesCountOptions<'_>) -> anyhow::Result<PossibleTranscripts> {
+    let candidates_to_be_elected = data.metadata.vacancies.ok_or_else(||anyhow!("Need to specify number of vacancies"))?;
+    let excluded_candidates : HashSet<CandidateIndex> = data.metadata.excluded.iter().cloned().collect();
+    let transcript = rules.distribute_preferences(data,candidates_to_be_elected,&excluded_candidates,&data.metadata.tie_resolutions,randomness,options.count_options());
+    Ok(PossibleTranscripts::RuntimeDecimals(TranscriptWithMetadata{ metadata: data.metadata.clone(), transcript }))
+}
+
+/// Optional variations on a count, for [Rules::count_with_options]. These are the options in [CountOptions] that don't depend on the type of the tally.
+#[derive(Default,Clone,Copy)]
+pub struct RulesCountOptions<'a> {
+    /// If present, only count votes of these types.
+    pub vote_types : Option<&'a [String]>,
+    pub print_progress_to_stdout : bool,
+    pub extractors : &'a [ExtractionRequest],
+    pub include_list_of_votes_in_transcript : bool,
+    /// See [CountOptions::strict_ties].
+    pub strict_ties : bool,
+    /// See [CountOptions::cancel].
+    pub cancel : Option<&'a AtomicBool>,
+}
+
+impl <'a> RulesCountOptions<'a> {
+    fn count_options<Tally:PartialEq+Clone+Display+FromStr>(self) -> CountOptions<'a,Tally> {
+        CountOptions{ vote_types: self.vote_types, print_progress_to_stdout: self.print_progress_to_stdout, extractors: self.extractors, include_list_of_votes_in_transcript: self.include_list_of_votes_in_transcript, strict_ties: self.strict_ties, cancel: self.cancel, ..Default::default() }
+    }
+}
+
 #[derive(Serialize, Deserialize,Clone,Debug)]
 pub struct RulesDetails{
     pub name : String,
@@ -206,7 +328,9 @@ impl RulesDetails {
             RulesDetails{ name: "NSWECRandomLC2019".to_string(), description: "My interpretation of the rules actually used by the NSW electoral commission for the NSW 2019 and 2023 legislative council elections. Note that there is considerable randomness so recounting with a different random choices will probably produce different results. ".to_string() },
             RulesDetails{ name: "Vic2018".to_string(), description: "My interpretation of the rules that should have been used by the VEC since the 2018 modification to 114A(28)(c) of the Electoral Act 2002, and a plausible if not literal interpretation of the rules prior to that.".to_string() },
             RulesDetails{ name: "WA2018".to_string(), description: "My interpretation of the Western Australian Legislative Council rules consistent with the 2008 published official distribution of preferences.".to_string() },
-            RulesDetails{ name: "IRV".to_string(), description: "IRV with tie resolution by count backs with any non-equality breaking ties where possible.".to_string() },
+            RulesDetails{ name: "IRV".to_string(), description: "IRV with tie resolution by count backs with any non-equality breaking ties where possible. Stops as soon as a candidate has a majority of continuing votes.".to_string() },
+            RulesDetails{ name: "IRVContinueToTwo".to_string(), description: "Same as IRV, except candidates are always excluded until only two are left.".to_string() },
+            RulesDetails{ name: "IRVBatch".to_string(), description: "Same as IRV, except that the lowest candidates whose combined votes are fewer than those of the next lowest candidate are excluded together. Faster for large fields, and elects the same candidate unless a later tie is resolved by a count back.".to_string() },
         ]
     }
 }
@@ -220,7 +344,74 @@ pub enum PossibleChanges {
     SixDigitDecimals(ElectionChanges<FixedPrecisionDecimal<6>>),
 }
 
+impl PossibleChanges {
+    /// The smallest number of ballots found that, if changed, would stop the given originally elected candidate being elected.
+    pub fn smallest_change_unelecting(&self,candidate:CandidateIndex) -> Option<BallotPaperCount> {
+        fn smallest<Tally:Clone>(changes:&ElectionChanges<Tally>,candidate:CandidateIndex) -> Option<BallotPaperCount> {
+            changes.changes.iter().filter(|c|c.outcome.list2only.contains(&candidate)).map(|c|c.ballots.n).min()
+        }
+        match self {
+            PossibleChanges::Integers(c) => smallest(c,candidate),
+            PossibleChanges::SignedIntegers(c) => smallest(c,candidate),
+            PossibleChanges::SixDigitDecimals(c) => smallest(c,candidate),
+        }
+    }
+}
+
+
+
+#[derive(Serialize, Deserialize,Debug,Clone)]
+#[serde(untagged)]
+pub enum PossibleFirstPreferences {
+    Integers(FirstPreferences<usize>),
+    SignedIntegers(FirstPreferences<isize>),
+    SixDigitDecimals(FirstPreferences<FixedPrecisionDecimal<6>>),
+}
+
+#[derive(Serialize, Deserialize,Debug,Clone)]
+#[serde(untagged)]
+pub enum PossibleNonElectionReasons {
+    Integers(Vec<NonElectionReason<usize>>),
+    SignedIntegers(Vec<NonElectionReason<isize>>),
+    SixDigitDecimals(Vec<NonElectionReason<FixedPrecisionDecimal<6>>>),
+    RuntimeDecimals(Vec<NonElectionReason<RuntimeDecimal>>),
+}
 
+#[derive(Serialize, Deserialize,Debug,Clone)]
+#[serde(untagged)]
+pub enum PossibleExhaustionCurves {
+    Integers(ExhaustionCurve<usize>),
+    SignedIntegers(ExhaustionCurve<isize>),
+    SixDigitDecimals(ExhaustionCurve<FixedPrecisionDecimal<6>>),
+    RuntimeDecimals(ExhaustionCurve<RuntimeDecimal>),
+}
+
+#[derive(Serialize, Deserialize,Debug,Clone)]
+#[serde(untagged)]
+pub enum PossibleExhaustionBreakdowns {
+    Integers(ExhaustionBreakdown<usize>),
+    SignedIntegers(ExhaustionBreakdown<isize>),
+    SixDigitDecimals(ExhaustionBreakdown<FixedPrecisionDecimal<6>>),
+    RuntimeDecimals(ExhaustionBreakdown<RuntimeDecimal>),
+}
+
+#[derive(Serialize, Deserialize,Debug,Clone)]
+#[serde(untagged)]
+pub enum PossibleFinalSeatAnalyses {
+    Integers(FinalSeatAnalysis<usize>),
+    SignedIntegers(FinalSeatAnalysis<isize>),
+    SixDigitDecimals(FinalSeatAnalysis<FixedPrecisionDecimal<6>>),
+    RuntimeDecimals(FinalSeatAnalysis<RuntimeDecimal>),
+}
+
+#[derive(Serialize, Deserialize,Debug,Clone)]
+#[serde(untagged)]
+pub enum PossibleTwoCandidatePreferred {
+    Integers((CandidateIndex,usize,CandidateIndex,usize)),
+    SignedIntegers((CandidateIndex,isize,CandidateIndex,isize)),
+    SixDigitDecimals((CandidateIndex,FixedPrecisionDecimal<6>,CandidateIndex,FixedPrecisionDecimal<6>)),
+    RuntimeDecimals((CandidateIndex,RuntimeDecimal,CandidateIndex,RuntimeDecimal)),
+}
 
 #[derive(Serialize, Deserialize,Debug,Clone)]
 #[serde(untagged)]
@@ -228,6 +419,15 @@ pub enum PossibleTranscripts {
     Integers(TranscriptWithMetadata<usize>),
     SignedIntegers(TranscriptWithMetadata<isize>),
     SixDigitDecimals(TranscriptWithMetadata<FixedPrecisionDecimal<6>>),
+    /// Tallies of rules set at run time, see [stv::configurable_rules].
+    RuntimeDecimals(TranscriptWithMetadata<RuntimeDecimal>),
+}
+
+/// Upgrading old file layouts doesn't depend upon the type of the tallies.
+impl VersionedFile for PossibleTranscripts {
+    fn upgrade_one_version(value: &mut serde_json::Map<String, serde_json::Value>, version: u64) -> anyhow::Result<()> {
+        TranscriptWithMetadata::<usize>::upgrade_one_version(value,version)
+    }
 }
 
 impl PossibleTranscripts {
@@ -236,6 +436,166 @@ impl PossibleTranscripts {
             PossibleTranscripts::Integers(t) => {&t.transcript.elected}
             PossibleTranscripts::SignedIntegers(t) => {&t.transcript.elected}
             PossibleTranscripts::SixDigitDecimals(t) => {&t.transcript.elected}
+            PossibleTranscripts::RuntimeDecimals(t) => {&t.transcript.elected}
+        }
+    }
+
+    pub fn metadata(&self) -> &ElectionMetadata {
+        match self {
+            PossibleTranscripts::Integers(t) => &t.metadata,
+            PossibleTranscripts::SignedIntegers(t) => &t.metadata,
+            PossibleTranscripts::SixDigitDecimals(t) => &t.metadata,
+            PossibleTranscripts::RuntimeDecimals(t) => &t.metadata,
+        }
+    }
+
+    pub fn num_counts(&self) -> usize {
+        match self {
+            PossibleTranscripts::Integers(t) => t.transcript.counts.len(),
+            PossibleTranscripts::SignedIntegers(t) => t.transcript.counts.len(),
+            PossibleTranscripts::SixDigitDecimals(t) => t.transcript.counts.len(),
+            PossibleTranscripts::RuntimeDecimals(t) => t.transcript.counts.len(),
+        }
+    }
+
+    /// Record the randomness beacon used to seed random tie resolution. See [stv::random_util::Randomness::from_beacon].
+    pub fn set_randomness_beacon(&mut self,beacon:String) {
+        match self {
+            PossibleTranscripts::Integers(t) => t.transcript.randomness_beacon=Some(beacon),
+            PossibleTranscripts::SignedIntegers(t) => t.transcript.randomness_beacon=Some(beacon),
+            PossibleTranscripts::SixDigitDecimals(t) => t.transcript.randomness_beacon=Some(beacon),
+            PossibleTranscripts::RuntimeDecimals(t) => t.transcript.randomness_beacon=Some(beacon),
+        }
+    }
+
+    /// Merge consecutive exclusions of candidates with no votes. See [stv::distribution_of_preferences_transcript::Transcript::merge_vacuous_exclusions].
+    pub fn merge_vacuous_exclusions(&mut self) {
+        match self {
+            PossibleTranscripts::Integers(t) => t.transcript.merge_vacuous_exclusions(),
+            PossibleTranscripts::SignedIntegers(t) => t.transcript.merge_vacuous_exclusions(),
+            PossibleTranscripts::SixDigitDecimals(t) => t.transcript.merge_vacuous_exclusions(),
+            PossibleTranscripts::RuntimeDecimals(t) => t.transcript.merge_vacuous_exclusions(),
+        }
+    }
+
+    /// Write a CSV file listing every transfer value created in the count. See [write_transfer_value_ledger_csv].
+    pub fn write_transfer_value_ledger_csv<W:Write>(&self,name_format:NameFormat,format:TransferValueFormat,writer:W) -> anyhow::Result<()> {
+        match self {
+            PossibleTranscripts::Integers(t) => write_transfer_value_ledger_csv(&t.transcript.transfer_value_ledger(),Some(&t.metadata),name_format,format,writer),
+            PossibleTranscripts::SignedIntegers(t) => write_transfer_value_ledger_csv(&t.transcript.transfer_value_ledger(),Some(&t.metadata),name_format,format,writer),
+            PossibleTranscripts::SixDigitDecimals(t) => write_transfer_value_ledger_csv(&t.transcript.transfer_value_ledger(),Some(&t.metadata),name_format,format,writer),
+            PossibleTranscripts::RuntimeDecimals(t) => write_transfer_value_ledger_csv(&t.transcript.transfer_value_ledger(),Some(&t.metadata),name_format,format,writer),
+        }
+    }
+
+    /// Counts in which a transfer value increased. See [stv::distribution_of_preferences_transcript::Transcript::transfer_value_anomalies].
+    pub fn transfer_value_anomalies(&self) -> Vec<TVAnomaly> {
+        match self {
+            PossibleTranscripts::Integers(t) => t.transcript.transfer_value_anomalies(),
+            PossibleTranscripts::SignedIntegers(t) => t.transcript.transfer_value_anomalies(),
+            PossibleTranscripts::SixDigitDecimals(t) => t.transcript.transfer_value_anomalies(),
+            PossibleTranscripts::RuntimeDecimals(t) => t.transcript.transfer_value_anomalies(),
+        }
+    }
+
+    /// The transcript as text in the style of the AEC. See [transcript_to_aec_text].
+    pub fn to_aec_text(&self) -> String {
+        match self {
+            PossibleTranscripts::Integers(t) => transcript_to_aec_text(&t.transcript,&t.metadata),
+            PossibleTranscripts::SignedIntegers(t) => transcript_to_aec_text(&t.transcript,&t.metadata),
+            PossibleTranscripts::SixDigitDecimals(t) => transcript_to_aec_text(&t.transcript,&t.metadata),
+            PossibleTranscripts::RuntimeDecimals(t) => transcript_to_aec_text(&t.transcript,&t.metadata),
+        }
+    }
+
+    /// Write the per count, per candidate tallies as a Parquet file. See [write_transcript_parquet].
+    pub fn write_parquet<W:Write+Send>(&self,writer:W) -> anyhow::Result<()> {
+        match self {
+            PossibleTranscripts::Integers(t) => write_transcript_parquet(&t.transcript,&t.metadata,writer),
+            PossibleTranscripts::SignedIntegers(t) => write_transcript_parquet(&t.transcript,&t.metadata,writer),
+            PossibleTranscripts::SixDigitDecimals(t) => write_transcript_parquet(&t.transcript,&t.metadata,writer),
+            PossibleTranscripts::RuntimeDecimals(t) => write_transcript_parquet(&t.transcript,&t.metadata,writer),
+        }
+    }
+
+    /// The continuing candidates at the end of the given count who are certain to lose. See [stv::certain_losers].
+    /// Signed tallies are only used by rules in which a continuing candidate's tally can go down, which the test relies on not happening, so nothing is reported for them.
+    pub fn certain_losers_at_count(&self,count:CountIndex) -> Vec<CandidateIndex> {
+        match self {
+            PossibleTranscripts::Integers(t) => t.transcript.certain_losers_at_count(count),
+            PossibleTranscripts::SignedIntegers(_) => vec![],
+            PossibleTranscripts::SixDigitDecimals(t) => t.transcript.certain_losers_at_count(count),
+            PossibleTranscripts::RuntimeDecimals(t) => t.transcript.certain_losers_at_count(count),
+        }
+    }
+
+    /// Why each candidate not elected was not elected. See [stv::non_election_reasons].
+    pub fn non_election_reasons(&self) -> PossibleNonElectionReasons {
+        match self {
+            PossibleTranscripts::Integers(t) => PossibleNonElectionReasons::Integers(t.transcript.non_election_reasons(&t.metadata)),
+            PossibleTranscripts::SignedIntegers(t) => PossibleNonElectionReasons::SignedIntegers(t.transcript.non_election_reasons(&t.metadata)),
+            PossibleTranscripts::SixDigitDecimals(t) => PossibleNonElectionReasons::SixDigitDecimals(t.transcript.non_election_reasons(&t.metadata)),
+            PossibleTranscripts::RuntimeDecimals(t) => PossibleNonElectionReasons::RuntimeDecimals(t.transcript.non_election_reasons(&t.metadata)),
+        }
+    }
+
+    /// The exhausted and rounding values at the end of each count. See [stv::exhaustion_curve].
+    pub fn exhaustion_curve(&self) -> PossibleExhaustionCurves {
+        match self {
+            PossibleTranscripts::Integers(t) => PossibleExhaustionCurves::Integers(t.transcript.exhaustion_summary()),
+            PossibleTranscripts::SignedIntegers(t) => PossibleExhaustionCurves::SignedIntegers(t.transcript.exhaustion_summary()),
+            PossibleTranscripts::SixDigitDecimals(t) => PossibleExhaustionCurves::SixDigitDecimals(t.transcript.exhaustion_summary()),
+            PossibleTranscripts::RuntimeDecimals(t) => PossibleExhaustionCurves::RuntimeDecimals(t.transcript.exhaustion_summary()),
+        }
+    }
+
+    /// The split between value lost to rounding and value exhausted, per count and cumulative. See [stv::exhaustion_curve::ExhaustionBreakdown].
+    pub fn exhaustion_breakdown(&self) -> PossibleExhaustionBreakdowns {
+        match self {
+            PossibleTranscripts::Integers(t) => PossibleExhaustionBreakdowns::Integers(t.transcript.exhaustion_breakdown()),
+            PossibleTranscripts::SignedIntegers(t) => PossibleExhaustionBreakdowns::SignedIntegers(t.transcript.exhaustion_breakdown()),
+            PossibleTranscripts::SixDigitDecimals(t) => PossibleExhaustionBreakdowns::SixDigitDecimals(t.transcript.exhaustion_breakdown()),
+            PossibleTranscripts::RuntimeDecimals(t) => PossibleExhaustionBreakdowns::RuntimeDecimals(t.transcript.exhaustion_breakdown()),
+        }
+    }
+
+    /// The contest for the last seat awarded, if anyone was elected. See [stv::final_seat].
+    pub fn final_seat_analysis(&self) -> Option<PossibleFinalSeatAnalyses> {
+        match self {
+            PossibleTranscripts::Integers(t) => t.transcript.final_seat_analysis().map(PossibleFinalSeatAnalyses::Integers),
+            PossibleTranscripts::SignedIntegers(t) => t.transcript.final_seat_analysis().map(PossibleFinalSeatAnalyses::SignedIntegers),
+            PossibleTranscripts::SixDigitDecimals(t) => t.transcript.final_seat_analysis().map(PossibleFinalSeatAnalyses::SixDigitDecimals),
+            PossibleTranscripts::RuntimeDecimals(t) => t.transcript.final_seat_analysis().map(PossibleFinalSeatAnalyses::RuntimeDecimals),
+        }
+    }
+
+    /// The last two candidates standing and their tallies, if the count got down to two. See [stv::two_candidate_preferred].
+    pub fn final_two_candidate_preferred(&self) -> Option<PossibleTwoCandidatePreferred> {
+        match self {
+            PossibleTranscripts::Integers(t) => t.transcript.final_two_candidate_preferred().map(PossibleTwoCandidatePreferred::Integers),
+            PossibleTranscripts::SignedIntegers(t) => t.transcript.final_two_candidate_preferred().map(PossibleTwoCandidatePreferred::SignedIntegers),
+            PossibleTranscripts::SixDigitDecimals(t) => t.transcript.final_two_candidate_preferred().map(PossibleTwoCandidatePreferred::SixDigitDecimals),
+            PossibleTranscripts::RuntimeDecimals(t) => t.transcript.final_two_candidate_preferred().map(PossibleTwoCandidatePreferred::RuntimeDecimals),
+        }
+    }
+
+    /// Why the count stopped before it finished, if it did. See [stv::distribution_of_preferences_transcript::Transcript::stopped].
+    pub fn stopped(&self) -> Option<&CountStopped> {
+        match self {
+            PossibleTranscripts::Integers(t) => t.transcript.stopped.as_ref(),
+            PossibleTranscripts::SignedIntegers(t) => t.transcript.stopped.as_ref(),
+            PossibleTranscripts::SixDigitDecimals(t) => t.transcript.stopped.as_ref(),
+            PossibleTranscripts::RuntimeDecimals(t) => t.transcript.stopped.as_ref(),
+        }
+    }
+
+    /// The ballot papers held by a candidate when elected. See [stv::trace_votes].
+    pub fn votes_electing(&self,who:CandidateIndex) -> Result<ElectionData,TraceVotesError> {
+        match self {
+            PossibleTranscripts::Integers(t) => votes_electing(t,who),
+            PossibleTranscripts::SignedIntegers(t) => votes_electing(t,who),
+            PossibleTranscripts::SixDigitDecimals(t) => votes_electing(t,who),
+            PossibleTranscripts::RuntimeDecimals(t) => votes_electing(t,who),
         }
     }
 
@@ -244,12 +604,19 @@ impl PossibleTranscripts {
             (PossibleTranscripts::Integers(t1), PossibleTranscripts::Integers(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
             (PossibleTranscripts::Integers(t1), PossibleTranscripts::SignedIntegers(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
             (PossibleTranscripts::Integers(t1), PossibleTranscripts::SixDigitDecimals(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
+            (PossibleTranscripts::Integers(t1), PossibleTranscripts::RuntimeDecimals(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
             (PossibleTranscripts::SignedIntegers(t1), PossibleTranscripts::Integers(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
             (PossibleTranscripts::SignedIntegers(t1), PossibleTranscripts::SignedIntegers(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
             (PossibleTranscripts::SignedIntegers(t1), PossibleTranscripts::SixDigitDecimals(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
+            (PossibleTranscripts::SignedIntegers(t1), PossibleTranscripts::RuntimeDecimals(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
             (PossibleTranscripts::SixDigitDecimals(t1), PossibleTranscripts::Integers(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
             (PossibleTranscripts::SixDigitDecimals(t1), PossibleTranscripts::SignedIntegers(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
             (PossibleTranscripts::SixDigitDecimals(t1), PossibleTranscripts::SixDigitDecimals(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
+            (PossibleTranscripts::SixDigitDecimals(t1), PossibleTranscripts::RuntimeDecimals(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
+            (PossibleTranscripts::RuntimeDecimals(t1), PossibleTranscripts::Integers(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
+            (PossibleTranscripts::RuntimeDecimals(t1), PossibleTranscripts::SignedIntegers(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
+            (PossibleTranscripts::RuntimeDecimals(t1), PossibleTranscripts::SixDigitDecimals(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
+            (PossibleTranscripts::RuntimeDecimals(t1), PossibleTranscripts::RuntimeDecimals(t2)) => compare_transcripts(&t1.transcript,&t2.transcript),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,102 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Seat stability: combine the comparison of several plausible interpretations of the rules with
+//! margin analysis, to summarise which elected seats are robust and which are fragile.
+//!
+//! A seat (a candidate elected under at least one of the rules) is *robust* if the candidate is
+//! elected under all the rules considered, and no change of a small number of ballots was found
+//! that would stop them being elected under any of those rules. Otherwise it is *fragile*.
+//!
+//! Margins come from the heuristic search in the margin crate, so they are upper bounds; a seat
+//! marked robust may still have a smaller margin that was not found.
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use stv::ballot_metadata::{CandidateIndex, ElectionMetadata};
+use stv::ballot_pile::BallotPaperCount;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+use crate::ChangeOptions;
+use crate::rules::Rules;
+
+/// The default for what is considered a small margin in [stability_report], as a fraction of the formal votes.
+pub const DEFAULT_SMALL_MARGIN_FRACTION : f64 = 0.01;
+
+/// How stable one seat is across several rules.
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct SeatStability {
+    pub candidate : CandidateIndex,
+    /// The rules under which the candidate is elected.
+    pub elected_under : Vec<String>,
+    /// The smallest number of ballots found that, if changed, would stop the candidate being elected under
+    /// one of the rules they were elected under, along with that rule. None if no such change was found.
+    pub smallest_margin : Option<(BallotPaperCount,String)>,
+    /// Elected under all the rules, and no margin at or below the small margin threshold was found.
+    pub robust : bool,
+}
+
+/// For one contest, which seats are robust and which are fragile over a set of rules.
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct StabilityReport {
+    pub metadata : ElectionMetadata,
+    pub rules : Vec<String>,
+    /// A seat with a margin of this many ballots or fewer is fragile.
+    pub small_margin : BallotPaperCount,
+    /// Each candidate elected under at least one rule, in order of candidate index.
+    pub seats : Vec<SeatStability>,
+}
+
+/// Count the data under each of the rules, and report on the stability of each seat, considering a margin
+/// of at most [DEFAULT_SMALL_MARGIN_FRACTION] of the formal votes to be small.
+pub fn stability_report(data:&ElectionData,rules:&[Rules]) -> anyhow::Result<StabilityReport> {
+    let small_margin = BallotPaperCount((data.num_votes() as f64*DEFAULT_SMALL_MARGIN_FRACTION) as usize);
+    StabilityReport::create(data,rules,small_margin)
+}
+
+impl StabilityReport {
+    /// Count the data under each of the rules, and search for the margin of each seat under each rule it is won under.
+    pub fn create(data:&ElectionData,rules:&[Rules],small_margin:BallotPaperCount) -> anyhow::Result<Self> {
+        let mut seats : Vec<SeatStability> = vec![];
+        for rule in rules {
+            let transcript = rule.count_simple(data,false,&mut Randomness::ReverseDonkeyVote,&[],false)?;
+            let mut data_with_results = data.clone();
+            data_with_results.metadata.results=Some(transcript.elected().clone());
+            let changes = rule.find_changes(&data_with_results,&ChangeOptions::default(),false)?;
+            for &candidate in transcript.elected() {
+                let seat = match seats.iter_mut().find(|s|s.candidate==candidate) {
+                    Some(existing) => existing,
+                    None => { seats.push(SeatStability{candidate,elected_under:vec![],smallest_margin:None,robust:false}); seats.last_mut().unwrap() }
+                };
+                seat.elected_under.push(rule.to_string());
+                if let Some(margin) = changes.smallest_change_unelecting(candidate) {
+                    if seat.smallest_margin.as_ref().map(|(m,_)|margin< *m).unwrap_or(true) { seat.smallest_margin=Some((margin,rule.to_string())); }
+                }
+            }
+        }
+        for seat in &mut seats {
+            seat.robust = seat.elected_under.len()==rules.len() && seat.smallest_margin.as_ref().map(|(m,_)|*m>small_margin).unwrap_or(true);
+        }
+        seats.sort_by_key(|s|s.candidate.0);
+        Ok(StabilityReport{metadata:data.metadata.clone(),rules:rules.iter().map(|r|r.to_string()).collect(),small_margin,seats})
+    }
+
+    pub fn robust(&self) -> Vec<CandidateIndex> { self.seats.iter().filter(|s|s.robust).map(|s|s.candidate).collect() }
+    pub fn fragile(&self) -> Vec<CandidateIndex> { self.seats.iter().filter(|s|!s.robust).map(|s|s.candidate).collect() }
+}
+
+impl fmt::Display for StabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.metadata.name.human_readable_name())?;
+        writeln!(f,"Candidate\tStability\tElected under\tSmallest margin")?;
+        for seat in &self.seats {
+            let elected_under = if seat.elected_under.len()==self.rules.len() { "all".to_string() } else { seat.elected_under.join(" ") };
+            let margin = match &seat.smallest_margin { Some((m,rule)) => format!("{} ({})",m,rule), None => "none found".to_string() };
+            writeln!(f,"{}\t{}\t{}\t{}",self.metadata.candidate(seat.candidate).name,if seat.robust {"robust"} else {"fragile"},elected_under,margin)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the batch comparison of rules over the example .stv files.
+
+use std::path::Path;
+use main_app::compare_different_rules::BatchRulesComparison;
+use main_app::rules::Rules;
+use stv::compare_transcripts::DifferenceBetweenTranscripts;
+
+#[test]
+fn test_compare_federal_rules_on_examples() {
+    let rules = [Rules::AEC2013,Rules::AEC2016,Rules::AEC2019,Rules::FederalPre2021,Rules::FederalPost2021,Rules::FederalPost2021Manual];
+    let comparison = BatchRulesComparison::create_for_directory(Path::new("../examples"),&rules).unwrap();
+    assert_eq!(comparison.rules.len(),6);
+    let contest = |name:&str| comparison.contests.iter().find(|c|c.contest==name).unwrap();
+    // Bulk exclusion changes the rounding of transfer values, and thus who is elected. See the comment in the .stv file.
+    let rounding = contest("MultipleExclusionRounding.stv");
+    assert!(matches!(rounding.most_serious_difference(),DifferenceBetweenTranscripts::DifferentCandidatesElected(_)));
+    assert_eq!(rounding.rules_with_most_serious_difference(),vec!["AEC2016".to_string(),"AEC2019".to_string(),"FederalPost2021".to_string()]);
+    assert_eq!(contest("SimpleExample.stv").most_serious_difference(),DifferenceBetweenTranscripts::Same);
+    let summary = comparison.summary();
+    assert_eq!(summary[0].0,"DifferentCandidatesElected");
+    assert!(summary[0].1>=1);
+    assert_eq!(summary.iter().map(|(_,n)|*n).sum::<usize>(),comparison.contests.len());
+    assert!(comparison.to_string().contains("MultipleExclusionRounding.stv\tDifferentCandidatesElected\tAEC2016 AEC2019 FederalPost2021"));
+}
@@ -0,0 +1,78 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Run the concrete_stv program on the example data.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Run concrete_stv with the given rules on the given .stv file with --compare-official, returning stdout.
+fn count_comparing_official(rules:&str,votes:&Path,name:&str) -> String {
+    let transcript : PathBuf = std::env::temp_dir().join(format!("concrete_stv_test_cli_{}_{}.transcript",std::process::id(),name));
+    let output = Command::new(env!("CARGO_BIN_EXE_concrete_stv")).arg(rules).arg(votes).arg("--transcript").arg(&transcript).arg("--compare-official").output().unwrap();
+    assert!(output.status.success(),"concrete_stv failed : {}",String::from_utf8_lossy(&output.stderr));
+    assert!(transcript.exists());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_compare_official() {
+    // SimpleExample.stv has the results A1, C1, A2 embedded.
+    let out = count_comparing_official("AEC2019",Path::new("../examples/SimpleExample.stv"),"same");
+    assert!(out.contains("Matches official results"),"{}",out);
+    // Change the official results so they are in a different order, and then different.
+    let mut data : serde_json::Value = serde_json::from_reader(std::fs::File::open("../examples/SimpleExample.stv").unwrap()).unwrap();
+    let modified = std::env::temp_dir().join(format!("concrete_stv_test_cli_{}_modified.stv",std::process::id()));
+    data["metadata"]["results"] = serde_json::json!([0,2,3]);
+    std::fs::write(&modified,data.to_string()).unwrap();
+    let out = count_comparing_official("AEC2019",&modified,"order");
+    assert!(out.contains("different order"),"{}",out);
+    data["metadata"]["results"] = serde_json::json!([0,2,4]);
+    std::fs::write(&modified,data.to_string()).unwrap();
+    let out = count_comparing_official("AEC2019",&modified,"different");
+    assert!(out.contains("DIFFERENT candidates elected"),"{}",out);
+    assert!(out.contains("Only computed [[\"A2\"]] only official [[\"P1\"]]"),"{}",out);
+    // no results at all.
+    let out = count_comparing_official("AEC2019",Path::new("../examples/MultipleExclusionRounding.stv"),"none");
+    assert!(out.contains("No official results"),"{}",out);
+}
+
+#[test]
+fn test_step() {
+    use std::io::Write;
+    use std::process::Stdio;
+    let mut child = Command::new(env!("CARGO_BIN_EXE_concrete_stv")).arg("step").arg("../examples/SimpleExample.stv").arg("--rules").arg("AEC2019").stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(b"c\nn\nr\nwhy C1\nwhy 4\nq\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(),"concrete_stv step failed : {}",String::from_utf8_lossy(&output.stderr));
+    let out = String::from_utf8(output.stdout).unwrap();
+    assert!(out.contains("Quota 61"),"{}",out);
+    assert!(out.contains("Count 1 : first preferences"),"{}",out);
+    assert!(out.contains("The count is over. Elected 2 A1, 0 C1, 3 A2"),"{}",out);
+    assert!(out.contains("0 C1 was elected in count 1 (ReachedQuota) with a tally of 110."),"{}",out);
+    assert!(out.contains("4 P1 was excluded starting in count 4, having a tally of 24"),"{}",out);
+}
+
+#[test]
+fn test_exclude_party() {
+    // Excluding the Anticlockwise Alliance (party 1, column B) excludes both A1 and A2 before the count, so they get nothing at count 1.
+    for (description,name) in [("Anticlockwise Alliance","party_name"),("1","party_index"),("B","party_column")] {
+        let transcript : PathBuf = std::env::temp_dir().join(format!("concrete_stv_test_cli_{}_{}.transcript",std::process::id(),name));
+        let output = Command::new(env!("CARGO_BIN_EXE_concrete_stv")).arg("AEC2019").arg("../examples/SimpleExample.stv").arg("--exclude-party").arg(description).arg("--transcript").arg(&transcript).output().unwrap();
+        assert!(output.status.success(),"concrete_stv failed : {}",String::from_utf8_lossy(&output.stderr));
+        let transcript : serde_json::Value = serde_json::from_reader(std::fs::File::open(&transcript).unwrap()).unwrap();
+        assert_eq!(transcript["metadata"]["excluded"],serde_json::json!([2,3]));
+        let first_count = &transcript["transcript"]["counts"][0]["status"];
+        for candidate in [2,3] {
+            assert_eq!(first_count["tallies"]["candidate"][candidate],serde_json::json!(0));
+            assert_eq!(first_count["papers"]["candidate"][candidate],serde_json::json!(0));
+        }
+        let elected = transcript["transcript"]["elected"].as_array().unwrap();
+        assert!(!elected.contains(&serde_json::json!(2)) && !elected.contains(&serde_json::json!(3)),"{:?}",elected);
+    }
+    let output = Command::new(env!("CARGO_BIN_EXE_concrete_stv")).arg("AEC2019").arg("../examples/SimpleExample.stv").arg("--exclude-party").arg("Nonexistent Party").output().unwrap();
+    assert!(!output.status.success());
+}
@@ -0,0 +1,37 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the seat stability report over an example .stv file.
+
+use std::fs::File;
+use main_app::rules::Rules;
+use main_app::seat_stability::stability_report;
+use stv::ballot_metadata::CandidateIndex;
+use stv::election_data::ElectionData;
+use stv::file_format::load_with_migration;
+
+#[test]
+fn test_seat_stability_multiple_exclusion_rounding() {
+    let data : ElectionData = load_with_migration(File::open("../examples/MultipleExclusionRounding.stv").unwrap()).unwrap();
+    // Bulk exclusion changes who is elected between these rules. See the comment in the .stv file.
+    let rules = [Rules::FederalPre2021,Rules::AEC2016];
+    let report = stability_report(&data,&rules).unwrap();
+    assert_eq!(report.rules,vec!["FederalPre2021".to_string(),"AEC2016".to_string()]);
+    let seat = |c:usize| report.seats.iter().find(|s|s.candidate==CandidateIndex(c)).unwrap();
+    // Q is elected on the first count under both rules.
+    assert_eq!(seat(0).elected_under.len(),2);
+    // W1 and W2 are separated by one vote, so whichever gets the second seat is fragile.
+    let w_seats : Vec<_> = report.seats.iter().filter(|s|s.candidate==CandidateIndex(1) || s.candidate==CandidateIndex(2)).collect();
+    assert!(!w_seats.is_empty());
+    for w in &w_seats {
+        assert!(!w.robust);
+        assert!(report.fragile().contains(&w.candidate));
+    }
+    // Different rules elect different second candidates.
+    assert_eq!(w_seats.len(),2);
+    assert!(w_seats.iter().all(|w|w.elected_under.len()==1));
+    assert!(report.to_string().contains("fragile"));
+}
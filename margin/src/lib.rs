@@ -12,4 +12,9 @@ pub mod find_outcome_changes;
 pub mod vote_changes;
 pub mod choose_votes;
 pub mod evaluate_and_optimize_vote_changes;
-pub mod record_changes;
\ No newline at end of file
+pub mod record_changes;
+pub mod support_certificate;
+pub mod nonmonotonicity;
+pub mod pivotal_ballots;
+
+pub use pivotal_ballots::{pivotal_ballot_types, PivotalBallotType};
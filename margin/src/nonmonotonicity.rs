@@ -0,0 +1,86 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Look for non-monotonicity : an elected candidate who would not have been elected if they had more first preference support.
+//!
+//! The perturbations tried are of the simplest form : some voters who gave their first preference to another candidate
+//! instead put the elected candidate first, leaving the rest of their preferences in the same order. Only below the
+//! line votes are changed, as raising a candidate in an above the line vote would also raise their running mates.
+//! Every number of ballots up to a given maximum is tried for each other candidate, smallest first, so the witness
+//! found is a smallest one of this form. Other, smaller, non-monotonic perturbations may exist.
+
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use stv::ballot_metadata::CandidateIndex;
+use stv::ballot_paper::BTL;
+use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::election_data::ElectionData;
+use stv::preference_distribution::PreferenceDistributionRules;
+use stv::random_util::Randomness;
+use crate::choose_votes::ChooseVotesOptions;
+use crate::retroscope::Retroscope;
+
+/// A demonstration that an elected candidate would not have been elected with more first preference support.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct NonMonotonicityWitness {
+    /// The candidate who was elected, but would not have been with the extra support.
+    pub candidate : CandidateIndex,
+    /// The candidate who originally got the first preference on the changed ballots.
+    pub raised_over : CandidateIndex,
+    /// The total number of ballots changed.
+    pub ballots : BallotPaperCount,
+    /// The original ballots changed, with `n` being the number of such ballots changed.
+    pub changed : Vec<BTL>,
+    /// Who would be elected after the change.
+    pub elected_after_change : Vec<CandidateIndex>,
+}
+
+impl NonMonotonicityWitness {
+    /// The election data with the changed ballots having [Self::candidate] moved to first preference.
+    pub fn apply_to_votes(&self,data:&ElectionData) -> ElectionData {
+        let mut res = data.clone();
+        for changed in &self.changed {
+            let mut togo = changed.n;
+            for original in res.btl.iter_mut().filter(|b|b.candidates==changed.candidates) {
+                let take = togo.min(original.n);
+                original.n-=take;
+                togo-=take;
+            }
+            let mut candidates = vec![self.candidate];
+            candidates.extend(changed.candidates.iter().cloned().filter(|&c|c!=self.candidate));
+            res.btl.push(BTL{ candidates, n: changed.n });
+        }
+        res
+    }
+}
+
+/// Look for a way of unseating `candidate` by changing at most `max_ballots` ballots from other candidates' first preferences to `candidate`'s.
+/// Returns None if `candidate` was not elected in the first place, or no such change was found.
+/// See the [module documentation](crate::nonmonotonicity) for the perturbations tried.
+pub fn find_nonmonotonicity<Rules:PreferenceDistributionRules>(data:&ElectionData,candidate:CandidateIndex,max_ballots:BallotPaperCount) -> Option<NonMonotonicityWitness> {
+    let transcript = data.distribute_preferences::<Rules>(&mut Randomness::ReverseDonkeyVote);
+    if !transcript.elected.contains(&candidate) { return None; }
+    let mut retroscope = Retroscope::new(data,&data.metadata.excluded);
+    retroscope.apply(CountIndex(0),&transcript.counts[0]);
+    let options = ChooseVotesOptions{ allow_atl: false, allow_first_pref: true, allow_verifiable: true, ballot_types_considered_unverifiable: HashSet::default(), allow_additions: false, allow_from: None, allow_to: None };
+    let others : Vec<CandidateIndex> = (0..data.metadata.candidates.len()).map(CandidateIndex).filter(|&c|c!=candidate && !data.metadata.excluded.contains(&c)).collect();
+    let num_atl = data.atl.len();
+    for n in 1..=max_ballots.0 {
+        for &raised_over in &others {
+            let mut chooser = retroscope.get_chooser(raised_over,data,&options);
+            let Some(found) = chooser.get_votes::<Rules>(Rules::Tally::from(BallotPaperCount(n)),false) else { continue };
+            let changed : Vec<BTL> = found.iter().flat_map(|f|f.ballots.iter()).map(|take|BTL{ candidates: data.btl[take.from.0-num_atl].candidates.clone(), n: take.n }).collect();
+            let mut witness = NonMonotonicityWitness{ candidate, raised_over, ballots: BallotPaperCount(changed.iter().map(|b|b.n).sum()), changed, elected_after_change: vec![] };
+            let changed_transcript = witness.apply_to_votes(data).distribute_preferences::<Rules>(&mut Randomness::ReverseDonkeyVote);
+            if !changed_transcript.elected.contains(&candidate) {
+                witness.elected_after_change = changed_transcript.elected;
+                return Some(witness);
+            }
+        }
+    }
+    None
+}
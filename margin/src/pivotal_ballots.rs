@@ -0,0 +1,100 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Find the "pivotal" ballot types : the kinds of ballots for which changing the fewest would flip
+//! an elected/unelected boundary. This guides auditors toward the most sensitive ballots.
+//!
+//! For each elected candidate, the boundary considered is with the highest continuing unelected candidate
+//! (the runner up) at the count in which they were elected. The ballots sitting on the winner's pile at that
+//! count are grouped by the preferences used to get there (the prefix up to and including the winner), and by transfer value.
+//! For each group, the number of those ballots that would need to be changed to go to the runner up instead to
+//! put the runner up ahead is computed.
+//!
+//! Like [crate::find_outcome_changes], this is a heuristic : it only looks at the tallies in that one count,
+//! and changing ballots would in general alter earlier counts and transfer values too.
+
+use std::collections::HashMap;
+use num_traits::One;
+use serde::{Deserialize, Serialize};
+use stv::ballot_metadata::CandidateIndex;
+use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{BigRational, PreferenceDistributionRules};
+use stv::random_util::Randomness;
+use stv::transfer_value::TransferValue;
+use crate::choose_votes::ChooseVotesOptions;
+use crate::retroscope::Retroscope;
+
+/// A kind of ballot that could be changed to flip an elected/unelected boundary. See [pivotal_ballot_types].
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct PivotalBallotType {
+    /// The elected candidate on whose pile these ballots sit.
+    pub winner : CandidateIndex,
+    /// The highest unelected continuing candidate when the winner was elected, who would get the changed ballots.
+    pub runner_up : CandidateIndex,
+    /// The count in which the winner was elected.
+    pub count : CountIndex,
+    /// The preferences on these ballots used to get to the winner, ending with the winner.
+    pub prefix : Vec<CandidateIndex>,
+    /// Whether these are above the line votes.
+    pub atl : bool,
+    /// The transfer value of these ballots on the winner's pile.
+    pub transfer_value : TransferValue,
+    /// The number of ballots of this type on the winner's pile.
+    pub available : BallotPaperCount,
+    /// The number of ballots of this type that would need to be changed from the winner to the runner up to put the runner up ahead.
+    /// If this is more than [Self::available], then this type of ballot could not flip the boundary on its own.
+    pub needed : BallotPaperCount,
+}
+
+impl PivotalBallotType {
+    /// Whether there are enough ballots of this type to flip the boundary on their own.
+    pub fn sufficient(&self) -> bool { self.needed<=self.available }
+}
+
+/// Find the ballot types most efficient at flipping each elected/unelected boundary. See the module documentation.
+///
+/// Sorted most pivotal first, that is, types that can flip a boundary on their own first, then by fewest ballots needed.
+/// Only ballots allowed by `options` are considered, and boundaries where `options` forbids taking votes from the winner or giving them to the runner up are skipped.
+pub fn pivotal_ballot_types<Rules:PreferenceDistributionRules>(data:&ElectionData,options:&ChooseVotesOptions) -> Vec<PivotalBallotType> {
+    let transcript = data.distribute_preferences::<Rules>(&mut Randomness::ReverseDonkeyVote);
+    let mut retroscope = Retroscope::new(data,&data.metadata.excluded);
+    let two = BigRational::one()+BigRational::one();
+    let mut res = vec![];
+    for (index,count) in transcript.counts.iter().enumerate() {
+        let count_index = CountIndex(index);
+        retroscope.apply(count_index,count);
+        for elected in &count.elected {
+            let winner = elected.who;
+            let runner_up = retroscope.continuing.iter().filter(|c|!transcript.elected.contains(c)).max_by_key(|c|(count.status.tallies.candidate[c.0].clone(),usize::MAX-c.0)).cloned();
+            let Some(runner_up) = runner_up else { continue };
+            if !(options.allow_from_candidate(winner) && options.allow_to_candidate(runner_up)) { continue; }
+            let gap = Rules::convert_tally_to_rational(count.status.tallies.candidate[winner.0].clone())-Rules::convert_tally_to_rational(count.status.tallies.candidate[runner_up.0].clone());
+            let half_gap = gap/two.clone();
+            // group everything on the winner's pile by prefix, ATL or not, and transfer value.
+            let mut chooser = retroscope.get_chooser(winner,data,options);
+            let everything = chooser.votes_available_total::<Rules>();
+            let mut groups : HashMap<(Vec<CandidateIndex>,bool,TransferValue),BallotPaperCount> = HashMap::default();
+            for parcel in chooser.get_votes::<Rules>(everything,options.allow_atl).unwrap_or_default() {
+                for taken in parcel.ballots {
+                    let atl = taken.from.0<data.atl.len();
+                    let status = if atl { &retroscope.votes.atl[taken.from.0] } else { &retroscope.votes.btl[taken.from.0-data.atl.len()] };
+                    let prefix = status.prefs[..=status.upto].to_vec();
+                    *groups.entry((prefix,atl,parcel.tv.clone())).or_insert(BallotPaperCount(0))+=BallotPaperCount(taken.n);
+                }
+            }
+            for ((prefix,atl,transfer_value),available) in groups {
+                // need strictly more than half the gap to move to put the runner up ahead.
+                let mut needed = transfer_value.num_ballot_papers_to_get_this_tv(half_gap.clone());
+                if transfer_value.mul(needed)<=half_gap { needed+=BallotPaperCount(1); }
+                res.push(PivotalBallotType{ winner, runner_up, count: count_index, prefix, atl, transfer_value, available, needed });
+            }
+        }
+    }
+    res.sort_by_key(|t|(!t.sufficient(),t.needed,usize::MAX-t.available.0,t.winner.0,t.prefix.iter().map(|c|c.0).collect::<Vec<_>>(),t.atl));
+    res
+}
@@ -0,0 +1,108 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Work out a "support certificate" for an elected candidate : a small set of ballots
+//! that were, together, sufficient to elect them. This helps explain "who elected whom".
+//!
+//! This is a heuristic rather than an exact minimum. The ballots sitting on the candidate's
+//! pile when they were elected are used, taking the highest transfer values first, until their
+//! combined (exact, unrounded) value reaches the quota. This is the smallest such subset of that pile,
+//! but a different set of ballots could conceivably be smaller, and removing the other ballots from
+//! the election would in general change transfer values, so this should not be read as a strict
+//! statement that these ballots alone would have elected the candidate.
+
+use std::collections::HashMap;
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use stv::ballot_metadata::CandidateIndex;
+use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::{CountIndex, Transcript};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{BigRational, PreferenceDistributionRules};
+use stv::transfer_value::TransferValue;
+use num_traits::Signed;
+use crate::retroscope::{Retroscope, RetroscopeVoteIndex};
+
+/// Some ballots from a single vote (ATL or BTL structure) in an [ElectionData].
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct BallotSetEntry {
+    /// Which vote this is, in the style of [Retroscope], that is ATL votes then BTL votes.
+    pub vote : RetroscopeVoteIndex,
+    /// The number of ballots with this vote used. This may be less than the number of people who voted this way.
+    pub ballots : BallotPaperCount,
+    /// The transfer value the ballots had when sitting on the elected candidate's pile.
+    pub transfer_value : TransferValue,
+    /// The count at which the ballots arrived on the elected candidate's pile.
+    pub count_arrived : CountIndex,
+    /// The candidate who got the first preference from these ballots.
+    pub first_preference : CandidateIndex,
+}
+
+/// A set of ballots, such as produced by [support_certificate].
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct BallotSet {
+    pub entries : Vec<BallotSetEntry>,
+    /// The total number of ballots over all entries.
+    pub ballots : BallotPaperCount,
+}
+
+impl BallotSet {
+    /// Summarise by first preference, giving the number of ballots whose first preference was each candidate.
+    /// Sorted by decreasing number of ballots.
+    pub fn by_first_preference(&self) -> Vec<(CandidateIndex,BallotPaperCount)> {
+        let mut counts : HashMap<CandidateIndex,usize> = HashMap::default();
+        for entry in &self.entries {
+            *counts.entry(entry.first_preference).or_insert(0)+=entry.ballots.0;
+        }
+        let mut res : Vec<(CandidateIndex,BallotPaperCount)> = counts.into_iter().map(|(c,n)|(c,BallotPaperCount(n))).collect();
+        res.sort_by_key(|(c,n)|(usize::MAX-n.0,c.0));
+        res
+    }
+}
+
+/// Find a small set of ballots that were sufficient to give the candidate a quota, tracing back through transfers
+/// to the original ballots (and thus first preferences). See the module documentation for the heuristic nature of this.
+///
+/// If the candidate was elected without reaching a quota (e.g. as one of the last remaining candidates),
+/// then all the ballots on their pile when elected are returned. An error is returned if the candidate was not elected.
+pub fn support_certificate<Rules:PreferenceDistributionRules>(data:&ElectionData,transcript:&Transcript<Rules::Tally>,candidate:CandidateIndex) -> anyhow::Result<BallotSet> {
+    let elected_count = transcript.counts.iter().position(|c|c.elected.iter().any(|e|e.who==candidate)).ok_or_else(||anyhow!("Candidate {} was not elected",data.metadata.candidate(candidate).name))?;
+    let mut retroscope = Retroscope::new(data,&data.metadata.excluded);
+    for (index,count) in transcript.counts[..=elected_count].iter().enumerate() {
+        retroscope.apply(CountIndex(index),count);
+    }
+    let mut pile : Vec<BallotSetEntry> = vec![];
+    for (&count_arrived,votes) in &retroscope.piles_by_candidate[candidate.0].by_count {
+        let transfer_value = retroscope.transfer_value(count_arrived);
+        for &vote in votes {
+            let (n,first_preference) = if vote.0<data.atl.len() {
+                let atl = &data.atl[vote.0];
+                (atl.n,atl.resolve_to_candidates(&data.metadata)[0])
+            } else {
+                let btl = &data.btl[vote.0-data.atl.len()];
+                (btl.n,btl.candidates[0])
+            };
+            pile.push(BallotSetEntry{ vote, ballots: BallotPaperCount(n), transfer_value: transfer_value.clone(), count_arrived, first_preference })
+        }
+    }
+    // highest transfer value first. Ties broken by vote index for determinism.
+    pile.sort_by(|a,b|b.transfer_value.cmp(&a.transfer_value).then(a.vote.0.cmp(&b.vote.0)));
+    let pile_value : BigRational = pile.iter().map(|e|e.transfer_value.mul(e.ballots)).sum();
+    let quota : Option<BigRational> = transcript.quota.as_ref().map(|q|Rules::convert_tally_to_rational(q.quota.clone())).filter(|quota|*quota<=pile_value);
+    let mut res = BallotSet{ entries: vec![], ballots: BallotPaperCount(0) };
+    let mut still_needed = quota;
+    for mut entry in pile {
+        if let Some(needed) = still_needed.as_mut() {
+            if !needed.is_positive() { break; }
+            let enough = entry.transfer_value.num_ballot_papers_to_get_this_tv(needed.clone());
+            if enough<entry.ballots { entry.ballots=enough; }
+            *needed-=entry.transfer_value.mul(entry.ballots);
+        }
+        res.ballots+=entry.ballots;
+        res.entries.push(entry);
+    }
+    Ok(res)
+}
@@ -0,0 +1,56 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check finding non-monotonicity on the classic single vacancy example.
+
+use federal::FederalRulesPre2021;
+use margin::nonmonotonicity::find_nonmonotonicity;
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use stv::ballot_paper::BTL;
+use stv::ballot_pile::BallotPaperCount;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+
+/// One vacancy. C is excluded first, and their preferences elect A. But if some of B's voters put A first,
+/// B is excluded instead, and B's preferences elect C.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: ElectionMetadata {
+            candidates: vec![
+                Candidate::from_name("A"),
+                Candidate::from_name("B"),
+                Candidate::from_name("C"),
+            ],
+            vacancies: Some(NumberOfCandidates(1)),
+            ..Default::default()
+        },
+        btl: vec![
+            BTL{ candidates: vec![CandidateIndex(0)], n: 38 },
+            BTL{ candidates: vec![CandidateIndex(1),CandidateIndex(2)], n: 35 },
+            BTL{ candidates: vec![CandidateIndex(2),CandidateIndex(0)], n: 27 },
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_nonmonotonicity() {
+    let data = make_data();
+    let transcript = data.distribute_preferences::<FederalRulesPre2021>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0)]);
+    // Not enough ballots allowed to get B below C.
+    assert!(find_nonmonotonicity::<FederalRulesPre2021>(&data,CandidateIndex(0),BallotPaperCount(7)).is_none());
+    let witness = find_nonmonotonicity::<FederalRulesPre2021>(&data,CandidateIndex(0),BallotPaperCount(20)).unwrap();
+    assert_eq!(witness.raised_over,CandidateIndex(1));
+    assert_eq!(witness.elected_after_change,vec![CandidateIndex(2)]);
+    // With 8 changed, B and C tie on 27, and the tie is resolved by excluding B, the earlier on the ballot paper.
+    assert_eq!(witness.ballots,BallotPaperCount(8));
+    let changed = witness.apply_to_votes(&data);
+    assert_eq!(changed.num_votes(),100);
+    assert_eq!(changed.distribute_preferences::<FederalRulesPre2021>(&mut Randomness::ReverseDonkeyVote).elected,vec![CandidateIndex(2)]);
+    // B is elected either way.
+    assert!(find_nonmonotonicity::<FederalRulesPre2021>(&data,CandidateIndex(1),BallotPaperCount(20)).is_none());
+}
@@ -0,0 +1,65 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the pivotal ballot types on a close contest against a hand analysis.
+
+use std::collections::HashSet;
+use federal::FederalRulesPre2021;
+use margin::choose_votes::ChooseVotesOptions;
+use margin::pivotal_ballot_types;
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use stv::ballot_paper::BTL;
+use stv::ballot_pile::BallotPaperCount;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+use stv::transfer_value::TransferValue;
+
+#[test]
+fn test_pivotal_ballot_types() {
+    let vote_data = ElectionData {
+        metadata: ElectionMetadata {
+            candidates: vec![
+                Candidate::from_name("A"),
+                Candidate::from_name("B"),
+                Candidate::from_name("C"),
+                Candidate::from_name("D"),
+            ],
+            vacancies: Some(NumberOfCandidates(2)),
+            ..Default::default()
+        },
+        btl: vec![
+            BTL{ candidates: vec![CandidateIndex(0),CandidateIndex(2)], n: 60 },
+            BTL{ candidates: vec![CandidateIndex(1)], n: 20 },
+            BTL{ candidates: vec![CandidateIndex(2)], n: 14 },
+            BTL{ candidates: vec![CandidateIndex(3)], n: 19 },
+        ],
+        ..Default::default()
+    };
+    let transcript = vote_data.distribute_preferences::<FederalRulesPre2021>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(2)]);
+    let options = ChooseVotesOptions{ allow_atl: true, allow_first_pref: true, allow_verifiable: true, ballot_types_considered_unverifiable: HashSet::default(), allow_additions: false, allow_from: None, allow_to: None };
+    let pivotal = pivotal_ballot_types::<FederalRulesPre2021>(&vote_data,&options);
+    // Hand analysis : the quota is 113/3+1 = 38. A is elected on first preferences, with a surplus of 22 going to C at transfer value 22/60, giving C 14+22=36.
+    // D is excluded, leaving C elected ahead of B, 36 to 20. Moving more than 8 votes from C to B puts B ahead, which needs 9 of C's first preference ballots,
+    // or 22 of the ballots that came from A at transfer value 11/30 (22*11/30 is just over 8).
+    // For A versus runner up B on first preferences, 60 to 20, 21 ballots are needed.
+    assert_eq!(pivotal.len(),3);
+    assert_eq!(pivotal[0].winner,CandidateIndex(2));
+    assert_eq!(pivotal[0].runner_up,CandidateIndex(1));
+    assert_eq!(pivotal[0].prefix,vec![CandidateIndex(2)]);
+    assert_eq!(pivotal[0].transfer_value,TransferValue::one());
+    assert_eq!(pivotal[0].available,BallotPaperCount(14));
+    assert_eq!(pivotal[0].needed,BallotPaperCount(9));
+    assert_eq!(pivotal[1].winner,CandidateIndex(0));
+    assert_eq!(pivotal[1].prefix,vec![CandidateIndex(0)]);
+    assert_eq!(pivotal[1].needed,BallotPaperCount(21));
+    assert_eq!(pivotal[2].winner,CandidateIndex(2));
+    assert_eq!(pivotal[2].prefix,vec![CandidateIndex(0),CandidateIndex(2)]);
+    assert_eq!(pivotal[2].transfer_value,TransferValue::from_surplus(22,BallotPaperCount(60)));
+    assert_eq!(pivotal[2].available,BallotPaperCount(60));
+    assert_eq!(pivotal[2].needed,BallotPaperCount(22));
+    assert!(pivotal.iter().all(|p|p.sufficient()));
+}
@@ -41,8 +41,8 @@ fn test_retroscope() {
                 Candidate{ name: "C1".to_string(),  party: None, position: None, ec_id: None },
             ],
             parties: vec![
-                Party{ column_id: "A".to_string(), name: "The group of people who like A".to_string(),  abbreviation: None, atl_allowed: true, candidates: vec![CandidateIndex(0),CandidateIndex(1)], tickets: vec![] },
-                Party{ column_id: "B".to_string(), name: "The group of people who like B".to_string(),  abbreviation: None, atl_allowed: true, candidates: vec![CandidateIndex(2),CandidateIndex(3)], tickets: vec![] },
+                Party{ column_id: "A".to_string(), name: "The group of people who like A".to_string(),  abbreviation: None, atl_allowed: true, candidates: vec![CandidateIndex(0),CandidateIndex(1)], tickets: vec![], official_atl_votes: None },
+                Party{ column_id: "B".to_string(), name: "The group of people who like B".to_string(),  abbreviation: None, atl_allowed: true, candidates: vec![CandidateIndex(2),CandidateIndex(3)], tickets: vec![], official_atl_votes: None },
             ],
             source: vec![],
             results: Some(vec![CandidateIndex(0),CandidateIndex(2),CandidateIndex(3)]),
@@ -50,6 +50,7 @@ fn test_retroscope() {
             enrolment: None,
             secondary_vacancies: None,
             excluded: vec![],
+            withdrawn_before_count: vec![],
             tie_resolutions: Default::default()
         },
         atl: vec![
@@ -67,6 +68,8 @@ fn test_retroscope() {
         ],
         btl_types: vec![],
         btl_transfer_values: vec![],
+        atl_annotations: vec![],
+        btl_annotations: vec![],
         informal: 0
     };
     let transcript = vote_data.distribute_preferences::<FederalRulesPre2021>(&mut Randomness::ReverseDonkeyVote);
@@ -0,0 +1,52 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the support certificate on a simple election with two quotas.
+
+use federal::FederalRulesPre2021;
+use margin::support_certificate::support_certificate;
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use stv::ballot_paper::BTL;
+use stv::ballot_pile::BallotPaperCount;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+use stv::transfer_value::TransferValue;
+
+#[test]
+fn test_support_certificate() {
+    let vote_data = ElectionData {
+        metadata: ElectionMetadata {
+            candidates: vec![
+                Candidate::from_name("A"),
+                Candidate::from_name("B"),
+                Candidate::from_name("C"),
+            ],
+            vacancies: Some(NumberOfCandidates(2)),
+            ..Default::default()
+        },
+        btl: vec![
+            BTL{ candidates: vec![CandidateIndex(0),CandidateIndex(1)], n: 50 },
+            BTL{ candidates: vec![CandidateIndex(1)], n: 20 },
+            BTL{ candidates: vec![CandidateIndex(2)], n: 25 },
+            BTL{ candidates: vec![CandidateIndex(2),CandidateIndex(1)], n: 5 },
+        ],
+        ..Default::default()
+    };
+    let transcript = vote_data.distribute_preferences::<FederalRulesPre2021>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+    // quota is 100/3+1 = 34. A gets it from their first preferences.
+    let for_a = support_certificate::<FederalRulesPre2021>(&vote_data,&transcript,CandidateIndex(0)).unwrap();
+    assert_eq!(for_a.ballots,BallotPaperCount(34));
+    assert_eq!(for_a.by_first_preference(),vec![(CandidateIndex(0),BallotPaperCount(34))]);
+    // B has 20 first preferences, and needs 14 more from A's surplus at transfer value 16/50, so 44 ballots.
+    let for_b = support_certificate::<FederalRulesPre2021>(&vote_data,&transcript,CandidateIndex(1)).unwrap();
+    assert_eq!(for_b.ballots,BallotPaperCount(64));
+    assert_eq!(for_b.by_first_preference(),vec![(CandidateIndex(0),BallotPaperCount(44)),(CandidateIndex(1),BallotPaperCount(20))]);
+    assert_eq!(for_b.entries[0].transfer_value,TransferValue::one());
+    assert_eq!(for_b.entries[1].transfer_value,TransferValue::from_surplus(16,BallotPaperCount(50)));
+    // C was not elected.
+    assert!(support_certificate::<FederalRulesPre2021>(&vote_data,&transcript,CandidateIndex(2)).is_err());
+}
@@ -0,0 +1,28 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Builds the `no_std` subset of stv, using it from a `no_std` crate.
+//! The tests in `tests/` then run the fixed precision decimal and transfer value rounding against that build.
+//!
+//! Run with `cargo test` in this directory.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use stv::arithmetic::BallotPaperCount;
+use stv::fixed_precision_decimal::FixedPrecisionDecimal;
+use stv::transfer_value::{DecimalRounding, TransferValue};
+
+/// The transfer value surplus/papers rounded down to 6 decimal places, applied to the given number of papers, as done in the ACT.
+pub fn act_style_transfer(surplus:usize,papers:BallotPaperCount,applied_to:BallotPaperCount) -> FixedPrecisionDecimal<6> {
+    let tv = TransferValue::from_surplus(surplus,papers).round_down_to_decimal_digits(6);
+    FixedPrecisionDecimal::from_rational_rounding_down(tv.mul(applied_to))
+}
+
+/// Render a transfer value as a 6 digit decimal.
+pub fn six_digits(tv:&TransferValue) -> String { tv.to_decimal_string(6,DecimalRounding::Down) }
@@ -0,0 +1,40 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Fixed precision decimal rounding, using stv built without std.
+
+use no_std_check::{act_style_transfer, six_digits};
+use stv::arithmetic::{BallotPaperCount, RoundUpToUsize};
+use stv::fixed_precision_decimal::FixedPrecisionDecimal;
+use stv::transfer_value::TransferValue;
+
+type SixDigitDecimal = FixedPrecisionDecimal<6>;
+
+#[test]
+fn test_round_down() {
+    let parsed : SixDigitDecimal = "45.25".parse().unwrap();
+    assert_eq!("45.25",parsed.to_string());
+    assert_eq!("45",parsed.round_down().to_string());
+    assert_eq!(46,parsed.ceil());
+    let whole : SixDigitDecimal = BallotPaperCount(42).into();
+    assert_eq!(whole,whole.round_down());
+    assert_eq!(42,whole.ceil());
+    assert_eq!("0.000001",SixDigitDecimal::from_scaled_value(1).to_string());
+    // division rounds down.
+    assert_eq!("14.166666",(SixDigitDecimal::from(BallotPaperCount(85))/6).to_string());
+}
+
+#[test]
+fn test_from_rational_rounding_down() {
+    let third = TransferValue::from_surplus(1,BallotPaperCount(3));
+    assert_eq!("0.333333",six_digits(&third));
+    // 1/3 applied to 100 papers is 33.3333..., which rounds down to 33.333333.
+    assert_eq!("33.333333",SixDigitDecimal::from_rational_rounding_down(third.mul(BallotPaperCount(100))).to_string());
+    // with the transfer value first rounded down to 0.333333, that is 33.3333.
+    assert_eq!("33.3333",act_style_transfer(1,BallotPaperCount(3),BallotPaperCount(100)).to_string());
+    // 2/3 rounded down to 0.666666 applied to 3 papers is 1.999998, so a vote is lost when rounding down to an integer.
+    assert_eq!("1",act_style_transfer(2,BallotPaperCount(3),BallotPaperCount(3)).round_down().to_string());
+}
@@ -0,0 +1,80 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Rules for Northern Ireland Assembly and local council elections, counted by the
+//! Electoral Office for Northern Ireland (EONI), and a parser for the stage by stage results EONI publishes.
+//!
+//! The ballot papers are not published, so a count cannot be redone from the raw data. What can be done
+//! is to check the published results for internal consistency and against the quota, and to count
+//! other data (e.g. synthetic or modified ballots) using these rules.
+
+use stv::ballot_pile::{BallotPaperCount, DoNotSplitByCountNumber};
+use stv::fixed_precision_decimal::FixedPrecisionDecimal;
+use stv::preference_distribution::{BigRational, LastParcelUse, PreferenceDistributionRules, SurplusTransferMethod, TransferValueMethod, WhenToDoElectCandidateClauseChecking};
+use stv::tie_resolution::MethodOfTieResolution;
+use stv::transfer_value::TransferValue;
+
+pub mod parse;
+
+/// My understanding of the rules used for the Northern Ireland Assembly (the Northern Ireland Assembly (Elections) Order)
+/// and for district council elections (the Local Elections (Northern Ireland) Order 1985). As far as the count goes, these are the same.
+///
+/// Interpretation notes:
+/// * The quota is the Droop quota, round_down(valid votes/(vacancies+1))+1. First preference votes and the quota are whole numbers.
+/// * Transferred votes are not whole numbers. The value of a paper in a surplus transfer is the surplus divided by the number of
+///   transferable papers (ones with a next available preference), calculated to two decimal places ignoring the remainder.
+///   Votes are thus kept to two decimal places, as in the EONI count sheets. Fractions lost by truncating the transfer value
+///   are not transferred to anyone, and appear here as lost to rounding.
+/// * If the surplus comes from original (first preference) votes, all the candidate's papers are examined. If it comes from
+///   transferred votes, only the papers in the sub-parcel last transferred to the candidate are examined (the last parcel).
+/// * If the transferable papers in that parcel are worth no more than the surplus at their current value, they are all transferred
+///   at their current value. This is modeled by limiting the new transfer value to the value at which the papers were received.
+/// * The largest surplus is transferred first. Surpluses are transferred before any exclusion.
+/// * An excluded candidate's papers are transferred at the value at which they were received. EONI reports this as a single stage;
+///   here papers of different values are transferred in separate counts, highest value first, so that a later surplus can be
+///   drawn from a parcel of a single value. The results at the end of the exclusion are the same.
+/// * When the number of continuing candidates equals the number of vacancies remaining, they are all elected.
+///
+/// Not modeled:
+/// * Ties are resolved by looking at the original votes of the tied candidates, then at the first stage at which they had different
+///   values, and only then by lot. This looks forward through the counts rather than back, unlike anything currently in
+///   [MethodOfTieResolution], so ties are left to be resolved by lot (the EC decision).
+/// * The returning officer may exclude several candidates at once, or defer a small surplus, when this cannot affect the result.
+///   Doing so changes the stages in the count sheet, but not who is elected.
+pub struct NorthernIreland {}
+
+impl PreferenceDistributionRules for NorthernIreland {
+    type Tally = FixedPrecisionDecimal<2>;
+    type SplitByNumber = DoNotSplitByCountNumber;
+
+    fn use_last_parcel_for_surplus_distribution() -> LastParcelUse { LastParcelUse::LiterallyLast }
+    fn transfer_value_method() -> TransferValueMethod { TransferValueMethod::SurplusOverContinuingBallotsLimitedToPriorTransferValue }
+    /// The transfer value is truncated to two decimal places.
+    fn make_transfer_value(surplus: Self::Tally, ballots: BallotPaperCount) -> TransferValue {
+        TransferValue::from_surplus((surplus.get_scaled_value()/ballots.0 as u64) as usize,BallotPaperCount(Self::Tally::SCALE as usize))
+    }
+    fn convert_tally_to_rational(tally: Self::Tally) -> BigRational { tally.to_rational() }
+    fn convert_rational_to_tally_after_applying_transfer_value(rational: BigRational) -> Self::Tally { Self::Tally::from_rational_rounding_down(rational) }
+    /// As the transfer value has two decimal places, this is exact.
+    fn use_transfer_value(transfer_value: &TransferValue, ballots: BallotPaperCount) -> Self::Tally {
+        Self::Tally::from_scaled_value(transfer_value.mul_rounding_down(BallotPaperCount(ballots.0*(Self::Tally::SCALE as usize))) as u64)
+    }
+    fn surplus_distribution_subdivisions() -> SurplusTransferMethod { SurplusTransferMethod::JustOneTransferValue }
+    fn sort_exclusions_by_transfer_value() -> bool { true }
+    /// Ties are resolved by a countback from the first stage, which is not implemented, and then by lot.
+    fn resolve_ties_elected_one_of_last_two() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_elected_by_quota() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_elected_all_remaining() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_choose_lowest_candidate_for_exclusion() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn check_elected_if_in_middle_of_surplus_distribution() -> bool { true }
+    fn check_elected_if_in_middle_of_exclusion() -> bool { true }
+    fn finish_all_counts_in_elimination_when_all_elected() -> bool { false }
+    fn finish_all_surplus_distributions_when_all_elected() -> bool { false }
+    fn when_to_check_if_just_two_standing_for_shortcut_election() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::Never }
+    fn when_to_check_if_all_remaining_should_get_elected() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::AfterCheckingQuotaIfNoUndistributedSurplusExistsAndExclusionNotOngoing }
+    fn when_to_check_if_top_few_have_overwhelming_votes() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::Never }
+    fn name() -> String { "NorthernIreland".to_string() }
+}
@@ -0,0 +1,161 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Parse the stage by stage results (count sheet) published by EONI for each Assembly constituency or district electoral area,
+//! saved as a CSV file.
+//!
+//! * Rows before the header whose first cell is `Constituency` (or `District Electoral Area`), `Seats` (or `Number of Seats`),
+//!   `Valid Poll` or `Quota` give that value in the second cell. Other rows before the header are ignored.
+//! * The header row has `Candidate` in the first cell. Columns headed `Stage 1`, `Stage 2` etc. hold the totals after that stage.
+//!   A column headed `Status` or `Elected` contains `Elected` for elected candidates. Other columns, such as the party or the transfers
+//!   in each stage, are ignored; the transfers are the differences between successive totals.
+//! * Then there is a row for each candidate, with their name in the first cell, a row starting `Non-transferable` for
+//!   exhausted votes, and optionally a row starting `Loss of fractions` for the value lost by truncating transfer values to
+//!   two decimal places. Rows starting with `Total` and blank rows are ignored. Numbers may contain commas.
+//! * A blank total means unchanged from the previous stage for a candidate already elected, and zero otherwise, as
+//!   continuing candidates have their total shown at every stage. Likewise a blank for the non-transferable or loss of fractions rows
+//!   means unchanged.
+//!
+//! The sheet does not say explicitly what happened at each stage, so this is deduced. A candidate is elected at the first
+//! stage their total reaches the quota, or at the last stage if marked as elected without reaching the quota. A candidate not elected
+//! whose total goes down in a stage is excluded in that stage.
+//!
+//! Candidates are listed in the order of the sheet, which is normally alphabetical.
+
+use std::io::Read;
+use std::path::Path;
+use stv::ballot_metadata::{Candidate, CandidateIndex, DataSource, ElectionMetadata, ElectionName, NumberOfCandidates};
+use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::{PerCandidate, QuotaInfo};
+use stv::official_dop_transcript::OfficialDistributionOfPreferencesTranscript;
+use stv::parse_util::ParseError;
+
+/// The candidates and vacancies, and the official results, from an EONI count sheet.
+pub struct EONICountSheet {
+    pub metadata : ElectionMetadata,
+    pub official : OfficialDistributionOfPreferencesTranscript,
+}
+
+pub fn parse<P:AsRef<Path>>(path:P) -> Result<EONICountSheet,ParseError> {
+    let file = ParseError::open(path.as_ref())?;
+    let filename = path.as_ref().file_name().and_then(|s|s.to_str()).unwrap_or("");
+    parse_reader(file,filename)
+}
+
+fn parse_number(s:&str,line_number:usize) -> Result<f64,ParseError> {
+    s.trim().trim_start_matches('+').replace(',',"").parse::<f64>().map_err(|_|ParseError::MalformedPreferenceLine{line_number,reason:format!("Could not parse {} as a number",s)})
+}
+
+/// Like [parse], but read from something other than a file. The filename is used for the source, and
+/// as the electorate name if there is no `Constituency` row.
+pub fn parse_reader<R:Read>(reader:R,filename:&str) -> Result<EONICountSheet,ParseError> {
+    let mut csv = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_reader(reader);
+    let mut electorate : Option<String> = None;
+    let mut vacancies : Option<NumberOfCandidates> = None;
+    let mut valid_poll : Option<f64> = None;
+    let mut quota : Option<f64> = None;
+    let mut stage_columns : Vec<(usize,usize)> = vec![]; // (stage number, column)
+    let mut status_column : Option<usize> = None;
+    let mut candidates : Vec<Candidate> = vec![];
+    let mut totals : Vec<Vec<Option<f64>>> = vec![]; // totals[candidate][stage]
+    let mut marked_elected : Vec<bool> = vec![];
+    let mut exhausted : Vec<Option<f64>> = vec![];
+    let mut lost_to_fractions : Vec<Option<f64>> = vec![];
+    for (row,record) in csv.records().enumerate() {
+        let line_number = row+1;
+        let record = record.map_err(|e|ParseError::MalformedPreferenceLine{line_number,reason:e.to_string()})?;
+        let label = record.get(0).unwrap_or("").trim();
+        let value = record.get(1).unwrap_or("").trim();
+        if stage_columns.is_empty() { // before the header
+            match label.to_lowercase().as_str() {
+                "candidate" => {
+                    for (column,heading) in record.iter().enumerate() {
+                        let heading = heading.trim().to_lowercase();
+                        if let Some(stage) = heading.strip_prefix("stage").and_then(|s|s.trim().parse::<usize>().ok()) { stage_columns.push((stage,column)); }
+                        else if heading=="status" || heading=="elected" { status_column=Some(column); }
+                    }
+                    if stage_columns.is_empty() { return Err(ParseError::MalformedMetadata{line_number,reason:"No stage columns (headed Stage 1, Stage 2 etc.) found".to_string()}); }
+                    stage_columns.sort();
+                    if stage_columns.iter().enumerate().any(|(i,&(stage,_))|stage!=i+1) { return Err(ParseError::MalformedMetadata{line_number,reason:"Stages should be numbered 1, 2, 3... with no gaps or repeats".to_string()}); }
+                }
+                "constituency" | "district electoral area" => electorate=Some(value.to_string()),
+                "seats" | "number of seats" => vacancies=Some(NumberOfCandidates(parse_number(value,line_number)? as usize)),
+                "valid poll" => valid_poll=Some(parse_number(value,line_number)?),
+                "quota" => quota=Some(parse_number(value,line_number)?),
+                _ => {}
+            }
+        } else if !label.is_empty() && !label.to_lowercase().starts_with("total") {
+            let mut stage_totals = vec![];
+            for &(_,column) in &stage_columns {
+                let cell = record.get(column).unwrap_or("").trim();
+                stage_totals.push(if cell.is_empty() { None } else { Some(parse_number(cell,line_number)?) });
+            }
+            if label.to_lowercase().starts_with("non-transferable") { exhausted=stage_totals; }
+            else if label.to_lowercase().starts_with("loss of fractions") { lost_to_fractions=stage_totals; }
+            else {
+                candidates.push(Candidate::from_name(label));
+                totals.push(stage_totals);
+                marked_elected.push(status_column.and_then(|column|record.get(column)).map(|s|s.trim().eq_ignore_ascii_case("elected")).unwrap_or(false));
+            }
+        }
+    }
+    if stage_columns.is_empty() { return Err(ParseError::UnexpectedEndOfFile); }
+    let num_stages = stage_columns.len();
+    let mut elected_already = vec![false;candidates.len()];
+    let mut excluded_already = vec![false;candidates.len()];
+    let mut official = OfficialDistributionOfPreferencesTranscript::default();
+    let mut last_totals : Option<PerCandidate<f64>> = None;
+    for stage in 0..num_stages {
+        official.finished_count();
+        let count = official.count();
+        let mut vote_total = PerCandidate::<f64>::default();
+        for (candidate,candidate_totals) in totals.iter().enumerate() {
+            let previous = last_totals.as_ref().map(|t|t.candidate[candidate]);
+            let total = match candidate_totals[stage] {
+                Some(total) => total,
+                None if elected_already[candidate] => previous.unwrap_or(0.0),
+                None => 0.0,
+            };
+            if !elected_already[candidate] && !excluded_already[candidate] && previous.map(|p|total<p).unwrap_or(false) {
+                excluded_already[candidate]=true;
+                count.excluded.push(CandidateIndex(candidate));
+            }
+            vote_total.candidate.push(total);
+        }
+        vote_total.exhausted = exhausted.get(stage).cloned().flatten().or_else(||last_totals.as_ref().map(|t|t.exhausted)).unwrap_or(0.0);
+        vote_total.rounding = lost_to_fractions.get(stage).cloned().flatten().or_else(||last_totals.as_ref().map(|t|t.rounding.resolve())).unwrap_or(0.0).into();
+        let mut elected_this_stage : Vec<CandidateIndex> = (0..candidates.len()).filter(|&c|!elected_already[c] && !excluded_already[c] && (quota.map(|q|vote_total.candidate[c]>=q).unwrap_or(false) || (stage+1==num_stages && marked_elected[c]))).map(CandidateIndex).collect();
+        elected_this_stage.sort_by(|a,b|vote_total.candidate[b.0].total_cmp(&vote_total.candidate[a.0]));
+        for &c in &elected_this_stage { elected_already[c.0]=true; }
+        count.elected=elected_this_stage;
+        count.vote_total=Some(vote_total.clone());
+        last_totals=Some(vote_total);
+    }
+    let first_preferences : f64 = official.counts[0].vote_total.as_ref().unwrap().candidate.iter().sum();
+    let vacancies = vacancies.unwrap_or(NumberOfCandidates(official.all_elected().len()));
+    official.quota = quota.map(|quota|QuotaInfo{ papers: BallotPaperCount(valid_poll.unwrap_or(first_preferences) as usize), vacancies, quota });
+    let metadata = ElectionMetadata{
+        name: ElectionName {
+            year: "".to_string(),
+            authority: "Electoral Office for Northern Ireland".to_string(),
+            name: "".to_string(),
+            electorate: electorate.unwrap_or_else(||filename.to_string()),
+            modifications: vec![],
+            comment: None,
+        },
+        candidates,
+        parties: vec![],
+        source: vec![DataSource{url:"".to_string(),files:vec![filename.to_string()],comments:None}],
+        results: None,
+        vacancies: Some(vacancies),
+        enrolment: None,
+        secondary_vacancies: None,
+        excluded: vec![],
+        withdrawn_before_count: vec![],
+        tie_resolutions: Default::default(),
+    };
+    Ok(EONICountSheet{ metadata, official })
+}
@@ -0,0 +1,99 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test the Northern Ireland rules and the EONI count sheet parser.
+
+use northern_ireland::NorthernIreland;
+use northern_ireland::parse::parse_reader;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::ballot_paper::BTL;
+use stv::ballot_pile::BallotPaperCount;
+use stv::election_data::ElectionData;
+use stv::parse_util::FileFinder;
+use stv::random_util::Randomness;
+use stv::transfer_value::TransferValue;
+use stv::verify_official_transcript::veryify_official_dop_transcript;
+
+/// The count sheet for [make_data], worked out by hand.
+/// The quota is 96/4+1=25. A's surplus of 20 goes at 20/40=0.50 per transferable paper, 15 to B and 5 to C.
+/// B's surplus of 5 comes from the last parcel of 30 papers, at 5/30=0.1666... truncated to 0.16, giving C 4.80 and losing 0.20.
+/// Then E and D are excluded.
+const COUNT_SHEET : &str = "Constituency,Test
+Number of Seats,3
+Valid Poll,96
+Quota,25
+Candidate,Party,Stage 1,Transfers,Stage 2,Transfers,Stage 3,Transfers,Stage 4,Transfers,Stage 5,Status
+,,,Surplus A,,Surplus B,,Exclusion E,,Exclusion D,,
+A,Party 1,45,-20.00,25.00,,,,,,,Elected
+B,Party 2,15,+15.00,30.00,-5.00,25.00,,,,,Elected
+C,Party 1,12,+5.00,17.00,+4.80,21.80,,21.80,+14.00,35.80,Elected
+D,Party 3,14,,14.00,,14.00,+6.00,20.00,-20.00,,
+E,,10,,10.00,,10.00,-10.00,,,,
+Non-transferable,,,,,,,+4.00,4.00,+6.00,10.00,
+Loss of fractions,,,,,+0.20,0.20,,,,,
+Totals,,96,,96.00,,96.00,,96.00,,96.00,
+";
+
+fn make_data() -> ElectionData {
+    let sheet = parse_reader(COUNT_SHEET.as_bytes(),"test.csv").unwrap();
+    let prefs = |n:usize,candidates:&[usize]| BTL{ candidates: candidates.iter().map(|&c|CandidateIndex(c)).collect(), n };
+    ElectionData {
+        metadata: sheet.metadata,
+        btl: vec![prefs(30,&[0,1,2]),prefs(10,&[0,2]),prefs(5,&[0]),prefs(15,&[1]),prefs(12,&[2,3]),prefs(14,&[3,2]),prefs(6,&[4,3]),prefs(4,&[4,1])],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parse_count_sheet() {
+    let sheet = parse_reader(COUNT_SHEET.as_bytes(),"test.csv").unwrap();
+    assert_eq!("Test",sheet.metadata.name.electorate);
+    assert_eq!(vec!["A","B","C","D","E"],sheet.metadata.candidates.iter().map(|c|c.name.as_str()).collect::<Vec<_>>());
+    assert_eq!(Some(NumberOfCandidates(3)),sheet.metadata.vacancies);
+    assert_eq!(5,sheet.official.counts.len());
+    assert_eq!(vec![CandidateIndex(0)],sheet.official.counts[0].elected);
+    assert_eq!(vec![CandidateIndex(1)],sheet.official.counts[1].elected);
+    assert_eq!(vec![CandidateIndex(4)],sheet.official.counts[3].excluded);
+    assert_eq!(vec![CandidateIndex(3)],sheet.official.counts[4].excluded);
+    assert_eq!(vec![CandidateIndex(2)],sheet.official.counts[4].elected);
+    // blank means unchanged for an elected candidate, otherwise zero.
+    assert_eq!(vec![25.0,25.0,21.8,20.0,0.0],sheet.official.counts[3].vote_total.as_ref().unwrap().candidate);
+    assert_eq!(0.2,sheet.official.counts[4].vote_total.as_ref().unwrap().rounding.resolve());
+    veryify_official_dop_transcript::<NorthernIreland>(&sheet.official,&sheet.metadata).unwrap();
+    assert!(parse_reader("Candidate,Party\nA,Party 1\n".as_bytes(),"bad.csv").is_err());
+}
+
+#[test]
+fn test_count_matches_count_sheet() {
+    let data = make_data();
+    let transcript = data.distribute_preferences::<NorthernIreland>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(TransferValue::from_surplus(16,BallotPaperCount(100)),transcript.counts[2].created_transfer_value.as_ref().unwrap().transfer_value);
+    assert_eq!(vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(2)],transcript.elected);
+    let sheet = parse_reader(COUNT_SHEET.as_bytes(),"test.csv").unwrap();
+    sheet.official.compare_with_transcript(&transcript);
+}
+
+/// Check a published EONI count sheet, the 2022 Assembly election in Belfast South, saved as a CSV file in the data repository.
+/// This needs the data repository, like the tests of the Australian jurisdictions against official transcripts.
+#[test]
+fn test_published_count_sheet_is_consistent() {
+    let path = FileFinder::find_ec_data_repository().find_raw_data_file("Belfast South.csv","NI/Assembly2022","https://www.eoni.org.uk/").unwrap();
+    let sheet = northern_ireland::parse::parse(&path).unwrap();
+    let quota = sheet.official.quota.as_ref().unwrap();
+    assert_eq!(Some(NumberOfCandidates(5)),sheet.metadata.vacancies);
+    // The Droop quota, from the valid poll, which should be the sum of the first preferences.
+    assert_eq!((quota.papers.0/6+1) as f64,quota.quota);
+    let first_preferences : f64 = sheet.official.counts[0].vote_total.as_ref().unwrap().candidate.iter().sum();
+    assert_eq!(quota.papers.0 as f64,first_preferences);
+    // Nothing is gained or lost in any stage, allowing for the sheet being to two decimal places.
+    for count in &sheet.official.counts {
+        let total = count.vote_total.as_ref().unwrap();
+        let sum = total.candidate.iter().sum::<f64>()+total.exhausted+total.rounding.resolve();
+        assert!((sum-first_preferences).abs()<0.005*(total.candidate.len()+2) as f64,"Stage {:?} adds to {}",count.count_name,sum);
+    }
+    assert_eq!(5,sheet.official.all_elected().len());
+    veryify_official_dop_transcript::<NorthernIreland>(&sheet.official,&sheet.metadata).unwrap();
+}
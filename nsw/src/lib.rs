@@ -19,9 +19,10 @@ pub mod nsw_random_rules;
 pub mod run_election_multiple_times;
 
 use std::cmp::Ordering;
+use std::marker::PhantomData;
 use std::fmt::{Debug, Display};
 use std::str::FromStr;
-use stv::preference_distribution::{BigRational, CountNamingMethod, LastParcelUse, PreferenceDistributionRules, SurplusTransferMethod, TransferValueMethod, WhenToDoElectCandidateClauseChecking};
+use stv::preference_distribution::{BigRational, CountNamingMethod, LastParcelUse, PreferenceDistributionRules, SurplusFractionDenominatorPolicy, SurplusTransferMethod, TransferValueMethod, WhenToDoElectCandidateClauseChecking};
 use stv::ballot_pile::{BallotPaperCount, DoNotSplitByCountNumber, FullySplitByCountNumber, HowSplitByCountNumber};
 use stv::distribution_of_preferences_transcript::{CountIndex, Transcript};
 use stv::transfer_value::{convert_usize_to_rational, round_rational_down_to_isize, round_rational_down_to_usize, TransferValue};
@@ -268,12 +269,12 @@ impl NSWECLocalGov2021 {
 /// a more complex name.
 ///
 /// Note that most things are the same as NSWECLocalGov2021 except the tally is now signed and
-/// prohibit_negative_surplus_fraction() is set.
+/// surplus_fraction_denominator_policy() is AllowNegative.
 pub struct NSWECLocalGov2021Literal {
 }
 
 impl PreferenceDistributionRules for NSWECLocalGov2021Literal {
-    fn prohibit_negative_surplus_fraction() -> bool { false }
+    fn surplus_fraction_denominator_policy() -> SurplusFractionDenominatorPolicy { SurplusFractionDenominatorPolicy::AllowNegative }
     type Tally = isize;
     type SplitByNumber = FullySplitByCountNumber;
 
@@ -316,11 +317,58 @@ impl PreferenceDistributionRules for NSWECLocalGov2021Literal {
 
 
 
+/// How an IRV count ends. With exhausted ballots, a candidate may get a majority of continuing votes well before only two are left.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum IRVTermination {
+    /// Stop as soon as a candidate has more votes than all other continuing candidates combined, that is, a majority of continuing votes.
+    MajorityOfContinuingVotes,
+    /// Always exclude candidates until only two are left, electing the one with more votes.
+    ContinueToTwoCandidates,
+}
+
+/// The ways simple IRV counts can differ.
+pub trait IRVVariations {
+    fn name() -> String;
+    fn termination() -> IRVTermination;
+    /// See [PreferenceDistributionRules::batch_exclude_candidates_who_cannot_catch_up]. Faster for large fields; not what any legislation specifies.
+    fn batch_exclusion() -> bool { false }
+}
+
+/// Stop as soon as someone has a majority of continuing votes.
+pub struct IRVMajorityOfContinuingVotes {}
+impl IRVVariations for IRVMajorityOfContinuingVotes {
+    fn name() -> String { "IRV".to_string() }
+    fn termination() -> IRVTermination { IRVTermination::MajorityOfContinuingVotes }
+}
+
+/// Always exclude down to two candidates.
+pub struct IRVContinueToTwoCandidates {}
+impl IRVVariations for IRVContinueToTwoCandidates {
+    fn name() -> String { "IRVContinueToTwo".to_string() }
+    fn termination() -> IRVTermination { IRVTermination::ContinueToTwoCandidates }
+}
+
+/// Like [IRVMajorityOfContinuingVotes], except that groups of low candidates who cannot catch up are excluded together, for speed.
+pub struct IRVMajorityOfContinuingVotesBatchExclusion {}
+impl IRVVariations for IRVMajorityOfContinuingVotesBatchExclusion {
+    fn name() -> String { "IRVBatch".to_string() }
+    fn termination() -> IRVTermination { IRVTermination::MajorityOfContinuingVotes }
+    fn batch_exclusion() -> bool { true }
+}
+
 /// A simple IRV computation.
-pub struct SimpleIRVAnyDifferenceBreaksTies {
+pub struct SimpleIRV<V:IRVVariations> {
+    phantom : PhantomData<V>
 }
 
-impl PreferenceDistributionRules for SimpleIRVAnyDifferenceBreaksTies {
+/// IRV stopping as soon as someone has a majority of continuing votes.
+pub type SimpleIRVAnyDifferenceBreaksTies = SimpleIRV<IRVMajorityOfContinuingVotes>;
+/// IRV always excluding down to two candidates, so the final two candidate preferred count is always shown.
+pub type SimpleIRVContinueToTwoCandidates = SimpleIRV<IRVContinueToTwoCandidates>;
+/// IRV stopping as soon as someone has a majority of continuing votes, excluding hopeless candidates in batches.
+pub type SimpleIRVBatchExclusion = SimpleIRV<IRVMajorityOfContinuingVotesBatchExclusion>;
+
+impl <V:IRVVariations> PreferenceDistributionRules for SimpleIRV<V> {
     type Tally = usize;
     type SplitByNumber = DoNotSplitByCountNumber;
 
@@ -355,11 +403,17 @@ impl PreferenceDistributionRules for SimpleIRVAnyDifferenceBreaksTies {
 
     // termination condition in case of tie for top.
     fn when_to_check_if_all_remaining_should_get_elected() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::AfterCheckingQuotaIfExclusionNotOngoing }
-    // normal termination condition
-    fn when_to_check_if_top_few_have_overwhelming_votes() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::AfterCheckingQuota}
+    // normal termination condition, unless continuing to two candidates when the check for just two standing does it.
+    fn when_to_check_if_top_few_have_overwhelming_votes() -> WhenToDoElectCandidateClauseChecking {
+        match V::termination() {
+            IRVTermination::MajorityOfContinuingVotes => WhenToDoElectCandidateClauseChecking::AfterCheckingQuota,
+            IRVTermination::ContinueToTwoCandidates => WhenToDoElectCandidateClauseChecking::Never,
+        }
+    }
 
     fn should_eliminate_multiple_candidates_federal_rule_13a() -> bool { false }
-    fn name() -> String { "IRV".to_string() }
+    fn batch_exclude_candidates_who_cannot_catch_up() -> bool { V::batch_exclusion() }
+    fn name() -> String { V::name() }
     fn how_to_name_counts() -> CountNamingMethod { CountNamingMethod::SimpleNumber }
 
     fn sort_subcounts_by_count() -> Option<Box<dyn FnMut(&Transcript<Self::Tally>,<<Self as PreferenceDistributionRules>::SplitByNumber as HowSplitByCountNumber>::KeyToDivide,<<Self as PreferenceDistributionRules>::SplitByNumber as HowSplitByCountNumber>::KeyToDivide) -> Ordering>> {
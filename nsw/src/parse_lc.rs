@@ -481,6 +481,7 @@ impl NSWLCDataLoader {
                     atl_allowed: group!="UG",
                     candidates: vec![],
                     tickets: vec![],
+                    official_atl_votes: None,
                 });
                 current_position = 0;
                 current_group = group.to_string();
@@ -520,6 +521,7 @@ impl NSWLCDataLoader {
             enrolment: None,
             secondary_vacancies: None,
             excluded: vec![],
+            withdrawn_before_count: vec![],
             tie_resolutions: Default::default()
         })
     }
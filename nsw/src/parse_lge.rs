@@ -381,6 +381,7 @@ impl NSWLGEDataLoader {
                     atl_allowed: group_code!="UG",
                     candidates: vec![],
                     tickets: vec![],
+                    official_atl_votes: None,
                 });
             }
             parties.last_mut().unwrap().candidates.push(CandidateIndex(candidates.len()));
@@ -405,6 +406,7 @@ impl NSWLGEDataLoader {
             enrolment: None,
             secondary_vacancies: None,
             excluded: vec![],
+            withdrawn_before_count: vec![],
             tie_resolutions: Default::default()
         })
     }
@@ -684,6 +686,7 @@ pub(crate) fn parse_fp_by_grp_and_candidate_by_vote_type(mut file:File,mayoral:b
                         atl_allowed: !col1_text.is_empty(),
                         candidates: vec![],
                         tickets: vec![],
+                        official_atl_votes: None,
                     });
                     current_position = 0;
                 }
@@ -714,6 +717,7 @@ pub(crate) fn parse_fp_by_grp_and_candidate_by_vote_type(mut file:File,mayoral:b
         enrolment,
         secondary_vacancies: None,
         excluded: vec![],
+        withdrawn_before_count: vec![],
         tie_resolutions: Default::default()
     })
 }
@@ -0,0 +1,34 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Data shared by the tests in this directory.
+
+// Each test uses only some of these.
+#![allow(dead_code)]
+
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use stv::ballot_paper::BTL;
+use stv::election_data::ElectionData;
+
+/// Metadata for candidates with the given names, not in any party, for the given number of vacancies.
+pub fn metadata(names:&[&str],vacancies:usize) -> ElectionMetadata {
+    ElectionMetadata{ candidates: names.iter().map(|name|Candidate::from_name(name)).collect(), vacancies: Some(NumberOfCandidates(vacancies)), ..Default::default() }
+}
+
+/// `n` below the line votes for the given candidates, in order of preference.
+pub fn btl(candidates:&[usize],n:usize) -> BTL {
+    BTL{ candidates: candidates.iter().map(|&c|CandidateIndex(c)).collect(), n }
+}
+
+/// A single member election with heavy exhaustion: A 45, B 28, C 15 (then B), D 12 (all exhausting).
+/// After D is excluded, A has 45 of the 88 continuing votes, a majority, although only 45% of the formal vote.
+pub fn heavy_exhaustion() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],1),
+        btl: vec![btl(&[0],45),btl(&[1],28),btl(&[2,1],15),btl(&[3],12)],
+        ..Default::default()
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check counting a region by STV and a ward by IRV off the same ballots.
+
+use nsw::{NSWECLocalGov2021, SimpleIRVAnyDifferenceBreaksTies};
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::composite::{count_composite, CompositeConfig, SubContest};
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata};
+
+/// Candidates 0 to 3 are standing for two seats in a region, candidates 4 to 6 for one seat in a ward.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["R0","R1","R2","R3","W4","W5","W6"],3),
+        btl: vec![
+            btl(&[0,4,1],40),
+            btl(&[5,1,2],25),
+            btl(&[2,6,3,5],20),
+            btl(&[6,3,5],15),
+            btl(&[4],10),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_composite_region_and_ward() {
+    let data = make_data();
+    let config = CompositeConfig{ sub_contests: vec![
+        SubContest{ name: "Region".to_string(), candidates: (0..4).map(CandidateIndex).collect(), vacancies: NumberOfCandidates(2) },
+        SubContest{ name: "Ward".to_string(), candidates: (4..7).map(CandidateIndex).collect(), vacancies: NumberOfCandidates(1) },
+    ]};
+    let transcripts = count_composite::<NSWECLocalGov2021,SimpleIRVAnyDifferenceBreaksTies>(&data,&config,&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcripts.len(),2);
+    // the region ignores ward candidates, so the 10 votes just for W4 exhaust immediately. Quota 34, R0's surplus elects R1.
+    let region = &transcripts[0];
+    assert_eq!(region.rules,"NSWECLocalGov2021");
+    assert_eq!(region.counts[0].status.tallies.candidate,vec![40,25,20,15,0,0,0]);
+    assert_eq!(region.counts[0].status.tallies.exhausted,10);
+    assert_eq!(region.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+    // the ward is counted by IRV. W5 is excluded, and their votes have no further ward preferences.
+    let ward = &transcripts[1];
+    assert_eq!(ward.rules,"IRV");
+    assert!(ward.quota.is_none());
+    assert_eq!(ward.counts[0].status.tallies.candidate,vec![0,0,0,0,50,25,35]);
+    assert_eq!(ward.counts.last().unwrap().status.tallies.exhausted,25);
+    assert_eq!(ward.elected,vec![CandidateIndex(4)]);
+}
@@ -0,0 +1,78 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that batch exclusion in IRV excludes hopeless candidates together without changing the winner.
+
+use nsw::{SimpleIRVAnyDifferenceBreaksTies, SimpleIRVBatchExclusion};
+use rand::Rng;
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha20Rng;
+use rand_chacha::rand_core::SeedableRng;
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use stv::ballot_paper::BTL;
+use stv::distribution_of_preferences_transcript::{ReasonForCount, Transcript};
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+
+mod common;
+use common::btl;
+
+fn make_data(num_candidates:usize,btl:Vec<BTL>) -> ElectionData {
+    ElectionData {
+        metadata: ElectionMetadata {
+            candidates: (0..num_candidates).map(|i|Candidate::from_name(&format!("Candidate {}",i))).collect(),
+            vacancies: Some(NumberOfCandidates(1)),
+            ..Default::default()
+        },
+        btl,
+        ..Default::default()
+    }
+}
+
+/// A large field where popularity falls off geometrically, so most candidates are hopeless.
+fn make_large_field(seed:u64,num_candidates:usize,distinct_votes:usize) -> ElectionData {
+    let mut rng = ChaCha20Rng::seed_from_u64(seed);
+    let popularity : Vec<f64> = (0..num_candidates).map(|c|0.95f64.powi(c as i32)).collect();
+    let total_popularity : f64 = popularity.iter().sum();
+    let mut votes = vec![];
+    for _ in 0..distinct_votes {
+        let mut r = rng.gen::<f64>()*total_popularity;
+        let first = popularity.iter().position(|&p|{ r-=p; r<0.0 }).unwrap_or(num_candidates-1);
+        let mut rest : Vec<usize> = (0..num_candidates).filter(|&c|c!=first).collect();
+        rest.shuffle(&mut rng);
+        rest.truncate(rng.gen_range(0..10));
+        rest.insert(0,first);
+        votes.push(btl(&rest,rng.gen_range(1..=20)));
+    }
+    make_data(num_candidates,votes)
+}
+
+#[test]
+fn test_batch_exclusion_of_hopeless_candidates() {
+    // A 40, B 30, C 20 (then B), D 5, E 3, F 1. The lowest four have 29 votes between them, fewer than B's 30.
+    let data = make_data(6,vec![btl(&[0],40),btl(&[1],30),btl(&[2,1],20),btl(&[3],5),btl(&[4],3),btl(&[5],1)]);
+    let transcript = data.distribute_preferences::<SimpleIRVBatchExclusion>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(1)]);
+    match &transcript.counts[1].reason {
+        ReasonForCount::Elimination(excluded) => assert_eq!(excluded,&vec![CandidateIndex(5),CandidateIndex(4),CandidateIndex(3),CandidateIndex(2)]),
+        other => panic!("Expecting an elimination, got {:?}",other),
+    }
+    let unbatched = data.distribute_preferences::<SimpleIRVAnyDifferenceBreaksTies>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(unbatched.elected,transcript.elected);
+    assert!(unbatched.counts.len()>transcript.counts.len());
+}
+
+#[test]
+fn test_batch_exclusion_same_winner_large_field() {
+    for seed in 1..=5 {
+        let data = make_large_field(seed,200,5000);
+        let batched = data.distribute_preferences::<SimpleIRVBatchExclusion>(&mut Randomness::ReverseDonkeyVote);
+        let unbatched = data.distribute_preferences::<SimpleIRVAnyDifferenceBreaksTies>(&mut Randomness::ReverseDonkeyVote);
+        assert_eq!(batched.elected,unbatched.elected,"seed {}",seed);
+        let exclusions = |transcript:&Transcript<usize>|transcript.counts.iter().filter(|c|!c.not_continuing.is_empty()).count();
+        assert!(exclusions(&batched)<exclusions(&unbatched),"seed {} batched {} exclusions, unbatched {}",seed,exclusions(&batched),exclusions(&unbatched));
+    }
+}
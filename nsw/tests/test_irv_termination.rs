@@ -0,0 +1,34 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the two IRV termination modes on an election with heavy exhaustion.
+
+use nsw::{SimpleIRVAnyDifferenceBreaksTies, SimpleIRVContinueToTwoCandidates};
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::ElectionReason;
+use stv::random_util::Randomness;
+
+mod common;
+use common::heavy_exhaustion;
+
+#[test]
+fn test_majority_of_continuing_votes_stops_early() {
+    let transcript = heavy_exhaustion().distribute_preferences::<SimpleIRVAnyDifferenceBreaksTies>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0)]);
+    assert_eq!(transcript.counts.len(),2);
+    assert_eq!(transcript.counts[1].elected[0].why,ElectionReason::OverwhelmingTally);
+}
+
+#[test]
+fn test_continue_to_two_candidates() {
+    let transcript = heavy_exhaustion().distribute_preferences::<SimpleIRVContinueToTwoCandidates>(&mut Randomness::ReverseDonkeyVote);
+    // A candidate with a majority of continuing votes can never be the lowest, so the winner is the same, but the count goes on until two are left.
+    assert_eq!(transcript.elected,vec![CandidateIndex(0)]);
+    assert_eq!(transcript.counts.len(),3);
+    let last = &transcript.counts[2].status.tallies;
+    assert_eq!(last.candidate,vec![45,43,0,0]);
+    assert_eq!(last.exhausted,12);
+}
@@ -58,6 +58,7 @@ mod tests {
                 enrolment: None,
                 secondary_vacancies: None,
                 excluded: vec![],
+                withdrawn_before_count: vec![],
                 tie_resolutions: Default::default()
             },
             atl: vec![],
@@ -73,6 +74,8 @@ mod tests {
             ],
             btl_types: vec![],
             btl_transfer_values: vec![],
+            atl_annotations: vec![],
+            btl_annotations: vec![],
             informal: 0
         };
         let transcript = data.distribute_preferences::<NSWLocalCouncilLegislation2021MyGuessAtHighlyAmbiguousLegislation>(&mut Randomness::ReverseDonkeyVote);
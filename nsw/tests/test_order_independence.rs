@@ -0,0 +1,35 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that the NSW rules give the same transcript for each of the example datasets regardless of the order of the ballots.
+
+use std::fs::File;
+use nsw::{NSWECLocalGov2021, NSWECLocalGov2021Literal, NSWLocalCouncilLegislation2021MyGuessAtHighlyAmbiguousLegislation};
+use stv::election_data::ElectionData;
+use stv::file_format::load_with_migration;
+use stv::order_independence::check_order_independent;
+use stv::preference_distribution::PreferenceDistributionRules;
+
+const SEEDS : [u64;5] = [1,2,3,4,5];
+
+fn assert_order_independent<Rules:PreferenceDistributionRules>(data:&ElectionData) {
+    if let Err(problem) = check_order_independent::<Rules>(data,&SEEDS) { panic!("{}",problem); }
+}
+
+fn examples() -> Vec<ElectionData> {
+    let mut paths : Vec<_> = std::fs::read_dir("../examples").unwrap().map(|e|e.unwrap().path()).filter(|p|p.extension().map(|e|e=="stv").unwrap_or(false)).collect();
+    paths.sort();
+    paths.iter().map(|p|load_with_migration(File::open(p).unwrap()).unwrap()).collect()
+}
+
+#[test]
+fn test_examples_order_independent() {
+    for data in examples() {
+        assert_order_independent::<NSWLocalCouncilLegislation2021MyGuessAtHighlyAmbiguousLegislation>(&data);
+        assert_order_independent::<NSWECLocalGov2021>(&data);
+        assert_order_independent::<NSWECLocalGov2021Literal>(&data);
+    }
+}
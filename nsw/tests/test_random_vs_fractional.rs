@@ -0,0 +1,46 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Compare the NSW random sample surplus transfer with the fractional method on a contest where they can differ.
+
+use nsw::NSWECLocalGov2021;
+use nsw::nsw_random_rules::NSWECRandomLGE2016;
+use stv::ballot_metadata::CandidateIndex;
+use stv::election_data::ElectionData;
+use stv::monte_carlo::compare_random_vs_fractional;
+
+mod common;
+use common::{btl, metadata};
+
+/// 3 vacancies, quota 27. A has 60 votes, all going on to B then half to C and half to D. A's surplus of 33 elects B,
+/// whose surplus of 9 comes from the papers received from A, so the next preferences of the papers chosen matter.
+/// Fractionally C and D each get about half of B's surplus, so D (2 ahead on first preferences) wins the last seat.
+/// Randomly, C wins if at least 6 of the 9 papers chosen from B go to C. The number of papers each candidate receives
+/// from a surplus in the random method is not itself random, so the ballots need to go through B to show the difference.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],3),
+        btl: vec![btl(&[0,1,2],30),btl(&[0,1,3],30),btl(&[1],3),btl(&[2],20),btl(&[3],22)],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_compare_random_vs_fractional() {
+    let data = make_data();
+    let report = compare_random_vs_fractional::<NSWECRandomLGE2016,NSWECLocalGov2021>(&data,40).unwrap();
+    assert_eq!(vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(3)],report.fractional_elected);
+    assert_eq!(40,report.random.samples);
+    assert_eq!(40,report.random.times_elected[0]);
+    assert_eq!(40,report.random.times_elected[1]);
+    assert_eq!(40,report.random.times_elected[2]+report.random.times_elected[3]);
+    assert_eq!(report.agreements,report.random.times_elected[3]);
+    assert!(report.agreements>0 && report.agreements<40,"agreements {}",report.agreements);
+    assert_eq!(vec![CandidateIndex(2),CandidateIndex(3)],report.differences());
+    assert_eq!(report.agreements as f64/40.0,report.proportion_agreeing());
+    // deterministic
+    assert_eq!(report,compare_random_vs_fractional::<NSWECRandomLGE2016,NSWECLocalGov2021>(&data,40).unwrap());
+}
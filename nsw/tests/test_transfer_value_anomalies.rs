@@ -0,0 +1,43 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the diagnostic for transfer values that increase.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+use nsw::{NSWECLocalGov2021, NSWECLocalGov2021Literal};
+use stv::ballot_metadata::CandidateIndex;
+use stv::election_data::ElectionData;
+use stv::file_format::load_with_migration;
+use stv::preference_distribution::{distribute_preferences, PreferenceDistributionRules};
+use stv::random_util::Randomness;
+use stv::transfer_value_ledger::TVAnomaly;
+
+fn anomalies<Rules:PreferenceDistributionRules>(example:&str) -> Vec<TVAnomaly> {
+    let data : ElectionData = load_with_migration(File::open(Path::new("../examples").join(example)).unwrap()).unwrap();
+    let excluded : HashSet<CandidateIndex> = data.metadata.excluded.iter().cloned().collect();
+    let transcript = distribute_preferences::<Rules>(&data,data.metadata.vacancies.unwrap(),&excluded,&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+    transcript.transfer_value_anomalies()
+}
+
+#[test]
+fn test_normal_counts_have_no_transfer_value_anomalies() {
+    for example in ["SimpleExample.stv","MultipleExclusionOrdering.stv","MultipleExclusionRounding.stv","TransferValueOverOne.stv"] {
+        assert_eq!(anomalies::<NSWECLocalGov2021>(example),vec![],"{}",example);
+    }
+    for example in ["SimpleExample.stv","MultipleExclusionOrdering.stv","MultipleExclusionRounding.stv"] {
+        assert_eq!(anomalies::<NSWECLocalGov2021Literal>(example),vec![],"{}",example);
+    }
+}
+
+#[test]
+fn test_transfer_value_over_one_is_anomalous() {
+    // Taking the NSW LGE legislation literally, W3 distributes a surplus with a negative surplus fraction, turning papers with a negative transfer value into ones with a large positive transfer value.
+    let found = anomalies::<NSWECLocalGov2021Literal>("TransferValueOverOne.stv");
+    assert!(!found.is_empty());
+    assert!(found.iter().any(|a|a.source_candidate==Some(CandidateIndex(2))));
+}
@@ -0,0 +1,37 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the final two candidate preferred count, when the count goes on until two candidates are left, when it stops early, and with candidates excluded before the count.
+
+use nsw::{SimpleIRVAnyDifferenceBreaksTies, SimpleIRVContinueToTwoCandidates};
+use stv::ballot_metadata::CandidateIndex;
+use stv::random_util::Randomness;
+
+mod common;
+use common::heavy_exhaustion;
+
+#[test]
+fn test_two_candidate_preferred_continue_to_two() {
+    let transcript = heavy_exhaustion().distribute_preferences::<SimpleIRVContinueToTwoCandidates>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.final_two_candidate_preferred(),Some((CandidateIndex(0),45,CandidateIndex(1),43)));
+}
+
+#[test]
+fn test_two_candidate_preferred_stopped_early() {
+    // A has a majority of continuing votes once D is excluded, so the count stops with three candidates in contention.
+    let transcript = heavy_exhaustion().distribute_preferences::<SimpleIRVAnyDifferenceBreaksTies>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.final_two_candidate_preferred(),None);
+}
+
+#[test]
+fn test_two_candidate_preferred_with_candidates_excluded_before_count() {
+    // With C and D excluded before the count, the C,B votes go straight to B.
+    let mut data = heavy_exhaustion();
+    data.metadata.excluded=vec![CandidateIndex(2),CandidateIndex(3)];
+    let transcript = data.distribute_preferences::<SimpleIRVAnyDifferenceBreaksTies>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0)]);
+    assert_eq!(transcript.final_two_candidate_preferred(),Some((CandidateIndex(0),45,CandidateIndex(1),43)));
+}
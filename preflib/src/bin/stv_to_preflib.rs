@@ -0,0 +1,45 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::Parser;
+use std::path::PathBuf;
+use std::fs::File;
+use preflib::PreflibDataType;
+use stv::election_data::ElectionData;
+
+#[derive(Parser)]
+#[clap(version = "0.1", author = "Andrew Conway", name="ConcreteSTV")]
+/// Convert a ConcreteSTV .stv file to preflib .soi or .soc format. See https://www.preflib.org/.
+/// ATL votes are converted to the equivalent BTL votes, and party information is lost.
+struct Opts {
+    /// The name of the .stv file to convert to preflib format
+    #[clap(value_parser)]
+    file : PathBuf,
+
+    /// The preflib data type to produce, soi (the default) or soc. soc requires every vote to rank every candidate.
+    #[clap(long,value_parser,default_value="soi")]
+    data_type : PreflibDataType,
+
+    /// An optional output file. If not specified, the input file name is used with the extension changed to the data type.
+    #[clap(short, long,value_parser)]
+    out : Option<PathBuf>,
+
+}
+
+
+
+fn main() -> anyhow::Result<()> {
+    let opt : Opts = Opts::parse();
+    let data : ElectionData = serde_json::from_reader(File::open(&opt.file)?)?;
+    let out_path = if let Some(path) = &opt.out { path.clone() } else {
+        let mut path = PathBuf::from(opt.file.file_name().unwrap_or_default());
+        path.set_extension(opt.data_type.name());
+        path
+    };
+    let out = File::create(&out_path)?;
+    preflib::write(&data,out,opt.data_type)?;
+    Ok(())
+}
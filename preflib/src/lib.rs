@@ -5,23 +5,34 @@
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
 
-//! Parsing preference data from https://www.preflib.org/
+//! Parsing and writing preference data from https://www.preflib.org/
 
 
 
 
 pub mod parse_blt;
 
-use std::fs::File;
-use std::io::BufRead;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, Write};
 use std::path::Path;
-use anyhow::anyhow;
+use std::str::FromStr;
 use stv::ballot_metadata::{Candidate, CandidateIndex, DataSource, ElectionMetadata, ElectionName};
 use stv::ballot_paper::BTL;
 use stv::election_data::ElectionData;
+use stv::parse_util::ParseError;
 
-pub fn parse<P:AsRef<Path>>(path:P) -> anyhow::Result<ElectionData> {
-    let file = File::open(path)?;
+/// The maximum number of candidates that will be accepted. This is far more than any real election, and prevents
+/// a malformed file from causing an enormous memory allocation.
+pub const MAX_CANDIDATES : usize = 100000;
+
+pub fn parse<P:AsRef<Path>>(path:P) -> Result<ElectionData,ParseError> {
+    let file = ParseError::open(path.as_ref())?;
+    parse_reader(std::io::BufReader::new(file))
+}
+
+/// Like [parse], but read from something other than a file.
+pub fn parse_reader<R:BufRead>(reader:R) -> Result<ElectionData,ParseError> {
     let mut candidates : Vec<Candidate> = vec![];
     let mut name : ElectionName = ElectionName {
         year: "".to_string(),
@@ -33,8 +44,11 @@ pub fn parse<P:AsRef<Path>>(path:P) -> anyhow::Result<ElectionData> {
     };
     let mut source : Vec<DataSource> = vec![];
     let mut btl : Vec<BTL> = vec![];
-    for line in std::io::BufReader::new(file).lines() {
+    for (line_index,line) in reader.lines().enumerate() {
         let line = line?;
+        let line_number = line_index+1;
+        let malformed_metadata = |reason:String| ParseError::MalformedMetadata{line_number,reason};
+        let malformed_preferences = |reason:String| ParseError::MalformedPreferenceLine{line_number,reason};
         if line.starts_with("#") { // metadata
             if let Some((metadata_name,metadata_value)) = line[1..].split_once(':') {
                 let metadata_value = metadata_value.trim();
@@ -44,38 +58,40 @@ pub fn parse<P:AsRef<Path>>(path:P) -> anyhow::Result<ElectionData> {
                     "DESCRIPTION" => { if !metadata_value.is_empty() { name.comment=Some(metadata_value.to_string());}}
                     "DATA TYPE" => {
                         if metadata_value=="soc" || metadata_value=="soi" {} else {
-                            return Err(anyhow!("Can only handle formats soc or soi, got {}",metadata_value));
+                            return Err(ParseError::UnsupportedFormat(format!("Can only handle formats soc or soi, got {}",metadata_value)));
                         }
                     }
                     "NUMBER ALTERNATIVES" => {
-                        let n : usize = metadata_value.parse()?;
+                        let n : usize = metadata_value.parse().map_err(|e|malformed_metadata(format!("NUMBER ALTERNATIVES {} : {}",metadata_value,e)))?;
+                        if n>MAX_CANDIDATES { return Err(malformed_metadata(format!("NUMBER ALTERNATIVES {} is more than the maximum supported {}",n,MAX_CANDIDATES)))}
                         candidates.resize_with(n,||Candidate::from_name("unspecified"))
                     }
                     s if s.starts_with("ALTERNATIVE NAME") => { // #ALTERNATIVE NAME n : name
-                        let n : usize = s.trim_start_matches("ALTERNATIVE NAME").trim_start().parse()?;
-                        if n==0 { return Err(anyhow!("NUMBER ALTERNATIVES 0 is not understood"))}
+                        let n : usize = s.trim_start_matches("ALTERNATIVE NAME").trim_start().parse().map_err(|e|malformed_metadata(format!("{} : {}",s,e)))?;
+                        if n==0 { return Err(malformed_metadata("ALTERNATIVE NAME 0 is not understood".to_string()))}
+                        if n>MAX_CANDIDATES { return Err(malformed_metadata(format!("ALTERNATIVE NAME {} is more than the maximum supported {}",n,MAX_CANDIDATES)))}
                         if candidates.len()<n { candidates.resize_with(n,||Candidate::from_name("unspecified"))}
                         candidates[n-1].name=metadata_value.to_string();
                     }
                     _ => {}
                 }
             } else {
-                return Err(anyhow!("Metadata line without colon : {}",line));
+                return Err(malformed_metadata(format!("Metadata line without colon : {}",line)));
             }
         } else if line.is_empty() {}
         else { // preferences line
             if let Some((n,prefs)) = line.split_once(':') {
                 let num_candidates = candidates.len();
-                let n : usize = n.trim().parse()?;
+                let n : usize = n.trim().parse().map_err(|e|malformed_preferences(format!("number of voters {} : {}",n.trim(),e)))?;
                 let mut candidates : Vec<CandidateIndex> = vec![];
                 for candidate in prefs.trim().split(',') {
-                    let candidate : usize = candidate.trim().parse()?;
-                    if candidate<1 || candidate>num_candidates { return Err(anyhow!("Expecting candidate index between 1 and {}, got {}",num_candidates,candidate)) }
-                    candidates.push(CandidateIndex(candidate-1));
+                    let candidate : i64 = candidate.trim().parse().map_err(|e|malformed_preferences(format!("candidate {} : {}",candidate.trim(),e)))?;
+                    if candidate<1 || candidate>num_candidates as i64 { return Err(ParseError::CandidateIndexOutOfRange{line_number,index:candidate,num_candidates}) }
+                    candidates.push(CandidateIndex(candidate as usize-1));
                 }
                 btl.push(BTL{candidates,n});
             } else {
-                return Err(anyhow!("Expecting line of the form n : <preference list> got {}",line))
+                return Err(malformed_preferences(format!("Expecting line of the form n : <preference list> got {}",line)))
             }
         }
     }
@@ -89,6 +105,7 @@ pub fn parse<P:AsRef<Path>>(path:P) -> anyhow::Result<ElectionData> {
         enrolment: None,
         secondary_vacancies: None,
         excluded: vec![],
+        withdrawn_before_count: vec![],
         tie_resolutions: Default::default(),
     };
     let data = ElectionData{
@@ -99,7 +116,105 @@ pub fn parse<P:AsRef<Path>>(path:P) -> anyhow::Result<ElectionData> {
         btl,
         btl_types: vec![],
         btl_transfer_values: vec![],
+        atl_annotations: vec![],
+        btl_annotations: vec![],
         informal: 0,
     };
     Ok(data)
-}
\ No newline at end of file
+}
+
+/// The preflib data types that can be written by [write].
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum PreflibDataType {
+    /// Strict orders, incomplete list. Any vote can be written.
+    SOI,
+    /// Strict orders, complete list. Every vote must rank every candidate.
+    SOC,
+}
+
+impl PreflibDataType {
+    /// The name used in the DATA TYPE header and as the file extension.
+    pub fn name(self) -> &'static str {
+        match self {
+            PreflibDataType::SOI => "soi",
+            PreflibDataType::SOC => "soc",
+        }
+    }
+}
+
+impl Display for PreflibDataType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { f.write_str(self.name()) }
+}
+
+impl FromStr for PreflibDataType {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "soi" => Ok(PreflibDataType::SOI),
+            "soc" => Ok(PreflibDataType::SOC),
+            _ => Err(anyhow::anyhow!("Unsupported preflib data type {}, expecting soi or soc",s)),
+        }
+    }
+}
+
+/// A reason why [write] could not produce a preflib file.
+#[derive(thiserror::Error,Debug)]
+pub enum PreflibWriteError {
+    #[error("IO error {0}")]
+    IO(#[from] std::io::Error),
+    #[error("A vote ranks {ranked} of the {num_candidates} candidates, but soc requires all candidates to be ranked. Use soi instead.")]
+    IncompleteOrderForSOC{ranked:usize,num_candidates:usize},
+    #[error("A vote ranks candidate {0} more than once, which cannot be represented as a strict order.")]
+    RepeatedCandidate(CandidateIndex),
+    #[error("A vote refers to candidate {0} which does not exist.")]
+    CandidateOutOfRange(CandidateIndex),
+    #[error("Votes have weights (transfer values), which preflib cannot represent.")]
+    WeightedVotes,
+}
+
+/// Write the votes in preflib format (see https://www.preflib.org/format). This is the inverse of [parse].
+///
+/// ATL votes are converted to the equivalent list of candidates, and then identical preference lists are
+/// consolidated into a single line, sorted by decreasing number of voters as is the preflib convention.
+/// ConcreteSTV votes are always strict orders, so no ties are ever written. Party information (including
+/// ungrouped candidates) is not representable in preflib and is dropped; candidates are written in the same
+/// order as the .stv file, with preflib's 1 based numbering. Votes with no preferences are omitted.
+pub fn write<W:Write>(data:&ElectionData,writer:W,data_type:PreflibDataType) -> Result<(),PreflibWriteError> {
+    if !(data.atl_transfer_values.is_empty() && data.btl_transfer_values.is_empty()) { return Err(PreflibWriteError::WeightedVotes); }
+    let num_candidates = data.metadata.candidates.len();
+    let mut orders : HashMap<Vec<CandidateIndex>,usize> = HashMap::default();
+    let mut add = |candidates:Vec<CandidateIndex>,n:usize| -> Result<(),PreflibWriteError> {
+        if candidates.is_empty() || n==0 { return Ok(()); }
+        let mut seen = vec![false;num_candidates];
+        for &c in &candidates {
+            if c.0>=num_candidates { return Err(PreflibWriteError::CandidateOutOfRange(c)); }
+            if seen[c.0] { return Err(PreflibWriteError::RepeatedCandidate(c)); }
+            seen[c.0]=true;
+        }
+        if data_type==PreflibDataType::SOC && candidates.len()!=num_candidates { return Err(PreflibWriteError::IncompleteOrderForSOC {ranked:candidates.len(),num_candidates}); }
+        *orders.entry(candidates).or_insert(0)+=n;
+        Ok(())
+    };
+    for atl in &data.atl { add(atl.resolve_to_candidates(&data.metadata),atl.n)?; }
+    for btl in &data.btl { add(btl.candidates.clone(),btl.n)?; }
+    let mut orders : Vec<(Vec<CandidateIndex>,usize)> = orders.into_iter().collect();
+    orders.sort_by(|(c1,n1),(c2,n2)|n2.cmp(n1).then_with(||c1.iter().map(|c|c.0).cmp(c2.iter().map(|c|c.0))));
+    let mut w = std::io::BufWriter::new(writer);
+    let title = data.metadata.name.human_readable_name();
+    writeln!(w,"# TITLE: {}",title)?;
+    writeln!(w,"# DESCRIPTION: {}",data.metadata.name.comment.as_deref().unwrap_or("").replace('\n'," "))?;
+    writeln!(w,"# DATA TYPE: {}",data_type)?;
+    writeln!(w,"# MODIFICATION TYPE: original")?;
+    writeln!(w,"# NUMBER ALTERNATIVES: {}",num_candidates)?;
+    writeln!(w,"# NUMBER VOTERS: {}",orders.iter().map(|(_,n)|*n).sum::<usize>())?;
+    writeln!(w,"# NUMBER UNIQUE ORDERS: {}",orders.len())?;
+    for (i,candidate) in data.metadata.candidates.iter().enumerate() {
+        writeln!(w,"# ALTERNATIVE NAME {}: {}",i+1,candidate.name)?;
+    }
+    for (candidates,n) in &orders {
+        let prefs : Vec<String> = candidates.iter().map(|c|(c.0+1).to_string()).collect();
+        writeln!(w,"{}: {}",n,prefs.join(","))?;
+    }
+    w.flush()?;
+    Ok(())
+}
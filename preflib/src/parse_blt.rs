@@ -13,24 +13,40 @@
 //! Subsequent lines list the "candidate name" "party", one per line.
 
 
-use std::fs::File;
-use std::io::BufRead;
-use std::num::ParseIntError;
+use std::io::{BufRead, Lines};
 use std::path::Path;
-use anyhow::anyhow;
 use stv::ballot_metadata::{Candidate, CandidateIndex, DataSource, ElectionMetadata, ElectionName, NumberOfCandidates, Party, PartyIndex};
 use stv::ballot_paper::{BTL, UniqueBTLBuilder};
 use stv::election_data::ElectionData;
+use stv::parse_util::ParseError;
 
-fn get_line(s:Option<std::io::Result<String>>) -> anyhow::Result<String> { Ok(s.ok_or_else(||anyhow!("No lines in file"))??) }
-fn parse_as_ints(s:String) -> Result<Vec<i64>,ParseIntError> {
-    let fields : Result<Vec<i64>,ParseIntError> = s.split_whitespace().map(|s|s.parse::<i64>()).collect();
-    fields
+/// Read lines, keeping track of the line number for error messages.
+struct NumberedLines<B:BufRead> {
+    lines : Lines<B>,
+    line_number : usize,
 }
 
-pub fn parse<P:AsRef<Path>>(path:P) -> anyhow::Result<ElectionData> {
-    let file = File::open(path.as_ref())?;
+impl <B:BufRead> NumberedLines<B> {
+    fn get_line(&mut self) -> Result<String,ParseError> {
+        self.line_number+=1;
+        Ok(self.lines.next().ok_or(ParseError::UnexpectedEndOfFile)??)
+    }
+    fn get_line_as_ints(&mut self) -> Result<Vec<i64>,ParseError> {
+        let line = self.get_line()?;
+        line.split_whitespace().map(|s|s.parse::<i64>()).collect::<Result<Vec<i64>,_>>().map_err(|e|self.malformed_preferences(format!("{} : {}",line,e)))
+    }
+    fn malformed_preferences(&self,reason:String) -> ParseError { ParseError::MalformedPreferenceLine{line_number:self.line_number,reason} }
+    fn malformed_metadata(&self,reason:String) -> ParseError { ParseError::MalformedMetadata{line_number:self.line_number,reason} }
+}
+
+pub fn parse<P:AsRef<Path>>(path:P) -> Result<ElectionData,ParseError> {
+    let file = ParseError::open(path.as_ref())?;
     let filename = path.as_ref().file_name().and_then(|s|s.to_str()).unwrap_or("");
+    parse_reader(std::io::BufReader::new(file),filename)
+}
+
+/// Like [parse], but read from something other than a file. The filename is used for the election name and source.
+pub fn parse_reader<R:BufRead>(reader:R,filename:&str) -> Result<ElectionData,ParseError> {
     let name : ElectionName = ElectionName {
         year: "".to_string(),
         authority: "".to_string(),
@@ -41,32 +57,34 @@ pub fn parse<P:AsRef<Path>>(path:P) -> anyhow::Result<ElectionData> {
     };
     let source : Vec<DataSource> = vec![ DataSource{url:"".to_string(),files:vec![filename.to_string()], comments: None }];
     let mut btls = UniqueBTLBuilder::default();
-    let mut lines =  std::io::BufReader::new(file).lines();
-    let firstline = parse_as_ints(get_line(lines.next())?)?;
-    if firstline.len()!=2 { return Err(anyhow!("Expecting the first line to have two fields")); }
-    let num_candidates = NumberOfCandidates(firstline[0] as usize);
-    let vacancies = NumberOfCandidates(firstline[1] as usize);
+    let mut lines = NumberedLines{ lines: reader.lines(), line_number: 0 };
+    let firstline = lines.get_line()?;
+    let firstline : Vec<usize> = firstline.split_whitespace().map(|s|s.parse::<usize>()).collect::<Result<Vec<usize>,_>>().map_err(|e|lines.malformed_metadata(format!("{} : {}",firstline,e)))?;
+    if firstline.len()!=2 { return Err(lines.malformed_metadata("Expecting the first line to have two fields".to_string())); }
+    let num_candidates = NumberOfCandidates(firstline[0]);
+    let vacancies = NumberOfCandidates(firstline[1]);
     let mut excluded : Vec<CandidateIndex> = vec![];
     loop {
-        let line = parse_as_ints(get_line(lines.next())?)?;
-        if line.len()==0 { return Err(anyhow!("Found blank line")); }
+        let line = lines.get_line_as_ints()?;
+        let check_candidate = |index:i64| if index<1 || index>num_candidates.0 as i64 { Err(ParseError::CandidateIndexOutOfRange{line_number:lines.line_number,index,num_candidates:num_candidates.0}) } else { Ok(CandidateIndex(index as usize-1)) };
+        if line.len()==0 { return Err(lines.malformed_preferences("Found blank line".to_string())); }
         if line[0]<0 { // remove candidate
             for c in line {
-                excluded.push(CandidateIndex((-c-1) as usize))
+                excluded.push(check_candidate(c.checked_neg().unwrap_or(0))?)
             }
         } else {
-            if line.last().copied()!=Some(0) {  return Err(anyhow!("Found preference line not ending in 0")); }
+            if line.last().copied()!=Some(0) {  return Err(lines.malformed_preferences("Found preference line not ending in 0".to_string())); }
             if line.len()==1 { break; }
-            let candidates : Vec<CandidateIndex> = line[1..line.len()-1].iter().map(|c|CandidateIndex((*c - 1) as usize)).collect();
+            let candidates : Vec<CandidateIndex> = line[1..line.len()-1].iter().map(|&c|check_candidate(c)).collect::<Result<Vec<CandidateIndex>,ParseError>>()?;
             btls.add_vote(BTL{candidates,n: line[0] as usize });
         }
     }
     let mut parties : Vec<Party> = vec![];
     let mut candidates : Vec<Candidate> = vec![];
     for _ in 0..num_candidates.0 {
-        let candidate_name_line = get_line(lines.next())?;
+        let candidate_name_line = lines.get_line()?;
         let fields : Vec<&str> = candidate_name_line.split('"').collect();
-        if fields.len()!=5 { return Err(anyhow!("Expecting candidate name and party in quotes")); }
+        if fields.len()!=5 { return Err(lines.malformed_metadata("Expecting candidate name and party in quotes".to_string())); }
         let candidate_name = fields[1];
         let party_name = fields[3];
         let party = parties.iter().position(|p|party_name==&p.name);
@@ -77,6 +95,7 @@ pub fn parse<P:AsRef<Path>>(path:P) -> anyhow::Result<ElectionData> {
             atl_allowed: false,
             candidates: vec![],
             tickets: vec![],
+            official_atl_votes: None,
         }); parties.len()-1});
         parties[party].candidates.push(CandidateIndex(candidates.len()));
         candidates.push(Candidate{
@@ -96,6 +115,7 @@ pub fn parse<P:AsRef<Path>>(path:P) -> anyhow::Result<ElectionData> {
         enrolment: None,
         secondary_vacancies: None,
         excluded,
+        withdrawn_before_count: vec![],
         tie_resolutions: Default::default(),
     };
     let data = ElectionData{
@@ -106,6 +126,8 @@ pub fn parse<P:AsRef<Path>>(path:P) -> anyhow::Result<ElectionData> {
         btl : btls.to_btls(),
         btl_types: vec![],
         btl_transfer_values: vec![],
+        atl_annotations: vec![],
+        btl_annotations: vec![],
         informal: 0,
     };
     Ok(data)
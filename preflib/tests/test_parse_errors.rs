@@ -0,0 +1,63 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that malformed preflib and .blt files produce the expected ParseError variants.
+
+use std::path::PathBuf;
+use stv::parse_util::ParseError;
+
+/// Write contents to a uniquely named temporary file, returning its path.
+fn temp_file(name:&str,contents:&str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("concrete_stv_test_parse_errors_{}_{}",std::process::id(),name));
+    std::fs::write(&path,contents).unwrap();
+    path
+}
+
+const PREFLIB_HEADER : &str = "# DATA TYPE: soi\n# NUMBER ALTERNATIVES: 3\n# ALTERNATIVE NAME 1: A\n# ALTERNATIVE NAME 2: B\n# ALTERNATIVE NAME 3: C\n";
+
+#[test]
+fn test_preflib_parse_errors() {
+    let good = preflib::parse(temp_file("good.soi",&format!("{}5: 1,2\n3: 3\n",PREFLIB_HEADER))).unwrap();
+    assert_eq!(good.btl.len(),2);
+    assert!(matches!(preflib::parse(std::env::temp_dir().join("concrete_stv_no_such_file.soi")),Err(ParseError::MissingFile(_))));
+    assert!(matches!(preflib::parse(temp_file("format.soi","# DATA TYPE: toc\n")),Err(ParseError::UnsupportedFormat(_))));
+    assert!(matches!(preflib::parse(temp_file("metadata.soi","# NUMBER ALTERNATIVES: three\n")),Err(ParseError::MalformedMetadata{line_number:1,..})));
+    assert!(matches!(preflib::parse(temp_file("line.soi",&format!("{}5: 1,2\n3 3\n",PREFLIB_HEADER))),Err(ParseError::MalformedPreferenceLine{line_number:7,..})));
+    assert!(matches!(preflib::parse(temp_file("range.soi",&format!("{}5: 1,4\n",PREFLIB_HEADER))),Err(ParseError::CandidateIndexOutOfRange{line_number:6,index:4,num_candidates:3})));
+    // still usable as an anyhow error.
+    let as_anyhow : anyhow::Result<stv::election_data::ElectionData> = preflib::parse(temp_file("anyhow.soi","# DATA TYPE: toc\n")).map_err(|e|e.into());
+    assert!(as_anyhow.unwrap_err().downcast_ref::<ParseError>().is_some());
+}
+
+#[test]
+fn test_blt_parse_errors() {
+    let good = preflib::parse_blt::parse(temp_file("good.blt","3 1\n5 1 2 0\n3 3 0\n0\n\"A\" \"X\"\n\"B\" \"X\"\n\"C\" \"Y\"\n")).unwrap();
+    assert_eq!(good.btl.len(),2);
+    assert_eq!(good.metadata.parties.len(),2);
+    assert!(matches!(preflib::parse_blt::parse(std::env::temp_dir().join("concrete_stv_no_such_file.blt")),Err(ParseError::MissingFile(_))));
+    assert!(matches!(preflib::parse_blt::parse(temp_file("header.blt","3\n")),Err(ParseError::MalformedMetadata{line_number:1,..})));
+    assert!(matches!(preflib::parse_blt::parse(temp_file("line.blt","3 1\n5 1 2 0\n3 3\n0\n")),Err(ParseError::MalformedPreferenceLine{line_number:3,..})));
+    assert!(matches!(preflib::parse_blt::parse(temp_file("range.blt","3 1\n5 1 7 0\n0\n")),Err(ParseError::CandidateIndexOutOfRange{line_number:2,index:7,num_candidates:3})));
+    assert!(matches!(preflib::parse_blt::parse(temp_file("eof.blt","3 1\n5 1 2 0\n")),Err(ParseError::UnexpectedEndOfFile)));
+}
+
+/// Inputs that used to panic or try to allocate enormous amounts of memory. Found by the fuzz harness in preflib/fuzz.
+#[test]
+fn test_fuzz_regressions() {
+    assert!(matches!(preflib::parse_reader("# NUMBER ALTERNATIVES: 999999999999999\n".as_bytes()),Err(ParseError::MalformedMetadata{line_number:1,..})));
+    assert!(matches!(preflib::parse_reader("# ALTERNATIVE NAME 999999999999999: A\n".as_bytes()),Err(ParseError::MalformedMetadata{line_number:1,..})));
+    assert!(matches!(preflib::parse_blt::parse_reader("3 1\n-9223372036854775808\n0\n".as_bytes(),"fuzz.blt"),Err(ParseError::CandidateIndexOutOfRange{line_number:2,..})));
+    assert!(matches!(preflib::parse_reader(&b"# TITLE: \xff\xfe\n"[..]),Err(ParseError::IO(_))));
+}
+
+/// Every truncation of a valid file should either parse or give an error, not panic.
+#[test]
+fn test_truncated_inputs_do_not_panic() {
+    let preflib_file = format!("{}5: 1,2\n3: 3\n",PREFLIB_HEADER);
+    for i in 0..preflib_file.len() { let _ = preflib::parse_reader(preflib_file[..i].as_bytes()); }
+    let blt_file = "3 1\n-2\n5 1 2 0\n3 3 0\n0\n\"A\" \"X\"\n\"B\" \"X\"\n\"C\" \"Y\"\n";
+    for i in 0..blt_file.len() { let _ = preflib::parse_blt::parse_reader(blt_file[..i].as_bytes(),"truncated.blt"); }
+}
@@ -0,0 +1,60 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that writing preflib format and reading it back gives the same votes.
+
+use std::collections::HashMap;
+use std::fs::File;
+use preflib::{PreflibDataType, PreflibWriteError};
+use stv::ballot_metadata::CandidateIndex;
+use stv::election_data::ElectionData;
+
+/// Consolidated votes as lists of candidate numbers, with ATL votes converted to BTL.
+fn votes_by_candidates(data:&ElectionData) -> HashMap<Vec<usize>,usize> {
+    let mut res : HashMap<Vec<usize>,usize> = HashMap::default();
+    let atl = data.atl.iter().map(|v|(v.resolve_to_candidates(&data.metadata),v.n));
+    let btl = data.btl.iter().map(|v|(v.candidates.clone(),v.n));
+    for (candidates,n) in atl.chain(btl) {
+        *res.entry(candidates.iter().map(|c|c.0).collect()).or_insert(0)+=n;
+    }
+    res
+}
+
+#[test]
+fn test_preflib_round_trip() {
+    let data : ElectionData = serde_json::from_reader(File::open("../examples/SimpleExample.stv").unwrap()).unwrap();
+    let mut written : Vec<u8> = vec![];
+    preflib::write(&data,&mut written,PreflibDataType::SOI).unwrap();
+    let text = String::from_utf8(written.clone()).unwrap();
+    assert!(text.contains("# DATA TYPE: soi\n"));
+    assert!(text.contains("# NUMBER ALTERNATIVES: 5\n"));
+    assert!(text.contains("# NUMBER VOTERS: 240\n"));
+    assert!(text.contains("# ALTERNATIVE NAME 1: C1\n"));
+    assert!(text.contains("\n110: 1,2,3,4\n")); // ATL vote for parties 0 then 1, 1 based.
+    let read = preflib::parse_reader(written.as_slice()).unwrap();
+    assert!(read.atl.is_empty());
+    assert_eq!(read.metadata.candidates.iter().map(|c|c.name.clone()).collect::<Vec<_>>(),data.metadata.candidates.iter().map(|c|c.name.clone()).collect::<Vec<_>>());
+    assert_eq!(votes_by_candidates(&read),votes_by_candidates(&data));
+    // consolidated, so already one line per distinct order.
+    assert_eq!(read.btl.len(),votes_by_candidates(&data).len());
+    // most popular first.
+    assert_eq!(read.btl[0].n,110);
+}
+
+#[test]
+fn test_preflib_soc_requires_complete_orders() {
+    let mut data : ElectionData = serde_json::from_reader(File::open("../examples/SimpleExample.stv").unwrap()).unwrap();
+    assert!(matches!(preflib::write(&data,std::io::sink(),PreflibDataType::SOC),Err(PreflibWriteError::IncompleteOrderForSOC{ranked:4,num_candidates:5})));
+    data.atl.clear();
+    data.btl.retain(|v|v.candidates.len()==5);
+    data.btl.push(stv::ballot_paper::BTL{ candidates: (0..5).rev().map(CandidateIndex).collect(), n: 7 });
+    let mut written : Vec<u8> = vec![];
+    preflib::write(&data,&mut written,PreflibDataType::SOC).unwrap();
+    let read = preflib::parse_reader(written.as_slice()).unwrap();
+    assert_eq!(votes_by_candidates(&read),votes_by_candidates(&data));
+    data.btl.push(stv::ballot_paper::BTL{ candidates: vec![CandidateIndex(1),CandidateIndex(1)], n: 1 });
+    assert!(matches!(preflib::write(&data,std::io::sink(),PreflibDataType::SOI),Err(PreflibWriteError::RepeatedCandidate(CandidateIndex(1)))));
+}
@@ -128,6 +128,7 @@ impl RawDataSource for SADataLoader {
             enrolment: None,
             secondary_vacancies: None,
             excluded: vec![],
+            withdrawn_before_count: vec![],
             tie_resolutions: Default::default(),
         })
     }
@@ -334,6 +335,7 @@ fn parse_first_preferences_pdf(path:&PathBuf,year:String,url:&str) -> anyhow::Re
         enrolment: None,
         secondary_vacancies: None,
         excluded: vec![],
+        withdrawn_before_count: vec![],
         tie_resolutions: Default::default(),
     })
 }
@@ -130,6 +130,7 @@ impl RawDataSource for SALGEDataLoader {
                 enrolment: Some(NumberOfCandidates(contest.post_election_info.total_enrollment.0)),
                 secondary_vacancies: None,
                 excluded: vec![],
+                withdrawn_before_count: vec![],
                 tie_resolutions: Default::default(),
             })
         } else { Err(anyhow!("Invalid electorate {}",electorate))}
@@ -265,6 +266,7 @@ fn parse_first_preferences_pdf(path:&PathBuf,year:String,url:&str) -> anyhow::Re
         enrolment: None,
         secondary_vacancies: None,
         excluded: vec![],
+        withdrawn_before_count: vec![],
         tie_resolutions: Default::default(),
     })
 }
@@ -0,0 +1,63 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Weights for ballots other than one per paper, used by statistics such as [crate::intent_table] and [crate::correlations]
+//! that would otherwise treat every ballot paper equally.
+//!
+//! The main use is weighting each ballot paper by its value at some point in a count, so that papers that have
+//! been transferred at a low transfer value, or exhausted, count for little or nothing.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use anyhow::anyhow;
+use num_traits::ToPrimitive;
+use stv::ballot_metadata::CandidateIndex;
+use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::{CountIndex, Transcript};
+use stv::election_data::ElectionData;
+
+/// A weight for each ballot paper, determined by its preferences. Papers with the same preferences get the same weight,
+/// as they are indistinguishable in a transcript.
+#[derive(Debug,Clone)]
+pub struct BallotWeights {
+    weight_by_preferences : HashMap<Vec<CandidateIndex>,f64>,
+}
+
+impl BallotWeights {
+    /// Weight each ballot paper by its value at the end of the given count; that is, the transfer value it has if held by a
+    /// candidate (elected or continuing), and 0 if it has been exhausted or set aside.
+    ///
+    /// The transcript must be of a count of `data` with the list of votes included (see `include_list_of_votes_in_transcript`
+    /// in [stv::preference_distribution::distribute_preferences_with_extractors]).
+    pub fn value_at_count<Tally:PartialEq+Clone+Display+FromStr+Debug>(data:&ElectionData,transcript:&Transcript<Tally>,count:CountIndex) -> anyhow::Result<Self> {
+        if count.0>=transcript.counts.len() { return Err(anyhow!("Count {} is not in the transcript, which has {} counts",count,transcript.counts.len())); }
+        let mut value : HashMap<Vec<CandidateIndex>,f64> = HashMap::new();
+        for single in &transcript.counts[..=count.0] {
+            let list_of_votes = single.status.list_of_votes.as_ref().ok_or_else(||anyhow!("The transcript does not include the list of votes"))?;
+            for deltas in &list_of_votes.candidate {
+                for with_tv in &deltas.tvs {
+                    let tv = with_tv.tv.mul(BallotPaperCount(1)).to_f64().unwrap_or(f64::NAN);
+                    for vote in &with_tv.votes {
+                        *value.entry(vote.candidates.clone()).or_insert(0.0)+=tv*vote.n as f64;
+                    }
+                }
+            }
+        }
+        let arena = typed_arena::Arena::<CandidateIndex>::new();
+        let mut papers : HashMap<&[CandidateIndex],usize> = HashMap::new();
+        for vote in data.resolve_atl(&arena,None) {
+            *papers.entry(vote.prefs).or_insert(0)+=vote.n.0;
+        }
+        let weight_by_preferences = value.into_iter().filter_map(|(prefs,value)|papers.get(prefs.as_slice()).filter(|&&n|n>0).map(|&n|(prefs,value/n as f64))).collect();
+        Ok(BallotWeights{weight_by_preferences})
+    }
+
+    /// The weight of a single ballot paper with the given preferences.
+    pub fn weight(&self,prefs:&[CandidateIndex]) -> f64 {
+        self.weight_by_preferences.get(prefs).cloned().unwrap_or(0.0)
+    }
+}
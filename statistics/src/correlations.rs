@@ -10,6 +10,8 @@ use stv::election_data::ElectionData;
 use serde::{Serialize,Deserialize};
 use stv::ballot_metadata::CandidateIndex;
 use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::CountIndex;
+use crate::ballot_weights::BallotWeights;
 use crate::dendrogram::Dendrogram;
 
 #[derive(Debug,Serialize,Deserialize,Clone)]
@@ -20,6 +22,7 @@ pub struct SquareMatrix {
 }
 
 #[derive(Debug,Serialize,Deserialize,Clone,Copy)]
+#[cfg_attr(feature="openapi",derive(utoipa::IntoParams),into_params(parameter_in=Query))]
 /// Options for doing correlations.
 pub struct CorrelationOptions {
     /// if true, then want to correlate candidates. If false, want to correlate parties.
@@ -30,6 +33,10 @@ pub struct CorrelationOptions {
     pub use_btl : bool,
     /// if true, do mean subtraction from vectors before correlating.
     pub subtract_mean : bool,
+    /// If present, weight each ballot paper by its value at the end of this count (starting from 0) of the official count, rather than 1.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature="openapi",param(value_type=Option<usize>))]
+    pub weight_by_value_at_count : Option<CountIndex>,
 }
 
 impl SquareMatrix {
@@ -43,6 +50,13 @@ impl SquareMatrix {
     ///
     /// It also computes the number of first preference votes for each candidate/group depending upon the options.
     pub fn compute_correlation_matrix(data:&ElectionData, options:&CorrelationOptions) -> SquareMatrix {
+        Self::compute_weighted_correlation_matrix(data,options,None)
+    }
+
+    /// Like [SquareMatrix::compute_correlation_matrix], but if `weights` is given each ballot paper contributes to the correlations
+    /// according to its weight rather than 1. First preferences are still counted unweighted.
+    /// `options.weight_by_value_at_count` is not used here; it is up to the caller to produce the appropriate weights (see [BallotWeights::value_at_count]).
+    pub fn compute_weighted_correlation_matrix(data:&ElectionData, options:&CorrelationOptions,weights:Option<&BallotWeights>) -> SquareMatrix {
         let n = if options.want_candidates { data.metadata.candidates.len() }  else { data.metadata.parties.len() };
         let mut self_dot_product = vec![0.0;n];
         let mut first_preferences = vec![BallotPaperCount(0);n];
@@ -55,7 +69,7 @@ impl SquareMatrix {
         let votes = data.resolve_atl(&arena,None);
         for vote in votes {
             if vote.prefs.len()>0 && if vote.is_atl() { options.use_atl } else { options.use_btl } {
-                let w = vote.n.0 as f64;
+                let w = vote.n.0 as f64*weights.map_or(1.0,|weights|weights.weight(vote.prefs));
                 count+=w;
                 let first_candidate = vote.prefs[0];
                 if options.want_candidates { first_preferences[first_candidate.0]+=vote.n; }
@@ -0,0 +1,42 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The Laakso-Taagepera effective number of candidates and parties, 1/Σp², where p is the proportion of first preferences each got.
+
+use stv::election_data::ElectionData;
+use serde::{Serialize,Deserialize};
+use stv::ballot_metadata::CandidateIndex;
+use stv::ballot_pile::BallotPaperCount;
+use crate::util::{first_preferences_by_candidate, first_preferences_by_party};
+
+#[derive(Debug,Serialize,Deserialize,Clone,Copy)]
+pub struct EffectiveNumbers {
+    /// The number of votes with a first preference, the denominator for the proportions.
+    pub votes : BallotPaperCount,
+    /// Effective number of candidates, by first preferences.
+    pub candidates : f64,
+    /// Effective number of parties, by first preferences for any candidate in the party. A candidate not in any party counts as their own party.
+    pub parties : f64,
+}
+
+impl EffectiveNumbers {
+    /// Compute from first preferences (with ATL votes going to the first candidate in the party). Zero if there are no votes.
+    pub fn compute(data:&ElectionData) -> EffectiveNumbers {
+        let by_candidate = first_preferences_by_candidate(data);
+        let mut by_party = first_preferences_by_party(data,&by_candidate);
+        by_party.extend(by_candidate.iter().enumerate().filter(|(candidate,_)|data.metadata.candidate(CandidateIndex(*candidate)).party.is_none()).map(|(_,&n)|n));
+        let total : usize = by_candidate.iter().sum();
+        EffectiveNumbers{ votes: BallotPaperCount(total), candidates: laakso_taagepera(&by_candidate,total), parties: laakso_taagepera(&by_party,total) }
+    }
+}
+
+/// 1/Σp² where p=count/total, or 0 if total is 0.
+fn laakso_taagepera(counts:&[usize],total:usize) -> f64 {
+    if total==0 { return 0.0; }
+    let total = total as f64;
+    let sum_squares : f64 = counts.iter().map(|&n|{ let p = n as f64/total; p*p }).sum();
+    1.0/sum_squares
+}
@@ -9,6 +9,8 @@ use stv::election_data::ElectionData;
 use serde::{Serialize, Deserialize};
 use stv::ballot_metadata::CandidateIndex;
 use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::CountIndex;
+use crate::ballot_weights::BallotWeights;
 
 #[derive(Debug,Serialize,Deserialize,Clone)]
 /// A table showing who people prefer of some specified candidates/parties given that their first preference is for some other given candidate/party.
@@ -17,9 +19,13 @@ use stv::ballot_pile::BallotPaperCount;
 ///  * have candidate(group) who[j] in their preference list before any other member of who. If no member of who is in their preference list, j=who.len().
 pub struct IntentTable {
     pub table : Vec<Vec<BallotPaperCount>>,
+    /// If weights were supplied, the same table with each ballot paper counted at its weight rather than 1.
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    pub weighted : Option<Vec<Vec<f64>>>,
 }
 
 #[derive(Debug,Serialize,Deserialize,Clone)]
+#[cfg_attr(feature="openapi",derive(utoipa::IntoParams),into_params(parameter_in=Query))]
 pub struct IntentTableOptions {
     /// if true, then the first preference distribution is by groups, otherwise by candidates.
     pub first_pref_by_groups : bool,
@@ -32,15 +38,27 @@ pub struct IntentTableOptions {
     /// who is of interest. If who_is_groups, then a GroupIndex, else a CandidateIndex.
     /// Serialized oddly to make it easy to work with a URL parameter.
     #[serde(deserialize_with = "crate::util::deserialize_stringified_usize_list",serialize_with="crate::util::serialize_stringified_usize_list")]
+    #[cfg_attr(feature="openapi",param(value_type=String))]
     pub who : Vec<usize>,
+    /// If present, also produce a table with each ballot paper weighted by its value at the end of this count (starting from 0) of the official count, rather than 1.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature="openapi",param(value_type=Option<usize>))]
+    pub weight_by_value_at_count : Option<CountIndex>,
 }
 
 impl IntentTable {
     pub fn compute(data:&ElectionData,options:&IntentTableOptions) -> Self {
+        Self::compute_weighted(data,options,None)
+    }
+
+    /// Like [IntentTable::compute], but if `weights` is given also fill in [IntentTable::weighted].
+    /// `options.weight_by_value_at_count` is not used here; it is up to the caller to produce the appropriate weights (see [BallotWeights::value_at_count]).
+    pub fn compute_weighted(data:&ElectionData,options:&IntentTableOptions,weights:Option<&BallotWeights>) -> Self {
         let num_rows = if options.first_pref_by_groups { data.metadata.parties.len() } else { data.metadata.candidates.len() };
         let num_cols = options.who.len()+1;
         let exhausted_column = options.who.len();
         let mut table = vec![vec![BallotPaperCount(0);num_cols];num_rows];
+        let mut weighted = weights.map(|_|vec![vec![0.0;num_cols];num_rows]);
 
         let mut candidate_to_who_index = vec![exhausted_column;data.metadata.candidates.len()]; // a map from candidate index to the candidate
         for who_index in 0..options.who.len() {
@@ -68,9 +86,12 @@ impl IntentTable {
                         }
                     }
                     table[first_preference][found_col]+=vote.n;
+                    if let (Some(weighted),Some(weights)) = (&mut weighted,weights) {
+                        weighted[first_preference][found_col]+=weights.weight(vote.prefs)*vote.n.0 as f64;
+                    }
                 }
             }
         }
-        IntentTable{table}
+        IntentTable{table,weighted}
     }
 }
\ No newline at end of file
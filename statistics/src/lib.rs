@@ -13,3 +13,6 @@ pub mod intent_table;
 pub mod mean_preference;
 pub mod util;
 pub mod dendrogram;
+pub mod effective_numbers;
+pub mod viability_threshold;
+pub mod ballot_weights;
@@ -33,7 +33,20 @@ pub struct ByTypeOfVotes {
     pub num_btl : usize,
 }
 
+/// The [SimpleStatistics] for the votes with a particular value of an annotation, e.g. a polling place. See [SimpleStatistics::by_annotation].
+#[derive(Debug,Serialize,Deserialize,Clone)]
+pub struct ByAnnotation {
+    pub value : String,
+    pub statistics : SimpleStatistics,
+}
+
 impl SimpleStatistics {
+    /// Compute statistics separately for each value of the annotation `key`, e.g. for each polling place if key is "polling_place".
+    /// See [stv::election_data::BallotAnnotation]. Informal votes are not annotated, so are not included.
+    pub fn by_annotation(data:&ElectionData,key:&str) -> Vec<ByAnnotation> {
+        data.annotation_values(key).into_iter().map(|value|ByAnnotation{ value: value.to_string(), statistics: SimpleStatistics::new(&data.restrict_to_annotation(key,value)) }).collect()
+    }
+
     pub fn new(data:&ElectionData) -> Self {
         let mut vote_types = vec![];
         for t in &data.atl_types {
@@ -6,6 +6,28 @@
 
 use std::fmt;
 use serde::{de, Serializer};
+use stv::ballot_metadata::CandidateIndex;
+use stv::election_data::ElectionData;
+
+/// The number of first preferences for each candidate, with ATL votes going to the first candidate in the party.
+pub fn first_preferences_by_candidate(data:&ElectionData) -> Vec<usize> {
+    let mut by_candidate = vec![0usize;data.metadata.candidates.len()];
+    let arena = typed_arena::Arena::<CandidateIndex>::new();
+    let votes = data.resolve_atl(&arena,None);
+    for vote in &votes {
+        if let Some(first_pref) = vote.prefs.first() { by_candidate[first_pref.0]+=vote.n.0; }
+    }
+    by_candidate
+}
+
+/// Sum first preferences by candidate (from [first_preferences_by_candidate]) by party. Candidates not in any party are omitted.
+pub fn first_preferences_by_party(data:&ElectionData,by_candidate:&[usize]) -> Vec<usize> {
+    let mut by_party = vec![0usize;data.metadata.parties.len()];
+    for (candidate,&n) in by_candidate.iter().enumerate() {
+        if let Some(party) = data.metadata.candidate(CandidateIndex(candidate)).party { by_party[party.0]+=n; }
+    }
+    by_party
+}
 
 /// utility function for serde serializing a list of integers as a comma separated list.
 /// Useful for using with actix get-parameter serialization.
@@ -0,0 +1,53 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Which candidates and parties got at least a given fraction of the first preferences, such as the 4% needed for a deposit to be returned.
+//! This is a reporting statistic; it has no effect on the count.
+
+use stv::election_data::ElectionData;
+use serde::{Serialize,Deserialize};
+use stv::ballot_metadata::{CandidateIndex, PartyIndex};
+use stv::ballot_pile::BallotPaperCount;
+use crate::util::{first_preferences_by_candidate, first_preferences_by_party};
+
+#[derive(Debug,Serialize,Deserialize,Clone)]
+pub struct ViabilityThreshold {
+    /// The fraction of first preferences needed, e.g. 0.04.
+    pub threshold : f64,
+    /// The number of votes with a first preference, the denominator for the fractions.
+    pub votes : BallotPaperCount,
+    /// The number of first preferences for each candidate, with ATL votes going to the first candidate in the party.
+    pub candidate_first_preferences : Vec<BallotPaperCount>,
+    /// The number of first preferences for each party, for any candidate in the party.
+    pub party_first_preferences : Vec<BallotPaperCount>,
+    /// Candidates with a fraction of the first preferences at least the threshold, in ballot paper order.
+    pub candidates_above : Vec<CandidateIndex>,
+    /// Candidates with a fraction of the first preferences less than the threshold, in ballot paper order.
+    pub candidates_below : Vec<CandidateIndex>,
+    /// Parties with a fraction of the first preferences at least the threshold, in ballot paper order.
+    pub parties_above : Vec<PartyIndex>,
+    /// Parties with a fraction of the first preferences less than the threshold, in ballot paper order.
+    pub parties_below : Vec<PartyIndex>,
+}
+
+impl ViabilityThreshold {
+    /// Compute from first preferences. Reaching the threshold exactly counts as above it. If there are no votes, everyone is below any positive threshold.
+    pub fn compute(data:&ElectionData,threshold:f64) -> ViabilityThreshold {
+        let by_candidate = first_preferences_by_candidate(data);
+        let by_party = first_preferences_by_party(data,&by_candidate);
+        let total : usize = by_candidate.iter().sum();
+        let is_above = |n:usize| if total==0 { threshold<=0.0 } else { n as f64>=threshold*total as f64 };
+        let (candidates_above,candidates_below) : (Vec<CandidateIndex>,Vec<CandidateIndex>) = (0..by_candidate.len()).map(CandidateIndex).partition(|c|is_above(by_candidate[c.0]));
+        let (parties_above,parties_below) : (Vec<PartyIndex>,Vec<PartyIndex>) = (0..by_party.len()).map(PartyIndex).partition(|p|is_above(by_party[p.0]));
+        ViabilityThreshold{
+            threshold,
+            votes: BallotPaperCount(total),
+            candidate_first_preferences: by_candidate.into_iter().map(BallotPaperCount).collect(),
+            party_first_preferences: by_party.into_iter().map(BallotPaperCount).collect(),
+            candidates_above, candidates_below, parties_above, parties_below,
+        }
+    }
+}
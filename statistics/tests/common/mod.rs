@@ -0,0 +1,55 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Data shared by the tests in this directory.
+
+// Each test uses only some of these.
+#![allow(dead_code)]
+
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates, Party, PartyIndex};
+use stv::ballot_paper::{ATL, BTL};
+use stv::election_data::ElectionData;
+
+/// Metadata for one vacancy. Each candidate is given as a name and the index of their party, if any, in `party_names`.
+/// Every party can be voted for above the line.
+pub fn metadata(candidates:&[(&str,Option<usize>)],party_names:&[&str]) -> ElectionMetadata {
+    ElectionMetadata{
+        candidates: candidates.iter().map(|&(name,party)|Candidate{ party: party.map(PartyIndex), ..Candidate::from_name(name) }).collect(),
+        parties: party_names.iter().enumerate().map(|(index,&name)|Party{
+            column_id: name.to_string(),
+            name: name.to_string(),
+            abbreviation: None,
+            atl_allowed: true,
+            candidates: candidates.iter().enumerate().filter(|(_,(_,party))|*party==Some(index)).map(|(c,_)|CandidateIndex(c)).collect(),
+            tickets: vec![],
+            official_atl_votes: None,
+        }).collect(),
+        vacancies: Some(NumberOfCandidates(1)),
+        ..Default::default()
+    }
+}
+
+/// `n` above the line votes for the given parties, in order of preference.
+pub fn atl(parties:&[usize],n:usize) -> ATL {
+    ATL{ parties: parties.iter().map(|&p|PartyIndex(p)).collect(), n, ticket_index: None }
+}
+
+/// `n` below the line votes for the given candidates, in order of preference.
+pub fn btl(candidates:&[usize],n:usize) -> BTL {
+    BTL{ candidates: candidates.iter().map(|&c|CandidateIndex(c)).collect(), n }
+}
+
+/// Candidates A and B in party X, and C in no party, for one vacancy.
+/// Votes are ATL 30 for party X, and BTL 20 for A, 30 for B then A, and 20 for C.
+/// So first preferences are A 50 (30 of them ATL), B 30 and C 20, and party X has 80.
+pub fn party_x_and_c() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&[("A",Some(0)),("B",Some(0)),("C",None)],&["X"]),
+        atl: vec![atl(&[0],30)],
+        btl: vec![btl(&[0],20),btl(&[1,0],30),btl(&[2],20)],
+        ..Default::default()
+    }
+}
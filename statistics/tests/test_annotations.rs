@@ -0,0 +1,70 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that per ballot annotations such as polling place survive serialization, and can be used to break down statistics.
+
+use statistics::effective_numbers::EffectiveNumbers;
+use statistics::simple_statistics::SimpleStatistics;
+use stv::ballot_pile::BallotPaperCount;
+use stv::election_data::{BallotAnnotation, ElectionData, VoteTypeSpecification};
+
+mod common;
+use common::party_x_and_c;
+
+fn polling_place(value:&str,first_index_inclusive:usize,last_index_exclusive:usize) -> BallotAnnotation {
+    BallotAnnotation{ key: "polling_place".to_string(), value: value.to_string(), first_index_inclusive, last_index_exclusive }
+}
+
+/// [party_x_and_c], with the ATL votes and the BTL votes for A from North, and the other BTL votes from South. The 20 votes for C are postal.
+fn make_data() -> ElectionData {
+    ElectionData {
+        btl_types: vec![VoteTypeSpecification{ vote_type: "Postal".to_string(), first_index_inclusive: 2, last_index_exclusive: 3 }],
+        atl_annotations: vec![polling_place("North",0,1)],
+        btl_annotations: vec![polling_place("North",0,1),polling_place("South",1,3)],
+        informal: 7,
+        ..party_x_and_c()
+    }
+}
+
+#[test]
+fn test_annotations_round_trip() {
+    let data = make_data();
+    let json = serde_json::to_string(&data).unwrap();
+    let read : ElectionData = serde_json::from_str(&json).unwrap();
+    assert_eq!(read.atl_annotations,data.atl_annotations);
+    assert_eq!(read.btl_annotations,data.btl_annotations);
+    // files without annotations are unaffected.
+    let mut plain = make_data();
+    plain.atl_annotations.clear();
+    plain.btl_annotations.clear();
+    let json = serde_json::to_string(&plain).unwrap();
+    assert!(!json.contains("annotations"));
+    let read : ElectionData = serde_json::from_str(&json).unwrap();
+    assert!(read.atl_annotations.is_empty());
+    assert!(read.btl_annotations.is_empty());
+}
+
+#[test]
+fn test_statistics_by_polling_place() {
+    let data = make_data();
+    assert_eq!(data.annotation_values("polling_place"),vec!["North","South"]);
+    let by_place = SimpleStatistics::by_annotation(&data,"polling_place");
+    assert_eq!(by_place.len(),2);
+    assert_eq!(by_place[0].value,"North");
+    assert_eq!(by_place[0].statistics.num_atl,30);
+    assert_eq!(by_place[0].statistics.num_btl,20);
+    assert!(by_place[0].statistics.vote_types.is_empty());
+    assert_eq!(by_place[1].value,"South");
+    assert_eq!(by_place[1].statistics.num_atl,0);
+    assert_eq!(by_place[1].statistics.num_btl,50);
+    assert_eq!(by_place[1].statistics.vote_types.len(),1);
+    assert_eq!(by_place[1].statistics.vote_types[0].num_btl,20); // the vote type range was remapped.
+    // other statistics can be filtered the same way.
+    let south = EffectiveNumbers::compute(&data.restrict_to_annotation("polling_place","South"));
+    assert_eq!(south.votes,BallotPaperCount(50));
+    // first preferences are B 30, C 20, so 1/(0.6²+0.4²) = 1/0.52.
+    assert!((south.candidates-1.0/0.52).abs()<1e-9,"candidates {}",south.candidates);
+}
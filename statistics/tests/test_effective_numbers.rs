@@ -0,0 +1,24 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the effective number of candidates and parties against hand computed values.
+
+use statistics::effective_numbers::EffectiveNumbers;
+use stv::ballot_pile::BallotPaperCount;
+
+mod common;
+use common::party_x_and_c;
+
+#[test]
+fn test_effective_numbers_three_candidates() {
+    let data = party_x_and_c();
+    let effective = EffectiveNumbers::compute(&data);
+    assert_eq!(effective.votes,BallotPaperCount(100));
+    // first preferences are A 50 (30 of them ATL), B 30, C 20, so 1/(0.5²+0.3²+0.2²) = 1/0.38.
+    assert!((effective.candidates-1.0/0.38).abs()<1e-9,"candidates {}",effective.candidates);
+    // party X has 80 and C, in no party, 20, so 1/(0.8²+0.2²) = 1/0.68.
+    assert!((effective.parties-1.0/0.68).abs()<1e-9,"parties {}",effective.parties);
+}
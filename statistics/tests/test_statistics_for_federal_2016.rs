@@ -67,7 +67,8 @@ fn test_tasmanian_statistics_using_just_formal_votes() {
         want_candidates: true,
         use_atl: false,
         use_btl: true,
-        subtract_mean: false
+        subtract_mean: false,
+        weight_by_value_at_count: None,
     }).to_distance_matrix();
     expect(&d,0,0,0.0);
     expect(&d,0,1,0.01303692991283878);
@@ -84,6 +85,7 @@ fn test_tasmanian_statistics_using_just_formal_votes() {
         use_atl: true,
         use_btl: true,
         subtract_mean: true,
+        weight_by_value_at_count: None,
     }).to_distance_matrix();
     expect(&d,0,0,0.0);
     expect(&d,0,1,1.0743019530531643);
@@ -120,7 +122,8 @@ fn test_tasmanian_statistics_using_just_formal_votes() {
         who_is_groups: false,
         use_atl: true,
         use_btl: true,
-        who: vec![2,8]
+        who: vec![2,8],
+        weight_by_value_at_count: None,
     });
 
     assert_eq!(intent.table[0][0].0,3277);
@@ -135,7 +138,8 @@ fn test_tasmanian_statistics_using_just_formal_votes() {
         who_is_groups: true,
         use_atl: true,
         use_btl: true,
-        who: vec![1,2,5]
+        who: vec![1,2,5],
+        weight_by_value_at_count: None,
     });
 
     assert_eq!(intent.table[0][0].0,2767); // Family First -> ALP
@@ -0,0 +1,39 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check which candidates and parties clear a first preference threshold.
+
+use statistics::viability_threshold::ViabilityThreshold;
+use stv::ballot_metadata::{CandidateIndex, PartyIndex};
+use stv::ballot_pile::BallotPaperCount;
+use stv::election_data::ElectionData;
+
+mod common;
+use common::{atl, btl, metadata};
+
+#[test]
+fn test_viability_threshold() {
+    let data = ElectionData {
+        metadata: metadata(&[("A",Some(0)),("B",Some(0)),("C",None),("D",Some(1))],&["X","Y"]),
+        atl: vec![atl(&[0],30)],
+        btl: vec![btl(&[0],20),btl(&[1,0],3),btl(&[2],20),btl(&[3],27)],
+        ..Default::default()
+    };
+    // first preferences are A 50 (30 of them ATL), B 3, C 20 and D 27, so parties X 53 and Y 27.
+    let viability = ViabilityThreshold::compute(&data,0.25);
+    assert_eq!(viability.votes,BallotPaperCount(100));
+    assert_eq!(viability.candidate_first_preferences,vec![BallotPaperCount(50),BallotPaperCount(3),BallotPaperCount(20),BallotPaperCount(27)]);
+    assert_eq!(viability.party_first_preferences,vec![BallotPaperCount(53),BallotPaperCount(27)]);
+    assert_eq!(viability.candidates_above,vec![CandidateIndex(0),CandidateIndex(3)]);
+    assert_eq!(viability.candidates_below,vec![CandidateIndex(1),CandidateIndex(2)]);
+    assert_eq!(viability.parties_above,vec![PartyIndex(0),PartyIndex(1)]);
+    assert!(viability.parties_below.is_empty());
+    let viability = ViabilityThreshold::compute(&data,0.3);
+    assert_eq!(viability.candidates_above,vec![CandidateIndex(0)]);
+    assert_eq!(viability.candidates_below,vec![CandidateIndex(1),CandidateIndex(2),CandidateIndex(3)]);
+    assert_eq!(viability.parties_above,vec![PartyIndex(0)]);
+    assert_eq!(viability.parties_below,vec![PartyIndex(1)]);
+}
@@ -0,0 +1,108 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that intent tables weighted by ballot value at a given count differ from unweighted ones in the expected way.
+
+use std::collections::HashSet;
+use statistics::ballot_weights::BallotWeights;
+use statistics::intent_table::{IntentTable, IntentTableOptions};
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use stv::ballot_paper::BTL;
+use stv::ballot_pile::{BallotPaperCount, DoNotSplitByCountNumber};
+use stv::distribution_of_preferences_transcript::{CountIndex, Transcript};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{distribute_preferences_with_extractors, BigRational, LastParcelUse, PreferenceDistributionRules, SurplusTransferMethod, TransferValueMethod, WhenToDoElectCandidateClauseChecking};
+use stv::random_util::Randomness;
+use stv::tie_resolution::{MethodOfTieResolution, TieResolutionsMadeByEC};
+use stv::transfer_value::{convert_usize_to_rational, round_rational_down_to_usize, TransferValue};
+
+struct SimpleRules {}
+
+impl PreferenceDistributionRules for SimpleRules {
+    type Tally = usize;
+    type SplitByNumber = DoNotSplitByCountNumber;
+
+    fn use_last_parcel_for_surplus_distribution() -> LastParcelUse { LastParcelUse::No }
+    fn transfer_value_method() -> TransferValueMethod { TransferValueMethod::SurplusOverBallots }
+    fn convert_tally_to_rational(tally: Self::Tally) -> BigRational { convert_usize_to_rational(tally) }
+    fn convert_rational_to_tally_after_applying_transfer_value(rational: BigRational) -> Self::Tally { round_rational_down_to_usize(rational) }
+    fn make_transfer_value(surplus: usize, ballots: BallotPaperCount) -> TransferValue { TransferValue::from_surplus(surplus,ballots) }
+    fn use_transfer_value(transfer_value: &TransferValue, ballots: BallotPaperCount) -> usize { transfer_value.mul_rounding_down(ballots) }
+    fn surplus_distribution_subdivisions() -> SurplusTransferMethod { SurplusTransferMethod::JustOneTransferValue }
+    fn sort_exclusions_by_transfer_value() -> bool { true }
+    fn resolve_ties_elected_one_of_last_two() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_elected_by_quota() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_elected_all_remaining() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_choose_lowest_candidate_for_exclusion() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn check_elected_if_in_middle_of_surplus_distribution() -> bool { true }
+    fn check_elected_if_in_middle_of_exclusion() -> bool { true }
+    fn finish_all_counts_in_elimination_when_all_elected() -> bool { false }
+    fn finish_all_surplus_distributions_when_all_elected() -> bool { false }
+    fn when_to_check_if_just_two_standing_for_shortcut_election() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::Never }
+    fn when_to_check_if_all_remaining_should_get_elected() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::AfterCheckingQuota }
+    fn when_to_check_if_top_few_have_overwhelming_votes() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::Never }
+    fn name() -> String { "SimpleRules".to_string() }
+}
+
+/// Two vacancies, quota 34. A gets 60 votes all going to B, so A's surplus of 26 goes to B at a transfer value of 26/60, electing B.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: ElectionMetadata {
+            candidates: vec![Candidate::from_name("A"),Candidate::from_name("B"),Candidate::from_name("C"),Candidate::from_name("D")],
+            vacancies: Some(NumberOfCandidates(2)),
+            ..Default::default()
+        },
+        btl: vec![
+            BTL{ candidates: vec![CandidateIndex(0),CandidateIndex(1)], n: 60 },
+            BTL{ candidates: vec![CandidateIndex(1)], n: 20 },
+            BTL{ candidates: vec![CandidateIndex(2)], n: 15 },
+            BTL{ candidates: vec![CandidateIndex(3),CandidateIndex(2)], n: 5 },
+        ],
+        ..Default::default()
+    }
+}
+
+fn count(data:&ElectionData,include_list_of_votes_in_transcript:bool) -> Transcript<usize> {
+    distribute_preferences_with_extractors::<SimpleRules>(data,NumberOfCandidates(2),&HashSet::new(),&TieResolutionsMadeByEC::default(),None,false,&mut Randomness::ReverseDonkeyVote,&[],include_list_of_votes_in_transcript)
+}
+
+fn expect_close(actual:f64,expected:f64) {
+    assert!((actual-expected).abs()<1e-9,"Got {} expecting {}",actual,expected);
+}
+
+#[test]
+fn test_weighted_intent_table() {
+    let data = make_data();
+    let transcript = count(&data,true);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+    let options = IntentTableOptions{ first_pref_by_groups: false, who_is_groups: false, use_atl: true, use_btl: true, who: vec![1], weight_by_value_at_count: Some(CountIndex(1)) };
+    let unweighted = IntentTable::compute(&data,&options);
+    assert_eq!(unweighted.table[0],vec![BallotPaperCount(60),BallotPaperCount(0)]);
+    assert_eq!(unweighted.table[3],vec![BallotPaperCount(0),BallotPaperCount(5)]);
+    assert!(unweighted.weighted.is_none());
+    // At the end of the first count, every paper is still with its first preference at full value, so weighting changes nothing.
+    let weights = BallotWeights::value_at_count(&data,&transcript,CountIndex(0)).unwrap();
+    let weighted = IntentTable::compute_weighted(&data,&options,Some(&weights)).weighted.unwrap();
+    for (row,unweighted_row) in weighted.iter().zip(unweighted.table.iter()) {
+        for (&w,&u) in row.iter().zip(unweighted_row.iter()) { expect_close(w,u.0 as f64); }
+    }
+    // After A's surplus is distributed, A's 60 papers are with B at a value of 26/60 each; the others are unchanged.
+    let weights = BallotWeights::value_at_count(&data,&transcript,CountIndex(1)).unwrap();
+    let weighted = IntentTable::compute_weighted(&data,&options,Some(&weights));
+    assert_eq!(weighted.table,unweighted.table);
+    let weighted = weighted.weighted.unwrap();
+    expect_close(weighted[0][0],26.0);
+    expect_close(weighted[1][0],20.0);
+    expect_close(weighted[2][1],15.0);
+    expect_close(weighted[3][1],5.0);
+}
+
+#[test]
+fn test_weights_need_list_of_votes() {
+    let data = make_data();
+    assert!(BallotWeights::value_at_count(&data,&count(&data,false),CountIndex(0)).is_err());
+    assert!(BallotWeights::value_at_count(&data,&count(&data,true),CountIndex(100)).is_err());
+}
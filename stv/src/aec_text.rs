@@ -0,0 +1,110 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Write a transcript as text in the style of the distribution of preferences reports published by the AEC,
+//! for side by side comparison with official results.
+//!
+//! Each count has a heading like "Count 2. Distribution of surplus of SMITH, John", the transfer value if there is one,
+//! and a row per candidate giving the papers and votes transferred in that count and the progressive total,
+//! followed by rows for exhausted votes, votes lost or gained by rounding (and set aside, if applicable) and the total.
+
+use std::fmt::{Debug, Display, Write};
+use std::ops::{Add, Sub};
+use std::str::FromStr;
+use num::Zero;
+use crate::ballot_metadata::{CandidateIndex, ElectionMetadata, NameFormat};
+use crate::ballot_pile::BallotPaperCount;
+use crate::distribution_of_preferences_transcript::{ElectionReason, PerCandidate, ReasonForCount, SingleCount, Transcript};
+use crate::signed_version::SignedVersion;
+use crate::transfer_value::{DecimalRounding, TransferValueFormat};
+
+/// The AEC writes transfer values truncated to 8 decimal places.
+const TRANSFER_VALUE_FORMAT : TransferValueFormat = TransferValueFormat::Decimal{places:8,rounding:DecimalRounding::Down};
+/// The width of each numeric column.
+const COLUMN_WIDTH : usize = 12;
+
+/// Write `transcript` as text in the style of the AEC. See the module documentation.
+pub fn transcript_to_aec_text<Tally:PartialEq+Clone+Display+FromStr+Debug+Ord+Zero+Add<Output=Tally>+Sub<Output=Tally>>(transcript:&Transcript<Tally>,metadata:&ElectionMetadata) -> String {
+    let names : Vec<String> = metadata.candidates.iter().map(|c|c.display_name(NameFormat::LastFirst)).collect();
+    let name_width = names.iter().map(|n|n.len()).chain(std::iter::once("Gain/loss (rounding)".len())).max().unwrap_or(0);
+    let mut res = String::new();
+    writeln!(res,"Distribution of preferences : {} {} {}",metadata.name.name,metadata.name.electorate,metadata.name.year).unwrap();
+    if let Some(quota) = &transcript.quota {
+        writeln!(res,"Vacancies {}, formal papers {}, quota {}",quota.vacancies,quota.papers,quota.quota).unwrap();
+    }
+    let mut elected_so_far : Vec<CandidateIndex> = vec![];
+    for (index,count) in transcript.counts.iter().enumerate() {
+        let previous = if index==0 { None } else { Some(&transcript.counts[index-1]) };
+        writeln!(res).unwrap();
+        writeln!(res,"Count {}. {}",index+1,heading(count,&names)).unwrap();
+        if let Some(transfer_value) = count.created_transfer_value.as_ref().map(|tv|&tv.transfer_value).or(count.portion.transfer_value.as_ref()) {
+            if index>0 { writeln!(res,"Transfer value {}",TRANSFER_VALUE_FORMAT.format(transfer_value)).unwrap(); }
+        }
+        writeln!(res,"{:name_width$}{:>COLUMN_WIDTH$}{:>COLUMN_WIDTH$}{:>COLUMN_WIDTH$}","","Papers","Votes","Total").unwrap();
+        let tallies = &count.status.tallies;
+        let papers = &count.status.papers;
+        let papers_delta = |now:BallotPaperCount,before:Option<BallotPaperCount>| (now.0 as isize-before.map(|b|b.0 as isize).unwrap_or(0)).to_string();
+        let row = |res:&mut String,name:&str,papers:String,votes:String,total:String| write!(res,"{:name_width$}{:>COLUMN_WIDTH$}{:>COLUMN_WIDTH$}{:>COLUMN_WIDTH$}",name,papers,votes,total).unwrap();
+        for (candidate,name) in names.iter().enumerate() {
+            row(&mut res,name,papers_delta(papers.candidate[candidate],previous.map(|p|p.status.papers.candidate[candidate])),
+                delta(&tallies.candidate[candidate],previous.map(|p|&p.status.tallies.candidate[candidate])).to_string(),
+                tallies.candidate[candidate].to_string());
+            if let Some(elected) = count.elected.iter().find(|e|e.who.0==candidate) {
+                elected_so_far.push(elected.who);
+                write!(res,"  Elected {} ({})",elected_so_far.len(),why(elected.why)).unwrap();
+            } else if count.not_continuing.contains(&CandidateIndex(candidate)) && !elected_so_far.contains(&CandidateIndex(candidate)) { res.push_str("  Excluded"); }
+            res.push('\n');
+        }
+        row(&mut res,"Exhausted",papers_delta(papers.exhausted,previous.map(|p|p.status.papers.exhausted)),delta(&tallies.exhausted,previous.map(|p|&p.status.tallies.exhausted)).to_string(),tallies.exhausted.to_string());
+        res.push('\n');
+        let rounding_delta = match previous { None => tallies.rounding.clone(), Some(p) => tallies.rounding.clone()-p.status.tallies.rounding.clone() };
+        row(&mut res,"Gain/loss (rounding)",String::new(),rounding_delta.to_string(),tallies.rounding.to_string());
+        res.push('\n');
+        if let Some(set_aside) = &tallies.set_aside {
+            let previous_set_aside = previous.and_then(|p|p.status.tallies.set_aside.as_ref());
+            let papers_set_aside = papers.set_aside.unwrap_or(BallotPaperCount(0));
+            row(&mut res,"Set aside",papers_delta(papers_set_aside,previous.and_then(|p|p.status.papers.set_aside)),delta(set_aside,previous_set_aside).to_string(),set_aside.to_string());
+            res.push('\n');
+        }
+        row(&mut res,"Total",String::new(),String::new(),total(tallies).to_string());
+        res.push('\n');
+    }
+    res
+}
+
+fn heading<Tally:PartialEq+Clone+Display+FromStr>(count:&SingleCount<Tally>,names:&[String]) -> String {
+    if let Some(name) = &count.count_name { return name.clone(); }
+    let list = |candidates:&[CandidateIndex]| candidates.iter().map(|c|names[c.0].as_str()).collect::<Vec<_>>().join(" and ");
+    match &count.reason {
+        ReasonForCount::FirstPreferenceCount => "First preferences".to_string(),
+        ReasonForCount::ExcessDistribution(candidate) => format!("Distribution of surplus of {}",names[candidate.0]),
+        ReasonForCount::Elimination(candidates) => format!("Exclusion of {}",list(candidates)),
+    }
+}
+
+fn why(reason:ElectionReason) -> &'static str {
+    match reason {
+        ElectionReason::ReachedQuota => "quota",
+        ElectionReason::HighestOfLastTwoStanding => "highest of last two",
+        ElectionReason::AllRemainingMustBeElected => "all remaining elected",
+        ElectionReason::OverwhelmingTally => "overwhelming tally",
+    }
+}
+
+/// The change from `before` (zero if None) to `now`, which may be negative.
+fn delta<Tally:Clone+Display+Ord+Zero+Add<Output=Tally>+Sub<Output=Tally>>(now:&Tally,before:Option<&Tally>) -> SignedVersion<Tally> {
+    let now = SignedVersion{ negative: false, value: now.clone() };
+    match before {
+        None => now,
+        Some(before) => now-SignedVersion{ negative: false, value: before.clone() },
+    }
+}
+
+fn total<Tally:PartialEq+Clone+Display+FromStr+Ord+Zero+Add<Output=Tally>+Sub<Output=Tally>>(tallies:&PerCandidate<Tally>) -> SignedVersion<Tally> {
+    let sum = tallies.candidate.iter().chain(std::iter::once(&tallies.exhausted)).chain(tallies.set_aside.iter()).fold(Tally::zero(),|acc,t|acc+t.clone());
+    SignedVersion{ negative: false, value: sum }+tallies.rounding.clone()
+}
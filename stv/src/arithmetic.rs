@@ -0,0 +1,98 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Basic numeric types and traits used by the tally types, with no dependence on std,
+//! so that they are available when the `std` feature is disabled.
+
+use core::fmt;
+use core::fmt::{Debug, Display};
+use core::iter::Sum;
+use core::ops::{AddAssign, Sub, Add, SubAssign};
+use core::str::FromStr;
+use num::{ToPrimitive, Zero};
+use serde::{Serialize,Deserialize};
+
+/// A number representing a count of pieces of paper.
+/// This is distinct from votes which may be fractional in the presence of weights.
+#[derive(Copy,Clone,Eq, PartialEq,Serialize,Deserialize,Ord, PartialOrd)]
+pub struct BallotPaperCount(pub usize);
+
+impl From<BallotPaperCount> for usize {
+    fn from(value: BallotPaperCount) -> Self {
+        value.0
+    }
+}
+impl From<BallotPaperCount> for isize {
+    fn from(value: BallotPaperCount) -> Self {
+        value.0 as isize // not ideal as overflow is possible if you are running on a 32 bit machine with over 2 billion ballots and you (insanely) want support for negative transfer values. But the overflow is not your biggest problem then.
+    }
+}
+impl AddAssign for BallotPaperCount {
+    fn add_assign(&mut self, rhs: Self) { self.0+=rhs.0; }
+}
+impl SubAssign for BallotPaperCount {
+    fn sub_assign(&mut self, rhs: Self) { self.0-=rhs.0; }
+}
+
+impl Sub for BallotPaperCount {
+    type Output = BallotPaperCount;
+    fn sub(self, rhs: Self) -> Self::Output { BallotPaperCount(self.0-rhs.0) }
+}
+
+impl Add for BallotPaperCount {
+    type Output = BallotPaperCount;
+    fn add(self, rhs: Self) -> Self::Output { BallotPaperCount(self.0+rhs.0) }
+}
+// type alias really, don't want long display
+impl Display for BallotPaperCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.0) }
+}
+// type alias really, don't want long display
+impl Debug for BallotPaperCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.0) }
+}
+impl Zero for BallotPaperCount {
+    fn zero() -> Self { BallotPaperCount(0) }
+    fn is_zero(&self) -> bool { self.0 == 0 }
+}
+impl FromStr for BallotPaperCount {
+    type Err = <usize as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(BallotPaperCount(s.parse()?))
+    }
+}
+impl Sum for BallotPaperCount {
+    fn sum<I: Iterator<Item=Self>>(iter: I) -> Self {
+        BallotPaperCount(usize::sum(iter.map(|b|b.0)))
+    }
+}
+
+pub trait RoundUpToUsize {
+    /// round up to the next integer.
+    fn ceil(&self) -> usize;
+}
+
+impl RoundUpToUsize for usize {
+    fn ceil(&self) -> usize { *self }
+}
+impl RoundUpToUsize for isize {
+    fn ceil(&self) -> usize { self.to_usize().unwrap_or(0) } // not ideal but we shouldn't expect negative vote tallies to behave sensibly. Used in margin computation and surplus distribution so should not be a problem unless its use grows.
+}
+
+// like From<X> but implemented for usize (if there are more than 2^53 votes, the official transcript checking will have problems).
+
+pub trait CanConvertToF64PossiblyLossily {
+    fn convert_to_f64(&self) -> f64;
+}
+
+impl CanConvertToF64PossiblyLossily for usize {
+    fn convert_to_f64(&self) -> f64 { *self as f64 }
+}
+impl CanConvertToF64PossiblyLossily for isize {
+    fn convert_to_f64(&self) -> f64 { *self as f64 }
+}
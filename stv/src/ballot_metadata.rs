@@ -11,6 +11,7 @@ use serde::{Serialize,Deserialize};
 use std::fmt;
 use std::path::PathBuf;
 use crate::election_data::ElectionData;
+use crate::ballot_pile::BallotPaperCount;
 use std::fs::File;
 use std::collections::HashMap;
 use std::iter::Map;
@@ -18,6 +19,7 @@ use std::ops::{Range, Sub};
 use std::str::FromStr;
 use thiserror::Error;
 use crate::tie_resolution::TieResolutionsMadeByEC;
+use crate::file_format::load_with_migration;
 
 /// a candidate, referred to by position on the ballot paper, 0 being first
 #[derive(Clone, Copy, PartialEq, Eq, Hash,Serialize,Deserialize)]
@@ -79,7 +81,7 @@ impl Sub for NumberOfCandidates {
 }
 
 /// Information about the election
-#[derive(Debug,Serialize,Deserialize,Clone)]
+#[derive(Debug,Serialize,Deserialize,Clone,Default)]
 pub struct ElectionMetadata {
     pub name : ElectionName,
     pub candidates : Vec<Candidate>,
@@ -103,6 +105,12 @@ pub struct ElectionMetadata {
     /// Candidates who are usually excluded, e.g. if they died on the election day or were ruled ineligible to stand. Looking at you 2016.
     #[serde(skip_serializing_if = "Vec::is_empty",default)]
     pub excluded : Vec<CandidateIndex>,
+    /// Candidates who withdrew before the count (e.g. after the ballot papers were printed), who are treated as if they had never been on the ballot paper.
+    /// Like [Self::excluded] they never are continuing candidates, so votes skip over them to the next preference from the first count. Unlike
+    /// excluded candidates, a ballot paper expressing preferences only for withdrawn candidates is not counted at all, so is never part of the
+    /// quota base, even if [crate::preference_distribution::PreferenceDistributionRules::should_exhausted_votes_count_for_quota_computation].
+    #[serde(skip_serializing_if = "Vec::is_empty",default)]
+    pub withdrawn_before_count : Vec<CandidateIndex>,
     #[serde(flatten)]
     pub tie_resolutions : TieResolutionsMadeByEC,
 }
@@ -124,6 +132,23 @@ impl DataSource {
 impl ElectionMetadata {
     pub fn party(&self,index:PartyIndex) -> &Party { &self.parties[index.0] }
     pub fn candidate(&self,index:CandidateIndex) -> &Candidate { &self.candidates[index.0] }
+    /// The [StableCandidateId] of each candidate, in candidate order. If multiple candidates have the same name and party,
+    /// the second gets "-2" appended to its identifier, the third "-3", etc., so these are only stable if such candidates are not reordered relative to each other.
+    pub fn stable_candidate_ids(&self) -> Vec<StableCandidateId> {
+        let mut times_seen : HashMap<StableCandidateId,usize> = HashMap::default();
+        self.candidates.iter().map(|c|{
+            let id = c.stable_id(c.party.map(|p|self.party(p).name.as_str()));
+            let seen = times_seen.entry(id.clone()).or_insert(0);
+            *seen+=1;
+            if *seen==1 { id } else { StableCandidateId(format!("{}-{}",id,seen)) }
+        }).collect()
+    }
+    /// The [StableCandidateId] of a given candidate. If looking up many, [Self::stable_candidate_ids] is more efficient.
+    pub fn stable_candidate_id(&self,index:CandidateIndex) -> StableCandidateId { self.stable_candidate_ids().swap_remove(index.0) }
+    /// Get a hashmap going from [StableCandidateId] to index.
+    pub fn get_stable_candidate_id_lookup(&self) -> HashMap<StableCandidateId,CandidateIndex> {
+        self.stable_candidate_ids().into_iter().enumerate().map(|(i,id)|(id,CandidateIndex(i))).collect()
+    }
     /// Get a hashmap going from candidate name to index
     pub fn get_candidate_name_lookup(&self) -> HashMap<String,CandidateIndex> {
         let mut res = HashMap::default();
@@ -191,10 +216,42 @@ impl ElectionMetadata {
     pub fn party_list_to_string(&self,list : &[PartyIndex]) -> String {
         list.iter().map(|&c|self.party(c).best_name()).collect::<Vec<_>>().join(", ")
     }
+
+    /// Find a candidate given a name typed by a human, matching either the candidate name as stored or its no_comma_name.
+    /// It is an error if no candidate, or more than one candidate, matches.
+    pub fn find_candidate_by_name(&self,name:&str) -> Result<CandidateIndex,ParseMetadataError> {
+        let name = name.trim();
+        let mut found : Option<CandidateIndex> = None;
+        for (index,candidate) in self.candidates.iter().enumerate() {
+            if candidate.name.trim()==name || candidate.no_comma_name()==name {
+                if found.is_some() { return Err(ParseMetadataError::AmbiguousCandidateName(name.to_string())) }
+                found=Some(CandidateIndex(index));
+            }
+        }
+        found.ok_or_else(||ParseMetadataError::UnknownCandidateName(name.to_string()))
+    }
+
+    /// Find a party given a description typed by a human, which may be its index (starting counting at zero),
+    /// its name, its abbreviation or its column id (e.g. `A`). An index takes precedence; otherwise it is an error
+    /// if no party, or more than one party, matches.
+    pub fn find_party(&self,description:&str) -> Result<PartyIndex,ParseMetadataError> {
+        let description = description.trim();
+        if let Ok(index) = description.parse::<usize>() {
+            if index<self.parties.len() { return Ok(PartyIndex(index)) }
+        }
+        let mut found : Option<PartyIndex> = None;
+        for (index,party) in self.parties.iter().enumerate() {
+            if party.name.trim()==description || party.column_id==description || party.abbreviation.as_deref()==Some(description) {
+                if found.is_some() { return Err(ParseMetadataError::AmbiguousPartyName(description.to_string())) }
+                found=Some(PartyIndex(index));
+            }
+        }
+        found.ok_or_else(||ParseMetadataError::UnknownPartyName(description.to_string()))
+    }
 }
 
 /// Which election it was.
-#[derive(Debug,Serialize,Deserialize,Clone)]
+#[derive(Debug,Serialize,Deserialize,Clone,Default)]
 pub struct ElectionName {
     /// The year this election was held
     pub year : String,
@@ -230,7 +287,7 @@ impl ElectionName {
     pub fn load_cached_data(&self) -> std::io::Result<ElectionData> {
         let name = self.cache_file_name();
         let file = File::open(name)?;
-        Ok(serde_json::from_reader(file)?)
+        load_with_migration(file).map_err(std::io::Error::other)
     }
 }
 
@@ -251,6 +308,10 @@ pub struct Party {
     /// the group voting tickets for this party, if any.
     #[serde(skip_serializing_if = "Vec::is_empty",default)]
     pub tickets : Vec<Vec<CandidateIndex>>,
+    /// The number of above the line votes for this party published by the electoral commission, if known. This is useful when
+    /// the above the line votes had to be reconstructed from other data; see [ElectionData::validate_atl_reconstruction].
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    pub official_atl_votes : Option<BallotPaperCount>,
 }
 
 impl Party {
@@ -273,6 +334,17 @@ pub struct Candidate {
     pub ec_id : Option<String>,
 }
 
+/// An identifier for a candidate that does not depend upon the order of candidates in the metadata, unlike [CandidateIndex].
+/// It is derived from the candidate's name and party name (see [ElectionMetadata::stable_candidate_ids]), so it can be used
+/// to refer to a candidate in data (such as tie resolutions) that may be used with metadata in which the candidates have been reordered.
+#[derive(Clone,PartialEq,Eq,Hash,Serialize,Deserialize,Debug)]
+#[serde(transparent)]
+pub struct StableCandidateId(pub String);
+
+impl fmt::Display for StableCandidateId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.0) }
+}
+
 impl Candidate {
     /// if the candidate name is "Surname, first", change to "first Surname"
     pub fn no_comma_name(&self) -> String {
@@ -282,6 +354,69 @@ impl Candidate {
     }
 
     pub fn from_name(name:&str) -> Self { Candidate{name:name.to_string(),party:None,position:None,ec_id:None}}
+
+    /// A stable identifier for this candidate, given the name of their party, if any. This is a 64 bit FNV-1a hash of the name and
+    /// party name written in hexadecimal, a fixed algorithm so that it will not change between versions of ConcreteSTV.
+    /// Candidates with the same name in the same party get the same identifier; [ElectionMetadata::stable_candidate_ids] distinguishes them.
+    pub fn stable_id(&self,party_name:Option<&str>) -> StableCandidateId {
+        let mut hash : u64 = 0xcbf29ce484222325;
+        for b in self.name.bytes().chain(std::iter::once(0x1f)).chain(party_name.unwrap_or("").bytes()) {
+            hash^=b as u64;
+            hash=hash.wrapping_mul(0x100000001b3);
+        }
+        StableCandidateId(format!("{:016x}",hash))
+    }
+
+    /// Best effort split of the name into (surname,given names).
+    /// * "SURNAME, Given Names" is split at the comma.
+    /// * "Given Names SURNAME" uses the trailing words in upper case as the surname if there are some (but not all words are upper case), otherwise the last word.
+    /// * A single word is not split, and None is returned.
+    pub fn surname_and_given_names(&self) -> Option<(String,String)> {
+        if let Some((surname,given)) = self.name.split_once(',') {
+            let (surname,given) = (surname.trim(),given.trim());
+            return if surname.is_empty() || given.is_empty() { None } else { Some((surname.to_string(),given.to_string())) };
+        }
+        let words : Vec<&str> = self.name.split_whitespace().collect();
+        if words.len()<2 { return None; }
+        let is_upper_case = |w:&&&str| w.chars().any(|c|c.is_alphabetic()) && !w.chars().any(|c|c.is_lowercase());
+        let num_upper_at_end = words.iter().rev().take_while(is_upper_case).count();
+        let surname_words = if num_upper_at_end>0 && num_upper_at_end<words.len() { num_upper_at_end } else { 1 };
+        let split = words.len()-surname_words;
+        Some((words[split..].join(" "),words[..split].join(" ")))
+    }
+
+    /// The name in the given format. If the name can't be split into surname and given names, it is returned as stored.
+    pub fn display_name(&self,format:NameFormat) -> String {
+        match (format,self.surname_and_given_names()) {
+            (NameFormat::LastFirst,Some((surname,given))) => surname+", "+&given,
+            (NameFormat::FirstLast,Some((surname,given))) => given+" "+&surname,
+            _ => self.name.clone(),
+        }
+    }
+}
+
+/// How to write candidate names. Electoral commission files variously use "SURNAME, Given" and "Given SURNAME".
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Serialize,Deserialize,Default)]
+pub enum NameFormat {
+    /// Exactly as in the .stv file.
+    #[default]
+    AsStored,
+    /// e.g. "SMITH, John"
+    LastFirst,
+    /// e.g. "John SMITH"
+    FirstLast,
+}
+
+impl FromStr for NameFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "asstored" | "as_stored" => Ok(NameFormat::AsStored),
+            "lastfirst" | "last_first" => Ok(NameFormat::LastFirst),
+            "firstlast" | "first_last" => Ok(NameFormat::FirstLast),
+            _ => Err(format!("Unknown name format {}, expecting AsStored, LastFirst or FirstLast",s)),
+        }
+    }
 }
 
 /// There are lots of places where one needs to parse some file to extract a list of candidates
@@ -311,8 +446,19 @@ pub enum ParseMetadataError {
     PartyExpectedButNotAvailable,
     #[error("could not find candidate name : {0}")]
     UnknownCandidateName(String),
+    #[error("more than one candidate has the name : {0}")]
+    AmbiguousCandidateName(String),
+    #[error("could not find party : {0}")]
+    UnknownPartyName(String),
+    #[error("more than one party matches : {0}")]
+    AmbiguousPartyName(String),
 }
 
+/// The number of vacancies was needed, but is not given in the [ElectionMetadata].
+#[derive(Error,Debug,Clone,Copy,PartialEq,Eq)]
+#[error("Need to specify number of vacancies")]
+pub struct MissingVacancies;
+
 impl CandidateAndPartyBuilder {
     pub fn last_party(&self) -> Result<&Party, ParseMetadataError> { self.parties.last().ok_or(ParseMetadataError::PartyExpectedButNotAvailable {}) }
     pub fn last_party_mut(&mut self) -> Result<&mut Party, ParseMetadataError> { self.parties.last_mut().ok_or(ParseMetadataError::PartyExpectedButNotAvailable {}) }
@@ -326,6 +472,7 @@ impl CandidateAndPartyBuilder {
             atl_allowed,
             candidates: vec![],
             tickets: vec![],
+            official_atl_votes: None,
         })
     }
 
@@ -375,3 +522,35 @@ impl CandidateAndPartyBuilder {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, ElectionName, ParseMetadataError};
+    use crate::tie_resolution::TieResolutionsMadeByEC;
+
+    fn metadata_with_candidates(names:&[&str]) -> ElectionMetadata {
+        ElectionMetadata{
+            name: ElectionName{ year: "2024".to_string(), authority: "Test".to_string(), name: "Test".to_string(), electorate: "Test".to_string(), modifications: vec![], comment: None },
+            candidates: names.iter().map(|n|Candidate::from_name(n)).collect(),
+            parties: vec![],
+            source: vec![],
+            results: None,
+            vacancies: None,
+            enrolment: None,
+            secondary_vacancies: None,
+            excluded: vec![],
+            withdrawn_before_count: vec![],
+            tie_resolutions: TieResolutionsMadeByEC::default(),
+        }
+    }
+
+    #[test]
+    fn test_find_candidate_by_name() {
+        let metadata = metadata_with_candidates(&["Smith, J","Jones, K","Brown, A","Brown, A"]);
+        assert_eq!(CandidateIndex(0),metadata.find_candidate_by_name("Smith, J").unwrap());
+        assert_eq!(CandidateIndex(1),metadata.find_candidate_by_name(" Jones, K ").unwrap());
+        assert_eq!(CandidateIndex(1),metadata.find_candidate_by_name("K Jones").unwrap());
+        assert!(matches!(metadata.find_candidate_by_name("Brown, A"),Err(ParseMetadataError::AmbiguousCandidateName(_))));
+        assert!(matches!(metadata.find_candidate_by_name("Smith"),Err(ParseMetadataError::UnknownCandidateName(_))));
+    }
+}
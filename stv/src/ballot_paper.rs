@@ -12,6 +12,7 @@ use crate::ballot_metadata::{CandidateIndex, ElectionMetadata, PartyIndex};
 use serde::{Deserialize,Serialize};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use anyhow::anyhow;
 use crate::election_data::{ElectionData, VoteTypeSpecification};
 
@@ -59,6 +60,78 @@ pub fn parse_marking(marking:&str) -> RawBallotMarking {
     }
 }
 
+/// What to do with a ballot whose numbering skips a number, e.g. 1,2,4,5 with no 3.
+/// In all cases numbering stops just before the first repeated number, e.g. 1,2,2,3 is only formal as 1.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Default,Serialize,Deserialize)]
+pub enum GapPolicy {
+    /// Preferences are only valid up to the gap. E.g. 1,2,4,5 is treated as 1,2 then exhausts. This is the most common rule.
+    #[default]
+    TruncateAtGap,
+    /// A gap anywhere before the first repeated number makes the whole ballot informal.
+    Informal,
+    /// The numbers after the gap are still used, in order. E.g. 1,2,4,5 is treated as 1,2,3,4.
+    IgnoreGap,
+}
+
+impl Display for GapPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            GapPolicy::TruncateAtGap => "TruncateAtGap",
+            GapPolicy::Informal => "Informal",
+            GapPolicy::IgnoreGap => "IgnoreGap",
+        })
+    }
+}
+
+impl FromStr for GapPolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "TruncateAtGap" => Ok(GapPolicy::TruncateAtGap),
+            "Informal" => Ok(GapPolicy::Informal),
+            "IgnoreGap" => Ok(GapPolicy::IgnoreGap),
+            _ => Err("No such gap policy. Allowed policies are TruncateAtGap, Informal, IgnoreGap"),
+        }
+    }
+}
+
+/// What to do with a ballot that has preferences marked both above and below the line.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Default,Serialize,Deserialize)]
+pub enum MixedBallotPolicy {
+    /// Use the below the line vote if it is formal, otherwise the above the line vote.
+    /// This is what all the jurisdictions currently supported by ConcreteSTV do.
+    #[default]
+    BtlTakesPrecedence,
+    /// "Ticket plus personal vote" : if the above the line vote is formal, the below the line preferences (however few) are
+    /// used first, followed by the candidates from the above the line vote who have not already been listed. For a party with
+    /// group voting tickets, its first ticket is used. If the above the line vote is not formal, this is the same as [MixedBallotPolicy::BtlTakesPrecedence].
+    ///
+    /// None of the Australian jurisdictions supported by ConcreteSTV use this rule; it is provided for studying hypothetical rules.
+    BtlPrefixThenAtl,
+}
+
+impl Display for MixedBallotPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MixedBallotPolicy::BtlTakesPrecedence => "BtlTakesPrecedence",
+            MixedBallotPolicy::BtlPrefixThenAtl => "BtlPrefixThenAtl",
+        })
+    }
+}
+
+impl FromStr for MixedBallotPolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "BtlTakesPrecedence" => Ok(MixedBallotPolicy::BtlTakesPrecedence),
+            "BtlPrefixThenAtl" => Ok(MixedBallotPolicy::BtlPrefixThenAtl),
+            _ => Err("No such mixed ballot policy. Allowed policies are BtlTakesPrecedence, BtlPrefixThenAtl"),
+        }
+    }
+}
+
 /// The collection of numbers written by the voter on the ballot.
 pub struct RawBallotMarkings<'a> {
     /// atl[i] is the marking for party atl_parties[i].
@@ -129,20 +202,46 @@ impl<'a> RawBallotMarkings<'a> {
     /// Given a raw vote, interpret it as a list of preferences.
     /// Using AEC style rules,
     pub fn interpret_vote(&self,min_atl_prefs_needed:usize,min_btl_prefs_needed:usize) -> Option<FormalVote> {
-        if let Some(btl) = self.interpret_vote_as_btl(min_btl_prefs_needed) {
+        self.interpret_vote_with_gap_policy(min_atl_prefs_needed,min_btl_prefs_needed,GapPolicy::TruncateAtGap)
+    }
+
+    /// Like [Self::interpret_vote], but with a specified treatment of gaps in the numbering.
+    pub fn interpret_vote_with_gap_policy(&self,min_atl_prefs_needed:usize,min_btl_prefs_needed:usize,gap_policy:GapPolicy) -> Option<FormalVote> {
+        if let Some(btl) = self.interpret_vote_as_btl_with_gap_policy(min_btl_prefs_needed,gap_policy) {
             Some(FormalVote::Btl(btl))
-        } else if let Some(atl)  = self.interpret_vote_as_atl(min_atl_prefs_needed) {
+        } else if let Some(atl)  = self.interpret_vote_as_atl(min_atl_prefs_needed,gap_policy) {
             Some(FormalVote::Atl(atl))
         } else {None}
     }
 
+    /// Like [Self::interpret_vote_with_gap_policy], but with a specified treatment of ballots marked both above and below the line.
+    /// The metadata is used to turn the above the line vote into candidates for [MixedBallotPolicy::BtlPrefixThenAtl].
+    pub fn interpret_vote_with_mixed_policy(&self,min_atl_prefs_needed:usize,min_btl_prefs_needed:usize,gap_policy:GapPolicy,mixed_policy:MixedBallotPolicy,metadata:&ElectionMetadata) -> Option<FormalVote> {
+        match mixed_policy {
+            MixedBallotPolicy::BtlTakesPrecedence => self.interpret_vote_with_gap_policy(min_atl_prefs_needed,min_btl_prefs_needed,gap_policy),
+            MixedBallotPolicy::BtlPrefixThenAtl => {
+                let Some(atl) = self.interpret_vote_as_atl(min_atl_prefs_needed,gap_policy) else {
+                    return self.interpret_vote_as_btl_with_gap_policy(min_btl_prefs_needed,gap_policy).map(FormalVote::Btl);
+                };
+                let mut candidates = self.interpret_vote_as_btl_with_gap_policy(1,gap_policy).map(|btl|btl.candidates).unwrap_or_default();
+                if candidates.is_empty() { return Some(FormalVote::Atl(atl)); }
+                let party = metadata.party(atl.first_party());
+                let tail = if party.tickets.is_empty() { atl.resolve_to_candidates(metadata) } else { party.tickets[0].clone() };
+                for candidate in tail {
+                    if !candidates.contains(&candidate) { candidates.push(candidate); }
+                }
+                Some(FormalVote::Btl(BTL{ candidates, n: 1 }))
+            }
+        }
+    }
+
     /// Interpret a list of markings as preferences.
     /// * Ignore all repeated numbers. E.g. 1 2 2 ignore the 2s.
-    /// * Ignore all numbers after a gap. E.g. 1 3 4 ignore the 3 and 4 (unless allow_gaps) is true
+    /// * Deal with numbers after a gap (e.g. 1 3 4) as specified by gap_policy.
     /// * Treat a cross as a 1 iff consider_cross_as_one true
     /// Otherwise take the longest list of preferences starting at 1.
-    /// The return type is given by a (provided) function
-    fn look_for_continuous_streams<T:Copy,F : Fn(usize)->T>(markings:&[RawBallotMarking],result_generator:F,consider_cross_as_one:bool,allow_gaps:bool) -> Vec<T> {
+    /// The return type is given by a (provided) function. Returns None if the gap policy makes the markings informal.
+    fn look_for_continuous_streams<T:Copy,F : Fn(usize)->T>(markings:&[RawBallotMarking],result_generator:F,consider_cross_as_one:bool,gap_policy:GapPolicy) -> Option<Vec<T>> {
         let mut times_seen = vec![0 as usize;markings.len()];
         let mut prefs = vec![result_generator(0);markings.len()];
         for i in 0..markings.len() {
@@ -158,33 +257,43 @@ impl<'a> RawBallotMarkings<'a> {
                 _ => {}
             }
         }
-        if allow_gaps {
-            let mut num_good = 0;
-            let mut num_up_to = 0;
-            while num_up_to<times_seen.len() && times_seen[num_up_to]<2 {
-                if times_seen[num_up_to]==0 { prefs.remove(num_good); } else { num_good+=1; }
-                num_up_to+=1;
+        match gap_policy {
+            GapPolicy::IgnoreGap => {
+                let mut num_good = 0;
+                let mut num_up_to = 0;
+                while num_up_to<times_seen.len() && times_seen[num_up_to]<2 {
+                    if times_seen[num_up_to]==0 { prefs.remove(num_good); } else { num_good+=1; }
+                    num_up_to+=1;
+                }
+                prefs.truncate(num_good);
+            }
+            GapPolicy::TruncateAtGap | GapPolicy::Informal => {
+                let mut num_good = 0;
+                while num_good<times_seen.len() && times_seen[num_good]==1 { num_good+=1; }
+                if gap_policy==GapPolicy::Informal && num_good<times_seen.len() && times_seen[num_good]==0 {
+                    // a gap rather than a repeat. Informal if anything is numbered after it, before a repeat.
+                    if times_seen[num_good..].iter().take_while(|&&seen|seen<2).any(|&seen|seen==1) { return None; }
+                }
+                prefs.truncate(num_good);
             }
-            prefs.truncate(num_good);
-        } else {
-            let mut num_good = 0;
-            while num_good<times_seen.len() && times_seen[num_good]==1 { num_good+=1; }
-            prefs.truncate(num_good);
         }
-        prefs
+        Some(prefs)
     }
 
-    fn interpret_vote_as_atl(&'a self,min_atl_prefs_needed:usize) -> Option<ATL> {
-        let prefs = RawBallotMarkings::look_for_continuous_streams(self.atl,|i|self.atl_parties[i],true,false);
+    fn interpret_vote_as_atl(&'a self,min_atl_prefs_needed:usize,gap_policy:GapPolicy) -> Option<ATL> {
+        let prefs = RawBallotMarkings::look_for_continuous_streams(self.atl,|i|self.atl_parties[i],true,gap_policy)?;
         if prefs.len()>=min_atl_prefs_needed { Some(ATL{ parties: prefs, n: 1, ticket_index: None })} else { None }
     }
     pub fn interpret_vote_as_btl(&'a self, min_btl_prefs_needed:usize) -> Option<BTL> {
-        let prefs = RawBallotMarkings::look_for_continuous_streams(self.btl,|i|CandidateIndex(i),true,false);
-        if prefs.len()>=min_btl_prefs_needed { Some(BTL{ candidates: prefs, n: 1 })} else { None }
+        self.interpret_vote_as_btl_with_gap_policy(min_btl_prefs_needed,GapPolicy::TruncateAtGap)
     }
     /// intepret 1 2 4 5 as a valid sequence.
     pub fn interpret_vote_as_btl_allowing_gaps(&'a self, min_btl_prefs_needed:usize) -> Option<BTL> {
-        let prefs = RawBallotMarkings::look_for_continuous_streams(self.btl,|i|CandidateIndex(i),true,true);
+        self.interpret_vote_as_btl_with_gap_policy(min_btl_prefs_needed,GapPolicy::IgnoreGap)
+    }
+    /// Interpret as a BTL vote, with a specified treatment of gaps in the numbering.
+    pub fn interpret_vote_as_btl_with_gap_policy(&'a self, min_btl_prefs_needed:usize,gap_policy:GapPolicy) -> Option<BTL> {
+        let prefs = RawBallotMarkings::look_for_continuous_streams(self.btl,|i|CandidateIndex(i),true,gap_policy)?;
         if prefs.len()>=min_btl_prefs_needed { Some(BTL{ candidates: prefs, n: 1 })} else { None }
     }
 }
@@ -290,7 +399,7 @@ impl UniqueVoteBuilderMultipleTypes {
                 btl.append(&mut new_btl)
             }
         }
-        ElectionData{ metadata, atl, atl_types, atl_transfer_values: vec![], btl, btl_types, btl_transfer_values: vec![], informal }
+        ElectionData{ metadata, atl, atl_types, atl_transfer_values: vec![], btl, btl_types, btl_transfer_values: vec![], atl_annotations: vec![], btl_annotations: vec![], informal }
     }
 }
 
@@ -428,6 +537,8 @@ impl PreferencesComingOutOfOrderHelper {
             btl,
             btl_types,
             btl_transfer_values: vec![],
+            atl_annotations: vec![],
+            btl_annotations: vec![],
             informal: self.informal,
         }
     }
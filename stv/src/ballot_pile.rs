@@ -12,77 +12,19 @@
 use std::cmp::Ordering;
 use crate::ballot_metadata::CandidateIndex;
 use crate::ballot_paper::VoteSource;
-use std::collections::{HashSet, HashMap};
+use std::collections::{HashSet, HashMap, BTreeMap};
 use crate::transfer_value::TransferValue;
 use num::{Zero};
-use std::ops::{AddAssign, Sub, Add, SubAssign};
-use serde::Deserialize;
-use serde::Serialize;
+use std::ops::{AddAssign, Sub};
 use std::hash::Hash;
 use crate::distribution_of_preferences_transcript::{PortionOfReasonBeingDoneThisCount, CountIndex, Transcript};
 use crate::util::{DetectUnique, CollectAll};
-use std::fmt;
 use std::fmt::{Debug, Display};
-use std::iter::Sum;
 use std::str::FromStr;
 use crate::random_util::Randomness;
 use crate::simple_list_of_votes::{ListOfVotes, Vote, VotesWithGivenTransferValue};
 
-/// A number representing a count of pieces of paper.
-/// This is distinct from votes which may be fractional in the presence of weights.
-#[derive(Copy,Clone,Eq, PartialEq,Serialize,Deserialize,Ord, PartialOrd)]
-pub struct BallotPaperCount(pub usize);
-
-impl From<BallotPaperCount> for usize {
-    fn from(value: BallotPaperCount) -> Self {
-        value.0
-    }
-}
-impl From<BallotPaperCount> for isize {
-    fn from(value: BallotPaperCount) -> Self {
-        value.0 as isize // not ideal as overflow is possible if you are running on a 32 bit machine with over 2 billion ballots and you (insanely) want support for negative transfer values. But the overflow is not your biggest problem then.
-    }
-}
-impl AddAssign for BallotPaperCount {
-    fn add_assign(&mut self, rhs: Self) { self.0+=rhs.0; }
-}
-impl SubAssign for BallotPaperCount {
-    fn sub_assign(&mut self, rhs: Self) { self.0-=rhs.0; }
-}
-
-impl Sub for BallotPaperCount {
-    type Output = BallotPaperCount;
-    fn sub(self, rhs: Self) -> Self::Output { BallotPaperCount(self.0-rhs.0) }
-}
-
-impl Add for BallotPaperCount {
-    type Output = BallotPaperCount;
-    fn add(self, rhs: Self) -> Self::Output { BallotPaperCount(self.0+rhs.0) }
-}
-// type alias really, don't want long display
-impl Display for BallotPaperCount {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.0) }
-}
-// type alias really, don't want long display
-impl Debug for BallotPaperCount {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.0) }
-}
-impl Zero for BallotPaperCount {
-    fn zero() -> Self { BallotPaperCount(0) }
-    fn is_zero(&self) -> bool { self.0 == 0 }
-}
-impl FromStr for BallotPaperCount {
-    type Err = <usize as FromStr>::Err;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(BallotPaperCount(s.parse()?))
-    }
-}
-impl Sum for BallotPaperCount {
-    fn sum<I: Iterator<Item=Self>>(iter: I) -> Self {
-        BallotPaperCount(usize::sum(iter.map(|b|b.0)))
-    }
-}
+pub use crate::arithmetic::BallotPaperCount;
 
 /// A vote, resolved into BTL, that is somewhere through being distributed.
 /// Ignore preferences with index less than upto.
@@ -256,6 +198,38 @@ impl <'a> VotesWithSameTransferValue<'a> {
         (the_chosen_ones,the_unchosen_ones)
     }
 
+    /// Choose `num_to_choose` ballots by the Cincinnati method, and return (the chosen ones,the rest).
+    /// The ballots are considered in order (expanding each vote into its `n` ballots), and every `k`th ballot is chosen, where `k` is
+    /// the number of ballots divided by `num_to_choose`, rounded down (but at least 1). If the ballot at a position has no next preference
+    /// in `continuing_candidates` or has already been chosen, the next ballot that can be chosen is taken instead, going back to the start
+    /// when the end is reached. The positions are not affected by this. If there are fewer than `num_to_choose` ballots with a next preference,
+    /// they are all chosen.
+    pub fn choose_every_nth(&self,num_to_choose:BallotPaperCount,continuing_candidates:&HashSet<CandidateIndex>) -> (VotesWithSameTransferValue<'a>,VotesWithSameTransferValue<'a>) {
+        let transferable : Vec<bool> = self.votes.iter().map(|v|v.next(continuing_candidates).is_some()).collect();
+        let num_transferable : usize = self.votes.iter().zip(transferable.iter()).filter(|(_,t)|**t).map(|(v,_)|v.n.0).sum();
+        let to_choose = num_to_choose.0.min(num_transferable);
+        let mut num_chosen_by_vote = vec![0;self.votes.len()];
+        if to_choose>0 {
+            let vote_of_ballot : Vec<usize> = self.votes.iter().enumerate().flat_map(|(i,v)|std::iter::repeat_n(i,v.n.0)).collect();
+            let total = vote_of_ballot.len();
+            let interval = (total/num_to_choose.0).max(1);
+            let mut chosen = vec![false;total];
+            for i in 1..=to_choose {
+                let mut position = (i*interval-1)%total;
+                while chosen[position] || !transferable[vote_of_ballot[position]] { position=(position+1)%total; }
+                chosen[position]=true;
+                num_chosen_by_vote[vote_of_ballot[position]]+=1;
+            }
+        }
+        let mut the_chosen_ones = VotesWithSameTransferValue::default();
+        let mut the_unchosen_ones = VotesWithSameTransferValue::default();
+        for (v,num_chosen) in self.votes.iter().zip(num_chosen_by_vote) {
+            if num_chosen>0 { the_chosen_ones.add_vote(PartiallyDistributedVote{ n: BallotPaperCount(num_chosen), ..*v }); }
+            if v.n.0>num_chosen { the_unchosen_ones.add_vote(PartiallyDistributedVote{ n: BallotPaperCount(v.n.0-num_chosen), ..*v }); }
+        }
+        (the_chosen_ones,the_unchosen_ones)
+    }
+
     /// Take some (non-random) set of ballots and return them. Similar to set_aside but without randomness. Choice has no meaning. Slightly faster than set_aside
     pub fn set_aside_arbitrarily(&self,num_to_set_aside:BallotPaperCount) -> (VotesWithSameTransferValue<'a>,VotesWithSameTransferValue<'a>) {
         assert!(num_to_set_aside<=self.num_ballots);
@@ -314,6 +288,11 @@ impl <'a> DistributedVotes<'a> {
         }
         DistributedVotes{by_candidate, exhausted_votes, exhausted,exhausted_atl}
     }
+
+    /// The number of ballot papers going to each candidate, followed by the number exhausted.
+    pub fn ballot_paper_counts(&self) -> Vec<BallotPaperCount> {
+        self.by_candidate.iter().map(|v|v.num_ballots).chain(std::iter::once(self.exhausted)).collect()
+    }
 /*
     use crate::verify_official_transcript::OracleFromOfficialDOP;
 
@@ -428,12 +407,13 @@ struct LastParcelInfo<Tally> {
 /// These would typically be the votes given to a particular individual.
 pub struct VotesWithMultipleTransferValues<'a,S:HowSplitByCountNumber,Tally> {
     last_parcel : Option<LastParcelInfo<Tally>>, // This is literally the last time add() was called. There may conceivably be multiple in a single count.
-    by_provenance : HashMap<(S::KeyToDivide,TransferValue),(PileProvenance<Tally>,VotesWithSameTransferValue<'a>)>
+    /// A BTreeMap rather than a HashMap so that iteration order, which affects the order of papers when merged, is the same every run.
+    by_provenance : BTreeMap<(S::KeyToDivide,TransferValue),(PileProvenance<Tally>,VotesWithSameTransferValue<'a>)>
 }
 
 impl <'a,S:HowSplitByCountNumber,Tally> Default for VotesWithMultipleTransferValues<'a,S,Tally> {
     fn default() -> Self {
-        VotesWithMultipleTransferValues{ last_parcel: None, by_provenance: HashMap::default() }
+        VotesWithMultipleTransferValues{ last_parcel: None, by_provenance: BTreeMap::default() }
     }
 }
 
@@ -470,6 +450,16 @@ impl <'a,S:HowSplitByCountNumber,Tally:AddAssign+Zero+Clone+Display+FromStr+Part
         });
     }
 
+    /// Like add, but just add a tally with no ballot papers. This does not count as a new last parcel.
+    /// Used for the experimental redistribution of exhausted votes.
+    pub fn add_value_without_ballots(& mut self,transfer_value:TransferValue,count_index:CountIndex,when_tv_created:Option<CountIndex>,tally:Tally) {
+        let key = (S::key(count_index,when_tv_created),transfer_value);
+        let entry = self.by_provenance.entry(key).or_insert_with(||
+            (PileProvenance{ source_counts: Default::default(),when_tv_created,tally:Tally::zero()}, VotesWithSameTransferValue::default()));
+        let prior_state = StateBeforeAddition{votes_len:entry.1.votes.len()};
+        entry.0.add(count_index,when_tv_created,tally,prior_state);
+    }
+
     pub fn get_all_provenance_keys(&self) -> Vec<(S::KeyToDivide,TransferValue)> {
         let mut res: Vec<(S::KeyToDivide,TransferValue)> = vec![];
         for x in self.by_provenance.keys() {
@@ -551,7 +541,7 @@ impl <'a,S:HowSplitByCountNumber,Tally:AddAssign+Zero+Clone+Display+FromStr+Part
     /// Clears this object.
     pub fn extract_all_ballots_separated_by_transfer_value(&'_ mut self) -> Vec<(TransferValue,(Tally,VotesWithSameTransferValue<'a>,PortionOfReasonBeingDoneThisCount))> {
         let mut helpers : HashMap<TransferValue,MergeVotesHelper<Tally>> = HashMap::default();
-        for ((_,tv),(prov,votes)) in self.by_provenance.drain() {
+        for ((_,tv),(prov,votes)) in std::mem::take(&mut self.by_provenance) {
             let helper = helpers.entry(tv.clone()).or_insert_with(||MergeVotesHelper::default());
             helper.add(tv,prov,votes);
         }
@@ -564,7 +554,7 @@ impl <'a,S:HowSplitByCountNumber,Tally:AddAssign+Zero+Clone+Display+FromStr+Part
     /// Sorting will be by  the standard Ord on the key, unless overridden by a custom function.
     pub fn extract_all_ballots_separated_by_key(&'_ mut self,custom_sort:Option<Box<dyn FnMut(&Transcript<Tally>,<S as HowSplitByCountNumber>::KeyToDivide,<S as HowSplitByCountNumber>::KeyToDivide)->Ordering>>,transcript:&Transcript<Tally>) -> Vec<(TransferValue,(Tally,VotesWithSameTransferValue<'a>,PortionOfReasonBeingDoneThisCount))> {
         let mut res = vec![];
-        let mut sorted_by_key : Vec<_> = self.by_provenance.drain().collect(); // not sorted yet
+        let mut sorted_by_key : Vec<_> = std::mem::take(&mut self.by_provenance).into_iter().collect(); // sorted by key, but may need custom sort
         if let Some(mut f) = custom_sort {
             sorted_by_key.sort_by(|((key1,_),_),((key2,_),_)|f(transcript,key1.clone(),key2.clone()));
         } else {
@@ -584,7 +574,7 @@ impl <'a,S:HowSplitByCountNumber,Tally:AddAssign+Zero+Clone+Display+FromStr+Part
     /// Clears this object.
     pub fn extract_all_ballots_ignoring_transfer_value(&'_ mut self) -> (Tally,VotesWithSameTransferValue<'a>,PortionOfReasonBeingDoneThisCount) {
         let mut helper = MergeVotesHelper::default();
-        for ((_,tv),(prov,votes)) in self.by_provenance.drain() {
+        for ((_,tv),(prov,votes)) in std::mem::take(&mut self.by_provenance) {
             helper.add(tv,prov,votes);
         }
         helper.extract()
@@ -0,0 +1,97 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the ballots in an [ElectionData] against the first preference totals published by an electoral commission
+//! for each batch (or polling place) the ballots came from.
+//!
+//! This is a data integrity check, useful for finding ballots that were lost, duplicated or assigned to the wrong batch
+//! when producing a .stv file. The batch each ballot came from is given by a [crate::election_data::BallotAnnotation],
+//! by default with key [BATCH_ANNOTATION_KEY].
+
+use std::collections::BTreeMap;
+use serde::{Serialize,Deserialize};
+use crate::ballot_metadata::CandidateIndex;
+use crate::ballot_pile::BallotPaperCount;
+use crate::election_data::{BallotAnnotation, ElectionData};
+
+/// The annotation key used by [reconcile_against_batch_totals].
+pub const BATCH_ANNOTATION_KEY : &str = "batch";
+
+/// A candidate in a batch whose first preferences in the data differ from the official number.
+#[derive(thiserror::Error,Debug,Clone,Serialize,Deserialize,PartialEq,Eq)]
+#[error("batch {batch} has {actual} first preferences for candidate {candidate} but the official number is {official}")]
+pub struct BatchMismatch {
+    pub batch : String,
+    pub candidate : CandidateIndex,
+    pub actual : BallotPaperCount,
+    pub official : BallotPaperCount,
+}
+
+/// The result of [reconcile_against_batch_totals].
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,Eq)]
+pub struct BatchReconciliation {
+    /// The batches whose first preferences all match the official totals, in sorted order.
+    pub matched : Vec<String>,
+    /// Each candidate in each batch whose first preferences don't match the official totals.
+    /// A batch with official totals but no ballots in the data has all its ballots missing.
+    pub mismatches : Vec<BatchMismatch>,
+    /// The batches in the data that have no official totals, in sorted order.
+    pub not_in_official : Vec<String>,
+    /// The number of formal ballots in the data not annotated with any batch.
+    pub unbatched : BallotPaperCount,
+}
+
+impl BatchReconciliation {
+    /// True if every batch in the data and the official totals match, and every ballot is in some batch.
+    pub fn is_ok(&self) -> bool { self.mismatches.is_empty() && self.not_in_official.is_empty() && self.unbatched.0==0 }
+}
+
+/// Compare the first preferences for each candidate in each batch with `official_batch_totals`, which maps the batch
+/// name (the annotation value) to the official first preferences for each candidate. Above the line votes count as first
+/// preferences for the first candidate of the first party, as is usual in published first preference counts.
+/// Candidates missing from the end of an official list are taken to have no first preferences.
+pub fn reconcile_against_batch_totals(data:&ElectionData,official_batch_totals:&BTreeMap<String,Vec<BallotPaperCount>>) -> BatchReconciliation {
+    reconcile_against_annotation_totals(data,BATCH_ANNOTATION_KEY,official_batch_totals)
+}
+
+/// Like [reconcile_against_batch_totals], but the batches are given by the annotation `key`, e.g. "polling_place".
+pub fn reconcile_against_annotation_totals(data:&ElectionData,key:&str,official_batch_totals:&BTreeMap<String,Vec<BallotPaperCount>>) -> BatchReconciliation {
+    let num_candidates = data.metadata.candidates.len();
+    let atl_first : Vec<Option<CandidateIndex>> = data.atl.iter().map(|v|v.parties.first().and_then(|p|data.metadata.parties.get(p.0)).and_then(|p|p.candidates.first().cloned())).collect();
+    let btl_first : Vec<Option<CandidateIndex>> = data.btl.iter().map(|v|v.candidates.first().cloned()).collect();
+    let mut actual : BTreeMap<&str,Vec<BallotPaperCount>> = BTreeMap::new();
+    fn add<'a>(actual:&mut BTreeMap<&'a str,Vec<BallotPaperCount>>,annotation:&'a BallotAnnotation,first:&[Option<CandidateIndex>],n:impl Fn(usize)->usize,num_candidates:usize) {
+        let tallies = actual.entry(annotation.value.as_str()).or_insert_with(||vec![BallotPaperCount(0);num_candidates]);
+        for i in annotation.range() {
+            if let Some(tally) = first[i].and_then(|c|tallies.get_mut(c.0)) { tally.0+=n(i); }
+        }
+    }
+    let mut atl_batched = vec![false;data.atl.len()];
+    let mut btl_batched = vec![false;data.btl.len()];
+    for a in data.atl_annotations.iter().filter(|a|a.key==key) {
+        add(&mut actual,a,&atl_first,|i|data.atl[i].n,num_candidates);
+        for b in &mut atl_batched[a.range()] { *b=true; }
+    }
+    for a in data.btl_annotations.iter().filter(|a|a.key==key) {
+        add(&mut actual,a,&btl_first,|i|data.btl[i].n,num_candidates);
+        for b in &mut btl_batched[a.range()] { *b=true; }
+    }
+    let unbatched = BallotPaperCount(data.atl.iter().zip(atl_batched).filter(|(_,b)|!b).map(|(v,_)|v.n).sum::<usize>()+data.btl.iter().zip(btl_batched).filter(|(_,b)|!b).map(|(v,_)|v.n).sum::<usize>());
+    let mut res = BatchReconciliation{ matched: vec![], mismatches: vec![], not_in_official: vec![], unbatched };
+    let no_votes = vec![BallotPaperCount(0);num_candidates];
+    for (&batch,_) in actual.iter().filter(|(batch,_)|!official_batch_totals.contains_key(**batch)) { res.not_in_official.push(batch.to_string()); }
+    for (batch,official) in official_batch_totals {
+        let actual = actual.get(batch.as_str()).unwrap_or(&no_votes);
+        let mismatches_before = res.mismatches.len();
+        for c in 0..num_candidates.max(official.len()) {
+            let actual = actual.get(c).cloned().unwrap_or(BallotPaperCount(0));
+            let official = official.get(c).cloned().unwrap_or(BallotPaperCount(0));
+            if actual!=official { res.mismatches.push(BatchMismatch{ batch: batch.clone(), candidate: CandidateIndex(c), actual, official }); }
+        }
+        if res.mismatches.len()==mismatches_before { res.matched.push(batch.clone()); }
+    }
+    res
+}
@@ -0,0 +1,61 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! HYPOTHETICAL modelling of candidate blocs that must be elected together or not at all.
+//!
+//! This is NOT part of STV as described in any legislation; it is purely for coalition analysis.
+//! Each bloc is either "in", with its members protected from exclusion (see [crate::preference_distribution::PreferenceDistributor::set_protected]),
+//! or "out", with its members excluded before the count. Every combination of in and out is counted,
+//! and an outcome is feasible if every bloc ends up with either all or none of its members elected.
+
+use std::collections::HashSet;
+use serde::{Serialize,Deserialize};
+use crate::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use crate::distribution_of_preferences_transcript::Transcript;
+use crate::election_data::ElectionData;
+use crate::preference_distribution::{distribute_preferences_with_options, CountOptions, PreferenceDistributionRules};
+use crate::random_util::Randomness;
+use crate::tie_resolution::TieResolutionsMadeByEC;
+
+/// The result of counting with one combination of blocs in and out.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct BlocOutcome {
+    /// for each bloc, whether it was "in" (protected) or "out" (excluded).
+    pub bloc_in : Vec<bool>,
+    /// The candidates elected.
+    pub elected : Vec<CandidateIndex>,
+    /// Whether every bloc had all or none of its members elected.
+    pub feasible : bool,
+}
+
+/// Count every combination of `blocs` in or out, as described in the module documentation. There are 2^blocs.len() counts.
+/// Other arguments are as for [crate::preference_distribution::distribute_preferences]. Transcripts are not returned, just the elected candidates.
+pub fn count_with_bloc_constraints<Rules:PreferenceDistributionRules>(data:&ElectionData,candidates_to_be_elected:NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,blocs:&[Vec<CandidateIndex>],ec_resolutions:&TieResolutionsMadeByEC,vote_types:Option<&[String]>) -> Vec<BlocOutcome> {
+    (0..1usize<<blocs.len()).map(|combination|{
+        let bloc_in : Vec<bool> = (0..blocs.len()).map(|i|combination&(1<<i)!=0).collect();
+        let transcript = count_one_combination::<Rules>(data,candidates_to_be_elected,excluded_candidates,blocs,&bloc_in,ec_resolutions,vote_types);
+        let feasible = blocs.iter().all(|bloc|{
+            let num_elected = bloc.iter().filter(|c|transcript.elected.contains(c)).count();
+            num_elected==0 || num_elected==bloc.len()
+        });
+        BlocOutcome{ bloc_in, elected: transcript.elected, feasible }
+    }).collect()
+}
+
+/// Like [count_with_bloc_constraints], but only the feasible outcomes.
+pub fn feasible_bloc_outcomes<Rules:PreferenceDistributionRules>(data:&ElectionData,candidates_to_be_elected:NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,blocs:&[Vec<CandidateIndex>],ec_resolutions:&TieResolutionsMadeByEC,vote_types:Option<&[String]>) -> Vec<BlocOutcome> {
+    count_with_bloc_constraints::<Rules>(data,candidates_to_be_elected,excluded_candidates,blocs,ec_resolutions,vote_types).into_iter().filter(|outcome|outcome.feasible).collect()
+}
+
+fn count_one_combination<Rules:PreferenceDistributionRules>(data:&ElectionData,candidates_to_be_elected:NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,blocs:&[Vec<CandidateIndex>],bloc_in:&[bool],ec_resolutions:&TieResolutionsMadeByEC,vote_types:Option<&[String]>) -> Transcript<Rules::Tally> {
+    let mut excluded = excluded_candidates.clone();
+    let mut protected = vec![];
+    for (bloc,&is_in) in blocs.iter().zip(bloc_in) {
+        if is_in { protected.extend(bloc.iter().cloned()) } else { excluded.extend(bloc.iter().cloned()) }
+    }
+    distribute_preferences_with_options::<Rules>(data,candidates_to_be_elected,&excluded,ec_resolutions,&mut Randomness::ReverseDonkeyVote,CountOptions{ vote_types, protected: &protected, ..Default::default() })
+}
@@ -0,0 +1,66 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Work out which continuing candidates are certain to lose, part way through a count.
+//! This is useful when watching a count progress, as an early warning of who is effectively out.
+//!
+//! The test used is the same one that justifies bulk exclusion. Take the lowest few continuing
+//! candidates as a group G. Let S be all the value that could still move between candidates : the tallies
+//! of excluded candidates whose votes have not all been distributed, plus any amount by which any candidate
+//! exceeds the quota. If the total of G plus S is less than both the quota and the tally of the next
+//! lowest continuing candidate, and there would still be enough candidates left without G to fill the remaining vacancies,
+//! then no member of G can ever get a quota, or get ahead of any candidate not in G, and so every member of G
+//! will be excluded before any candidate not in G. Transfers never increase the total value that can move,
+//! as surpluses created by a later election can only come from value that moved there.
+//!
+//! This relies on two properties that hold for all the rules in ConcreteSTV that use unsigned tallies:
+//! tallies of continuing candidates never decrease, and a transfer never creates more value than it removes
+//! (rounding is always down). It is deliberately conservative; a candidate not reported may still be certain to lose.
+
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
+use std::ops::{AddAssign, Sub};
+use std::str::FromStr;
+use num::Zero;
+use crate::ballot_metadata::CandidateIndex;
+use crate::distribution_of_preferences_transcript::{CountIndex, Transcript};
+
+impl <Tally:PartialEq+Clone+Display+FromStr+Debug+Ord+AddAssign+Sub<Output=Tally>+Zero> Transcript<Tally> {
+    /// The continuing candidates at the end of the given count who are provably certain never to be elected, in increasing order of tally.
+    /// See the [module documentation](crate::certain_losers) for the test used.
+    ///
+    /// This only uses counts up to and including `count`, so works on a partial transcript.
+    /// Returns nothing if there is no quota (e.g. IRV), as the number of vacancies is then not recorded.
+    pub fn certain_losers_at_count(&self,count:CountIndex) -> Vec<CandidateIndex> {
+        let Some(quota) = &self.quota else { return vec![] };
+        let counts = &self.counts[..=count.0];
+        let status = &counts[count.0].status.tallies;
+        let elected : HashSet<CandidateIndex> = counts.iter().flat_map(|c|c.elected.iter().map(|e|e.who)).collect();
+        let not_continuing : HashSet<CandidateIndex> = counts.iter().flat_map(|c|c.not_continuing.iter().cloned()).chain(elected.iter().cloned()).collect();
+        let surplus = |tally:&Tally| if *tally>quota.quota { tally.clone()-quota.quota.clone() } else { Tally::zero() };
+        let mut movable = Tally::zero();
+        let mut continuing : Vec<(CandidateIndex,Tally)> = vec![];
+        for (i,tally) in status.candidate.iter().enumerate() {
+            let candidate = CandidateIndex(i);
+            if elected.contains(&candidate) { movable+=surplus(tally); }
+            else if not_continuing.contains(&candidate) { movable+=tally.clone(); }
+            else { movable+=surplus(tally); continuing.push((candidate,tally.clone())); }
+        }
+        if let Some(set_aside) = &status.set_aside { movable+=set_aside.clone(); }
+        continuing.sort_by(|(c1,t1),(c2,t2)|t1.cmp(t2).then(c1.0.cmp(&c2.0)));
+        let remaining_vacancies = quota.vacancies.0.saturating_sub(elected.len());
+        if remaining_vacancies==0 { return continuing.into_iter().map(|(c,_)|c).collect(); }
+        let mut group_plus_movable = movable;
+        let mut certain = 0;
+        for k in 1..continuing.len() {
+            group_plus_movable+=continuing[k-1].1.clone();
+            if continuing.len()-k<remaining_vacancies { break; }
+            if group_plus_movable<continuing[k].1 && group_plus_movable<quota.quota { certain=k; }
+        }
+        continuing.truncate(certain);
+        continuing.into_iter().map(|(c,_)|c).collect()
+    }
+}
@@ -8,9 +8,10 @@
 use crate::preference_distribution::{PreferenceDistributionRules, WhenToDoElectCandidateClauseChecking, TransferValueMethod, SurplusTransferMethod, LastParcelUse};
 use crate::election_data::ElectionData;
 use crate::distribution_of_preferences_transcript::{Transcript, TranscriptWithMetadata};
+use std::collections::HashSet;
 use std::fs::File;
 use std::path::PathBuf;
-use crate::ballot_metadata::ElectionMetadata;
+use crate::ballot_metadata::{CandidateIndex, ElectionMetadata};
 use crate::compare_transcripts::{DifferenceBetweenTranscripts, compare_transcripts};
 use serde::{Serialize,Deserialize};
 use std::fmt::{Debug, Display, Formatter};
@@ -102,6 +103,7 @@ impl CompareRules {
 
                 fn check_elected_if_in_middle_of_surplus_distribution() -> bool { R::check_elected_if_in_middle_of_surplus_distribution() }
                 fn check_elected_if_in_middle_of_exclusion() -> bool { R::check_elected_if_in_middle_of_exclusion() }
+                fn defer_surplus_of_candidate_elected_mid_exclusion() -> bool { R::defer_surplus_of_candidate_elected_mid_exclusion() }
                 fn finish_all_counts_in_elimination_when_all_elected() -> bool { R::finish_all_counts_in_elimination_when_all_elected() }
                 fn finish_all_surplus_distributions_when_all_elected() -> bool { R::finish_all_surplus_distributions_when_all_elected() }
                 fn when_to_check_if_just_two_standing_for_shortcut_election() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::AfterDeterminingWhoToExcludeButBeforeTransferringAnyPapers }
@@ -143,6 +145,7 @@ impl CompareRules {
 
                 fn check_elected_if_in_middle_of_surplus_distribution() -> bool { R::check_elected_if_in_middle_of_surplus_distribution() }
                 fn check_elected_if_in_middle_of_exclusion() -> bool { R::check_elected_if_in_middle_of_exclusion() }
+                fn defer_surplus_of_candidate_elected_mid_exclusion() -> bool { R::defer_surplus_of_candidate_elected_mid_exclusion() }
                 fn when_to_check_if_top_few_have_overwhelming_votes() -> WhenToDoElectCandidateClauseChecking { R::when_to_check_if_top_few_have_overwhelming_votes() }
 
                 fn finish_all_counts_in_elimination_when_all_elected() -> bool { R::finish_all_counts_in_elimination_when_all_elected() }
@@ -231,3 +234,115 @@ impl CompareRulesResults {
         }
     }
 }
+
+/// Whether two sets of rules elect different candidates (ignoring the order of election) on the given data.
+/// Ties are resolved by reverse donkey vote, as in [CompareRules].
+pub fn rules_elect_different_candidates<RulesA:PreferenceDistributionRules,RulesB:PreferenceDistributionRules>(data:&ElectionData) -> bool {
+    let elected = |elected:Vec<CandidateIndex>| elected.into_iter().collect::<HashSet<_>>();
+    elected(data.distribute_preferences::<RulesA>(&mut Randomness::ReverseDonkeyVote).elected)!=elected(data.distribute_preferences::<RulesB>(&mut Randomness::ReverseDonkeyVote).elected)
+}
+
+/// Given a dataset on which two sets of rules elect different candidates, find a small dataset on which they still do,
+/// in the style of delta debugging. This is useful for producing examples like `MultipleExclusionOrdering.stv`.
+///
+/// This greedily tries, keeping each change after which the rules still elect different candidates,
+/// * removing groups of votes, starting with large groups,
+/// * reducing the number of people who voted each way,
+/// * removing the last preference of each vote,
+/// * merging votes with the same preferences,
+///
+/// and repeats until none of these help. The result is minimal in the sense that no single one of these changes can be made,
+/// not necessarily the smallest possible. The candidates and vacancies are unchanged. Vote types and annotations are dropped,
+/// as are the official results.
+///
+/// The data is returned unchanged if the rules do not elect different candidates on it, or if it has votes with
+/// transfer values, as removing votes would mess up the ranges the transfer values apply to.
+pub fn minimize_divergence_dataset<RulesA:PreferenceDistributionRules,RulesB:PreferenceDistributionRules>(data:&ElectionData) -> ElectionData {
+    let diverges = |data:&ElectionData| rules_elect_different_candidates::<RulesA,RulesB>(data);
+    if !(data.atl_transfer_values.is_empty() && data.btl_transfer_values.is_empty() && diverges(data)) { return data.clone(); }
+    let mut best = data.clone();
+    best.atl_types.clear();
+    best.btl_types.clear();
+    best.atl_annotations.clear();
+    best.btl_annotations.clear();
+    best.metadata.results=None;
+    best.metadata.name.modifications.push("minimized".to_string());
+    loop {
+        let mut improved = false;
+        // remove groups of votes, keeping at least one.
+        let mut chunk = (best.atl.len()+best.btl.len())/2;
+        while chunk>0 {
+            let mut start = 0;
+            while start<best.atl.len()+best.btl.len() {
+                let trial = without_votes(&best,start,chunk);
+                if trial.atl.len()+trial.btl.len()>0 && diverges(&trial) { best=trial; improved=true; } else { start+=chunk; }
+            }
+            chunk/=2;
+        }
+        // reduce the number of people who voted each way.
+        for vote in 0..best.atl.len()+best.btl.len() {
+            let mut step = *number_voting(&mut best,vote)/2;
+            while step>0 {
+                let mut trial = best.clone();
+                let n = number_voting(&mut trial,vote);
+                if step>=*n { step=*n-1; continue; }
+                *n-=step;
+                if diverges(&trial) { best=trial; improved=true; } else { step/=2; }
+            }
+        }
+        // remove the last preference.
+        for vote in 0..best.atl.len() {
+            while best.atl[vote].parties.len()>1 {
+                let mut trial = best.clone();
+                trial.atl[vote].parties.pop();
+                if diverges(&trial) { best=trial; improved=true; } else { break; }
+            }
+        }
+        for vote in 0..best.btl.len() {
+            while best.btl[vote].candidates.len()>1 {
+                let mut trial = best.clone();
+                trial.btl[vote].candidates.pop();
+                if diverges(&trial) { best=trial; improved=true; } else { break; }
+            }
+        }
+        // merge votes with the same preferences.
+        let trial = merge_identical_votes(&best);
+        if trial.atl.len()+trial.btl.len()<best.atl.len()+best.btl.len() && diverges(&trial) { best=trial; improved=true; }
+        if !improved { return best; }
+    }
+}
+
+/// The number of people voting for the vote with the given index, counting ATL votes first and then BTL votes.
+fn number_voting(data:&mut ElectionData,vote:usize) -> &mut usize {
+    if vote<data.atl.len() { &mut data.atl[vote].n } else { &mut data.btl[vote-data.atl.len()].n }
+}
+
+/// A copy of the data without the `len` votes starting at `start`, counting ATL votes first and then BTL votes.
+fn without_votes(data:&ElectionData,start:usize,len:usize) -> ElectionData {
+    let mut res = data.clone();
+    let keep = |index:usize| index<start || index>=start+len;
+    let num_atl = data.atl.len();
+    res.atl=data.atl.iter().enumerate().filter(|(i,_)|keep(*i)).map(|(_,v)|v.clone()).collect();
+    res.btl=data.btl.iter().enumerate().filter(|(i,_)|keep(num_atl+*i)).map(|(_,v)|v.clone()).collect();
+    res
+}
+
+/// A copy of the data with votes with the same preferences (and ticket) combined.
+fn merge_identical_votes(data:&ElectionData) -> ElectionData {
+    let mut res = data.clone();
+    res.atl.clear();
+    res.btl.clear();
+    for atl in &data.atl {
+        match res.atl.iter_mut().find(|v|v.parties==atl.parties && v.ticket_index==atl.ticket_index) {
+            Some(existing) => existing.n+=atl.n,
+            None => res.atl.push(atl.clone()),
+        }
+    }
+    for btl in &data.btl {
+        match res.btl.iter_mut().find(|v|v.candidates==btl.candidates) {
+            Some(existing) => existing.n+=btl.n,
+            None => res.btl.push(btl.clone()),
+        }
+    }
+    res
+}
@@ -40,6 +40,70 @@ impl Display for DifferenceBetweenTranscripts {
     }
 }
 
+impl DifferenceBetweenTranscripts {
+    /// A short name for the kind of difference, without the details.
+    pub fn category(&self) -> &'static str {
+        match self {
+            DifferenceBetweenTranscripts::DifferentCandidatesElected(_) => "DifferentCandidatesElected",
+            DifferenceBetweenTranscripts::CandidatesOrderedDifferentWay(_) => "CandidatesOrderedDifferentWay",
+            DifferenceBetweenTranscripts::DifferentValues(_) => "DifferentValues",
+            DifferenceBetweenTranscripts::DifferentNumberOfCounts => "DifferentNumberOfCounts",
+            DifferenceBetweenTranscripts::Same => "Same",
+        }
+    }
+
+    /// How serious the difference is, 0 for [DifferenceBetweenTranscripts::Same] and larger for more serious.
+    /// Consistent with the order of the variants.
+    pub fn seriousness(&self) -> u8 {
+        match self {
+            DifferenceBetweenTranscripts::DifferentCandidatesElected(_) => 4,
+            DifferenceBetweenTranscripts::CandidatesOrderedDifferentWay(_) => 3,
+            DifferenceBetweenTranscripts::DifferentValues(_) => 2,
+            DifferenceBetweenTranscripts::DifferentNumberOfCounts => 1,
+            DifferenceBetweenTranscripts::Same => 0,
+        }
+    }
+}
+
+/// The first place two transcripts diverge, which is more useful than [DifferenceBetweenTranscripts] when debugging why two sets of rules differ.
+/// See [first_detailed_difference].
+#[derive(Clone,Debug,Serialize,Deserialize,Eq, PartialEq)]
+pub struct DetailedDifference {
+    /// The first count with a difference.
+    pub count : CountIndex,
+    /// The first thing in that count that was different.
+    pub kind : DetailedDifferenceKind,
+}
+
+/// What was different in a [DetailedDifference]. Checked in this order.
+#[derive(Clone,Debug,Serialize,Deserialize,Eq, PartialEq)]
+pub enum DetailedDifferenceKind {
+    /// One transcript has fewer counts, and all prior counts were the same.
+    MissingCount,
+    /// Different candidates elected in this count.
+    Elected,
+    /// Different candidates stopped continuing (other than by being elected) in this count.
+    NotContinuing,
+    /// A transfer value was created in one but not the other, or they were different.
+    TransferValue,
+    /// The tally of this candidate at the end of the count.
+    Tally(CandidateIndex),
+    /// The exhausted tally at the end of the count.
+    Exhausted,
+    /// The tally lost to rounding at the end of the count.
+    Rounding,
+    /// The tally set aside at the end of the count.
+    SetAside,
+    /// The tallies are the same but the number of papers differ.
+    Papers,
+}
+
+impl Display for DetailedDifference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f,"{:?} at count {}",self.kind,self.count.0+1)
+    }
+}
+
 #[derive(Clone,Debug,Serialize,Deserialize,Eq, PartialEq)]
 pub struct DifferentCandidateLists {
     pub list1 : Vec<CandidateIndex>,
@@ -86,6 +150,18 @@ impl DeltasInCandidateLists {
 }
 
 
+/// Compare a computed list of elected candidates (in order of election) with the official results in the metadata, if present.
+/// The result will be [DifferenceBetweenTranscripts::Same], [DifferenceBetweenTranscripts::CandidatesOrderedDifferentWay]
+/// or [DifferenceBetweenTranscripts::DifferentCandidatesElected], with list1 being the computed list and list2 the official.
+pub fn compare_elected_with_official(elected:&[CandidateIndex],metadata:&ElectionMetadata) -> Option<DifferenceBetweenTranscripts> {
+    let official = metadata.results.as_ref()?;
+    Some(if elected==official.as_slice() { DifferenceBetweenTranscripts::Same } else {
+        let dcl = DifferentCandidateLists{list1:elected.to_vec(),list2:official.clone()};
+        if elected.len()==official.len() && elected.iter().all(|c|official.contains(c)) { DifferenceBetweenTranscripts::CandidatesOrderedDifferentWay(dcl) }
+        else { DifferenceBetweenTranscripts::DifferentCandidatesElected(dcl) }
+    })
+}
+
 pub fn compare_transcripts<Tally1:PartialEq+Clone+Display+FromStr+Debug+CanConvertToF64PossiblyLossily,Tally2:PartialEq+Clone+Display+FromStr+Debug+CanConvertToF64PossiblyLossily>(transcript1:&Transcript<Tally1>,transcript2:&Transcript<Tally2>) -> DifferenceBetweenTranscripts {
     // first compare who was elected.
     if transcript1.elected!=transcript2.elected { // High priority!
@@ -100,4 +176,32 @@ pub fn compare_transcripts<Tally1:PartialEq+Clone+Display+FromStr+Debug+CanConve
         }
         if transcript1.counts.len()==transcript2.counts.len() { DifferenceBetweenTranscripts::Same } else { DifferenceBetweenTranscripts::DifferentNumberOfCounts }
     }
-}
\ No newline at end of file
+}
+
+/// Find the first difference between two transcripts, if any, regardless of whether the same candidates were elected.
+/// Tallies are compared after conversion to f64, as in [compare_transcripts].
+pub fn first_detailed_difference<Tally1:PartialEq+Clone+Display+FromStr+Debug+CanConvertToF64PossiblyLossily,Tally2:PartialEq+Clone+Display+FromStr+Debug+CanConvertToF64PossiblyLossily>(transcript1:&Transcript<Tally1>,transcript2:&Transcript<Tally2>) -> Option<DetailedDifference> {
+    for count_index in 0..min(transcript1.counts.len(),transcript2.counts.len()) {
+        let count1 = &transcript1.counts[count_index];
+        let count2 = &transcript2.counts[count_index];
+        let tallies1 = &count1.status.tallies;
+        let tallies2 = &count2.status.tallies;
+        let same_f64 = |a:&Tally1,b:&Tally2| a.convert_to_f64()==b.convert_to_f64();
+        let kind = if count1.elected!=count2.elected { Some(DetailedDifferenceKind::Elected) }
+            else if count1.not_continuing!=count2.not_continuing { Some(DetailedDifferenceKind::NotContinuing) }
+            else if count1.created_transfer_value.as_ref().map(|tv|&tv.transfer_value)!=count2.created_transfer_value.as_ref().map(|tv|&tv.transfer_value) { Some(DetailedDifferenceKind::TransferValue) }
+            else if let Some(candidate) = (0..tallies1.candidate.len().max(tallies2.candidate.len())).find(|&i|match (tallies1.candidate.get(i),tallies2.candidate.get(i)) { (Some(a),Some(b)) => !same_f64(a,b), _ => true }) { Some(DetailedDifferenceKind::Tally(CandidateIndex(candidate))) }
+            else if !same_f64(&tallies1.exhausted,&tallies2.exhausted) { Some(DetailedDifferenceKind::Exhausted) }
+            else if tallies1.rounding.convert_f64(|t|t.convert_to_f64())!=tallies2.rounding.convert_f64(|t|t.convert_to_f64()) { Some(DetailedDifferenceKind::Rounding) }
+            else if match (tallies1.set_aside.as_ref(),tallies2.set_aside.as_ref()) { (None,None) => false, (Some(a),Some(b)) => !same_f64(a,b), _ => true } { Some(DetailedDifferenceKind::SetAside) }
+            else if !count1.status.same(&count2.status) { Some(DetailedDifferenceKind::Papers) }
+            else { None };
+        if let Some(kind) = kind { return Some(DetailedDifference{ count: CountIndex(count_index), kind }) }
+    }
+    if transcript1.counts.len()==transcript2.counts.len() { None } else { Some(DetailedDifference{ count: CountIndex(min(transcript1.counts.len(),transcript2.counts.len())), kind: DetailedDifferenceKind::MissingCount }) }
+}
+
+/// Like [compare_transcripts], but also the [first_detailed_difference].
+pub fn compare_transcripts_detailed<Tally1:PartialEq+Clone+Display+FromStr+Debug+CanConvertToF64PossiblyLossily,Tally2:PartialEq+Clone+Display+FromStr+Debug+CanConvertToF64PossiblyLossily>(transcript1:&Transcript<Tally1>,transcript2:&Transcript<Tally2>) -> (DifferenceBetweenTranscripts,Option<DetailedDifference>) {
+    (compare_transcripts(transcript1,transcript2),first_detailed_difference(transcript1,transcript2))
+}
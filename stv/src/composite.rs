@@ -0,0 +1,55 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Count several separate contests off the same ballots.
+//!
+//! Some mixed systems, such as some local government structures, fill some seats by single member wards counted
+//! by IRV, and others by a multi member region counted by STV, where the candidates for each contest appear on the same ballot paper.
+//! Each contest only considers its own candidates : the preferences for other candidates are skipped over,
+//! and a ballot with no preferences for any of a contest's candidates is exhausted in that contest's first count.
+
+use std::collections::HashSet;
+use serde::{Serialize,Deserialize};
+use crate::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use crate::distribution_of_preferences_transcript::Transcript;
+use crate::election_data::ElectionData;
+use crate::preference_distribution::{distribute_preferences, PreferenceDistributionRules};
+use crate::random_util::Randomness;
+
+/// One of the contests in a [CompositeConfig].
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct SubContest {
+    /// A human readable name, e.g. the ward or region.
+    pub name : String,
+    /// The candidates standing in this contest.
+    pub candidates : Vec<CandidateIndex>,
+    /// The number of seats filled by this contest.
+    pub vacancies : NumberOfCandidates,
+}
+
+/// The contests to be counted by [count_composite].
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct CompositeConfig {
+    pub sub_contests : Vec<SubContest>,
+}
+
+/// Count each contest in `config` separately from the same `data`, returning the transcripts in the same order.
+/// Contests filling a single seat are counted with `SingleSeatRules` (typically IRV), the others with `MultiSeatRules` (STV).
+///
+/// Candidates in `data.metadata.excluded` are excluded from every contest. Panics if a contest has more vacancies than candidates.
+pub fn count_composite<MultiSeatRules:PreferenceDistributionRules,SingleSeatRules:PreferenceDistributionRules<Tally=MultiSeatRules::Tally>>(data:&ElectionData,config:&CompositeConfig,randomness:&mut Randomness) -> Vec<Transcript<MultiSeatRules::Tally>> {
+    config.sub_contests.iter().map(|contest|{
+        assert!(contest.vacancies.0<=contest.candidates.len(),"Contest {} has {} vacancies but only {} candidates",contest.name,contest.vacancies,contest.candidates.len());
+        let standing : HashSet<CandidateIndex> = contest.candidates.iter().cloned().collect();
+        let excluded : HashSet<CandidateIndex> = (0..data.metadata.candidates.len()).map(CandidateIndex).filter(|c|!standing.contains(c)).chain(data.metadata.excluded.iter().cloned()).collect();
+        if contest.vacancies.0==1 {
+            distribute_preferences::<SingleSeatRules>(data,contest.vacancies,&excluded,&data.metadata.tie_resolutions,None,false,randomness)
+        } else {
+            distribute_preferences::<MultiSeatRules>(data,contest.vacancies,&excluded,&data.metadata.tie_resolutions,None,false,randomness)
+        }
+    }).collect()
+}
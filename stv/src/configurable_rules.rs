@@ -0,0 +1,234 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Rules chosen at run time from a configuration file, for experimenting with variations without writing a new Rust type.
+//!
+//! [ConfigurableRules] holds the parameters of [PreferenceDistributionRules] as fields, and can be read from
+//! a TOML or JSON file. Fields that have defaults in [PreferenceDistributionRules] can be omitted. For instance
+//! ```toml
+//! name = "MyRules"
+//! decimal_places = 0
+//! use_last_parcel_for_surplus_distribution = "No"
+//! transfer_value_method = "SurplusOverBallots"
+//! surplus_distribution_subdivisions = "JustOneTransferValue"
+//! sort_exclusions_by_transfer_value = true
+//! resolve_ties_elected_one_of_last_two = "None"
+//! resolve_ties_elected_by_quota = "RequireHistoricalCountsToBeAllDifferent"
+//! resolve_ties_elected_all_remaining = "RequireHistoricalCountsToBeAllDifferent"
+//! resolve_ties_choose_lowest_candidate_for_exclusion = "RequireHistoricalCountsToBeAllDifferent"
+//! check_elected_if_in_middle_of_surplus_distribution = true
+//! check_elected_if_in_middle_of_exclusion = true
+//! finish_all_counts_in_elimination_when_all_elected = false
+//! finish_all_surplus_distributions_when_all_elected = false
+//! when_to_check_if_just_two_standing_for_shortcut_election = "AfterCheckingQuotaIfNoUndistributedSurplusExistsAndExclusionNotOngoing"
+//! when_to_check_if_all_remaining_should_get_elected = "AfterCheckingQuotaIfNoUndistributedSurplusExistsAndExclusionNotOngoing"
+//! when_to_check_if_top_few_have_overwhelming_votes = "Never"
+//! ```
+//!
+//! As the methods of [PreferenceDistributionRules] don't take `self`, the configuration is made available to them by
+//! [ConfigurableRules::with], which sets a shared reference to it for the current thread while counting. Code that counts
+//! on other threads (e.g. worker threads) needs to call [ConfigurableRules::with] on each of them, passing on the
+//! [Arc] from [ConfigurableRules::current_shared] if need be. Tallies are [RuntimeDecimal]s with
+//! [ConfigurableRules::decimal_places] decimal places, rounded down when a transfer value is applied.
+//!
+//! Functionality that needs code, such as [WhenToDoElectCandidateClauseChecking::Custom] or the various `munge` functions,
+//! is not available. A configuration using [WhenToDoElectCandidateClauseChecking::Custom] is rejected when it is read.
+//! Votes are not split by count number.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use anyhow::anyhow;
+use num::BigInt;
+use serde::{Deserialize, Serialize};
+use crate::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use crate::ballot_pile::{BallotPaperCount, DoNotSplitByCountNumber};
+use crate::distribution_of_preferences_transcript::Transcript;
+use crate::election_data::ElectionData;
+use crate::preference_distribution::{distribute_preferences_with_options, BigRational, CountOptions, CountNamingMethod, DeferLastTwoStandingShortcut, DeferSurplusDistribution, LastParcelUse, PreferenceDistributionRules, SurplusFractionDenominatorPolicy, SurplusTransferMethod, TransferRounding, TransferValueMethod, WhenToDoElectCandidateClauseChecking};
+use crate::random_util::Randomness;
+use crate::runtime_decimal::RuntimeDecimal;
+use crate::tie_resolution::{MethodOfTieResolution, TieResolutionsMadeByEC};
+use crate::transfer_value::TransferValue;
+
+/// The parameters of a set of rules. See the module documentation.
+/// Each field (other than [Self::decimal_places]) is the value returned by the [PreferenceDistributionRules] function of the same name.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigurableRules {
+    pub name : String,
+    /// The number of decimal places tallies are kept to. 0 for whole votes.
+    #[serde(default)]
+    pub decimal_places : u32,
+    #[serde(default="yes")]
+    pub has_quota : bool,
+    pub use_last_parcel_for_surplus_distribution : LastParcelUse,
+    pub transfer_value_method : TransferValueMethod,
+    pub surplus_distribution_subdivisions : SurplusTransferMethod,
+    pub sort_exclusions_by_transfer_value : bool,
+    pub resolve_ties_elected_one_of_last_two : MethodOfTieResolution,
+    pub resolve_ties_elected_by_quota : MethodOfTieResolution,
+    pub resolve_ties_elected_all_remaining : MethodOfTieResolution,
+    pub resolve_ties_choose_lowest_candidate_for_exclusion : MethodOfTieResolution,
+    pub check_elected_if_in_middle_of_surplus_distribution : bool,
+    pub check_elected_if_in_middle_of_exclusion : bool,
+    #[serde(default="yes")]
+    pub defer_surplus_of_candidate_elected_mid_exclusion : bool,
+    pub finish_all_counts_in_elimination_when_all_elected : bool,
+    pub finish_all_surplus_distributions_when_all_elected : bool,
+    pub when_to_check_if_just_two_standing_for_shortcut_election : WhenToDoElectCandidateClauseChecking,
+    pub when_to_check_if_all_remaining_should_get_elected : WhenToDoElectCandidateClauseChecking,
+    pub when_to_check_if_top_few_have_overwhelming_votes : WhenToDoElectCandidateClauseChecking,
+    #[serde(default)]
+    pub when_checking_if_top_few_have_overwhelming_votes_require_exactly_one : bool,
+    #[serde(default="default_defer_last_two_standing_shortcut")]
+    pub defer_last_two_standing_shortcut : DeferLastTwoStandingShortcut,
+    #[serde(default="default_when_should_surplus_distribution_be_deferred")]
+    pub when_should_surplus_distribution_be_deferred : DeferSurplusDistribution,
+    #[serde(default)]
+    pub batch_exclude_candidates_who_cannot_catch_up : bool,
+    #[serde(default)]
+    pub should_eliminate_multiple_candidates_federal_rule_13a : bool,
+    #[serde(default)]
+    pub count_set_aside_due_to_transfer_value_limit_as_rounding : bool,
+    #[serde(default="default_how_to_name_counts")]
+    pub how_to_name_counts : CountNamingMethod,
+    #[serde(default)]
+    pub should_exhausted_votes_count_for_quota_computation : bool,
+    #[serde(default)]
+    pub major_count_if_someone_elected : bool,
+    #[serde(default="default_surplus_fraction_denominator_policy")]
+    pub surplus_fraction_denominator_policy : SurplusFractionDenominatorPolicy,
+    #[serde(default="yes")]
+    pub elect_highest_first : bool,
+    #[serde(default)]
+    pub recompute_quota_after_each_election : bool,
+    #[serde(default)]
+    pub quota_is_strict_greater : bool,
+    #[serde(default="default_transfer_rounding")]
+    pub transfer_rounding : TransferRounding,
+    #[serde(default)]
+    pub redistribute_exhausted_proportionally : bool,
+    #[serde(default)]
+    pub minimum_transfer_value : Option<TransferValue>,
+}
+
+fn yes() -> bool { true }
+fn default_defer_last_two_standing_shortcut() -> DeferLastTwoStandingShortcut { DeferLastTwoStandingShortcut::Never }
+fn default_when_should_surplus_distribution_be_deferred() -> DeferSurplusDistribution { DeferSurplusDistribution::AlwaysDistributeAllSurplusBeforeAnyExclusions }
+fn default_how_to_name_counts() -> CountNamingMethod { CountNamingMethod::SimpleNumber }
+fn default_surplus_fraction_denominator_policy() -> SurplusFractionDenominatorPolicy { SurplusFractionDenominatorPolicy::ClampToOne }
+fn default_transfer_rounding() -> TransferRounding { TransferRounding::PerCandidateDown }
+
+thread_local! {
+    /// The rules used by [ConfigurableRules] as [PreferenceDistributionRules] on this thread. See [ConfigurableRules::with].
+    static CURRENT_RULES : RefCell<Option<Arc<ConfigurableRules>>> = const { RefCell::new(None) };
+}
+
+/// Restores the previous value of [CURRENT_RULES] when dropped, so [ConfigurableRules::with] can be nested and is panic safe.
+struct RestoreCurrentRules(Option<Arc<ConfigurableRules>>);
+
+/// [ConfigurableRules] was used as [PreferenceDistributionRules] outside [ConfigurableRules::with] on this thread.
+#[derive(thiserror::Error,Debug,Clone,Copy)]
+#[error("ConfigurableRules was used as PreferenceDistributionRules without rules being set on this thread. Count with ConfigurableRules::distribute_preferences, or inside ConfigurableRules::with, which needs to be called on each thread that counts.")]
+pub struct ConfigurableRulesNotSet;
+
+impl Drop for RestoreCurrentRules {
+    fn drop(&mut self) { CURRENT_RULES.with(|c|*c.borrow_mut()=self.0.take()); }
+}
+
+impl ConfigurableRules {
+    /// Read from a TOML file if the name ends in `.toml`, otherwise a JSON file.
+    pub fn load(path:&Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e|anyhow!("Could not read rules configuration {} : {}",path.display(),e))?;
+        if path.extension().map(|e|e.eq_ignore_ascii_case("toml")).unwrap_or(false) { Self::from_toml_str(&text) } else { Self::from_json_str(&text) }
+    }
+
+    pub fn from_toml_str(text:&str) -> anyhow::Result<Self> { toml::from_str::<Self>(text)?.check() }
+
+    pub fn from_json_str(text:&str) -> anyhow::Result<Self> { serde_json::from_str::<Self>(text)?.check() }
+
+    /// Reject settings that can't be honoured without code, rather than counting under different rules.
+    fn check(self) -> anyhow::Result<Self> {
+        for (field,when) in [("when_to_check_if_just_two_standing_for_shortcut_election",self.when_to_check_if_just_two_standing_for_shortcut_election),("when_to_check_if_all_remaining_should_get_elected",self.when_to_check_if_all_remaining_should_get_elected),("when_to_check_if_top_few_have_overwhelming_votes",self.when_to_check_if_top_few_have_overwhelming_votes)] {
+            if when==WhenToDoElectCandidateClauseChecking::Custom { return Err(anyhow!("{} can't be Custom in a rules configuration, as custom checks need code.",field)); }
+        }
+        Ok(self)
+    }
+
+    /// Run `f` with these being the rules used by [ConfigurableRules] as [PreferenceDistributionRules] on this thread.
+    pub fn with<R>(self:&Arc<Self>,f:impl FnOnce()->R) -> R {
+        let _restore = RestoreCurrentRules(CURRENT_RULES.with(|c|c.replace(Some(self.clone()))));
+        f()
+    }
+
+    /// The rules set by [Self::with] on this thread, for instance to pass on to a worker thread.
+    pub fn current_shared() -> Result<Arc<ConfigurableRules>,ConfigurableRulesNotSet> {
+        CURRENT_RULES.with(|c|c.borrow().clone()).ok_or(ConfigurableRulesNotSet)
+    }
+
+    /// Like [distribute_preferences_with_options], using these rules.
+    pub fn distribute_preferences(self:&Arc<Self>,data:&ElectionData,candidates_to_be_elected:NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:&TieResolutionsMadeByEC,randomness:&mut Randomness,options:CountOptions<'_,RuntimeDecimal>) -> Transcript<RuntimeDecimal> {
+        self.with(||distribute_preferences_with_options::<ConfigurableRules>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,options))
+    }
+
+    /// Get something from the rules set by [Self::with]. The methods of [PreferenceDistributionRules] can't return an error,
+    /// so if there are none this panics with the explanation in [ConfigurableRulesNotSet].
+    fn current<R>(f:impl FnOnce(&ConfigurableRules)->R) -> R {
+        CURRENT_RULES.with(|c|match c.borrow().as_ref() {
+            Some(rules) => f(rules),
+            None => panic!("{}",ConfigurableRulesNotSet),
+        })
+    }
+}
+
+impl PreferenceDistributionRules for ConfigurableRules {
+    type Tally = RuntimeDecimal;
+    type SplitByNumber = DoNotSplitByCountNumber;
+
+    fn has_quota() -> bool { Self::current(|r|r.has_quota) }
+    fn use_last_parcel_for_surplus_distribution() -> LastParcelUse { Self::current(|r|r.use_last_parcel_for_surplus_distribution) }
+    fn transfer_value_method() -> TransferValueMethod { Self::current(|r|r.transfer_value_method) }
+    fn convert_tally_to_rational(tally: Self::Tally) -> BigRational { tally.to_rational() }
+    fn convert_rational_to_tally_after_applying_transfer_value(rational: BigRational) -> Self::Tally { RuntimeDecimal::from_rational_rounding_down(rational,Self::current(|r|r.decimal_places)) }
+    fn make_transfer_value(surplus: Self::Tally, ballots: BallotPaperCount) -> TransferValue {
+        let surplus = surplus.to_rational();
+        TransferValue::new(surplus.numer().clone(),surplus.denom()*BigInt::from(ballots.0))
+    }
+    fn use_transfer_value(transfer_value: &TransferValue, ballots: BallotPaperCount) -> Self::Tally { RuntimeDecimal::from_rational_rounding_down(transfer_value.mul(ballots),Self::current(|r|r.decimal_places)) }
+    fn surplus_distribution_subdivisions() -> SurplusTransferMethod { Self::current(|r|r.surplus_distribution_subdivisions) }
+    fn sort_exclusions_by_transfer_value() -> bool { Self::current(|r|r.sort_exclusions_by_transfer_value) }
+    fn resolve_ties_elected_one_of_last_two() -> MethodOfTieResolution { Self::current(|r|r.resolve_ties_elected_one_of_last_two) }
+    fn resolve_ties_elected_by_quota() -> MethodOfTieResolution { Self::current(|r|r.resolve_ties_elected_by_quota) }
+    fn resolve_ties_elected_all_remaining() -> MethodOfTieResolution { Self::current(|r|r.resolve_ties_elected_all_remaining) }
+    fn resolve_ties_choose_lowest_candidate_for_exclusion() -> MethodOfTieResolution { Self::current(|r|r.resolve_ties_choose_lowest_candidate_for_exclusion) }
+    fn check_elected_if_in_middle_of_surplus_distribution() -> bool { Self::current(|r|r.check_elected_if_in_middle_of_surplus_distribution) }
+    fn check_elected_if_in_middle_of_exclusion() -> bool { Self::current(|r|r.check_elected_if_in_middle_of_exclusion) }
+    fn defer_surplus_of_candidate_elected_mid_exclusion() -> bool { Self::current(|r|r.defer_surplus_of_candidate_elected_mid_exclusion) }
+    fn finish_all_counts_in_elimination_when_all_elected() -> bool { Self::current(|r|r.finish_all_counts_in_elimination_when_all_elected) }
+    fn finish_all_surplus_distributions_when_all_elected() -> bool { Self::current(|r|r.finish_all_surplus_distributions_when_all_elected) }
+    fn when_to_check_if_just_two_standing_for_shortcut_election() -> WhenToDoElectCandidateClauseChecking { Self::current(|r|r.when_to_check_if_just_two_standing_for_shortcut_election) }
+    fn when_to_check_if_all_remaining_should_get_elected() -> WhenToDoElectCandidateClauseChecking { Self::current(|r|r.when_to_check_if_all_remaining_should_get_elected) }
+    fn when_to_check_if_top_few_have_overwhelming_votes() -> WhenToDoElectCandidateClauseChecking { Self::current(|r|r.when_to_check_if_top_few_have_overwhelming_votes) }
+    fn when_checking_if_top_few_have_overwhelming_votes_require_exactly_one() -> bool { Self::current(|r|r.when_checking_if_top_few_have_overwhelming_votes_require_exactly_one) }
+    fn defer_last_two_standing_shortcut() -> DeferLastTwoStandingShortcut { Self::current(|r|r.defer_last_two_standing_shortcut) }
+    fn when_should_surplus_distribution_be_deferred() -> DeferSurplusDistribution { Self::current(|r|r.when_should_surplus_distribution_be_deferred) }
+    fn batch_exclude_candidates_who_cannot_catch_up() -> bool { Self::current(|r|r.batch_exclude_candidates_who_cannot_catch_up) }
+    fn should_eliminate_multiple_candidates_federal_rule_13a() -> bool { Self::current(|r|r.should_eliminate_multiple_candidates_federal_rule_13a) }
+    fn count_set_aside_due_to_transfer_value_limit_as_rounding() -> bool { Self::current(|r|r.count_set_aside_due_to_transfer_value_limit_as_rounding) }
+    fn name() -> String { Self::current(|r|r.name.clone()) }
+    fn how_to_name_counts() -> CountNamingMethod { Self::current(|r|r.how_to_name_counts) }
+    fn should_exhausted_votes_count_for_quota_computation() -> bool { Self::current(|r|r.should_exhausted_votes_count_for_quota_computation) }
+    fn major_count_if_someone_elected() -> bool { Self::current(|r|r.major_count_if_someone_elected) }
+    fn surplus_fraction_denominator_policy() -> SurplusFractionDenominatorPolicy { Self::current(|r|r.surplus_fraction_denominator_policy) }
+    fn elect_highest_first() -> bool { Self::current(|r|r.elect_highest_first) }
+    fn recompute_quota_after_each_election() -> bool { Self::current(|r|r.recompute_quota_after_each_election) }
+    fn quota_is_strict_greater() -> bool { Self::current(|r|r.quota_is_strict_greater) }
+    fn transfer_rounding() -> TransferRounding { Self::current(|r|r.transfer_rounding) }
+    fn redistribute_exhausted_proportionally() -> bool { Self::current(|r|r.redistribute_exhausted_proportionally) }
+    fn minimum_transfer_value() -> Option<TransferValue> { Self::current(|r|r.minimum_transfer_value.clone()) }
+}
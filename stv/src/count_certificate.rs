@@ -0,0 +1,125 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A count certificate is a manifest published alongside a count result, recording enough to let a third party
+//! independently reproduce the count and confirm they got the same transcript.
+
+use std::collections::{BTreeMap, HashSet};
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::ballot_metadata::{CandidateIndex, MissingVacancies, StableCandidateId};
+use crate::distribution_of_preferences_transcript::Transcript;
+use crate::election_data::ElectionData;
+use crate::preference_distribution::{distribute_preferences, PreferenceDistributionRules};
+use crate::random_util::Randomness;
+
+/// What is needed to reproduce a count, and hashes of the inputs and output to confirm it was reproduced.
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq,Eq)]
+pub struct CountCertificate {
+    /// SHA-256 hash, in hexadecimal, of the election data in the canonical (JSON) form.
+    pub data_hash : String,
+    /// The name of the rules used, [PreferenceDistributionRules::name].
+    pub rules : String,
+    /// The main options in the rules, to make it obvious if a rules name has been reused for different rules.
+    pub rules_configuration : BTreeMap<String,String>,
+    /// If present, ties not resolved by the rules or the EC were resolved by a PRNG seeded with this. Otherwise by reverse donkey vote.
+    pub seed : Option<u64>,
+    /// The version of ConcreteSTV that produced the count.
+    pub software_version : String,
+    /// The candidates elected, in the order elected.
+    pub elected : Vec<CandidateIndex>,
+    /// The stable ids of the candidates elected, in the same order as elected.
+    pub elected_stable_ids : Vec<StableCandidateId>,
+    /// SHA-256 hash, in hexadecimal, of the transcript in JSON form.
+    pub transcript_hash : String,
+}
+
+/// Hex SHA-256 of the JSON serialization of something.
+fn sha256_of_json<T:Serialize>(value:&T) -> String {
+    let json = serde_json::to_vec(value).expect("Could not serialize");
+    Sha256::digest(&json).iter().map(|b|format!("{:02x}",b)).collect()
+}
+
+/// The main, simple to describe options of some rules.
+pub fn rules_configuration<Rules:PreferenceDistributionRules>() -> BTreeMap<String,String> {
+    let mut res = BTreeMap::new();
+    let mut add = |key:&str,value:String| { res.insert(key.to_string(),value); };
+    add("has_quota",Rules::has_quota().to_string());
+    add("use_last_parcel_for_surplus_distribution",format!("{:?}",Rules::use_last_parcel_for_surplus_distribution()));
+    add("transfer_value_method",format!("{:?}",Rules::transfer_value_method()));
+    add("surplus_distribution_subdivisions",format!("{:?}",Rules::surplus_distribution_subdivisions()));
+    add("sort_exclusions_by_transfer_value",Rules::sort_exclusions_by_transfer_value().to_string());
+    add("resolve_ties_elected_one_of_last_two",format!("{:?}",Rules::resolve_ties_elected_one_of_last_two()));
+    add("resolve_ties_elected_by_quota",format!("{:?}",Rules::resolve_ties_elected_by_quota()));
+    add("resolve_ties_elected_all_remaining",format!("{:?}",Rules::resolve_ties_elected_all_remaining()));
+    add("resolve_ties_choose_lowest_candidate_for_exclusion",format!("{:?}",Rules::resolve_ties_choose_lowest_candidate_for_exclusion()));
+    add("check_elected_if_in_middle_of_surplus_distribution",Rules::check_elected_if_in_middle_of_surplus_distribution().to_string());
+    add("check_elected_if_in_middle_of_exclusion",Rules::check_elected_if_in_middle_of_exclusion().to_string());
+    add("defer_surplus_of_candidate_elected_mid_exclusion",Rules::defer_surplus_of_candidate_elected_mid_exclusion().to_string());
+    add("finish_all_counts_in_elimination_when_all_elected",Rules::finish_all_counts_in_elimination_when_all_elected().to_string());
+    add("finish_all_surplus_distributions_when_all_elected",Rules::finish_all_surplus_distributions_when_all_elected().to_string());
+    add("when_to_check_if_just_two_standing_for_shortcut_election",format!("{:?}",Rules::when_to_check_if_just_two_standing_for_shortcut_election()));
+    add("when_to_check_if_all_remaining_should_get_elected",format!("{:?}",Rules::when_to_check_if_all_remaining_should_get_elected()));
+    add("when_to_check_if_top_few_have_overwhelming_votes",format!("{:?}",Rules::when_to_check_if_top_few_have_overwhelming_votes()));
+    add("defer_last_two_standing_shortcut",format!("{:?}",Rules::defer_last_two_standing_shortcut()));
+    add("when_should_surplus_distribution_be_deferred",format!("{:?}",Rules::when_should_surplus_distribution_be_deferred()));
+    add("should_eliminate_multiple_candidates_federal_rule_13a",Rules::should_eliminate_multiple_candidates_federal_rule_13a().to_string());
+    add("should_exhausted_votes_count_for_quota_computation",Rules::should_exhausted_votes_count_for_quota_computation().to_string());
+    add("surplus_fraction_denominator_policy",format!("{:?}",Rules::surplus_fraction_denominator_policy()));
+    add("transfer_rounding",format!("{:?}",Rules::transfer_rounding()));
+    add("minimum_transfer_value",Rules::minimum_transfer_value().map(|tv|tv.to_string()).unwrap_or_default());
+    res
+}
+
+/// The randomness used for a count with a given seed. Unlike `Randomness::from(Option<u64>)`, the seed value is used.
+fn randomness_for_seed(seed:Option<u64>) -> Randomness {
+    match seed {
+        None => Randomness::ReverseDonkeyVote,
+        Some(seed) => Randomness::PRNG(rand_chacha::ChaCha20Rng::seed_from_u64(seed)),
+    }
+}
+
+impl CountCertificate {
+    /// Make a certificate for a transcript produced by counting `data` with the given rules and seed, using the
+    /// vacancies, excluded candidates and tie resolutions in the metadata, as [Self::count] does.
+    pub fn produce<Rules:PreferenceDistributionRules>(data:&ElectionData,transcript:&Transcript<Rules::Tally>,seed:Option<u64>) -> CountCertificate {
+        let stable_ids = data.metadata.stable_candidate_ids();
+        CountCertificate {
+            data_hash: sha256_of_json(data),
+            rules: Rules::name(),
+            rules_configuration: rules_configuration::<Rules>(),
+            seed,
+            software_version: env!("CARGO_PKG_VERSION").to_string(),
+            elected: transcript.elected.clone(),
+            elected_stable_ids: transcript.elected.iter().map(|c|stable_ids[c.0].clone()).collect(),
+            transcript_hash: sha256_of_json(transcript),
+        }
+    }
+
+    /// Count `data` in the way expected by [Self::produce] and [Self::verify].
+    pub fn count<Rules:PreferenceDistributionRules>(data:&ElectionData,seed:Option<u64>) -> Result<Transcript<Rules::Tally>,MissingVacancies> {
+        let vacancies = data.metadata.vacancies.ok_or(MissingVacancies)?;
+        let excluded : HashSet<CandidateIndex> = data.metadata.excluded.iter().cloned().collect();
+        Ok(distribute_preferences::<Rules>(data,vacancies,&excluded,&data.metadata.tie_resolutions,None,false,&mut randomness_for_seed(seed)))
+    }
+
+    /// Count and produce the certificate.
+    pub fn count_and_produce<Rules:PreferenceDistributionRules>(data:&ElectionData,seed:Option<u64>) -> Result<(Transcript<Rules::Tally>,CountCertificate),MissingVacancies> {
+        let transcript = Self::count::<Rules>(data,seed)?;
+        let certificate = Self::produce::<Rules>(data,&transcript,seed);
+        Ok((transcript,certificate))
+    }
+
+    /// Check that this certificate is for `data` and the given rules, and that recounting gives the same transcript.
+    /// The software version is not checked, but a different version could conceivably produce a slightly different transcript.
+    /// Data without the number of vacancies can't be counted, so never verifies.
+    pub fn verify<Rules:PreferenceDistributionRules>(&self,data:&ElectionData) -> bool {
+        if self.data_hash!=sha256_of_json(data) || self.rules!=Rules::name() || self.rules_configuration!=rules_configuration::<Rules>() { return false; }
+        let Ok(transcript) = Self::count::<Rules>(data,self.seed) else { return false };
+        transcript.elected==self.elected && sha256_of_json(&transcript)==self.transcript_hash
+    }
+}
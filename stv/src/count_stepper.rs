@@ -0,0 +1,179 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Step through a count interactively, for teaching and debugging.
+//!
+//! Commands are read a line at a time, and the results written out, so this can be driven by a person
+//! at a terminal or by a script. See [HELP] for the commands. Each step is one action of the count
+//! ([PreferenceDistributor::step]), that is one surplus distribution or exclusion, which may take several counts.
+
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+use crate::ballot_metadata::{CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use crate::distribution_of_preferences_transcript::{ReasonForCount, SingleCount, Transcript};
+use crate::election_data::ElectionData;
+use crate::preference_distribution::{PreferenceDistributionRules, PreferenceDistributor};
+use crate::random_util::Randomness;
+use crate::tie_resolution::TieResolutionsMadeByEC;
+
+/// The commands understood by [step_interactively].
+pub const HELP : &str = "Commands:
+  next (or n, or an empty line)  do the next surplus distribution or exclusion
+  run (or r)                     do the rest of the count
+  tallies (or t)                 show the current tally of every candidate
+  continuing (or c)              show the continuing candidates, highest tally first, and the quota
+  surpluses (or s)               show the surpluses not yet distributed
+  why X                          explain why candidate X (a number starting from 0, or a name) was elected or excluded
+  help (or h or ?)               show this message
+  quit (or q)                    stop
+";
+
+/// Describe a candidate as number and name.
+fn candidate_name(metadata:&ElectionMetadata,candidate:CandidateIndex) -> String { format!("{} {}",candidate,metadata.candidate(candidate).name) }
+
+fn candidate_names(metadata:&ElectionMetadata,candidates:&[CandidateIndex]) -> String {
+    candidates.iter().map(|&c|candidate_name(metadata,c)).collect::<Vec<_>>().join(", ")
+}
+
+/// Find a candidate given either a number (starting from 0) or a name.
+fn find_candidate(metadata:&ElectionMetadata,s:&str) -> Result<CandidateIndex,String> {
+    match s.trim().parse::<usize>() {
+        Ok(index) if index<metadata.candidates.len() => Ok(CandidateIndex(index)),
+        Ok(index) => Err(format!("No candidate number {}; there are {} candidates",index,metadata.candidates.len())),
+        Err(_) => metadata.find_candidate_by_name(s).map_err(|e|e.to_string()),
+    }
+}
+
+/// The human readable name of a count, with count_index starting from 0.
+fn count_label<Tally:PartialEq+Clone+Display+FromStr>(count_index:usize,count:&SingleCount<Tally>) -> String {
+    count.count_name.clone().unwrap_or_else(||(count_index+1).to_string())
+}
+
+/// A one or more line description of a single count, with count_index starting from 0.
+pub fn describe_count<Tally:PartialEq+Clone+Display+FromStr>(metadata:&ElectionMetadata,count_index:usize,count:&SingleCount<Tally>) -> String {
+    let name = count_label(count_index,count);
+    let what = match &count.reason {
+        ReasonForCount::FirstPreferenceCount => "first preferences".to_string(),
+        ReasonForCount::ExcessDistribution(who) => format!("surplus of {}",candidate_name(metadata,*who)),
+        ReasonForCount::Elimination(who) if count.no_votes_to_transfer => format!("exclusion of {}, with no votes to transfer",candidate_names(metadata,who)),
+        ReasonForCount::Elimination(who) => format!("exclusion of {}",candidate_names(metadata,who)),
+    };
+    let mut res = format!("Count {} : {}",name,what);
+    if let Some(tv) = &count.portion.transfer_value { res.push_str(&format!(" of papers with transfer value {}",tv)); }
+    res.push('\n');
+    if let Some(created) = &count.created_transfer_value {
+        res.push_str(&format!("  Surplus {} distributed at transfer value {}\n",created.surplus,created.transfer_value));
+    }
+    for decision in &count.decisions {
+        res.push_str(&format!("  Tie resolved, least favoured first : {}\n",decision.increasing_favour.iter().map(|c|candidate_names(metadata,c)).collect::<Vec<_>>().join(" < ")));
+    }
+    for elected in &count.elected {
+        res.push_str(&format!("  Elected {} ({:?})\n",candidate_name(metadata,elected.who),elected.why));
+    }
+    res
+}
+
+/// Explain, from the counts done so far, why a candidate was elected or excluded, or say that they are still continuing.
+pub fn explain_candidate<Tally:PartialEq+Clone+Display+FromStr+Debug+Ord>(metadata:&ElectionMetadata,transcript:&Transcript<Tally>,who:CandidateIndex) -> String {
+    let name = candidate_name(metadata,who);
+    let decisions_involving = |count:&SingleCount<Tally>| count.decisions.iter().filter(|d|d.increasing_favour.iter().any(|c|c.contains(&who))).map(|d|format!(" A tie was resolved, least favoured first : {}.",d.increasing_favour.iter().map(|c|candidate_names(metadata,c)).collect::<Vec<_>>().join(" < "))).collect::<String>();
+    for (index,count) in transcript.counts.iter().enumerate() {
+        if let Some(elected) = count.elected.iter().find(|e|e.who==who) {
+            return format!("{} was elected in count {} ({:?}) with a tally of {}.{}",name,count_label(index,count),elected.why,count.status.tallies.candidate[who.0],decisions_involving(count));
+        }
+        if let ReasonForCount::Elimination(excluded) = &count.reason {
+            if excluded.contains(&who) {
+                let Some(previous) = index.checked_sub(1).map(|i|&transcript.counts[i]) else { return format!("{} was excluded in count {}.",name,count_label(index,count)) };
+                // Candidates continuing at the start of this count : not excluded or elected before, and not excluded before the count.
+                let mut continuing : Vec<CandidateIndex> = (0..metadata.candidates.len()).map(CandidateIndex).filter(|c|{
+                    !metadata.excluded.contains(c) && !metadata.withdrawn_before_count.contains(c) && !transcript.counts[0].not_continuing.contains(c) &&
+                    !transcript.counts[..index].iter().any(|earlier|earlier.elected.iter().any(|e|e.who==*c) || matches!(&earlier.reason,ReasonForCount::Elimination(e) if e.contains(c)))
+                }).collect();
+                continuing.sort_by_key(|c|previous.status.tallies.candidate[c.0].clone());
+                let others : Vec<String> = continuing.iter().filter(|c|!excluded.contains(c)).take(3).map(|&c|format!("{} {}",candidate_name(metadata,c),previous.status.tallies.candidate[c.0])).collect();
+                let together = if excluded.len()>1 { format!(" together with {}",candidate_names(metadata,&excluded.iter().cloned().filter(|&c|c!=who).collect::<Vec<_>>())) } else { "".to_string() };
+                let mut res = format!("{} was excluded{} starting in count {}, having a tally of {}, the lowest of the continuing candidates.",name,together,count_label(index,count),previous.status.tallies.candidate[who.0]);
+                if !others.is_empty() { res.push_str(&format!(" The next lowest were {}.",others.join(", "))); }
+                res.push_str(&decisions_involving(previous));
+                res.push_str(&decisions_involving(count));
+                return res;
+            }
+        }
+    }
+    if metadata.excluded.contains(&who) || metadata.withdrawn_before_count.contains(&who) { format!("{} was excluded before the count started.",name) }
+    else { format!("{} has not been elected or excluded.",name) }
+}
+
+/// Count an election, stepping through it one action at a time under control of commands read from `input`, writing results to `output`.
+/// Returns the transcript of the counts done, which is the whole count unless quit early.
+pub fn step_interactively<Rules:PreferenceDistributionRules,R:BufRead,W:Write>(data:&ElectionData,candidates_to_be_elected:NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:&TieResolutionsMadeByEC,randomness:&mut Randomness,input:R,mut output:W) -> std::io::Result<Transcript<Rules::Tally>> {
+    let metadata = &data.metadata;
+    let arena = typed_arena::Arena::<CandidateIndex>::new();
+    let votes = data.resolve_atl_including_weights(&arena,None);
+    let mut work : PreferenceDistributor<'_,Rules> = PreferenceDistributor::new(data,&votes,candidates_to_be_elected,excluded_candidates,ec_resolutions,false,None,randomness,&[],false);
+    work.start();
+    writeln!(output,"Quota {}",work.quota())?;
+    let mut counts_written = 0;
+    let mut finished = false;
+    let write_new_counts = |work:&PreferenceDistributor<'_,Rules>,output:&mut W,counts_written:&mut usize| -> std::io::Result<()> {
+        let counts = &work.transcript().counts;
+        for (index,count) in counts.iter().enumerate().skip(*counts_written) {
+            write!(output,"{}",describe_count(metadata,index,count))?;
+        }
+        *counts_written=counts.len();
+        Ok(())
+    };
+    let do_step = |work:&mut PreferenceDistributor<'_,Rules>,output:&mut W,counts_written:&mut usize,finished:&mut bool| -> std::io::Result<()> {
+        if *finished { return writeln!(output,"The count is over."); }
+        if work.step() { write_new_counts(work,output,counts_written) } else {
+            work.finish();
+            *finished=true;
+            writeln!(output,"The count is over. Elected {}",candidate_names(metadata,&work.transcript().elected))
+        }
+    };
+    write_new_counts(&work,&mut output,&mut counts_written)?;
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        let (command,argument) = line.split_once(' ').map(|(c,a)|(c,a.trim())).unwrap_or((line,""));
+        match command {
+            "" | "n" | "next" => do_step(&mut work,&mut output,&mut counts_written,&mut finished)?,
+            "r" | "run" => {
+                while !finished { do_step(&mut work,&mut output,&mut counts_written,&mut finished)?; }
+            }
+            "t" | "tallies" => {
+                for candidate in metadata.candidate_indices() {
+                    writeln!(output,"{}\t{}",candidate_name(metadata,candidate),work.tally(candidate))?;
+                }
+            }
+            "c" | "continuing" => {
+                writeln!(output,"Quota {}",work.quota())?;
+                for &candidate in work.continuing_candidates_sorted_by_tally().iter().rev() {
+                    writeln!(output,"{}\t{}",candidate_name(metadata,candidate),work.tally(candidate))?;
+                }
+            }
+            "s" | "surpluses" => {
+                let pending = work.pending_surpluses();
+                if pending.is_empty() { writeln!(output,"No undistributed surpluses.")?; }
+                for (candidate,surplus) in pending {
+                    writeln!(output,"{}\t{}",candidate_name(metadata,candidate),surplus)?;
+                }
+            }
+            "why" => match find_candidate(metadata,argument) {
+                Ok(who) => writeln!(output,"{}",explain_candidate(metadata,work.transcript(),who))?,
+                Err(e) => writeln!(output,"{}",e)?,
+            }
+            "h" | "help" | "?" => write!(output,"{}",HELP)?,
+            "q" | "quit" => break,
+            _ => writeln!(output,"Unknown command {}. Type help for a list of commands.",command)?,
+        }
+        output.flush()?;
+    }
+    if !finished { work.finish(); }
+    Ok(work.into_transcript())
+}
@@ -0,0 +1,156 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Compare two versions of the ballot data for a contest, for instance when an electoral commission republishes the ballots,
+//! reporting which groups of identical ballots changed, along with changes to candidates and other metadata.
+//!
+//! Candidates are matched by [crate::ballot_metadata::StableCandidateId] (name and party) rather than by position, so
+//! reordering the candidates does not make every ballot look different. Ballots are consolidated with [ElectionData::distinct_ballots],
+//! so ATL votes are compared with the equivalent BTL preferences.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use serde::{Serialize,Deserialize};
+use crate::ballot_metadata::{CandidateIndex, ElectionMetadata};
+use crate::election_data::ElectionData;
+
+/// A change in the candidates between two versions of the data.
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,Eq)]
+pub enum CandidateChange {
+    /// A candidate only in the new data, with their index in the new data.
+    Added{name:String,new_index:CandidateIndex},
+    /// A candidate only in the old data, with their index in the old data.
+    Removed{name:String,old_index:CandidateIndex},
+    /// A candidate in a different position in the new data.
+    Moved{name:String,old_index:CandidateIndex,new_index:CandidateIndex},
+}
+
+/// A group of identical ballots whose number differs between the old and new data. Zero means not present.
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,Eq)]
+pub struct BallotGroupChange {
+    /// The preferences, using [DataDiff::candidates] indices.
+    pub preferences : Vec<CandidateIndex>,
+    pub old_count : usize,
+    pub new_count : usize,
+}
+
+/// The differences between two versions of the data for a contest. See [ElectionData::diff].
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct DataDiff {
+    /// The names of all candidates in either version: those in the old data in the old order, followed by those only in the new data.
+    /// Candidate indices elsewhere in this structure refer to this list.
+    pub candidates : Vec<String>,
+    pub candidate_changes : Vec<CandidateChange>,
+    /// Human readable descriptions of other changes to the metadata.
+    pub metadata_changes : Vec<String>,
+    /// Groups of ballots only in the new data. old_count is zero.
+    pub added : Vec<BallotGroupChange>,
+    /// Groups of ballots only in the old data. new_count is zero.
+    pub removed : Vec<BallotGroupChange>,
+    /// Groups of ballots in both, but with a different number of ballots.
+    pub changed : Vec<BallotGroupChange>,
+    /// The change (new minus old) in first preferences for each candidate in [Self::candidates].
+    pub first_preference_delta : Vec<isize>,
+    /// The change (new minus old) in the number of informal votes.
+    pub informal_delta : isize,
+}
+
+impl DataDiff {
+    /// True iff nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.candidate_changes.is_empty() && self.metadata_changes.is_empty() && self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty() && self.informal_delta==0
+    }
+    fn names(&self,preferences:&[CandidateIndex]) -> String {
+        preferences.iter().map(|c|self.candidates[c.0].as_str()).collect::<Vec<_>>().join(" > ")
+    }
+}
+
+impl Display for DataDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() { return writeln!(f,"No differences"); }
+        for change in &self.candidate_changes {
+            match change {
+                CandidateChange::Added { name, new_index } => writeln!(f,"Candidate added : {} at position {}",name,new_index)?,
+                CandidateChange::Removed { name, old_index } => writeln!(f,"Candidate removed : {} was at position {}",name,old_index)?,
+                CandidateChange::Moved { name, old_index, new_index } => writeln!(f,"Candidate moved : {} from position {} to {}",name,old_index,new_index)?,
+            }
+        }
+        for change in &self.metadata_changes { writeln!(f,"{}",change)?; }
+        for (heading,groups) in [("Added",&self.added),("Removed",&self.removed),("Changed",&self.changed)] {
+            if !groups.is_empty() { writeln!(f,"{} ballot groups : {}",heading,groups.len())?; }
+            for group in groups { writeln!(f,"  {} -> {} : {}",group.old_count,group.new_count,self.names(&group.preferences))?; }
+        }
+        for (candidate,&delta) in self.first_preference_delta.iter().enumerate() {
+            if delta!=0 { writeln!(f,"First preferences {} : {:+}",self.candidates[candidate],delta)?; }
+        }
+        if self.informal_delta!=0 { writeln!(f,"Informal : {:+}",self.informal_delta)?; }
+        Ok(())
+    }
+}
+
+impl ElectionData {
+    /// Find the differences between this (old) data and some other (new) data for the same contest.
+    pub fn diff(&self,new:&ElectionData) -> DataDiff {
+        let old_ids = self.metadata.stable_candidate_ids();
+        let new_ids = new.metadata.stable_candidate_ids();
+        let old_lookup = self.metadata.get_stable_candidate_id_lookup();
+        let mut candidates : Vec<String> = self.metadata.candidates.iter().map(|c|c.name.clone()).collect();
+        let mut candidate_changes = vec![];
+        // new_to_combined[i] is the index in candidates of new candidate i.
+        let mut new_to_combined = vec![];
+        for (i,id) in new_ids.iter().enumerate() {
+            let name = new.metadata.candidates[i].name.clone();
+            if let Some(&old_index) = old_lookup.get(id) {
+                if old_index.0!=i { candidate_changes.push(CandidateChange::Moved{name,old_index,new_index:CandidateIndex(i)}); }
+                new_to_combined.push(old_index);
+            } else {
+                candidate_changes.push(CandidateChange::Added{name:name.clone(),new_index:CandidateIndex(i)});
+                new_to_combined.push(CandidateIndex(candidates.len()));
+                candidates.push(name);
+            }
+        }
+        for (i,id) in old_ids.iter().enumerate() {
+            if !new_ids.contains(id) { candidate_changes.push(CandidateChange::Removed{name:self.metadata.candidates[i].name.clone(),old_index:CandidateIndex(i)}); }
+        }
+        let mut metadata_changes = vec![];
+        let mut compare = |what:&str,old:String,new:String| { if old!=new { metadata_changes.push(format!("{} changed from {} to {}",what,old,new)); } };
+        compare("Name",self.metadata.name.human_readable_name(),new.metadata.name.human_readable_name());
+        compare("Vacancies",format!("{:?}",self.metadata.vacancies),format!("{:?}",new.metadata.vacancies));
+        compare("Number of parties",self.metadata.parties.len().to_string(),new.metadata.parties.len().to_string());
+        let names = |metadata:&ElectionMetadata,list:&[CandidateIndex]| format!("[{}]",list.iter().map(|&c|metadata.candidate(c).name.as_str()).collect::<Vec<_>>().join(", "));
+        compare("Excluded",names(&self.metadata,&self.metadata.excluded),names(&new.metadata,&new.metadata.excluded));
+        compare("Withdrawn",names(&self.metadata,&self.metadata.withdrawn_before_count),names(&new.metadata,&new.metadata.withdrawn_before_count));
+        compare("Official results",self.metadata.results.as_ref().map(|r|names(&self.metadata,r)).unwrap_or_default(),new.metadata.results.as_ref().map(|r|names(&new.metadata,r)).unwrap_or_default());
+        // compare ballots
+        let mut old_groups : HashMap<Vec<CandidateIndex>,usize> = self.distinct_ballots().into_iter().collect();
+        let mut added = vec![];
+        let mut changed = vec![];
+        let mut first_preference_delta = vec![0isize;candidates.len()];
+        for (preferences,n) in &old_groups { if let Some(first) = preferences.first() { first_preference_delta[first.0]-=*n as isize; } }
+        for (preferences,new_count) in new.distinct_ballots() {
+            let preferences : Vec<CandidateIndex> = preferences.iter().map(|c|new_to_combined[c.0]).collect();
+            if let Some(first) = preferences.first() { first_preference_delta[first.0]+=new_count as isize; }
+            match old_groups.remove(&preferences) {
+                None => added.push(BallotGroupChange{preferences,old_count:0,new_count}),
+                Some(old_count) if old_count!=new_count => changed.push(BallotGroupChange{preferences,old_count,new_count}),
+                _ => {}
+            }
+        }
+        let mut removed : Vec<BallotGroupChange> = old_groups.into_iter().map(|(preferences,old_count)|BallotGroupChange{preferences,old_count,new_count:0}).collect();
+        for groups in [&mut added,&mut removed,&mut changed] { groups.sort_by(|a,b|a.preferences.iter().map(|c|c.0).cmp(b.preferences.iter().map(|c|c.0))); }
+        DataDiff{
+            candidates,
+            candidate_changes,
+            metadata_changes,
+            added,
+            removed,
+            changed,
+            first_preference_delta,
+            informal_delta: new.informal as isize-self.informal as isize,
+        }
+    }
+}
@@ -13,12 +13,12 @@ use crate::ballot_metadata::{CandidateIndex, ElectionMetadata, NumberOfCandidate
 use crate::transfer_value::{TransferValue, StringSerializedRational};
 use serde::{Serialize,Deserialize};
 use std::fmt::{Debug, Display, Formatter};
-use crate::preference_distribution::TransferValueMethod;
+use crate::preference_distribution::{SurplusFractionDenominatorPolicy, TransferValueMethod};
 use crate::signed_version::SignedVersion;
 use std::str::FromStr;
 use crate::official_dop_transcript::CanConvertToF64PossiblyLossily;
 use crate::simple_list_of_votes::ListOfVotes;
-use crate::tie_resolution::TieResolutionExplicitDecision;
+use crate::tie_resolution::{TieResolutionExplicitDecision, UnresolvedTie};
 
 
 /// The index of a count. 0 means the first. This is different from the human readable
@@ -257,6 +257,29 @@ pub struct TransferValueCreation<Tally> {
     pub continuing_ballots : BallotPaperCount,
     pub transfer_value : TransferValue,
     pub source : TransferValueMethod,
+    /// If the denominator of the transfer value or surplus fraction was zero or negative, how this was dealt with.
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    pub denominator_policy_applied : Option<SurplusFractionDenominatorPolicy>,
+}
+
+/// Some of the ballot papers in a parcel transferred in an exclusion, all with the same first preference. See [SingleCount::parcel_sources].
+#[derive(Clone,Serialize,Deserialize,Debug,PartialEq)]
+pub struct ParcelSource {
+    /// The first preference marked on the ballot papers (for above the line votes, the first candidate of the first party).
+    pub first_preference : CandidateIndex,
+    pub papers : BallotPaperCount,
+}
+
+/// The working used to decide on a bulk exclusion under Commonwealth Electoral Act 1918 section 273 subsection 13A.
+/// See [crate::preference_distribution::PreferenceDistributor::find_candidates_for_multiple_elimination_federal_rule_13a] for definitions.
+#[derive(Clone,Serialize,Deserialize,Debug)]
+pub struct BulkExclusionNotionalVotes<Tally> {
+    /// The shortfall of the continuing candidate standing highest in the poll.
+    pub leading_shortfall : Tally,
+    /// The sum of the shortfalls of the leading candidates, one per remaining unfilled vacancy.
+    pub vacancy_shortfall : Tally,
+    /// The notional votes of each continuing candidate, lowest standing in the poll first.
+    pub notional_votes : Vec<(CandidateIndex,Tally)>,
 }
 
 /// Sometimes the Electoral Commission needs to make a decision, such as tie resolution.
@@ -294,14 +317,50 @@ pub struct SingleCount<Tally:PartialEq+Clone+Display+FromStr> {
     pub created_transfer_value : Option<TransferValueCreation<Tally>>,
     /// the decisions made by the EC (possibly randomly)
     pub decisions : Vec<TieResolutionExplicitDecision>,
-    /// if there are any set aside for quota votes on this distribution (at time of writing only used for old NSW)
+    /// For a surplus distribution, the ballot papers that stay with the elected candidate as part of their quota, by the candidate
+    /// (or exhausted) they would otherwise go to. For the NSW random method these are actual papers; for methods that transfer all
+    /// papers at a reduced value, the equivalent number of whole papers. See [SingleCount::papers_retained_for_quota].
     #[serde(skip_serializing_if = "Option::is_none",default)]
     pub set_aside_for_quota: Option<PerCandidate<BallotPaperCount>>,
+    /// if this count starts a bulk exclusion under federal rule 13A, the notional votes used to decide it.
+    #[serde(skip_serializing_if = "Option::is_none",default="Option::default")]
+    pub bulk_exclusion : Option<BulkExclusionNotionalVotes<Tally>>,
+    /// If the quota for the remaining vacancies was recomputed at the end of this count, the new quota.
+    /// See [crate::preference_distribution::PreferenceDistributionRules::recompute_quota_after_each_election].
+    #[serde(skip_serializing_if = "Option::is_none",default="Option::default")]
+    pub new_quota : Option<Tally>,
+    /// If this count is an exclusion, the ballot papers in the parcel transferred, broken down by first preference, in candidate order.
+    /// Only present if the list of votes is included in the transcript.
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    pub parcel_sources : Option<Vec<ParcelSource>>,
     /// status at end of count.
     pub status : EndCountStatus<Tally>,
     /// A special name for the count, if not 1,2,3,... Mainly used so that each exclusion or surplus distribution is a single "major" count with possibly minor counts included.
     #[serde(skip_serializing_if = "Option::is_none",default)]
     pub count_name : Option<String>,
+    /// True if this is the exclusion of one or more candidates with no votes, so nothing was transferred, and consecutive such
+    /// counts have been merged into this one. Only set by [Transcript::merge_vacuous_exclusions].
+    #[serde(skip_serializing_if = "std::ops::Not::not",default)]
+    pub no_votes_to_transfer : bool,
+}
+
+impl <Tally:PartialEq+Clone+Display+FromStr> SingleCount<Tally> {
+    /// The total number of ballot papers retained by the elected candidate in a surplus distribution, the figure the AEC shows
+    /// as the papers set aside for quota. None if this count is not a surplus distribution (or the transcript predates this being recorded).
+    pub fn papers_retained_for_quota(&self) -> Option<BallotPaperCount> {
+        self.set_aside_for_quota.as_ref().map(|s|s.candidate.iter().fold(s.exhausted,|a,&b|a+b))
+    }
+}
+
+impl <Tally:PartialEq+Clone+Display+FromStr+CanConvertToF64PossiblyLossily> SingleCount<Tally> {
+    /// The candidate's tally divided by the number of ballot papers they hold at the end of this count.
+    /// This is 1 if every paper is worth a whole vote, and less if some came with a transfer value less than 1.
+    /// None if the candidate holds no papers, or the per candidate paper counts were not kept for this count
+    /// (see [crate::preference_distribution::PreferenceDistributor::set_retain_full_count_status]).
+    pub fn paper_vote_ratio(&self,candidate:CandidateIndex) -> Option<f64> {
+        let papers = self.status.papers.candidate.get(candidate.0)?.0;
+        if papers==0 { None } else { Some(self.status.tallies.candidate[candidate.0].convert_to_f64()/papers as f64) }
+    }
 }
 
 #[derive(Clone,Serialize,Deserialize,Debug)]
@@ -325,6 +384,31 @@ pub struct Transcript<Tally:PartialEq+Clone+Display+FromStr+Debug> {
     pub quota : Option<QuotaInfo<Tally>>,
     pub counts : Vec<SingleCount<Tally>>,
     pub elected : Vec<CandidateIndex>,
+    /// True if there were no more candidates than vacancies at the start of the count, so all the candidates were elected on the first count.
+    #[serde(skip_serializing_if = "std::ops::Not::not",default)]
+    pub undersubscribed : bool,
+    /// If random tie resolution was seeded from a public randomness beacon (see [crate::random_util::Randomness::from_beacon]), the beacon value in hexadecimal, so the count can be checked.
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    pub randomness_beacon : Option<String>,
+    /// If the count was stopped before it finished, why. The last count is the one in which it stopped.
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    pub stopped : Option<CountStopped>,
+}
+
+/// Why a count was stopped before it finished, either as the rules did not say how to continue, or as it was cancelled.
+#[derive(thiserror::Error,Debug,Clone,Serialize,Deserialize,PartialEq,Eq)]
+pub enum CountStopped {
+    /// The denominator of the transfer value or surplus fraction for distributing the candidate's surplus was zero or negative,
+    /// and the rules use [SurplusFractionDenominatorPolicy::Error]. The surplus was set aside rather than transferred.
+    #[error("The transfer value denominator for the surplus of candidate {candidate} in count {count} (starting from 0) is zero or negative, which these rules treat as an error.")]
+    SurplusFractionDenominator{count:CountIndex,candidate:CandidateIndex},
+    /// A tie the legislation provides no procedure for was not resolved by the supplied tie resolutions, in a strict count.
+    /// See [crate::preference_distribution::distribute_preferences_strict].
+    #[error(transparent)]
+    UnresolvedTie(UnresolvedTie),
+    /// The count was cancelled, via [crate::preference_distribution::PreferenceDistributor::set_cancel], before it finished. All the counts in the transcript are complete.
+    #[error("The count was cancelled before it finished.")]
+    Cancelled,
 }
 
 fn produce_none<T>() -> Option<T> { None }
@@ -333,6 +417,65 @@ impl <Tally:PartialEq+Clone+Display+FromStr+Debug> Transcript<Tally> {
     pub fn count(&self,index:CountIndex) -> &SingleCount<Tally> {
         &self.counts[index.0]
     }
+
+    /// Whether the given count is a vacuous exclusion : the exclusion of candidates who had no votes, so nothing changed.
+    /// Such counts are made just to record the exclusion.
+    pub fn is_vacuous_exclusion(&self,index:CountIndex) -> bool {
+        let count = &self.counts[index.0];
+        index.0>0 && count.reason.is_elimination() && count.reason_completed && count.portion.transfer_value.is_none()
+            && count.portion.papers_came_from_counts.is_empty() && count.created_transfer_value.is_none()
+            && count.status.tallies==self.counts[index.0-1].status.tallies
+    }
+
+    /// Merge each run of consecutive vacuous exclusions (see [Self::is_vacuous_exclusion]) into a single count excluding all
+    /// those candidates, marked with [SingleCount::no_votes_to_transfer]. This makes transcripts of contests with many
+    /// candidates with no votes easier to read. A run is broken by a candidate being elected, so the order of election is unchanged.
+    ///
+    /// If any counts are merged, later counts are renumbered, so every count is given a [SingleCount::count_name] (if it
+    /// does not already have one) of its number in the original transcript, e.g. `5-7` for counts 5, 6 and 7 merged,
+    /// and references to other counts are updated.
+    pub fn merge_vacuous_exclusions(&mut self) {
+        let vacuous : Vec<bool> = (0..self.counts.len()).map(|i|self.is_vacuous_exclusion(CountIndex(i))).collect();
+        let original_len = self.counts.len();
+        let mut new_index : Vec<CountIndex> = vec![]; // new_index[original count index]
+        let mut names : Vec<(String,String)> = vec![]; // first and last original name of each new count.
+        let mut merged : Vec<SingleCount<Tally>> = vec![];
+        for (index,mut count) in std::mem::take(&mut self.counts).into_iter().enumerate() {
+            let name = count.count_name.clone().unwrap_or_else(||(index+1).to_string());
+            if vacuous[index] {
+                if let Some(previous) = merged.last_mut().filter(|previous|previous.no_votes_to_transfer && previous.elected.is_empty()) {
+                    if let (ReasonForCount::Elimination(so_far),ReasonForCount::Elimination(more)) = (&mut previous.reason,count.reason) { so_far.extend(more); }
+                    previous.not_continuing.append(&mut count.not_continuing);
+                    previous.decisions.append(&mut count.decisions);
+                    previous.elected=count.elected;
+                    previous.status=count.status;
+                    new_index.push(CountIndex(merged.len()-1));
+                    names.last_mut().unwrap().1=name;
+                    continue;
+                }
+                count.no_votes_to_transfer=true;
+            }
+            new_index.push(CountIndex(merged.len()));
+            names.push((name.clone(),name));
+            merged.push(count);
+        }
+        if merged.len()<original_len {
+            for (count,(first,last)) in merged.iter_mut().zip(names) {
+                if count.count_name.is_none() || first!=last { count.count_name=Some(if first==last { first } else { format!("{}-{}",first,last) }); }
+                count.portion.when_tv_created=count.portion.when_tv_created.map(|c|new_index[c.0]);
+                for c in &mut count.portion.papers_came_from_counts { *c=new_index[c.0]; }
+                count.portion.papers_came_from_counts.dedup();
+            }
+        }
+        self.counts=merged;
+    }
+    /// Make the tie decisions in this transcript also reference [crate::ballot_metadata::StableCandidateId]s, as of the given metadata,
+    /// so they remain meaningful if they are reused with metadata in which the candidates are in a different order.
+    pub fn add_stable_candidate_ids(&mut self,metadata:&ElectionMetadata) {
+        for count in &mut self.counts {
+            for decision in &mut count.decisions { decision.add_stable_candidate_ids(metadata); }
+        }
+    }
 }
 
 #[derive(Clone,Serialize,Deserialize,Debug)]
@@ -341,4 +484,11 @@ pub struct TranscriptWithMetadata<Tally:PartialEq+Clone+Display+FromStr+Debug> {
     pub transcript : Transcript<Tally>,
 }
 
+impl <Tally:PartialEq+Clone+Display+FromStr+Debug> TranscriptWithMetadata<Tally> {
+    /// See [Transcript::add_stable_candidate_ids].
+    pub fn add_stable_candidate_ids(&mut self) {
+        self.transcript.add_stable_candidate_ids(&self.metadata);
+    }
+}
+
 
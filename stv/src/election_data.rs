@@ -5,7 +5,7 @@
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use crate::ballot_metadata::{ElectionMetadata, CandidateIndex};
 use crate::ballot_paper::{ATL, BTL, VoteSource};
 use crate::ballot_pile::{PartiallyDistributedVote};
@@ -17,6 +17,7 @@ use crate::distribution_of_preferences_transcript::Transcript;
 use crate::preference_distribution::{BigRational, distribute_preferences, PreferenceDistributionRules};
 use crate::random_util::Randomness;
 use crate::transfer_value::TransferValue;
+use crate::file_format::save_with_version;
 
 /*
 /// Complete list of raw ballot markings.
@@ -26,7 +27,7 @@ pub struct RawElectionData {
 }*/
 
 /// Formal votes for the election.
-#[derive(Debug,Serialize,Deserialize,Clone)]
+#[derive(Debug,Serialize,Deserialize,Clone,Default)]
 pub struct ElectionData {
     pub metadata : ElectionMetadata,
     pub atl : Vec<ATL>,
@@ -39,6 +40,12 @@ pub struct ElectionData {
     pub btl_types : Vec<VoteTypeSpecification>,
     #[serde(skip_serializing_if = "Vec::is_empty",default)]
     pub btl_transfer_values : Vec<VoteValueSpecification>,
+    /// Optional additional information about ATL votes, such as polling place. See [BallotAnnotation].
+    #[serde(skip_serializing_if = "Vec::is_empty",default)]
+    pub atl_annotations : Vec<BallotAnnotation>,
+    /// Optional additional information about BTL votes, such as polling place. See [BallotAnnotation].
+    #[serde(skip_serializing_if = "Vec::is_empty",default)]
+    pub btl_annotations : Vec<BallotAnnotation>,
     /// number of informal votes
     pub informal : usize,
 }
@@ -68,6 +75,24 @@ pub struct VoteValueSpecification {
     pub last_index_exclusive : usize,
 }
 
+/// Additional information about some votes beyond their type, such as the polling place or batch they came from,
+/// for finer grained statistics. Like [VoteTypeSpecification], this annotates a range of indices of the existing ATL or BTL votes,
+/// but there may be any number of keys (e.g. "polling_place", "batch"), and ranges for different keys may overlap.
+/// These are not used in counting.
+#[derive(Debug,Serialize,Deserialize,Clone,PartialEq,Eq)]
+pub struct BallotAnnotation {
+    /// what sort of information this is, e.g. "polling_place".
+    pub key : String,
+    /// the information, e.g. "Springfield North".
+    pub value : String,
+    pub first_index_inclusive : usize,
+    pub last_index_exclusive : usize,
+}
+
+impl BallotAnnotation {
+    pub fn range(&self) -> Range<usize> { self.first_index_inclusive..self.last_index_exclusive }
+}
+
 impl VoteValueSpecification {
     pub fn range(&self) -> Range<usize> { self.first_index_inclusive..self.last_index_exclusive }
 }
@@ -141,6 +166,42 @@ impl ElectionData {
     pub fn num_votes(&self) -> usize {
         self.num_atl()+self.num_btl()
     }
+    /// Give the BTL vote `self.btl[index]` a starting weight (value per ballot paper) of `value`,
+    /// instead of the usual 1. This is stored in [Self::btl_transfer_values], which is rebuilt
+    /// to cover every BTL vote, with adjacent votes of the same weight merged into one range.
+    /// If every BTL vote ends up with weight 1, [Self::btl_transfer_values] is left empty.
+    ///
+    /// The first preference count and the quota then use the value of the votes rather than the number of papers.
+    /// Any BTL vote not covered by an existing range is treated as having weight 1.
+    pub fn with_ballot_weight(mut self,index:usize,value:TransferValue) -> Self {
+        self.btl_transfer_values = Self::set_weight(&self.btl_transfer_values,self.btl.len(),index,value);
+        self
+    }
+
+    /// Like [Self::with_ballot_weight], but for the ATL vote `self.atl[index]`.
+    pub fn with_atl_ballot_weight(mut self,index:usize,value:TransferValue) -> Self {
+        self.atl_transfer_values = Self::set_weight(&self.atl_transfer_values,self.atl.len(),index,value);
+        self
+    }
+
+    fn set_weight(specs:&[VoteValueSpecification],len:usize,index:usize,value:TransferValue) -> Vec<VoteValueSpecification> {
+        assert!(index<len,"Vote index {} out of range, only {} votes",index,len);
+        let mut weights = vec![TransferValue::one();len];
+        for spec in specs {
+            for w in &mut weights[spec.first_index_inclusive.min(len)..spec.last_index_exclusive.min(len)] { *w=spec.value.clone(); }
+        }
+        weights[index]=value;
+        if weights.iter().all(|w|*w==TransferValue::one()) { return vec![]; }
+        let mut res : Vec<VoteValueSpecification> = vec![];
+        for (i,w) in weights.into_iter().enumerate() {
+            match res.last_mut() {
+                Some(last) if last.value==w => { last.last_index_exclusive=i+1; }
+                _ => res.push(VoteValueSpecification{ value: w, first_index_inclusive: i, last_index_exclusive: i+1 }),
+            }
+        }
+        res
+    }
+
     /// Get a list of all votes with ATL votes converted to the corresponding BTL equivalent.
     /// Requires an arena to hold interpreted preference lists. This can be allocated by
     /// If vote_types is None, use all votes.
@@ -222,6 +283,25 @@ impl ElectionData {
         res
     }
 
+    /// Get every distinct preference sequence, with the number of ballots expressing it.
+    /// ATL votes are converted to the equivalent BTL preferences (using tickets where applicable), and merged
+    /// with identical BTL votes. Vote weights ([Self::btl_transfer_values] and [Self::atl_transfer_values]) are
+    /// ignored; the weight is just the number of ballot papers, so the weights sum to [Self::num_votes].
+    ///
+    /// The result is sorted by decreasing weight, ties broken by the sequence.
+    pub fn distinct_ballots(&self) -> Vec<(Vec<CandidateIndex>,usize)> {
+        let mut weights : HashMap<Vec<CandidateIndex>,usize> = HashMap::new();
+        for a in &self.atl {
+            *weights.entry(a.resolve_to_candidates(&self.metadata)).or_insert(0)+=a.n;
+        }
+        for b in &self.btl {
+            if let Some(w) = weights.get_mut(&b.candidates) { *w+=b.n; } else { weights.insert(b.candidates.clone(),b.n); }
+        }
+        let mut res : Vec<(Vec<CandidateIndex>,usize)> = weights.into_iter().collect();
+        res.sort_by(|(prefs1,n1),(prefs2,n2)|n2.cmp(n1).then_with(||prefs1.iter().map(|c|c.0).cmp(prefs2.iter().map(|c|c.0))));
+        res
+    }
+
     pub fn print_summary(&self) {
         println!("Summary for {}",self.metadata.name.human_readable_name());
         println!("{} formal votes, {} informal",self.num_votes(),self.informal);
@@ -250,14 +330,69 @@ impl ElectionData {
         }
     }
 
+    /// The distinct values for a given annotation key, such as all the polling places, in sorted order.
+    pub fn annotation_values(&self,key:&str) -> Vec<&str> {
+        let mut res : Vec<&str> = self.atl_annotations.iter().chain(self.btl_annotations.iter()).filter(|a|a.key==key).map(|a|a.value.as_str()).collect::<HashSet<&str>>().into_iter().collect();
+        res.sort();
+        res
+    }
+
+    /// Just the votes annotated with the given key and value, e.g. those from a particular polling place.
+    /// Vote types, weights and other annotations are kept for the remaining votes. Informal votes are not annotated, so are dropped.
+    pub fn restrict_to_annotation(&self,key:&str,value:&str) -> ElectionData {
+        fn keep(annotations:&[BallotAnnotation],len:usize,key:&str,value:&str) -> Vec<bool> {
+            let mut res = vec![false;len];
+            for a in annotations.iter().filter(|a|a.key==key && a.value==value) {
+                for k in &mut res[a.range()] { *k=true; }
+            }
+            res
+        }
+        /// new_index[i] is the index in the restricted list of the first kept vote at or after index i.
+        fn new_index(keep:&[bool]) -> Vec<usize> {
+            let mut res = vec![0];
+            for &k in keep { res.push(res.last().unwrap()+if k {1} else {0}); }
+            res
+        }
+        fn remap<T:Clone>(specs:&[T],new_index:&[usize],range:impl Fn(&T)->Range<usize>,set:impl Fn(&mut T,usize,usize)) -> Vec<T> {
+            specs.iter().filter_map(|spec|{
+                let r = range(spec);
+                let (first,last) = (new_index[r.start],new_index[r.end]);
+                if first==last { None } else {
+                    let mut spec = spec.clone();
+                    set(&mut spec,first,last);
+                    Some(spec)
+                }
+            }).collect()
+        }
+        let atl_keep = keep(&self.atl_annotations,self.atl.len(),key,value);
+        let btl_keep = keep(&self.btl_annotations,self.btl.len(),key,value);
+        let atl_index = new_index(&atl_keep);
+        let btl_index = new_index(&btl_keep);
+        let mut metadata = self.metadata.clone();
+        metadata.name.modifications.push(format!("Restricted to {} {}",key,value));
+        ElectionData{
+            metadata,
+            atl: self.atl.iter().zip(atl_keep.iter()).filter(|(_,&k)|k).map(|(v,_)|v.clone()).collect(),
+            atl_types: remap(&self.atl_types,&atl_index,|s|s.range(),|s,f,l|{s.first_index_inclusive=f; s.last_index_exclusive=l;}),
+            atl_transfer_values: remap(&self.atl_transfer_values,&atl_index,|s|s.range(),|s,f,l|{s.first_index_inclusive=f; s.last_index_exclusive=l;}),
+            btl: self.btl.iter().zip(btl_keep.iter()).filter(|(_,&k)|k).map(|(v,_)|v.clone()).collect(),
+            btl_types: remap(&self.btl_types,&btl_index,|s|s.range(),|s,f,l|{s.first_index_inclusive=f; s.last_index_exclusive=l;}),
+            btl_transfer_values: remap(&self.btl_transfer_values,&btl_index,|s|s.range(),|s,f,l|{s.first_index_inclusive=f; s.last_index_exclusive=l;}),
+            atl_annotations: remap(&self.atl_annotations,&atl_index,|s|s.range(),|s,f,l|{s.first_index_inclusive=f; s.last_index_exclusive=l;}),
+            btl_annotations: remap(&self.btl_annotations,&btl_index,|s|s.range(),|s,f,l|{s.first_index_inclusive=f; s.last_index_exclusive=l;}),
+            informal: 0,
+        }
+    }
+
+    /// All the vote types used, sorted alphabetically.
     pub fn all_vote_types(&self) -> Vec<&str> {
-        self.atl_types.iter().chain(self.btl_types.iter()).map(|s|s.vote_type.as_str()).collect::<HashSet<&str>>().into_iter().collect()
+        self.atl_types.iter().chain(self.btl_types.iter()).map(|s|s.vote_type.as_str()).collect::<BTreeSet<&str>>().into_iter().collect()
     }
     pub fn save_to_cache(&self) -> std::io::Result<()> {
         let name = self.metadata.name.cache_file_name();
         std::fs::create_dir_all(name.parent().unwrap())?;
         let file = File::create(name)?;
-        serde_json::to_writer(file,&self)?;
+        save_with_version(file,self).map_err(std::io::Error::other)?;
         Ok(())
     }
 
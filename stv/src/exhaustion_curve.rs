@@ -0,0 +1,114 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! How the exhausted vote builds up as a count progresses. This is a key figure for studies of optional preferential voting.
+//!
+//! Votes lost to rounding are not exhausted votes, and are kept separate : the exhausted curve is only
+//! the value of ballots that had no further preferences for a continuing candidate. [ExhaustionBreakdown]
+//! gives both, per count and cumulatively.
+
+use std::fmt::{Debug, Display};
+use std::ops::{Add, Sub};
+use std::str::FromStr;
+use num::Zero;
+use serde::{Serialize,Deserialize};
+use crate::arithmetic::CanConvertToF64PossiblyLossily;
+use crate::distribution_of_preferences_transcript::{CountIndex, PerCandidate, Transcript};
+use crate::signed_version::SignedVersion;
+
+/// The exhausted and rounding values at the end of each count, along with the exhausted value as a fraction of the total formal value.
+#[derive(Clone,Serialize,Deserialize,Debug)]
+pub struct ExhaustionCurve<Tally:Display+FromStr> {
+    /// The total value of formal votes, being everything distributed in the first count.
+    pub total_formal : f64,
+    /// See [Transcript::exhaustion_curve].
+    pub exhausted : Vec<(CountIndex,Tally)>,
+    /// See [Transcript::exhaustion_curve_fraction].
+    pub exhausted_fraction : Vec<(CountIndex,f64)>,
+    /// See [Transcript::rounding_curve].
+    pub rounding : Vec<(CountIndex,SignedVersion<Tally>)>,
+}
+
+/// The value exhausted and the value lost to rounding during one count, and the cumulative values at the end of it.
+#[derive(Clone,Serialize,Deserialize,Debug)]
+pub struct ExhaustionBreakdownAtCount<Tally:Display+FromStr> {
+    pub count : CountIndex,
+    /// The value exhausted during this count. Negative if exhausted votes were redistributed.
+    pub exhausted_this_count : SignedVersion<Tally>,
+    /// The value lost to rounding during this count.
+    pub rounding_this_count : SignedVersion<Tally>,
+    /// The cumulative exhausted value at the end of this count.
+    pub exhausted : Tally,
+    /// The cumulative value lost to rounding at the end of this count.
+    pub rounding : SignedVersion<Tally>,
+}
+
+/// The split between value lost to rounding and value genuinely exhausted, per count and in total.
+#[derive(Clone,Serialize,Deserialize,Debug)]
+pub struct ExhaustionBreakdown<Tally:Display+FromStr> {
+    pub counts : Vec<ExhaustionBreakdownAtCount<Tally>>,
+    /// The sum of the exhausted value over all counts, which is the exhausted value at the end of the last count.
+    pub total_exhausted : SignedVersion<Tally>,
+    /// The sum of the rounding over all counts, which is the rounding at the end of the last count.
+    pub total_rounding : SignedVersion<Tally>,
+}
+
+/// The sum of everything in a [PerCandidate], as a f64.
+fn total_f64<Tally:PartialEq+Clone+Display+FromStr+CanConvertToF64PossiblyLossily>(tallies:&PerCandidate<Tally>) -> f64 {
+    tallies.candidate.iter().map(|t|t.convert_to_f64()).sum::<f64>()+tallies.exhausted.convert_to_f64()+tallies.set_aside.iter().map(|t|t.convert_to_f64()).sum::<f64>()+tallies.rounding.convert_f64(|t|t.convert_to_f64())
+}
+
+impl <Tally:PartialEq+Clone+Display+FromStr+Debug+CanConvertToF64PossiblyLossily> Transcript<Tally> {
+    /// The cumulative exhausted value at the end of each count. This does not include votes lost to rounding, see [Self::rounding_curve].
+    pub fn exhaustion_curve(&self) -> Vec<(CountIndex,Tally)> {
+        self.counts.iter().enumerate().map(|(i,c)|(CountIndex(i),c.status.tallies.exhausted.clone())).collect()
+    }
+
+    /// The cumulative value lost (or, if negative, gained) to rounding at the end of each count.
+    pub fn rounding_curve(&self) -> Vec<(CountIndex,SignedVersion<Tally>)> {
+        self.counts.iter().enumerate().map(|(i,c)|(CountIndex(i),c.status.tallies.rounding.clone())).collect()
+    }
+
+    /// The total value of formal votes, being the sum of everything (including exhausted, set aside and rounding) at the end of the first count. 0 if there are no counts.
+    pub fn total_formal_value(&self) -> f64 {
+        self.counts.first().map(|c|total_f64(&c.status.tallies)).unwrap_or(0.0)
+    }
+
+    /// Like [Self::exhaustion_curve], but as a fraction of [Self::total_formal_value]. NaN if there are no formal votes.
+    pub fn exhaustion_curve_fraction(&self) -> Vec<(CountIndex,f64)> {
+        let total = self.total_formal_value();
+        self.exhaustion_curve().into_iter().map(|(i,e)|(i,e.convert_to_f64()/total)).collect()
+    }
+
+    /// All the above curves, in one serializable structure.
+    pub fn exhaustion_summary(&self) -> ExhaustionCurve<Tally> {
+        ExhaustionCurve{ total_formal: self.total_formal_value(), exhausted: self.exhaustion_curve(), exhausted_fraction: self.exhaustion_curve_fraction(), rounding: self.rounding_curve() }
+    }
+}
+
+impl <Tally:PartialEq+Clone+Display+FromStr+Debug+Ord+Zero+Add<Output=Tally>+Sub<Output=Tally>> Transcript<Tally> {
+    /// The exhausted value and rounding for each count and cumulatively. See [ExhaustionBreakdown].
+    pub fn exhaustion_breakdown(&self) -> ExhaustionBreakdown<Tally> {
+        let mut counts = vec![];
+        let mut total_exhausted = SignedVersion::<Tally>::zero();
+        let mut total_rounding = SignedVersion::<Tally>::zero();
+        let mut previous_exhausted = SignedVersion::<Tally>::zero();
+        let mut previous_rounding = SignedVersion::<Tally>::zero();
+        for (i,c) in self.counts.iter().enumerate() {
+            let exhausted = SignedVersion::from(c.status.tallies.exhausted.clone());
+            let rounding = c.status.tallies.rounding.clone();
+            let exhausted_this_count = exhausted.clone()-previous_exhausted;
+            let rounding_this_count = rounding.clone()-previous_rounding;
+            total_exhausted = total_exhausted+exhausted_this_count.clone();
+            total_rounding = total_rounding+rounding_this_count.clone();
+            counts.push(ExhaustionBreakdownAtCount{count:CountIndex(i),exhausted_this_count,rounding_this_count,exhausted:c.status.tallies.exhausted.clone(),rounding:rounding.clone()});
+            previous_exhausted=exhausted;
+            previous_rounding=rounding;
+        }
+        ExhaustionBreakdown{counts,total_exhausted,total_rounding}
+    }
+}
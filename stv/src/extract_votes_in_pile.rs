@@ -12,13 +12,17 @@ use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use crate::ballot_metadata::CandidateIndex;
+use crate::distribution_of_preferences_transcript::CountIndex;
 use crate::election_data::ElectionData;
+use crate::file_format::save_with_version;
 
 /// Which votes you want to extract from the transcript.
 #[derive(Debug,Clone)]
 pub enum WhatToExtract {
     /// The votes used to elect a particular candidate, as defined in Schedule 4, part 4.3 Casual Vacancies in _Electoral Act 1992_
-    ACTVotesUsedToElectCandidate(CandidateIndex)
+    ACTVotesUsedToElectCandidate(CandidateIndex),
+    /// The state of the count at the end of a given count, see [crate::preference_distribution::PreferenceDistributor::export_current_state_as_election_data].
+    StateAtEndOfCount(CountIndex),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -27,6 +31,8 @@ pub enum ExtractError {
     UnknownThingToExtract,
     #[error("could not parse as a candidate number")]
     CouldNotParseCandidateNumber,
+    #[error("could not parse as a count number")]
+    CouldNotParseCountNumber,
     #[error("unknown thing to do with extracted votes")]
     UnknownThingToDo,
     #[error("an extraction request should be what to extract, followed by a semicolon, followed by what to do with it, and there was no semicolon")]
@@ -37,6 +43,8 @@ impl FromStr for WhatToExtract {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if let Some(candidate) = s.strip_prefix("UsedToElectACT:") {
             candidate.parse::<CandidateIndex>().map_err(|_| ExtractError::CouldNotParseCandidateNumber).map(|c|WhatToExtract::ACTVotesUsedToElectCandidate(c))
+        } else if let Some(count) = s.strip_prefix("StateAtEndOfCount:") {
+            count.parse::<usize>().map_err(|_| ExtractError::CouldNotParseCountNumber).map(|c|WhatToExtract::StateAtEndOfCount(CountIndex(c)))
         } else {
             Err(ExtractError::UnknownThingToExtract)
         }
@@ -86,7 +94,7 @@ impl WhatToDoWithExtractedVotes {
         match self {
             WhatToDoWithExtractedVotes::SaveToFile(path) => {
                 let out = File::create(path).expect("Error creating file to write out election data");
-                serde_json::to_writer(out,&data).expect("Error writing out election data");
+                save_with_version(out,&data).expect("Error writing out election data");
             }
             WhatToDoWithExtractedVotes::CallFunction(f) => { f.lock().unwrap()(data) }
         }
@@ -0,0 +1,144 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Version tagging of the JSON files ConcreteSTV writes (.stv election data and .transcript files), and upgrading of older layouts when reading them.
+//!
+//! Files are written with a top level `format_version` field alongside the normal contents, so older versions of
+//! ConcreteSTV (which ignore unknown fields) can still read them. Files without the field are version 1, the
+//! layout used before versions were recorded.
+//!
+//! Version history:
+//! * 1 : Untagged. Tie resolutions in the metadata may be in the obsolete `{"favoured":...,"disfavoured":...}` form.
+//! * 2 : Tagged. Tie resolutions are always given as `increasing_favour` lists.
+//! * 3 : Tie resolutions (and tie decisions in transcripts) given as `increasing_favour` lists also have `candidate_ids`,
+//!   [crate::ballot_metadata::StableCandidateId]s that remain meaningful if the candidates are reordered. Older files have them added when read.
+
+use std::fmt::{Debug, Display};
+use std::io::{Read, Write};
+use std::str::FromStr;
+use anyhow::anyhow;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use crate::ballot_metadata::{ElectionMetadata, StableCandidateId};
+use crate::distribution_of_preferences_transcript::TranscriptWithMetadata;
+use crate::election_data::ElectionData;
+
+/// The name of the top level JSON field containing the format version.
+pub const FORMAT_VERSION_FIELD : &str = "format_version";
+/// The format version written by this version of ConcreteSTV.
+pub const CURRENT_FORMAT_VERSION : u64 = 3;
+
+/// A type stored in a version tagged file.
+pub trait VersionedFile : Serialize+DeserializeOwned {
+    /// Change `value` from the layout of format version `version` to that of version `version+1`.
+    fn upgrade_one_version(value:&mut Map<String,Value>,version:u64) -> anyhow::Result<()>;
+}
+
+impl VersionedFile for ElectionData {
+    fn upgrade_one_version(value: &mut Map<String, Value>, version: u64) -> anyhow::Result<()> { upgrade_metadata(value,version) }
+}
+
+impl <Tally:PartialEq+Clone+Display+FromStr+Debug+Serialize+DeserializeOwned> VersionedFile for TranscriptWithMetadata<Tally> {
+    fn upgrade_one_version(value: &mut Map<String, Value>, version: u64) -> anyhow::Result<()> {
+        upgrade_metadata(value,version)?;
+        if version==2 {
+            let ids = stable_candidate_ids(value)?;
+            if let Some(Value::Array(counts)) = value.get_mut("transcript").and_then(|t|t.get_mut("counts")) {
+                for count in counts {
+                    if let Some(Value::Array(decisions)) = count.get_mut("decisions") {
+                        for decision in decisions { add_stable_candidate_ids(decision,&ids)?; }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The stable candidate ids for the `metadata` field of `value`, which should be in the current layout apart from stable ids.
+fn stable_candidate_ids(value:&Map<String,Value>) -> anyhow::Result<Vec<StableCandidateId>> {
+    let metadata : ElectionMetadata = serde_json::from_value(value.get("metadata").cloned().ok_or_else(||anyhow!("No metadata"))?)?;
+    Ok(metadata.stable_candidate_ids())
+}
+
+/// If `decision` is a JSON [crate::tie_resolution::TieResolutionExplicitDecision] without stable ids, add them.
+fn add_stable_candidate_ids(decision:&mut Value,ids:&[StableCandidateId]) -> anyhow::Result<()> {
+    let Some(object) = decision.as_object_mut() else { return Ok(()) }; // an old style list of indices, which can't hold ids.
+    if object.contains_key("candidate_ids") { return Ok(()) }
+    let Some(Value::Array(increasing_favour)) = object.get("increasing_favour") else { return Ok(()) };
+    let mut candidate_ids = vec![];
+    for same_level in increasing_favour {
+        let Value::Array(candidates) = same_level else { return Err(anyhow!("Expecting a list of candidates, not {}",same_level)) };
+        candidate_ids.push(candidates.iter().map(|c|c.as_u64().and_then(|c|ids.get(c as usize)).cloned().ok_or_else(||anyhow!("Invalid candidate {} in tie resolution",c))).collect::<anyhow::Result<Vec<_>>>()?);
+    }
+    object.insert("candidate_ids".to_string(),serde_json::to_value(candidate_ids)?);
+    Ok(())
+}
+
+/// Upgrade the `metadata` field of `value`, an [crate::ballot_metadata::ElectionMetadata], from format version `version`.
+fn upgrade_metadata(value:&mut Map<String,Value>,version:u64) -> anyhow::Result<()> {
+    if version==1 {
+        if let Some(Value::Array(resolutions)) = value.get_mut("metadata").and_then(|m|m.get_mut("tie_resolutions")) {
+            for resolution in resolutions {
+                if let Some(obsolete) = resolution.as_object() {
+                    if let (Some(favoured),Some(disfavoured)) = (obsolete.get("favoured"),obsolete.get("disfavoured")) {
+                        *resolution = json!({"increasing_favour":[disfavoured,favoured]});
+                    }
+                }
+            }
+        }
+    }
+    if version==2 {
+        let ids = stable_candidate_ids(value)?;
+        if let Some(Value::Array(resolutions)) = value.get_mut("metadata").and_then(|m|m.get_mut("tie_resolutions")) {
+            for resolution in resolutions { add_stable_candidate_ids(resolution,&ids)?; }
+        }
+    }
+    Ok(())
+}
+
+/// Convert to JSON with the [FORMAT_VERSION_FIELD] set to [CURRENT_FORMAT_VERSION].
+pub fn to_versioned_value<T:Serialize+?Sized>(value:&T) -> anyhow::Result<Value> {
+    let mut res = serde_json::to_value(value)?;
+    let object = res.as_object_mut().ok_or_else(||anyhow!("Only JSON objects can be version tagged"))?;
+    object.insert(FORMAT_VERSION_FIELD.to_string(),Value::from(CURRENT_FORMAT_VERSION));
+    Ok(res)
+}
+
+/// Write as JSON with the [FORMAT_VERSION_FIELD] set to [CURRENT_FORMAT_VERSION].
+pub fn save_with_version<T:Serialize+?Sized,W:Write>(writer:W,value:&T) -> anyhow::Result<()> {
+    serde_json::to_writer(writer,&to_versioned_value(value)?)?;
+    Ok(())
+}
+
+/// Parse JSON written by this or an older version of ConcreteSTV, upgrading older layouts to the current one.
+/// Fails if the file was written by a newer version with a format this does not know about.
+pub fn from_value_with_migration<T:VersionedFile>(value:Value) -> anyhow::Result<T> {
+    let Value::Object(mut object) = value else { return Err(anyhow!("Expecting a JSON object")) };
+    let mut version = match object.remove(FORMAT_VERSION_FIELD) {
+        None => 1,
+        Some(v) => v.as_u64().ok_or_else(||anyhow!("{} should be a positive integer, not {}",FORMAT_VERSION_FIELD,v))?,
+    };
+    if version==0 || version>CURRENT_FORMAT_VERSION {
+        return Err(anyhow!("File has format version {} but this version of ConcreteSTV only understands versions 1 to {}. Perhaps it was written by a newer version of ConcreteSTV.",version,CURRENT_FORMAT_VERSION));
+    }
+    while version<CURRENT_FORMAT_VERSION {
+        T::upgrade_one_version(&mut object,version)?;
+        version+=1;
+    }
+    Ok(serde_json::from_value(Value::Object(object))?)
+}
+
+/// Read JSON written by this or an older version of ConcreteSTV, upgrading older layouts to the current one. See [from_value_with_migration].
+pub fn load_with_migration<T:VersionedFile,R:Read>(reader:R) -> anyhow::Result<T> {
+    from_value_with_migration(serde_json::from_reader(reader)?)
+}
+
+/// Like [load_with_migration], but from a string.
+pub fn load_str_with_migration<T:VersionedFile>(json:&str) -> anyhow::Result<T> {
+    from_value_with_migration(serde_json::from_str(json)?)
+}
@@ -0,0 +1,79 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Analysis of the contest for the last seat awarded, which is usually the only one in doubt.
+//!
+//! The candidates elected before the final seat are taken as fixed, and the final seat's contest is
+//! summarised : who won it, who came closest, by how much, and which tie resolutions affected either of them.
+
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
+use std::ops::Sub;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::ballot_metadata::CandidateIndex;
+use crate::distribution_of_preferences_transcript::{CountIndex, ElectionReason, Transcript};
+use crate::election_data::ElectionData;
+use crate::preference_distribution::{distribute_preferences, PreferenceDistributionRules};
+use crate::random_util::Randomness;
+use crate::signed_version::SignedVersion;
+use crate::tie_resolution::TieResolutionExplicitDecision;
+
+/// The contest for the last seat awarded in a count.
+#[derive(Clone,Serialize,Deserialize,Debug)]
+pub struct FinalSeatAnalysis<Tally:Display+FromStr> {
+    /// The candidates elected before the final seat, in order of election.
+    pub prior_winners : Vec<CandidateIndex>,
+    /// The candidate elected to the final seat.
+    pub winner : CandidateIndex,
+    /// The count in which the winner was elected.
+    pub elected_in_count : CountIndex,
+    pub why : ElectionReason,
+    /// The unelected candidate who remained in contention the longest, ties broken by tally. None if there was no other candidate.
+    pub runner_up : Option<CandidateIndex>,
+    /// The last count at the end of which both the winner and runner-up were continuing. The margin is measured here.
+    pub margin_count : Option<CountIndex>,
+    /// The winner's tally minus the runner-up's tally at the end of the margin count.
+    /// This is a difference in tallies, not the number of ballots that would need to change; see the margin crate for that.
+    pub margin : Option<SignedVersion<Tally>>,
+    /// Tie resolution decisions, and the counts they were made in, that involved the winner or the runner-up.
+    pub tie_decisions : Vec<(CountIndex,TieResolutionExplicitDecision)>,
+}
+
+impl <Tally:PartialEq+Clone+Display+FromStr+Debug+Ord+Sub<Output=Tally>> Transcript<Tally> {
+    /// Analyse the contest for the last seat awarded. None if no one was elected.
+    pub fn final_seat_analysis(&self) -> Option<FinalSeatAnalysis<Tally>> {
+        let (&winner,prior) = self.elected.split_last()?;
+        let (elected_in_count,elected) = self.counts.iter().enumerate().find_map(|(i,c)|c.elected.iter().find(|e|e.who==winner).map(|e|(CountIndex(i),*e)))?;
+        let elected_set : HashSet<CandidateIndex> = self.elected.iter().cloned().collect();
+        let num_candidates = self.counts.first()?.status.tallies.candidate.len();
+        // the last count at the end of which the candidate was continuing. Candidates excluded before the first count are not considered.
+        let last_continuing = |c:CandidateIndex| match self.counts.iter().position(|count|count.not_continuing.contains(&c)) {
+            Some(stopped) => stopped.checked_sub(1).map(CountIndex),
+            None => Some(CountIndex(self.counts.len()-1)),
+        };
+        let runner_up = (0..num_candidates).map(CandidateIndex).filter(|c|!elected_set.contains(c))
+            .filter_map(|c|last_continuing(c).map(|last|(c,last)))
+            .max_by(|(c1,last1),(c2,last2)|last1.0.cmp(&last2.0).then_with(||self.count(*last1).status.tallies.candidate[c1.0].cmp(&self.count(*last2).status.tallies.candidate[c2.0])).then(c2.0.cmp(&c1.0)));
+        let margin_count = runner_up.map(|(_,last)|CountIndex(last.0.min(elected_in_count.0)));
+        let margin = runner_up.zip(margin_count).map(|((r,_),count)|{
+            let tallies = &self.count(count).status.tallies.candidate;
+            let (w,r) = (tallies[winner.0].clone(),tallies[r.0].clone());
+            if w>=r { SignedVersion{negative:false,value:w-r} } else { SignedVersion{negative:true,value:r-w} }
+        });
+        let runner_up = runner_up.map(|(r,_)|r);
+        let involved = |d:&TieResolutionExplicitDecision|d.increasing_favour.iter().flatten().any(|&c|c==winner || Some(c)==runner_up);
+        let tie_decisions = self.counts.iter().enumerate().flat_map(|(i,c)|c.decisions.iter().filter(|d|involved(d)).map(move |d|(CountIndex(i),d.clone()))).collect();
+        Some(FinalSeatAnalysis{prior_winners:prior.to_vec(),winner,elected_in_count,why:elected.why,runner_up,margin_count,margin,tie_decisions})
+    }
+}
+
+/// Count the data with the given rules, and analyse the contest for the last seat awarded. None if no one was elected.
+pub fn analyze_final_seat<Rules:PreferenceDistributionRules>(data:&ElectionData,randomness:&mut Randomness) -> Option<FinalSeatAnalysis<Rules::Tally>> {
+    let excluded : HashSet<CandidateIndex> = data.metadata.excluded.iter().cloned().collect();
+    let transcript = distribute_preferences::<Rules>(data,data.metadata.vacancies?,&excluded,&data.metadata.tie_resolutions,None,false,randomness);
+    transcript.final_seat_analysis()
+}
@@ -15,12 +15,14 @@ use crate::parse_util::CanReadRawMarkings;
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug,Serialize,Deserialize,Clone)]
+#[cfg_attr(feature="openapi",derive(utoipa::ToSchema))]
 pub struct FindVoteHit {
     pub metadata : HashMap<String,String>,
     pub votes : String, // comma separated list of votes.
 }
 
 #[derive(Debug,Serialize,Deserialize,Clone)]
+#[cfg_attr(feature="openapi",derive(utoipa::ToSchema))]
 pub struct SearchMatchesWithSameScore {
     pub score : usize,
     pub hits : Vec<FindVoteHit>,
@@ -29,12 +31,14 @@ pub struct SearchMatchesWithSameScore {
 }
 
 #[derive(Debug,Serialize,Deserialize,Clone)]
+#[cfg_attr(feature="openapi",derive(utoipa::ToSchema))]
 pub struct FindMyVoteResult {
     /// best matches, highest score earliest.
     pub best : Vec<SearchMatchesWithSameScore>,
 }
 
 #[derive(Debug,Serialize,Deserialize,Clone)]
+#[cfg_attr(feature="openapi",derive(utoipa::ToSchema))]
 pub struct FindMyVoteQuery {
     /// a comma separated string of preferences
     pub query : String,
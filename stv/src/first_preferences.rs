@@ -0,0 +1,48 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The first preference count and the quota, without doing the rest of the distribution of preferences.
+//! This is much faster than a full count, and is often all that is needed for a summary.
+
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use serde::{Serialize,Deserialize};
+use crate::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use crate::distribution_of_preferences_transcript::{PerCandidate, QuotaInfo};
+use crate::election_data::ElectionData;
+use crate::preference_distribution::{PreferenceDistributionRules, PreferenceDistributor};
+use crate::random_util::Randomness;
+use crate::tie_resolution::TieResolutionsMadeByEC;
+
+/// The result of the first preference count.
+#[derive(Clone,Serialize,Deserialize,Debug)]
+pub struct FirstPreferences<Tally:PartialEq+Clone+Display+FromStr+Debug> {
+    /// The quota, the same as in the transcript of a full count. None if the rules don't use a quota.
+    pub quota : Option<QuotaInfo<Tally>>,
+    /// The tallies after the first preference count.
+    pub tallies : PerCandidate<Tally>,
+    /// The candidates whose first preferences are at least the quota (or exceed it, if [PreferenceDistributionRules::quota_is_strict_greater]), highest tally first.
+    pub over_quota : Vec<CandidateIndex>,
+}
+
+/// Do the first preference count (including computing the quota), but not the rest of the distribution of preferences.
+/// Arguments are as for [crate::preference_distribution::distribute_preferences].
+pub fn first_preferences<Rules:PreferenceDistributionRules>(data:&ElectionData,candidates_to_be_elected : NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:& TieResolutionsMadeByEC,vote_types : Option<&[String]>,randomness:&mut Randomness) -> FirstPreferences<Rules::Tally> {
+    let arena = typed_arena::Arena::<CandidateIndex>::new();
+    let votes = data.resolve_atl_including_weights(&arena,vote_types);
+    let mut work : PreferenceDistributor<'_,Rules> = PreferenceDistributor::new(data,&votes,candidates_to_be_elected,excluded_candidates,ec_resolutions,false,None,randomness,&[],false);
+    work.distribute_first_preferences();
+    let transcript = work.transcript;
+    let tallies = transcript.counts.last().expect("First preference count produces a count").status.tallies.clone();
+    let mut over_quota : Vec<CandidateIndex> = match &transcript.quota {
+        Some(quota) => (0..tallies.candidate.len()).map(CandidateIndex).filter(|c|if Rules::quota_is_strict_greater() { tallies.candidate[c.0]>quota.quota } else { tallies.candidate[c.0]>=quota.quota }).collect(),
+        None => vec![],
+    };
+    over_quota.sort_by(|a,b|tallies.candidate[b.0].cmp(&tallies.candidate[a.0]));
+    FirstPreferences{ quota: transcript.quota, tallies, over_quota }
+}
@@ -8,15 +8,15 @@
 
 //! A fixed precision decimal type for jurisdictions like ACT who count votes to a particular number of decimal places.
 
-use std::ops::{AddAssign, SubAssign, Sub, Add, Div};
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::ops::{AddAssign, SubAssign, Sub, Add, Div};
 use num::{Zero, BigRational, BigInt, ToPrimitive};
-use std::fmt::{Debug, Display, Formatter};
-use std::iter::Sum;
+use core::fmt::{Debug, Display, Formatter};
+use core::iter::Sum;
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
-use std::str::FromStr;
-use crate::ballot_pile::BallotPaperCount;
-use crate::official_dop_transcript::CanConvertToF64PossiblyLossily;
-use crate::preference_distribution::RoundUpToUsize;
+use core::str::FromStr;
+use crate::arithmetic::{BallotPaperCount, CanConvertToF64PossiblyLossily, RoundUpToUsize};
 
 /// Stores a fixed precision decimal number as an integer scaled by 10^DIGITS
 #[derive(Copy, Clone,Eq, PartialEq,Ord, PartialOrd,Hash)]
@@ -83,7 +83,7 @@ impl <const DIGITS:usize> Zero for FixedPrecisionDecimal<DIGITS> {
 }
 
 impl <const DIGITS:usize> Display for FixedPrecisionDecimal<DIGITS> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         let int_portion = self.scaled_value/Self::SCALE;
         let frac_portion = self.scaled_value%Self::SCALE;
         if frac_portion==0 { write!(f,"{}",int_portion)}
@@ -95,7 +95,7 @@ impl <const DIGITS:usize> Display for FixedPrecisionDecimal<DIGITS> {
 }
 
 impl <const DIGITS:usize> Debug for FixedPrecisionDecimal<DIGITS> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f,"{}",self)
     }
 }
@@ -176,7 +176,7 @@ impl <const DIGITS:usize> Div<usize> for FixedPrecisionDecimal<DIGITS> {
 mod tests {
     use crate::fixed_precision_decimal::FixedPrecisionDecimal;
     use num::Zero;
-    use crate::ballot_pile::BallotPaperCount;
+    use crate::arithmetic::BallotPaperCount;
 
     #[test]
     fn test_six_digit_decimal() {
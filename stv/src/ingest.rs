@@ -0,0 +1,159 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Convert ballots that are not preferential (approval and cumulative ballots) into
+//! something that can be counted by STV.
+//!
+//! **This is not a faithful STV input.** An approval or cumulative ballot does not say how the voter would
+//! rank the candidates, so some modeling assumption is needed, and the result of the count depends on it.
+//! ConcreteSTV cannot represent equal preferences, so each ballot is split into several weighted ballots, using
+//! the vote weights ([ElectionData::btl_transfer_values]) that are otherwise used for things like votes
+//! already partially distributed.
+//!
+//! * An approval ballot approving k candidates is treated as ranking them equal first, and nobody else.
+//!   It is split into all k! orderings of the approved candidates, each with weight 1/k!. So each approved
+//!   candidate gets 1/k of a vote on first preferences, and after an exclusion the value on that candidate is shared equally
+//!   amongst the other approved candidates. The number of approvals per ballot is limited to [MAX_APPROVALS_TO_SPLIT].
+//! * A cumulative ballot giving p_c points to candidate c, out of a total of P points, is split into one ballot
+//!   per candidate given points, with weight p_c/P, with c as first preference followed by the other candidates given points
+//!   in decreasing order of points (ties broken by order on the ballot paper). Candidates given no points are not ranked.
+//!
+//! Weights are applied to whole parcels of papers, so rules that round tallies to integers will lose some
+//! of the fractional value on first preferences. Rules with decimal tallies are more appropriate.
+
+use std::collections::HashMap;
+use num::BigInt;
+use serde::{Serialize,Deserialize};
+use crate::ballot_metadata::{CandidateIndex, ElectionMetadata};
+use crate::ballot_paper::BTL;
+use crate::election_data::{ElectionData, VoteValueSpecification};
+use crate::transfer_value::TransferValue;
+
+/// The maximum number of candidates a single approval ballot may approve, as the ballot is split into
+/// every ordering of them.
+pub const MAX_APPROVALS_TO_SPLIT : usize = 8;
+
+/// A number of identical approval ballots.
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct ApprovalBallot {
+    /// The approved candidates. Order is not meaningful.
+    pub approved : Vec<CandidateIndex>,
+    /// The number of people who voted this way.
+    pub n : usize,
+}
+
+/// A number of identical cumulative ballots.
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct CumulativeBallot {
+    /// The number of points given to each candidate. Candidates not listed get no points.
+    pub points : Vec<(CandidateIndex,usize)>,
+    /// The number of people who voted this way.
+    pub n : usize,
+}
+
+#[derive(thiserror::Error,Debug,Clone,PartialEq,Eq)]
+pub enum IngestError {
+    #[error("ballot {ballot} lists candidate {candidate} which does not exist")]
+    CandidateOutOfRange{ballot:usize,candidate:CandidateIndex},
+    #[error("ballot {ballot} lists candidate {candidate} more than once")]
+    DuplicateCandidate{ballot:usize,candidate:CandidateIndex},
+    #[error("ballot {ballot} approves {approved} candidates, but at most {MAX_APPROVALS_TO_SPLIT} are supported")]
+    TooManyApprovals{ballot:usize,approved:usize},
+}
+
+/// Check a list of candidates on ballot number `ballot` are valid and distinct.
+fn check_candidates(ballot:usize,candidates:impl Iterator<Item=CandidateIndex>,metadata:&ElectionMetadata) -> Result<(),IngestError> {
+    let mut seen = vec![false;metadata.candidates.len()];
+    for candidate in candidates {
+        if candidate.0>=seen.len() { return Err(IngestError::CandidateOutOfRange {ballot,candidate}); }
+        if seen[candidate.0] { return Err(IngestError::DuplicateCandidate {ballot,candidate}); }
+        seen[candidate.0]=true;
+    }
+    Ok(())
+}
+
+/// Accumulates weighted BTL votes, and produces them grouped by weight.
+#[derive(Default)]
+struct WeightedVotes {
+    by_weight : HashMap<TransferValue,Vec<BTL>>,
+    informal : usize,
+}
+
+impl WeightedVotes {
+    fn add(&mut self,weight:TransferValue,candidates:Vec<CandidateIndex>,n:usize) {
+        self.by_weight.entry(weight).or_default().push(BTL{candidates,n});
+    }
+
+    fn into_election_data(self,metadata:ElectionMetadata) -> ElectionData {
+        let mut by_weight : Vec<(TransferValue,Vec<BTL>)> = self.by_weight.into_iter().collect();
+        by_weight.sort_by(|(tv1,_),(tv2,_)|tv2.cmp(tv1));
+        let mut btl = vec![];
+        let mut btl_transfer_values = vec![];
+        for (value,votes) in by_weight {
+            let first_index_inclusive = btl.len();
+            btl.extend(votes);
+            btl_transfer_values.push(VoteValueSpecification{ value, first_index_inclusive, last_index_exclusive: btl.len() });
+        }
+        ElectionData{
+            metadata,
+            atl: vec![],
+            atl_types: vec![],
+            atl_transfer_values: vec![],
+            btl,
+            btl_types: vec![],
+            btl_transfer_values,
+            atl_annotations: vec![],
+            btl_annotations: vec![],
+            informal: self.informal,
+        }
+    }
+}
+
+/// Call f on every ordering of the candidates.
+fn for_each_ordering(candidates:&mut Vec<CandidateIndex>,start:usize,f:&mut impl FnMut(&[CandidateIndex])) {
+    if start>=candidates.len() { f(candidates); return; }
+    for i in start..candidates.len() {
+        candidates.swap(start,i);
+        for_each_ordering(candidates,start+1,f);
+        candidates.swap(start,i);
+    }
+}
+
+/// Convert approval ballots to weighted preferential ballots. See the [module documentation](crate::ingest) for the modeling assumptions.
+/// The metadata should describe the candidates, and usually the number of vacancies. Ballots approving nobody are counted as informal.
+pub fn approval_to_election_data(metadata:ElectionMetadata,ballots:&[ApprovalBallot]) -> Result<ElectionData,IngestError> {
+    let mut votes = WeightedVotes::default();
+    for (ballot,approval) in ballots.iter().enumerate() {
+        check_candidates(ballot,approval.approved.iter().cloned(),&metadata)?;
+        let k = approval.approved.len();
+        if k==0 { votes.informal+=approval.n; continue; }
+        if k>MAX_APPROVALS_TO_SPLIT { return Err(IngestError::TooManyApprovals {ballot,approved:k}); }
+        let orderings : usize = (1..=k).product();
+        let weight = TransferValue::new(BigInt::from(1),BigInt::from(orderings));
+        let mut approved = approval.approved.clone();
+        approved.sort_by_key(|c|c.0);
+        for_each_ordering(&mut approved,0,&mut |ordering|votes.add(weight.clone(),ordering.to_vec(),approval.n));
+    }
+    Ok(votes.into_election_data(metadata))
+}
+
+/// Convert cumulative ballots to weighted preferential ballots. See the [module documentation](crate::ingest) for the modeling assumptions.
+/// The metadata should describe the candidates, and usually the number of vacancies. Ballots giving no points to anyone are counted as informal.
+pub fn cumulative_to_election_data(metadata:ElectionMetadata,ballots:&[CumulativeBallot]) -> Result<ElectionData,IngestError> {
+    let mut votes = WeightedVotes::default();
+    for (ballot,cumulative) in ballots.iter().enumerate() {
+        check_candidates(ballot,cumulative.points.iter().map(|(c,_)|*c),&metadata)?;
+        let mut given : Vec<(CandidateIndex,usize)> = cumulative.points.iter().cloned().filter(|(_,p)|*p>0).collect();
+        let total : usize = given.iter().map(|(_,p)|*p).sum();
+        if total==0 { votes.informal+=cumulative.n; continue; }
+        given.sort_by_key(|(c,p)|(usize::MAX-p,c.0));
+        for &(first,points) in &given {
+            let candidates : Vec<CandidateIndex> = std::iter::once(first).chain(given.iter().map(|(c,_)|*c).filter(|c|*c!=first)).collect();
+            votes.add(TransferValue::new(BigInt::from(points),BigInt::from(total)),candidates,cumulative.n);
+        }
+    }
+    Ok(votes.into_election_data(metadata))
+}
@@ -4,29 +4,65 @@
 // ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
+//! Without the default `std` feature, only the core arithmetic ([arithmetic], [fixed_precision_decimal] and most of [transfer_value]) is available, as `no_std` + `alloc`.
+#![cfg_attr(not(feature="std"),no_std)]
 
-pub mod ballot_paper;
-pub mod ballot_metadata;
-pub mod election_data;
-pub mod ballot_pile;
+extern crate alloc;
+
+pub mod arithmetic;
+#[cfg(feature="std")] pub mod ballot_paper;
+#[cfg(feature="std")] pub mod ballot_metadata;
+#[cfg(feature="std")] pub mod election_data;
+#[cfg(feature="std")] pub mod ballot_pile;
 pub mod transfer_value;
-pub mod preference_distribution;
-pub mod distribution_of_preferences_transcript;
-pub mod util;
-pub mod tie_resolution;
-pub mod official_dop_transcript;
-pub mod parse_util;
-pub mod compare_transcripts;
-pub mod compare_rules;
+#[cfg(feature="std")] pub mod preference_distribution;
+#[cfg(feature="std")] pub mod distribution_of_preferences_transcript;
+#[cfg(feature="std")] pub mod util;
+#[cfg(feature="std")] pub mod tie_resolution;
+#[cfg(feature="std")] pub mod official_dop_transcript;
+#[cfg(feature="std")] pub mod parse_util;
+#[cfg(feature="std")] pub mod compare_transcripts;
+#[cfg(feature="std")] pub mod compare_rules;
 pub mod fixed_precision_decimal;
-pub mod signed_version;
-pub mod download;
-pub mod monte_carlo;
-pub mod datasource_description;
-pub mod run_once_globally;
-pub mod errors_btl;
-pub mod find_vote;
-pub mod verify_official_transcript;
-pub mod random_util;
-pub mod extract_votes_in_pile;
-pub mod simple_list_of_votes;
\ No newline at end of file
+pub mod runtime_decimal;
+#[cfg(feature="std")] pub mod signed_version;
+#[cfg(feature="std")] pub mod download;
+#[cfg(feature="std")] pub mod monte_carlo;
+#[cfg(feature="std")] pub mod datasource_description;
+#[cfg(feature="std")] pub mod run_once_globally;
+#[cfg(feature="std")] pub mod errors_btl;
+#[cfg(feature="std")] pub mod find_vote;
+#[cfg(feature="std")] pub mod verify_official_transcript;
+#[cfg(feature="std")] pub mod random_util;
+#[cfg(feature="std")] pub mod extract_votes_in_pile;
+#[cfg(feature="std")] pub mod simple_list_of_votes;
+#[cfg(feature="std")] pub mod transfer_value_ledger;
+#[cfg(feature="std")] pub mod validate_election_data;
+#[cfg(feature="std")] pub mod batch_reconciliation;
+#[cfg(feature="std")] pub mod certain_losers;
+#[cfg(feature="std")] pub mod ingest;
+#[cfg(feature="std")] pub mod tally_audit;
+#[cfg(feature="std")] pub mod robson_rotation;
+#[cfg(feature="std")] pub mod first_preferences;
+#[cfg(feature="std")] pub mod tie_branches;
+#[cfg(feature="std")] pub mod merge_electorates;
+#[cfg(feature="std")] pub mod non_election_reasons;
+#[cfg(feature="std")] pub mod file_format;
+#[cfg(feature="std")] pub mod preference_oracle;
+#[cfg(feature="std")] pub mod synthetic;
+#[cfg(feature="std")] pub mod exhaustion_curve;
+#[cfg(feature="std")] pub mod two_candidate_preferred;
+#[cfg(feature="std")] pub mod composite;
+#[cfg(feature="std")] pub mod bloc_constraints;
+#[cfg(feature="std")] pub mod aec_text;
+#[cfg(feature="std")] pub mod order_independence;
+#[cfg(feature="std")] pub mod public_release;
+#[cfg(feature="std")] pub mod data_diff;
+#[cfg(feature="std")] pub mod count_certificate;
+#[cfg(feature="std")] pub mod provisional;
+#[cfg(feature="std")] pub mod optional_preferential;
+#[cfg(feature="std")] pub mod final_seat;
+#[cfg(feature="std")] pub mod configurable_rules;
+#[cfg(feature="std")] pub mod trace_votes;
+#[cfg(feature="std")] pub mod count_stepper;
+#[cfg(feature="parquet")] pub mod parquet_export;
@@ -0,0 +1,101 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Combine several electorates of the same election, with disjoint candidates, into one dataset, for region wide analysis
+//! such as preference flows across a whole state. The result is not meaningful to count as a single election.
+
+use anyhow::anyhow;
+use crate::ballot_metadata::{CandidateIndex, ElectionMetadata, ElectionName, NumberOfCandidates, PartyIndex};
+use crate::ballot_paper::{ATL, BTL};
+use crate::election_data::{BallotAnnotation, ElectionData, VoteTypeSpecification, VoteValueSpecification};
+use crate::tie_resolution::{TieResolutionAtom, TieResolutionExplicitDecisionInCount, TieResolutionsMadeByEC};
+
+impl ElectionData {
+    /// Make a single dataset out of several electorates of the same election (same year, authority and election name).
+    ///
+    /// Candidates and parties are concatenated in the order given, with indices renumbered, and ballots remapped accordingly.
+    /// Vacancies and enrolment are summed if known for every electorate. EC tie resolutions are kept, but without the count
+    /// they came up in, as count numbers don't carry across.
+    pub fn merge_disjoint(others:&[ElectionData]) -> anyhow::Result<ElectionData> {
+        let first = others.first().ok_or_else(||anyhow!("No electorates to merge"))?;
+        for other in others {
+            let (a,b) = (&first.metadata.name,&other.metadata.name);
+            if a.year!=b.year || a.authority!=b.authority || a.name!=b.name {
+                return Err(anyhow!("Can't merge {} {} {} with {} {} {} as they are different elections",a.authority,a.year,a.name,b.authority,b.year,b.name));
+            }
+        }
+        let mut metadata = ElectionMetadata{
+            name: ElectionName {
+                year: first.metadata.name.year.clone(),
+                authority: first.metadata.name.authority.clone(),
+                name: first.metadata.name.name.clone(),
+                electorate: others.iter().map(|d|d.metadata.name.electorate.as_str()).collect::<Vec<_>>().join("+"),
+                modifications: others.iter().flat_map(|d|d.metadata.name.modifications.iter().cloned()).collect(),
+                comment: None,
+            },
+            candidates: vec![],
+            parties: vec![],
+            source: others.iter().flat_map(|d|d.metadata.source.iter().cloned()).collect(),
+            results: if others.iter().all(|d|d.metadata.results.is_some()) { Some(vec![]) } else { None },
+            vacancies: sum_if_all_known(others.iter().map(|d|d.metadata.vacancies)),
+            enrolment: sum_if_all_known(others.iter().map(|d|d.metadata.enrolment)),
+            secondary_vacancies: sum_if_all_known(others.iter().map(|d|d.metadata.secondary_vacancies)),
+            excluded: vec![],
+            withdrawn_before_count: vec![],
+            tie_resolutions: TieResolutionsMadeByEC::default(),
+        };
+        metadata.name.modifications.push(format!("Merged electorates {}",metadata.name.electorate));
+        let mut res = ElectionData{ metadata, atl: vec![], atl_types: vec![], atl_transfer_values: vec![], btl: vec![], btl_types: vec![], btl_transfer_values: vec![], atl_annotations: vec![], btl_annotations: vec![], informal: 0 };
+        for data in others {
+            let candidate_offset = res.metadata.candidates.len();
+            let party_offset = res.metadata.parties.len();
+            let atl_offset = res.atl.len();
+            let btl_offset = res.btl.len();
+            let c = |c:&CandidateIndex|CandidateIndex(c.0+candidate_offset);
+            let p = |p:&PartyIndex|PartyIndex(p.0+party_offset);
+            let cs = |v:&[CandidateIndex]|v.iter().map(c).collect::<Vec<_>>();
+            res.metadata.candidates.extend(data.metadata.candidates.iter().map(|candidate|{
+                let mut candidate = candidate.clone();
+                candidate.party=candidate.party.as_ref().map(p);
+                candidate
+            }));
+            res.metadata.parties.extend(data.metadata.parties.iter().map(|party|{
+                let mut party = party.clone();
+                party.candidates=cs(&party.candidates);
+                party.tickets=party.tickets.iter().map(|t|cs(t)).collect();
+                party
+            }));
+            if let (Some(results),Some(these)) = (res.metadata.results.as_mut(),data.metadata.results.as_ref()) { results.extend(these.iter().map(c)); }
+            res.metadata.excluded.extend(data.metadata.excluded.iter().map(c));
+            res.metadata.tie_resolutions.tie_resolutions.extend(data.metadata.tie_resolutions.tie_resolutions.iter().map(|atom|match atom {
+                TieResolutionAtom::IncreasingFavour(v) => TieResolutionAtom::IncreasingFavour(cs(v)),
+                TieResolutionAtom::ExplicitDecision(d) => {
+                    let mut decision = d.decision.clone();
+                    decision.increasing_favour=decision.increasing_favour.iter().map(|v|cs(v)).collect();
+                    TieResolutionAtom::ExplicitDecision(TieResolutionExplicitDecisionInCount{ decision, came_up_in: None })
+                }
+            }));
+            res.atl.extend(data.atl.iter().map(|v|ATL{ parties: v.parties.iter().map(p).collect(), n: v.n, ticket_index: v.ticket_index }));
+            res.btl.extend(data.btl.iter().map(|v|BTL{ candidates: cs(&v.candidates), n: v.n }));
+            res.atl_types.extend(data.atl_types.iter().map(|s|VoteTypeSpecification{ vote_type: s.vote_type.clone(), first_index_inclusive: s.first_index_inclusive+atl_offset, last_index_exclusive: s.last_index_exclusive+atl_offset }));
+            res.btl_types.extend(data.btl_types.iter().map(|s|VoteTypeSpecification{ vote_type: s.vote_type.clone(), first_index_inclusive: s.first_index_inclusive+btl_offset, last_index_exclusive: s.last_index_exclusive+btl_offset }));
+            res.atl_transfer_values.extend(data.atl_transfer_values.iter().map(|s|VoteValueSpecification{ value: s.value.clone(), first_index_inclusive: s.first_index_inclusive+atl_offset, last_index_exclusive: s.last_index_exclusive+atl_offset }));
+            res.btl_transfer_values.extend(data.btl_transfer_values.iter().map(|s|VoteValueSpecification{ value: s.value.clone(), first_index_inclusive: s.first_index_inclusive+btl_offset, last_index_exclusive: s.last_index_exclusive+btl_offset }));
+            res.atl_annotations.extend(data.atl_annotations.iter().map(|a|BallotAnnotation{ key: a.key.clone(), value: a.value.clone(), first_index_inclusive: a.first_index_inclusive+atl_offset, last_index_exclusive: a.last_index_exclusive+atl_offset }));
+            res.btl_annotations.extend(data.btl_annotations.iter().map(|a|BallotAnnotation{ key: a.key.clone(), value: a.value.clone(), first_index_inclusive: a.first_index_inclusive+btl_offset, last_index_exclusive: a.last_index_exclusive+btl_offset }));
+            res.informal+=data.informal;
+        }
+        Ok(res)
+    }
+}
+
+/// The sum of some numbers of candidates, or None if any are unknown.
+fn sum_if_all_known(values:impl Iterator<Item=Option<NumberOfCandidates>>) -> Option<NumberOfCandidates> {
+    let mut sum = 0;
+    for v in values { sum+=v?.0; }
+    Some(NumberOfCandidates(sum))
+}
@@ -5,10 +5,19 @@
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
 
-//! Some utilities useful for Monte-Carlo experiments.
+//! Some utilities useful for Monte-Carlo experiments, including bootstrap estimates of how
+//! certain an election outcome is given sampling noise in the ballots, and a comparison of random sample
+//! and fractional surplus transfers.
 
 
-use rand::Rng;
+use std::collections::HashSet;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use crate::ballot_metadata::{CandidateIndex, MissingVacancies};
+use crate::election_data::ElectionData;
+use crate::preference_distribution::{distribute_preferences_final_status_only, PreferenceDistributionRules};
+use crate::random_util::Randomness;
 
 #[derive(Default,Clone)]
 pub struct SampleWithReplacement<E> {
@@ -33,3 +42,104 @@ impl <E:Clone> SampleWithReplacement<E> {
 }
 
 
+/// How often each candidate was elected over a number of resampled counts. See [bootstrap_outcomes].
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,Eq)]
+pub struct OutcomeDistribution {
+    /// The number of resampled counts done.
+    pub samples : usize,
+    /// `times_elected[i]` is the number of samples in which candidate `i` was elected.
+    pub times_elected : Vec<usize>,
+}
+
+impl OutcomeDistribution {
+    /// The proportion of samples in which the candidate was elected, between 0 and 1.
+    pub fn probability_elected(&self,candidate:CandidateIndex) -> f64 {
+        if self.samples==0 { 0.0 } else { self.times_elected[candidate.0] as f64/self.samples as f64 }
+    }
+    /// The candidates elected in at least one sample, most frequently elected first.
+    pub fn sometimes_elected(&self) -> Vec<CandidateIndex> {
+        let mut res : Vec<CandidateIndex> = (0..self.times_elected.len()).filter(|&i|self.times_elected[i]>0).map(CandidateIndex).collect();
+        res.sort_by_key(|c|std::cmp::Reverse(self.times_elected[c.0]));
+        res
+    }
+}
+
+/// Estimate how robust the outcome of an election is to sampling noise in the ballots, by a bootstrap.
+///
+/// Do `n` counts, each on a sample of the formal ballots (ATL and BTL together) drawn with replacement
+/// from `data`, of the same total size. Ties are resolved using a PRNG, so the result is a deterministic function of `seed`.
+/// Informal votes and any vote types or weights are unchanged, as only the number of each distinct ballot is resampled.
+pub fn bootstrap_outcomes<Rules:PreferenceDistributionRules>(data:&ElectionData,n:usize,seed:u64) -> Result<OutcomeDistribution,MissingVacancies> {
+    let vacancies = data.metadata.vacancies.ok_or(MissingVacancies)?;
+    let excluded : HashSet<CandidateIndex> = data.metadata.excluded.iter().cloned().collect();
+    let mut rng = ChaCha20Rng::seed_from_u64(seed);
+    let num_atl = data.atl.len();
+    let mut cumulative : Vec<usize> = Vec::with_capacity(num_atl+data.btl.len());
+    let mut total = 0;
+    for count in data.atl.iter().map(|v|v.n).chain(data.btl.iter().map(|v|v.n)) {
+        total+=count;
+        cumulative.push(total);
+    }
+    let mut sample = data.clone();
+    let mut times_elected = vec![0;data.metadata.candidates.len()];
+    for _ in 0..n {
+        for v in &mut sample.atl { v.n=0; }
+        for v in &mut sample.btl { v.n=0; }
+        for _ in 0..total {
+            let chosen = rng.gen_range(0..total);
+            let index = cumulative.partition_point(|&c|c<=chosen);
+            if index<num_atl { sample.atl[index].n+=1; } else { sample.btl[index-num_atl].n+=1; }
+        }
+        let mut randomness = Randomness::PRNG(ChaCha20Rng::seed_from_u64(rng.gen()));
+        for elected in distribute_preferences_final_status_only::<Rules>(&sample,vacancies,&excluded,&data.metadata.tie_resolutions,None,&mut randomness).elected { times_elected[elected.0]+=1; }
+    }
+    Ok(OutcomeDistribution{ samples: n, times_elected })
+}
+
+/// The result of [compare_random_vs_fractional].
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,Eq)]
+pub struct RandomVsFractionalReport {
+    /// How often each candidate was elected by the random sample method, over all seeds.
+    pub random : OutcomeDistribution,
+    /// The candidates elected by the fractional method, in order of election.
+    pub fractional_elected : Vec<CandidateIndex>,
+    /// The number of seeds for which the random sample method elected the same set of candidates as the fractional method.
+    pub agreements : usize,
+}
+
+impl RandomVsFractionalReport {
+    /// The proportion of seeds for which the two methods elected the same candidates, between 0 and 1.
+    pub fn proportion_agreeing(&self) -> f64 {
+        if self.random.samples==0 { 0.0 } else { self.agreements as f64/self.random.samples as f64 }
+    }
+    /// Candidates elected by one method but not always by the other. That is, candidates elected by the fractional method
+    /// but not by the random method for some seeds, and candidates elected by the random method for some seeds but not by the fractional method.
+    pub fn differences(&self) -> Vec<CandidateIndex> {
+        (0..self.random.times_elected.len()).map(CandidateIndex).filter(|c|{
+            let times = self.random.times_elected[c.0];
+            if self.fractional_elected.contains(c) { times<self.random.samples } else { times>0 }
+        }).collect()
+    }
+}
+
+/// Count the same data with a method that distributes surpluses by a random sample of whole ballot papers (e.g. the NSW
+/// [crate::preference_distribution::SurplusTransferMethod::PickRandomlyAfterDistribution]) and with a fractional (Gregory) method,
+/// and report how often they agree on who is elected.
+///
+/// The random method is counted `n_seeds` times, with the PRNG seeded by 0,1,...,n_seeds-1, so the result is deterministic.
+/// The fractional method is counted once, with any ties resolved using a PRNG seeded by 0.
+pub fn compare_random_vs_fractional<Random:PreferenceDistributionRules,Fractional:PreferenceDistributionRules>(data:&ElectionData,n_seeds:usize) -> Result<RandomVsFractionalReport,MissingVacancies> {
+    let vacancies = data.metadata.vacancies.ok_or(MissingVacancies)?;
+    let excluded : HashSet<CandidateIndex> = data.metadata.excluded.iter().cloned().collect();
+    let fractional_elected = distribute_preferences_final_status_only::<Fractional>(data,vacancies,&excluded,&data.metadata.tie_resolutions,None,&mut Randomness::PRNG(ChaCha20Rng::seed_from_u64(0))).elected;
+    let fractional_set : HashSet<CandidateIndex> = fractional_elected.iter().cloned().collect();
+    let mut times_elected = vec![0;data.metadata.candidates.len()];
+    let mut agreements = 0;
+    for seed in 0..n_seeds {
+        let mut randomness = Randomness::PRNG(ChaCha20Rng::seed_from_u64(seed as u64));
+        let elected = distribute_preferences_final_status_only::<Random>(data,vacancies,&excluded,&data.metadata.tie_resolutions,None,&mut randomness).elected;
+        for c in &elected { times_elected[c.0]+=1; }
+        if elected.into_iter().collect::<HashSet<_>>()==fractional_set { agreements+=1; }
+    }
+    Ok(RandomVsFractionalReport{ random: OutcomeDistribution{ samples: n_seeds, times_elected }, fractional_elected, agreements })
+}
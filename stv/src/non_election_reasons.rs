@@ -0,0 +1,58 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! A structured explanation of why each candidate who was not elected was not elected.
+//! This is the information people usually dig through a transcript to find.
+
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use serde::{Serialize,Deserialize};
+use crate::ballot_metadata::{CandidateIndex, ElectionMetadata};
+use crate::distribution_of_preferences_transcript::{CountIndex, ReasonForCount, Transcript};
+
+/// Why a candidate was not elected.
+#[derive(Clone,Copy,Serialize,Deserialize,Debug,PartialEq,Eq)]
+pub enum WhyNotElected {
+    /// The candidate was excluded (e.g. ineligible) or withdrew before the count started, and so never was a continuing candidate.
+    ExcludedBeforeCount,
+    /// The candidate was excluded, starting at the given count.
+    Excluded(CountIndex),
+    /// The candidate was still continuing when all the vacancies were filled.
+    ContinuingWhenAllSeatsFilled,
+}
+
+/// Why a particular candidate was not elected.
+#[derive(Clone,Serialize,Deserialize,Debug)]
+pub struct NonElectionReason<Tally> {
+    pub candidate : CandidateIndex,
+    pub why : WhyNotElected,
+    /// The candidate's tally when they stopped being a continuing candidate. For an excluded candidate,
+    /// this is the tally at the end of the count before their exclusion started. For a candidate continuing
+    /// when all seats were filled, this is the tally at the end of the last count.
+    pub final_tally : Tally,
+}
+
+impl <Tally:PartialEq+Clone+Display+FromStr+Debug> Transcript<Tally> {
+    /// For each candidate in `metadata` not elected in this transcript, in candidate order, why they were not elected.
+    /// Candidates excluded before the count are taken from `metadata.excluded`.
+    pub fn non_election_reasons(&self,metadata:&ElectionMetadata) -> Vec<NonElectionReason<Tally>> {
+        let mut res = vec![];
+        let Some(last) = self.counts.last() else { return res };
+        for candidate in (0..metadata.candidates.len()).map(CandidateIndex) {
+            if self.elected.contains(&candidate) { continue; }
+            let (why,final_tally) = if metadata.excluded.contains(&candidate) || metadata.withdrawn_before_count.contains(&candidate) || self.counts[0].not_continuing.contains(&candidate) {
+                (WhyNotElected::ExcludedBeforeCount,self.counts[0].status.tallies.candidate[candidate.0].clone())
+            } else if let Some(count) = self.counts.iter().position(|c|matches!(&c.reason,ReasonForCount::Elimination(excluded) if excluded.contains(&candidate))) {
+                (WhyNotElected::Excluded(CountIndex(count)),self.counts[count.saturating_sub(1)].status.tallies.candidate[candidate.0].clone())
+            } else {
+                (WhyNotElected::ContinuingWhenAllSeatsFilled,last.status.tallies.candidate[candidate.0].clone())
+            };
+            res.push(NonElectionReason{candidate,why,final_tally});
+        }
+        res
+    }
+}
@@ -193,18 +193,7 @@ impl OfficialDOPForOneCount {
     pub fn paper_delta(&mut self) -> &mut PerCandidate<isize> { self.paper_delta.get_or_insert_with(Default::default) }
 }
 
-// like From<X> but implemented for usize (if there are more than 2^53 votes, the official transcript checking will have problems).
-
-pub trait CanConvertToF64PossiblyLossily {
-    fn convert_to_f64(&self) -> f64;
-}
-
-impl CanConvertToF64PossiblyLossily for usize {
-    fn convert_to_f64(&self) -> f64 { *self as f64 }
-}
-impl CanConvertToF64PossiblyLossily for isize {
-    fn convert_to_f64(&self) -> f64 { *self as f64 }
-}
+pub use crate::arithmetic::CanConvertToF64PossiblyLossily;
 
 impl OfficialDOPForOneCount {
     // given a string containing a comma separated list of 1 based counts, starting with start_count_list_string and ending in suffix,
@@ -0,0 +1,86 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Model what would happen if full preferential voting were optional preferential.
+//!
+//! Given data where voters were compelled to number every square, truncate each vote at where the voter
+//! plausibly would have stopped if allowed to, and count both. We can't know what the voters intended, so the
+//! point at which a vote is truncated is given by a [TruncationModel].
+
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use num::Zero;
+use crate::ballot_metadata::{CandidateIndex, ElectionMetadata, MissingVacancies, PartyIndex};
+use crate::ballot_paper::{ATL, BTL};
+use crate::compare_transcripts::{compare_transcripts, DifferenceBetweenTranscripts};
+use crate::distribution_of_preferences_transcript::Transcript;
+use crate::election_data::ElectionData;
+use crate::preference_distribution::{distribute_preferences, PreferenceDistributionRules};
+use crate::random_util::Randomness;
+
+/// Where a voter is assumed to have stopped numbering preferences, had they been allowed to.
+#[derive(Clone,Copy,Debug,Serialize,Deserialize,PartialEq,Eq)]
+pub enum TruncationModel {
+    /// Keep only the first n preferences : n candidates below the line, or n groups above the line.
+    AfterPreferences(usize),
+    /// Keep preferences while they are for the party of the first preference. Above the line, this is just the
+    /// first preference. Below the line, if the first preference is for a candidate not in a party that can be voted for
+    /// above the line (such as an ungrouped candidate), just the first preference is kept.
+    AfterOwnParty,
+}
+
+impl TruncationModel {
+    fn truncate_atl(&self,parties:&[PartyIndex]) -> Vec<PartyIndex> {
+        let keep = match self {
+            TruncationModel::AfterPreferences(n) => *n,
+            TruncationModel::AfterOwnParty => 1,
+        };
+        parties.iter().take(keep).cloned().collect()
+    }
+
+    fn truncate_btl(&self,candidates:&[CandidateIndex],metadata:&ElectionMetadata) -> Vec<CandidateIndex> {
+        match self {
+            TruncationModel::AfterPreferences(n) => candidates.iter().take(*n).cloned().collect(),
+            TruncationModel::AfterOwnParty => {
+                let party = candidates.first().and_then(|&c|metadata.candidate(c).party).filter(|&p|metadata.party(p).atl_allowed);
+                let same_party = candidates.iter().skip(1).take_while(|&&c|party.is_some() && metadata.candidate(c).party==party).count();
+                candidates.iter().take(1+same_party).cloned().collect()
+            }
+        }
+    }
+
+    /// Truncate every vote in the data according to this model. Vote types and counts of votes are unchanged.
+    pub fn apply(&self,data:&ElectionData) -> ElectionData {
+        let mut res = data.clone();
+        res.atl = data.atl.iter().map(|v|ATL{parties:self.truncate_atl(&v.parties),..v.clone()}).collect();
+        res.btl = data.btl.iter().map(|v|BTL{candidates:self.truncate_btl(&v.candidates,&data.metadata),n:v.n}).collect();
+        res
+    }
+}
+
+/// The result of [model_optional_preferential].
+#[derive(Clone,Debug)]
+pub struct OptionalPreferentialModel<Tally> {
+    /// The truncated data, which can be counted or otherwise analysed to see the increased exhaustion.
+    pub truncated : ElectionData,
+    /// The difference between the count of the original data (first) and that of the truncated data (second).
+    pub difference : DifferenceBetweenTranscripts,
+    /// The number of votes exhausted by the end of the count of the original data.
+    pub exhausted_full : Tally,
+    /// The number of votes exhausted by the end of the count of the truncated data.
+    pub exhausted_optional : Tally,
+}
+
+/// Count the data as is, and after truncating votes using the given model, and compare the results.
+pub fn model_optional_preferential<Rules:PreferenceDistributionRules>(data:&ElectionData,model:TruncationModel,randomness:&mut Randomness) -> Result<OptionalPreferentialModel<Rules::Tally>,MissingVacancies> {
+    let vacancies = data.metadata.vacancies.ok_or(MissingVacancies)?;
+    let truncated = model.apply(data);
+    let excluded : HashSet<CandidateIndex> = data.metadata.excluded.iter().cloned().collect();
+    let full = distribute_preferences::<Rules>(data,vacancies,&excluded,&data.metadata.tie_resolutions,None,false,randomness);
+    let optional = distribute_preferences::<Rules>(&truncated,vacancies,&excluded,&data.metadata.tie_resolutions,None,false,randomness);
+    let exhausted = |transcript:&Transcript<Rules::Tally>| transcript.counts.last().map(|count|count.status.tallies.exhausted.clone()).unwrap_or_else(Rules::Tally::zero);
+    Ok(OptionalPreferentialModel{ difference: compare_transcripts(&full,&optional), exhausted_full: exhausted(&full), exhausted_optional: exhausted(&optional), truncated })
+}
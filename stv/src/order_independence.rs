@@ -0,0 +1,60 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Check that the result of a count does not depend on the order in which ballots appear in the input.
+//!
+//! A subtle class of bugs (e.g. iterating over a HashMap or HashSet) makes results depend on the order of
+//! ballots in the input file. The legislation never depends on this, so this is a useful correctness check.
+
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha20Rng;
+use crate::compare_transcripts::{compare_transcripts, first_detailed_difference, DifferenceBetweenTranscripts};
+use crate::election_data::ElectionData;
+use crate::preference_distribution::PreferenceDistributionRules;
+use crate::random_util::Randomness;
+
+/// A copy of `data` with the order of the ATL and BTL votes shuffled, using a pseudo random number generator with the given seed.
+/// Votes are only moved within ranges that have the same vote type, weight and annotations, so those remain valid.
+pub fn shuffle_ballots(data:&ElectionData,seed:u64) -> ElectionData {
+    fn shuffle<T>(votes:&mut [T],boundaries:impl Iterator<Item=usize>,rng:&mut ChaCha20Rng) {
+        let mut boundaries : Vec<usize> = boundaries.chain([0,votes.len()]).filter(|&b|b<=votes.len()).collect();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+        for segment in boundaries.windows(2) {
+            votes[segment[0]..segment[1]].shuffle(rng);
+        }
+    }
+    let mut rng = ChaCha20Rng::seed_from_u64(seed);
+    let mut res = data.clone();
+    let atl_boundaries = data.atl_types.iter().flat_map(|s|[s.first_index_inclusive,s.last_index_exclusive])
+        .chain(data.atl_transfer_values.iter().flat_map(|s|[s.first_index_inclusive,s.last_index_exclusive]))
+        .chain(data.atl_annotations.iter().flat_map(|s|[s.first_index_inclusive,s.last_index_exclusive]));
+    shuffle(&mut res.atl,atl_boundaries,&mut rng);
+    let btl_boundaries = data.btl_types.iter().flat_map(|s|[s.first_index_inclusive,s.last_index_exclusive])
+        .chain(data.btl_transfer_values.iter().flat_map(|s|[s.first_index_inclusive,s.last_index_exclusive]))
+        .chain(data.btl_annotations.iter().flat_map(|s|[s.first_index_inclusive,s.last_index_exclusive]));
+    shuffle(&mut res.btl,btl_boundaries,&mut rng);
+    res
+}
+
+/// Count `data` (with the vacancies, exclusions and tie resolutions in its metadata) as given, and shuffled with each of the seeds,
+/// and check that the transcripts are the same. Ties not resolved by the metadata are resolved by reverse donkey vote, which does not depend on ballot order.
+///
+/// Returns a description of the first difference found, if any.
+pub fn check_order_independent<Rules:PreferenceDistributionRules>(data:&ElectionData,seeds:&[u64]) -> Result<(),String> {
+    let original = data.distribute_preferences::<Rules>(&mut Randomness::ReverseDonkeyVote);
+    for &seed in seeds {
+        let shuffled = shuffle_ballots(data,seed).distribute_preferences::<Rules>(&mut Randomness::ReverseDonkeyVote);
+        let difference = compare_transcripts(&original,&shuffled);
+        if difference!=DifferenceBetweenTranscripts::Same {
+            let location = first_detailed_difference(&original,&shuffled).map(|d|d.to_string()).unwrap_or_default();
+            return Err(format!("{} with {} rules shuffled with seed {} : {} {}",data.metadata.name.human_readable_name(),Rules::name(),seed,difference,location));
+        }
+    }
+    Ok(())
+}
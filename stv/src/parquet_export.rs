@@ -0,0 +1,90 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Export the per count, per candidate tallies and papers of a transcript as an Arrow table, and write it as a Parquet file.
+//! This is much easier to load into data science tools such as pandas or polars than the JSON transcript.
+//!
+//! There is one row for each candidate in each count, with columns
+//! * `count` : the index of the count, starting at 0, as in the `counts` array of the JSON transcript.
+//! * `count_name` : the human readable name of the count, usually the index plus one.
+//! * `candidate` : the index of the candidate, starting at 0.
+//! * `candidate_name` : the name of the candidate.
+//! * `tally` : the candidate's tally at the end of the count, possibly rounded if not an integer.
+//! * `papers` : the number of ballot papers the candidate has at the end of the count.
+//! * `status` : `Continuing`, `Elected` (in this or an earlier count) or `Excluded` (including ineligible).
+//!
+//! This requires the `parquet` feature.
+
+use std::fmt::{Debug, Display};
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use crate::arithmetic::CanConvertToF64PossiblyLossily;
+use crate::ballot_metadata::ElectionMetadata;
+use crate::distribution_of_preferences_transcript::Transcript;
+
+/// The schema of the table produced by [transcript_to_record_batch].
+pub fn transcript_table_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("count",DataType::UInt64,false),
+        Field::new("count_name",DataType::Utf8,false),
+        Field::new("candidate",DataType::UInt64,false),
+        Field::new("candidate_name",DataType::Utf8,false),
+        Field::new("tally",DataType::Float64,false),
+        Field::new("papers",DataType::UInt64,false),
+        Field::new("status",DataType::Utf8,false),
+    ])
+}
+
+/// Flatten the transcript into a table with one row per count per candidate. See the module documentation for the columns.
+pub fn transcript_to_record_batch<Tally:PartialEq+Clone+Display+FromStr+Debug+CanConvertToF64PossiblyLossily>(transcript:&Transcript<Tally>,metadata:&ElectionMetadata) -> Result<RecordBatch,ArrowError> {
+    let num_candidates = metadata.candidates.len();
+    let mut count : Vec<u64> = vec![];
+    let mut count_name : Vec<String> = vec![];
+    let mut candidate : Vec<u64> = vec![];
+    let mut candidate_name : Vec<&str> = vec![];
+    let mut tally : Vec<f64> = vec![];
+    let mut papers : Vec<u64> = vec![];
+    let mut status : Vec<&'static str> = vec![];
+    let mut elected = vec![false;num_candidates];
+    let mut not_continuing = vec![false;num_candidates];
+    for (count_index,c) in transcript.counts.iter().enumerate() {
+        for e in &c.elected { elected[e.who.0]=true; }
+        for n in &c.not_continuing { not_continuing[n.0]=true; }
+        let name = c.count_name.clone().unwrap_or_else(||(count_index+1).to_string());
+        for candidate_index in 0..num_candidates {
+            count.push(count_index as u64);
+            count_name.push(name.clone());
+            candidate.push(candidate_index as u64);
+            candidate_name.push(&metadata.candidates[candidate_index].name);
+            tally.push(c.status.tallies.candidate[candidate_index].convert_to_f64());
+            papers.push(c.status.papers.candidate[candidate_index].0 as u64);
+            status.push(if elected[candidate_index] { "Elected" } else if not_continuing[candidate_index] { "Excluded" } else { "Continuing" });
+        }
+    }
+    let columns : Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from(count)),
+        Arc::new(StringArray::from(count_name)),
+        Arc::new(UInt64Array::from(candidate)),
+        Arc::new(StringArray::from(candidate_name)),
+        Arc::new(Float64Array::from(tally)),
+        Arc::new(UInt64Array::from(papers)),
+        Arc::new(StringArray::from(status)),
+    ];
+    RecordBatch::try_new(Arc::new(transcript_table_schema()),columns)
+}
+
+/// Write the table produced by [transcript_to_record_batch] as a Parquet file.
+pub fn write_transcript_parquet<Tally:PartialEq+Clone+Display+FromStr+Debug+CanConvertToF64PossiblyLossily,W:Write+Send>(transcript:&Transcript<Tally>,metadata:&ElectionMetadata,writer:W) -> anyhow::Result<()> {
+    let batch = transcript_to_record_batch(transcript,metadata)?;
+    let mut writer = ArrowWriter::try_new(writer,batch.schema(),None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
@@ -22,7 +22,7 @@ use std::process::Command;
 use std::str::FromStr;
 use std::sync::Mutex;
 use reqwest::Url;
-use crate::ballot_paper::{RawBallotMarkings};
+use crate::ballot_paper::{GapPolicy, RawBallotMarkings};
 use crate::compare_transcripts::{DeltasInCandidateLists, DifferentCandidateLists};
 use crate::datasource_description::{AssociatedRules, Copyright};
 use crate::errors_btl::ObviousErrorsInBTLVotes;
@@ -69,6 +69,7 @@ impl CandidateAndGroupInformationBuilder {
             atl_allowed: g.ticket_id.is_some(),
             candidates: vec![],
             tickets: g.tickets.clone(),
+            official_atl_votes: None,
         }).collect();
         for candidate_index in 0..self.candidates.len() {
             let candidate = & self.candidates[candidate_index];
@@ -122,6 +123,38 @@ impl Display for MissingAlternateNamedFiles {
 impl Error for MissingAlternateNamedFiles {
 }
 
+/// A structured error for parsers of files of preferences, so library users can tell
+/// different failure modes apart. Converts into anyhow::Error like any other error.
+/// Line numbers start at 1.
+#[derive(thiserror::Error, Debug)]
+pub enum ParseError {
+    #[error("Missing file {0}")]
+    MissingFile(PathBuf),
+    #[error("Error reading file : {0}")]
+    IO(std::io::Error),
+    #[error("Unsupported format : {0}")]
+    UnsupportedFormat(String),
+    #[error("Malformed header or metadata on line {line_number} : {reason}")]
+    MalformedMetadata{line_number:usize,reason:String},
+    #[error("Malformed preference line {line_number} : {reason}")]
+    MalformedPreferenceLine{line_number:usize,reason:String},
+    #[error("Expecting candidate index between 1 and {num_candidates}, got {index} on line {line_number}")]
+    CandidateIndexOutOfRange{line_number:usize,index:i64,num_candidates:usize},
+    #[error("Unexpected end of file")]
+    UnexpectedEndOfFile,
+}
+
+impl ParseError {
+    /// Open a file, giving ParseError::MissingFile if it does not exist.
+    pub fn open(path:&Path) -> Result<File,ParseError> {
+        File::open(path).map_err(|e| if e.kind()==std::io::ErrorKind::NotFound { ParseError::MissingFile(path.to_path_buf()) } else { ParseError::IO(e) })
+    }
+}
+
+impl From<std::io::Error> for ParseError {
+    fn from(value: std::io::Error) -> Self { ParseError::IO(value) }
+}
+
 
 
 pub trait RawDataSource : KnowsAboutRawMarkings {
@@ -175,6 +208,9 @@ pub trait RawDataSource : KnowsAboutRawMarkings {
     fn can_read_raw_markings(&self) -> bool { false}
     /// Get the official transcript for the election. May not be available for all electorates.
     fn read_official_dop_transcript(&self,metadata:&ElectionMetadata) -> anyhow::Result<OfficialDistributionOfPreferencesTranscript>;
+    /// Change how ballots with a gap in their numbering are interpreted when reading raw markings.
+    /// Only possible for data sources that interpret raw markings themselves; others are given preference lists by the EC.
+    fn set_gap_policy(&mut self,_gap_policy:GapPolicy) -> anyhow::Result<()> { Err(anyhow!("This data source does not interpret raw markings, so the treatment of gaps in numbering can't be changed.")) }
 
 }
 
@@ -13,17 +13,18 @@ use num::{BigInt, Signed, ToPrimitive, Zero};
 pub use num::BigRational as BigRational;
 use crate::election_data::{ElectionData, VoteValueSpecification};
 use crate::ballot_pile::{VotesWithMultipleTransferValues, HowSplitByCountNumber, PartiallyDistributedVote, BallotPaperCount, DistributedVotes, VotesWithSameTransferValue};
-use std::collections::{HashMap, HashSet, VecDeque};
-use crate::ballot_metadata::{CandidateIndex, NumberOfCandidates};
-use crate::transfer_value::{TransferValue, StringSerializedRational};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use crate::ballot_metadata::{CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use crate::transfer_value::{TransferValue, StringSerializedRational, apportion_largest_remainder};
 use std::ops::{AddAssign, Neg, SubAssign, Sub, Range, Div, Mul};
 use std::fmt::{Debug, Display};
-use crate::distribution_of_preferences_transcript::{ElectionReason, CandidateElected, TransferValueCreation, Transcript, ReasonForCount, PortionOfReasonBeingDoneThisCount, SingleCount, EndCountStatus, PerCandidate, QuotaInfo, CountIndex};
+use crate::distribution_of_preferences_transcript::{BulkExclusionNotionalVotes, ElectionReason, CandidateElected, TransferValueCreation, Transcript, CountStopped, ReasonForCount, PortionOfReasonBeingDoneThisCount, SingleCount, EndCountStatus, PerCandidate, QuotaInfo, CountIndex, ParcelSource};
 use crate::util::{DetectUnique, CollectAll};
-use crate::tie_resolution::{MethodOfTieResolution, TieResolutionsMadeByEC, TieResolutionGranularityNeeded, TieResolutionExplicitDecision, TieResolutionUsage};
+use crate::tie_resolution::{MethodOfTieResolution, TieResolutionsMadeByEC, TieResolutionGranularityNeeded, TieResolutionExplicitDecision, TieResolutionUsage, UnresolvedTie};
 use std::hash::Hash;
 use std::iter::Sum;
 use std::cmp::{min, Ordering};
+use std::sync::atomic::AtomicBool;
 use serde::{Serialize,Deserialize};
 use std::str::FromStr;
 use crate::ballot_paper::{ATL, BTL, VoteSource};
@@ -32,13 +33,14 @@ use crate::official_dop_transcript::CanConvertToF64PossiblyLossily;
 use crate::random_util::Randomness;
 use crate::signed_version::SignedVersion;
 use crate::simple_list_of_votes::ListOfVotes;
-use crate::verify_official_transcript::OracleFromOfficialDOP;
+use crate::tally_audit::{TallyAudit, TallyAuditEntry, TallyAuditTarget, TallyMutation};
+use crate::preference_oracle::PreferenceOracle;
 
 
 /// Many systems have a special rules for termination when there are a small number of
 /// candidates left (e.g. equal to the number of if there are exactly 2 candidates left
 /// and 1 vacancy. This can be done at a variety of times.
-#[derive(Copy, Clone,Debug,Eq, PartialEq)]
+#[derive(Copy, Clone,Debug,Eq, PartialEq,Serialize,Deserialize)]
 pub enum WhenToDoElectCandidateClauseChecking {
     /// Don't do this type of check
     Never,
@@ -54,6 +56,27 @@ pub enum WhenToDoElectCandidateClauseChecking {
     AfterDeterminingWhoToExcludeButBeforeTransferringAnyPapers,
     /// Like AfterDeterminingWhoToExcludeButBeforeTransferringAnyPapers, but don't interrupt for 1 of 2.
     AfterDeterminingWhoToExcludeButBeforeTransferringAnyPapersOrQuotaButOnlyIfContinuingCandidatesEqualsUnfilledVacanciesAndNotAfterSurplusIfMoreSurplusesAvailable,
+    /// At the same times as AfterCheckingQuota, but only if [PreferenceDistributionRules::when_to_check_all_remaining_custom] returns true.
+    /// This allows unusual timings, such as only from a nominated count onward, without needing a new variant for each.
+    Custom,
+}
+
+/// The state of the count when deciding whether to do an elect candidate clause check, passed to
+/// [PreferenceDistributionRules::when_to_check_all_remaining_custom].
+#[derive(Clone,Debug)]
+pub struct ElectCandidateClauseCheckContext<'a> {
+    /// The count just done, 0 being the first preference count.
+    pub count : CountIndex,
+    /// Why the count just done was done.
+    pub reason : &'a ReasonForCount,
+    /// Whether the reason for the count has been completed, e.g. all papers for an exclusion have been transferred.
+    pub reason_completed : bool,
+    /// Whether there is a surplus that has not been fully distributed yet.
+    pub undistributed_surplus : bool,
+    /// The number of continuing candidates.
+    pub continuing : NumberOfCandidates,
+    /// The number of vacancies not yet filled.
+    pub remaining_to_elect : NumberOfCandidates,
 }
 
 #[derive(Copy,Clone,Serialize,Deserialize,Debug)]
@@ -82,7 +105,7 @@ impl TransferValueMethod {
     }
 }
 
-#[derive(Copy,Clone,Serialize,Deserialize)]
+#[derive(Copy,Clone,Serialize,Deserialize,Debug)]
 /// How to name counts.
 pub enum CountNamingMethod {
     SimpleNumber, // 1,2,3,4,... the most common method.
@@ -92,21 +115,68 @@ pub enum CountNamingMethod {
 
 
 /// What general algorithm to use to do surplus transfers.
-#[derive(Copy,Clone,Serialize,Deserialize,Eq, PartialEq)]
+#[derive(Copy,Clone,Serialize,Deserialize,Eq, PartialEq,Debug)]
 pub enum SurplusTransferMethod {
     JustOneTransferValue, // Bunch votes together and do a single transfer. E.g. Federal.
     ScaleTransferValues, // Do separate transfers based on provenance, with transfer values scaled.
     MergeSameTransferValuesAndScale, // Like ScaleTransferValues except merge transfer values and do highest first.
     PickRandomlyAfterDistribution, // NSW stochastic method - pick a subset of the ballots randomly and all have TV 1.
+    CincinnatiEveryNth, // Cambridge, Massachusetts - pick every nth ballot, in order, from the last parcel, and all have TV 1. See [crate::ballot_pile::VotesWithSameTransferValue::choose_every_nth].
 }
 
 /// In most STV you do surplus distributions before exclusions. But some (cough cough NSW) defer the surplus distributions under some conditions.
+#[derive(Copy,Clone,Debug,Serialize,Deserialize)]
 pub enum DeferSurplusDistribution {
     AlwaysDistributeAllSurplusBeforeAnyExclusions, // the thing done by almost everyone
     DeferIfSumOfUndistributedSurplussesLessThanDifferenceBetweenTwoLowestContinuingCandidates, // NSW Randomized algorithm LC
     DeferIfSumOfUndistributedSurplussesLessThanOrEqualToDifferenceBetweenTwoLowestContinuingCandidates, // NSW Randomized algorithm LGE
 }
 
+/// Whether the shortcut of electing the higher of the last two continuing candidates for the last vacancy
+/// (e.g. federal rule 17) should be held back while there is an undistributed surplus. This is separate from
+/// [PreferenceDistributionRules::when_to_check_if_just_two_standing_for_shortcut_election] which controls when the check is made at all.
+///
+/// This matters in situations like 2019 QLD Senate count 287, where G. Rennick was elected on quota leaving
+/// two continuing candidates for one vacancy with Rennick's surplus not yet distributed. Applying the shortcut
+/// immediately elects whoever is ahead before the surplus, and if they are exactly tied the casting vote decides,
+/// even though the surplus could have separated them.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Serialize,Deserialize)]
+pub enum DeferLastTwoStandingShortcut {
+    /// Apply the shortcut whenever it is checked (normal).
+    Never,
+    /// Don't apply the shortcut if the last two are exactly tied and there is a distributable surplus that could break the tie.
+    IfTiedAndSurplusDistributable,
+    /// Don't apply the shortcut while there is any distributable surplus.
+    IfSurplusDistributable,
+}
+
+/// How the value of ballot papers transferred with a transfer value is turned into a tally for each recipient.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Serialize,Deserialize)]
+pub enum TransferRounding {
+    /// Each candidate (and the exhausted pile) gets [PreferenceDistributionRules::use_transfer_value] of their papers,
+    /// each rounded separately, with the fractions lost to rounding (normal, "Gregory fractional" with rounding down).
+    PerCandidateDown,
+    /// Whole votes are apportioned to the recipients by the largest remainder (Hamilton) method so that the
+    /// amounts given out add up to the rounded down exact value transferred, typically the whole surplus ("Gregory integer").
+    LargestRemainder,
+}
+
+/// What to do when the denominator of a transfer value or surplus fraction is zero or negative.
+/// This can happen in pathological cases, e.g. when all ballots in a surplus exhaust, or when the denominator
+/// is votes minus exhausted votes, with votes rounded down and exhausted votes not.
+/// When one of these is used, it is recorded in [TransferValueCreation::denominator_policy_applied].
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Serialize,Deserialize)]
+pub enum SurplusFractionDenominatorPolicy {
+    /// Use a transfer value (or surplus fraction) of one.
+    ClampToOne,
+    /// Set the surplus aside and stop the count, as the legislation does not say what to do. See [CountStopped::SurplusFractionDenominator].
+    Error,
+    /// Use the literal computation even if it gives a negative surplus fraction, with all sorts of terrible consequences.
+    /// A zero denominator still gives one, as there is nothing else sensible.
+    AllowNegative,
+}
+
+#[derive(Copy,Clone,Debug,Serialize,Deserialize)]
 pub enum LastParcelUse {
     No, // Consider all votes (normal)
     LiterallyLast, // ACT
@@ -114,18 +184,20 @@ pub enum LastParcelUse {
     LastPlusIfItWasSurplusDistributionPriorSurplusDistributionsWithoutAnyoneElectedPlusSimilarBonusIfExclusion, // NSW 2012 bug
 }
 
-
-pub trait RoundUpToUsize {
-    /// round up to the next integer.
-    fn ceil(&self) -> usize;
+/// HYPOTHETICAL - not in any legislation, but a tool for modelling representation constraints such as gender or region quotas.
+/// At most `maximum` of `candidates` may be elected. See [PreferenceDistributor::set_representation_constraints] for how it is enforced.
+///
+/// Only maximums are modelled. A minimum (e.g. at least 2 of a group of candidates elected) can often be expressed as a maximum
+/// on the other candidates (at most vacancies-2 of them elected).
+#[derive(Clone,Debug,Eq,PartialEq,Serialize,Deserialize)]
+pub struct RepresentationConstraint {
+    /// A human readable description of the group, e.g. "Northern region".
+    pub name : String,
+    pub candidates : Vec<CandidateIndex>,
+    pub maximum : usize,
 }
 
-impl RoundUpToUsize for usize {
-    fn ceil(&self) -> usize { *self }
-}
-impl RoundUpToUsize for isize {
-    fn ceil(&self) -> usize { self.to_usize().unwrap_or(0) } // not ideal but we shouldn't expect negative vote tallies to behave sensibly. Used in margin computation and surplus distribution so should not be a problem unless its use grows.
-}
+pub use crate::arithmetic::RoundUpToUsize;
 
 pub trait PreferenceDistributionRules {
     /// The type for the number of votes. Usually an integer.
@@ -161,6 +233,10 @@ pub trait PreferenceDistributionRules {
     fn check_elected_if_in_middle_of_surplus_distribution() -> bool;
     /// If false, then don't check quota or for election when part way through an exclusion.
     fn check_elected_if_in_middle_of_exclusion() -> bool;
+    /// If a candidate is elected part way through an exclusion, whether their surplus is only distributed after the remaining papers of the
+    /// excluded candidate(s) have been transferred (true, as explicitly required in WA and Vic), or whether it is distributed before the
+    /// exclusion continues (false). Only relevant if [Self::check_elected_if_in_middle_of_exclusion] is true. Default true.
+    fn defer_surplus_of_candidate_elected_mid_exclusion() -> bool { true }
     /// An elimination may involve multiple steps. If all vacancies are filled but not all steps are finished, do you finish all the counts, even though it cannot change the result of the election?
     fn finish_all_counts_in_elimination_when_all_elected() -> bool;
     /// If all vacancies are filled but not all surplus distributions are done, do you finish the surplus distributions, even though it cannot change the result of the election?
@@ -172,12 +248,24 @@ pub trait PreferenceDistributionRules {
     fn when_to_check_if_top_few_have_overwhelming_votes() -> WhenToDoElectCandidateClauseChecking;
     /// only relevant if above is chosen, in which case require V=1.
     fn when_checking_if_top_few_have_overwhelming_votes_require_exactly_one() -> bool { false }
+    /// Only relevant if one of the when_to_check functions above returns [WhenToDoElectCandidateClauseChecking::Custom], in which case
+    /// the check is only done if this returns true for the current state of the count. Default never.
+    fn when_to_check_all_remaining_custom(_context:&ElectCandidateClauseCheckContext) -> bool { false }
 
     // how to do the elimination
 
+    /// Whether to hold back the last two standing shortcut while there is a distributable surplus. Default never.
+    fn defer_last_two_standing_shortcut() -> DeferLastTwoStandingShortcut { DeferLastTwoStandingShortcut::Never }
+
     /// Whether to defer surplus distribution and do elimination instead. This is only done in the NSW randomized algorithm, as no one else would do such a terrible thing, and so is defaulted to never.
     fn when_should_surplus_distribution_be_deferred() -> DeferSurplusDistribution { DeferSurplusDistribution::AlwaysDistributeAllSurplusBeforeAnyExclusions }
 
+    /// Only used if there is no quota (IRV). If true, then rather than excluding just the lowest candidate, exclude together the largest
+    /// group of lowest candidates whose combined tally is less than that of the next lowest candidate. They would all be excluded one at a time
+    /// before anyone else anyway, so this is much faster for large fields without changing who is elected, with the caveat that a later tie
+    /// resolved by looking back at earlier counts could see different counts.
+    fn batch_exclude_candidates_who_cannot_catch_up() -> bool { false }
+
     /// Whether the Commonwealth Electoral Act 1918, Section 273, subsection 13A multiple elimination abomination should be used. This is defaulted to false as no one else would do such a terrible thing, and even the AEC has only sometimes done it.
     fn should_eliminate_multiple_candidates_federal_rule_13a() -> bool { false }
 
@@ -205,16 +293,61 @@ pub trait PreferenceDistributionRules {
     /// Only currently implemented for the NSWEC randomized algorithm.
     fn use_f32_arithmetic_when_applying_transfer_values_instead_of_exact() -> bool { false }
 
+    /// What to do if the denominator of a transfer value (continuing ballots) or surplus fraction (votes, or votes less exhausted votes) is zero or negative.
     /// If a surplus distribution transfer is computed by a "surplus fraction" times the original transfer value,
     /// and the "surplus fraction" is computed by surplus/(votes-exhausted votes), and the votes is rounded down,
     /// and the exhausted votes isn't, then the denominator could be negative. The NSW LGE legislation seems to
-    /// allow this. Set this to be false if you want to allow this behaviour, which then causes all sorts of terrible problems.
-    fn prohibit_negative_surplus_fraction() -> bool { true }
+    /// allow this. Default [SurplusFractionDenominatorPolicy::ClampToOne].
+    fn surplus_fraction_denominator_policy() -> SurplusFractionDenominatorPolicy { SurplusFractionDenominatorPolicy::ClampToOne }
+
+    /// When several candidates reach quota in the same count, whether they are declared elected highest tally first (the usual case)
+    /// or lowest tally first. Surpluses are distributed in the same order as the candidates are declared elected.
+    fn elect_highest_first() -> bool { true }
+
+    /// If true, then after each count in which candidates are elected by reaching the quota, the quota for the remaining
+    /// vacancies is recomputed as round_down(V/(1+remaining vacancies))+1, where V is the value of all votes not yet
+    /// used to fill a seat: the sum of the tallies of the continuing candidates, plus the undistributed surpluses of
+    /// elected candidates. Votes that have exhausted, been lost to rounding or set aside are thus not counted.
+    ///
+    /// The surplus of an elected candidate is always relative to the quota in force when they were elected.
+    /// This is not the same as the Meek method, where the quota is recomputed continuously and elected candidates keep votes.
+    fn recompute_quota_after_each_election() -> bool { false }
+
+    /// If true, a candidate is only elected by quota if their tally strictly exceeds the quota, rather than reaching it.
+    /// A candidate whose tally equals the quota then stays continuing (with no surplus) until some later count takes them over it.
+    ///
+    /// None of the jurisdictions modeled here do this; their legislation elects a candidate whose votes are "equal to or greater than" the quota.
+    /// It models descriptions of STV using the exact Droop quota V/(N+1) without rounding or the +1, such as Droop's
+    /// original formulation and some textbooks, where a candidate must "exceed" the quota.
+    /// Default false.
+    fn quota_is_strict_greater() -> bool { false }
+
+    /// How the amounts given to each recipient of a transfer are rounded. Default [TransferRounding::PerCandidateDown].
+    /// Not applied to the NSW random selection method of surplus distribution.
+    fn transfer_rounding() -> TransferRounding { TransferRounding::PerCandidateDown }
+
+    /// EXPERIMENTAL - this is not in any legislation I know of, but is a reform that has been proposed to avoid wasted votes.
+    /// If true, then whenever votes would be exhausted in a transfer, the value they would have had is instead
+    /// given to the continuing candidates in proportion to their current tallies. The ballot papers are still counted as exhausted.
+    /// Any fraction lost when converting the proportional shares to a tally goes to rounding, as does the redistributed
+    /// value held by a candidate who is later excluded (as there are no ballot papers to pass it on with).
+    /// Not applied to the NSW random selection method of surplus distribution.
+    fn redistribute_exhausted_proportionally() -> bool { false }
+
+    /// HYPOTHETICAL - this is not in any legislation I know of, but is a reform that has been proposed to avoid
+    /// distributing tiny fractions of votes. If Some, then whenever ballot papers would be transferred in a surplus distribution
+    /// or exclusion with a transfer value below this, they are instead set aside : their value goes into the set aside tally,
+    /// and the papers are counted as set aside rather than going to any candidate or being exhausted.
+    /// Not applied to first preferences or to the NSW random selection method of surplus distribution.
+    fn minimum_transfer_value() -> Option<TransferValue> { None }
 
     /// Change the votes otherwise being classified as exhausted. Changes will go into the lost due to rounding tally.
     fn munge_exhausted_votes(exhausted:Self::Tally,_is_exclusion:bool) -> Self::Tally { exhausted }
     /// Change the transfer value when it is being used as a limit (e.g. in ACT rule 1C(4))
     fn munge_transfer_value_when_used_as_limit(original:TransferValue) -> TransferValue { original }
+    /// Change the surplus fraction (the factor by which incoming transfer values are scaled) when the surplus transfer method is
+    /// ScaleTransferValues or MergeSameTransferValuesAndScale, e.g. rounding it to a fixed number of decimal places.
+    fn munge_surplus_fraction(fraction:TransferValue) -> TransferValue { fraction }
     /// In exclusions, sort the sub-counts by this function. Used to support wierd bug in NSWEC2021.
     /// Also used in surplus distributions when the surplus transfer method is ScaleTransferValues.
     fn sort_subcounts_by_count() -> Option<Box<dyn FnMut(&Transcript<Self::Tally>,<<Self as PreferenceDistributionRules>::SplitByNumber as HowSplitByCountNumber>::KeyToDivide,<<Self as PreferenceDistributionRules>::SplitByNumber as HowSplitByCountNumber>::KeyToDivide) -> Ordering>> { None }
@@ -225,7 +358,10 @@ struct PendingTranscript<Tally> {
     not_continuing : Vec<CandidateIndex>,
     created_transfer_value : Option<TransferValueCreation<Tally>>,
     decisions : Vec<TieResolutionExplicitDecision>,
-    set_aside_for_quota: Option<PerCandidate<BallotPaperCount>>
+    set_aside_for_quota: Option<PerCandidate<BallotPaperCount>>,
+    bulk_exclusion : Option<BulkExclusionNotionalVotes<Tally>>,
+    new_quota : Option<Tally>,
+    parcel_sources : Option<Vec<ParcelSource>>,
 }
 
 /// The main workhorse class that does preference distribution.
@@ -235,11 +371,15 @@ pub struct PreferenceDistributor<'a,Rules:PreferenceDistributionRules> {
     original_votes:&'a Vec<(TransferValue,Vec<PartiallyDistributedVote<'a>>)>,
     num_candidates : usize,
     candidates_to_be_elected : NumberOfCandidates,
+    /// The quota for the remaining vacancies. Usually constant, see [PreferenceDistributionRules::recompute_quota_after_each_election].
     quota : Rules::Tally,
+    /// The quota that applied when a given candidate was elected.
+    quota_when_elected : Vec<Option<Rules::Tally>>,
     /// The tally, by candidate.
     tallys : Vec<Rules::Tally>,
     /// the papers that a particular candidate currently has.
     papers : Vec<VotesWithMultipleTransferValues<'a,Rules::SplitByNumber,Rules::Tally>>,
+    /// Only used for membership tests, as iteration order is arbitrary. Use continuing_candidates_sorted_by_tally for anything ordered.
     continuing_candidates : HashSet<CandidateIndex>,
     /// Candidates sorted lowest first, highest last.
     continuing_candidates_sorted_by_tally : Vec<CandidateIndex>,
@@ -248,6 +388,8 @@ pub struct PreferenceDistributor<'a,Rules:PreferenceDistributionRules> {
     tally_lost_to_rounding : SignedVersion<Rules::Tally>, // may be negative if rounding is up.
     tally_exhausted : Rules::Tally,
     tally_set_aside : Option<Rules::Tally>,
+    /// ballot papers set aside, see [PreferenceDistributionRules::minimum_transfer_value].
+    papers_set_aside : Option<BallotPaperCount>,
     current_count : CountIndex,
     current_major_count : CountIndex,
     current_minor_count : CountIndex,
@@ -260,17 +402,38 @@ pub struct PreferenceDistributor<'a,Rules:PreferenceDistributionRules> {
     in_this_count : PendingTranscript<Rules::Tally>,
     pub(crate) transcript : Transcript<Rules::Tally>,
     print_progress_to_stdout : bool, // if true, then print tallys etc to stdout.
-    oracle : Option<OracleFromOfficialDOP<'a>>,
+    /// if present, may override where votes go and resolve ties. See [PreferenceOracle].
+    oracle : Option<&'a mut dyn PreferenceOracle>,
     extractors : &'a [ExtractionRequest],
     include_list_of_votes_in_transcript : bool,
     exhausted_list_of_votes : ListOfVotes,
     set_aside_list_of_votes : ListOfVotes,
     last_written_list_of_votes_by_candidate : Vec<ListOfVotes>,
+    /// if present, every change to a tally is logged here.
+    tally_audit : Option<TallyAudit<Rules::Tally>>,
+    /// candidates who will not be excluded, see [Self::set_protected].
+    protected : HashSet<CandidateIndex>,
+    /// if present, used instead of computing the quota. See [Self::set_fixed_quota].
+    fixed_quota : Option<Rules::Tally>,
+    /// See [Self::set_always_finish_all_counts].
+    always_finish_all_counts : bool,
+    /// See [Self::set_strict_ties].
+    strict_ties : bool,
+    /// See [Self::set_cancel].
+    cancel : Option<&'a AtomicBool>,
+    /// true once the count would normally have stopped, and further counts are only being done because of always_finish_all_counts.
+    finishing_counts_after_normal_end : bool,
+    /// See [Self::set_retain_full_count_status].
+    retain_paper_counts_for_every_count : bool,
+    /// candidates who receive no transfers after a given count, see [Self::set_freeze_candidate_after].
+    freeze_candidate_after : Vec<(CandidateIndex,CountIndex)>,
+    /// See [Self::set_representation_constraints].
+    representation_constraints : Vec<RepresentationConstraint>,
 }
 
 impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
 {
-    pub fn new(data : &'a ElectionData,original_votes:&'a Vec<(TransferValue,Vec<PartiallyDistributedVote<'a>>)>,candidates_to_be_elected : NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:&'a TieResolutionsMadeByEC,print_progress_to_stdout : bool,oracle : Option<OracleFromOfficialDOP<'a>>,randomness:&'a mut Randomness,extractors:&'a [ExtractionRequest],include_list_of_votes_in_transcript:bool) -> Self {
+    pub fn new(data : &'a ElectionData,original_votes:&'a Vec<(TransferValue,Vec<PartiallyDistributedVote<'a>>)>,candidates_to_be_elected : NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:&'a TieResolutionsMadeByEC,print_progress_to_stdout : bool,oracle : Option<&'a mut dyn PreferenceOracle>,randomness:&'a mut Randomness,extractors:&'a [ExtractionRequest],include_list_of_votes_in_transcript:bool) -> Self {
         let num_candidates = data.metadata.candidates.len();
         let tallys = vec![Rules::Tally::zero();num_candidates];
         let mut papers = vec![];
@@ -278,7 +441,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
         let mut continuing_candidates : HashSet<CandidateIndex> = HashSet::default();
         let mut continuing_candidates_sorted_by_tally = vec![];
         for i in 0..num_candidates {
-            if !excluded_candidates.contains(&CandidateIndex(i)) {
+            if !excluded_candidates.contains(&CandidateIndex(i)) && !data.metadata.withdrawn_before_count.contains(&CandidateIndex(i)) {
                 continuing_candidates.insert(CandidateIndex(i));
                 continuing_candidates_sorted_by_tally.push(CandidateIndex(i));
             }
@@ -299,12 +462,14 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             tally_lost_to_rounding: Zero::zero(),
             tally_exhausted: Rules::Tally::zero(),
             tally_set_aside: None,
+            papers_set_aside: None,
             current_count : CountIndex(0),
             current_major_count: CountIndex(1),
             current_minor_count : CountIndex(1),
             pending_surplus_distribution : VecDeque::default(),
             elected_candidates : vec![],
             candidate_elected_at_count: vec![None;num_candidates],
+            quota_when_elected: vec![None;num_candidates],
             randomness,
             in_this_count : PendingTranscript {
                 elected: vec![],
@@ -312,12 +477,18 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
                 created_transfer_value: None,
                 decisions: vec![],
                 set_aside_for_quota: None,
+                bulk_exclusion: None,
+                new_quota: None,
+                parcel_sources: None,
             },
             transcript : Transcript {
                 rules : Rules::name(),
                 quota: None,
                 counts: vec![],
-                elected: vec![]
+                elected: vec![],
+                undersubscribed: false,
+                randomness_beacon: None,
+                stopped: None,
             },
             print_progress_to_stdout,
             oracle,
@@ -326,22 +497,135 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             exhausted_list_of_votes: Default::default(),
             set_aside_list_of_votes: Default::default(),
             last_written_list_of_votes_by_candidate: vec![ListOfVotes::default();num_candidates],
+            tally_audit: None,
+            protected: HashSet::default(),
+            fixed_quota: None,
+            always_finish_all_counts: false,
+            strict_ties: false,
+            cancel: None,
+            finishing_counts_after_normal_end: false,
+            retain_paper_counts_for_every_count: true,
+            freeze_candidate_after: vec![],
+            representation_constraints: vec![],
+        }
+    }
+
+    /// HYPOTHETICAL - this is not in any legislation, but is a tool for asking "what if these candidates were guaranteed not to be excluded".
+    /// Protected candidates are skipped when choosing the lowest candidate(s) to exclude, even if they have the lowest tally.
+    /// They can still be elected, or lose, in the normal way. If every continuing candidate is protected, the lowest is excluded anyway.
+    pub fn set_protected(&mut self,protected:&[CandidateIndex]) { self.protected=protected.iter().cloned().collect(); }
+
+    /// HYPOTHETICAL - this is not in any legislation, but is a tool for modelling a candidate becoming ineligible partway through a count
+    /// (e.g. disqualified mid-count). For each `(candidate,count)`, in counts after `count` (0 being the first preference count)
+    /// the candidate receives no more papers; papers that would go to them skip past them to the next continuing candidate as if they
+    /// were not continuing. Their tally is otherwise frozen, and they can still be elected or excluded in the normal way,
+    /// in which case papers they already hold are distributed as usual.
+    pub fn set_freeze_candidate_after(&mut self,freeze_candidate_after:&[(CandidateIndex,CountIndex)]) { self.freeze_candidate_after=freeze_candidate_after.to_vec(); }
+
+    /// HYPOTHETICAL - this is not in any legislation, but is a tool for modelling representation constraints. Modelling assumptions:
+    /// * A candidate whose election would take a group over its maximum is "blocked". Blocked candidates are skipped when electing
+    ///   candidates who reached a quota ([Self::check_elected_by_quota]), highest of the last two standing, or all remaining candidates
+    ///   ([Self::check_if_should_elect_all_remaining], which then only counts candidates who are not blocked), and the next continuing
+    ///   candidate is considered instead. Candidates elected in the same count are considered in the order they are elected.
+    /// * A blocked candidate can never be elected, so is excluded at the next exclusion, ahead of any other continuing candidate
+    ///   whatever their tallies, so their papers go to candidates who can still be elected. Bulk exclusion is not done while any are blocked.
+    /// * A blocked candidate keeps their tally (even if it is over the quota) until excluded; nothing is done with any surplus.
+    /// * Other ways of being elected, such as [Self::check_if_top_few_have_overwhelming_votes], are not constrained.
+    /// * If the constraints leave too few candidates who can be elected, some vacancies are left unfilled.
+    pub fn set_representation_constraints(&mut self,constraints:&[RepresentationConstraint]) { self.representation_constraints=constraints.to_vec(); }
+
+    /// Whether electing the candidate now would violate one of the [RepresentationConstraint]s. See [Self::set_representation_constraints].
+    pub fn blocked_by_representation_constraint(&self,candidate:CandidateIndex) -> bool {
+        self.representation_constraints.iter().any(|constraint|constraint.candidates.contains(&candidate) && constraint.candidates.iter().filter(|c|self.elected_candidates.contains(c)).count()>=constraint.maximum)
+    }
+
+    /// The continuing candidates, lowest tally first, who can no longer be elected because of a [RepresentationConstraint].
+    fn continuing_candidates_blocked_by_representation_constraint(&self) -> Vec<CandidateIndex> {
+        if self.representation_constraints.is_empty() { return vec![]; }
+        self.continuing_candidates_sorted_by_tally.iter().filter(|&&c|self.blocked_by_representation_constraint(c)).cloned().collect()
+    }
+
+    /// If some continuing candidates are currently frozen by [Self::set_freeze_candidate_after], the continuing candidates who can receive papers. None if no one is frozen.
+    fn candidates_receiving_transfers_if_some_frozen(&self) -> Option<HashSet<CandidateIndex>> {
+        let frozen_now = |&&(candidate,count):&&(CandidateIndex,CountIndex)| self.current_count>count && self.continuing_candidates.contains(&candidate);
+        if self.freeze_candidate_after.iter().any(|f|frozen_now(&f)) {
+            let mut receiving = self.continuing_candidates.clone();
+            for (candidate,_) in self.freeze_candidate_after.iter().filter(frozen_now) { receiving.remove(candidate); }
+            Some(receiving)
+        } else { None }
+    }
+
+    /// DEBUGGING - not in any legislation. After the count would normally stop, keep going, distributing any undistributed surpluses
+    /// and excluding the lowest continuing candidate until no candidates are continuing, so the full transfer picture is visible.
+    /// This overrides early termination in the rules (such as [PreferenceDistributionRules::finish_all_surplus_distributions_when_all_elected]).
+    /// It never changes who is elected (or in what order), as no one is elected in the extra counts.
+    pub fn set_always_finish_all_counts(&mut self,always_finish_all_counts:bool) { self.always_finish_all_counts=always_finish_all_counts; }
+
+    /// If true, stop the count (see [Transcript::stopped]) at a tie that the legislation provides no procedure for
+    /// ([MethodOfTieResolution::None]) and that is not resolved by an oracle or the supplied [TieResolutionsMadeByEC],
+    /// rather than resolving it by randomness. The count in which the tie came up is finished first.
+    pub fn set_strict_ties(&mut self,strict_ties:bool) { self.strict_ties=strict_ties; }
+
+    /// If the flag is set (e.g. from another thread), stop the count (see [Transcript::stopped]) before starting the next count.
+    /// Useful to put a time limit on a count.
+    pub fn set_cancel(&mut self,cancel:Option<&'a AtomicBool>) { self.cancel=cancel; }
+
+    /// If false, only keep the per candidate paper counts (`status.papers.candidate` and `status.atl_papers`) for the last count,
+    /// leaving them empty for earlier counts. This saves two allocations per count, which adds up for contests with many counts,
+    /// for callers who only need the final result. Tallies are always kept, as they are needed to resolve ties by looking back at earlier counts.
+    /// Default true.
+    pub fn set_retain_full_count_status(&mut self,retain:bool) { self.retain_paper_counts_for_every_count=retain; }
+
+    /// Use the given quota rather than computing it from the first preferences. Used when resuming a count from [Self::export_current_state_as_election_data].
+    pub fn set_fixed_quota(&mut self,quota:Rules::Tally) { self.fixed_quota=Some(quota); }
+
+    /// Start logging every change to a tally. See [crate::tally_audit].
+    pub fn enable_tally_audit(&mut self) { self.tally_audit=Some(TallyAudit::default()); }
+
+    /// Get the log of tally changes, if [Self::enable_tally_audit] was called.
+    pub fn take_tally_audit(&mut self) -> Option<TallyAudit<Rules::Tally>> { self.tally_audit.take() }
+
+    /// Record that `change` was added to `target` in the tally audit, if enabled.
+    fn audit(&mut self,target:TallyAuditTarget,why:TallyMutation,change:impl FnOnce()->SignedVersion<Rules::Tally>,transfer_value:Option<&TransferValue>) {
+        if let Some(audit) = &mut self.tally_audit {
+            audit.entries.push(TallyAuditEntry{ count: self.current_count, target, why, change: change(), transfer_value: transfer_value.cloned() });
         }
     }
 
     /// distribute the given votes by the next preference (unless there is an oracle overriding it).
-    pub fn distribute(&mut self,votes:&Vec<PartiallyDistributedVote<'a>>) -> DistributedVotes<'a> {
+    pub fn distribute(&mut self,votes:&Vec<PartiallyDistributedVote<'a>>) -> DistributedVotes<'a> { self.distribute_from(&[],votes) }
+
+    /// Like [Self::distribute], telling the oracle (if any) which candidates the votes belong to.
+    fn distribute_from(&mut self,from:&[CandidateIndex],votes:&Vec<PartiallyDistributedVote<'a>>) -> DistributedVotes<'a> {
+        let some_frozen = self.candidates_receiving_transfers_if_some_frozen();
+        let receiving = some_frozen.as_ref().unwrap_or(&self.continuing_candidates);
+        let natural = DistributedVotes::distribute(votes,receiving,self.num_candidates);
         if let Some(oracle) = &mut self.oracle {
-            if let Some(mut oracle_by_candidate) = oracle.get_distribution_by_candidate(self.current_count) {
-                return DistributedVotes::distribute_by_oracle(votes,&self.continuing_candidates,self.num_candidates,&mut oracle_by_candidate)
+            if let Some(mut oracle_by_candidate) = oracle.get_distribution_by_candidate(self.current_count,from,&natural.ballot_paper_counts()) {
+                return DistributedVotes::distribute_by_oracle(votes,receiving,self.num_candidates,&mut oracle_by_candidate)
             }
         }
-        DistributedVotes::distribute(votes,&self.continuing_candidates,self.num_candidates)
+        natural
     }
 
+    /// Distribute first preferences, and compute the quota.
+    ///
+    /// Candidates who are excluded or withdrawn before the count are not continuing, so votes go to the first continuing candidate.
+    /// Votes for no continuing candidate are exhausted, and are part of the quota base iff [PreferenceDistributionRules::should_exhausted_votes_count_for_quota_computation].
+    /// The exception is votes only for candidates [ElectionMetadata::withdrawn_before_count], which are not counted at all, as if they had been left blank.
+    ///
+    /// If there are no more continuing candidates than vacancies, they are all elected at the end of the first preference count, and the
+    /// transcript is marked [Transcript::undersubscribed]. Otherwise the count would exclude candidates it should elect.
     pub fn distribute_first_preferences(& mut self) {
+        self.transcript.undersubscribed = self.continuing_candidates.len()<=self.candidates_to_be_elected.0;
         let mut total_first_preferences = Rules::Tally::zero();
+        let withdrawn = &self.data.metadata.withdrawn_before_count;
         for (tv,original_votes) in self.original_votes {
+            let votes_not_just_for_withdrawn : Vec<PartiallyDistributedVote<'a>>;
+            let original_votes = if withdrawn.is_empty() { original_votes } else {
+                votes_not_just_for_withdrawn = original_votes.iter().filter(|v|v.prefs.iter().any(|c|!withdrawn.contains(c))).cloned().collect();
+                &votes_not_just_for_withdrawn
+            };
             let mut ballots_with_this_tv = BallotPaperCount(0);
             for v in original_votes { ballots_with_this_tv+=v.n; }
             let original_worth = Rules::use_transfer_value(tv,ballots_with_this_tv);
@@ -355,6 +639,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             let is_last_step = tv==&self.original_votes.last().unwrap().0;
             if is_last_step {
                 self.compute_quota(total_first_preferences.clone());
+                if self.transcript.undersubscribed { self.elect_all_continuing_as_undersubscribed(); }
             }
             self.end_of_count_step(ReasonForCount::FirstPreferenceCount, PortionOfReasonBeingDoneThisCount {
                 transfer_value: Some(tv.clone()),
@@ -364,6 +649,15 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
         }
     }
 
+    /// Elect all the continuing candidates, highest tally first, as there are not enough candidates to fill the vacancies.
+    fn elect_all_continuing_as_undersubscribed(&mut self) {
+        self.resort_candidates();
+        if self.print_progress_to_stdout { println!("Only {} candidates for {} vacancies, so all are elected.",self.continuing_candidates.len(),self.candidates_to_be_elected); }
+        for c in self.continuing_candidates_sorted_by_tally.clone().into_iter().rev() {
+            self.declare_elected(c,ElectionReason::AllRemainingMustBeElected);
+        }
+    }
+
     pub fn resort_candidates(&mut self) {
         let tallies = &self.tallys;
         let key = |c:&CandidateIndex|tallies[c.0].clone();
@@ -371,10 +665,14 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
     }
 
     /// quota = round_down(first_preferences/(1+num_to_elect))+1
+    ///
+    /// first_preferences is the total value of the first preference tallies, not the number of papers, so
+    /// votes with a starting weight other than 1 ([ElectionData::btl_transfer_values]) count for their value. If that
+    /// value is not an integer it is rounded down before dividing.
     pub fn compute_quota(&mut self,total_first_preferences:Rules::Tally) {
         if Rules::has_quota() {
             let total_first_preferences = BallotPaperCount(Rules::convert_tally_to_rational(total_first_preferences.clone()).to_integer().to_usize().unwrap()); // usually trivial and valid, unless there are papers with TV other than 1, in which case rounded down.
-            self.quota = Rules::Tally::from(BallotPaperCount(total_first_preferences.0/(1+self.candidates_to_be_elected.0)+1));
+            self.quota = self.fixed_quota.clone().unwrap_or_else(||Rules::Tally::from(BallotPaperCount(total_first_preferences.0/(1+self.candidates_to_be_elected.0)+1)));
             self.transcript.quota = Some(QuotaInfo{
                 papers: total_first_preferences,
                 vacancies: self.candidates_to_be_elected,
@@ -386,8 +684,34 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
         }
     }
 
+    /// Recompute the quota for the remaining vacancies, see [PreferenceDistributionRules::recompute_quota_after_each_election].
+    fn recompute_quota(&mut self) {
+        let remaining = self.remaining_to_elect();
+        if remaining.0==0 { return; }
+        let mut value = self.total_undistributed_surplus_votes();
+        for &candidate in &self.continuing_candidates_sorted_by_tally { value+=self.tally(candidate); }
+        let value = Rules::convert_tally_to_rational(value).to_integer().to_usize().unwrap(); // rounded down, as in compute_quota.
+        self.quota = Rules::Tally::from(BallotPaperCount(value/(1+remaining.0)+1));
+        self.in_this_count.new_quota = Some(self.quota.clone());
+        if self.print_progress_to_stdout { println!("Quota recomputed as {}", self.quota); }
+    }
+
+    /// The quota relevant to a candidate's surplus; the quota when they were elected, or the current quota if not elected.
+    fn quota_for(&self,candidate:CandidateIndex) -> Rules::Tally { self.quota_when_elected[candidate.0].clone().unwrap_or_else(||self.quota.clone()) }
+
     pub fn tally(&self,candidate:CandidateIndex) -> Rules::Tally { self.tallys[candidate.0].clone() }
 
+    /// The current quota.
+    pub fn quota(&self) -> Rules::Tally { self.quota.clone() }
+    /// The continuing candidates, lowest tally first.
+    pub fn continuing_candidates_sorted_by_tally(&self) -> &[CandidateIndex] { &self.continuing_candidates_sorted_by_tally }
+    /// Elected candidates whose surpluses have not yet been distributed, and the surplus, in the order they will be distributed.
+    pub fn pending_surpluses(&self) -> Vec<(CandidateIndex,Rules::Tally)> { self.pending_surplus_distribution.iter().map(|&c|(c,self.tally(c)-self.quota_for(c))).collect() }
+    /// The transcript of the counts done so far.
+    pub fn transcript(&self) -> &Transcript<Rules::Tally> { &self.transcript }
+    /// The transcript of the counts done, consuming self.
+    pub fn into_transcript(self) -> Transcript<Rules::Tally> { self.transcript }
+
     // declare that a candidate is no longer continuing.
     fn no_longer_continuing(&mut self,candidate:CandidateIndex,used_in_current_count:bool) {
         if !used_in_current_count { self.in_this_count.not_continuing.push(candidate); }
@@ -401,6 +725,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
         self.transcript.elected.push(who);
         self.no_longer_continuing(who,true);
         self.candidate_elected_at_count[who.0]=Some(self.current_count);
+        self.quota_when_elected[who.0]=Some(self.quota.clone());
     }
 
     /// Make new election data consisting of the votes that were used to elect this candidate, using the ACT legislation for Casual Vacancies, Part 4.3 of schedule 4
@@ -411,7 +736,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
         let mut metadata = self.data.metadata.clone();
         metadata.vacancies=Some(NumberOfCandidates(1));
         metadata.name.modifications.push(format!("Votes that resulted in {} becoming elected",self.data.metadata.candidate(who).name));
-        let mut by_transfer_value : HashMap<TransferValue,Vec<VotesWithSameTransferValue<'a>>> = HashMap::new();
+        let mut by_transfer_value : BTreeMap<TransferValue,Vec<VotesWithSameTransferValue<'a>>> = BTreeMap::new();
         let mut add_votes = |tv:TransferValue,votes:VotesWithSameTransferValue<'a>|{
             by_transfer_value.entry(tv).or_default().push(votes);
         };
@@ -421,7 +746,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             add_votes(tv,votes)
         }
         if let Some((tv,votes)) = last_parcel_votes {
-            let q_minus_n : BigRational = Rules::convert_tally_to_rational(self.quota.clone()-votes_prior_round.clone());
+            let q_minus_n : BigRational = Rules::convert_tally_to_rational(self.quota_for(who)-votes_prior_round.clone());
             let mut ballots_with_next_available_preference = VotesWithSameTransferValue::default();
             let mut ballots_without_next_available_preference = VotesWithSameTransferValue::default();
             for vote in votes.votes {
@@ -457,51 +782,38 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             }
         };
         // got all the votes in by_transfer_value, now just need to order them and convert to ElectionData.
-        let mut atl : Vec<ATL> = vec![];
-        let mut atl_transfer_values : Vec<VoteValueSpecification> = vec![];
-        let mut btl : Vec<BTL> = vec![];
-        let mut btl_transfer_values : Vec<VoteValueSpecification> = vec![];
-        let mut ordered_transfer_values : Vec<(TransferValue,Vec<VotesWithSameTransferValue<'a>>)> = by_transfer_value.into_iter().collect();
-        ordered_transfer_values.sort_by(|(tv1,_),(tv2,_)|tv2.cmp(tv1));
-        for (tv,votes_vec) in ordered_transfer_values {
-            let atl_start = atl.len();
-            let btl_start = btl.len();
-            for votes in votes_vec {
-                for vote in votes.votes {
-                    match vote.source {
-                        VoteSource::Btl(source) => { btl.push(BTL{ candidates: source.candidates.clone(), n: vote.n.0 }); }
-                        VoteSource::Atl(source) => { atl.push(ATL{ parties: source.parties.clone(), n: vote.n.0, ticket_index: None }); }
-                    }
-                }
-            }
-            if atl_start!=atl.len() {
-                atl_transfer_values.push(VoteValueSpecification{
-                    value: tv.clone(),
-                    first_index_inclusive: atl_start,
-                    last_index_exclusive: atl.len(),
-                })
-            }
-            if btl_start!=btl.len() {
-                btl_transfer_values.push(VoteValueSpecification{
-                    value: tv,
-                    first_index_inclusive: btl_start,
-                    last_index_exclusive: btl.len(),
-                })
+        votes_by_transfer_value_to_election_data(metadata,by_transfer_value)
+    }
+
+    /// Make new election data representing the state of the count right now : the continuing candidates, and the
+    /// ballot papers they hold at their current transfer values. Counting it from scratch, with the quota fixed at the
+    /// current quota (see [CountOptions::fixed_quota]), reproduces the remainder of this count. This is
+    /// useful for teaching, and for resuming a count.
+    ///
+    /// * Candidates who are elected with no surplus pending, or excluded, are listed as excluded. Papers still held by
+    ///   a candidate part way through being excluded are included, and so will go to their next continuing preference.
+    /// * Candidates elected with a surplus still to be distributed are kept, with their papers, and the number of
+    ///   vacancies includes them, so they will be elected again in the first count and then have their surplus distributed.
+    ///
+    /// This is exact for the common rules, but some information is lost : which count papers arrived in (so last parcel rules
+    /// will treat all of an elected candidate's papers as the last parcel), rounding already lost, and any redirection by an oracle.
+    /// Tallies are recomputed from the papers, so may differ slightly from the current tallies if rounding was done per transfer.
+    pub fn export_current_state_as_election_data(&self) -> ElectionData {
+        let mut metadata = self.data.metadata.clone();
+        let keep : HashSet<CandidateIndex> = self.continuing_candidates.iter().chain(self.pending_surplus_distribution.iter()).cloned().collect();
+        metadata.vacancies=Some(NumberOfCandidates(self.remaining_to_elect().0+self.pending_surplus_distribution.len()));
+        metadata.excluded=(0..self.num_candidates).map(CandidateIndex).filter(|c|!keep.contains(c)).collect();
+        metadata.name.modifications.push(format!("State of the count after {} counts",self.transcript.counts.len()));
+        let mut by_transfer_value : BTreeMap<TransferValue,Vec<VotesWithSameTransferValue<'a>>> = BTreeMap::new();
+        for (candidate,papers) in self.papers.iter().enumerate() {
+            if self.elected_candidates.contains(&CandidateIndex(candidate)) && !keep.contains(&CandidateIndex(candidate)) { continue; } // their papers are used up in their quota.
+            for (tv,votes) in papers.duplicate_all_votes(false).0 {
+                by_transfer_value.entry(tv).or_default().push(votes);
             }
-        };
-        ElectionData{
-            metadata,
-            atl,
-            atl_types: vec![],
-            atl_transfer_values,
-            btl,
-            btl_types: vec![],
-            btl_transfer_values,
-            informal: 0,
         }
+        votes_by_transfer_value_to_election_data(metadata,by_transfer_value)
     }
 
-
     /// See if there are any ties in the tallys for the candidates in
     /// to_check (which should be already sorted by tally). If there are,
     /// resolve them, first using "how", secondly using an Oracle, if present,
@@ -520,17 +832,20 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
                     TieResolutionGranularityNeeded::LowestSeparated(n) if n<=differs && n>i  => Some(TieResolutionGranularityNeeded::LowestSeparated(n-i)),
                     _ => None, // no resolution needed as all in or all not in.
                 } {
-                    for (still_tied,remaining_granularity) in how.resolve(tied,&self.transcript,sub_granularity) {
+                    for (still_tied,remaining_granularity) in how.resolve(tied,&self.transcript,&self.data.metadata,sub_granularity) {
                         let solved_by_oracle = if let Some(oracle) = &mut self.oracle {
                             if let Some(solution) = oracle.resolve_tie_resolution(self.current_count,remaining_granularity,still_tied) {
-                                let resolutions = TieResolutionsMadeByEC{ tie_resolutions: vec![solution] };
-                                let decision = resolutions.resolve(still_tied,remaining_granularity,usage,self.current_count,&mut self.randomness);
+                                let resolutions = TieResolutionsMadeByEC{ tie_resolutions: vec![solution], default_policies: vec![] };
+                                let decision = resolutions.resolve(still_tied,remaining_granularity,usage,self.current_count,&self.data.metadata,&mut self.randomness);
                                 self.in_this_count.decisions.push(decision);
                                 true
                             } else { false }
                         } else { false };
                         if !solved_by_oracle {
-                            let decision = self.ec_resolutions.resolve(still_tied,remaining_granularity,usage,self.current_count,&mut self.randomness);
+                            let decision = self.ec_resolutions.resolve(still_tied,remaining_granularity,usage,self.current_count,&self.data.metadata,&mut self.randomness);
+                            if self.strict_ties && matches!(how,MethodOfTieResolution::None) && self.transcript.stopped.is_none() && !self.ec_resolutions.is_determined_by_ec(&decision,self.current_count,&self.data.metadata) {
+                                self.transcript.stopped=Some(CountStopped::UnresolvedTie(UnresolvedTie::new(self.current_count,&decision,&self.data.metadata)));
+                            }
                             self.in_this_count.decisions.push(decision);
                         }
                     }
@@ -548,14 +863,22 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
         self.continuing_candidates_sorted_by_tally[to_check].copy_from_slice(&tied_candidates); // copy resolved order back.
     }
 
+    /// Whether candidate c has enough votes to be elected by quota, see [PreferenceDistributionRules::quota_is_strict_greater].
+    fn reached_quota(&self,c:CandidateIndex) -> bool {
+        if Rules::quota_is_strict_greater() { self.tally(c)>self.quota } else { self.tally(c)>=self.quota }
+    }
+
     pub fn check_elected_by_quota(&mut self) {
-        let mut elected_by_quota : Vec<CandidateIndex> = self.continuing_candidates_sorted_by_tally.iter().rev().take_while(|&&c|self.tally(c)>=self.quota).cloned().collect();
+        let mut elected_by_quota : Vec<CandidateIndex> = self.continuing_candidates_sorted_by_tally.iter().rev().take_while(|&&c|self.reached_quota(c)).cloned().collect();
         elected_by_quota.reverse(); // make sure low to high so that tie checking ordering is compatible.
         self.check_for_ties_and_resolve(&mut elected_by_quota,Rules::resolve_ties_elected_by_quota(),TieResolutionGranularityNeeded::Total,TieResolutionUsage::OrderElected);
-        for &c in elected_by_quota.iter().rev() {
+        if Rules::elect_highest_first() { elected_by_quota.reverse(); }
+        for &c in elected_by_quota.iter() {
+            if self.blocked_by_representation_constraint(c) { continue; }
             self.declare_elected(c,ElectionReason::ReachedQuota);
             if self.tally(c)>self.quota { self.pending_surplus_distribution.push_back(c); }
         }
+        if Rules::recompute_quota_after_each_election() && Rules::has_quota() && elected_by_quota.iter().any(|c|self.elected_candidates.contains(c)) { self.recompute_quota(); }
     }
 
     pub fn number_continuing_candidates(&self) -> NumberOfCandidates { NumberOfCandidates(self.continuing_candidates.len() )}
@@ -581,7 +904,9 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             let mut possibilities = self.continuing_candidates_sorted_by_tally.clone();
             self.check_for_ties_and_resolve(&mut possibilities,Rules::resolve_ties_elected_one_of_last_two(),TieResolutionGranularityNeeded::Total,TieResolutionUsage::ShortcutWinner);
             // elect the highest, Electoral officer resolved ties.
-            self.declare_elected(possibilities[1],ElectionReason::HighestOfLastTwoStanding);
+            if let Some(&highest) = possibilities.iter().rev().find(|&&c|!self.blocked_by_representation_constraint(c)) {
+                self.declare_elected(highest,ElectionReason::HighestOfLastTwoStanding);
+            }
         }
     }
 
@@ -590,10 +915,12 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
     /// > number of continuing candidates is equal to the number of
     /// > remaining unfilled vacancies, those candidates shall be elected.
     pub fn check_if_should_elect_all_remaining(&mut self) {
-        if self.number_continuing_candidates()==self.remaining_to_elect() {
-            let mut elected_group = self.continuing_candidates_sorted_by_tally.clone();
+        let blocked = self.continuing_candidates_blocked_by_representation_constraint();
+        if NumberOfCandidates(self.continuing_candidates.len()-blocked.len())==self.remaining_to_elect() {
+            let mut elected_group : Vec<CandidateIndex> = self.continuing_candidates_sorted_by_tally.iter().filter(|c|!blocked.contains(c)).cloned().collect();
             self.check_for_ties_and_resolve(&mut elected_group,Rules::resolve_ties_elected_all_remaining(),TieResolutionGranularityNeeded::Total,TieResolutionUsage::ShortcutWinner);
             for &c in elected_group.iter().rev() {
+                if self.blocked_by_representation_constraint(c) { continue; }
                 self.declare_elected(c,ElectionReason::AllRemainingMustBeElected);
             }
         }
@@ -603,7 +930,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
     pub fn total_undistributed_surplus_votes(&self) -> Rules::Tally {
         let mut sum = Rules::Tally::zero();
         for &candidate in self.pending_surplus_distribution.iter() {
-            sum+=self.tally(candidate)-self.quota.clone();
+            sum+=self.tally(candidate)-self.quota_for(candidate);
         }
         sum
     }
@@ -644,9 +971,18 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             WhenToDoElectCandidateClauseChecking::AfterDeterminingWhoToExcludeButBeforeTransferringAnyPapers => true,
             WhenToDoElectCandidateClauseChecking::AfterDeterminingWhoToExcludeButBeforeTransferringAnyPapersOrQuotaButOnlyIfContinuingCandidatesEqualsUnfilledVacanciesAndNotAfterSurplusIfMoreSurplusesAvailable => !(reason.is_surplus() && self.has_distributable_surplus()),
             WhenToDoElectCandidateClauseChecking::AfterCheckingQuotaIfNoUndistributedSurplusExists => self.pending_surplus_distribution.is_empty(),
+            WhenToDoElectCandidateClauseChecking::Custom => Rules::when_to_check_all_remaining_custom(&ElectCandidateClauseCheckContext{
+                count: self.current_count,
+                reason,
+                reason_completed,
+                undistributed_surplus: !self.pending_surplus_distribution.is_empty(),
+                continuing: self.number_continuing_candidates(),
+                remaining_to_elect: self.remaining_to_elect(),
+            }),
         }
     }
     pub fn check_elected(&mut self,reason : &ReasonForCount,reason_completed : bool) {
+        if self.finishing_counts_after_normal_end { return; } // see set_always_finish_all_counts.
         let check_quota = match Rules::when_to_check_if_all_remaining_should_get_elected() {
             WhenToDoElectCandidateClauseChecking::AfterDeterminingWhoToExcludeButBeforeTransferringAnyPapersOrQuotaButOnlyIfContinuingCandidatesEqualsUnfilledVacanciesAndNotAfterSurplusIfMoreSurplusesAvailable =>
                 match reason {
@@ -657,7 +993,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             _ => true,
         };
         if check_quota  { self.check_elected_by_quota(); }
-        if self.should_check(Rules::when_to_check_if_just_two_standing_for_shortcut_election(),reason,reason_completed) {
+        if self.should_check(Rules::when_to_check_if_just_two_standing_for_shortcut_election(),reason,reason_completed) && !self.should_defer_last_two_standing_shortcut() {
             self.check_elected_by_highest_of_remaining_2_when_1_needed_no_tie_resolution();
         }
         if self.should_check(Rules::when_to_check_if_all_remaining_should_get_elected(),reason,reason_completed) {
@@ -704,6 +1040,9 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
                 WhatToExtract::ACTVotesUsedToElectCandidate(who) if self.in_this_count.elected.iter().any(|c|c.who==*who) => {
                     e.what_to_do_with_it.do_it(self.extract_votes_electing_act(*who))
                 }
+                WhatToExtract::StateAtEndOfCount(count) if *count==self.current_count => {
+                    e.what_to_do_with_it.do_it(self.export_current_state_as_election_data())
+                }
                 _ => {}
             }
         }
@@ -712,10 +1051,13 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             portion,
             reason_completed,
             elected: self.in_this_count.elected.clone(),
-            not_continuing: self.in_this_count.not_continuing.clone(),
+            not_continuing: std::mem::take(&mut self.in_this_count.not_continuing),
             created_transfer_value: self.in_this_count.created_transfer_value.take(),
             decisions: std::mem::take(&mut self.in_this_count.decisions),
             set_aside_for_quota: self.in_this_count.set_aside_for_quota.take(),
+            bulk_exclusion: self.in_this_count.bulk_exclusion.take(),
+            new_quota: self.in_this_count.new_quota.take(),
+            parcel_sources: self.in_this_count.parcel_sources.take(),
             status: EndCountStatus {
                 tallies: PerCandidate {
                     candidate: self.tallys.clone(),
@@ -723,18 +1065,8 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
                     rounding:  self.tally_lost_to_rounding.clone(),
                     set_aside: self.tally_set_aside.clone(),
                 },
-                papers: PerCandidate {
-                    candidate: self.papers.iter().map(|p|p.num_ballots()).collect(),
-                    exhausted: self.exhausted,
-                    rounding:  Zero::zero(),
-                    set_aside: None
-                },
-                atl_papers: Some(PerCandidate {
-                    candidate: self.papers.iter().map(|p|p.num_atl_ballots()).collect(),
-                    exhausted: self.exhausted_atl,
-                    rounding:  Zero::zero(),
-                    set_aside: None
-                }),
+                papers: self.paper_counts(self.retain_paper_counts_for_every_count),
+                atl_papers: if self.retain_paper_counts_for_every_count { Some(self.atl_paper_counts()) } else { None },
                 list_of_votes: if self.include_list_of_votes_in_transcript {
                     let mut candidate = vec![];
                     for (papers,already_written) in self.papers.iter().zip(self.last_written_list_of_votes_by_candidate.iter_mut()) {
@@ -752,6 +1084,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
                 } else {None},
             },
             count_name,
+            no_votes_to_transfer: false,
         });
         self.current_count=CountIndex(self.current_count.0+1);
         if reason_completed || (Rules::major_count_if_someone_elected() && !self.in_this_count.elected.is_empty()) { self.current_major_count=CountIndex(self.current_major_count.0+1); self.current_minor_count=CountIndex(1); }
@@ -759,8 +1092,29 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
         self.in_this_count.not_continuing=self.in_this_count.elected.drain(..).map(|e|e.who).collect();
     }
 
+    /// The number of papers held by each candidate, exhausted and set aside. If not per_candidate, the candidate list is left empty, which does not allocate.
+    fn paper_counts(&self,per_candidate:bool) -> PerCandidate<BallotPaperCount> {
+        PerCandidate {
+            candidate: if per_candidate { self.papers.iter().map(|p|p.num_ballots()).collect() } else { vec![] },
+            exhausted: self.exhausted,
+            rounding:  Zero::zero(),
+            set_aside: self.papers_set_aside,
+        }
+    }
+
+    /// The number of ATL papers held by each candidate, and exhausted.
+    fn atl_paper_counts(&self) -> PerCandidate<BallotPaperCount> {
+        PerCandidate {
+            candidate: self.papers.iter().map(|p|p.num_atl_ballots()).collect(),
+            exhausted: self.exhausted_atl,
+            rounding:  Zero::zero(),
+            set_aside: None
+        }
+    }
+
     /// add some given number to the set_aside value. This is behind an option making it non-trivial.
     fn add_set_aside(&mut self,set_aside:Rules::Tally) {
+        self.audit(TallyAuditTarget::SetAside,TallyMutation::SetAside,||set_aside.clone().into(),None);
         let new_value = match self.tally_set_aside.take() {
             Some(v) => v+set_aside,
             None => set_aside,
@@ -810,10 +1164,9 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
     /// If distribute_randomly_nsw is true, transfer a random subset with TV 1.
     pub fn distribute_surplus_all_with_same_transfer_value(&mut self,candidate_to_distribute:CandidateIndex,distribute_randomly_nsw:bool) -> PortionOfReasonBeingDoneThisCount {
         let votes : Rules::Tally = self.tally(candidate_to_distribute);
-        let surplus: Rules::Tally  = votes.clone()-self.quota.clone();
-        self.tallys[candidate_to_distribute.0]=self.quota.clone();
-        // the code below is not production but is to find the oddity of a transfer value being increased.
-        // let original_provinances = self.papers[candidate_to_distribute.0].get_all_provenance_keys();
+        let surplus: Rules::Tally  = votes.clone()-self.quota_for(candidate_to_distribute);
+        self.tallys[candidate_to_distribute.0]=self.quota_for(candidate_to_distribute);
+        self.audit(TallyAuditTarget::Candidate(candidate_to_distribute),TallyMutation::SurplusRemoved,||negative(surplus.clone()),None);
         let (_tally_here,ballots,provenance) = match Rules::use_last_parcel_for_surplus_distribution() {
             LastParcelUse::No => self.papers[candidate_to_distribute.0].extract_all_ballots_ignoring_transfer_value(),
             LastParcelUse::LiterallyLast => self.papers[candidate_to_distribute.0].extract_last_parcel(),
@@ -827,18 +1180,11 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             },
         };
         let ballots_considered : BallotPaperCount = ballots.num_ballots;
-        let distributed = self.distribute(&ballots.votes);
+        let distributed = self.distribute_from(&[candidate_to_distribute],&ballots.votes);
         let continuing_ballots = ballots_considered-distributed.exhausted;
         let tv_denom = if Rules::transfer_value_method().denom_is_just_continuing() {continuing_ballots} else {ballots.num_ballots};
+        let denominator_policy_applied = if tv_denom.is_zero() { Some(Rules::surplus_fraction_denominator_policy()) } else { None };
         let mut transfer_value : TransferValue = if tv_denom.is_zero() { TransferValue::one() } else {Rules::make_transfer_value(surplus.clone(),tv_denom)};
-        // the code below is not production but is to find the oddity of a transfer value being increased.
-        //for (_,original_tv) in original_provinances {
-        //    if original_tv.lt(&transfer_value) {
-        //        println!("Found case count {} of tv {} less than {} election {}",self.current_count,original_tv,transfer_value,self.data.metadata.name.human_readable_name());
-        //        panic!("Found oddity")
-        //    }
-        //}
-        //
         let mut original_worth : Rules::Tally = surplus.clone();
         if Rules::transfer_value_method().limit_to_incoming_transfer_value() {
             let old_tv = provenance.transfer_value.clone().expect("If you are going to limit to an incoming transfer value, there must be a unique one.");
@@ -855,10 +1201,15 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             }
         }
         // println!("Parcelling out {} votes with TV {} over {} ballots",original_worth,transfer_value,tv_denom);
-        if distribute_randomly_nsw { // this is a terrible thing.
+        if self.stop_if_denominator_is_an_error(denominator_policy_applied,candidate_to_distribute,&distributed,&original_worth) { // set aside, and the count stops here.
+        } else if Rules::surplus_distribution_subdivisions()==SurplusTransferMethod::CincinnatiEveryNth {
+            self.parcel_out_votes_every_nth(&ballots,BallotPaperCount(surplus.ceil()),candidate_to_distribute);
+        } else if distribute_randomly_nsw { // this is a terrible thing.
             self.parcel_out_votes_random_portion_set_by_transfer_value(transfer_value.clone(),distributed,BallotPaperCount(surplus.ceil()),candidate_to_distribute);
-        } else {
-            self.parcel_out_votes_with_given_transfer_value(transfer_value.clone(),distributed,Some(self.current_count),original_worth,!Rules::transfer_value_method().denom_is_just_continuing(),false,None);
+        } else if !self.set_aside_if_below_minimum_transfer_value(&transfer_value,&distributed,&original_worth) {
+            let distribute_exhausted_votes = !Rules::transfer_value_method().denom_is_just_continuing();
+            self.record_papers_retained_for_quota(&transfer_value,&distributed,if distribute_exhausted_votes { Some(&transfer_value) } else { None });
+            self.parcel_out_votes_with_given_transfer_value(transfer_value.clone(),distributed,Some(self.current_count),original_worth,distribute_exhausted_votes,false,None);
         }
         self.in_this_count.created_transfer_value=Some(TransferValueCreation{
             surplus,
@@ -870,6 +1221,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             continuing_ballots,
             transfer_value,
             source: Rules::transfer_value_method(),
+            denominator_policy_applied,
         });
         provenance
     }
@@ -878,7 +1230,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
     /// Then multiply this ratio by the transfer value that everything came with.
     pub fn distribute_surplus_by_scaling_incoming_transfer_values(&mut self,candidate_to_distribute:CandidateIndex,merge_same_tv:bool)  {
         let votes : Rules::Tally = self.tally(candidate_to_distribute);
-        let surplus: Rules::Tally  = votes.clone()-self.quota.clone();
+        let surplus: Rules::Tally  = votes.clone()-self.quota_for(candidate_to_distribute);
         let votes_to_distribute : Vec<(TransferValue,(Rules::Tally,VotesWithSameTransferValue,PortionOfReasonBeingDoneThisCount))> =
             if merge_same_tv { self.papers[candidate_to_distribute.0].extract_all_ballots_separated_by_transfer_value() } // sorted highest TV to lowest
             else {
@@ -904,18 +1256,20 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
         let mut total_value_of_exhausted_votes = BigRational::zero();
         let continuing_candidates_when_distribution_done = self.continuing_candidates_sorted_by_tally.len();
         for (tv,(step_tally,ballots,prov)) in votes_to_distribute {
-            let distributed = self.distribute(&ballots.votes); // note that this will use the wrong oracle count value if an oracle is used.
+            let distributed = self.distribute_from(&[candidate_to_distribute],&ballots.votes); // note that this will use the wrong oracle count value if an oracle is used.
             let exhausted_value = tv.mul(distributed.exhausted);
             total_value_of_exhausted_votes+=exhausted_value.clone();
             partially_distributed.push((tv,step_tally,ballots,prov,distributed,exhausted_value));
         }
         let general_tv_denom : BigRational = if Rules::transfer_value_method().denom_is_just_continuing()  { Rules::convert_tally_to_rational(votes.clone())-total_value_of_exhausted_votes.clone() } else { Rules::convert_tally_to_rational(votes.clone()) } ;
-        let quota : BigRational = Rules::convert_tally_to_rational(self.quota.clone());
+        let quota : BigRational = Rules::convert_tally_to_rational(self.quota_for(candidate_to_distribute));
         let special_factor_excluded : Option<BigRational> = if Rules::transfer_value_method().denom_is_just_continuing() || total_value_of_exhausted_votes<=quota || Rules::count_set_aside_due_to_transfer_value_limit_as_rounding() { None } else { Some((total_value_of_exhausted_votes.clone()-quota.clone())/total_value_of_exhausted_votes.clone()) }; // (AV-Q)/Av
         let original_worth_ratio = Rules::convert_tally_to_rational(surplus.clone())/Rules::convert_tally_to_rational(votes.clone());
         let surplus_rational = Rules::convert_tally_to_rational(surplus.clone());
         //println!("TV based on surplus {} = {}-{} divided by {} = {}-{}",surplus_rational,votes,self.quota,general_tv_denom,votes,total_value_of_exhausted_votes);
-        let general_tv = if general_tv_denom<=surplus_rational && (Rules::prohibit_negative_surplus_fraction() || !general_tv_denom.is_negative()) { TransferValue::one() } else { TransferValue(surplus_rational/general_tv_denom) };
+        let denominator_policy_applied = if general_tv_denom.is_positive() { None } else { Some(Rules::surplus_fraction_denominator_policy()) };
+        let allow_negative = denominator_policy_applied==Some(SurplusFractionDenominatorPolicy::AllowNegative);
+        let general_tv = if general_tv_denom<=surplus_rational && !(allow_negative && general_tv_denom.is_negative()) { TransferValue::one() } else { Rules::munge_surplus_fraction(TransferValue(surplus_rational/general_tv_denom)) };
         //println!("quota {} exhausted {} special factor excluded {:?} TV {}",self.quota,total_value_of_exhausted_votes,special_factor_excluded,general_tv);
         let mut current_remaining_tally_for_candidate_being_distributed : BigRational = Rules::convert_tally_to_rational(votes.clone());
         let mut togo = partially_distributed.len();
@@ -929,10 +1283,16 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             let after : Rules::Tally = Rules::convert_rational_to_tally_after_applying_transfer_value(current_remaining_tally_for_candidate_being_distributed.clone());
             self.tallys[candidate_to_distribute.0] = after.clone();
             let original_worth = before-after;
-            let distributed = if continuing_candidates_when_distribution_done == self.continuing_candidates_sorted_by_tally.len() && self.oracle.is_none() {distributed} else { self.distribute(&ballots.votes) }; // recompute if the continuing candidates list changed, or oracle exists
+            self.audit(TallyAuditTarget::Candidate(candidate_to_distribute),TallyMutation::SurplusRemoved,||negative(original_worth.clone()),None);
+            let distributed = if continuing_candidates_when_distribution_done == self.continuing_candidates_sorted_by_tally.len() && self.oracle.is_none() {distributed} else { self.distribute_from(&[candidate_to_distribute],&ballots.votes) }; // recompute if the continuing candidates list changed, or oracle exists
             let transfer_value = TransferValue(tv.0*general_tv.0.clone());
             let continuing_ballots = ballots.num_ballots-distributed.exhausted;
-            self.parcel_out_votes_with_given_transfer_value(transfer_value.clone(),distributed,Some(self.current_count),original_worth,special_factor_excluded.is_some() || !Rules::transfer_value_method().denom_is_just_continuing(),false,special_factor_excluded.as_ref());
+            if !(self.stop_if_denominator_is_an_error(denominator_policy_applied,candidate_to_distribute,&distributed,&original_worth) || self.set_aside_if_below_minimum_transfer_value(&transfer_value,&distributed,&original_worth)) {
+                let distribute_exhausted_votes = special_factor_excluded.is_some() || !Rules::transfer_value_method().denom_is_just_continuing();
+                let exhausted_tv = if let Some(em) = &special_factor_excluded { TransferValue(transfer_value.0.clone()*em) } else { transfer_value.clone() };
+                self.record_papers_retained_for_quota(&transfer_value,&distributed,if distribute_exhausted_votes { Some(&exhausted_tv) } else { None });
+                self.parcel_out_votes_with_given_transfer_value(transfer_value.clone(),distributed,Some(self.current_count),original_worth,distribute_exhausted_votes,false,special_factor_excluded.as_ref());
+            }
             self.in_this_count.created_transfer_value=Some(TransferValueCreation{
                 surplus: surplus.clone(),
                 votes : votes.clone(),
@@ -943,20 +1303,54 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
                 continuing_ballots,
                 transfer_value,
                 source: Rules::transfer_value_method(),
+                denominator_policy_applied,
             });
             self.end_of_count_step(ReasonForCount::ExcessDistribution(candidate_to_distribute), provenance, is_final_step);
-
+            if self.transcript.stopped.is_some() { break; }
         }
     }
 
+    /// Record, in the same form as [SingleCount::set_aside_for_quota] for the NSW random method, the ballot papers that
+    /// stay with the elected candidate when `distributed` is transferred as a surplus at `transfer_value`.
+    /// The value of a paper is split rather than the paper itself, so this is the equivalent number of whole papers:
+    /// for each destination, the papers less the number of whole papers' worth of value transferred (rounded down, and limited to
+    /// between none and all the papers, as some rules can produce negative transfer values or ones over 1).
+    /// `exhausted_transfer_value` is None if no value is transferred with exhausted papers, in which case they all stay.
+    fn record_papers_retained_for_quota(&mut self,transfer_value:&TransferValue,distributed:&DistributedVotes<'a>,exhausted_transfer_value:Option<&TransferValue>) {
+        let retained = |tv:&TransferValue,papers:BallotPaperCount| papers-BallotPaperCount(tv.mul_rounding_down_isize(papers).clamp(0,papers.0 as isize) as usize);
+        self.in_this_count.set_aside_for_quota = Some(PerCandidate {
+            candidate: distributed.by_candidate.iter().map(|v|retained(transfer_value,v.num_ballots)).collect(),
+            exhausted: exhausted_transfer_value.map(|tv|retained(tv,distributed.exhausted)).unwrap_or(distributed.exhausted),
+            rounding: SignedVersion { negative: false, value: BallotPaperCount::zero() },
+            set_aside: None,
+        });
+    }
+
     /// Parcel out votes by next continuing candidate with a given transfer value.
     /// Returns the (total value of votes distributed to candidates,total value of votes distributed to candidates and exhausted)
     pub fn parcel_out_votes_with_given_transfer_value(&mut self,transfer_value:TransferValue,distributed:DistributedVotes<'a>,when_tv_created:Option<CountIndex>,original_worth:Rules::Tally,distribute_exhausted_votes:bool,is_exclusion:bool,extra_multiple_for_exhausted:Option<&BigRational>) -> (Rules::Tally,Rules::Tally) {
         let mut tally_distributed = Rules::Tally::zero();
+        let exhausted_tv = if let Some(em) = extra_multiple_for_exhausted { TransferValue(transfer_value.0.clone()*em) } else { transfer_value.clone() };
+        let apportioned : Option<Vec<usize>> = match Rules::transfer_rounding() {
+            TransferRounding::PerCandidateDown => None,
+            TransferRounding::LargestRemainder => {
+                let mut shares : Vec<BigRational> = distributed.by_candidate.iter().map(|b|transfer_value.mul(b.num_ballots)).collect();
+                if distribute_exhausted_votes { shares.push(exhausted_tv.mul(distributed.exhausted)); }
+                Some(apportion_largest_remainder(&shares))
+            }
+        };
+        let worth_of = |index:usize,tv:&TransferValue,ballots:BallotPaperCount| -> Rules::Tally {
+            match &apportioned {
+                Some(apportioned) => Rules::Tally::from(BallotPaperCount(apportioned[index])),
+                None => Rules::use_transfer_value(tv,ballots),
+            }
+        };
+        let exhausted_index = distributed.by_candidate.len();
         for (candidate_index,candidate_ballots) in distributed.by_candidate.into_iter().enumerate() {
             if candidate_ballots.num_ballots.0>0 {
-                let worth:Rules::Tally = Rules::use_transfer_value(&transfer_value,candidate_ballots.num_ballots);
+                let worth:Rules::Tally = worth_of(candidate_index,&transfer_value,candidate_ballots.num_ballots);
                 self.tallys[candidate_index]+=worth.clone();
+                self.audit(TallyAuditTarget::Candidate(CandidateIndex(candidate_index)),TallyMutation::Transfer,||worth.clone().into(),Some(&transfer_value));
                 tally_distributed +=worth.clone();
                 self.papers[candidate_index].add(&candidate_ballots, transfer_value.clone(), self.current_count, when_tv_created, worth);
             }
@@ -964,27 +1358,78 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
         let tally_distributed_to_candidates = tally_distributed.clone();
         if distributed.exhausted.0>0 {
             if distribute_exhausted_votes {
-                let exhausted_tv = if let Some(em) = extra_multiple_for_exhausted { TransferValue(transfer_value.0*em) } else { transfer_value };
-                let worth:Rules::Tally = Rules::use_transfer_value(&exhausted_tv,distributed.exhausted);
-                let worth:Rules::Tally = Rules::munge_exhausted_votes(worth,is_exclusion); // support emulation of weird bugs.
-                self.tally_exhausted+=worth.clone();
-                tally_distributed+=worth.clone();
+                let worth:Rules::Tally = worth_of(exhausted_index,&exhausted_tv,distributed.exhausted);
+                if Rules::redistribute_exhausted_proportionally() {
+                    tally_distributed+=self.redistribute_value_proportionally(worth,transfer_value.clone(),when_tv_created);
+                } else {
+                    let worth:Rules::Tally = Rules::munge_exhausted_votes(worth,is_exclusion); // support emulation of weird bugs.
+                    self.tally_exhausted+=worth.clone();
+                    self.audit(TallyAuditTarget::Exhausted,TallyMutation::Transfer,||worth.clone().into(),Some(&exhausted_tv));
+                    tally_distributed+=worth.clone();
+                }
             }
             // always distribute the papers.
             self.exhausted+=distributed.exhausted;
             self.exhausted_atl+=distributed.exhausted_atl;
         }
+        self.audit(TallyAuditTarget::Rounding,TallyMutation::Rounding,||SignedVersion::from(original_worth.clone())-SignedVersion::from(tally_distributed.clone()),Some(&transfer_value));
         self.tally_lost_to_rounding+=original_worth;
         self.tally_lost_to_rounding-=tally_distributed.clone();
         (tally_distributed_to_candidates,tally_distributed)
     }
 
+    /// If the transfer value is below [PreferenceDistributionRules::minimum_transfer_value], set aside the distributed
+    /// papers and their value `original_worth` instead of transferring them, and return true. Otherwise do nothing and return false.
+    fn set_aside_if_below_minimum_transfer_value(&mut self,transfer_value:&TransferValue,distributed:&DistributedVotes<'a>,original_worth:&Rules::Tally) -> bool {
+        match Rules::minimum_transfer_value() {
+            Some(minimum) if *transfer_value<minimum => {
+                let papers = distributed.by_candidate.iter().map(|v|v.num_ballots).fold(distributed.exhausted,|a,b|a+b);
+                self.papers_set_aside=Some(self.papers_set_aside.unwrap_or(BallotPaperCount(0))+papers);
+                self.add_set_aside(original_worth.clone());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// If `policy`, the policy applied to a zero or negative transfer value denominator, is [SurplusFractionDenominatorPolicy::Error],
+    /// set aside `distributed` (worth `original_worth`) rather than transferring it, and stop the count. Returns true if so.
+    fn stop_if_denominator_is_an_error(&mut self,policy:Option<SurplusFractionDenominatorPolicy>,candidate:CandidateIndex,distributed:&DistributedVotes<'a>,original_worth:&Rules::Tally) -> bool {
+        if policy!=Some(SurplusFractionDenominatorPolicy::Error) { return false; }
+        let papers = distributed.by_candidate.iter().map(|v|v.num_ballots).fold(distributed.exhausted,|a,b|a+b);
+        self.papers_set_aside=Some(self.papers_set_aside.unwrap_or(BallotPaperCount(0))+papers);
+        self.add_set_aside(original_worth.clone());
+        self.transcript.stopped=Some(CountStopped::SurplusFractionDenominator{count:self.current_count,candidate});
+        true
+    }
+
+    /// Give the value `worth` to the continuing candidates in proportion to their current tallies, for the experimental
+    /// [PreferenceDistributionRules::redistribute_exhausted_proportionally]. Returns the total value given out, which may be
+    /// less than worth due to rounding.
+    fn redistribute_value_proportionally(&mut self,worth:Rules::Tally,transfer_value:TransferValue,when_tv_created:Option<CountIndex>) -> Rules::Tally {
+        let mut total_given = Rules::Tally::zero();
+        let recipients : Vec<CandidateIndex> = self.continuing_candidates_sorted_by_tally.clone();
+        let total_continuing : BigRational = recipients.iter().map(|&c|Rules::convert_tally_to_rational(self.tally(c))).sum();
+        if total_continuing.is_positive() {
+            let worth = Rules::convert_tally_to_rational(worth);
+            for candidate in recipients {
+                let share = Rules::convert_rational_to_tally_after_applying_transfer_value(worth.clone()*Rules::convert_tally_to_rational(self.tally(candidate))/total_continuing.clone());
+                if !share.is_zero() {
+                    self.tallys[candidate.0]+=share.clone();
+                    self.audit(TallyAuditTarget::Candidate(candidate),TallyMutation::ExhaustedRedistributedProportionally,||share.clone().into(),Some(&transfer_value));
+                    self.papers[candidate.0].add_value_without_ballots(transfer_value.clone(),self.current_count,when_tv_created,share.clone());
+                    total_given+=share;
+                }
+            }
+        }
+        total_given
+    }
 
 
     /// Parcel out votes by next continuing candidate with a given transfer value.
     /// Returns to the candidate being distributed the ones kept for quota.
     fn parcel_out_votes_random_portion_set_by_transfer_value(&mut self,transfer_value:TransferValue,distributed:DistributedVotes<'a>,surplus:BallotPaperCount,candidate_being_distributed:CandidateIndex)  {
-        let (set_aside_by_candidate,ec_decisions) = transfer_value.calculate_number_of_ballot_papers_to_be_set_aside(surplus,self.num_candidates,&self.transcript,&distributed,Rules::use_f32_arithmetic_when_applying_transfer_values_instead_of_exact(),self.ec_resolutions,self.current_count,&mut self.randomness);
+        let (set_aside_by_candidate,ec_decisions) = transfer_value.calculate_number_of_ballot_papers_to_be_set_aside(surplus,self.num_candidates,&self.transcript,&self.data.metadata,&distributed,Rules::use_f32_arithmetic_when_applying_transfer_values_instead_of_exact(),self.ec_resolutions,self.current_count,&mut self.randomness);
         self.in_this_count.decisions.extend(ec_decisions);
         // do the actual distribution
         let mut total_transferred : BallotPaperCount = BallotPaperCount::zero();
@@ -995,6 +1440,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
                     let worth = chosen.num_ballots;
                     total_transferred +=worth;
                     self.tallys[candidate_index]+=worth.into();
+                    self.audit(TallyAuditTarget::Candidate(CandidateIndex(candidate_index)),TallyMutation::RandomTransfer,||Rules::Tally::from(worth).into(),Some(&transfer_value));
                     self.papers[candidate_index].add(&chosen, TransferValue::one(), self.current_count, None, worth.into());
                 }
                 if unchosen.num_ballots.0>0 { // the ones not chosen are returned to the original owner so that s/he keeps a quota of ballot papers.
@@ -1013,6 +1459,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
         assert_eq!(exhausted_retained_for_quota.num_ballots,exhausted_that_are_set_aside_for_quota);
         self.exhausted += exhausted_that_would_be_distributed_if_they_could_be;
         self.tally_exhausted += exhausted_that_would_be_distributed_if_they_could_be.into();
+        self.audit(TallyAuditTarget::Exhausted,TallyMutation::RandomTransfer,||Rules::Tally::from(exhausted_that_would_be_distributed_if_they_could_be).into(),Some(&transfer_value));
         self.exhausted_atl += exhausted_set_aside.num_atl_ballots;
         self.in_this_count.set_aside_for_quota = Some(PerCandidate {
             candidate: set_aside_by_candidate,
@@ -1022,6 +1469,41 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
         });
     }
 
+    /// Transfer `surplus` ballots chosen by the Cincinnati method from `ballots`, in order, at their full value. The rest are returned to the candidate being distributed.
+    /// If there are not enough ballots with a next preference, the shortfall is exhausted.
+    fn parcel_out_votes_every_nth(&mut self,ballots:&VotesWithSameTransferValue<'a>,surplus:BallotPaperCount,candidate_being_distributed:CandidateIndex) {
+        let some_frozen = self.candidates_receiving_transfers_if_some_frozen();
+        let receiving = some_frozen.as_ref().unwrap_or(&self.continuing_candidates);
+        let (chosen,unchosen) = ballots.choose_every_nth(surplus,receiving);
+        let distributed = DistributedVotes::distribute(&chosen.votes,receiving,self.num_candidates);
+        let shortfall = surplus-chosen.num_ballots;
+        let (retained,exhausted) = unchosen.set_aside_arbitrarily(shortfall.min(unchosen.num_ballots)); // the last parcel may be smaller than the surplus.
+        let retained_by_next_preference = DistributedVotes::distribute(&retained.votes,receiving,self.num_candidates);
+        for (candidate_index,candidate_ballots) in distributed.by_candidate.iter().enumerate() {
+            if candidate_ballots.num_ballots.0>0 {
+                let worth = candidate_ballots.num_ballots;
+                self.tallys[candidate_index]+=worth.into();
+                self.audit(TallyAuditTarget::Candidate(CandidateIndex(candidate_index)),TallyMutation::EveryNthTransfer,||Rules::Tally::from(worth).into(),None);
+                self.papers[candidate_index].add(candidate_ballots, TransferValue::one(), self.current_count, None, worth.into());
+            }
+        }
+        self.in_this_count.set_aside_for_quota = Some(PerCandidate {
+            candidate: retained_by_next_preference.by_candidate.iter().map(|v|v.num_ballots).collect(),
+            exhausted: retained_by_next_preference.exhausted,
+            rounding: SignedVersion { negative: false, value: BallotPaperCount::zero() },
+            set_aside: None,
+        });
+        if retained.num_ballots.0>0 {
+            self.papers[candidate_being_distributed.0].add(&retained, TransferValue::one(), self.current_count, None, retained.num_ballots.into());
+        }
+        if shortfall.0>0 {
+            self.exhausted += exhausted.num_ballots;
+            self.exhausted_atl += exhausted.num_atl_ballots;
+            self.tally_exhausted += shortfall.into();
+            self.audit(TallyAuditTarget::Exhausted,TallyMutation::EveryNthTransfer,||Rules::Tally::from(shortfall).into(),None);
+        }
+    }
+
     pub fn distribute_surplus(&mut self,candidate_to_distribute:CandidateIndex) {
         // println!("Distributing surplus for {}",self.data.metadata.candidate(candidate_to_distribute).name);
         match Rules::surplus_distribution_subdivisions() {
@@ -1035,6 +1517,10 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
                 let provenance = self.distribute_surplus_all_with_same_transfer_value(candidate_to_distribute,true);
                 self.end_of_count_step(ReasonForCount::ExcessDistribution(candidate_to_distribute), provenance, true);
             }
+            SurplusTransferMethod::CincinnatiEveryNth => { // the choice of method is made in distribute_surplus_all_with_same_transfer_value as it needs the ballots in order.
+                let provenance = self.distribute_surplus_all_with_same_transfer_value(candidate_to_distribute,false);
+                self.end_of_count_step(ReasonForCount::ExcessDistribution(candidate_to_distribute), provenance, true);
+            }
         }
     }
 
@@ -1046,8 +1532,11 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
     }
 
     pub fn find_lowest_candidate(&mut self) -> Vec<CandidateIndex> {
-        let lowest_tally = self.tally(self.continuing_candidates_sorted_by_tally[0]);
-        let mut possibilities : Vec<CandidateIndex> = self.continuing_candidates_sorted_by_tally.iter().take_while(|&&c|self.tally(c)==lowest_tally).cloned().collect();
+        let blocked = self.continuing_candidates_blocked_by_representation_constraint();
+        let excludable : Vec<CandidateIndex> = self.continuing_candidates_sorted_by_tally.iter().filter(|c|!self.protected.contains(c)).cloned().collect();
+        let excludable = if !blocked.is_empty() { blocked } else if excludable.is_empty() { self.continuing_candidates_sorted_by_tally.clone() } else { excludable };
+        let lowest_tally = self.tally(excludable[0]);
+        let mut possibilities : Vec<CandidateIndex> = excludable.iter().take_while(|&&c|self.tally(c)==lowest_tally).cloned().collect();
         self.check_for_ties_and_resolve(&mut possibilities,Rules::resolve_ties_choose_lowest_candidate_for_exclusion(),TieResolutionGranularityNeeded::LowestSeparated(1),TieResolutionUsage::Exclusion);
         possibilities.truncate(1);
         possibilities
@@ -1152,6 +1641,8 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             notional_votes.push(self.tally(candidate)+notional_votes.last().cloned().unwrap_or_else(Rules::Tally::zero))
         }
         //println!("Notional votes {}",notional_votes.iter().map(|v|v.to_string()).collect::<Vec<_>>().join("\t"));
+        // kept for the transcript, before any tie resolution below reorders the candidates.
+        let notional_votes_by_candidate : Vec<(CandidateIndex,Rules::Tally)> = self.continuing_candidates_sorted_by_tally.iter().cloned().zip(notional_votes.iter().cloned()).collect();
         // Find Candidate B. There is no point finding Candidate A, we merely need to
         // find a candidate B who is the highest ranking candidate with fewer notional
         // votes than the vacancy shortfall, and a number of notional votes < votes of higher person.
@@ -1190,6 +1681,12 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             while tie_start>0 && tally_of_highest_excluded==self.tally(self.continuing_candidates_sorted_by_tally[tie_start-1]) { tie_start-=1; }
             self.check_for_ties_and_resolve_inplace(tie_start..tie_end,Rules::resolve_ties_choose_lowest_candidate_for_exclusion(),TieResolutionGranularityNeeded::LowestSeparated(candidates_to_exclude-tie_start),TieResolutionUsage::Exclusion);
         }
+        // record the working for the transcript.
+        self.in_this_count.bulk_exclusion = Some(BulkExclusionNotionalVotes{
+            leading_shortfall,
+            vacancy_shortfall,
+            notional_votes: notional_votes_by_candidate,
+        });
         // exclude the lowest candidates_to_exclude candidates.
         Some(self.continuing_candidates_sorted_by_tally[0..candidates_to_exclude].to_vec())
     }
@@ -1224,6 +1721,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
     /// > (iii) all those ballot papers must be transferred to the
     /// > continuing candidate.
     pub fn exclude(&mut self, candidates_to_exclude:Vec<CandidateIndex>) {
+        let pending_surplus_before_exclusion = self.pending_surplus_distribution.len();
         for &candidate in &candidates_to_exclude {
             // println!("Excluding {}",self.data.metadata.candidate(candidate).name);
             self.no_longer_continuing(candidate,false);
@@ -1244,7 +1742,7 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             }, false);
             return; // Don't transfer any papers!
         }
-        let mut provenances : HashSet<(<Rules::SplitByNumber as HowSplitByCountNumber>::KeyToDivide,TransferValue)> = HashSet::default();
+        let mut provenances : BTreeSet<(<Rules::SplitByNumber as HowSplitByCountNumber>::KeyToDivide,TransferValue)> = BTreeSet::default();
         for &candidate in &candidates_to_exclude {
             for prov in self.papers[candidate.0].get_all_provenance_keys() {
                 provenances.insert(prov);
@@ -1259,6 +1757,8 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             }, true);
             return;
         }
+        // Start with highest transfer value first, which only matters if nothing below distinguishes them.
+        provenances.sort_by(|a,b|b.1.cmp(&a.1));
         // First sort by S::KeyToDivide
         provenances.sort_by_key(|f|f.0.clone()); // stable sort, will preserve ordering of other key stuff
         // Then stable sort by TransferValue
@@ -1280,14 +1780,22 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
                     when_tv_created.add(from.when_tv_created);
                     original_worth+=from.tally.clone();
                     papers_came_from_counts.extend(from.source_counts.iter().map(|p|p.count_index));
+                    self.audit(TallyAuditTarget::Candidate(candidate),TallyMutation::ExcludedVotesRemoved,||negative(from.tally.clone()),Some(&key.1));
                     self.tallys[candidate.0]-=from.tally;
                     if all_votes.num_ballots.0==0 { all_votes=votes; }
                     else { all_votes.add(&votes.votes); }
                 }
             }
             let when_tv_created=when_tv_created.take().flatten();
-            let distributed = self.distribute(&all_votes.votes);
-            self.parcel_out_votes_with_given_transfer_value(key.1.clone(),distributed,when_tv_created,original_worth,true,true,None);
+            if self.include_list_of_votes_in_transcript {
+                let mut by_first_preference : BTreeMap<usize,BallotPaperCount> = BTreeMap::new();
+                for vote in &all_votes.votes { *by_first_preference.entry(vote.prefs[0].0).or_insert(BallotPaperCount(0))+=vote.n; }
+                self.in_this_count.parcel_sources=Some(by_first_preference.into_iter().map(|(c,papers)|ParcelSource{ first_preference: CandidateIndex(c), papers }).collect());
+            }
+            let distributed = self.distribute_from(&candidates_to_exclude,&all_votes.votes);
+            if !self.set_aside_if_below_minimum_transfer_value(&key.1,&distributed,&original_worth) {
+                self.parcel_out_votes_with_given_transfer_value(key.1.clone(),distributed,when_tv_created,original_worth,true,true,None);
+            }
             togo-=1;
             self.end_of_count_step(ReasonForCount::Elimination(candidates_to_exclude.clone()), PortionOfReasonBeingDoneThisCount {
                 transfer_value: Some(key.1),
@@ -1295,19 +1803,51 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
                 papers_came_from_counts: papers_came_from_counts.take(),
             }, togo==0);
             if self.remaining_to_elect()==NumberOfCandidates(0) && !Rules::finish_all_counts_in_elimination_when_all_elected() { break; }
+            if self.transcript.stopped.is_some() { break; }
+            if togo>0 && self.remaining_to_elect()>NumberOfCandidates(0) && !Rules::defer_surplus_of_candidate_elected_mid_exclusion() {
+                // distribute the surpluses of candidates elected during this exclusion before continuing it. Surpluses already pending are left in the queue.
+                while let Some(candidate) = self.pending_surplus_distribution.remove(pending_surplus_before_exclusion) {
+                    self.distribute_surplus(candidate);
+                    if self.remaining_to_elect()==NumberOfCandidates(0) { break; }
+                }
+            }
         }
     }
 
 
+    /// For IRV, find the largest group of two or more lowest candidates whose combined tally is less than the next lowest candidate's tally,
+    /// leaving at least two candidates. See [PreferenceDistributionRules::batch_exclude_candidates_who_cannot_catch_up].
+    pub fn find_candidates_for_irv_batch_exclusion(&self) -> Option<Vec<CandidateIndex>> {
+        let sorted = &self.continuing_candidates_sorted_by_tally;
+        let mut sum = Rules::Tally::zero();
+        let mut batch_size = 0;
+        for (i,pair) in sorted.windows(2).enumerate().take(sorted.len().saturating_sub(2)) {
+            sum+=self.tally(pair[0]);
+            if sum<self.tally(pair[1]) { batch_size=i+1; }
+        }
+        let batch : Vec<CandidateIndex> = sorted[..batch_size].to_vec();
+        if batch.len()<2 || batch.iter().any(|c|self.protected.contains(c)) { None } else { Some(batch) }
+    }
+
     pub fn exclude_lowest(&mut self) {
         let candidates_to_exclude : Vec<CandidateIndex> =
-            if Rules::should_eliminate_multiple_candidates_federal_rule_13a() { self.find_candidates_for_multiple_elimination_federal_rule_13a().unwrap_or_else(||self.find_lowest_candidate()) }
+            if !self.continuing_candidates_blocked_by_representation_constraint().is_empty() { self.find_lowest_candidate() }
+            else if !Rules::has_quota() && Rules::batch_exclude_candidates_who_cannot_catch_up() { self.find_candidates_for_irv_batch_exclusion().unwrap_or_else(||self.find_lowest_candidate()) }
+            else if Rules::should_eliminate_multiple_candidates_federal_rule_13a() { self.find_candidates_for_multiple_elimination_federal_rule_13a().filter(|bulk|!bulk.iter().any(|c|self.protected.contains(c))).unwrap_or_else(||self.find_lowest_candidate()) }
             else { self.find_lowest_candidate() };
         self.exclude(candidates_to_exclude);
     }
     pub fn has_distributable_surplus(&self) -> bool {
         !(self.pending_surplus_distribution.is_empty() || self.should_defer_surplus())
     }
+    /// See [PreferenceDistributionRules::defer_last_two_standing_shortcut]
+    pub fn should_defer_last_two_standing_shortcut(&self) -> bool {
+        match Rules::defer_last_two_standing_shortcut() {
+            DeferLastTwoStandingShortcut::Never => false,
+            DeferLastTwoStandingShortcut::IfTiedAndSurplusDistributable => self.has_distributable_surplus() && self.continuing_candidates_sorted_by_tally.len()==2 && self.tally(self.continuing_candidates_sorted_by_tally[0])==self.tally(self.continuing_candidates_sorted_by_tally[1]),
+            DeferLastTwoStandingShortcut::IfSurplusDistributable => self.has_distributable_surplus(),
+        }
+    }
     pub fn should_defer_surplus(&self) -> bool {
         match Rules::when_should_surplus_distribution_be_deferred() {
             DeferSurplusDistribution::AlwaysDistributeAllSurplusBeforeAnyExclusions => false,
@@ -1319,20 +1859,100 @@ impl <'a,Rules:PreferenceDistributionRules> PreferenceDistributor<'a,Rules>
             }
         }
     }
+    /// Do the whole count. Equivalent to [Self::start], then [Self::step] until it returns false, then [Self::finish].
     pub fn go(&mut self) {
+        self.start();
+        while self.step() {}
+        self.finish();
+    }
+
+    /// Start a count incrementally by distributing first preferences. Follow with calls to [Self::step].
+    pub fn start(&mut self) {
         if self.print_progress_to_stdout { self.print_candidates_names(); }
         self.distribute_first_preferences();
-        while (self.remaining_to_elect()>NumberOfCandidates(0) && self.continuing_candidates.len()>0) || (Rules::finish_all_surplus_distributions_when_all_elected() && (!self.continuing_candidates_sorted_by_tally.is_empty()) && !self.pending_surplus_distribution.is_empty()) {
-            if self.should_defer_surplus() {
-                self.exclude_lowest();
+    }
+
+    /// Do the next action of a count started with [Self::start] : distribute one surplus or do one exclusion.
+    /// This may take several counts. Returns false, doing nothing, if the count is over or has been stopped (see [Transcript::stopped]), in which case call [Self::finish].
+    pub fn step(&mut self) -> bool {
+        if self.transcript.stopped.is_some() { return false; }
+        if self.cancel.map(|cancel|cancel.load(std::sync::atomic::Ordering::Relaxed)).unwrap_or(false) {
+            self.transcript.stopped=Some(CountStopped::Cancelled);
+            return false;
+        }
+        let normally_continue = (self.remaining_to_elect()>NumberOfCandidates(0) && self.continuing_candidates.len()>0) || (Rules::finish_all_surplus_distributions_when_all_elected() && (!self.continuing_candidates_sorted_by_tally.is_empty()) && !self.pending_surplus_distribution.is_empty());
+        if !normally_continue {
+            if self.always_finish_all_counts && !self.continuing_candidates.is_empty() { self.finishing_counts_after_normal_end=true; }
+            else { return false; }
+        }
+        if self.should_defer_surplus() {
+            self.exclude_lowest();
+        } else {
+            if let Some(candidate) = self.pending_surplus_distribution.pop_front() {
+                self.distribute_surplus(candidate);
             } else {
-                if let Some(candidate) = self.pending_surplus_distribution.pop_front() {
-                    self.distribute_surplus(candidate);
-                } else {
-                    self.exclude_lowest();
+                self.exclude_lowest();
+            }
+        }
+        true
+    }
+
+    /// Tidy up the transcript after the last [Self::step].
+    pub fn finish(&mut self) {
+        if !self.retain_paper_counts_for_every_count {
+            let papers = self.paper_counts(true);
+            let atl_papers = self.atl_paper_counts();
+            if let Some(last) = self.transcript.counts.last_mut() {
+                last.status.papers=papers;
+                last.status.atl_papers=Some(atl_papers);
+            }
+        }
+    }
+}
+
+/// Convert votes grouped by transfer value into [ElectionData], highest transfer value first.
+fn votes_by_transfer_value_to_election_data<'a>(metadata:ElectionMetadata,by_transfer_value:BTreeMap<TransferValue,Vec<VotesWithSameTransferValue<'a>>>) -> ElectionData {
+    let mut atl : Vec<ATL> = vec![];
+    let mut atl_transfer_values : Vec<VoteValueSpecification> = vec![];
+    let mut btl : Vec<BTL> = vec![];
+    let mut btl_transfer_values : Vec<VoteValueSpecification> = vec![];
+    for (tv,votes_vec) in by_transfer_value.into_iter().rev() {
+        let atl_start = atl.len();
+        let btl_start = btl.len();
+        for votes in votes_vec {
+            for vote in votes.votes {
+                match vote.source {
+                    VoteSource::Btl(source) => { btl.push(BTL{ candidates: source.candidates.clone(), n: vote.n.0 }); }
+                    VoteSource::Atl(source) => { atl.push(ATL{ parties: source.parties.clone(), n: vote.n.0, ticket_index: None }); }
                 }
             }
         }
+        if atl_start!=atl.len() {
+            atl_transfer_values.push(VoteValueSpecification{
+                value: tv.clone(),
+                first_index_inclusive: atl_start,
+                last_index_exclusive: atl.len(),
+            })
+        }
+        if btl_start!=btl.len() {
+            btl_transfer_values.push(VoteValueSpecification{
+                value: tv,
+                first_index_inclusive: btl_start,
+                last_index_exclusive: btl.len(),
+            })
+        }
+    };
+    ElectionData{
+        metadata,
+        atl,
+        atl_types: vec![],
+        atl_transfer_values,
+        btl,
+        btl_types: vec![],
+        btl_transfer_values,
+        atl_annotations: vec![],
+        btl_annotations: vec![],
+        informal: 0,
     }
 }
 
@@ -1344,6 +1964,97 @@ pub fn distribute_preferences_with_extractors<Rules:PreferenceDistributionRules>
     work.transcript
 }
 
+/// A tally as a negative signed value, for recording removals in the tally audit.
+fn negative<Tally:Zero>(value:Tally) -> SignedVersion<Tally> { SignedVersion{ negative: !value.is_zero(), value } }
+
+/// Optional variations on a count, for [distribute_preferences_with_options]. The default is an ordinary count.
+pub struct CountOptions<'a,Tally:PartialEq+Clone+Display+FromStr> {
+    /// If present, only count votes of these types. See [ElectionData::resolve_atl_including_weights].
+    pub vote_types : Option<&'a [String]>,
+    pub print_progress_to_stdout : bool,
+    /// Votes to extract partway through the count. See [ExtractionRequest].
+    pub extractors : &'a [ExtractionRequest],
+    pub include_list_of_votes_in_transcript : bool,
+    /// If present, filled in with a log of every change made to a tally. See [crate::tally_audit].
+    pub tally_audit : Option<&'a mut TallyAudit<Tally>>,
+    /// Candidates protected from exclusion. See [PreferenceDistributor::set_protected].
+    pub protected : &'a [CandidateIndex],
+    /// If present, may override where votes go. See [PreferenceOracle].
+    pub oracle : Option<&'a mut dyn PreferenceOracle>,
+    /// If present, use this quota rather than computing it. Use with [PreferenceDistributor::export_current_state_as_election_data] to resume a count.
+    pub fixed_quota : Option<Tally>,
+    /// Keep counting after the count would normally stop; this never changes who is elected. See [PreferenceDistributor::set_always_finish_all_counts].
+    pub always_finish_all_counts : bool,
+    /// Candidates who receive no transfers after a given count. See [PreferenceDistributor::set_freeze_candidate_after].
+    pub freeze_candidate_after : &'a [(CandidateIndex,CountIndex)],
+    /// Limits on how many of a group of candidates may be elected. See [PreferenceDistributor::set_representation_constraints].
+    pub representation_constraints : &'a [RepresentationConstraint],
+    /// Stop the count at a tie that the legislation provides no procedure for. See [PreferenceDistributor::set_strict_ties].
+    pub strict_ties : bool,
+    /// If present, stop the count when this is set. See [PreferenceDistributor::set_cancel].
+    pub cancel : Option<&'a AtomicBool>,
+}
+
+impl <'a,Tally:PartialEq+Clone+Display+FromStr> Default for CountOptions<'a,Tally> {
+    fn default() -> Self {
+        CountOptions{
+            vote_types: None,
+            print_progress_to_stdout: false,
+            extractors: &[],
+            include_list_of_votes_in_transcript: false,
+            tally_audit: None,
+            protected: &[],
+            oracle: None,
+            fixed_quota: None,
+            always_finish_all_counts: false,
+            freeze_candidate_after: &[],
+            representation_constraints: &[],
+            strict_ties: false,
+            cancel: None,
+        }
+    }
+}
+
+/// Like [distribute_preferences], but with the variations given in `options`.
+pub fn distribute_preferences_with_options<Rules:PreferenceDistributionRules>(data:&ElectionData,candidates_to_be_elected : NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:& TieResolutionsMadeByEC,randomness:&mut Randomness,options:CountOptions<'_,Rules::Tally>) -> Transcript<Rules::Tally> {
+    let arena = typed_arena::Arena::<CandidateIndex>::new();
+    let votes = data.resolve_atl_including_weights(&arena,options.vote_types);
+    let mut work : PreferenceDistributor<'_,Rules> = PreferenceDistributor::new(data,&votes,candidates_to_be_elected,excluded_candidates,ec_resolutions,options.print_progress_to_stdout,options.oracle.map(|oracle|oracle as &mut dyn PreferenceOracle),randomness,options.extractors,options.include_list_of_votes_in_transcript);
+    work.set_protected(options.protected);
+    work.set_freeze_candidate_after(options.freeze_candidate_after);
+    work.set_representation_constraints(options.representation_constraints);
+    if let Some(quota) = options.fixed_quota { work.set_fixed_quota(quota); }
+    work.set_always_finish_all_counts(options.always_finish_all_counts);
+    work.set_strict_ties(options.strict_ties);
+    work.set_cancel(options.cancel);
+    if options.tally_audit.is_some() { work.enable_tally_audit(); }
+    work.go();
+    if let Some(tally_audit) = options.tally_audit { *tally_audit=work.take_tally_audit().unwrap_or_default(); }
+    work.transcript
+}
+
+/// Like [distribute_preferences], but rather than resolving by randomness a tie that the legislation provides no procedure for
+/// ([MethodOfTieResolution::None]) and that ec_resolutions does not resolve, stop the count and return an error identifying it.
+/// This forces the user to supply a resolution for such ties. Ties the legislation says to resolve by lot are still resolved by `randomness`.
+pub fn distribute_preferences_strict<Rules:PreferenceDistributionRules>(data:&ElectionData,candidates_to_be_elected : NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:& TieResolutionsMadeByEC,vote_types : Option<&[String]>,randomness:&mut Randomness) -> Result<Transcript<Rules::Tally>,UnresolvedTie> {
+    let transcript = distribute_preferences_with_options::<Rules>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,randomness,CountOptions{ vote_types, strict_ties: true, ..Default::default() });
+    match transcript.stopped {
+        Some(CountStopped::UnresolvedTie(tie)) => Err(tie),
+        _ => Ok(transcript),
+    }
+}
+
+/// Like [distribute_preferences], but only the last count in the transcript has per candidate paper counts. See [PreferenceDistributor::set_retain_full_count_status].
+/// This is faster for callers who only need the final result, such as Monte Carlo experiments.
+pub fn distribute_preferences_final_status_only<Rules:PreferenceDistributionRules>(data:&ElectionData,candidates_to_be_elected : NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:& TieResolutionsMadeByEC,vote_types : Option<&[String]>,randomness:&mut Randomness) -> Transcript<Rules::Tally> {
+    let arena = typed_arena::Arena::<CandidateIndex>::new();
+    let votes = data.resolve_atl_including_weights(&arena,vote_types);
+    let mut work : PreferenceDistributor<'_,Rules> = PreferenceDistributor::new(data,&votes,candidates_to_be_elected,excluded_candidates,ec_resolutions,false,None,randomness,&[],false);
+    work.set_retain_full_count_status(false);
+    work.go();
+    work.transcript
+}
+
 pub fn distribute_preferences<Rules:PreferenceDistributionRules>(data:&ElectionData,candidates_to_be_elected : NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:& TieResolutionsMadeByEC,vote_types : Option<&[String]>,print_progress_to_stdout:bool,randomness:&mut Randomness) -> Transcript<Rules::Tally> {
     distribute_preferences_with_extractors::<Rules>(data,candidates_to_be_elected,excluded_candidates,ec_resolutions,vote_types,print_progress_to_stdout,randomness,&[],false)
 }
\ No newline at end of file
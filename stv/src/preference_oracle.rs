@@ -0,0 +1,37 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A hook for overriding where ballot papers go when they are distributed, instead of following their preferences.
+//!
+//! This is used to replay an official distribution of preferences when the actual ballots are not published
+//! ([crate::verify_official_transcript::OracleFromOfficialDOP]), and can be used for counterfactual questions
+//! such as "what if 10% of candidate X's ballots exhausted instead of going to their next preference".
+
+use crate::ballot_metadata::CandidateIndex;
+use crate::ballot_pile::BallotPaperCount;
+use crate::distribution_of_preferences_transcript::CountIndex;
+use crate::tie_resolution::{TieResolutionAtom, TieResolutionGranularityNeeded};
+
+/// Something that may override where ballot papers go when they are distributed.
+/// Pass to [crate::preference_distribution::CountOptions::oracle] or [crate::preference_distribution::PreferenceDistributor::new].
+///
+/// Determinism requirements: the distribution of preferences should be reproducible, so answers should depend
+/// only on the arguments and on state built up from earlier calls, not on anything external like time or an unseeded random number generator.
+/// The oracle may be asked more than once in the same count (e.g. once per transfer value when excluding a candidate, or
+/// a second time if the continuing candidates changed during a surplus distribution), and should give consistent answers.
+pub trait PreferenceOracle {
+    /// Decide where the ballot papers being distributed in count `current_count` go.
+    /// * `from` is the candidates whose papers are being distributed, empty for first preferences.
+    /// * `natural` is where the papers would go by their preferences: the number of papers for each candidate, followed by the number exhausted.
+    ///
+    /// Return None to use the natural distribution, or the number of papers to give each candidate (indexed by candidate, followed by an element for exhausted).
+    /// Numbers given for candidates who are not continuing are ignored, and papers not given to a candidate are exhausted.
+    /// Papers redirected by an oracle lose their further preferences, so they will exhaust if distributed again unless the oracle directs them then too.
+    fn get_distribution_by_candidate(&mut self,current_count:CountIndex,from:&[CandidateIndex],natural:&[BallotPaperCount]) -> Option<Vec<BallotPaperCount>>;
+
+    /// Optionally resolve a tie amongst `tied_candidates` in count `current_count`, before the EC decisions are consulted.
+    fn resolve_tie_resolution(&mut self,_current_count:CountIndex,_granularity:TieResolutionGranularityNeeded,_tied_candidates:&[CandidateIndex]) -> Option<TieResolutionAtom> { None }
+}
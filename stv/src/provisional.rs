@@ -0,0 +1,57 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Counting with some ballots challenged, and so counted provisionally.
+//!
+//! The challenged ballots are specified by vote type (see [VoteTypeSpecification]). The count is done
+//! both with and without them, to see whether the outcome depends upon how the challenges are resolved.
+
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::ballot_metadata::MissingVacancies;
+use crate::ballot_pile::BallotPaperCount;
+use crate::compare_transcripts::{DeltasInCandidateLists, DifferentCandidateLists};
+use crate::distribution_of_preferences_transcript::Transcript;
+use crate::election_data::{ElectionData, VoteTypeSpecification};
+use crate::preference_distribution::{distribute_preferences, PreferenceDistributionRules};
+use crate::random_util::Randomness;
+
+/// The results of counting with and without the provisional ballots.
+#[derive(Clone,Serialize,Deserialize,Debug)]
+pub struct ProvisionalReport<Tally:PartialEq+Clone+Display+FromStr+Debug> {
+    /// The vote types considered provisional.
+    pub provisional_vote_types : Vec<String>,
+    /// The number of ballot papers of the provisional vote types.
+    pub provisional_ballots : BallotPaperCount,
+    /// The count with the provisional ballots included.
+    pub including_provisional : Transcript<Tally>,
+    /// The count with the provisional ballots excluded.
+    pub excluding_provisional : Transcript<Tally>,
+    /// The candidates elected, with list1 being those with the provisional ballots included, and list2 without.
+    pub elected_differences : DeltasInCandidateLists,
+}
+
+impl <Tally:PartialEq+Clone+Display+FromStr+Debug> ProvisionalReport<Tally> {
+    /// Whether resolving the challenges to the provisional ballots could change who is elected.
+    pub fn is_outcome_determinative(&self) -> bool { !self.elected_differences.is_empty() }
+}
+
+/// Count the data twice, once including and once excluding the ballots of the vote types in `provisional_vote_types`,
+/// and compare who is elected. Votes not of any type are never provisional.
+pub fn count_with_provisional<Rules:PreferenceDistributionRules>(data:&ElectionData,provisional_vote_types:&[String],randomness:&mut Randomness) -> Result<ProvisionalReport<Rules::Tally>,MissingVacancies> {
+    let vacancies = data.metadata.vacancies.ok_or(MissingVacancies)?;
+    let provisional_vote_types : Vec<String> = provisional_vote_types.iter().filter(|t|!t.is_empty()).cloned().collect();
+    let non_provisional_vote_types : Vec<String> = data.all_vote_types().into_iter().filter(|t|!provisional_vote_types.iter().any(|p|p==t)).map(|t|t.to_string()).chain(std::iter::once(String::new())).collect();
+    let count = |vote_types:Option<&[String]>,randomness:&mut Randomness| distribute_preferences::<Rules>(data,vacancies,&data.metadata.excluded.iter().cloned().collect::<HashSet<_>>(),&data.metadata.tie_resolutions,vote_types,false,randomness);
+    let including_provisional = count(None,randomness);
+    let excluding_provisional = count(Some(&non_provisional_vote_types),randomness);
+    let papers = |votes:&[usize],specs:&[VoteTypeSpecification]| VoteTypeSpecification::restrict(Some(&provisional_vote_types),specs,votes.len()).into_iter().flat_map(|r|votes[r].iter().copied()).sum::<usize>();
+    let provisional_ballots = BallotPaperCount(papers(&data.atl.iter().map(|v|v.n).collect::<Vec<_>>(),&data.atl_types)+papers(&data.btl.iter().map(|v|v.n).collect::<Vec<_>>(),&data.btl_types));
+    let elected_differences = DifferentCandidateLists{list1:including_provisional.elected.clone(),list2:excluding_provisional.elected.clone()}.into();
+    Ok(ProvisionalReport{provisional_vote_types,provisional_ballots,including_provisional,excluding_provisional,elected_differences})
+}
@@ -0,0 +1,153 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Make a version of an election's ballots suitable for public release, reducing the risk that a voter can be identified by an unusual pattern of preferences.
+//!
+//! A voter could be coerced into voting with a distinctive pattern of preferences (an "Italian attack"), which could then be checked in published ballot data.
+//! [ElectionData::to_public_release] does the following:
+//! * Identical ballots are consolidated into a single entry with a count, sorted by decreasing count, so the order of ballots in the original data is not released.
+//! * Per ballot metadata - vote types and annotations such as polling place - is removed. Vote values (transfer values) are kept, as they are needed to count.
+//! * Preference sequences expressed by fewer than k ballots are truncated, dropping their last preferences until they match a sequence expressed by at least k ballots
+//!   (including those truncated to it), or until only [PublicReleasePolicy::min_preferences_kept] preferences are left.
+//!   Longer sequences are truncated first, so a sequence that is only shared by k ballots after other ballots have been truncated to it is kept.
+//!
+//! This gives a k-anonymity style guarantee: every released preference sequence with more than [PublicReleasePolicy::min_preferences_kept]
+//! preferences is shared by at least k released ballots (with the same vote value and, for ATL votes, the same ticket).
+//! Short sequences may still be rare; they are not truncated further as that would lose the first preference.
+//! This only considers the preferences; it does not protect against identification by other means.
+//!
+//! The effect on the count is bounded:
+//! * First preferences are unchanged, so the first count is identical.
+//! * At most [PublicReleaseReport::ballots_truncated] ballots are different from the original, so if this is less than the margin
+//!   of the election (the number of ballots that would need to be changed to change who is elected) the same candidates are elected.
+//!
+//! Truncated ballots may not satisfy the formality rules of the jurisdiction (e.g. a minimum number of BTL preferences), but they can be counted.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::ops::Range;
+use serde::{Serialize,Deserialize};
+use crate::ballot_paper::{ATL, BTL};
+use crate::election_data::{ElectionData, VoteValueSpecification};
+use crate::transfer_value::TransferValue;
+
+/// Parameters for [ElectionData::to_public_release]
+#[derive(Debug,Clone,Copy,Serialize,Deserialize)]
+pub struct PublicReleasePolicy {
+    /// k. Preference sequences expressed by fewer than this many ballots are truncated. 0 or 1 means nothing is truncated.
+    pub min_ballots_per_sequence : usize,
+    /// Sequences are never truncated to fewer than this many preferences. Values less than 1 are treated as 1, so first preferences are always kept.
+    pub min_preferences_kept : usize,
+}
+
+impl Default for PublicReleasePolicy {
+    fn default() -> Self { PublicReleasePolicy{ min_ballots_per_sequence: 1, min_preferences_kept: 1 } }
+}
+
+/// What was done by [ElectionData::to_public_release]
+#[derive(Debug,Clone,Copy,Default,Serialize,Deserialize,PartialEq,Eq)]
+pub struct PublicReleaseReport {
+    /// The number of ballots (ATL and BTL) that had preferences removed.
+    pub ballots_truncated : usize,
+    /// The number of distinct preference sequences (ATL and BTL) in the original data.
+    pub distinct_sequences_before : usize,
+    /// The number of distinct preference sequences (ATL and BTL) released.
+    pub distinct_sequences_after : usize,
+}
+
+/// Ballots with a given key (ticket for ATL votes) and preference sequence.
+struct Consolidated<K,T> {
+    key : K,
+    prefs : Vec<T>,
+    n : usize,
+    /// how many of the n had preferences removed.
+    truncated : usize,
+}
+
+/// Consolidate identical ballots, and truncate rare ones as described in [crate::public_release]. Returns entries sorted by decreasing number of ballots.
+fn consolidate_and_truncate<K:Clone+Eq+Hash,T:Clone+Eq+Hash>(votes:impl Iterator<Item=(K,Vec<T>,usize)>,policy:&PublicReleasePolicy,report:&mut PublicReleaseReport) -> Vec<Consolidated<K,T>> {
+    let mut entries : Vec<Consolidated<K,T>> = vec![];
+    let mut index : HashMap<(K,Vec<T>),usize> = HashMap::new();
+    let mut find_or_add = |entries:&mut Vec<Consolidated<K,T>>,key:&K,prefs:&[T]| -> usize {
+        *index.entry((key.clone(),prefs.to_vec())).or_insert_with(||{ entries.push(Consolidated{ key: key.clone(), prefs: prefs.to_vec(), n: 0, truncated: 0 }); entries.len()-1 })
+    };
+    for (key,prefs,n) in votes {
+        let position = find_or_add(&mut entries,&key,&prefs);
+        entries[position].n+=n;
+    }
+    report.distinct_sequences_before+=entries.len();
+    let min_len = policy.min_preferences_kept.max(1);
+    let max_len = entries.iter().map(|e|e.prefs.len()).max().unwrap_or(0);
+    for len in (min_len+1..=max_len).rev() { // longest first, so everything truncated to a sequence is there before deciding whether to truncate it.
+        for i in 0..entries.len() {
+            if entries[i].prefs.len()==len && entries[i].n>0 && entries[i].n<policy.min_ballots_per_sequence {
+                let key = entries[i].key.clone();
+                let prefix = entries[i].prefs[..len-1].to_vec();
+                let moved = entries[i].n;
+                let position = find_or_add(&mut entries,&key,&prefix);
+                entries[position].n+=moved;
+                entries[position].truncated+=moved;
+                entries[i].n=0;
+                entries[i].truncated=0;
+            }
+        }
+    }
+    entries.retain(|e|e.n>0);
+    entries.sort_by_key(|e|std::cmp::Reverse(e.n)); // stable, so ties stay in the order first seen.
+    report.distinct_sequences_after+=entries.len();
+    report.ballots_truncated+=entries.iter().map(|e|e.truncated).sum::<usize>();
+    entries
+}
+
+/// The ranges of the votes with each vote value, in the manner of [ElectionData::resolve_atl_including_weights].
+fn ranges_by_value(values:&[VoteValueSpecification],num_votes:usize) -> BTreeMap<TransferValue,Vec<Range<usize>>> {
+    let mut res : BTreeMap<TransferValue,Vec<Range<usize>>> = BTreeMap::new();
+    if values.is_empty() { res.entry(TransferValue::one()).or_default().push(0..num_votes); }
+    for v in values { res.entry(v.value.clone()).or_default().push(v.range()); }
+    res
+}
+
+/// Apply [consolidate_and_truncate] separately for each vote value, highest value first. Returns the new votes and their value specifications, if the original had any.
+fn release_by_value<V,K:Clone+Eq+Hash,T:Clone+Eq+Hash>(votes:&[V],values:&[VoteValueSpecification],split:impl Fn(&V)->(K,Vec<T>,usize),join:impl Fn(Consolidated<K,T>)->V,policy:&PublicReleasePolicy,report:&mut PublicReleaseReport) -> (Vec<V>,Vec<VoteValueSpecification>) {
+    let mut res : Vec<V> = vec![];
+    let mut res_values : Vec<VoteValueSpecification> = vec![];
+    for (value,ranges) in ranges_by_value(values,votes.len()).into_iter().rev() {
+        let first_index_inclusive = res.len();
+        let consolidated = consolidate_and_truncate(ranges.into_iter().flat_map(|r|votes[r].iter()).map(&split),policy,report);
+        res.extend(consolidated.into_iter().map(&join));
+        if !values.is_empty() && res.len()>first_index_inclusive { res_values.push(VoteValueSpecification{ value, first_index_inclusive, last_index_exclusive: res.len() }); }
+    }
+    (res,res_values)
+}
+
+impl ElectionData {
+    /// Make a version of this data suitable for public release, as described in [crate::public_release].
+    pub fn to_public_release(&self,policy:&PublicReleasePolicy) -> ElectionData {
+        self.to_public_release_with_report(policy).0
+    }
+
+    /// Like [Self::to_public_release], but also report what was done, in particular how many ballots were altered.
+    pub fn to_public_release_with_report(&self,policy:&PublicReleasePolicy) -> (ElectionData,PublicReleaseReport) {
+        let mut report = PublicReleaseReport::default();
+        let (atl,atl_transfer_values) = release_by_value(&self.atl,&self.atl_transfer_values,|v|(v.ticket_index,v.parties.clone(),v.n),|c|ATL{ parties: c.prefs, n: c.n, ticket_index: c.key },policy,&mut report);
+        let (btl,btl_transfer_values) = release_by_value(&self.btl,&self.btl_transfer_values,|v|((),v.candidates.clone(),v.n),|c|BTL{ candidates: c.prefs, n: c.n },policy,&mut report);
+        let mut metadata = self.metadata.clone();
+        metadata.name.modifications.push(format!("Prepared for public release with sequences of fewer than {} ballots truncated to at least {} preferences; {} ballots truncated",policy.min_ballots_per_sequence,policy.min_preferences_kept.max(1),report.ballots_truncated));
+        (ElectionData{
+            metadata,
+            atl,
+            atl_types: vec![],
+            atl_transfer_values,
+            btl,
+            btl_types: vec![],
+            btl_transfer_values,
+            atl_annotations: vec![],
+            btl_annotations: vec![],
+            informal: self.informal,
+        },report)
+    }
+}
@@ -10,6 +10,8 @@
 
 use rand::distributions::{Distribution, Uniform};
 use rand::prelude::SliceRandom;
+use rand::SeedableRng;
+use sha2::{Digest, Sha256};
 use crate::ballot_metadata::CandidateIndex;
 
 
@@ -38,6 +40,13 @@ impl Randomness {
         }
     }
 
+    /// Use a pseudo random number generator seeded from a published randomness beacon value, such as a drand or NIST beacon round.
+    /// As the beacon value was not known in advance, and anyone can repeat the count with it, this makes random tie resolution
+    /// verifiable after the fact. The seed is the SHA-256 hash of the beacon bytes, so a beacon value of any length may be used.
+    pub fn from_beacon(beacon:&[u8]) -> Self {
+        Randomness::PRNG(rand_chacha::ChaCha20Rng::from_seed(Sha256::digest(beacon).into()))
+    }
+
     /// Make a boolean array of length len such that num_true of them are true.
     /// If the randomness is ReverseDonkeyVote, take the first n.
     /// ```
@@ -76,7 +85,6 @@ impl Randomness {
 
 }
 
-use rand::SeedableRng;
 impl From<Option<u64>> for Randomness {
     fn from(value: Option<u64>) -> Self {
         match value {
@@ -84,4 +92,15 @@ impl From<Option<u64>> for Randomness {
             Some(_) => Randomness::PRNG(rand_chacha::ChaCha20Rng::seed_from_u64(1)),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Parse a beacon value written in hexadecimal, as beacons are usually published, for [Randomness::from_beacon].
+/// ```
+/// assert_eq!(stv::random_util::parse_hex_beacon("00ff1A").unwrap(),vec![0,255,26]);
+/// assert!(stv::random_util::parse_hex_beacon("0f0").is_err());
+/// ```
+pub fn parse_hex_beacon(hex:&str) -> Result<Vec<u8>,String> {
+    let hex = hex.trim();
+    if hex.is_empty() || !hex.len().is_multiple_of(2) { return Err(format!("A beacon value should be a non-empty, even number of hexadecimal digits, not {}",hex)); }
+    (0..hex.len()).step_by(2).map(|i|hex.get(i..i+2).and_then(|byte|u8::from_str_radix(byte,16).ok()).ok_or_else(||format!("{} is not a hexadecimal beacon value",hex))).collect()
+}
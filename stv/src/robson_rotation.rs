@@ -0,0 +1,87 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Simulate the effect of Robson Rotation on an election whose ballots were printed with a fixed candidate order.
+//!
+//! Tasmania and the ACT print several versions of the ballot paper, cyclically rotating the order of the candidates
+//! within each column, so that no candidate systematically gets the donkey vote from voters who just number
+//! a party's candidates in the order printed. This module provides a crude model of what would have happened
+//! in an election without rotation (e.g. a federal Senate election) if it had used rotation.
+//!
+//! The modeling assumptions are:
+//! * A BTL ballot is considered to be following the printed order if its first preferences are *all* the candidates
+//!   of a single column (with at least 2 candidates), in the order printed. Only this leading run is changed; the remaining
+//!   preferences are left unchanged. Ballots that only follow part of a column, or follow a column later in their preferences,
+//!   are left alone, as there is no unambiguous way of rotating them.
+//! * A given proportion of such ballots did so because of position on the ballot. The rest are assumed to genuinely prefer
+//!   that order (e.g. following a how-to-vote card) and are left unchanged.
+//! * Each of the k cyclic rotations of a column is printed equally often, so the position driven ballots are split as evenly
+//!   as possible amongst the k rotations, the unrotated order getting any remainder first.
+//! * ATL votes are unchanged, as the jurisdictions using Robson Rotation don't have them.
+//!
+//! Each ballot keeps a first preference for a candidate in the same column, so party first preference totals are unchanged.
+
+use serde::{Serialize,Deserialize};
+use crate::ballot_metadata::CandidateIndex;
+use crate::ballot_paper::BTL;
+use crate::election_data::{BallotAnnotation, ElectionData, VoteTypeSpecification, VoteValueSpecification};
+
+/// Parameters for [ElectionData::apply_robson_rotation_model]
+#[derive(Debug,Clone,Copy,Serialize,Deserialize)]
+pub struct RobsonRotationParams {
+    /// The proportion (0 to 1) of ballots following the printed order of a column that are assumed to do so because of position on the ballot.
+    /// The number redistributed is rounded down.
+    pub proportion_position_driven : f64,
+}
+
+impl Default for RobsonRotationParams {
+    fn default() -> Self { RobsonRotationParams{ proportion_position_driven: 1.0 } }
+}
+
+impl ElectionData {
+    /// Make a new ElectionData simulating the effect of Robson Rotation, under the assumptions described in [crate::robson_rotation].
+    ///
+    /// A BTL vote that is redistributed is replaced by one BTL vote per rotation; vote type and value annotations are carried across.
+    pub fn apply_robson_rotation_model(&self,params:RobsonRotationParams) -> ElectionData {
+        let mut btl : Vec<BTL> = vec![];
+        let mut new_start : Vec<usize> = vec![]; // new_start[i] is the index in btl of the first vote produced from self.btl[i].
+        for vote in &self.btl {
+            new_start.push(btl.len());
+            let column = self.metadata.parties.iter().map(|p|&p.candidates).find(|c|c.len()>=2 && vote.candidates.starts_with(c));
+            let moved = if column.is_some() { ((vote.n as f64)*params.proportion_position_driven.clamp(0.0,1.0)).floor() as usize } else { 0 };
+            if moved==0 { btl.push(vote.clone()); continue; }
+            let column = column.unwrap();
+            let k = column.len();
+            for rotation in 0..k {
+                let mut n = moved/k;
+                if rotation<moved%k { n+=1; }
+                if rotation==0 { n+=vote.n-moved; } // the ones not moved.
+                if n>0 {
+                    let mut candidates : Vec<CandidateIndex> = column[rotation..].iter().chain(column[..rotation].iter()).cloned().collect();
+                    candidates.extend_from_slice(&vote.candidates[k..]);
+                    btl.push(BTL{ candidates, n });
+                }
+            }
+        }
+        new_start.push(btl.len());
+        let remap = |i:usize| new_start[i.min(self.btl.len())];
+        let mut metadata = self.metadata.clone();
+        metadata.name.modifications.push(format!("Robson rotation modeled with {} of ballots following printed order assumed position driven",params.proportion_position_driven));
+        ElectionData{
+            metadata,
+            atl: self.atl.clone(),
+            atl_types: self.atl_types.clone(),
+            atl_transfer_values: self.atl_transfer_values.clone(),
+            btl,
+            btl_types: self.btl_types.iter().map(|t|VoteTypeSpecification{ vote_type: t.vote_type.clone(), first_index_inclusive: remap(t.first_index_inclusive), last_index_exclusive: remap(t.last_index_exclusive) }).collect(),
+            btl_transfer_values: self.btl_transfer_values.iter().map(|t|VoteValueSpecification{ value: t.value.clone(), first_index_inclusive: remap(t.first_index_inclusive), last_index_exclusive: remap(t.last_index_exclusive) }).collect(),
+            atl_annotations: self.atl_annotations.clone(),
+            btl_annotations: self.btl_annotations.iter().map(|a|BallotAnnotation{ key: a.key.clone(), value: a.value.clone(), first_index_inclusive: remap(a.first_index_inclusive), last_index_exclusive: remap(a.last_index_exclusive) }).collect(),
+            informal: self.informal,
+        }
+    }
+}
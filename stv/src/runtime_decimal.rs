@@ -0,0 +1,368 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+//! A decimal type whose number of decimal places is chosen at run time, rather than at compile time
+//! like [crate::fixed_precision_decimal::FixedPrecisionDecimal]. This lets one set of rules be
+//! parameterised by the number of decimal places without a new tally type for each.
+//!
+//! Each value carries its scale (number of decimal places). Integers, such as those produced by [Zero::zero]
+//! or from a [BallotPaperCount], have scale 0 and are exactly representable at any scale, so they combine with
+//! values of any scale. Combining or comparing two values with different non zero scales is an error, as is a result
+//! too large for an i128; the `checked_` functions return a [RuntimeDecimalError], and the operator traits (which can't
+//! return an error) panic.
+
+use alloc::string::{String, ToString};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::ops::{AddAssign, SubAssign, Sub, Add, Div};
+use core::fmt::{Debug, Display, Formatter};
+use core::iter::Sum;
+use core::num::ParseIntError;
+use core::str::FromStr;
+use num::{Zero, BigRational, BigInt, ToPrimitive};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use crate::arithmetic::{BallotPaperCount, CanConvertToF64PossiblyLossily, RoundUpToUsize};
+
+/// Stores a decimal number as an integer `value` scaled by 10^`scale`.
+#[derive(Copy,Clone)]
+pub struct RuntimeDecimal {
+    scale : u32,
+    value : i128,
+}
+
+/// An attempt to combine or compare two [RuntimeDecimal] values with different non zero scales.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct MismatchedScales {
+    pub left : u32,
+    pub right : u32,
+}
+
+impl Display for MismatchedScales {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f,"Can not combine decimals with {} and {} decimal places",self.left,self.right)
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for MismatchedScales {}
+
+/// Why an operation on [RuntimeDecimal] values failed.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum RuntimeDecimalError {
+    MismatchedScales(MismatchedScales),
+    /// The result, at the scale needed, is too large for an i128.
+    Overflow,
+}
+
+impl From<MismatchedScales> for RuntimeDecimalError {
+    fn from(e: MismatchedScales) -> Self { RuntimeDecimalError::MismatchedScales(e) }
+}
+
+impl Display for RuntimeDecimalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RuntimeDecimalError::MismatchedScales(e) => write!(f,"{}",e),
+            RuntimeDecimalError::Overflow => write!(f,"Decimal value too large"),
+        }
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for RuntimeDecimalError {}
+
+/// Why a string could not be parsed as a [RuntimeDecimal].
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub enum ParseRuntimeDecimalError {
+    Int(ParseIntError),
+    /// More than [RuntimeDecimal::MAX_SCALE] decimal places.
+    TooManyDecimalPlaces(usize),
+}
+
+impl Display for ParseRuntimeDecimalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseRuntimeDecimalError::Int(e) => write!(f,"{}",e),
+            ParseRuntimeDecimalError::TooManyDecimalPlaces(places) => write!(f,"{} decimal places is more than the maximum of {}",places,RuntimeDecimal::MAX_SCALE),
+        }
+    }
+}
+
+impl RuntimeDecimal {
+    /// The largest supported scale, as 10^38 is the largest power of 10 that fits in an i128.
+    pub const MAX_SCALE : u32 = 38;
+
+    /// The value representing scaled_value/10^scale.
+    pub fn new(scaled_value:i128,scale:u32) -> Self {
+        assert!(scale<=Self::MAX_SCALE,"Scale {} is more than the maximum of {}",scale,Self::MAX_SCALE);
+        RuntimeDecimal{scale,value:scaled_value}
+    }
+    /// The number of decimal places.
+    pub fn scale(&self) -> u32 { self.scale }
+    /// return 10^scale * the value this number represents.
+    pub fn get_scaled_value(&self) -> i128 { self.value }
+
+    fn power_of_ten(scale:u32) -> i128 { 10i128.pow(scale) }
+
+    /// The scale of the result of combining self and other.
+    fn common_scale(&self,other:&Self) -> Result<u32,MismatchedScales> {
+        if self.scale==other.scale || other.scale==0 { Ok(self.scale) }
+        else if self.scale==0 { Ok(other.scale) }
+        else { Err(MismatchedScales{left:self.scale,right:other.scale}) }
+    }
+    /// The scaled value at a scale at least as large as self's.
+    fn value_at_scale(&self,scale:u32) -> Result<i128,RuntimeDecimalError> { self.value.checked_mul(Self::power_of_ten(scale-self.scale)).ok_or(RuntimeDecimalError::Overflow) }
+
+    /// The same value with the given number of decimal places. Only works for integers (scale 0) or if the scale is unchanged.
+    pub fn with_scale(&self,scale:u32) -> Result<Self,RuntimeDecimalError> {
+        if self.scale==scale || self.scale==0 { Ok(RuntimeDecimal::new(self.value_at_scale(scale)?,scale)) }
+        else { Err(MismatchedScales{left:self.scale,right:scale}.into()) }
+    }
+
+    pub fn checked_add(&self,rhs:&Self) -> Result<Self,RuntimeDecimalError> {
+        let scale = self.common_scale(rhs)?;
+        Ok(RuntimeDecimal{scale,value:self.value_at_scale(scale)?.checked_add(rhs.value_at_scale(scale)?).ok_or(RuntimeDecimalError::Overflow)?})
+    }
+    pub fn checked_sub(&self,rhs:&Self) -> Result<Self,RuntimeDecimalError> {
+        let scale = self.common_scale(rhs)?;
+        Ok(RuntimeDecimal{scale,value:self.value_at_scale(scale)?.checked_sub(rhs.value_at_scale(scale)?).ok_or(RuntimeDecimalError::Overflow)?})
+    }
+    pub fn checked_cmp(&self,rhs:&Self) -> Result<Ordering,RuntimeDecimalError> {
+        let scale = self.common_scale(rhs)?;
+        Ok(self.value_at_scale(scale)?.cmp(&rhs.value_at_scale(scale)?))
+    }
+
+    pub fn to_rational(&self) -> BigRational { BigRational::new(BigInt::from(self.value),BigInt::from(Self::power_of_ten(self.scale))) }
+    /// The largest value with the given number of decimal places that is not more than rational.
+    pub fn from_rational_rounding_down(rational:BigRational,scale:u32) -> Self {
+        let scaled = (rational*BigInt::from(Self::power_of_ten(scale))).floor().to_integer();
+        RuntimeDecimal::new(scaled.to_i128().expect("Value too large for a RuntimeDecimal"),scale)
+    }
+}
+
+impl From<RuntimeDecimal> for f64 {
+    fn from(v: RuntimeDecimal) -> Self { v.convert_to_f64() }
+}
+impl CanConvertToF64PossiblyLossily for RuntimeDecimal {
+    fn convert_to_f64(&self) -> f64 {
+        self.value as f64/(RuntimeDecimal::power_of_ten(self.scale) as f64)
+    }
+}
+
+impl AddAssign for RuntimeDecimal {
+    fn add_assign(&mut self, rhs: Self) { *self=*self+rhs; }
+}
+impl SubAssign for RuntimeDecimal {
+    fn sub_assign(&mut self, rhs: Self) { *self=*self-rhs; }
+}
+impl Add for RuntimeDecimal {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output { self.checked_add(&rhs).unwrap_or_else(|e|panic!("{}",e)) }
+}
+impl Sub for RuntimeDecimal {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output { self.checked_sub(&rhs).unwrap_or_else(|e|panic!("{}",e)) }
+}
+
+impl PartialEq for RuntimeDecimal {
+    fn eq(&self, other: &Self) -> bool { self.cmp(other)==Ordering::Equal }
+}
+impl Eq for RuntimeDecimal {}
+impl PartialOrd for RuntimeDecimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+impl Ord for RuntimeDecimal {
+    fn cmp(&self, other: &Self) -> Ordering { self.checked_cmp(other).unwrap_or_else(|e|panic!("{}",e)) }
+}
+impl Hash for RuntimeDecimal {
+    /// The scaled value is hashed without trailing zeros after the decimal point, so that equal values at different scales
+    /// (such as an integer and the same integer with decimal places) hash the same, consistent with [PartialEq].
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut value = self.value;
+        let mut scale = self.scale;
+        while scale>0 && value%10==0 { value/=10; scale-=1; }
+        value.hash(state);
+        scale.hash(state);
+    }
+}
+
+impl From<BallotPaperCount> for RuntimeDecimal {
+    fn from(v: BallotPaperCount) -> Self { RuntimeDecimal{scale:0,value:v.0 as i128} }
+}
+
+impl Zero for RuntimeDecimal {
+    fn zero() -> Self { RuntimeDecimal{scale:0,value:0} }
+    fn is_zero(&self) -> bool { self.value==0 }
+}
+
+impl Display for RuntimeDecimal {
+    /// Shows all the decimal places, so that the scale is preserved when parsed.
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        if self.scale==0 { write!(f,"{}",self.value) }
+        else {
+            let scale = RuntimeDecimal::power_of_ten(self.scale).unsigned_abs();
+            let magnitude = self.value.unsigned_abs();
+            write!(f,"{}{}.{:03$}",if self.value<0 {"-"} else {""},magnitude/scale,magnitude%scale,self.scale as usize)
+        }
+    }
+}
+
+impl Debug for RuntimeDecimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f,"{}",self)
+    }
+}
+
+impl Sum for RuntimeDecimal {
+    fn sum<I: Iterator<Item=Self>>(iter: I) -> Self {
+        let mut res = Self::zero();
+        for v in iter {
+            res+=v
+        }
+        res
+    }
+}
+
+impl <'a> Sum<&'a Self> for RuntimeDecimal {
+    fn sum<I: Iterator<Item=&'a Self>>(iter: I) -> Self {
+        let mut res = Self::zero();
+        for v in iter {
+            res+=*v
+        }
+        res
+    }
+}
+
+impl FromStr for RuntimeDecimal {
+    type Err = ParseRuntimeDecimalError;
+
+    /// The scale is the number of digits after the decimal point, if any.
+    fn from_str(buf: &str) -> Result<Self, Self::Err> {
+        if let Some((int_part,frac_part)) = buf.split_once('.') {
+            if frac_part.len()>Self::MAX_SCALE as usize { return Err(ParseRuntimeDecimalError::TooManyDecimalPlaces(frac_part.len())); }
+            let value : i128 = (int_part.to_string()+frac_part).parse().map_err(ParseRuntimeDecimalError::Int)?;
+            Ok(RuntimeDecimal{ scale: frac_part.len() as u32, value })
+        } else {
+            Ok(RuntimeDecimal{ scale: 0, value: buf.parse().map_err(ParseRuntimeDecimalError::Int)? })
+        }
+    }
+}
+
+impl Serialize for RuntimeDecimal {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl <'de> Deserialize<'de> for RuntimeDecimal {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let buf = String::deserialize(deserializer)?;
+        buf.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl RoundUpToUsize for RuntimeDecimal {
+    fn ceil(&self) -> usize {
+        let scale = RuntimeDecimal::power_of_ten(self.scale);
+        (-(-self.value).div_euclid(scale)).to_usize().unwrap_or(0) // like isize, negative values give 0.
+    }
+}
+
+impl Div<usize> for RuntimeDecimal {
+    type Output = Self;
+
+    fn div(self, rhs: usize) -> Self::Output {
+        RuntimeDecimal{scale:self.scale,value: self.value/(rhs as i128)}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use crate::runtime_decimal::{MismatchedScales, RuntimeDecimal, RuntimeDecimalError};
+    use num::Zero;
+    use crate::arithmetic::{BallotPaperCount, RoundUpToUsize};
+
+    fn check_scale(scale:u32) {
+        assert!(RuntimeDecimal::zero().is_zero());
+        let d_42 : RuntimeDecimal = BallotPaperCount(42).into();
+        assert_eq!("42",format!("{}",d_42));
+        let third = RuntimeDecimal::from_rational_rounding_down(num::BigRational::new(1.into(),3.into()),scale);
+        assert_eq!(third.scale(),scale);
+        let expected_third = "0.".to_string()+&"3".repeat(scale as usize);
+        assert_eq!(expected_third,format!("{}",third));
+        // integers adopt the scale of what they are combined with.
+        let sum = d_42+third;
+        assert_eq!(sum.scale(),scale);
+        assert_eq!(format!("42.{}","3".repeat(scale as usize)),format!("{}",sum));
+        assert_eq!(d_42,sum-third);
+        assert!(sum>d_42);
+        assert_eq!(sum.ceil(),43);
+        let total : RuntimeDecimal = [third,third,third].iter().sum();
+        assert_eq!(format!("0.{}","9".repeat(scale as usize)),format!("{}",total));
+        assert_eq!(total.ceil(),1);
+        let parsed : RuntimeDecimal = format!("{}",sum).parse().unwrap();
+        assert_eq!(parsed.scale(),scale);
+        assert_eq!(parsed,sum);
+        assert_eq!(d_42.with_scale(scale).unwrap().get_scaled_value(),42*10i128.pow(scale));
+    }
+
+    #[test]
+    fn test_scale_5() { check_scale(5); }
+    #[test]
+    fn test_scale_6() { check_scale(6); }
+    #[test]
+    fn test_scale_8() { check_scale(8); }
+
+    #[test]
+    fn test_mismatched_scales_rejected() {
+        let five : RuntimeDecimal = "1.00001".parse().unwrap();
+        let eight : RuntimeDecimal = "1.00000001".parse().unwrap();
+        assert_eq!(five.checked_add(&eight),Err(RuntimeDecimalError::MismatchedScales(MismatchedScales{left:5,right:8})));
+        assert!(five.checked_sub(&eight).is_err());
+        assert!(five.checked_cmp(&eight).is_err());
+        assert!(five.with_scale(8).is_err());
+        assert!(std::panic::catch_unwind(||five+eight).is_err());
+        assert!(std::panic::catch_unwind(||five<eight).is_err());
+    }
+
+    fn hash(v:&RuntimeDecimal) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        v.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_hash_uses_fractional_part() {
+        let a : RuntimeDecimal = "1.25".parse().unwrap();
+        let b : RuntimeDecimal = "1.50".parse().unwrap();
+        assert_ne!(hash(&a),hash(&b));
+        // equal values at different scales hash the same.
+        let one : RuntimeDecimal = "1".parse().unwrap();
+        assert_eq!(one,"1.00".parse::<RuntimeDecimal>().unwrap());
+        assert_eq!(hash(&one),hash(&"1.00".parse().unwrap()));
+        assert_eq!(hash(&"1.5".parse().unwrap()),hash(&b));
+    }
+
+    #[test]
+    fn test_ceil_of_negative_is_zero() {
+        let v : RuntimeDecimal = "-2.5".parse().unwrap();
+        assert_eq!(v.ceil(),0);
+    }
+
+    #[test]
+    fn test_overflow_is_an_error() {
+        let big = RuntimeDecimal::new(i128::MAX/10,0);
+        let small : RuntimeDecimal = "0.01".parse().unwrap();
+        assert_eq!(big.checked_add(&small),Err(RuntimeDecimalError::Overflow));
+        assert_eq!(big.checked_cmp(&small),Err(RuntimeDecimalError::Overflow));
+        assert_eq!(big.with_scale(2),Err(RuntimeDecimalError::Overflow));
+        let max = RuntimeDecimal::new(i128::MAX,0);
+        assert_eq!(max.checked_add(&max),Err(RuntimeDecimalError::Overflow));
+    }
+}
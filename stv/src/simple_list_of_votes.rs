@@ -7,7 +7,7 @@
 
 //! A simple list of votes
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Display, Formatter};
 use std::num::{ParseIntError};
 use std::str::FromStr;
@@ -26,20 +26,26 @@ pub struct ListOfVotes {
     pub tvs : Vec<VotesWithGivenTransferValue>
 }
 
+/// Votes in the order first seen, and a map from preferences to position in that list.
+type VotesInOrderFirstSeen<'a> = (Vec<(&'a Vec<CandidateIndex>,isize)>,HashMap<&'a Vec<CandidateIndex>,usize>);
+
 impl ListOfVotes {
+    /// Compute self-rhs. The result is ordered by transfer value highest to lowest, and within a transfer value
+    /// votes are in the order they first appear in self then rhs, so the result is the same every run.
     pub fn sub<'a>(&'a self,rhs:&'a Self) -> Self {
-        let mut by_tv : HashMap<TransferValue,HashMap<&'a Vec<CandidateIndex>,isize>> = HashMap::new();
+        let mut by_tv : BTreeMap<TransferValue,VotesInOrderFirstSeen<'a>> = BTreeMap::new();
         let mut add = |mul:isize,what:&'a Self| { // add mul*what to by_tv
             for vtv in &what.tvs {
-                let tv = by_tv.entry(vtv.tv.clone()).or_insert_with(||HashMap::new());
+                let (votes,index) = by_tv.entry(vtv.tv.clone()).or_default();
                 for v in &vtv.votes {
-                    *tv.entry(&v.candidates).or_insert(0)+=mul*v.n;
+                    let position = *index.entry(&v.candidates).or_insert_with(||{ votes.push((&v.candidates,0)); votes.len()-1 });
+                    votes[position].1+=mul*v.n;
                 }
             }
         };
         add(1,self);
         add(-1,rhs);
-        let tvs = by_tv.into_iter().map(|(tv,vs)|VotesWithGivenTransferValue{ tv, votes: vs.into_iter().filter(|(_,n)|*n!=0).map(|(v,n)|Vote{n,candidates:v.clone()}).collect() }).filter(|v|!v.votes.is_empty()).collect();
+        let tvs = by_tv.into_iter().rev().map(|(tv,(vs,_))|VotesWithGivenTransferValue{ tv, votes: vs.into_iter().filter(|(_,n)|*n!=0).map(|(v,n)|Vote{n,candidates:v.clone()}).collect() }).filter(|v|!v.votes.is_empty()).collect();
         ListOfVotes{tvs}
     }
 }
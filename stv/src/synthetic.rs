@@ -0,0 +1,190 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Generate randomized but vaguely realistic election data, for testing and benchmarking.
+//!
+//! The model is:
+//! * Each group (a party, or an ungrouped independent candidate) gets a support level drawn from a symmetric
+//!   Dirichlet distribution with the given concentration. Small concentrations give a few dominant parties and a long
+//!   tail, large concentrations give evenly matched parties.
+//! * Each voter ranks groups by repeatedly choosing one of the remaining groups with probability proportional to support
+//!   (a Plackett-Luce model), stopping after each group with probability equal to the exhaustion rate.
+//! * Above the line voters just list the parties. Below the line voters list each chosen party's candidates, usually in
+//!   the party's order, but shuffled with probability 1-party_loyalty.
+//!
+//! The same parameters and seed always give the same data.
+
+use std::collections::BTreeMap;
+use rand::{Rng, SeedableRng};
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha20Rng;
+use crate::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, ElectionName, NumberOfCandidates, Party, PartyIndex};
+use crate::ballot_paper::{ATL, BTL};
+use crate::election_data::ElectionData;
+
+/// Parameters for [generate].
+#[derive(Clone,Debug)]
+pub struct SyntheticElectionParams {
+    /// The number of parties, each of which may be voted for above the line.
+    pub parties : usize,
+    /// The number of candidates in each party.
+    pub candidates_per_party : usize,
+    /// The number of ungrouped candidates, not in any party.
+    pub independents : usize,
+    /// The number of vacancies to fill.
+    pub vacancies : usize,
+    /// The number of formal ballots.
+    pub voters : usize,
+    /// The Dirichlet concentration parameter for group support. Must be positive. 1 is a reasonable value.
+    pub concentration : f64,
+    /// The probability that a voter stops marking preferences after each group. 0 means every voter gives a full ranking.
+    pub exhaustion_rate : f64,
+    /// The proportion of voters who vote above the line.
+    pub atl_fraction : f64,
+    /// The probability that a below the line voter lists a party's candidates in the party's order.
+    pub party_loyalty : f64,
+}
+
+impl Default for SyntheticElectionParams {
+    fn default() -> Self {
+        SyntheticElectionParams{ parties: 8, candidates_per_party: 2, independents: 2, vacancies: 6, voters: 10000, concentration: 1.0, exhaustion_rate: 0.2, atl_fraction: 0.8, party_loyalty: 0.8 }
+    }
+}
+
+impl SyntheticElectionParams {
+    fn num_candidates(&self) -> usize { self.parties*self.candidates_per_party+self.independents }
+}
+
+/// A standard normal deviate, by the Box-Muller transform.
+fn standard_normal(rng:&mut ChaCha20Rng) -> f64 {
+    let u1 : f64 = 1.0-rng.gen::<f64>(); // in (0,1], so ln is finite.
+    let u2 : f64 = rng.gen();
+    (-2.0*u1.ln()).sqrt()*(2.0*std::f64::consts::PI*u2).cos()
+}
+
+/// A Gamma(shape,1) deviate, by the method of Marsaglia and Tsang.
+fn gamma(rng:&mut ChaCha20Rng,shape:f64) -> f64 {
+    if shape<1.0 { // boost, see Marsaglia and Tsang section 6.
+        let u : f64 = 1.0-rng.gen::<f64>();
+        return gamma(rng,shape+1.0)*u.powf(1.0/shape);
+    }
+    let d = shape-1.0/3.0;
+    let c = 1.0/(9.0*d).sqrt();
+    loop {
+        let x = standard_normal(rng);
+        let v = 1.0+c*x;
+        if v<=0.0 { continue; }
+        let v = v*v*v;
+        let u : f64 = 1.0-rng.gen::<f64>();
+        if u.ln()<0.5*x*x+d-d*v+d*v.ln() { return d*v; }
+    }
+}
+
+/// Choose a ranking of groups, stopping after each with probability exhaustion_rate. At least one group is chosen.
+fn rank_groups(rng:&mut ChaCha20Rng,support:&[f64],exhaustion_rate:f64) -> Vec<usize> {
+    let mut remaining : Vec<usize> = (0..support.len()).collect();
+    let mut res = vec![];
+    while !remaining.is_empty() {
+        let total : f64 = remaining.iter().map(|&g|support[g]).sum();
+        let mut r = rng.gen::<f64>()*total;
+        let mut chosen = remaining.len()-1; // in case of floating point rounding.
+        for (i,&g) in remaining.iter().enumerate() {
+            if r<support[g] { chosen=i; break; }
+            r-=support[g];
+        }
+        res.push(remaining.remove(chosen));
+        if rng.gen_bool(exhaustion_rate) { break; }
+    }
+    res
+}
+
+/// A column name like on a ballot paper : A,B,...,Z,AA,AB,...
+fn column_id(mut index:usize) -> String {
+    let mut res = String::new();
+    loop {
+        res.insert(0,(b'A'+(index%26) as u8) as char);
+        if index<26 { return res; }
+        index=index/26-1;
+    }
+}
+
+/// Generate election data using the model described in the module documentation.
+/// Candidates are numbered party by party, then the independents. Identical votes are merged.
+///
+/// Panics if the parameters are inconsistent, e.g. more vacancies than candidates.
+pub fn generate(params:&SyntheticElectionParams,seed:u64) -> ElectionData {
+    let num_candidates = params.num_candidates();
+    assert!(num_candidates>0,"Need at least one candidate");
+    assert!(params.vacancies<=num_candidates,"Can't have {} vacancies with only {} candidates",params.vacancies,num_candidates);
+    assert!(params.concentration>0.0,"Dirichlet concentration must be positive");
+    assert!((0.0..=1.0).contains(&params.exhaustion_rate) && (0.0..=1.0).contains(&params.atl_fraction) && (0.0..=1.0).contains(&params.party_loyalty),"Probabilities must be between 0 and 1");
+    let mut rng = ChaCha20Rng::seed_from_u64(seed);
+    let candidates : Vec<Candidate> = (0..num_candidates).map(|c|{
+        let party = if c<params.parties*params.candidates_per_party { Some(c/params.candidates_per_party) } else { None };
+        Candidate{ name: format!("Candidate {}",c), party: party.map(PartyIndex), position: party.map(|_|1+c%params.candidates_per_party), ec_id: None }
+    }).collect();
+    let parties : Vec<Party> = (0..params.parties).map(|p|Party{
+        column_id: column_id(p),
+        name: format!("Party {}",p),
+        abbreviation: None,
+        atl_allowed: true,
+        candidates: (p*params.candidates_per_party..(p+1)*params.candidates_per_party).map(CandidateIndex).collect(),
+        tickets: vec![],
+        official_atl_votes: None
+    }).collect();
+    // groups are the parties, then each independent.
+    let group_candidates : Vec<Vec<CandidateIndex>> = parties.iter().map(|p|p.candidates.clone()).chain((params.parties*params.candidates_per_party..num_candidates).map(|c|vec![CandidateIndex(c)])).collect();
+    let gammas : Vec<f64> = (0..group_candidates.len()).map(|_|gamma(&mut rng,params.concentration)).collect();
+    let total_gamma : f64 = gammas.iter().sum();
+    let support : Vec<f64> = gammas.iter().map(|g|if total_gamma>0.0 { g/total_gamma } else { 1.0 }).collect();
+    let party_support = &support[..params.parties];
+    let mut atl : BTreeMap<Vec<usize>,usize> = BTreeMap::new();
+    let mut btl : BTreeMap<Vec<usize>,usize> = BTreeMap::new();
+    for _ in 0..params.voters {
+        if params.parties>0 && rng.gen_bool(params.atl_fraction) {
+            *atl.entry(rank_groups(&mut rng,party_support,params.exhaustion_rate)).or_insert(0)+=1;
+        } else {
+            let mut prefs = vec![];
+            for group in rank_groups(&mut rng,&support,params.exhaustion_rate) {
+                let mut in_group : Vec<usize> = group_candidates[group].iter().map(|c|c.0).collect();
+                if !rng.gen_bool(params.party_loyalty) { in_group.shuffle(&mut rng); }
+                prefs.extend(in_group);
+            }
+            *btl.entry(prefs).or_insert(0)+=1;
+        }
+    }
+    ElectionData {
+        metadata: ElectionMetadata {
+            name: ElectionName {
+                year: "".to_string(),
+                authority: "Synthetic".to_string(),
+                name: "Synthetic".to_string(),
+                electorate: "".to_string(),
+                modifications: vec![],
+                comment: Some(format!("Generated by stv::synthetic with seed {} from {:?}",seed,params))
+            },
+            candidates,
+            parties,
+            source: vec![],
+            results: None,
+            vacancies: Some(NumberOfCandidates(params.vacancies)),
+            enrolment: None,
+            secondary_vacancies: None,
+            excluded: vec![],
+            withdrawn_before_count: vec![],
+            tie_resolutions: Default::default()
+        },
+        atl: atl.into_iter().map(|(parties,n)|ATL{ parties: parties.into_iter().map(PartyIndex).collect(), n, ticket_index: None }).collect(),
+        atl_types: vec![],
+        atl_transfer_values: vec![],
+        btl: btl.into_iter().map(|(candidates,n)|BTL{ candidates: candidates.into_iter().map(CandidateIndex).collect(), n }).collect(),
+        btl_types: vec![],
+        btl_transfer_values: vec![],
+        atl_annotations: vec![],
+        btl_annotations: vec![],
+        informal: 0
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! An optional, very detailed, log of every change made to a tally during a count.
+//!
+//! The transcript only shows the state at the end of each count. When a jurisdiction's numbers
+//! don't reconcile with ConcreteSTV's, it can help to see every individual change that produced
+//! those states. This is much bigger than the transcript, so is only produced on request, see
+//! [crate::preference_distribution::CountOptions::tally_audit].
+
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+use num::Zero;
+use serde::{Serialize,Deserialize};
+use crate::ballot_metadata::CandidateIndex;
+use crate::distribution_of_preferences_transcript::CountIndex;
+use crate::signed_version::SignedVersion;
+use crate::transfer_value::TransferValue;
+
+/// Which tally was changed.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub enum TallyAuditTarget {
+    Candidate(CandidateIndex),
+    Exhausted,
+    /// Value lost to rounding (which may be negative).
+    Rounding,
+    /// Value set aside, e.g. due to limiting a transfer value to the incoming transfer value.
+    SetAside,
+}
+
+/// Why the tally was changed.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub enum TallyMutation {
+    /// Ballot papers transferred to the target (including as first preferences).
+    Transfer,
+    /// Ballot papers transferred by the NSW random selection method.
+    RandomTransfer,
+    /// Ballot papers transferred by the Cincinnati every nth ballot method.
+    EveryNthTransfer,
+    /// Exhausted value given to continuing candidates in proportion to their tallies.
+    ExhaustedRedistributedProportionally,
+    /// The surplus removed from an elected candidate prior to distribution.
+    SurplusRemoved,
+    /// The votes of an excluded candidate removed prior to distribution.
+    ExcludedVotesRemoved,
+    /// The difference between the value removed and the value given out in a transfer.
+    Rounding,
+    /// Value set aside.
+    SetAside,
+}
+
+/// A single change to a tally.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct TallyAuditEntry<Tally:PartialEq+Clone+Display+FromStr> {
+    /// The count during which the change happened. The reason for the count is in the transcript.
+    pub count : CountIndex,
+    pub target : TallyAuditTarget,
+    pub why : TallyMutation,
+    /// The amount added to the tally (negative if removed).
+    pub change : SignedVersion<Tally>,
+    /// The transfer value used, if relevant.
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    pub transfer_value : Option<TransferValue>,
+}
+
+/// Every change made to a tally during a count, in order.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct TallyAudit<Tally:PartialEq+Clone+Display+FromStr> {
+    pub entries : Vec<TallyAuditEntry<Tally>>,
+}
+
+impl <Tally:PartialEq+Clone+Display+FromStr> Default for TallyAudit<Tally> {
+    fn default() -> Self { TallyAudit{ entries: vec![] } }
+}
+
+impl <Tally:PartialEq+Clone+Display+FromStr+AddAssign+SubAssign+Ord+Zero+Add<Output=Tally>+Sub<Output=Tally>> TallyAudit<Tally> {
+    /// The sum of all changes to the given target. As everything starts at zero, this should be the final value of that tally.
+    pub fn total(&self,target:TallyAuditTarget) -> SignedVersion<Tally> {
+        let mut total = SignedVersion::<Tally>::zero();
+        for e in self.entries.iter().filter(|e|e.target==target) {
+            total = total+e.change.clone();
+        }
+        total
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Ties that persist to the final determination are resolved by lot, so the result is inherently uncertain.
+//! Rather than picking one outcome, this module does a count for every possible way each such tie could be resolved,
+//! which quantifies how much the result depends on luck.
+//!
+//! Ties resolved by the supplied [TieResolutionsMadeByEC] are taken as known, and not branched on.
+//!
+//! Conversely, [replay_transcript_with_tie_override] explores a single alternative to one tie recorded in an existing transcript.
+
+use std::collections::HashSet;
+use serde::{Serialize,Deserialize};
+use crate::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use crate::distribution_of_preferences_transcript::{CountIndex, Transcript};
+use crate::election_data::ElectionData;
+use crate::preference_distribution::{distribute_preferences, PreferenceDistributionRules};
+use crate::random_util::Randomness;
+use crate::tie_resolution::{TieResolutionAtom, TieResolutionExplicitDecision, TieResolutionExplicitDecisionInCount, TieResolutionGranularityNeeded, TieResolutionsMadeByEC, TieResolutionUsage};
+
+/// One way a tie that would otherwise be resolved by lot was resolved.
+#[derive(Clone,Serialize,Deserialize,Debug,PartialEq,Eq)]
+pub struct TieChoice {
+    /// The count in which the tie came up.
+    pub count : CountIndex,
+    /// How it was resolved.
+    pub decision : TieResolutionExplicitDecision,
+}
+
+/// Do a count for each possible resolution of each tie resolved by lot, returning the choices made and the transcript for each.
+/// A tie met only in some branches is only branched on there.
+///
+/// At most `max_branches` outcomes are returned; if exactly that many are returned there may be others that were not explored.
+/// Other arguments are as for [distribute_preferences].
+pub fn distribute_preferences_all_tie_branches<Rules:PreferenceDistributionRules>(data:&ElectionData,candidates_to_be_elected : NumberOfCandidates,excluded_candidates:&HashSet<CandidateIndex>,ec_resolutions:& TieResolutionsMadeByEC,vote_types : Option<&[String]>,max_branches:usize) -> Vec<(Vec<TieChoice>,Transcript<Rules::Tally>)> {
+    let mut res = vec![];
+    let mut pending : Vec<Vec<TieChoice>> = vec![vec![]];
+    while let Some(choices) = pending.pop() {
+        if res.len()>=max_branches { break; }
+        let mut resolutions = ec_resolutions.clone();
+        resolutions.tie_resolutions.extend(choices.iter().map(|choice|TieResolutionAtom::ExplicitDecision(TieResolutionExplicitDecisionInCount{ decision: choice.decision.clone(), came_up_in: Some(choice.count) })));
+        let transcript = distribute_preferences::<Rules>(data,candidates_to_be_elected,excluded_candidates,&resolutions,vote_types,false,&mut Randomness::ReverseDonkeyVote);
+        match resolutions.first_unresolved_tie(&transcript,&data.metadata) {
+            None => res.push((choices,transcript)),
+            Some(tie) => {
+                // push in reverse so that branches are explored in the order given by all_alternatives.
+                for alternative in all_alternatives(&tie.decision).into_iter().rev() {
+                    let mut choices = choices.clone();
+                    choices.push(TieChoice{ count:tie.count, decision: alternative });
+                    pending.push(choices);
+                }
+            }
+        }
+    }
+    res
+}
+
+/// Recount an election with one of the tie decisions recorded in a transcript resolved differently, all other recorded decisions being kept.
+/// This answers questions like "the EC drew a name out of a hat; what if it had been the other name?"
+///
+/// `new_order` lists the tied candidates in order of increasing favour, like [TieResolutionExplicitDecision::from_resolution].
+/// The tie in `count` that mentions exactly the candidates in `new_order` is the one overridden.
+/// Returns None if there is no such recorded tie.
+///
+/// Recorded decisions are tied to the count they came up in, so once the recount diverges later recorded decisions may no longer apply;
+/// such ties are resolved by the tie resolutions in `data`, or failing that by reverse donkey vote.
+pub fn replay_transcript_with_tie_override<Rules:PreferenceDistributionRules>(data:&ElectionData,transcript:&Transcript<Rules::Tally>,count:CountIndex,new_order:&[CandidateIndex]) -> Option<Transcript<Rules::Tally>> {
+    let mut overridden = false;
+    let mut recorded : Vec<TieResolutionAtom> = vec![];
+    for (index,single) in transcript.counts.iter().enumerate() {
+        for decision in &single.decisions {
+            let decision = if CountIndex(index)==count && !overridden && decision.mentions_exactly_these_candidates(new_order) {
+                overridden=true;
+                let mut replacement = TieResolutionExplicitDecision::from_resolution(new_order,granularity_of(decision),decision.usage.unwrap_or(TieResolutionUsage::Exclusion));
+                replacement.usage=decision.usage;
+                replacement
+            } else { decision.clone() };
+            recorded.push(TieResolutionAtom::ExplicitDecision(TieResolutionExplicitDecisionInCount{ decision, came_up_in: Some(CountIndex(index)) }));
+        }
+    }
+    if !overridden { return None; }
+    // the first applicable resolution is used, so the recorded decisions go before any general ones in the metadata.
+    let mut resolutions = TieResolutionsMadeByEC{ tie_resolutions: recorded, default_policies: data.metadata.tie_resolutions.default_policies.clone() };
+    resolutions.tie_resolutions.extend(data.metadata.tie_resolutions.tie_resolutions.iter().cloned());
+    let vacancies = transcript.quota.as_ref().map(|q|q.vacancies).or(data.metadata.vacancies).unwrap_or(NumberOfCandidates(transcript.elected.len()));
+    let excluded : HashSet<CandidateIndex> = data.metadata.excluded.iter().cloned().collect();
+    Some(distribute_preferences::<Rules>(data,vacancies,&excluded,&resolutions,None,false,&mut Randomness::ReverseDonkeyVote))
+}
+
+fn granularity_of(decision:&TieResolutionExplicitDecision) -> TieResolutionGranularityNeeded {
+    if decision.increasing_favour.len()==2 { TieResolutionGranularityNeeded::LowestSeparated(decision.increasing_favour[0].len()) } else { TieResolutionGranularityNeeded::Total }
+}
+
+/// All the ways the tie resolved by `decision` could have been resolved, at the same granularity.
+fn all_alternatives(decision:&TieResolutionExplicitDecision) -> Vec<TieResolutionExplicitDecision> {
+    let tied : Vec<CandidateIndex> = decision.increasing_favour.iter().flatten().cloned().collect();
+    let granularity = granularity_of(decision);
+    let orders : Vec<Vec<CandidateIndex>> = match granularity {
+        TieResolutionGranularityNeeded::Total => permutations(&tied),
+        TieResolutionGranularityNeeded::LowestSeparated(num_low) => subsets(&tied,num_low).into_iter().map(|low|{
+            let high = tied.iter().filter(|c|!low.contains(c)).cloned();
+            low.iter().cloned().chain(high).collect()
+        }).collect(),
+    };
+    orders.into_iter().map(|order|TieResolutionExplicitDecision::from_resolution(&order,granularity,decision.usage.expect("Only decisions with a usage are branched on"))).collect()
+}
+
+/// All orderings of the given candidates.
+fn permutations(candidates:&[CandidateIndex]) -> Vec<Vec<CandidateIndex>> {
+    if candidates.is_empty() { return vec![vec![]]; }
+    let mut res = vec![];
+    for (i,&first) in candidates.iter().enumerate() {
+        let rest : Vec<CandidateIndex> = candidates.iter().enumerate().filter(|(j,_)|*j!=i).map(|(_,&c)|c).collect();
+        for mut tail in permutations(&rest) {
+            tail.insert(0,first);
+            res.push(tail);
+        }
+    }
+    res
+}
+
+/// All subsets of size n of the given candidates, preserving order.
+fn subsets(candidates:&[CandidateIndex],n:usize) -> Vec<Vec<CandidateIndex>> {
+    if n==0 { return vec![vec![]]; }
+    if candidates.len()<n { return vec![]; }
+    let mut res : Vec<Vec<CandidateIndex>> = subsets(&candidates[1..],n-1).into_iter().map(|mut tail|{ tail.insert(0,candidates[0]); tail }).collect();
+    res.extend(subsets(&candidates[1..],n));
+    res
+}
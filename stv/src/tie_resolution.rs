@@ -5,7 +5,7 @@
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
 
-use crate::ballot_metadata::CandidateIndex;
+use crate::ballot_metadata::{CandidateIndex, ElectionMetadata, StableCandidateId};
 use crate::distribution_of_preferences_transcript::{CountIndex, Transcript};
 use std::collections::{HashSet, HashMap};
 use std::hash::Hash;
@@ -16,7 +16,7 @@ use std::str::FromStr;
 use crate::compare_transcripts::DeltasInCandidateLists;
 use crate::random_util::Randomness;
 
-#[derive(Debug,Clone,Copy)]
+#[derive(Debug,Clone,Copy,Serialize,Deserialize)]
 pub enum MethodOfTieResolution {
     None,
     /// Require that at some prior point *all* the counts were different
@@ -52,6 +52,10 @@ pub enum MethodOfTieResolution {
     AnyDifferenceIsADiscriminatorOnlyConsideringCountsWhereAnActionIsFinishedGiveUpIfNotFullSolution,
     /// Like AnyDifferenceIsADiscriminator but only consider major counts like RequireHistoricalCountsToBeAllDifferentOnlyConsideringCountsWhereAnActionIsFinished
     AnyDifferenceIsADiscriminatorOnlyConsideringCountsWhereAnActionIsFinished,
+    /// Ignore history and order the tied candidates by their name as given in the metadata, as done by some overseas jurisdictions.
+    /// A name earlier in the alphabet is treated as having more votes; that is, it is elected first, or excluded last.
+    /// Candidates with identical names are left for the EC to resolve.
+    AlphabeticalByName,
 }
 
 /// Sometimes you need tie resolution to distinguish all candidates (e.g. for order elected),
@@ -68,7 +72,8 @@ pub enum TieResolutionGranularityNeeded {
 impl MethodOfTieResolution {
     /// sort tied_candidates low to high based upon the given method of tie resolution.
     /// If the method does not resolve it, return a DecisionMadeByEC object.
-    pub fn resolve<'a,Tally:Clone+Hash+Ord+Display+FromStr+Debug>(self,tied_candidates: &'a mut [CandidateIndex],transcript:  &Transcript<Tally>,granularity:TieResolutionGranularityNeeded) -> Vec<(&'a mut [CandidateIndex],TieResolutionGranularityNeeded)> {
+    /// The metadata is used for the candidate names by AlphabeticalByName.
+    pub fn resolve<'a,Tally:Clone+Hash+Ord+Display+FromStr+Debug>(self,tied_candidates: &'a mut [CandidateIndex],transcript:  &Transcript<Tally>,metadata:&ElectionMetadata,granularity:TieResolutionGranularityNeeded) -> Vec<(&'a mut [CandidateIndex],TieResolutionGranularityNeeded)> {
         let resolved = match self {
             MethodOfTieResolution::None => false,
             MethodOfTieResolution::RequireHistoricalCountsToBeAllDifferent => resolve_ties_require_all_different(tied_candidates,transcript,false),
@@ -77,6 +82,7 @@ impl MethodOfTieResolution {
             MethodOfTieResolution::RequireHistoricalCountsToBeAllDifferentOnlyConsideringCountsWhereAnActionIsFinished => resolve_ties_require_all_different(tied_candidates,transcript,true),
             MethodOfTieResolution::AnyDifferenceIsADiscriminatorOnlyConsideringCountsWhereAnActionIsFinishedGiveUpIfNotFullSolution => resolve_ties_any_different_give_up_if_cant_do_everything(tied_candidates, transcript, granularity, true),
             MethodOfTieResolution::AnyDifferenceIsADiscriminatorOnlyConsideringCountsWhereAnActionIsFinished => return resolve_ties_any_different(tied_candidates, transcript, granularity, true),
+            MethodOfTieResolution::AlphabeticalByName => resolve_ties_alphabetical(tied_candidates,metadata),
         };
         if resolved { vec![] } else { vec![(tied_candidates,granularity)] }
     }
@@ -91,7 +97,8 @@ impl MethodOfTieResolution {
 /// all the candidates. The relative order in this list is the relative order of the candidates
 /// in the new list. (low to high)
 ///
-/// If nothing matches, then a candidate with a smaller index (earlier on the paper generally)
+/// If nothing matches, then a general policy for that usage (see [Self::default_policy_for]) is used if there is one.
+/// Otherwise a candidate with a smaller index (earlier on the paper generally)
 /// will be put before (generally a worse position) than a candidate with a smaller index.
 /// This seems to be what many ECs do in practice.
 ///
@@ -105,13 +112,49 @@ impl MethodOfTieResolution {
 /// If an EC ever perversely decides to do this, I guess I will need to support it. But no need to
 /// introduce added complexity until then
 #[derive(Serialize,Deserialize,Debug,Clone)]
+#[cfg_attr(feature="openapi",derive(utoipa::ToSchema))]
 pub struct TieResolutionsMadeByEC {
     #[serde(skip_serializing_if = "Vec::is_empty",default)]
-    pub tie_resolutions : Vec<TieResolutionAtom>
+    #[cfg_attr(feature="openapi",schema(value_type=Vec<Object>))]
+    pub tie_resolutions : Vec<TieResolutionAtom>,
+    /// General policies used for any tie in a given context that is not resolved by tie_resolutions. See [Self::default_policy_for].
+    #[serde(skip_serializing_if = "Vec::is_empty",default)]
+    #[cfg_attr(feature="openapi",schema(value_type=Vec<Object>))]
+    pub default_policies : Vec<DefaultTieResolutionPolicy>,
 }
 
 impl Default for TieResolutionsMadeByEC {
-    fn default() -> Self { TieResolutionsMadeByEC{tie_resolutions:vec![]}}
+    fn default() -> Self { TieResolutionsMadeByEC{tie_resolutions:vec![],default_policies:vec![]}}
+}
+
+/// A way of resolving any tie, without needing to know in advance which candidates will be tied.
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,Eq,PartialEq)]
+pub enum TieResolutionPolicy {
+    /// A candidate with a higher index (later on the ballot paper) is favoured, like [Randomness::ReverseDonkeyVote].
+    FavourHigherCandidateIndex,
+    /// A candidate with a lower index (earlier on the ballot paper) is favoured.
+    FavourLowerCandidateIndex,
+    /// A candidate whose name is earlier in the alphabet is favoured, like [MethodOfTieResolution::AlphabeticalByName].
+    /// Candidates with identical names are not resolved by this policy.
+    AlphabeticalByName,
+}
+
+impl TieResolutionPolicy {
+    /// Sort tied_candidates low to high. Return true iff this policy resolved the tie.
+    fn resolve(self,tied_candidates: &mut [CandidateIndex],metadata:&ElectionMetadata) -> bool {
+        match self {
+            TieResolutionPolicy::FavourHigherCandidateIndex => { tied_candidates.sort_by_key(|c|c.0); true }
+            TieResolutionPolicy::FavourLowerCandidateIndex => { tied_candidates.sort_by_key(|c|std::cmp::Reverse(c.0)); true }
+            TieResolutionPolicy::AlphabeticalByName => resolve_ties_alphabetical(tied_candidates,metadata),
+        }
+    }
+}
+
+/// A policy applying to all ties with a given usage.
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,Eq,PartialEq)]
+pub struct DefaultTieResolutionPolicy {
+    pub usage : TieResolutionUsage,
+    pub policy : TieResolutionPolicy,
 }
 
 #[derive(Serialize,Deserialize,Debug,Clone,Eq,PartialEq)]
@@ -130,7 +173,7 @@ impl From<TieResolutionAtomWithBackwardsCompatibility> for TieResolutionAtom {
             TieResolutionAtomWithBackwardsCompatibility::IncreasingFavour(decision) => TieResolutionAtom::IncreasingFavour(decision),
             TieResolutionAtomWithBackwardsCompatibility::ExplicitDecision(decision) => TieResolutionAtom::ExplicitDecision(decision),
             TieResolutionAtomWithBackwardsCompatibility::OldExplicitDecision(decision) =>
-                TieResolutionAtom::ExplicitDecision(TieResolutionExplicitDecisionInCount{ decision: TieResolutionExplicitDecision { increasing_favour: vec![decision.disfavoured,decision.favoured], usage: None }, came_up_in: None }),
+                TieResolutionAtom::ExplicitDecision(TieResolutionExplicitDecisionInCount{ decision: TieResolutionExplicitDecision { increasing_favour: vec![decision.disfavoured,decision.favoured], usage: None, candidate_ids: vec![] }, came_up_in: None }),
         }
     }
 }
@@ -180,6 +223,11 @@ pub struct TieResolutionExplicitDecision {
     pub increasing_favour: Vec<Vec<CandidateIndex>>,
     #[serde(skip_serializing_if = "Option::is_none",default)]
     pub usage : Option<TieResolutionUsage>,
+    /// Optionally, the [StableCandidateId]s of the candidates in increasing_favour, with the same structure.
+    /// If present, these take precedence over the indices when resolving ties, so the decision remains meaningful if the
+    /// order of candidates in the metadata changes. See [Self::add_stable_candidate_ids].
+    #[serde(skip_serializing_if = "Vec::is_empty",default)]
+    pub candidate_ids : Vec<Vec<StableCandidateId>>,
 }
 
 /// Where a tie resolution was performed.
@@ -212,6 +260,7 @@ impl TieResolutionExplicitDecision {
         TieResolutionExplicitDecision {
             increasing_favour: vec![disfavoured,favoured],
             usage: None,
+            candidate_ids: vec![],
         }
     }
     /// make a decision given a final ordering of candidates and a given granularity and usage.
@@ -221,6 +270,7 @@ impl TieResolutionExplicitDecision {
                 TieResolutionExplicitDecision {
                     increasing_favour: resolved_order.iter().map(|c|vec![*c]).collect(),
                     usage: Some(usage),
+                    candidate_ids: vec![],
                 }
             }
             TieResolutionGranularityNeeded::LowestSeparated(disfavoured) => {
@@ -229,6 +279,7 @@ impl TieResolutionExplicitDecision {
                         resolved_order[..disfavoured].to_vec(),resolved_order[disfavoured..].to_vec()
                     ],
                     usage: Some(usage),
+                    candidate_ids: vec![],
                 }
             }
         }
@@ -270,7 +321,7 @@ impl TieResolutionExplicitDecision {
                         &&  excluded_deltas.list2only.iter().all(|candidate_excluded_only_in_my|disfavoured.contains(candidate_excluded_only_in_my)) { // well, that would explain it.
                         let favoured = favoured.iter().filter(|&w|!excluded_deltas.list1only.contains(w)).chain(excluded_deltas.list2only.iter()).cloned().collect::<Vec<_>>();
                         let disfavoured = disfavoured.iter().filter(|&w|!excluded_deltas.list2only.contains(w)).chain(excluded_deltas.list1only.iter()).cloned().collect::<Vec<_>>();
-                        Some(TieResolutionExplicitDecision { increasing_favour: vec![disfavoured, favoured], usage: self.usage })
+                        Some(TieResolutionExplicitDecision { increasing_favour: vec![disfavoured, favoured], usage: self.usage, candidate_ids: vec![] })
                     } else { None }
                 } else { None }
             },
@@ -285,6 +336,20 @@ impl TieResolutionExplicitDecision {
     pub fn mentions_exactly_these_candidates(&self,candidates:&[CandidateIndex]) -> bool {
         candidates.len()==self.num_candidates_mentioned() && candidates.iter().all(|c|self.increasing_favour.iter().any(|v|v.contains(c)))
     }
+
+    /// Record the [StableCandidateId]s of the candidates in increasing_favour, as of the given metadata.
+    pub fn add_stable_candidate_ids(&mut self,metadata:&ElectionMetadata) {
+        let ids = metadata.stable_candidate_ids();
+        self.candidate_ids = self.increasing_favour.iter().map(|v|v.iter().map(|c|ids[c.0].clone()).collect()).collect();
+    }
+
+    /// If there are [Self::candidate_ids], the same decision with increasing_favour recomputed from them using the given lookup
+    /// (from [ElectionMetadata::get_stable_candidate_id_lookup]). Error if some id is not in the lookup.
+    pub fn reindexed(&self,lookup:&HashMap<StableCandidateId,CandidateIndex>) -> anyhow::Result<Option<TieResolutionExplicitDecision>> {
+        if self.candidate_ids.is_empty() { return Ok(None); }
+        let increasing_favour = self.candidate_ids.iter().map(|v|v.iter().map(|id|lookup.get(id).cloned().ok_or_else(||anyhow!("No candidate with stable id {}",id))).collect::<anyhow::Result<Vec<_>>>()).collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Some(TieResolutionExplicitDecision{ increasing_favour, usage: self.usage, candidate_ids: self.candidate_ids.clone() }))
+    }
 }
 
 impl Display for TieResolutionExplicitDecision {
@@ -302,6 +367,31 @@ impl Display for TieResolutionExplicitDecision {
 
 
 
+/// A tie that was not resolved by the rules or by the supplied [TieResolutionsMadeByEC], and so would otherwise have been resolved by randomness.
+/// Some legislation provides no procedure at all for some ties ([MethodOfTieResolution::None]); in a strict count such as
+/// [crate::preference_distribution::distribute_preferences_strict] such a tie stops the count, forcing the user to supply a
+/// resolution (e.g. the result of the EC's decision). Ties the legislation says to resolve by lot are not errors.
+#[derive(thiserror::Error,Debug,Clone,Serialize,Deserialize,PartialEq,Eq)]
+#[error("Tie in count {count} (starting from 0) between {} not resolved by the rules or the supplied tie resolutions. A resolution needs to be supplied.",self.candidates.iter().zip(self.candidate_names.iter()).map(|(c,n)|format!("{} {}",c,n)).collect::<Vec<_>>().join(", "))]
+pub struct UnresolvedTie {
+    pub count : CountIndex,
+    /// The tied candidates.
+    pub candidates : Vec<CandidateIndex>,
+    /// The names of the tied candidates, in the same order as [Self::candidates].
+    pub candidate_names : Vec<String>,
+    /// The decision made, by randomness, in the count that found the tie.
+    pub decision : TieResolutionExplicitDecision,
+}
+
+impl UnresolvedTie {
+    /// The tie resolved by `decision`, made in the given count.
+    pub fn new(count:CountIndex,decision:&TieResolutionExplicitDecision,metadata:&ElectionMetadata) -> Self {
+        let candidates : Vec<CandidateIndex> = decision.increasing_favour.iter().flatten().cloned().collect();
+        let candidate_names = candidates.iter().map(|&c|metadata.candidate(c).name.clone()).collect();
+        UnresolvedTie{count,candidates,candidate_names,decision:decision.clone()}
+    }
+}
+
 impl TieResolutionsMadeByEC {
     /// Simple constructor that checks to see that a candidate is not repeated which would cause later bugs and would be ambiguous in any case.
     pub fn new(tie_resolutions : Vec<Vec<CandidateIndex>>) -> anyhow::Result<Self> {
@@ -314,23 +404,78 @@ impl TieResolutionsMadeByEC {
             }
         }
         let tie_resolutions = tie_resolutions.into_iter().map(|v|TieResolutionAtom::IncreasingFavour(v)).collect();
-        Ok(TieResolutionsMadeByEC{tie_resolutions})
+        Ok(TieResolutionsMadeByEC{tie_resolutions,default_policies:vec![]})
+    }
+    /// Migrate index based decisions to also reference [StableCandidateId]s, as of the given metadata, so that they remain meaningful
+    /// if the order of candidates changes. Old style [TieResolutionAtom::IncreasingFavour] decisions can't hold ids, and are left
+    /// unchanged; the number of these is returned.
+    pub fn add_stable_candidate_ids(&mut self,metadata:&ElectionMetadata) -> usize {
+        let mut index_only = 0;
+        for atom in &mut self.tie_resolutions {
+            match atom {
+                TieResolutionAtom::IncreasingFavour(_) => index_only+=1,
+                TieResolutionAtom::ExplicitDecision(decision) => decision.decision.add_stable_candidate_ids(metadata),
+            }
+        }
+        index_only
+    }
+    /// Recompute the candidate indices of decisions that have [StableCandidateId]s to match the given metadata,
+    /// for instance after loading them from a file made when the candidates were in a different order.
+    pub fn update_indices_from_stable_ids(&mut self,metadata:&ElectionMetadata) -> anyhow::Result<()> {
+        let lookup = metadata.get_stable_candidate_id_lookup();
+        for atom in &mut self.tie_resolutions {
+            if let TieResolutionAtom::ExplicitDecision(decision) = atom {
+                if let Some(reindexed) = decision.decision.reindexed(&lookup)? { decision.decision=reindexed; }
+            }
+        }
+        Ok(())
+    }
+    /// Use the given policy for any tie with the given usage that is not resolved by an explicit tie resolution, rather than randomness.
+    /// Replaces any prior policy for that usage. A policy for [TieResolutionUsage::OrderElected] also applies to
+    /// [TieResolutionUsage::OrderSurplusDistributed] unless there is a policy specifically for the latter.
+    pub fn default_policy_for(&mut self,usage:TieResolutionUsage,policy:TieResolutionPolicy) {
+        self.default_policies.retain(|p|p.usage!=usage);
+        self.default_policies.push(DefaultTieResolutionPolicy{usage,policy});
+    }
+    /// The policy, if any, to be used for ties with the given usage not resolved by an explicit tie resolution.
+    pub fn policy_for(&self,usage:TieResolutionUsage) -> Option<TieResolutionPolicy> {
+        let find = |usage:TieResolutionUsage| self.default_policies.iter().find(|p|p.usage==usage).map(|p|p.policy);
+        find(usage).or_else(||if usage==TieResolutionUsage::OrderSurplusDistributed { find(TieResolutionUsage::OrderElected) } else { None })
     }
     /// Sort tied_candidates appropriately (low to high), and then return a description of what was done.
-    /// If all else fails, use randomness.
-    pub fn resolve(&self, tied_candidates: &mut [CandidateIndex], granularity: TieResolutionGranularityNeeded,usage:TieResolutionUsage,current_count:CountIndex,randomness:&mut Randomness) -> TieResolutionExplicitDecision {
-        self.resolve_work(tied_candidates,granularity,usage,current_count,randomness);
+    /// If all else fails, use randomness. The metadata is used for candidate names by [TieResolutionPolicy::AlphabeticalByName].
+    pub fn resolve(&self, tied_candidates: &mut [CandidateIndex], granularity: TieResolutionGranularityNeeded,usage:TieResolutionUsage,current_count:CountIndex,metadata:&ElectionMetadata,randomness:&mut Randomness) -> TieResolutionExplicitDecision {
+        self.resolve_work(tied_candidates,granularity,usage,current_count,metadata,randomness);
         TieResolutionExplicitDecision::from_resolution(tied_candidates,granularity,usage)
     }
-    fn resolve_work(&self, tied_candidates: &mut [CandidateIndex], granularity: TieResolutionGranularityNeeded,usage:TieResolutionUsage,current_count:CountIndex,randomness:&mut Randomness)  {
+    /// See whether a decision that was made in a given count would have been determined by these resolutions, rather than by randomness.
+    /// Decisions without a usage can't be reproduced, so are treated as determined.
+    pub fn is_determined_by_ec(&self,decision:&TieResolutionExplicitDecision,count:CountIndex,metadata:&ElectionMetadata) -> bool {
+        match decision.usage {
+            None => true,
+            Some(usage) => {
+                let mut tied_candidates : Vec<CandidateIndex> = decision.increasing_favour.iter().flatten().cloned().collect();
+                let granularity = if decision.increasing_favour.len()==2 { TieResolutionGranularityNeeded::LowestSeparated(decision.increasing_favour[0].len()) } else { TieResolutionGranularityNeeded::Total };
+                self.resolve_work(&mut tied_candidates,granularity,usage,count,metadata,&mut Randomness::ReverseDonkeyVote)
+            }
+        }
+    }
+    /// The first tie decision in the transcript that would not have been determined by these resolutions, that is, was resolved by randomness, if any.
+    /// This includes ties the legislation says to resolve by lot. The transcript should have been produced using these resolutions.
+    pub fn first_unresolved_tie<Tally:Clone+Display+FromStr+Debug+PartialEq>(&self,transcript:&Transcript<Tally>,metadata:&ElectionMetadata) -> Option<UnresolvedTie> {
+        transcript.counts.iter().enumerate().find_map(|(count,single)|single.decisions.iter().find(|decision|!self.is_determined_by_ec(decision,CountIndex(count),metadata)).map(|decision|UnresolvedTie::new(CountIndex(count),decision,metadata)))
+    }
+    /// Sort tied_candidates appropriately (low to high). Return true iff this was done by one of the resolutions or policies rather than by randomness.
+    fn resolve_work(&self, tied_candidates: &mut [CandidateIndex], granularity: TieResolutionGranularityNeeded,usage:TieResolutionUsage,current_count:CountIndex,metadata:&ElectionMetadata,randomness:&mut Randomness) -> bool {
         // println!("Trying to resolve {:?}",tied_candidates);
+        let mut stable_id_lookup : Option<HashMap<StableCandidateId,CandidateIndex>> = None;
         for atom in &self.tie_resolutions {
             match atom {
                 TieResolutionAtom::IncreasingFavour(decision) => {
                     let deemed_order : Vec<CandidateIndex> = decision.iter().filter(|&c|tied_candidates.contains(c)).cloned().collect();
                     if deemed_order.len()==tied_candidates.len() {
                         tied_candidates.copy_from_slice(&deemed_order);
-                        return;
+                        return true;
                     }
                     if granularity==TieResolutionGranularityNeeded::LowestSeparated(1) && decision.len()==2 && deemed_order.len()==2 {
                         // This is sufficient. One will be excluded and this should not re-arise.
@@ -338,10 +483,18 @@ impl TieResolutionsMadeByEC {
                         let last = decision[0]; // this is least favoured candidate, so should go at the start of the list, which is in ascending order.
                         let order_with_last_first = [last].into_iter().chain(tied_candidates.iter().cloned().filter(|&c|c!=last)).collect::<Vec<_>>();
                         tied_candidates.copy_from_slice(&order_with_last_first);
-                        return;
+                        return true;
                     }
                 }
                 TieResolutionAtom::ExplicitDecision(TieResolutionExplicitDecisionInCount{decision, came_up_in, }) => {
+                    // if there are stable ids, they take precedence over the indices. A decision about a candidate not in this contest can't apply.
+                    let reindexed = if decision.candidate_ids.is_empty() { None } else {
+                        match decision.reindexed(stable_id_lookup.get_or_insert_with(||metadata.get_stable_candidate_id_lookup())) {
+                            Ok(reindexed) => reindexed,
+                            Err(_) => continue,
+                        }
+                    };
+                    let decision = reindexed.as_ref().unwrap_or(decision);
                     let appropriate_usage = match decision.usage {
                         None => true,
                         Some(TieResolutionUsage::Exclusion) => usage==TieResolutionUsage::Exclusion,
@@ -366,13 +519,17 @@ impl TieResolutionsMadeByEC {
                             upto+=v.len();
                         }
                         assert_eq!(upto,tied_candidates.len());
-                        return;
+                        return true;
                     }
                 }
             }
         }
+        if let Some(policy) = self.policy_for(usage) {
+            if policy.resolve(tied_candidates,metadata) { return true; }
+        }
         // If all else fails, we need to do a draw.
         randomness.resolve(tied_candidates);
+        false
     }
 }
 
@@ -395,6 +552,14 @@ fn resolve_ties_require_all_different<Tally:Clone+Eq+Hash+Ord+Display+FromStr+De
     false
 }
 
+/// Sort candidates low to high by name, later in the alphabet being lower. Return true iff all names are different.
+fn resolve_ties_alphabetical(tied_candidates: &mut [CandidateIndex],metadata:&ElectionMetadata) -> bool {
+    let names : HashSet<&str> = tied_candidates.iter().map(|&c|metadata.candidate(c).name.as_str()).collect();
+    if names.len()!=tied_candidates.len() { return false; }
+    tied_candidates.sort_by(|&a,&b|metadata.candidate(b).name.cmp(&metadata.candidate(a).name));
+    true
+}
+
 /// Sort candidates low to high based on a countback where any difference is used as much as possible.
 /// Return remaining need for resolution, if any.
 fn resolve_ties_any_different<'a,Tally:Clone+Eq+Hash+Ord+Display+FromStr+Debug>(tied_candidates: &'a mut [CandidateIndex],transcript:  &Transcript<Tally>,granularity:TieResolutionGranularityNeeded,just_consider_major_counts:bool) -> Vec<(&'a mut [CandidateIndex],TieResolutionGranularityNeeded)> {
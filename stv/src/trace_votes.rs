@@ -0,0 +1,93 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Find the ballot papers that elected a candidate, from a transcript containing the list of votes.
+//!
+//! This is a best effort attribution for "who elected candidate X", usable for any rules. Unlike
+//! [crate::extract_votes_in_pile::WhatToExtract::ACTVotesUsedToElectCandidate], which follows the
+//! ACT casual vacancy legislation and needs to be done during the count, it works on a finished transcript.
+//!
+//! The attribution is simply the ballot papers the candidate held at the end of the count in which they
+//! were elected, each at the transfer value at which the candidate received it. Limitations:
+//! * If the candidate went over quota, all their papers are included, so the total value exceeds the quota.
+//!   No attempt is made to decide which papers formed the surplus.
+//! * A paper received at a fractional transfer value also (at its remaining value) helped elect the candidate(s)
+//!   whose surplus it came from, so the same ballot may appear, fractionally, in the traces of several candidates.
+//! * The value of a paper is its transfer value times the number of papers; rounding of tallies in the count
+//!   means this may not exactly match the candidate's tally.
+//! * A candidate elected without reaching a quota (e.g. as one of the last candidates remaining) is attributed
+//!   whatever papers they held at that point.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use crate::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use crate::ballot_paper::BTL;
+use crate::distribution_of_preferences_transcript::{CountIndex, TranscriptWithMetadata};
+use crate::election_data::{ElectionData, VoteValueSpecification};
+use crate::transfer_value::TransferValue;
+
+#[derive(thiserror::Error,Debug,Clone)]
+pub enum TraceVotesError {
+    #[error("Candidate {0} was not elected")]
+    NotElected(String),
+    #[error("The transcript does not include the list of votes; count with --include-list-of-votes-in-transcript")]
+    NoListOfVotes,
+}
+
+/// Preferences in the order first seen, and the net number of papers with each.
+type VotesInOrderFirstSeen = (Vec<Vec<CandidateIndex>>,HashMap<Vec<CandidateIndex>,isize>);
+
+/// The count in which a candidate was elected, if they were.
+pub fn count_elected<Tally:PartialEq+Clone+Display+FromStr+Debug>(transcript:&TranscriptWithMetadata<Tally>,who:CandidateIndex) -> Option<CountIndex> {
+    transcript.transcript.counts.iter().position(|c|c.elected.iter().any(|e|e.who==who)).map(CountIndex)
+}
+
+/// Make new election data consisting of the ballot papers that elected a candidate, with the transfer values
+/// at which the candidate received them. See the module documentation for the method and its limitations.
+pub fn votes_electing<Tally:PartialEq+Clone+Display+FromStr+Debug>(transcript:&TranscriptWithMetadata<Tally>,who:CandidateIndex) -> Result<ElectionData,TraceVotesError> {
+    let name = transcript.metadata.candidate(who).name.clone();
+    let elected_in = count_elected(transcript,who).ok_or_else(||TraceVotesError::NotElected(name.clone()))?;
+    // net papers held, by transfer value.
+    let mut held : BTreeMap<TransferValue,VotesInOrderFirstSeen> = BTreeMap::new();
+    for count in &transcript.transcript.counts[..=elected_in.0] {
+        let list_of_votes = count.status.list_of_votes.as_ref().ok_or(TraceVotesError::NoListOfVotes)?;
+        for with_tv in &list_of_votes.candidate[who.0].tvs {
+            let (order,n) = held.entry(with_tv.tv.clone()).or_default();
+            for vote in &with_tv.votes {
+                let n = n.entry(vote.candidates.clone()).or_insert_with(||{ order.push(vote.candidates.clone()); 0 });
+                *n+=vote.n;
+            }
+        }
+    }
+    let mut metadata = transcript.metadata.clone();
+    metadata.vacancies=Some(NumberOfCandidates(1));
+    metadata.name.modifications.push(format!("Votes attributed to electing {} from the papers held when elected",name));
+    let mut btl : Vec<BTL> = vec![];
+    let mut btl_transfer_values : Vec<VoteValueSpecification> = vec![];
+    for (tv,(order,n)) in held.into_iter().rev() {
+        let start = btl.len();
+        for candidates in order {
+            let n = n[&candidates];
+            if n>0 { btl.push(BTL{ candidates, n: n as usize }); }
+        }
+        if start!=btl.len() {
+            btl_transfer_values.push(VoteValueSpecification{ value: tv, first_index_inclusive: start, last_index_exclusive: btl.len() });
+        }
+    }
+    Ok(ElectionData{
+        metadata,
+        atl: vec![],
+        atl_types: vec![],
+        atl_transfer_values: vec![],
+        btl,
+        btl_types: vec![],
+        btl_transfer_values,
+        atl_annotations: vec![],
+        btl_annotations: vec![],
+        informal: 0,
+    })
+}
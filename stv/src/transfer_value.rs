@@ -1,22 +1,23 @@
-// Copyright 2021-2023 Andrew Conway.
+// Copyright 2021-2024 Andrew Conway.
 // This file is part of ConcreteSTV.
 // ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
 // ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
+//! Transfer values, and rendering of rational numbers.
+//! Apart from working out which ballot papers to set aside (which needs the rest of the counting code), this is available without the `std` feature.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use serde::Serialize;
 use serde::Deserialize;
 use num::{One, BigRational, BigInt, ToPrimitive, Zero};
-use crate::ballot_pile::{BallotPaperCount, DistributedVotes};
-use std::fmt::{Debug, Display, Formatter};
-use std::convert::TryFrom;
-use std::hash::Hash;
-use std::str::FromStr;
+use crate::arithmetic::BallotPaperCount;
+use core::fmt::{Debug, Display, Formatter};
+use core::convert::TryFrom;
+use core::str::FromStr;
 use num::rational::{ParseRatioError, Ratio};
-use crate::ballot_metadata::CandidateIndex;
-use crate::distribution_of_preferences_transcript::{CountIndex, Transcript};
-use crate::random_util::Randomness;
-use crate::tie_resolution::{MethodOfTieResolution, TieResolutionExplicitDecision, TieResolutionGranularityNeeded, TieResolutionsMadeByEC, TieResolutionUsage};
 
 #[derive(Clone,Debug,Serialize,Deserialize,Ord, PartialOrd, Eq, PartialEq,Hash)]
 #[serde(into = "String")]
@@ -94,6 +95,20 @@ pub fn round_rational_down_to_isize(rational:BigRational) -> isize {
     let rounded_down = rational.numer().clone()/rational.denom().clone();
     rounded_down.to_isize().unwrap()
 }
+/// Hamilton (largest remainder) apportionment of whole votes amongst non-negative exact shares.
+/// Each share gets its rounded down value, and the units left over from rounding down the total are given
+/// one each to the shares with the largest fractional parts, earlier shares winning ties.
+/// The result sums to the rounded down total of the shares.
+pub fn apportion_largest_remainder(shares:&[BigRational]) -> Vec<usize> {
+    let mut res : Vec<usize> = shares.iter().map(|s|round_rational_down_to_usize(s.clone())).collect();
+    let total : BigRational = shares.iter().fold(BigRational::zero(),|acc,s|acc+s);
+    let left_over = round_rational_down_to_usize(total)-res.iter().sum::<usize>();
+    let mut by_remainder : Vec<usize> = (0..shares.len()).collect();
+    by_remainder.sort_by(|&a,&b|shares[b].fract().cmp(&shares[a].fract()));
+    for &i in by_remainder.iter().take(left_over) { res[i]+=1; }
+    res
+}
+
 /// converts usize (or anything that can be converted to BigInt via From trait) into a BigRational.
 pub fn convert_usize_to_rational<T>(tally:T) -> BigRational
 where BigInt : From<T> {
@@ -102,7 +117,7 @@ where BigInt : From<T> {
 
 
 impl Display for TransferValue {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f,"{}",self.0)
     }
 }
@@ -128,7 +143,7 @@ impl TryFrom<String> for TransferValue {
 pub struct StringSerializedRational(pub BigRational);
 
 impl Display for StringSerializedRational {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f,"{}",self.0)
     }
 }
@@ -147,82 +162,260 @@ impl TryFrom<String> for StringSerializedRational {
     fn try_from(s: String) -> Result<Self, Self::Error> { Ok(StringSerializedRational(Ratio::from_str(&s)?)) }
 }
 
-/// Utility for NSW random style selection
-struct SelectVotesToSetAsideByTV {
-    candidate : CandidateIndex,
-    distributed : BallotPaperCount,
-    integer_portion : BallotPaperCount,
-    fractional_portion : BigRational,
+/// How to round when rendering a rational number as a fixed number of decimal places.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Serialize,Deserialize)]
+pub enum DecimalRounding {
+    /// Round towards negative infinity (truncate, for the usual positive values).
+    Down,
+    /// Round towards positive infinity.
+    Up,
+    /// Round to the nearest, with exact halves rounded away from zero.
+    Nearest,
 }
 
+impl FromStr for DecimalRounding {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "down" => Ok(DecimalRounding::Down),
+            "up" => Ok(DecimalRounding::Up),
+            "nearest" => Ok(DecimalRounding::Nearest),
+            _ => Err(format!("Unknown rounding {}, expecting down, up or nearest",s)),
+        }
+    }
+}
 
+/// Render a rational number as a decimal with exactly `places` digits after the decimal point (and no decimal point if places is 0).
+pub fn rational_to_decimal_string(value:&BigRational,places:u32,rounding:DecimalRounding) -> String {
+    let factor = BigInt::from(10).pow(places);
+    let scaled = value*BigRational::from_integer(factor.clone());
+    let scaled = match rounding {
+        DecimalRounding::Down => scaled.floor(),
+        DecimalRounding::Up => scaled.ceil(),
+        DecimalRounding::Nearest => scaled.round(),
+    }.to_integer();
+    let sign = if scaled<BigInt::zero() { "-" } else { "" };
+    let scaled = if scaled<BigInt::zero() { -scaled } else { scaled };
+    let integer_part = scaled.clone()/factor.clone();
+    if places==0 { format!("{}{}",sign,integer_part) }
+    else { format!("{}{}.{:0>width$}",sign,integer_part,(scaled%factor).to_string(),width=places as usize) }
+}
 
 impl TransferValue {
-    /// Implement the NSW Legislative Council and old LGE method of working out how many
-    /// votes for each candidate need to be set aside so that the correct number of ballot papers
-    /// are transferred.
-    ///
-    /// Returns an array of candidates
-    pub fn calculate_number_of_ballot_papers_to_be_set_aside<Tally:Clone+Hash+Ord+Display+FromStr+Debug>(&self, surplus:BallotPaperCount, num_candidates:usize, transcript:&Transcript<Tally>, distributed:&DistributedVotes<'_>, use_f32_instead_of_exact:bool, ec_resolutions: &TieResolutionsMadeByEC,current_count:CountIndex,randomness:&mut Randomness) -> (Vec<BallotPaperCount>, Vec<TieResolutionExplicitDecision>)  {
-        let mut ec_decision : Vec<TieResolutionExplicitDecision> = vec![];
-        let set_aside_by_candidate = if self.is_one() { // work out how to distribute.
-            vec![BallotPaperCount::zero();num_candidates]
-        } else {
-            let mut compute_transferred = vec![];
-            let mut extra_to_distribute : usize = surplus.min(distributed.by_candidate.iter().map(|c|c.num_ballots).sum()).0;
-            // println!("Transfer value : {}",self.0);
-            // Notes on trying to emulate
-            for candidate in 0..num_candidates {
-                let n_distributed = distributed.by_candidate[candidate].num_ballots;
-                let (integer_portion,fractional_portion) = if use_f32_instead_of_exact {
-                    // let tv_rounded_to_six_decimal_places = self.round_to_decimal_digits(6);
-                    // let portion = tv_rounded_to_six_decimal_places.0.to_f64().unwrap()*(n_distributed.0 as f64);
-                    // let portion = tv_rounded_to_six_decimal_places.mul(n_distributed).to_f64().unwrap(); // conversion to fp has to be after multiplication to replicate NSWEC bug.
-                    let portion = self.0.to_f32().unwrap()*(n_distributed.0 as f32); // it took quite a while to replicate this NSWEC bug.
-                    let integer_portion = portion.floor();
-                    let fractional_portion = portion-integer_portion;
-                    let fractional_portion = BigRational::from_float(fractional_portion).unwrap();
-                    // println!("Candidate {} integer {} fractional {} ~ {}",candidate,integer_portion,fractional_portion,fractional_portion.to_f64().unwrap());
-                    (integer_portion as usize,fractional_portion)
-                } else { self.mul_rounding_down_and_remainder(n_distributed) };
-                compute_transferred.push(SelectVotesToSetAsideByTV{
-                    candidate: CandidateIndex(candidate),
-                    distributed: n_distributed,
-                    integer_portion : BallotPaperCount(integer_portion),
-                    fractional_portion,
-                });
-                extra_to_distribute-=integer_portion;
-            }
-            if extra_to_distribute>0 {
-                compute_transferred.sort_unstable_by(|a,b|{
-                    let c1 = b.fractional_portion.cmp(&a.fractional_portion);
-                    if c1.is_eq() {
-                        b.integer_portion.cmp(&a.integer_portion)
-                    } else {c1}
-                });
-                if compute_transferred[extra_to_distribute-1].distributed==compute_transferred[extra_to_distribute].distributed { // need to split ties somehow.
-                    let mut start_tied_index = extra_to_distribute-1;
-                    while start_tied_index>0 && compute_transferred[extra_to_distribute].distributed==compute_transferred[start_tied_index-1].distributed { start_tied_index-=1; }
-                    let mut end_tied_index_exclusive = extra_to_distribute+1;
-                    while end_tied_index_exclusive<compute_transferred.len() && compute_transferred[extra_to_distribute].distributed==compute_transferred[end_tied_index_exclusive].distributed { end_tied_index_exclusive+=1; }
-                    let mut tied_candidates : Vec<CandidateIndex> = compute_transferred[start_tied_index..end_tied_index_exclusive].iter().map(|v|v.candidate).collect();
-                    let num_missing_out_on_rounding_up = end_tied_index_exclusive-extra_to_distribute;
-                    for (remaining_tied,remaining_granularity) in MethodOfTieResolution::AnyDifferenceIsADiscriminator.resolve(&mut tied_candidates, transcript, TieResolutionGranularityNeeded::LowestSeparated(num_missing_out_on_rounding_up)) {
-                        let decision = ec_resolutions.resolve(remaining_tied,remaining_granularity,TieResolutionUsage::RoundingUp,current_count,randomness);
-                        ec_decision.push(decision);
-                    };
-                    for i in 0..tied_candidates.len() {
-                        compute_transferred[i+start_tied_index].candidate=tied_candidates[tied_candidates.len()-1-i]; // tied_candidates is sorted low to high.
-                    }
+    /// Render as a decimal with the given number of decimal places, e.g. 1/3 with 6 places rounding down is 0.333333.
+    /// This is for display or interoperability; the exact fraction remains the canonical form.
+    pub fn to_decimal_string(&self,places:u32,rounding:DecimalRounding) -> String { rational_to_decimal_string(&self.0,places,rounding) }
+
+    /// The exact value of a decimal such as "0.142857" published by an electoral commission, here 142857/1000000.
+    /// Either the integer part or the fractional part (but not both) may be empty, e.g. ".5" or "1.".
+    pub fn from_decimal_str(s:&str) -> Result<TransferValue,String> {
+        let s = s.trim();
+        let (integer_part,fractional_part) = s.split_once('.').unwrap_or((s,""));
+        if integer_part.is_empty() && fractional_part.is_empty() { return Err(format!("No digits in transfer value {}",s)); }
+        let denominator = BigInt::from(10).pow(fractional_part.len() as u32);
+        let numerator = parse_decimal_digits(integer_part,s)?*denominator.clone()+parse_decimal_digits(fractional_part,s)?;
+        Ok(TransferValue(BigRational::new(numerator,denominator)))
+    }
+
+    /// The exact value of a repeating decimal, with the repeating digits in brackets, e.g. "0.(142857)" is 1/7 and "0.1(6)" is 1/6.
+    /// An electoral commission will generally publish a rounded form like 0.166667, so the caller needs to know what the repeating digits are.
+    pub fn from_repeating_decimal(s:&str) -> Result<TransferValue,String> {
+        let s = s.trim();
+        let (non_repeating,repeating) = s.strip_suffix(')').and_then(|s|s.split_once('(')).ok_or_else(||format!("Expecting repeating digits in brackets at the end of transfer value {}",s))?;
+        if repeating.is_empty() { return Err(format!("No repeating digits in transfer value {}",s)); }
+        let fractional_digits = non_repeating.split_once('.').ok_or_else(||format!("No decimal point before the repeating digits in transfer value {}",s))?.1.len() as u32;
+        let start = TransferValue::from_decimal_str(non_repeating)?;
+        // 0.000(R) with n zeros, where R has r digits, is R/(10^n*(10^r-1)).
+        let repeating_denominator = BigInt::from(10).pow(fractional_digits)*(BigInt::from(10).pow(repeating.len() as u32)-BigInt::one());
+        Ok(TransferValue(start.0+BigRational::new(parse_decimal_digits(repeating,s)?,repeating_denominator)))
+    }
+}
+
+/// Parse a possibly empty string of decimal digits (empty meaning zero), as part of the transfer value `whole`.
+fn parse_decimal_digits(digits:&str,whole:&str) -> Result<BigInt,String> {
+    if !digits.bytes().all(|b|b.is_ascii_digit()) { return Err(format!("Unexpected character in transfer value {}",whole)); }
+    Ok(if digits.is_empty() { BigInt::zero() } else { BigInt::parse_bytes(digits.as_bytes(),10).unwrap() })
+}
+
+impl StringSerializedRational {
+    /// Render as a decimal with the given number of decimal places. See [TransferValue::to_decimal_string].
+    pub fn to_decimal_string(&self,places:u32,rounding:DecimalRounding) -> String { rational_to_decimal_string(&self.0,places,rounding) }
+}
+
+/// How exporters should render transfer values.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Serialize,Deserialize,Default)]
+pub enum TransferValueFormat {
+    /// The canonical exact fraction, e.g. 1/3
+    #[default]
+    Exact,
+    /// A fixed number of decimal places, e.g. 0.333333
+    Decimal{places:u32,rounding:DecimalRounding},
+}
+
+impl TransferValueFormat {
+    pub fn format(&self,transfer_value:&TransferValue) -> String {
+        match self {
+            TransferValueFormat::Exact => transfer_value.to_string(),
+            TransferValueFormat::Decimal {places,rounding} => transfer_value.to_decimal_string(*places,*rounding),
+        }
+    }
+    pub fn format_rational(&self,value:&StringSerializedRational) -> String {
+        match self {
+            TransferValueFormat::Exact => value.to_string(),
+            TransferValueFormat::Decimal {places,rounding} => value.to_decimal_string(*places,*rounding),
+        }
+    }
+}
+
+#[cfg(feature="std")]
+mod set_aside {
+    use std::fmt::{Debug, Display};
+    use std::hash::Hash;
+    use std::str::FromStr;
+    use num::{BigRational, ToPrimitive, Zero};
+    use crate::ballot_metadata::{CandidateIndex, ElectionMetadata};
+    use crate::ballot_pile::{BallotPaperCount, DistributedVotes};
+    use crate::distribution_of_preferences_transcript::{CountIndex, Transcript};
+    use crate::random_util::Randomness;
+    use crate::tie_resolution::{MethodOfTieResolution, TieResolutionExplicitDecision, TieResolutionGranularityNeeded, TieResolutionsMadeByEC, TieResolutionUsage};
+    use crate::transfer_value::TransferValue;
+
+    /// Utility for NSW random style selection
+    struct SelectVotesToSetAsideByTV {
+        candidate : CandidateIndex,
+        distributed : BallotPaperCount,
+        integer_portion : BallotPaperCount,
+        fractional_portion : BigRational,
+    }
+
+
+
+    impl TransferValue {
+        /// Implement the NSW Legislative Council and old LGE method of working out how many
+        /// votes for each candidate need to be set aside so that the correct number of ballot papers
+        /// are transferred.
+        ///
+        /// Returns an array of candidates
+        pub fn calculate_number_of_ballot_papers_to_be_set_aside<Tally:Clone+Hash+Ord+Display+FromStr+Debug>(&self, surplus:BallotPaperCount, num_candidates:usize, transcript:&Transcript<Tally>, metadata:&ElectionMetadata, distributed:&DistributedVotes<'_>, use_f32_instead_of_exact:bool, ec_resolutions: &TieResolutionsMadeByEC,current_count:CountIndex,randomness:&mut Randomness) -> (Vec<BallotPaperCount>, Vec<TieResolutionExplicitDecision>)  {
+            let mut ec_decision : Vec<TieResolutionExplicitDecision> = vec![];
+            let set_aside_by_candidate = if self.is_one() { // work out how to distribute.
+                vec![BallotPaperCount::zero();num_candidates]
+            } else {
+                let mut compute_transferred = vec![];
+                let mut extra_to_distribute : usize = surplus.min(distributed.by_candidate.iter().map(|c|c.num_ballots).sum()).0;
+                // println!("Transfer value : {}",self.0);
+                // Notes on trying to emulate
+                for candidate in 0..num_candidates {
+                    let n_distributed = distributed.by_candidate[candidate].num_ballots;
+                    let (integer_portion,fractional_portion) = if use_f32_instead_of_exact {
+                        // let tv_rounded_to_six_decimal_places = self.round_to_decimal_digits(6);
+                        // let portion = tv_rounded_to_six_decimal_places.0.to_f64().unwrap()*(n_distributed.0 as f64);
+                        // let portion = tv_rounded_to_six_decimal_places.mul(n_distributed).to_f64().unwrap(); // conversion to fp has to be after multiplication to replicate NSWEC bug.
+                        let portion = self.0.to_f32().unwrap()*(n_distributed.0 as f32); // it took quite a while to replicate this NSWEC bug.
+                        let integer_portion = portion.floor();
+                        let fractional_portion = portion-integer_portion;
+                        let fractional_portion = BigRational::from_float(fractional_portion).unwrap();
+                        // println!("Candidate {} integer {} fractional {} ~ {}",candidate,integer_portion,fractional_portion,fractional_portion.to_f64().unwrap());
+                        (integer_portion as usize,fractional_portion)
+                    } else { self.mul_rounding_down_and_remainder(n_distributed) };
+                    compute_transferred.push(SelectVotesToSetAsideByTV{
+                        candidate: CandidateIndex(candidate),
+                        distributed: n_distributed,
+                        integer_portion : BallotPaperCount(integer_portion),
+                        fractional_portion,
+                    });
+                    extra_to_distribute-=integer_portion;
                 }
-                for i in 0..extra_to_distribute {
-                    compute_transferred[i].integer_portion+=BallotPaperCount(1);
+                if extra_to_distribute>0 {
+                    compute_transferred.sort_unstable_by(|a,b|{
+                        let c1 = b.fractional_portion.cmp(&a.fractional_portion);
+                        if c1.is_eq() {
+                            b.integer_portion.cmp(&a.integer_portion)
+                        } else {c1}
+                    });
+                    if compute_transferred[extra_to_distribute-1].distributed==compute_transferred[extra_to_distribute].distributed { // need to split ties somehow.
+                        let mut start_tied_index = extra_to_distribute-1;
+                        while start_tied_index>0 && compute_transferred[extra_to_distribute].distributed==compute_transferred[start_tied_index-1].distributed { start_tied_index-=1; }
+                        let mut end_tied_index_exclusive = extra_to_distribute+1;
+                        while end_tied_index_exclusive<compute_transferred.len() && compute_transferred[extra_to_distribute].distributed==compute_transferred[end_tied_index_exclusive].distributed { end_tied_index_exclusive+=1; }
+                        let mut tied_candidates : Vec<CandidateIndex> = compute_transferred[start_tied_index..end_tied_index_exclusive].iter().map(|v|v.candidate).collect();
+                        let num_missing_out_on_rounding_up = end_tied_index_exclusive-extra_to_distribute;
+                        for (remaining_tied,remaining_granularity) in MethodOfTieResolution::AnyDifferenceIsADiscriminator.resolve(&mut tied_candidates, transcript, metadata, TieResolutionGranularityNeeded::LowestSeparated(num_missing_out_on_rounding_up)) {
+                            let decision = ec_resolutions.resolve(remaining_tied,remaining_granularity,TieResolutionUsage::RoundingUp,current_count,metadata,randomness);
+                            ec_decision.push(decision);
+                        };
+                        for i in 0..tied_candidates.len() {
+                            compute_transferred[i+start_tied_index].candidate=tied_candidates[tied_candidates.len()-1-i]; // tied_candidates is sorted low to high.
+                        }
+                    }
+                    for i in 0..extra_to_distribute {
+                        compute_transferred[i].integer_portion+=BallotPaperCount(1);
+                    }
+                    compute_transferred.sort_unstable_by_key(|v|v.candidate.0)
                 }
-                compute_transferred.sort_unstable_by_key(|v|v.candidate.0)
-            }
-            compute_transferred.iter().map(|v|v.distributed-v.integer_portion).collect()
-        };
-        (set_aside_by_candidate,ec_decision)
+                compute_transferred.iter().map(|v|v.distributed-v.integer_portion).collect()
+            };
+            (set_aside_by_candidate,ec_decision)
+        }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use num::BigInt;
+    use crate::ballot_pile::BallotPaperCount;
+    use crate::transfer_value::{DecimalRounding, StringSerializedRational, TransferValue, TransferValueFormat};
+
+    #[test]
+    fn test_decimal_rendering() {
+        let third = TransferValue::from_surplus(1,BallotPaperCount(3));
+        assert_eq!("1/3",third.to_string());
+        assert_eq!("1/3",TransferValueFormat::Exact.format(&third));
+        assert_eq!("0.333333",TransferValueFormat::Decimal{places:6,rounding:DecimalRounding::Down}.format(&third));
+        assert_eq!("0.333334",third.to_decimal_string(6,DecimalRounding::Up));
+        assert_eq!("0.333333",third.to_decimal_string(6,DecimalRounding::Nearest));
+        let two_thirds = TransferValue::from_surplus(2,BallotPaperCount(3));
+        assert_eq!("0.666666",two_thirds.to_decimal_string(6,DecimalRounding::Down));
+        assert_eq!("0.666667",two_thirds.to_decimal_string(6,DecimalRounding::Nearest));
+        let half = TransferValue::from_surplus(1,BallotPaperCount(8));
+        assert_eq!("0.12",half.to_decimal_string(2,DecimalRounding::Down));
+        assert_eq!("0.13",half.to_decimal_string(2,DecimalRounding::Nearest));
+        assert_eq!("0.125000",half.to_decimal_string(6,DecimalRounding::Down));
+        assert_eq!("1",TransferValue::one().to_decimal_string(0,DecimalRounding::Down));
+        assert_eq!("1.000",TransferValue::one().to_decimal_string(3,DecimalRounding::Up));
+        assert_eq!("0.05",TransferValue::from_surplus(1,BallotPaperCount(20)).to_decimal_string(2,DecimalRounding::Down));
+        let negative = StringSerializedRational(num::BigRational::new(BigInt::from(-1),BigInt::from(3)));
+        assert_eq!("-0.334",negative.to_decimal_string(3,DecimalRounding::Down));
+        assert_eq!("-0.333",negative.to_decimal_string(3,DecimalRounding::Up));
+        assert_eq!(Ok(DecimalRounding::Nearest),"nearest".parse());
+    }
+
+    #[test]
+    fn test_from_decimal() {
+        assert_eq!(Ok(TransferValue::new(BigInt::from(142857),BigInt::from(1000000))),TransferValue::from_decimal_str("0.142857"));
+        assert_eq!(Ok(TransferValue::from_surplus(1,BallotPaperCount(8))),TransferValue::from_decimal_str("0.125"));
+        assert_eq!(Ok(TransferValue::from_surplus(1,BallotPaperCount(2))),TransferValue::from_decimal_str(".5"));
+        assert_eq!(Ok(TransferValue::one()),TransferValue::from_decimal_str("1"));
+        assert_eq!(Ok(TransferValue::one()),TransferValue::from_decimal_str("1.000000"));
+        let many_places = TransferValue::from_decimal_str("0.12345678901234567890123456789").unwrap();
+        assert_eq!("12345678901234567890123456789/100000000000000000000000000000",many_places.to_string());
+        assert_eq!("0.12345678901234567890123456789",many_places.to_decimal_string(29,DecimalRounding::Down));
+        assert!(TransferValue::from_decimal_str("").is_err());
+        assert!(TransferValue::from_decimal_str(".").is_err());
+        assert!(TransferValue::from_decimal_str("0.1.2").is_err());
+        assert!(TransferValue::from_decimal_str("-0.5").is_err());
+        assert!(TransferValue::from_decimal_str("1/3").is_err());
+    }
+
+    #[test]
+    fn test_from_repeating_decimal() {
+        assert_eq!(Ok(TransferValue::from_surplus(1,BallotPaperCount(7))),TransferValue::from_repeating_decimal("0.(142857)"));
+        assert_eq!(Ok(TransferValue::from_surplus(1,BallotPaperCount(6))),TransferValue::from_repeating_decimal("0.1(6)"));
+        assert_eq!(Ok(TransferValue::from_surplus(2,BallotPaperCount(3))),TransferValue::from_repeating_decimal("0.(6)"));
+        assert_eq!(Ok(TransferValue::one()),TransferValue::from_repeating_decimal("0.(9)"));
+        assert_eq!(Ok(TransferValue::from_surplus(7,BallotPaperCount(12))),TransferValue::from_repeating_decimal("0.58(3)"));
+        assert!(TransferValue::from_repeating_decimal("0.166667").is_err());
+        assert!(TransferValue::from_repeating_decimal("0.()").is_err());
+        assert!(TransferValue::from_repeating_decimal("(3)").is_err());
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! A flat table of every transfer value created during a count, for auditors who want to
+//! check the fractional arithmetic independently of the rest of the transcript, and a check
+//! for transfer values that unexpectedly increase.
+
+use std::fmt::{Debug, Display};
+use std::io::Write;
+use std::str::FromStr;
+use serde::{Serialize,Deserialize};
+use crate::ballot_metadata::{CandidateIndex, ElectionMetadata, NameFormat};
+use crate::ballot_pile::BallotPaperCount;
+use crate::distribution_of_preferences_transcript::{CountIndex, ReasonForCount, Transcript};
+use crate::preference_distribution::TransferValueMethod;
+use crate::transfer_value::{StringSerializedRational, TransferValue, TransferValueFormat};
+
+/// One row of the transfer value ledger. This is a flattened [crate::distribution_of_preferences_transcript::TransferValueCreation]
+/// along with information about the count in which it was created.
+#[derive(Clone,Serialize,Deserialize,Debug)]
+pub struct TransferValueLedgerEntry<Tally> {
+    /// The count in which the transfer value was created.
+    pub count : CountIndex,
+    /// The human readable name of the count, if not just count+1.
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    pub count_name : Option<String>,
+    /// The candidate whose surplus was being distributed, if any.
+    pub source_candidate : Option<CandidateIndex>,
+    pub surplus : Tally,
+    pub votes : Tally,
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    pub excluded_exhausted_tally : Option<StringSerializedRational>,
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    pub original_transfer_value : Option<TransferValue>,
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    pub multiplied_transfer_value : Option<TransferValue>,
+    /// The number of ballots considered for redistribution. This may be all or a last parcel.
+    pub ballots_considered : BallotPaperCount,
+    /// The number of the considered ballots that are continuing
+    pub continuing_ballots : BallotPaperCount,
+    pub transfer_value : TransferValue,
+    /// The numerator of transfer_value, in lowest terms.
+    pub numerator : String,
+    /// The denominator of transfer_value, in lowest terms.
+    pub denominator : String,
+    pub method : TransferValueMethod,
+}
+
+/// A transfer value created in a count that is greater than the transfer value of the papers it was applied to.
+/// This should be impossible for most rules, and indicates a bug or an unusual interaction of rules. See [Transcript::transfer_value_anomalies].
+#[derive(Clone,Serialize,Deserialize,Debug,PartialEq)]
+pub struct TVAnomaly {
+    /// The count in which the transfer value was created.
+    pub count : CountIndex,
+    /// The human readable name of the count, if not just count+1.
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    pub count_name : Option<String>,
+    /// The candidate whose surplus was being distributed, if any.
+    pub source_candidate : Option<CandidateIndex>,
+    /// The transfer value of the papers being distributed.
+    pub source_transfer_value : TransferValue,
+    /// The transfer value created, which is greater than source_transfer_value.
+    pub transfer_value : TransferValue,
+}
+
+impl Display for TVAnomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let count = self.count_name.clone().unwrap_or_else(||(self.count.0+1).to_string());
+        write!(f,"Count {}",count)?;
+        if let Some(candidate) = self.source_candidate { write!(f," (surplus of candidate {})",candidate)?; }
+        write!(f," created transfer value {} from papers with transfer value {}",self.transfer_value,self.source_transfer_value)
+    }
+}
+
+impl <Tally:PartialEq+Clone+Display+FromStr+Debug> Transcript<Tally> {
+    /// Get every transfer value created in this count, in the order they were created.
+    pub fn transfer_value_ledger(&self) -> Vec<TransferValueLedgerEntry<Tally>> {
+        let mut res = vec![];
+        for (index,count) in self.counts.iter().enumerate() {
+            if let Some(created) = &count.created_transfer_value {
+                res.push(TransferValueLedgerEntry{
+                    count: CountIndex(index),
+                    count_name: count.count_name.clone(),
+                    source_candidate: match count.reason {
+                        ReasonForCount::ExcessDistribution(candidate) => Some(candidate),
+                        _ => None,
+                    },
+                    surplus: created.surplus.clone(),
+                    votes: created.votes.clone(),
+                    excluded_exhausted_tally: created.excluded_exhausted_tally.clone(),
+                    original_transfer_value: created.original_transfer_value.clone(),
+                    multiplied_transfer_value: created.multiplied_transfer_value.clone(),
+                    ballots_considered: created.ballots_considered,
+                    continuing_ballots: created.continuing_ballots,
+                    transfer_value: created.transfer_value.clone(),
+                    numerator: created.transfer_value.0.numer().to_string(),
+                    denominator: created.transfer_value.0.denom().to_string(),
+                    method: created.source,
+                })
+            }
+        }
+        res
+    }
+
+    /// Find every count in which a transfer value was created that is greater than the transfer value of the papers it was applied to.
+    /// The source transfer value is the unique transfer value of the papers distributed, if one was recorded
+    /// ([crate::distribution_of_preferences_transcript::TransferValueCreation::original_transfer_value]), otherwise the transfer value of the portion
+    /// being distributed in that count. If papers with a mixture of transfer values were distributed together, neither is known, and the count is not checked.
+    pub fn transfer_value_anomalies(&self) -> Vec<TVAnomaly> {
+        let mut res = vec![];
+        for (index,count) in self.counts.iter().enumerate() {
+            if let Some(created) = &count.created_transfer_value {
+                if let Some(source) = created.original_transfer_value.as_ref().or(count.portion.transfer_value.as_ref()) {
+                    if created.transfer_value>*source {
+                        res.push(TVAnomaly{
+                            count: CountIndex(index),
+                            count_name: count.count_name.clone(),
+                            source_candidate: match count.reason {
+                                ReasonForCount::ExcessDistribution(candidate) => Some(candidate),
+                                _ => None,
+                            },
+                            source_transfer_value: source.clone(),
+                            transfer_value: created.transfer_value.clone(),
+                        })
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+/// Write a transfer value ledger as a CSV file with a header line.
+/// If metadata is provided, candidate names (formatted according to `name_format`) will be used instead of candidate indices.
+/// Transfer values are rendered according to `format`; the numerator and denominator columns are always exact.
+pub fn write_transfer_value_ledger_csv<Tally:Display,W:Write>(ledger:&[TransferValueLedgerEntry<Tally>],metadata:Option<&ElectionMetadata>,name_format:NameFormat,format:TransferValueFormat,writer:W) -> anyhow::Result<()> {
+    fn opt<T:Display>(v:&Option<T>) -> String { v.as_ref().map(|v|v.to_string()).unwrap_or_default() }
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(["Count","Source candidate","Surplus","Votes","Excluded exhausted tally","Original transfer value","Multiplied transfer value","Ballots considered","Continuing ballots","Transfer value","Numerator","Denominator","Method"])?;
+    for entry in ledger {
+        let count = entry.count_name.clone().unwrap_or_else(||(entry.count.0+1).to_string());
+        let source_candidate = match (entry.source_candidate,metadata) {
+            (Some(candidate),Some(metadata)) => metadata.candidate(candidate).display_name(name_format),
+            (candidate,_) => opt(&candidate),
+        };
+        csv.write_record([
+            count,
+            source_candidate,
+            entry.surplus.to_string(),
+            entry.votes.to_string(),
+            entry.excluded_exhausted_tally.as_ref().map(|v|format.format_rational(v)).unwrap_or_default(),
+            entry.original_transfer_value.as_ref().map(|tv|format.format(tv)).unwrap_or_default(),
+            entry.multiplied_transfer_value.as_ref().map(|tv|format.format(tv)).unwrap_or_default(),
+            entry.ballots_considered.to_string(),
+            entry.continuing_ballots.to_string(),
+            format.format(&entry.transfer_value),
+            entry.numerator.clone(),
+            entry.denominator.clone(),
+            format!("{:?}",entry.method),
+        ])?;
+    }
+    csv.flush()?;
+    Ok(())
+}
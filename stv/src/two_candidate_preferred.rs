@@ -0,0 +1,39 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The final two candidate preferred count, the headline figure for single seat (IRV) contests.
+
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use num::Zero;
+use crate::ballot_metadata::CandidateIndex;
+use crate::distribution_of_preferences_transcript::Transcript;
+
+impl <Tally:PartialEq+Clone+Display+FromStr+Debug+Ord+Zero> Transcript<Tally> {
+    /// If the count ended with exactly two candidates still in contention, those two candidates and their tallies,
+    /// highest tally first (or, if tied, the elected candidate first).
+    ///
+    /// Candidates elected in the last count are counted as still in contention; candidates elected earlier, or
+    /// excluded, are not. Candidates excluded before the count started are not recorded as such in the transcript,
+    /// so a candidate with a zero tally in every count is taken to be one of them. This is intended for single seat contests, where this is the final two candidate preferred count.
+    ///
+    /// None if the count stopped with more than two candidates in contention, e.g. because a candidate had a majority
+    /// of continuing votes before it got down to two. Recount with rules that continue to two candidates, such as
+    /// `IRVContinueToTwo`, to get the figure in that case.
+    pub fn final_two_candidate_preferred(&self) -> Option<(CandidateIndex,Tally,CandidateIndex,Tally)> {
+        let (last,previous) = self.counts.split_last()?;
+        let gone : HashSet<CandidateIndex> = self.counts.iter().flat_map(|c|c.not_continuing.iter().cloned()).chain(previous.iter().flat_map(|c|c.elected.iter().map(|e|e.who))).collect();
+        let tallies = &last.status.tallies.candidate;
+        let never_had_votes = |c:&CandidateIndex|self.counts.iter().all(|count|count.status.tallies.candidate[c.0].is_zero());
+        let mut remaining : Vec<CandidateIndex> = (0..tallies.len()).map(CandidateIndex).filter(|c|!(gone.contains(c) || never_had_votes(c))).collect();
+        if remaining.len()!=2 { return None; }
+        let elected_last = |c:&CandidateIndex|last.elected.iter().any(|e|e.who==*c);
+        remaining.sort_by(|a,b|tallies[b.0].cmp(&tallies[a.0]).then(elected_last(b).cmp(&elected_last(a))));
+        Some((remaining[0],tallies[remaining[0].0].clone(),remaining[1],tallies[remaining[1].0].clone()))
+    }
+}
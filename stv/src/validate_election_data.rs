@@ -0,0 +1,220 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Check that an [ElectionData] (typically read from a .stv file) is structurally sound before counting it.
+//! This is mainly useful for people producing .stv files from other sources.
+//!
+//! Note that [crate::errors_btl::ObviousErrorsInBTLVotes] does a related check on raw EC data
+//! (how many papers have repeated or missing preferences), which cannot be done on a .stv file as
+//! the original markings have already been converted to preference lists.
+//!
+//! Some electoral commissions only publish above the line votes converted into the equivalent below the line votes,
+//! so they have to be reconstructed heuristically (e.g. by the Victorian parser). [ElectionData::validate_atl_reconstruction]
+//! checks the result against the published number of above the line votes for each party.
+
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use serde::{Serialize,Deserialize};
+use crate::ballot_metadata::{CandidateIndex, PartyIndex};
+use crate::ballot_pile::BallotPaperCount;
+use crate::election_data::{ElectionData, VoteTypeSpecification, VoteValueSpecification};
+
+/// A problem found in an [ElectionData].
+/// ATL vote indices index into `atl`, BTL vote indices index into `btl`.
+#[derive(thiserror::Error,Debug,Clone,Serialize,Deserialize,PartialEq,Eq)]
+pub enum ValidationProblem {
+    #[error("there are no candidates")]
+    NoCandidates,
+    #[error("the number of vacancies is not set")]
+    VacanciesNotSet,
+    #[error("there are {vacancies} vacancies but only {candidates} candidates")]
+    NotMoreCandidatesThanVacancies{vacancies:usize,candidates:usize},
+    #[error("candidate {candidate} has party {party} which does not exist")]
+    CandidatePartyOutOfRange{candidate:CandidateIndex,party:PartyIndex},
+    #[error("candidate {candidate} has party {party} but is not in that party's candidate list")]
+    CandidateNotInOwnParty{candidate:CandidateIndex,party:PartyIndex},
+    #[error("party {party} lists candidate {candidate} which does not exist")]
+    PartyCandidateOutOfRange{party:PartyIndex,candidate:CandidateIndex},
+    #[error("party {party} lists candidate {candidate} who is in a different party")]
+    PartyCandidateInDifferentParty{party:PartyIndex,candidate:CandidateIndex},
+    #[error("party {party} ticket {ticket} lists candidate {candidate} which does not exist")]
+    TicketCandidateOutOfRange{party:PartyIndex,ticket:usize,candidate:CandidateIndex},
+    #[error("{what} lists candidate {candidate} which does not exist")]
+    ListedCandidateOutOfRange{what:String,candidate:CandidateIndex},
+    #[error("ATL vote {vote} has no parties")]
+    ATLEmpty{vote:usize},
+    #[error("ATL vote {vote} lists party {party} which does not exist")]
+    ATLPartyOutOfRange{vote:usize,party:PartyIndex},
+    #[error("ATL vote {vote} lists party {party} which does not allow above the line votes")]
+    ATLPartyNotAllowed{vote:usize,party:PartyIndex},
+    #[error("ATL vote {vote} lists party {party} more than once")]
+    ATLDuplicateParty{vote:usize,party:PartyIndex},
+    #[error("ATL vote {vote} uses ticket {ticket} which does not exist for its first party")]
+    ATLTicketOutOfRange{vote:usize,ticket:usize},
+    #[error("BTL vote {vote} has no candidates")]
+    BTLEmpty{vote:usize},
+    #[error("BTL vote {vote} lists candidate {candidate} which does not exist")]
+    BTLCandidateOutOfRange{vote:usize,candidate:CandidateIndex},
+    #[error("BTL vote {vote} lists candidate {candidate} more than once")]
+    BTLDuplicateCandidate{vote:usize,candidate:CandidateIndex},
+    #[error("{what} vote {vote} represents zero ballots")]
+    ZeroBallots{what:String,vote:usize},
+    #[error("{what} has index range {first_index_inclusive}..{last_index_exclusive} which is not within 0..{len}")]
+    InvalidIndexRange{what:String,first_index_inclusive:usize,last_index_exclusive:usize,len:usize},
+}
+
+impl ValidationProblem {
+    /// Whether this problem would stop the count from being meaningful (or even make it panic).
+    /// Other problems are warnings; they are legal but suspicious.
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self,ValidationProblem::NotMoreCandidatesThanVacancies {..} | ValidationProblem::ZeroBallots {..} | ValidationProblem::BTLEmpty {..} | ValidationProblem::ATLPartyNotAllowed {..})
+    }
+}
+
+/// A discrepancy between the above the line votes in an [ElectionData] and the official number recorded for each party
+/// in [crate::ballot_metadata::Party::official_atl_votes]. See [ElectionData::validate_atl_reconstruction].
+#[derive(thiserror::Error,Debug,Clone,Serialize,Deserialize,PartialEq,Eq)]
+pub enum AtlReconstructionWarning {
+    #[error("party {party} has {actual} above the line votes but the official number is {official}")]
+    DifferentFromOfficial{party:PartyIndex,actual:BallotPaperCount,official:BallotPaperCount},
+    #[error("party {party} has {actual} above the line votes but no official number, unlike other parties")]
+    NoOfficialNumber{party:PartyIndex,actual:BallotPaperCount},
+}
+
+/// The result of [ElectionData::validate].
+#[derive(Debug,Clone,Serialize,Deserialize,Default)]
+pub struct ValidationReport {
+    pub problems : Vec<ValidationProblem>,
+}
+
+impl ValidationReport {
+    pub fn has_fatal_problems(&self) -> bool { self.problems.iter().any(|p|p.is_fatal()) }
+    pub fn is_ok(&self) -> bool { self.problems.is_empty() }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.problems.is_empty() { return writeln!(f,"No problems found."); }
+        for p in &self.problems {
+            writeln!(f,"{} : {}",if p.is_fatal() {"Error"} else {"Warning"},p)?;
+        }
+        let fatal = self.problems.iter().filter(|p|p.is_fatal()).count();
+        writeln!(f,"{} errors, {} warnings.",fatal,self.problems.len()-fatal)
+    }
+}
+
+impl ElectionData {
+    /// Check this data is structurally sound. See [ValidationProblem] for the checks done.
+    pub fn validate(&self) -> ValidationReport {
+        let mut problems = vec![];
+        let metadata = &self.metadata;
+        let num_candidates = metadata.candidates.len();
+        let num_parties = metadata.parties.len();
+        let candidate_ok = |c:CandidateIndex| c.0<num_candidates;
+        if num_candidates==0 { problems.push(ValidationProblem::NoCandidates); }
+        match metadata.vacancies {
+            None => problems.push(ValidationProblem::VacanciesNotSet),
+            Some(vacancies) if vacancies.0>=num_candidates && num_candidates>0 => problems.push(ValidationProblem::NotMoreCandidatesThanVacancies {vacancies:vacancies.0,candidates:num_candidates}),
+            _ => {}
+        }
+        for (i,candidate) in metadata.candidates.iter().enumerate() {
+            if let Some(party) = candidate.party {
+                if party.0>=num_parties { problems.push(ValidationProblem::CandidatePartyOutOfRange {candidate:CandidateIndex(i),party}); }
+                else if !metadata.parties[party.0].candidates.contains(&CandidateIndex(i)) { problems.push(ValidationProblem::CandidateNotInOwnParty {candidate:CandidateIndex(i),party}); }
+            }
+        }
+        for (p,party) in metadata.parties.iter().enumerate() {
+            let party_index = PartyIndex(p);
+            for &candidate in &party.candidates {
+                if !candidate_ok(candidate) { problems.push(ValidationProblem::PartyCandidateOutOfRange {party:party_index,candidate}); }
+                else if metadata.candidates[candidate.0].party!=Some(party_index) { problems.push(ValidationProblem::PartyCandidateInDifferentParty {party:party_index,candidate}); }
+            }
+            for (ticket_index,ticket) in party.tickets.iter().enumerate() {
+                for &candidate in ticket {
+                    if !candidate_ok(candidate) { problems.push(ValidationProblem::TicketCandidateOutOfRange {party:party_index,ticket:ticket_index,candidate}); }
+                }
+            }
+        }
+        for &candidate in &metadata.excluded {
+            if !candidate_ok(candidate) { problems.push(ValidationProblem::ListedCandidateOutOfRange {what:"excluded".to_string(),candidate}); }
+        }
+        for &candidate in &metadata.withdrawn_before_count {
+            if !candidate_ok(candidate) { problems.push(ValidationProblem::ListedCandidateOutOfRange {what:"withdrawn_before_count".to_string(),candidate}); }
+        }
+        if let Some(results) = &metadata.results {
+            for &candidate in results {
+                if !candidate_ok(candidate) { problems.push(ValidationProblem::ListedCandidateOutOfRange {what:"results".to_string(),candidate}); }
+            }
+        }
+        for (vote,atl) in self.atl.iter().enumerate() {
+            if atl.n==0 { problems.push(ValidationProblem::ZeroBallots {what:"ATL".to_string(),vote}); }
+            if atl.parties.is_empty() { problems.push(ValidationProblem::ATLEmpty {vote}); continue; }
+            let mut seen : HashSet<PartyIndex> = HashSet::default();
+            for &party in &atl.parties {
+                if party.0>=num_parties { problems.push(ValidationProblem::ATLPartyOutOfRange {vote,party}); }
+                else if !metadata.parties[party.0].atl_allowed { problems.push(ValidationProblem::ATLPartyNotAllowed {vote,party}); }
+                if !seen.insert(party) { problems.push(ValidationProblem::ATLDuplicateParty {vote,party}); }
+            }
+            if let Some(ticket) = atl.ticket_index {
+                let first = atl.parties[0];
+                if first.0<num_parties && ticket>=metadata.parties[first.0].tickets.len() { problems.push(ValidationProblem::ATLTicketOutOfRange {vote,ticket}); }
+            }
+        }
+        for (vote,btl) in self.btl.iter().enumerate() {
+            if btl.n==0 { problems.push(ValidationProblem::ZeroBallots {what:"BTL".to_string(),vote}); }
+            if btl.candidates.is_empty() { problems.push(ValidationProblem::BTLEmpty {vote}); }
+            let mut seen : HashSet<CandidateIndex> = HashSet::default();
+            for &candidate in &btl.candidates {
+                if !candidate_ok(candidate) { problems.push(ValidationProblem::BTLCandidateOutOfRange {vote,candidate}); }
+                if !seen.insert(candidate) { problems.push(ValidationProblem::BTLDuplicateCandidate {vote,candidate}); }
+            }
+        }
+        let mut check_range = |what:&str,first_index_inclusive:usize,last_index_exclusive:usize,len:usize| {
+            if first_index_inclusive>last_index_exclusive || last_index_exclusive>len { problems.push(ValidationProblem::InvalidIndexRange {what:what.to_string(),first_index_inclusive,last_index_exclusive,len}); }
+        };
+        let types = |v:&Vec<VoteTypeSpecification>| v.iter().map(|s|(s.first_index_inclusive,s.last_index_exclusive)).collect::<Vec<_>>();
+        let values = |v:&Vec<VoteValueSpecification>| v.iter().map(|s|(s.first_index_inclusive,s.last_index_exclusive)).collect::<Vec<_>>();
+        for (first,last) in types(&self.atl_types) { check_range("atl_types",first,last,self.atl.len()); }
+        for (first,last) in types(&self.btl_types) { check_range("btl_types",first,last,self.btl.len()); }
+        for (first,last) in values(&self.atl_transfer_values) { check_range("atl_transfer_values",first,last,self.atl.len()); }
+        for (first,last) in values(&self.btl_transfer_values) { check_range("btl_transfer_values",first,last,self.btl.len()); }
+        for a in &self.atl_annotations { check_range("atl_annotations",a.first_index_inclusive,a.last_index_exclusive,self.atl.len()); }
+        for a in &self.btl_annotations { check_range("btl_annotations",a.first_index_inclusive,a.last_index_exclusive,self.btl.len()); }
+        ValidationReport{problems}
+    }
+
+    /// The number of above the line votes whose first preference is each party.
+    pub fn atl_votes_by_party(&self) -> Vec<BallotPaperCount> {
+        let mut res = vec![BallotPaperCount(0);self.metadata.parties.len()];
+        for atl in &self.atl {
+            if let Some(count) = atl.parties.first().and_then(|p|res.get_mut(p.0)) { count.0+=atl.n; }
+        }
+        res
+    }
+
+    /// Check the above the line votes for each party match the official numbers, if any are recorded, exactly.
+    /// This is mainly useful when the above the line votes were reconstructed from other data, which may have gone wrong.
+    pub fn validate_atl_reconstruction(&self) -> Vec<AtlReconstructionWarning> {
+        self.validate_atl_reconstruction_with_tolerance(BallotPaperCount(0))
+    }
+
+    /// Like [Self::validate_atl_reconstruction], but only report differences of more than `tolerance` votes.
+    /// If no party has an official number, nothing is checked.
+    pub fn validate_atl_reconstruction_with_tolerance(&self,tolerance:BallotPaperCount) -> Vec<AtlReconstructionWarning> {
+        let mut warnings = vec![];
+        if self.metadata.parties.iter().all(|p|p.official_atl_votes.is_none()) { return warnings; }
+        for (p,actual) in self.atl_votes_by_party().into_iter().enumerate() {
+            let party = PartyIndex(p);
+            match self.metadata.parties[p].official_atl_votes {
+                Some(official) if actual.0.abs_diff(official.0)>tolerance.0 => warnings.push(AtlReconstructionWarning::DifferentFromOfficial {party,actual,official}),
+                None if actual.0>0 => warnings.push(AtlReconstructionWarning::NoOfficialNumber {party,actual}),
+                _ => {}
+            }
+        }
+        warnings
+    }
+}
@@ -19,6 +19,7 @@ use crate::distribution_of_preferences_transcript::{CountIndex, PerCandidate, Qu
 use crate::election_data::ElectionData;
 use crate::official_dop_transcript::{CanConvertToF64PossiblyLossily, OfficialDistributionOfPreferencesTranscript};
 use crate::preference_distribution::{PreferenceDistributionRules, PreferenceDistributor};
+use crate::preference_oracle::PreferenceOracle;
 use crate::random_util::Randomness;
 use crate::tie_resolution::{TieResolutionAtom, TieResolutionExplicitDecision, TieResolutionExplicitDecisionInCount, TieResolutionGranularityNeeded, TieResolutionsMadeByEC};
 
@@ -245,14 +246,16 @@ pub fn distribute_preferences_using_official_results<Rules:PreferenceDistributio
         btl,
         btl_types: vec![],
         btl_transfer_values: vec![],
+        atl_annotations: vec![],
+        btl_annotations: vec![],
         informal: 0,
     };
     let ec_resolutions = metadata.tie_resolutions.clone(); // TODO make EC resolutions correct.
     let arena = typed_arena::Arena::<CandidateIndex>::new();
     let votes = data.resolve_atl_including_weights(&arena,None);
-    let oracle = OracleFromOfficialDOP{official, tie_resolutions: Default::default() };
+    let mut oracle = OracleFromOfficialDOP{official, tie_resolutions: Default::default() };
     let mut randomness = Randomness::ReverseDonkeyVote;
-    let mut work : PreferenceDistributor<'_,Rules> = PreferenceDistributor::new(&data,&votes,candidates_to_be_elected,&excluded_candidates,&ec_resolutions,print_progress_to_stdout,Some(oracle),&mut randomness,&[],false);
+    let mut work : PreferenceDistributor<'_,Rules> = PreferenceDistributor::new(&data,&votes,candidates_to_be_elected,&excluded_candidates,&ec_resolutions,print_progress_to_stdout,Some(&mut oracle),&mut randomness,&[],false);
     work.go();
     Ok(work.transcript)
 }
@@ -263,9 +266,9 @@ pub struct OracleFromOfficialDOP<'a> {
     tie_resolutions : TieResolutionsMadeByEC,
 }
 
-impl <'a> OracleFromOfficialDOP<'a> {
+impl <'a> PreferenceOracle for OracleFromOfficialDOP<'a> {
     /// The Oracle declares that verily votes shall be distributed as it shall say.
-    pub fn get_distribution_by_candidate(&mut self, current_count:CountIndex) -> Option<Vec<BallotPaperCount>> {
+    fn get_distribution_by_candidate(&mut self, current_count:CountIndex,_from:&[CandidateIndex],_natural:&[BallotPaperCount]) -> Option<Vec<BallotPaperCount>> {
         if current_count.0>=self.official.counts.len() { return None }
         let count = &self.official.counts[current_count.0];
         if let Some(paper_delta) = &count.paper_delta {
@@ -284,7 +287,7 @@ impl <'a> OracleFromOfficialDOP<'a> {
         }
     }
 
-    pub fn resolve_tie_resolution(&mut self, current_count:CountIndex,granularity:TieResolutionGranularityNeeded,tied_candidates:&[CandidateIndex]) -> Option<TieResolutionAtom> {
+    fn resolve_tie_resolution(&mut self, current_count:CountIndex,granularity:TieResolutionGranularityNeeded,tied_candidates:&[CandidateIndex]) -> Option<TieResolutionAtom> {
         if current_count.0>self.official.counts.len() { return None }
         let count = &self.official.counts[current_count.0];
         let mut elected_this_count : Vec<CandidateIndex> = count.elected.iter().filter(|c|tied_candidates.contains(c)).cloned().collect();
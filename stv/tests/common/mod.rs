@@ -0,0 +1,126 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Rules and data shared by the tests in this directory, so each test only needs to state what it varies.
+
+// Each test uses only some of these.
+#![allow(dead_code)]
+
+use std::marker::PhantomData;
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use stv::ballot_paper::BTL;
+use stv::ballot_pile::{BallotPaperCount, DoNotSplitByCountNumber};
+use stv::preference_distribution::{BigRational, DeferLastTwoStandingShortcut, ElectCandidateClauseCheckContext, LastParcelUse, PreferenceDistributionRules, SurplusFractionDenominatorPolicy, SurplusTransferMethod, TransferRounding, TransferValueMethod, WhenToDoElectCandidateClauseChecking};
+use stv::tie_resolution::MethodOfTieResolution;
+use stv::transfer_value::{convert_usize_to_rational, round_rational_down_to_usize, TransferValue};
+
+/// A variation on [SimpleRules]. Each function defaults to the value used by [SimpleRules], and
+/// is the function of the same name in [PreferenceDistributionRules] for [SimpleRulesWith].
+pub trait Variation {
+    fn use_last_parcel_for_surplus_distribution() -> LastParcelUse { LastParcelUse::No }
+    fn transfer_value_method() -> TransferValueMethod { TransferValueMethod::SurplusOverBallots }
+    fn surplus_distribution_subdivisions() -> SurplusTransferMethod { SurplusTransferMethod::JustOneTransferValue }
+    fn sort_exclusions_by_transfer_value() -> bool { false }
+    fn resolve_ties_elected_one_of_last_two() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_elected_by_quota() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_elected_all_remaining() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_choose_lowest_candidate_for_exclusion() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn check_elected_if_in_middle_of_surplus_distribution() -> bool { true }
+    fn check_elected_if_in_middle_of_exclusion() -> bool { true }
+    fn defer_surplus_of_candidate_elected_mid_exclusion() -> bool { true }
+    fn finish_all_counts_in_elimination_when_all_elected() -> bool { false }
+    fn finish_all_surplus_distributions_when_all_elected() -> bool { false }
+    fn when_to_check_if_just_two_standing_for_shortcut_election() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::Never }
+    fn when_to_check_if_all_remaining_should_get_elected() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::AfterCheckingQuota }
+    fn when_to_check_if_top_few_have_overwhelming_votes() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::Never }
+    fn when_to_check_all_remaining_custom(_context:&ElectCandidateClauseCheckContext) -> bool { false }
+    fn defer_last_two_standing_shortcut() -> DeferLastTwoStandingShortcut { DeferLastTwoStandingShortcut::Never }
+    fn should_eliminate_multiple_candidates_federal_rule_13a() -> bool { false }
+    fn should_exhausted_votes_count_for_quota_computation() -> bool { false }
+    fn surplus_fraction_denominator_policy() -> SurplusFractionDenominatorPolicy { SurplusFractionDenominatorPolicy::ClampToOne }
+    fn elect_highest_first() -> bool { true }
+    fn recompute_quota_after_each_election() -> bool { false }
+    fn quota_is_strict_greater() -> bool { false }
+    fn transfer_rounding() -> TransferRounding { TransferRounding::PerCandidateDown }
+    fn redistribute_exhausted_proportionally() -> bool { false }
+    fn minimum_transfer_value() -> Option<TransferValue> { None }
+    fn munge_surplus_fraction(fraction:TransferValue) -> TransferValue { fraction }
+    fn name() -> String { "SimpleRules".to_string() }
+}
+
+/// Simple integer rules, varied by `V`.
+pub struct SimpleRulesWith<V> { junk : PhantomData<V> }
+
+/// No variation.
+pub struct Unvaried {}
+impl Variation for Unvaried {}
+
+/// Simple integer rules.
+pub type SimpleRules = SimpleRulesWith<Unvaried>;
+
+/// Sorting exclusions by transfer value, which makes the order of exclusion parcels deterministic.
+pub struct SortedExclusions {}
+impl Variation for SortedExclusions {
+    fn sort_exclusions_by_transfer_value() -> bool { true }
+}
+
+/// Simple integer rules, sorting exclusions by transfer value.
+pub type SortedExclusionRules = SimpleRulesWith<SortedExclusions>;
+
+impl <V:Variation> PreferenceDistributionRules for SimpleRulesWith<V> {
+    type Tally = usize;
+    type SplitByNumber = DoNotSplitByCountNumber;
+
+    fn use_last_parcel_for_surplus_distribution() -> LastParcelUse { V::use_last_parcel_for_surplus_distribution() }
+    fn transfer_value_method() -> TransferValueMethod { V::transfer_value_method() }
+    fn convert_tally_to_rational(tally: Self::Tally) -> BigRational { convert_usize_to_rational(tally) }
+    fn convert_rational_to_tally_after_applying_transfer_value(rational: BigRational) -> Self::Tally { round_rational_down_to_usize(rational) }
+    fn make_transfer_value(surplus: usize, ballots: BallotPaperCount) -> TransferValue { TransferValue::from_surplus(surplus,ballots) }
+    fn use_transfer_value(transfer_value: &TransferValue, ballots: BallotPaperCount) -> usize { transfer_value.mul_rounding_down(ballots) }
+    fn surplus_distribution_subdivisions() -> SurplusTransferMethod { V::surplus_distribution_subdivisions() }
+    fn sort_exclusions_by_transfer_value() -> bool { V::sort_exclusions_by_transfer_value() }
+    fn resolve_ties_elected_one_of_last_two() -> MethodOfTieResolution { V::resolve_ties_elected_one_of_last_two() }
+    fn resolve_ties_elected_by_quota() -> MethodOfTieResolution { V::resolve_ties_elected_by_quota() }
+    fn resolve_ties_elected_all_remaining() -> MethodOfTieResolution { V::resolve_ties_elected_all_remaining() }
+    fn resolve_ties_choose_lowest_candidate_for_exclusion() -> MethodOfTieResolution { V::resolve_ties_choose_lowest_candidate_for_exclusion() }
+    fn check_elected_if_in_middle_of_surplus_distribution() -> bool { V::check_elected_if_in_middle_of_surplus_distribution() }
+    fn check_elected_if_in_middle_of_exclusion() -> bool { V::check_elected_if_in_middle_of_exclusion() }
+    fn defer_surplus_of_candidate_elected_mid_exclusion() -> bool { V::defer_surplus_of_candidate_elected_mid_exclusion() }
+    fn finish_all_counts_in_elimination_when_all_elected() -> bool { V::finish_all_counts_in_elimination_when_all_elected() }
+    fn finish_all_surplus_distributions_when_all_elected() -> bool { V::finish_all_surplus_distributions_when_all_elected() }
+    fn when_to_check_if_just_two_standing_for_shortcut_election() -> WhenToDoElectCandidateClauseChecking { V::when_to_check_if_just_two_standing_for_shortcut_election() }
+    fn when_to_check_if_all_remaining_should_get_elected() -> WhenToDoElectCandidateClauseChecking { V::when_to_check_if_all_remaining_should_get_elected() }
+    fn when_to_check_if_top_few_have_overwhelming_votes() -> WhenToDoElectCandidateClauseChecking { V::when_to_check_if_top_few_have_overwhelming_votes() }
+    fn when_to_check_all_remaining_custom(context:&ElectCandidateClauseCheckContext) -> bool { V::when_to_check_all_remaining_custom(context) }
+    fn defer_last_two_standing_shortcut() -> DeferLastTwoStandingShortcut { V::defer_last_two_standing_shortcut() }
+    fn should_eliminate_multiple_candidates_federal_rule_13a() -> bool { V::should_eliminate_multiple_candidates_federal_rule_13a() }
+    fn name() -> String { V::name() }
+    fn should_exhausted_votes_count_for_quota_computation() -> bool { V::should_exhausted_votes_count_for_quota_computation() }
+    fn surplus_fraction_denominator_policy() -> SurplusFractionDenominatorPolicy { V::surplus_fraction_denominator_policy() }
+    fn elect_highest_first() -> bool { V::elect_highest_first() }
+    fn recompute_quota_after_each_election() -> bool { V::recompute_quota_after_each_election() }
+    fn quota_is_strict_greater() -> bool { V::quota_is_strict_greater() }
+    fn transfer_rounding() -> TransferRounding { V::transfer_rounding() }
+    fn redistribute_exhausted_proportionally() -> bool { V::redistribute_exhausted_proportionally() }
+    fn minimum_transfer_value() -> Option<TransferValue> { V::minimum_transfer_value() }
+    fn munge_surplus_fraction(fraction:TransferValue) -> TransferValue { V::munge_surplus_fraction(fraction) }
+}
+
+/// Candidates with the given names, not in any party.
+pub fn candidates(names:&[&str]) -> Vec<Candidate> {
+    names.iter().map(|name|Candidate::from_name(name)).collect()
+}
+
+/// Metadata for candidates with the given names, not in any party, for the given number of vacancies.
+pub fn metadata(names:&[&str],vacancies:usize) -> ElectionMetadata {
+    ElectionMetadata{ candidates: candidates(names), vacancies: Some(NumberOfCandidates(vacancies)), ..Default::default() }
+}
+
+/// `n` below the line votes for the given candidates, in order of preference.
+pub fn btl(candidates:&[usize],n:usize) -> BTL {
+    BTL{ candidates: candidates.iter().map(|&c|CandidateIndex(c)).collect(), n }
+}
+
@@ -0,0 +1,52 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the AEC style plain text output of a transcript against a golden file.
+
+use std::collections::HashSet;
+use stv::aec_text::transcript_to_aec_text;
+use stv::ballot_metadata::{CandidateIndex, ElectionMetadata, ElectionName, NumberOfCandidates};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::distribute_preferences;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Five candidates for two vacancies, with E excluded before the count. A gets a quota on first preferences, with the surplus going to B.
+/// D is then excluded, electing C, and B is left continuing.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: ElectionMetadata {
+            name: ElectionName {
+                year: "2024".to_string(),
+                name: "Example".to_string(),
+                electorate: "Somewhere".to_string(),
+                ..Default::default()
+            },
+            excluded: vec![CandidateIndex(4)],
+            ..metadata(&["SMITH, Alice","JONES, Bob","WONG, Carol","NGUYEN, Dan","BROWN, Eve"],2)
+        },
+        btl: vec![
+            btl(&[0,1],40),
+            btl(&[1],20),
+            btl(&[2],25),
+            btl(&[3,2],10),
+        ],
+        ..Default::default()
+    }
+}
+
+const GOLDEN : &str = include_str!("aec_text_golden.txt");
+
+#[test]
+fn test_aec_text_matches_golden_file() {
+    let data = make_data();
+    let excluded : HashSet<CandidateIndex> = data.metadata.excluded.iter().cloned().collect();
+    let transcript = distribute_preferences::<SimpleRules>(&data,NumberOfCandidates(2),&excluded,&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+    let text = transcript_to_aec_text(&transcript,&data.metadata);
+    assert_eq!(text,GOLDEN);
+}
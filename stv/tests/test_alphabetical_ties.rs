@@ -0,0 +1,61 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check ties can be resolved alphabetically by candidate name.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+use stv::tie_resolution::MethodOfTieResolution;
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Simple integer rules, resolving ties for exclusion alphabetically.
+type AlphabeticalRules = SimpleRulesWith<Alphabetical>;
+
+struct Alphabetical {}
+
+impl Variation for Alphabetical {
+    fn resolve_ties_elected_one_of_last_two() -> MethodOfTieResolution { MethodOfTieResolution::AlphabeticalByName }
+    fn resolve_ties_elected_by_quota() -> MethodOfTieResolution { MethodOfTieResolution::AlphabeticalByName }
+    fn resolve_ties_elected_all_remaining() -> MethodOfTieResolution { MethodOfTieResolution::AlphabeticalByName }
+    fn resolve_ties_choose_lowest_candidate_for_exclusion() -> MethodOfTieResolution { MethodOfTieResolution::AlphabeticalByName }
+    fn name() -> String { "AlphabeticalRules".to_string() }
+}
+
+/// One vacancy. Candidates 2 and 3 are tied for exclusion at the first count; candidate 2's votes go to candidate 0, candidate 3's to candidate 1.
+fn make_data(names:[&str;4]) -> ElectionData {
+    ElectionData {
+        metadata: metadata(&names,1),
+        btl: vec![
+            btl(&[0],20),
+            btl(&[1],18),
+            btl(&[2,0],5),
+            btl(&[3,1],5),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_alphabetical_exclusion() {
+    // YOUNG comes after BROWN, so is treated as having fewer votes and excluded. Then SMITH reaches the quota of 25.
+    let transcript = make_data(["SMITH","JONES","YOUNG","BROWN"]).distribute_preferences::<AlphabeticalRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.counts[1].not_continuing,vec![CandidateIndex(2)]);
+    assert!(transcript.counts[1].decisions.is_empty());
+    assert_eq!(transcript.elected,vec![CandidateIndex(0)]);
+    // swapping the names of the tied candidates swaps who is excluded.
+    let transcript = make_data(["SMITH","JONES","BROWN","YOUNG"]).distribute_preferences::<AlphabeticalRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.counts[1].not_continuing,vec![CandidateIndex(3)]);
+    assert!(transcript.counts[1].decisions.is_empty());
+}
+
+#[test]
+fn test_identical_names_left_to_ec() {
+    let transcript = make_data(["SMITH","JONES","BROWN","BROWN"]).distribute_preferences::<AlphabeticalRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.counts[1].decisions.len(),1);
+}
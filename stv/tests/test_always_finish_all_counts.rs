@@ -0,0 +1,53 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that always_finish_all_counts gives more counts but does not change who is elected.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::distribution_of_preferences_transcript::{CountIndex, ReasonForCount, Transcript};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{distribute_preferences_with_options, CountOptions};
+use stv::random_util::Randomness;
+use stv::tie_resolution::TieResolutionsMadeByEC;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Candidates A, B, C for one vacancy, quota 51. A is elected on first preferences, with a surplus of 9.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C"],1),
+        btl: vec![
+            btl(&[0,1],60),
+            btl(&[1],25),
+            btl(&[2,1],15),
+        ],
+        ..Default::default()
+    }
+}
+
+fn count(always_finish_all_counts:bool) -> Transcript<usize> {
+    let data = make_data();
+    distribute_preferences_with_options::<SimpleRules>(&data,NumberOfCandidates(1),&HashSet::new(),&TieResolutionsMadeByEC::default(),&mut Randomness::ReverseDonkeyVote,CountOptions{ always_finish_all_counts, ..Default::default() })
+}
+
+#[test]
+fn test_always_finish_all_counts() {
+    let normal = count(false);
+    let finished = count(true);
+    assert_eq!(normal.elected,vec![CandidateIndex(0)]);
+    assert_eq!(finished.elected,normal.elected);
+    assert_eq!(normal.counts.len(),1);
+    // A's surplus, then exclusion of C, then of B.
+    assert_eq!(finished.counts.len(),4);
+    assert!(matches!(&finished.count(CountIndex(1)).reason,ReasonForCount::ExcessDistribution(c) if *c==CandidateIndex(0)));
+    assert!(matches!(&finished.count(CountIndex(2)).reason,ReasonForCount::Elimination(c) if c==&vec![CandidateIndex(2)]));
+    assert!(matches!(&finished.count(CountIndex(3)).reason,ReasonForCount::Elimination(c) if c==&vec![CandidateIndex(1)]));
+    assert_eq!(finished.count(CountIndex(1)).status.tallies.candidate,vec![51,34,15]);
+    assert_eq!(finished.count(CountIndex(2)).status.tallies.candidate,vec![51,49,0]);
+    for count in &finished.counts { assert!(count.elected.is_empty() || count.elected.iter().all(|e|e.who==CandidateIndex(0))); }
+}
@@ -0,0 +1,78 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check ballots with starting weights other than 1 are counted by value, including for the quota.
+
+use stv::ballot_pile::BallotPaperCount;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+use num::BigInt;
+use stv::transfer_value::TransferValue;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Four candidates for two vacancies, all with weight 1.
+fn make_data(c_votes:usize) -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0],10),
+            btl(&[1],8),
+            btl(&[2],c_votes),
+            btl(&[3],2),
+        ],
+        ..Default::default()
+    }
+}
+
+fn half() -> TransferValue { TransferValue::new(BigInt::from(1),BigInt::from(2)) }
+
+fn ranges(data:&ElectionData) -> Vec<(String,usize,usize)> {
+    data.btl_transfer_values.iter().map(|v|(v.value.to_string(),v.first_index_inclusive,v.last_index_exclusive)).collect()
+}
+
+#[test]
+fn test_with_ballot_weight_ranges() {
+    let data = make_data(6).with_ballot_weight(2,half());
+    assert_eq!(ranges(&data),vec![("1".to_string(),0,2),("1/2".to_string(),2,3),("1".to_string(),3,4)]);
+    let data = data.with_ballot_weight(3,half());
+    assert_eq!(ranges(&data),vec![("1".to_string(),0,2),("1/2".to_string(),2,4)]);
+    // setting everything back to 1 leaves the common, unweighted, representation.
+    let data = data.with_ballot_weight(2,TransferValue::one()).with_ballot_weight(3,TransferValue::one());
+    assert!(data.btl_transfer_values.is_empty());
+    assert!(data.validate().problems.is_empty());
+}
+
+#[test]
+fn test_quota_uses_value_of_weighted_ballots() {
+    // unweighted : 26 papers, quota 26/3+1 = 9.
+    let transcript = make_data(6).distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,9);
+    // C's 6 papers are worth 1/2 each, so only 3 : total value 23, quota 23/3+1 = 8.
+    let data = make_data(6).with_ballot_weight(2,half());
+    assert!(data.validate().problems.is_empty());
+    let transcript = data.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    let quota = transcript.quota.as_ref().unwrap();
+    assert_eq!(quota.papers,BallotPaperCount(23));
+    assert_eq!(quota.quota,8);
+    // one first preference step per distinct weight, highest first.
+    assert_eq!(transcript.counts[0].portion.transfer_value,Some(TransferValue::one()));
+    assert_eq!(transcript.counts[1].portion.transfer_value,Some(half()));
+    let first_preferences = &transcript.counts[1].status;
+    assert_eq!(first_preferences.tallies.candidate,vec![10,8,3,2]);
+    assert_eq!(first_preferences.papers.candidate[2],BallotPaperCount(6));
+}
+
+#[test]
+fn test_fractional_value_rounded_down_for_quota() {
+    // C's 7 papers at 1/2 are worth 3.5, rounded down to 3 by these integer rules : total 23, quota 8.
+    let transcript = make_data(7).with_ballot_weight(2,half()).distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    let quota = transcript.quota.as_ref().unwrap();
+    assert_eq!(quota.papers,BallotPaperCount(23));
+    assert_eq!(quota.quota,8);
+    assert_eq!(transcript.counts[1].status.tallies.candidate[2],3);
+}
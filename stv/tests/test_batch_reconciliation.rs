@@ -0,0 +1,84 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check reconciliation of first preferences per batch against official totals.
+
+use std::collections::BTreeMap;
+use stv::ballot_metadata::CandidateIndex;
+use stv::ballot_pile::BallotPaperCount;
+use stv::batch_reconciliation::{reconcile_against_annotation_totals, reconcile_against_batch_totals, BatchMismatch};
+use stv::election_data::ElectionData;
+
+/// Two parties A (candidates 0,1) and B (candidate 2), and an ungrouped candidate 3.
+/// Batch "Box 1" is ATL vote 0 and BTL vote 0, batch "Box 2" is ATL vote 1 and BTL votes 1 and 2. BTL vote 3 is in no batch.
+fn make_data() -> ElectionData {
+    let json = r#"{
+      "metadata" : {
+        "name" : { "year":"2024","authority":"Test","name":"Test","electorate":"Test" },
+        "candidates" : [
+          { "name":"A1","party":0,"position":1 },
+          { "name":"A2","party":0,"position":2 },
+          { "name":"B1","party":1,"position":1 },
+          { "name":"U" }
+        ],
+        "parties" : [
+          { "column_id":"A","name":"Party A","atl_allowed":true,"candidates":[0,1] },
+          { "column_id":"B","name":"Party B","atl_allowed":true,"candidates":[2] }
+        ],
+        "source" : [],
+        "vacancies" : 2
+      },
+      "atl" : [{"parties":[0,1],"n":10},{"parties":[1],"n":7}],
+      "btl" : [{"candidates":[3,2],"n":5},{"candidates":[1,0],"n":2},{"candidates":[3],"n":4},{"candidates":[2],"n":1}],
+      "atl_annotations" : [
+        {"key":"batch","value":"Box 1","first_index_inclusive":0,"last_index_exclusive":1},
+        {"key":"batch","value":"Box 2","first_index_inclusive":1,"last_index_exclusive":2},
+        {"key":"polling_place","value":"Springfield","first_index_inclusive":0,"last_index_exclusive":2}
+      ],
+      "btl_annotations" : [
+        {"key":"batch","value":"Box 1","first_index_inclusive":0,"last_index_exclusive":1},
+        {"key":"batch","value":"Box 2","first_index_inclusive":1,"last_index_exclusive":3},
+        {"key":"polling_place","value":"Springfield","first_index_inclusive":0,"last_index_exclusive":4}
+      ],
+      "informal" : 0
+    }"#;
+    serde_json::from_str(json).unwrap()
+}
+
+fn totals(batches:&[(&str,&[usize])]) -> BTreeMap<String,Vec<BallotPaperCount>> {
+    batches.iter().map(|(batch,tallies)|(batch.to_string(),tallies.iter().map(|&n|BallotPaperCount(n)).collect())).collect()
+}
+
+#[test]
+fn test_matching_totals() {
+    let data = make_data();
+    let res = reconcile_against_batch_totals(&data,&totals(&[("Box 1",&[10,0,0,5]),("Box 2",&[0,2,7,4])]));
+    assert_eq!(res.matched,vec!["Box 1".to_string(),"Box 2".to_string()]);
+    assert!(res.mismatches.is_empty());
+    assert!(res.not_in_official.is_empty());
+    assert_eq!(res.unbatched,BallotPaperCount(1));
+    assert!(!res.is_ok()); // because of the unbatched vote.
+    // all votes are in a polling place.
+    let res = reconcile_against_annotation_totals(&data,"polling_place",&totals(&[("Springfield",&[10,2,8,9])]));
+    assert!(res.is_ok());
+}
+
+#[test]
+fn test_mismatching_totals() {
+    let data = make_data();
+    let res = reconcile_against_batch_totals(&data,&totals(&[("Box 1",&[10,0,0,5]),("Box 2",&[0,3,7,4]),("Box 3",&[1])]));
+    // Box 3 has no votes in the data, and is missing candidates at the end of the official list, which have no votes.
+    assert_eq!(res.matched,vec!["Box 1".to_string()]);
+    assert_eq!(res.mismatches,vec![
+        BatchMismatch{ batch: "Box 2".to_string(), candidate: CandidateIndex(1), actual: BallotPaperCount(2), official: BallotPaperCount(3) },
+        BatchMismatch{ batch: "Box 3".to_string(), candidate: CandidateIndex(0), actual: BallotPaperCount(0), official: BallotPaperCount(1) },
+    ]);
+    assert_eq!(res.mismatches[0].to_string(),"batch Box 2 has 2 first preferences for candidate 1 but the official number is 3");
+    // a batch in the data with no official totals.
+    let res = reconcile_against_batch_totals(&data,&totals(&[("Box 1",&[10,0,0,5])]));
+    assert_eq!(res.not_in_official,vec!["Box 2".to_string()]);
+    assert!(res.mismatches.is_empty());
+}
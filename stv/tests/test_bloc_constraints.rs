@@ -0,0 +1,62 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check counting with bloc constraints, where a bloc of candidates must be either all elected or all not elected.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::bloc_constraints::{count_with_bloc_constraints, feasible_bloc_outcomes};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::distribute_preferences;
+use stv::random_util::Randomness;
+use stv::tie_resolution::TieResolutionsMadeByEC;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Four candidates for two vacancies, quota 11. C and D swap preferences, and are the bloc.
+/// Unconstrained, D is excluded electing C, then B is excluded electing A, splitting the bloc.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0],10),
+            btl(&[1],9),
+            btl(&[2,3],6),
+            btl(&[3,2],5),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_bloc_in_or_out() {
+    let data = make_data();
+    let unconstrained = distribute_preferences::<SimpleRules>(&data,NumberOfCandidates(2),&HashSet::new(),&TieResolutionsMadeByEC::default(),None,false,&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(unconstrained.elected,vec![CandidateIndex(2),CandidateIndex(0)]);
+    let blocs = vec![vec![CandidateIndex(2),CandidateIndex(3)]];
+    let outcomes = count_with_bloc_constraints::<SimpleRules>(&data,NumberOfCandidates(2),&HashSet::new(),&blocs,&TieResolutionsMadeByEC::default(),None);
+    assert_eq!(outcomes.len(),2);
+    assert_eq!(outcomes[0].bloc_in,vec![false]);
+    let mut elected = outcomes[0].elected.clone();
+    elected.sort_by_key(|c|c.0);
+    assert_eq!(elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+    assert_eq!(outcomes[1].bloc_in,vec![true]);
+    let mut elected = outcomes[1].elected.clone();
+    elected.sort_by_key(|c|c.0);
+    assert_eq!(elected,vec![CandidateIndex(2),CandidateIndex(3)]);
+    assert_eq!(feasible_bloc_outcomes::<SimpleRules>(&data,NumberOfCandidates(2),&HashSet::new(),&blocs,&TieResolutionsMadeByEC::default(),None).len(),2);
+}
+
+#[test]
+fn test_infeasible_bloc() {
+    // a bloc of three cannot fit in two vacancies.
+    let blocs = vec![vec![CandidateIndex(1),CandidateIndex(2),CandidateIndex(3)]];
+    let outcomes = count_with_bloc_constraints::<SimpleRules>(&make_data(),NumberOfCandidates(2),&HashSet::new(),&blocs,&TieResolutionsMadeByEC::default(),None);
+    assert!(!outcomes[1].feasible);
+    assert!(outcomes[0].feasible);
+    assert_eq!(outcomes[0].elected,vec![CandidateIndex(0)]);
+}
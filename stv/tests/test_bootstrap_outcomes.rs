@@ -0,0 +1,45 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test bootstrap resampling of election outcomes.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::election_data::ElectionData;
+use stv::monte_carlo::bootstrap_outcomes;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Candidates A, B, C, D for two vacancies. A and B are clear winners; C and D are nearly tied for last.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0],200),
+            btl(&[1],180),
+            btl(&[2],11),
+            btl(&[3],10),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_clear_winners_always_elected() {
+    let distribution = bootstrap_outcomes::<SimpleRules>(&make_data(),50,1).unwrap();
+    assert_eq!(50,distribution.samples);
+    assert_eq!(50,distribution.times_elected[0]);
+    assert_eq!(50,distribution.times_elected[1]);
+    assert_eq!(0,distribution.times_elected[2]+distribution.times_elected[3]);
+    assert_eq!(1.0,distribution.probability_elected(CandidateIndex(0)));
+    assert_eq!(vec![CandidateIndex(0),CandidateIndex(1)],distribution.sometimes_elected().into_iter().take(2).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_bootstrap_is_deterministic_given_seed() {
+    let data = make_data();
+    assert_eq!(bootstrap_outcomes::<SimpleRules>(&data,20,7).unwrap(),bootstrap_outcomes::<SimpleRules>(&data,20,7).unwrap());
+}
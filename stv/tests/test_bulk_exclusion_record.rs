@@ -0,0 +1,71 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the working for federal rule 13A bulk exclusions is recorded in the transcript.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::ReasonForCount;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Simple integer rules, with federal style bulk exclusion.
+type SimpleRules = SimpleRulesWith<BulkExclusion>;
+
+struct BulkExclusion {}
+
+impl Variation for BulkExclusion {
+    fn should_eliminate_multiple_candidates_federal_rule_13a() -> bool { true }
+}
+
+/// Six candidates for two vacancies, with two candidates far behind the rest.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D","E","F"],2),
+        btl: vec![
+            btl(&[0],20),
+            btl(&[1],19),
+            btl(&[2],18),
+            btl(&[3],17),
+            btl(&[4,3],3),
+            btl(&[5,0],2),
+        ],
+        ..Default::default()
+    }
+}
+
+fn excluded(reason:&ReasonForCount) -> Vec<CandidateIndex> {
+    match reason {
+        ReasonForCount::Elimination(candidates) => candidates.clone(),
+        _ => panic!("Expected an exclusion"),
+    }
+}
+
+/// Worked by hand: 79 votes, quota 27. The shortfalls of A and B are 7 and 8, so the leading shortfall is 7 and
+/// the vacancy shortfall 15. The notional votes of F,E,D,C,B,A are 2,5,22,40,59,79.
+/// Candidate B (in the sense of rule 13A) is E, as E has notional votes 5 < 15 and < D's 17 votes. As 5 is less than the
+/// leading shortfall, E and F are excluded together.
+#[test]
+fn test_bulk_exclusion_is_recorded() {
+    let transcript = make_data().distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,27);
+    assert!(transcript.counts[0].bulk_exclusion.is_none());
+    let count = &transcript.counts[1];
+    assert_eq!(excluded(&count.reason),vec![CandidateIndex(5),CandidateIndex(4)]);
+    let bulk = count.bulk_exclusion.as_ref().expect("bulk exclusion working should be recorded");
+    assert_eq!(bulk.leading_shortfall,7);
+    assert_eq!(bulk.vacancy_shortfall,15);
+    assert_eq!(bulk.notional_votes,vec![(CandidateIndex(5),2),(CandidateIndex(4),5),(CandidateIndex(3),22),(CandidateIndex(2),40),(CandidateIndex(1),59),(CandidateIndex(0),79)]);
+    // After that, A 22, D 20, B 19, C 18. The vacancy shortfall is 5+7=12, and no one has fewer notional votes, so no bulk exclusion.
+    assert_eq!(excluded(&transcript.counts[2].reason),vec![CandidateIndex(2)]);
+    assert!(transcript.counts[2].bulk_exclusion.is_none());
+    // It is in the serialized transcript only where used.
+    let json = serde_json::to_value(&transcript).unwrap();
+    assert_eq!(json["counts"][1]["bulk_exclusion"]["leading_shortfall"],7);
+    assert!(json["counts"][2].get("bulk_exclusion").is_none());
+}
@@ -0,0 +1,48 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that a count can be cancelled partway through.
+
+use std::collections::HashSet;
+use std::sync::atomic::AtomicBool;
+use stv::ballot_metadata::NumberOfCandidates;
+use stv::distribution_of_preferences_transcript::{CountStopped, Transcript};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{distribute_preferences_with_options, CountOptions};
+use stv::random_util::Randomness;
+use stv::tie_resolution::TieResolutionsMadeByEC;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// One vacancy, needing two exclusions: A 4, B 3, C 2 then B, D 1 then C.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],1),
+        btl: vec![btl(&[0],4),btl(&[1],3),btl(&[2,1],2),btl(&[3,2],1)],
+        ..Default::default()
+    }
+}
+
+fn count(cancel:&AtomicBool) -> Transcript<usize> {
+    distribute_preferences_with_options::<SimpleRules>(&make_data(),NumberOfCandidates(1),&HashSet::new(),&TieResolutionsMadeByEC::default(),&mut Randomness::ReverseDonkeyVote,CountOptions{ cancel: Some(cancel), ..Default::default() })
+}
+
+#[test]
+fn test_cancelled_count_stops_after_first_preferences() {
+    let transcript = count(&AtomicBool::new(true));
+    assert_eq!(transcript.stopped,Some(CountStopped::Cancelled));
+    assert_eq!(transcript.counts.len(),1);
+    assert!(transcript.elected.is_empty());
+}
+
+#[test]
+fn test_count_not_cancelled_finishes() {
+    let transcript = count(&AtomicBool::new(false));
+    assert_eq!(transcript.stopped,None);
+    assert!(transcript.counts.len()>1);
+    assert_eq!(transcript.elected.len(),1);
+}
@@ -0,0 +1,51 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the detection of candidates certain to lose part way through a count.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Five candidates for two vacancies. A gets a quota on first preferences, with a surplus going to B.
+fn make_data(c_votes:usize) -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D","E"],2),
+        btl: vec![
+            btl(&[0,1],40),
+            btl(&[1],30),
+            btl(&[2],c_votes),
+            btl(&[3],6),
+            btl(&[4,3],3),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_certain_losers() {
+    // 96 votes, quota 33, so A's surplus is 7. D and E have 9 between them, plus the surplus is 16, less than C's 17.
+    let transcript = make_data(17).distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,33);
+    assert_eq!(transcript.certain_losers_at_count(CountIndex(0)),vec![CandidateIndex(4),CandidateIndex(3)]);
+    // The surplus elects B, filling all vacancies, so every continuing candidate has lost.
+    assert_eq!(transcript.counts.len(),2);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+    assert_eq!(transcript.certain_losers_at_count(CountIndex(1)),vec![CandidateIndex(4),CandidateIndex(3),CandidateIndex(2)]);
+}
+
+#[test]
+fn test_near_miss_is_not_certain() {
+    // 95 votes, quota 32, so A's surplus is 8. D and E have 9 between them, plus the surplus is 17, which is not less than C's 16,
+    // so in principle D could get all the surplus and E's votes and pass C.
+    let transcript = make_data(16).distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,32);
+    assert_eq!(transcript.certain_losers_at_count(CountIndex(0)),vec![]);
+}
@@ -0,0 +1,81 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test producing and verifying count certificates.
+
+use stv::ballot_metadata::MissingVacancies;
+use stv::count_certificate::CountCertificate;
+use stv::election_data::ElectionData;
+
+mod common;
+use common::{btl, metadata, SimpleRules, SimpleRulesWith, Variation};
+
+/// Like [SimpleRules], but exhausted votes count for the quota.
+type OtherRules = SimpleRulesWith<ExhaustedCountForQuota>;
+
+/// Keeps the default name, deliberately the same as SimpleRules.
+struct ExhaustedCountForQuota {}
+
+impl Variation for ExhaustedCountForQuota {
+    fn should_exhausted_votes_count_for_quota_computation() -> bool { true }
+}
+
+/// Candidates A, B, C, D for two vacancies, with a tie between C and D for exclusion.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0],40),
+            btl(&[1,2],25),
+            btl(&[2,1],10),
+            btl(&[3,0],10),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_certificate_verifies() {
+    let data = make_data();
+    for seed in [None,Some(1),Some(2)] {
+        let (transcript,certificate) = CountCertificate::count_and_produce::<SimpleRules>(&data,seed).unwrap();
+        assert_eq!(transcript.elected,certificate.elected);
+        assert_eq!(certificate.elected.len(),certificate.elected_stable_ids.len());
+        assert_eq!("SimpleRules",certificate.rules);
+        assert_eq!(64,certificate.data_hash.len());
+        assert!(certificate.verify::<SimpleRules>(&data));
+        // survives publication.
+        let published : CountCertificate = serde_json::from_str(&serde_json::to_string(&certificate).unwrap()).unwrap();
+        assert_eq!(certificate,published);
+        assert!(published.verify::<SimpleRules>(&data));
+    }
+}
+
+#[test]
+fn test_certificate_detects_changes() {
+    let data = make_data();
+    let (_,certificate) = CountCertificate::count_and_produce::<SimpleRules>(&data,None).unwrap();
+    // different data
+    let mut changed_data = data.clone();
+    changed_data.btl[0].n+=1;
+    assert!(!certificate.verify::<SimpleRules>(&changed_data));
+    // different rules with the same name
+    assert!(!certificate.verify::<OtherRules>(&data));
+    // a tampered transcript hash or elected list
+    let mut tampered = certificate.clone();
+    tampered.transcript_hash=tampered.transcript_hash.replace('0',"1").replace('a',"b");
+    assert!(!tampered.verify::<SimpleRules>(&data));
+    let mut tampered = certificate.clone();
+    tampered.elected.reverse();
+    assert!(!tampered.verify::<SimpleRules>(&data));
+}
+
+#[test]
+fn test_missing_vacancies_is_an_error() {
+    let mut data = make_data();
+    data.metadata.vacancies=None;
+    assert_eq!(CountCertificate::count_and_produce::<SimpleRules>(&data,None).err(),Some(MissingVacancies));
+}
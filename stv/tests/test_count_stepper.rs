@@ -0,0 +1,69 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Drive the interactive count stepper with scripted input.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::count_stepper::step_interactively;
+use stv::election_data::ElectionData;
+use stv::preference_distribution::distribute_preferences;
+use stv::random_util::Randomness;
+use stv::tie_resolution::TieResolutionsMadeByEC;
+
+mod common;
+use common::{btl, metadata, SortedExclusionRules};
+
+/// Two vacancies, quota 34. A is elected on first preferences, with a surplus of 6 going to B. Then B and D are excluded, electing C.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0,1],40),
+            btl(&[1],10),
+            btl(&[2],30),
+            btl(&[3,2],20),
+        ],
+        ..Default::default()
+    }
+}
+
+/// Run the stepper with the given commands, returning the output and the transcript's elected candidates and number of counts.
+fn run(commands:&str) -> (String,Vec<CandidateIndex>,usize) {
+    let mut output : Vec<u8> = vec![];
+    let transcript = step_interactively::<SortedExclusionRules,_,_>(&make_data(),NumberOfCandidates(2),&HashSet::new(),&TieResolutionsMadeByEC::default(),&mut Randomness::ReverseDonkeyVote,commands.as_bytes(),&mut output).unwrap();
+    (String::from_utf8(output).unwrap(),transcript.elected,transcript.counts.len())
+}
+
+#[test]
+fn test_scripted_stepping() {
+    let (output,elected,num_counts) = run("t\nc\ns\nn\nwhy 1\nn\nwhy B\nwhy 3\nbogus\nr\nwhy C\nn\n");
+    assert!(output.contains("0 A\t40\n1 B\t10\n2 C\t30\n3 D\t20\n"),"{}",output); // tallies
+    assert!(output.contains("Quota 34\n2 C\t30\n3 D\t20\n1 B\t10\n"),"{}",output); // continuing
+    assert!(output.contains("0 A\t6\n"),"{}",output); // surpluses
+    assert!(output.contains("Count 2 : surplus of 0 A"),"{}",output);
+    assert!(output.contains("Surplus 6 distributed at transfer value 3/20"),"{}",output);
+    assert!(output.contains("1 B has not been elected or excluded."),"{}",output);
+    assert!(output.contains("1 B was excluded starting in count 3, having a tally of 16, the lowest of the continuing candidates. The next lowest were 3 D 20, 2 C 30."),"{}",output);
+    assert!(output.contains("Unknown command bogus"),"{}",output);
+    assert!(output.contains("The count is over. Elected 0 A, 2 C"),"{}",output);
+    assert!(output.contains("2 C was elected in count 5 (ReachedQuota) with a tally of 50."),"{}",output);
+    assert!(output.ends_with("The count is over.\n"),"{}",output);
+    let full = distribute_preferences::<SortedExclusionRules>(&make_data(),NumberOfCandidates(2),&HashSet::new(),&TieResolutionsMadeByEC::default(),None,false,&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(elected,full.elected);
+    assert_eq!(num_counts,full.counts.len());
+}
+
+#[test]
+fn test_quit_early() {
+    let (output,elected,num_counts) = run("q\nn\n");
+    assert_eq!(elected,vec![CandidateIndex(0)]);
+    assert_eq!(num_counts,1);
+    assert!(output.starts_with("Quota 34\nCount 1 : first preferences"),"{}",output);
+    // stopping at the end of the input also works.
+    let (_,_,num_counts) = run("n\n");
+    assert_eq!(num_counts,2);
+}
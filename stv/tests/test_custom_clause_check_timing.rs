@@ -0,0 +1,63 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test the custom timing for the all remaining candidates elected shortcut.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::{ElectionReason, Transcript};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{ElectCandidateClauseCheckContext, WhenToDoElectCandidateClauseChecking};
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules, SimpleRulesWith, Variation};
+
+/// Like [SimpleRules], but the all remaining elected shortcut only applies from the fifth count (index 4) onward.
+type FromFifthCountRules = SimpleRulesWith<FromFifthCount>;
+
+struct FromFifthCount {}
+
+impl Variation for FromFifthCount {
+    fn when_to_check_if_all_remaining_should_get_elected() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::Custom }
+    fn when_to_check_all_remaining_custom(context: &ElectCandidateClauseCheckContext) -> bool { context.count.0>=4 }
+    fn name() -> String { "FromFifthCountRules".to_string() }
+}
+
+/// Candidates A, B, D, E, F for two vacancies. E and F's votes exhaust when they are excluded; D's go to A,
+/// putting A over the quota at the fourth count (index 3), at which point B is the only continuing candidate.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","D","E","F"],2),
+        btl: vec![
+            btl(&[0],20),
+            btl(&[1],20),
+            btl(&[2,0],15),
+            btl(&[3],14),
+            btl(&[4],13),
+        ],
+        ..Default::default()
+    }
+}
+
+/// The index of the count at which the given candidate was elected, and why.
+fn when_elected(transcript:&Transcript<usize>,who:CandidateIndex) -> Option<(usize,ElectionReason)> {
+    transcript.counts.iter().enumerate().find_map(|(count,single)|single.elected.iter().find(|e|e.who==who).map(|e|(count,e.why)))
+}
+
+#[test]
+fn test_all_remaining_only_from_nominated_count() {
+    let data = make_data();
+    let usual = data.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(Some((3,ElectionReason::ReachedQuota)),when_elected(&usual,CandidateIndex(0)));
+    assert_eq!(Some((3,ElectionReason::AllRemainingMustBeElected)),when_elected(&usual,CandidateIndex(1)));
+    assert_eq!(4,usual.counts.len());
+    // A's surplus is distributed at count index 4 before B can be elected by the shortcut.
+    let custom = data.distribute_preferences::<FromFifthCountRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(Some((3,ElectionReason::ReachedQuota)),when_elected(&custom,CandidateIndex(0)));
+    assert_eq!(Some((4,ElectionReason::AllRemainingMustBeElected)),when_elected(&custom,CandidateIndex(1)));
+    assert_eq!(5,custom.counts.len());
+    assert_eq!(usual.elected,custom.elected);
+}
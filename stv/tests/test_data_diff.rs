@@ -0,0 +1,85 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check differences between versions of election data.
+
+use stv::ballot_metadata::{Candidate, CandidateIndex, NumberOfCandidates};
+use stv::data_diff::{BallotGroupChange, CandidateChange};
+use stv::election_data::ElectionData;
+
+mod common;
+use common::{btl, metadata};
+
+/// Candidates A, B, C for one vacancy.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C"],1),
+        btl: vec![
+            btl(&[0,1],40),
+            btl(&[1],20),
+            btl(&[2,1],15),
+            btl(&[2,0],3),
+        ],
+        informal: 5,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_identical() {
+    let diff = make_data().diff(&make_data());
+    assert!(diff.is_empty());
+    assert_eq!(diff.first_preference_delta,vec![0,0,0]);
+    assert_eq!(diff.to_string(),"No differences\n");
+}
+
+#[test]
+fn test_ballot_changes() {
+    let old = make_data();
+    let mut new = make_data();
+    new.btl[1].n=18; // 2 B votes removed
+    new.btl.remove(3); // C,A votes gone
+    new.btl.push(btl(&[0,2],4)); // A,C votes new
+    new.btl.push(btl(&[0,1],1)); // one more A,B vote, merged with the first group
+    new.informal=6;
+    let diff = old.diff(&new);
+    assert!(diff.candidate_changes.is_empty());
+    assert!(diff.metadata_changes.is_empty());
+    assert_eq!(diff.added,vec![BallotGroupChange{ preferences: vec![CandidateIndex(0),CandidateIndex(2)], old_count: 0, new_count: 4 }]);
+    assert_eq!(diff.removed,vec![BallotGroupChange{ preferences: vec![CandidateIndex(2),CandidateIndex(0)], old_count: 3, new_count: 0 }]);
+    assert_eq!(diff.changed,vec![
+        BallotGroupChange{ preferences: vec![CandidateIndex(0),CandidateIndex(1)], old_count: 40, new_count: 41 },
+        BallotGroupChange{ preferences: vec![CandidateIndex(1)], old_count: 20, new_count: 18 },
+    ]);
+    assert_eq!(diff.first_preference_delta,vec![5,-2,-3]);
+    assert_eq!(diff.informal_delta,1);
+}
+
+#[test]
+fn test_candidate_changes() {
+    let old = make_data();
+    // the same votes, with candidates reordered to C, A, B, and an extra candidate D with a vote, and a change in vacancies.
+    let mut new = make_data();
+    new.metadata.candidates = vec![Candidate::from_name("C"),Candidate::from_name("A"),Candidate::from_name("B"),Candidate::from_name("D")];
+    let remap = [1,2,0];
+    for b in &mut new.btl { for c in &mut b.candidates { *c=CandidateIndex(remap[c.0]); } }
+    new.btl.push(btl(&[3],1));
+    new.metadata.vacancies=Some(NumberOfCandidates(2));
+    let diff = old.diff(&new);
+    assert_eq!(diff.candidates,vec!["A","B","C","D"]);
+    assert_eq!(diff.candidate_changes,vec![
+        CandidateChange::Moved{ name: "C".to_string(), old_index: CandidateIndex(2), new_index: CandidateIndex(0) },
+        CandidateChange::Moved{ name: "A".to_string(), old_index: CandidateIndex(0), new_index: CandidateIndex(1) },
+        CandidateChange::Moved{ name: "B".to_string(), old_index: CandidateIndex(1), new_index: CandidateIndex(2) },
+        CandidateChange::Added{ name: "D".to_string(), new_index: CandidateIndex(3) },
+    ]);
+    assert_eq!(diff.metadata_changes.len(),1);
+    // the reordering does not change any ballots.
+    assert_eq!(diff.added,vec![BallotGroupChange{ preferences: vec![CandidateIndex(3)], old_count: 0, new_count: 1 }]);
+    assert!(diff.removed.is_empty());
+    assert!(diff.changed.is_empty());
+    assert_eq!(diff.first_preference_delta,vec![0,0,0,1]);
+}
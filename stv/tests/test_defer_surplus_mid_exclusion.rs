@@ -0,0 +1,86 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check whether the surplus of a candidate elected part way through an exclusion is deferred until the exclusion is finished,
+//! as in WA clause 9.
+
+use std::marker::PhantomData;
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::{ReasonForCount, Transcript};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::WhenToDoElectCandidateClauseChecking;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Like WA, candidates can be elected in the middle of an exclusion but not in the middle of a surplus distribution.
+/// The deferral of their surplus is given by the type parameter.
+type MidExclusionRules<D> = SimpleRulesWith<MidExclusion<D>>;
+
+struct MidExclusion<D> { junk : PhantomData<D> }
+
+trait DeferOrNot { const DEFER : bool; }
+struct Defer {}
+impl DeferOrNot for Defer { const DEFER : bool = true; }
+struct DoNotDefer {}
+impl DeferOrNot for DoNotDefer { const DEFER : bool = false; }
+
+impl <D:DeferOrNot> Variation for MidExclusion<D> {
+    fn sort_exclusions_by_transfer_value() -> bool { true }
+    fn check_elected_if_in_middle_of_surplus_distribution() -> bool { false }
+    fn defer_surplus_of_candidate_elected_mid_exclusion() -> bool { D::DEFER }
+    fn when_to_check_if_just_two_standing_for_shortcut_election() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::AfterCheckingQuotaIfNoUndistributedSurplusExistsAndExclusionNotOngoing }
+    fn name() -> String { "MidExclusionRules".to_string() }
+}
+
+/// Six candidates for three vacancies, 212 votes, quota 54. A is elected on first preferences, and the surplus of 6 at transfer value 1/10 goes to E.
+/// E is then the lowest and is excluded. E's first preferences are transferred first, electing B with a surplus of 8 before E's
+/// votes from A have been transferred.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D","E","F"],3),
+        btl: vec![
+            btl(&[0,4,3],60),
+            btl(&[1,2],42),
+            btl(&[2],30),
+            btl(&[3],30),
+            btl(&[4,1],20),
+            btl(&[5],30),
+        ],
+        ..Default::default()
+    }
+}
+
+/// The reasons for the first five counts, as strings.
+fn reasons(transcript:&Transcript<usize>) -> Vec<String> {
+    transcript.counts.iter().take(5).map(|c|format!("{:?}",c.reason)).collect()
+}
+
+fn first() -> String { format!("{:?}",ReasonForCount::FirstPreferenceCount) }
+fn surplus(candidate:usize) -> String { format!("{:?}",ReasonForCount::ExcessDistribution(CandidateIndex(candidate))) }
+fn exclude_e() -> String { format!("{:?}",ReasonForCount::Elimination(vec![CandidateIndex(4)])) }
+
+#[test]
+fn test_surplus_deferred_until_exclusion_finished() {
+    let transcript = make_data().distribute_preferences::<MidExclusionRules<Defer>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,54);
+    assert_eq!(transcript.counts[2].elected.iter().map(|e|e.who).collect::<Vec<_>>(),vec![CandidateIndex(1)]);
+    assert_eq!(transcript.counts[2].status.tallies.candidate[1],62);
+    assert_eq!(reasons(&transcript),vec![first(),surplus(0),exclude_e(),exclude_e(),surplus(1)]);
+    // E's votes from A went to D before B's surplus was distributed.
+    assert_eq!(transcript.counts[3].status.tallies.candidate[3],36);
+}
+
+#[test]
+fn test_surplus_not_deferred_until_exclusion_finished() {
+    let transcript = make_data().distribute_preferences::<MidExclusionRules<DoNotDefer>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.counts[2].elected.iter().map(|e|e.who).collect::<Vec<_>>(),vec![CandidateIndex(1)]);
+    assert_eq!(reasons(&transcript),vec![first(),surplus(0),exclude_e(),surplus(1),exclude_e()]);
+    // B's surplus of 8 at transfer value 8/62 gives C 5 from the 42 papers B,C; the 20 papers from E exhaust.
+    assert_eq!(transcript.counts[3].status.tallies.candidate[2],35);
+    assert_eq!(transcript.counts[4].status.tallies.candidate[3],36);
+}
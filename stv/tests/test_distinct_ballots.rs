@@ -0,0 +1,71 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test ElectionData::distinct_ballots
+
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, ElectionName, NumberOfCandidates, Party, PartyIndex};
+use stv::ballot_paper::{ATL, BTL};
+use stv::election_data::ElectionData;
+
+fn candidate(name:&str,party:Option<usize>,position:Option<usize>) -> Candidate {
+    Candidate{ name: name.to_string(), party: party.map(PartyIndex), position, ec_id: None }
+}
+
+/// Party A (candidates 0,1) with two tickets, party B (candidates 2,3) without tickets, and ungrouped candidate 4.
+fn election() -> ElectionData {
+    let c = |v:&[usize]| v.iter().map(|&i|CandidateIndex(i)).collect::<Vec<_>>();
+    ElectionData {
+        metadata: ElectionMetadata {
+            name: ElectionName{ year: "2024".to_string(), authority: "Test".to_string(), name: "Test".to_string(), electorate: "Test".to_string(), ..Default::default() },
+            candidates: vec![candidate("A1",Some(0),Some(1)),candidate("A2",Some(0),Some(2)),candidate("B1",Some(1),Some(1)),candidate("B2",Some(1),Some(2)),candidate("U",None,None)],
+            parties: vec![
+                Party{ column_id: "A".to_string(), name: "Party A".to_string(), abbreviation: None, atl_allowed: true, candidates: c(&[0,1]), tickets: vec![c(&[0,1,2,3,4]),c(&[1,0,4,3,2])], official_atl_votes: None },
+                Party{ column_id: "B".to_string(), name: "Party B".to_string(), abbreviation: None, atl_allowed: true, candidates: c(&[2,3]), tickets: vec![], official_atl_votes: None },
+            ],
+            vacancies: Some(NumberOfCandidates(2)),
+            ..Default::default()
+        },
+        atl: vec![
+            ATL{ parties: vec![PartyIndex(0)], n: 10, ticket_index: Some(0) },
+            ATL{ parties: vec![PartyIndex(0)], n: 6, ticket_index: Some(1) },
+            ATL{ parties: vec![PartyIndex(1),PartyIndex(0)], n: 5, ticket_index: None },
+            ATL{ parties: vec![PartyIndex(1)], n: 2, ticket_index: None },
+        ],
+        btl: vec![
+            BTL{ candidates: c(&[0,1,2,3,4]), n: 3 }, // same as ticket 0 of party A.
+            BTL{ candidates: c(&[2,3,0,1]), n: 4 }, // same as ATL B then A.
+            BTL{ candidates: c(&[4]), n: 1 },
+            BTL{ candidates: c(&[2,3]), n: 2 }, // same as ATL B only
+        ],
+        informal: 7,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_distinct_ballots() {
+    let data = election();
+    let distinct = data.distinct_ballots();
+    let simple : Vec<(Vec<usize>,usize)> = distinct.iter().map(|(prefs,n)|(prefs.iter().map(|c|c.0).collect(),*n)).collect();
+    assert_eq!(simple,vec![
+        (vec![0,1,2,3,4],13),
+        (vec![2,3,0,1],9),
+        (vec![1,0,4,3,2],6),
+        (vec![2,3],4),
+        (vec![4],1),
+    ]);
+    let total : usize = distinct.iter().map(|(_,n)|*n).sum();
+    assert_eq!(total,data.num_votes());
+    assert_eq!(total,33);
+}
+
+#[test]
+fn test_distinct_ballots_empty() {
+    let mut data = election();
+    data.atl.clear();
+    data.btl.clear();
+    assert!(data.distinct_ballots().is_empty());
+}
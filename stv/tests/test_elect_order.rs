@@ -0,0 +1,52 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the order in which candidates reaching quota in the same count are elected, and their surpluses distributed.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::ReasonForCount;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Simple integer rules, optionally electing the lowest candidate first.
+type SimpleRules<const HIGHEST_FIRST:bool> = SimpleRulesWith<ElectOrder<HIGHEST_FIRST>>;
+
+struct ElectOrder<const HIGHEST_FIRST:bool> {}
+
+impl <const HIGHEST_FIRST:bool> Variation for ElectOrder<HIGHEST_FIRST> {
+    fn elect_highest_first() -> bool { HIGHEST_FIRST }
+    fn name() -> String { format!("SimpleRules{}",if HIGHEST_FIRST {""} else {"LowestFirst"}) }
+}
+
+#[test]
+fn test_elect_order_within_count() {
+    let vote_data = ElectionData {
+        metadata: metadata(&["A","B","C","D","E"],4),
+        btl: vec![
+            btl(&[0,3],40),
+            btl(&[1,3],35),
+            btl(&[2,3],30),
+            btl(&[3],10),
+            btl(&[4],5),
+        ],
+        ..Default::default()
+    };
+    // Quota is 120/5+1 = 25, so A, B and C are all elected on first preferences.
+    // Highest first : A's surplus of 15 is enough to elect D.
+    let transcript = vote_data.distribute_preferences::<SimpleRules<true>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(2),CandidateIndex(3)]);
+    assert_eq!(transcript.counts.len(),2);
+    assert!(matches!(transcript.counts[1].reason,ReasonForCount::ExcessDistribution(CandidateIndex(0))));
+    // Lowest first : C's surplus of 5 is not enough to elect D, B's surplus of 10 is then needed.
+    let transcript = vote_data.distribute_preferences::<SimpleRules<false>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(2),CandidateIndex(1),CandidateIndex(0),CandidateIndex(3)]);
+    assert_eq!(transcript.counts.len(),3);
+    assert!(matches!(transcript.counts[1].reason,ReasonForCount::ExcessDistribution(CandidateIndex(2))));
+    assert!(matches!(transcript.counts[2].reason,ReasonForCount::ExcessDistribution(CandidateIndex(1))));
+}
@@ -0,0 +1,67 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the exhausted and rounding values after each count, and that the amounts in each count add up to the final totals.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Five candidates for two vacancies, with E excluded before the count. A gets a quota on first preferences, with the surplus going to B.
+/// D is then excluded, electing C, and B is left continuing.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0,1],35),
+            btl(&[0],10),
+            btl(&[1],20),
+            btl(&[2],26),
+            btl(&[3],4),
+            btl(&[3,2],9),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_exhaustion_curve() {
+    let data = make_data();
+    let transcript = data.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    // 104 votes, quota 35. A's surplus of 10 at transfer value 10/45 gives B 7 (from 7.78), exhausts 2 (from 2.22), and loses 1 to rounding.
+    // Excluding D exhausts 4 more, and gives C 9, electing C.
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(2)]);
+    assert_eq!(transcript.exhaustion_curve(),vec![(CountIndex(0),0),(CountIndex(1),2),(CountIndex(2),6)]);
+    // rounding is not exhaustion.
+    assert_eq!(transcript.rounding_curve().iter().map(|(i,r)|(*i,r.assume_positive())).collect::<Vec<_>>(),vec![(CountIndex(0),0),(CountIndex(1),1),(CountIndex(2),1)]);
+    assert_eq!(transcript.total_formal_value(),104.0);
+    assert_eq!(transcript.exhaustion_curve_fraction(),vec![(CountIndex(0),0.0),(CountIndex(1),2.0/104.0),(CountIndex(2),6.0/104.0)]);
+    let summary = transcript.exhaustion_summary();
+    assert_eq!(summary.total_formal,104.0);
+    assert_eq!(summary.exhausted,transcript.exhaustion_curve());
+    assert_eq!(summary.exhausted_fraction,transcript.exhaustion_curve_fraction());
+    assert_eq!(summary.rounding,transcript.rounding_curve());
+}
+
+#[test]
+fn test_exhaustion_breakdown_reconciles() {
+    let data = make_data();
+    let transcript = data.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    let breakdown = transcript.exhaustion_breakdown();
+    assert_eq!(breakdown.counts.iter().map(|c|c.exhausted_this_count.assume_positive()).collect::<Vec<_>>(),vec![0,2,4]);
+    assert_eq!(breakdown.counts.iter().map(|c|c.rounding_this_count.assume_positive()).collect::<Vec<_>>(),vec![0,1,0]);
+    assert_eq!(breakdown.counts.iter().map(|c|(c.count,c.exhausted)).collect::<Vec<_>>(),transcript.exhaustion_curve());
+    assert_eq!(breakdown.counts.iter().map(|c|(c.count,c.rounding.clone())).collect::<Vec<_>>(),transcript.rounding_curve());
+    // The totals reconcile with the final transcript's exhausted and rounding figures.
+    let last = &transcript.counts.last().unwrap().status.tallies;
+    assert_eq!(breakdown.total_exhausted.assume_positive(),last.exhausted);
+    assert_eq!(breakdown.total_rounding,last.rounding);
+    assert_eq!(breakdown.total_exhausted.assume_positive()+breakdown.total_rounding.assume_positive(),7);
+}
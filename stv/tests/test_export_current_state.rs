@@ -0,0 +1,82 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that the state of a count part way through can be exported as election data, and counting that gives the same result.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use stv::ballot_paper::ATL;
+use stv::ballot_metadata::{Party, PartyIndex};
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::election_data::ElectionData;
+use stv::extract_votes_in_pile::{ExtractionRequest, WhatToDoWithExtractedVotes, WhatToExtract};
+use stv::preference_distribution::{distribute_preferences_with_extractors, distribute_preferences_with_options, CountOptions};
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, SimpleRules};
+
+/// Five candidates for two vacancies, with E excluded before the count. A gets a quota on first preferences, with the surplus going to B.
+/// D is then excluded, electing C, and B is left continuing.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: ElectionMetadata {
+            candidates: vec![
+                Candidate{ name: "A".to_string(), party: Some(PartyIndex(0)), position: Some(1), ec_id: None },
+                Candidate{ name: "B".to_string(), party: Some(PartyIndex(0)), position: Some(2), ec_id: None },
+                Candidate::from_name("C"),
+                Candidate::from_name("D"),
+                Candidate::from_name("E"),
+                Candidate::from_name("F"),
+            ],
+            parties: vec![Party{ column_id: "A".to_string(), name: "AB".to_string(), abbreviation: None, atl_allowed: true, candidates: vec![CandidateIndex(0),CandidateIndex(1)], tickets: vec![], official_atl_votes: None }],
+            vacancies: Some(NumberOfCandidates(3)),
+            ..Default::default()
+        },
+        atl: vec![ATL{ parties: vec![PartyIndex(0)], n: 30, ticket_index: None }],
+        btl: vec![
+            btl(&[0,2,3],20),
+            btl(&[2,4],22),
+            btl(&[3,5,4],18),
+            btl(&[4,3],17),
+            btl(&[5,2],13),
+        ],
+        ..Default::default()
+    }
+}
+
+/// Count the data, taking a snapshot at the end of the given count, and check that counting the snapshot with the same quota gives the same result.
+fn check_snapshot(count:CountIndex) {
+    let data = make_data();
+    let snapshots : Arc<Mutex<Vec<ElectionData>>> = Arc::new(Mutex::new(vec![]));
+    let snapshots_for_closure = snapshots.clone();
+    let extractors = vec![ExtractionRequest{ what_to_extract: WhatToExtract::StateAtEndOfCount(count), what_to_do_with_it: WhatToDoWithExtractedVotes::CallFunction(Arc::new(Mutex::new(move|d|snapshots_for_closure.lock().unwrap().push(d)))) }];
+    let transcript = distribute_preferences_with_extractors::<SimpleRules>(&data,NumberOfCandidates(3),&HashSet::new(),&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote,&extractors,false);
+    let snapshot = snapshots.lock().unwrap().pop().expect("No snapshot taken");
+    assert_eq!(snapshot.metadata.vacancies.unwrap().0+snapshot.metadata.excluded.iter().filter(|c|transcript.elected.contains(c)).count(),3);
+    let quota = transcript.quota.as_ref().unwrap().quota;
+    let resumed = distribute_preferences_with_options::<SimpleRules>(&snapshot,snapshot.metadata.vacancies.unwrap(),&snapshot.metadata.excluded.iter().cloned().collect(),&snapshot.metadata.tie_resolutions,&mut Randomness::ReverseDonkeyVote,CountOptions{ fixed_quota: Some(quota), ..Default::default() });
+    assert_eq!(resumed.quota.as_ref().unwrap().quota,quota);
+    let already_elected : Vec<CandidateIndex> = transcript.counts[..=count.0].iter().flat_map(|c|c.elected.iter().map(|e|e.who)).collect();
+    let mut elected : Vec<CandidateIndex> = already_elected.iter().filter(|c|snapshot.metadata.excluded.contains(c)).cloned().chain(resumed.elected.iter().cloned()).collect();
+    elected.sort_by_key(|c|c.0);
+    let mut expected = transcript.elected.clone();
+    expected.sort_by_key(|c|c.0);
+    assert_eq!(elected,expected);
+}
+
+// Quota 31. A is elected on first preferences with a surplus of 19, transfer value 19/50, mostly to B via the ATL votes.
+// B is then excluded, and C elected on B's and F's papers, then D after E is excluded.
+
+#[test]
+fn test_snapshot_with_surplus_pending() { check_snapshot(CountIndex(0)); }
+
+#[test]
+fn test_snapshot_after_surplus() { check_snapshot(CountIndex(1)); }
+
+#[test]
+fn test_snapshot_after_exclusion() { check_snapshot(CountIndex(2)); }
@@ -0,0 +1,55 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that files in older layouts are upgraded when read, and that the format version is written.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::TranscriptWithMetadata;
+use stv::election_data::ElectionData;
+use stv::file_format::{load_str_with_migration, load_with_migration, save_with_version, to_versioned_value, CURRENT_FORMAT_VERSION, FORMAT_VERSION_FIELD};
+use stv::random_util::Randomness;
+use stv::tie_resolution::TieResolutionAtom;
+
+mod common;
+use common::SimpleRules;
+
+const V1_FIXTURE : &str = include_str!("v1_format.stv");
+
+#[test]
+fn test_v1_fixture_upgrades_and_counts_identically() {
+    let upgraded : ElectionData = load_str_with_migration(V1_FIXTURE).unwrap();
+    match &upgraded.metadata.tie_resolutions.tie_resolutions[..] {
+        [TieResolutionAtom::ExplicitDecision(decision)] => assert_eq!(decision.decision.increasing_favour,vec![vec![CandidateIndex(2)],vec![CandidateIndex(1)]]),
+        other => panic!("Unexpected tie resolutions {:?}",other),
+    }
+    let transcript = upgraded.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(1)]);
+    // the same as reading it directly, relying on serde's backwards compatibility.
+    let direct : ElectionData = serde_json::from_str(V1_FIXTURE).unwrap();
+    let direct_transcript = direct.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(serde_json::to_value(&transcript).unwrap(),serde_json::to_value(&direct_transcript).unwrap());
+    // saving writes the current version, which reads back the same.
+    let mut saved = vec![];
+    save_with_version(&mut saved,&upgraded).unwrap();
+    let saved_json : serde_json::Value = serde_json::from_slice(&saved).unwrap();
+    assert_eq!(saved_json[FORMAT_VERSION_FIELD],CURRENT_FORMAT_VERSION);
+    let reloaded : ElectionData = load_with_migration(&saved[..]).unwrap();
+    let reloaded_transcript = reloaded.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(serde_json::to_value(&transcript).unwrap(),serde_json::to_value(&reloaded_transcript).unwrap());
+    // transcripts are versioned too.
+    let with_metadata = TranscriptWithMetadata{ metadata: reloaded.metadata, transcript: reloaded_transcript };
+    let json = to_versioned_value(&with_metadata).unwrap().to_string();
+    let reloaded_with_metadata : TranscriptWithMetadata<usize> = load_str_with_migration(&json).unwrap();
+    assert_eq!(reloaded_with_metadata.transcript.elected,vec![CandidateIndex(1)]);
+}
+
+#[test]
+fn test_future_version_is_rejected() {
+    let mut json : serde_json::Value = serde_json::from_str(V1_FIXTURE).unwrap();
+    json[FORMAT_VERSION_FIELD]=serde_json::Value::from(CURRENT_FORMAT_VERSION+1);
+    let err = load_str_with_migration::<ElectionData>(&json.to_string()).unwrap_err();
+    assert!(err.to_string().contains("newer version of ConcreteSTV"),"{}",err);
+}
@@ -0,0 +1,56 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the analysis of the contest for the final seat.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::election_data::ElectionData;
+use stv::final_seat::analyze_final_seat;
+use stv::random_util::Randomness;
+use stv::signed_version::SignedVersion;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Four candidates for two vacancies. A is elected on first preferences, and D's votes go to C when D is excluded,
+/// leaving B and C to contest the final seat.
+fn make_data(b:usize,c:usize,d:usize) -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0],40),
+            btl(&[1],b),
+            btl(&[2],c),
+            btl(&[3,2],d),
+        ],
+        ..Default::default()
+    }
+}
+#[test]
+fn test_close_final_seat() {
+    // quota 31. After D is excluded, C has 26 and B 25.
+    let analysis = analyze_final_seat::<SimpleRules>(&make_data(25,22,4),&mut Randomness::ReverseDonkeyVote).unwrap();
+    assert_eq!(analysis.prior_winners,vec![CandidateIndex(0)]);
+    assert_eq!(analysis.winner,CandidateIndex(2));
+    assert_eq!(analysis.runner_up,Some(CandidateIndex(1)));
+    assert_eq!(analysis.margin,Some(SignedVersion{negative:false,value:1}));
+    // count 1 first preferences, count 2 A's surplus, count 3 D excluded, count 4 B excluded.
+    assert_eq!(analysis.margin_count,Some(CountIndex(2)));
+    assert_eq!(analysis.elected_in_count,CountIndex(3));
+    assert!(analysis.tie_decisions.is_empty());
+}
+
+#[test]
+fn test_tied_final_seat() {
+    // B and C tied on 25 after D is excluded, so a tie resolution decides who is excluded.
+    let analysis = analyze_final_seat::<SimpleRules>(&make_data(25,22,3),&mut Randomness::ReverseDonkeyVote).unwrap();
+    assert_eq!(analysis.margin,Some(SignedVersion{negative:false,value:0}));
+    assert_eq!(analysis.tie_decisions.len(),1);
+    assert_eq!(analysis.tie_decisions[0].0,CountIndex(3));
+    let runner_up = analysis.runner_up.unwrap();
+    assert_eq!(analysis.tie_decisions[0].1.increasing_favour,vec![vec![runner_up],vec![analysis.winner]]);
+}
@@ -0,0 +1,61 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that only keeping the full status of the last count gives the same result with fewer allocations.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use stv::ballot_metadata::CandidateIndex;
+use stv::preference_distribution::{distribute_preferences, distribute_preferences_final_status_only};
+use stv::random_util::Randomness;
+use stv::synthetic::{generate, SyntheticElectionParams};
+
+mod common;
+use common::SimpleRules;
+
+/// Count allocations, so the benefit can be measured. There is only one test in this file, so no other threads are allocating.
+struct CountingAllocator {}
+
+static ALLOCATIONS : AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1,Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) { System.dealloc(ptr,layout) }
+}
+
+#[global_allocator]
+static GLOBAL : CountingAllocator = CountingAllocator{};
+
+#[test]
+fn test_final_status_only_same_result_fewer_allocations() {
+    let params = SyntheticElectionParams{ parties: 10, candidates_per_party: 4, independents: 10, vacancies: 6, voters: 5000, ..Default::default() };
+    let data = generate(&params,3);
+    let excluded : HashSet<CandidateIndex> = HashSet::default();
+    let vacancies = data.metadata.vacancies.unwrap();
+    let before_full = ALLOCATIONS.load(Ordering::Relaxed);
+    let full = distribute_preferences::<SimpleRules>(&data,vacancies,&excluded,&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+    let allocations_full = ALLOCATIONS.load(Ordering::Relaxed)-before_full;
+    let before_final = ALLOCATIONS.load(Ordering::Relaxed);
+    let final_only = distribute_preferences_final_status_only::<SimpleRules>(&data,vacancies,&excluded,&data.metadata.tie_resolutions,None,&mut Randomness::ReverseDonkeyVote);
+    let allocations_final = ALLOCATIONS.load(Ordering::Relaxed)-before_final;
+    println!("{} counts, allocations with full status {}, with final status only {}",full.counts.len(),allocations_full,allocations_final);
+    assert!(full.counts.len()>40);
+    assert!(allocations_final+full.counts.len()<allocations_full);
+    assert!(final_only.counts[0].status.papers.candidate.is_empty());
+    assert!(final_only.counts[0].status.atl_papers.is_none());
+    // Apart from the per candidate paper counts of earlier counts, the transcript is unchanged.
+    let mut expected = full.clone();
+    let last = expected.counts.len()-1;
+    for count in &mut expected.counts[..last] {
+        count.status.papers.candidate.clear();
+        count.status.atl_papers=None;
+    }
+    assert_eq!(serde_json::to_string(&expected).unwrap(),serde_json::to_string(&final_only).unwrap());
+}
@@ -0,0 +1,43 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the first preference count on its own agrees with the start of a full count.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::election_data::ElectionData;
+use stv::first_preferences::first_preferences;
+use stv::random_util::Randomness;
+use stv::tie_resolution::TieResolutionsMadeByEC;
+
+mod common;
+use common::{btl, metadata, SortedExclusionRules};
+
+/// A 30, B 10, C 9, D 20. Quota 69/3+1=24, so only A is over quota.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0,2],30),
+            btl(&[1],10),
+            btl(&[2],9),
+            btl(&[3],20),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_first_preferences_match_full_count() {
+    let data = make_data();
+    let first = first_preferences::<SortedExclusionRules>(&data,NumberOfCandidates(2),&HashSet::new(),&TieResolutionsMadeByEC::default(),None,&mut Randomness::ReverseDonkeyVote);
+    let transcript = data.distribute_preferences::<SortedExclusionRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(first.quota.as_ref().unwrap().quota,transcript.quota.as_ref().unwrap().quota);
+    assert_eq!(first.quota.as_ref().unwrap().quota,24);
+    assert_eq!(first.tallies,transcript.counts[0].status.tallies);
+    assert_eq!(first.tallies.candidate,vec![30,10,9,20]);
+    assert_eq!(first.over_quota,vec![CandidateIndex(0)]);
+}
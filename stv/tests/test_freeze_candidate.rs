@@ -0,0 +1,54 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test the hypothetical freezing of a candidate partway through a count.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{distribute_preferences_with_options, CountOptions};
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Candidates A, B, F, C for one vacancy. C's votes go to F then B, and F's go to A.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","F","C"],1),
+        btl: vec![
+            btl(&[0],30),
+            btl(&[1],25),
+            btl(&[2,0],20),
+            btl(&[3,2,1],15),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_frozen_candidate_receives_no_transfers() {
+    let data = make_data();
+    let vacancies = data.metadata.vacancies.unwrap();
+    let count = |freeze:&[(CandidateIndex,CountIndex)]| distribute_preferences_with_options::<SimpleRules>(&data,vacancies,&HashSet::new(),&data.metadata.tie_resolutions,&mut Randomness::ReverseDonkeyVote,CountOptions{ freeze_candidate_after: freeze, ..Default::default() });
+    // Normally C's votes go to F, who then beats A after B is excluded.
+    let normal = count(&[]);
+    assert_eq!(35,normal.counts[1].status.tallies.candidate[2]);
+    assert_eq!(vec![CandidateIndex(2)],normal.elected);
+    // Frozen after the first count, F gets no more votes, so C's votes go past F to B. F is then excluded, and their votes elect A.
+    let frozen = count(&[(CandidateIndex(2),CountIndex(0))]);
+    assert_eq!(20,frozen.counts[0].status.tallies.candidate[2]);
+    for single in &frozen.counts[1..] {
+        assert!(single.status.tallies.candidate[2]<=20);
+    }
+    assert_eq!(40,frozen.counts[1].status.tallies.candidate[1]);
+    assert_eq!(vec![CandidateIndex(0)],frozen.elected);
+    // Frozen from a later count, it makes no difference.
+    let frozen_later = count(&[(CandidateIndex(2),CountIndex(5))]);
+    assert_eq!(normal.elected,frozen_later.elected);
+    assert_eq!(35,frozen_later.counts[1].status.tallies.candidate[2]);
+}
@@ -0,0 +1,84 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test the different treatments of ballots with gaps in their numbering.
+
+use stv::ballot_metadata::{CandidateIndex, PartyIndex};
+use stv::ballot_paper::{FormalVote, GapPolicy, parse_marking, RawBallotMarking, RawBallotMarkings};
+
+fn markings(numbers:&[&str]) -> Vec<RawBallotMarking> { numbers.iter().map(|m|parse_marking(m)).collect() }
+
+/// Interpret BTL markings with the given policy, returning candidate numbers.
+fn btl(numbers:&[&str],gap_policy:GapPolicy) -> Option<Vec<usize>> {
+    let btl = markings(numbers);
+    let raw = RawBallotMarkings{ atl: &[], btl: &btl, atl_parties: &[] };
+    raw.interpret_vote_as_btl_with_gap_policy(1,gap_policy).map(|v|v.candidates.iter().map(|c|c.0).collect())
+}
+
+const WITH_GAP : [&str;6] = ["2","1","","5","4",""]; // 1,2,4,5 with no 3.
+const WITH_REPEAT : [&str;6] = ["2","1","3","3","4",""]; // 1,2,3,3,4.
+const WITH_REPEAT_THEN_GAP : [&str;6] = ["2","1","2","","5",""]; // 1,2,2,5.
+const WITH_GAP_THEN_REPEAT : [&str;6] = ["2","1","4","4","",""]; // 1,2,4,4.
+
+#[test]
+fn test_truncate_at_gap() {
+    assert_eq!(btl(&WITH_GAP,GapPolicy::TruncateAtGap),Some(vec![1,0]));
+    assert_eq!(btl(&WITH_REPEAT,GapPolicy::TruncateAtGap),Some(vec![1,0]));
+    assert_eq!(btl(&WITH_REPEAT_THEN_GAP,GapPolicy::TruncateAtGap),Some(vec![1]));
+    assert_eq!(btl(&WITH_GAP_THEN_REPEAT,GapPolicy::TruncateAtGap),Some(vec![1,0]));
+    assert_eq!(btl(&["","3","2"],GapPolicy::TruncateAtGap),None); // no first preference
+    // the default is the same as the existing behaviour.
+    assert_eq!(GapPolicy::default(),GapPolicy::TruncateAtGap);
+    let raw = markings(&WITH_GAP);
+    assert_eq!((RawBallotMarkings{ atl: &[], btl: &raw, atl_parties: &[] }).interpret_vote_as_btl(1).unwrap().candidates,vec![CandidateIndex(1),CandidateIndex(0)]);
+}
+
+#[test]
+fn test_informal() {
+    assert_eq!(btl(&WITH_GAP,GapPolicy::Informal),None);
+    // a repeated number is not a gap, so is still truncated.
+    assert_eq!(btl(&WITH_REPEAT,GapPolicy::Informal),Some(vec![1,0]));
+    // the gap after the repeat is never reached.
+    assert_eq!(btl(&WITH_REPEAT_THEN_GAP,GapPolicy::Informal),Some(vec![1]));
+    // the gap is reached before the repeat, although nothing is validly numbered after the gap.
+    assert_eq!(btl(&WITH_GAP_THEN_REPEAT,GapPolicy::Informal),Some(vec![1,0]));
+    // stopping numbering is not a gap.
+    assert_eq!(btl(&["2","1","",""],GapPolicy::Informal),Some(vec![1,0]));
+}
+
+#[test]
+fn test_ignore_gap() {
+    assert_eq!(btl(&WITH_GAP,GapPolicy::IgnoreGap),Some(vec![1,0,4,3]));
+    assert_eq!(btl(&WITH_REPEAT,GapPolicy::IgnoreGap),Some(vec![1,0]));
+    assert_eq!(btl(&WITH_REPEAT_THEN_GAP,GapPolicy::IgnoreGap),Some(vec![1]));
+    assert_eq!(btl(&WITH_GAP_THEN_REPEAT,GapPolicy::IgnoreGap),Some(vec![1,0]));
+    assert_eq!(btl(&["","3","2"],GapPolicy::IgnoreGap),Some(vec![2,1]));
+}
+
+/// An informal BTL vote can still fall back on the ATL vote.
+#[test]
+fn test_informal_btl_falls_back_to_atl() {
+    let atl_parties = vec![PartyIndex(0),PartyIndex(1)];
+    let mut all = markings(&["1","2"]);
+    all.extend(markings(&WITH_GAP));
+    let raw = RawBallotMarkings::new(&atl_parties,&all);
+    match raw.interpret_vote_with_gap_policy(1,1,GapPolicy::Informal) {
+        Some(FormalVote::Atl(atl)) => assert_eq!(atl.parties,vec![PartyIndex(0),PartyIndex(1)]),
+        other => panic!("Expected ATL vote, got {:?}",other),
+    }
+    match raw.interpret_vote_with_gap_policy(1,1,GapPolicy::TruncateAtGap) {
+        Some(FormalVote::Btl(btl)) => assert_eq!(btl.candidates,vec![CandidateIndex(1),CandidateIndex(0)]),
+        other => panic!("Expected BTL vote, got {:?}",other),
+    }
+}
+
+#[test]
+fn test_parse_gap_policy() {
+    for policy in [GapPolicy::TruncateAtGap,GapPolicy::Informal,GapPolicy::IgnoreGap] {
+        assert_eq!(policy.to_string().parse::<GapPolicy>(),Ok(policy));
+    }
+    assert!("Sometimes".parse::<GapPolicy>().is_err());
+}
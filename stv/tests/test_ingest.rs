@@ -0,0 +1,66 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the conversion of approval and cumulative ballots to weighted preferential ballots.
+
+use stv::ballot_metadata::{Candidate, CandidateIndex};
+use stv::ingest::{approval_to_election_data, ApprovalBallot, cumulative_to_election_data, CumulativeBallot, IngestError};
+use stv::random_util::Randomness;
+use stv::transfer_value::TransferValue;
+
+mod common;
+use common::{metadata, SimpleRules};
+
+#[test]
+fn test_approval() {
+    let ballots = vec![
+        ApprovalBallot{ approved: vec![CandidateIndex(1),CandidateIndex(0)], n: 6 },
+        ApprovalBallot{ approved: vec![CandidateIndex(2)], n: 4 },
+        ApprovalBallot{ approved: vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(3)], n: 12 },
+        ApprovalBallot{ approved: vec![], n: 2 },
+    ];
+    let data = approval_to_election_data(metadata(&["A","B","C","D"],1),&ballots).unwrap();
+    assert_eq!(data.informal,2);
+    assert_eq!(data.btl.len(),1+2+6);
+    // highest weight first.
+    assert_eq!(data.btl_transfer_values.iter().map(|v|(v.value.clone(),v.first_index_inclusive,v.last_index_exclusive)).collect::<Vec<_>>(),vec![
+        (TransferValue::one(),0,1),
+        ("1/2".parse().unwrap(),1,3),
+        ("1/6".parse().unwrap(),3,9),
+    ]);
+    assert!(data.validate().is_ok());
+    // A gets 3+4, B 3+4, C 4 and D 4 on first preferences, which are counted in one step per weight.
+    let transcript = data.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.counts[2].status.tallies.candidate,vec![7,7,4,4]);
+}
+
+#[test]
+fn test_cumulative() {
+    let ballots = vec![
+        CumulativeBallot{ points: vec![(CandidateIndex(1),1),(CandidateIndex(0),2)], n: 3 },
+        CumulativeBallot{ points: vec![(CandidateIndex(2),3),(CandidateIndex(3),0)], n: 5 },
+        CumulativeBallot{ points: vec![(CandidateIndex(3),0)], n: 1 },
+    ];
+    let data = cumulative_to_election_data(metadata(&["A","B","C","D"],1),&ballots).unwrap();
+    assert_eq!(data.informal,1);
+    // A first then B, with weight 2/3 ; B first then A with weight 1/3 ; C alone with weight 1. D got no points so is not ranked.
+    assert_eq!(data.btl.iter().map(|v|(v.candidates.clone(),v.n)).collect::<Vec<_>>(),vec![
+        (vec![CandidateIndex(2)],5),
+        (vec![CandidateIndex(0),CandidateIndex(1)],3),
+        (vec![CandidateIndex(1),CandidateIndex(0)],3),
+    ]);
+    let transcript = data.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.counts[2].status.tallies.candidate,vec![2,1,5,0]);
+}
+
+#[test]
+fn test_ingest_errors() {
+    assert_eq!(approval_to_election_data(metadata(&["A","B","C","D"],1),&[ApprovalBallot{ approved: vec![CandidateIndex(4)], n: 1 }]).unwrap_err(),IngestError::CandidateOutOfRange {ballot:0,candidate:CandidateIndex(4)});
+    assert_eq!(cumulative_to_election_data(metadata(&["A","B","C","D"],1),&[CumulativeBallot{ points: vec![(CandidateIndex(1),1),(CandidateIndex(1),2)], n: 1 }]).unwrap_err(),IngestError::DuplicateCandidate {ballot:0,candidate:CandidateIndex(1)});
+    let mut many = metadata(&["A","B","C","D"],1);
+    many.candidates = (0..10).map(|i|Candidate::from_name(&i.to_string())).collect();
+    assert_eq!(approval_to_election_data(many,&[ApprovalBallot{ approved: (0..9).map(CandidateIndex).collect(), n: 1 }]).unwrap_err(),IngestError::TooManyApprovals {ballot:0,approved:9});
+}
@@ -0,0 +1,69 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check deferral of the last two standing shortcut while there is an undistributed surplus.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{DeferLastTwoStandingShortcut, WhenToDoElectCandidateClauseChecking};
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Simple integer rules that check for the last two standing shortcut at every opportunity, deferring it as specified by DEFER (0=Never, 1=IfTiedAndSurplusDistributable, 2=IfSurplusDistributable).
+type SimpleRules<const DEFER:u8> = SimpleRulesWith<LastTwoStanding<DEFER>>;
+
+struct LastTwoStanding<const DEFER:u8> {}
+
+impl <const DEFER:u8> Variation for LastTwoStanding<DEFER> {
+    fn when_to_check_if_just_two_standing_for_shortcut_election() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::AfterCheckingQuota }
+    fn defer_last_two_standing_shortcut() -> DeferLastTwoStandingShortcut {
+        match DEFER {
+            0 => DeferLastTwoStandingShortcut::Never,
+            1 => DeferLastTwoStandingShortcut::IfTiedAndSurplusDistributable,
+            _ => DeferLastTwoStandingShortcut::IfSurplusDistributable,
+        }
+    }
+    fn name() -> String { format!("SimpleRulesDefer{}",DEFER) }
+}
+
+/// Three candidates for 2 vacancies. A has a surplus which all goes to B.
+fn data(b_votes:usize,c_votes:usize) -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C"],2),
+        btl: vec![
+            btl(&[0,1],30),
+            btl(&[1],b_votes),
+            btl(&[2],c_votes),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_defer_last_two_standing_when_tied() {
+    // B and C are tied on 20. Quota is 70/3+1=24, A has a surplus of 6.
+    let vote_data = data(20,20);
+    let transcript = vote_data.distribute_preferences::<SimpleRules<0>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(2)]); // tie resolved without looking at the surplus.
+    assert_eq!(transcript.counts.len(),1);
+    let transcript = vote_data.distribute_preferences::<SimpleRules<1>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]); // A's surplus breaks the tie.
+    assert_eq!(transcript.counts.len(),2);
+}
+
+#[test]
+fn test_defer_last_two_standing_when_not_tied() {
+    // C is just ahead of B. Quota is 71/3+1=24, A has a surplus of 6.
+    let vote_data = data(20,21);
+    let transcript = vote_data.distribute_preferences::<SimpleRules<0>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(2)]);
+    let transcript = vote_data.distribute_preferences::<SimpleRules<1>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(2)]);
+    let transcript = vote_data.distribute_preferences::<SimpleRules<2>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+}
@@ -0,0 +1,82 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check merging electorates renumbers candidates, parties and ballots consistently.
+
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, ElectionName, NumberOfCandidates, Party, PartyIndex};
+use stv::ballot_paper::ATL;
+use stv::election_data::{ElectionData, VoteTypeSpecification};
+
+mod common;
+use common::btl;
+
+fn party(column_id:&str,candidates:&[usize]) -> Party {
+    Party{
+        column_id: column_id.to_string(),
+        name: column_id.to_string(),
+        abbreviation: None,
+        atl_allowed: true,
+        candidates: candidates.iter().map(|&c|CandidateIndex(c)).collect(),
+        tickets: vec![],
+        official_atl_votes: None,
+    }
+}
+
+/// An electorate with `num_candidates` candidates, all in party A, with one ATL vote and BTL votes for each candidate in order.
+fn make_data(electorate:&str,year:&str,num_candidates:usize) -> ElectionData {
+    ElectionData {
+        metadata: ElectionMetadata {
+            name: ElectionName {
+                year: year.to_string(),
+                authority: "EC".to_string(),
+                name: "State".to_string(),
+                electorate: electorate.to_string(),
+                ..Default::default()
+            },
+            candidates: (0..num_candidates).map(|i|Candidate{ party: Some(PartyIndex(0)), ..Candidate::from_name(&format!("{} {}",electorate,i)) }).collect(),
+            parties: vec![party("A",&(0..num_candidates).collect::<Vec<_>>())],
+            results: Some(vec![CandidateIndex(0)]),
+            vacancies: Some(NumberOfCandidates(1)),
+            ..Default::default()
+        },
+        atl: vec![ATL{ parties: vec![PartyIndex(0)], n: 10, ticket_index: None }],
+        btl: (0..num_candidates).map(|i|btl(&[i,(i+1)%num_candidates],i+1)).collect(),
+        btl_types: vec![
+            VoteTypeSpecification{ vote_type: "Postal".to_string(), first_index_inclusive: 0, last_index_exclusive: 1 },
+        ],
+        informal: 3,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_merge_two_electorates() {
+    let north = make_data("North","2024",2);
+    let south = make_data("South","2024",3);
+    let merged = ElectionData::merge_disjoint(&[north.clone(),south.clone()]).unwrap();
+    assert_eq!(merged.metadata.candidates.len(),5);
+    assert_eq!(merged.metadata.parties.len(),2);
+    assert_eq!(merged.num_votes(),north.num_votes()+south.num_votes());
+    assert_eq!(merged.informal,6);
+    assert_eq!(merged.metadata.vacancies,Some(NumberOfCandidates(2)));
+    assert_eq!(merged.metadata.name.electorate,"North+South");
+    assert!(merged.validate().is_ok());
+    // South's candidates and party are renumbered after North's.
+    assert_eq!(merged.metadata.parties[1].candidates,vec![CandidateIndex(2),CandidateIndex(3),CandidateIndex(4)]);
+    assert_eq!(merged.metadata.candidates[4].party,Some(PartyIndex(1)));
+    assert_eq!(merged.metadata.candidates[4].name,"South 2");
+    assert_eq!(merged.atl[1].parties,vec![PartyIndex(1)]);
+    assert_eq!(merged.btl[4].candidates,vec![CandidateIndex(4),CandidateIndex(2)]);
+    assert_eq!(merged.metadata.results,Some(vec![CandidateIndex(0),CandidateIndex(2)]));
+    assert_eq!(merged.btl_types.len(),2);
+    assert_eq!((merged.btl_types[1].first_index_inclusive,merged.btl_types[1].last_index_exclusive),(2,3));
+}
+
+#[test]
+fn test_merge_different_elections_fails() {
+    assert!(ElectionData::merge_disjoint(&[make_data("North","2024",2),make_data("South","2023",2)]).is_err());
+    assert!(ElectionData::merge_disjoint(&[]).is_err());
+}
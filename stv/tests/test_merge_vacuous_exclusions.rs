@@ -0,0 +1,70 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Merge consecutive exclusions of candidates with no votes in a transcript.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use stv::count_stepper::describe_count;
+use stv::distribution_of_preferences_transcript::{CountIndex, ReasonForCount};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::distribute_preferences;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, SortedExclusionRules};
+
+/// Two vacancies, quota 34. A is elected on first preferences, with a surplus of 6 going to B. E, F and G have no votes,
+/// so are excluded in three vacuous counts. Then B and D are excluded, electing C.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: ElectionMetadata {
+            candidates: ["A","B","C","D","E","F","G"].iter().map(|&name|Candidate::from_name(name)).collect(),
+            vacancies: Some(NumberOfCandidates(2)),
+            ..Default::default()
+        },
+        btl: vec![
+            btl(&[0,1],40),
+            btl(&[1],10),
+            btl(&[2],30),
+            btl(&[3,2],20),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_merge_vacuous_exclusions() {
+    let data = make_data();
+    let raw = distribute_preferences::<SortedExclusionRules>(&data,NumberOfCandidates(2),&HashSet::new(),&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+    let vacuous : Vec<usize> = (0..raw.counts.len()).filter(|&i|raw.is_vacuous_exclusion(CountIndex(i))).collect();
+    assert_eq!(vacuous,vec![2,3,4]);
+    let mut merged = raw.clone();
+    merged.merge_vacuous_exclusions();
+    assert_eq!(merged.counts.len(),raw.counts.len()-2);
+    assert_eq!(merged.elected,raw.elected);
+    let count = &merged.counts[2];
+    match &count.reason {
+        ReasonForCount::Elimination(excluded) => assert_eq!(excluded.iter().copied().collect::<HashSet<_>>(),[4,5,6].iter().map(|&c|CandidateIndex(c)).collect::<HashSet<_>>()),
+        _ => panic!("Expecting an exclusion, got {:?}",count.reason),
+    }
+    assert!(count.no_votes_to_transfer);
+    assert_eq!(count.count_name.as_deref(),Some("3-5"));
+    assert!(describe_count(&data.metadata,2,count).contains("with no votes to transfer"),"{}",describe_count(&data.metadata,2,count));
+    // Other counts keep their original numbers and contents.
+    for (merged_count,raw_index) in merged.counts.iter().zip([0,1,2,5,6,7,8,9,10].iter()).skip(3) {
+        let raw_count = &raw.counts[*raw_index];
+        assert_eq!(merged_count.count_name,Some((raw_index+1).to_string()));
+        assert!(!merged_count.no_votes_to_transfer);
+        assert_eq!(merged_count.status.tallies,raw_count.status.tallies);
+        assert_eq!(merged_count.portion.papers_came_from_counts,raw_count.portion.papers_came_from_counts); // all before the merged counts.
+    }
+    assert_eq!(merged.counts.last().unwrap().status.tallies,raw.counts.last().unwrap().status.tallies);
+    // Merging again does nothing more.
+    let mut again = merged.clone();
+    again.merge_vacuous_exclusions();
+    assert_eq!(again.counts.len(),merged.counts.len());
+}
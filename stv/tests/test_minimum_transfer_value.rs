@@ -0,0 +1,75 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that a surplus with a transfer value below the minimum is set aside rather than transferred.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::Transcript;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+use stv::transfer_value::TransferValue;
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Simple integer rules, with a minimum transfer value of MINIMUM_TV_PERCENT percent, or none if 0.
+type SimpleRules<const MINIMUM_TV_PERCENT:usize> = SimpleRulesWith<MinimumTransferValue<MINIMUM_TV_PERCENT>>;
+
+struct MinimumTransferValue<const MINIMUM_TV_PERCENT:usize> {}
+
+impl <const MINIMUM_TV_PERCENT:usize> Variation for MinimumTransferValue<MINIMUM_TV_PERCENT> {
+    fn minimum_transfer_value() -> Option<TransferValue> { if MINIMUM_TV_PERCENT==0 { None } else { Some(TransferValue::from_surplus(MINIMUM_TV_PERCENT,BallotPaperCount(100))) } }
+}
+
+/// Five candidates for two vacancies, with E excluded before the count. A gets a quota on first preferences, with the surplus going to B.
+/// D is then excluded, electing C, and B is left continuing.
+fn count<const MINIMUM_TV_PERCENT:usize>() -> Transcript<usize> {
+    let data = ElectionData {
+        metadata: metadata(&["A","B","C"],2),
+        btl: vec![
+            btl(&[0,1],40),
+            btl(&[1],20),
+            btl(&[2],25),
+        ],
+        ..Default::default()
+    };
+    data.distribute_preferences::<SimpleRules<MINIMUM_TV_PERCENT>>(&mut Randomness::ReverseDonkeyVote)
+}
+
+// 85 votes, quota 29. A's surplus of 11 has transfer value 11/40 = 0.275, which elects B. If it is set aside instead, C wins.
+
+#[test]
+fn test_no_minimum_transfer_value() {
+    let transcript = count::<0>();
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+    assert!(transcript.counts.iter().all(|c|c.status.tallies.set_aside.is_none()));
+}
+
+#[test]
+fn test_transfer_value_above_minimum() {
+    let transcript = count::<25>();
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+    assert_eq!(transcript.counts[1].status.tallies.candidate,vec![29,31,25]);
+    assert!(transcript.counts.iter().all(|c|c.status.tallies.set_aside.is_none()));
+}
+
+#[test]
+fn test_transfer_value_below_minimum() {
+    let transcript = count::<30>();
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(2)]);
+    let surplus = &transcript.counts[1].status;
+    assert_eq!(surplus.tallies.candidate,vec![29,20,25]);
+    assert_eq!(surplus.tallies.set_aside,Some(11));
+    assert_eq!(surplus.tallies.sum(),85);
+    assert_eq!(surplus.papers.candidate,vec![0,20,25].into_iter().map(BallotPaperCount).collect::<Vec<_>>());
+    assert_eq!(surplus.papers.set_aside,Some(BallotPaperCount(40)));
+    // B is then excluded, with transfer value 1, so those papers exhaust rather than being set aside.
+    let last = &transcript.counts.last().unwrap().status;
+    assert_eq!(last.tallies.exhausted,20);
+    assert_eq!(last.tallies.set_aside,Some(11));
+    assert_eq!(last.tallies.sum(),85);
+}
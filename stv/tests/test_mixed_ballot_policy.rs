@@ -0,0 +1,86 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test the different treatments of ballots marked both above and below the line.
+
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates, Party, PartyIndex};
+use stv::ballot_paper::{FormalVote, GapPolicy, MixedBallotPolicy, parse_marking, RawBallotMarking, RawBallotMarkings};
+
+/// Two parties, A and B in P, C and D in Q.
+fn metadata(q_ticket:Option<Vec<CandidateIndex>>) -> ElectionMetadata {
+    let party = |column_id:&str,candidates:Vec<CandidateIndex>,tickets:Vec<Vec<CandidateIndex>>| Party{ column_id: column_id.to_string(), name: column_id.to_string(), abbreviation: None, atl_allowed: true, candidates, tickets, official_atl_votes: None };
+    let candidate = |name:&str,party:usize,position:usize| Candidate{ name: name.to_string(), party: Some(PartyIndex(party)), position: Some(position), ec_id: None };
+    ElectionMetadata {
+        candidates: vec![candidate("A",0,1),candidate("B",0,2),candidate("C",1,1),candidate("D",1,2)],
+        parties: vec![
+            party("P",vec![CandidateIndex(0),CandidateIndex(1)],vec![]),
+            party("Q",vec![CandidateIndex(2),CandidateIndex(3)],q_ticket.into_iter().collect()),
+        ],
+        vacancies: Some(NumberOfCandidates(2)),
+        ..Default::default()
+    }
+}
+
+/// Interpret markings (ATL boxes for P and Q, then BTL boxes for A,B,C,D), needing 1 ATL preference or 4 BTL preferences.
+fn interpret(numbers:&[&str;6],mixed_policy:MixedBallotPolicy,metadata:&ElectionMetadata) -> Option<FormalVote> {
+    let atl_parties = vec![PartyIndex(0),PartyIndex(1)];
+    let all : Vec<RawBallotMarking> = numbers.iter().map(|m|parse_marking(m)).collect();
+    RawBallotMarkings::new(&atl_parties,&all).interpret_vote_with_mixed_policy(1,4,GapPolicy::TruncateAtGap,mixed_policy,metadata)
+}
+
+fn candidates(vote:Option<FormalVote>) -> Vec<usize> {
+    match vote {
+        Some(FormalVote::Btl(btl)) => btl.candidates.iter().map(|c|c.0).collect(),
+        other => panic!("Expected BTL vote, got {:?}",other),
+    }
+}
+
+fn parties(vote:Option<FormalVote>) -> Vec<usize> {
+    match vote {
+        Some(FormalVote::Atl(atl)) => atl.parties.iter().map(|p|p.0).collect(),
+        other => panic!("Expected ATL vote, got {:?}",other),
+    }
+}
+
+#[test]
+fn test_btl_prefix_overrides_atl() {
+    let metadata = metadata(None);
+    let ballot = ["2","1","2","","","1"]; // Q then P above the line, D then A below the line.
+    assert_eq!(candidates(interpret(&ballot,MixedBallotPolicy::BtlPrefixThenAtl,&metadata)),vec![3,0,2,1]);
+    // normally the BTL vote is informal (too few preferences) so the ATL vote is used.
+    assert_eq!(parties(interpret(&ballot,MixedBallotPolicy::BtlTakesPrecedence,&metadata)),vec![1,0]);
+    // a formal BTL vote is still used as a prefix, which changes nothing when it is complete.
+    let full = ["1","","4","3","2","1"];
+    assert_eq!(candidates(interpret(&full,MixedBallotPolicy::BtlPrefixThenAtl,&metadata)),vec![3,2,1,0]);
+    assert_eq!(candidates(interpret(&full,MixedBallotPolicy::BtlTakesPrecedence,&metadata)),vec![3,2,1,0]);
+}
+
+#[test]
+fn test_btl_prefix_then_ticket() {
+    let metadata = metadata(Some(vec![CandidateIndex(3),CandidateIndex(2),CandidateIndex(0),CandidateIndex(1)]));
+    assert_eq!(candidates(interpret(&["","1","1","","",""],MixedBallotPolicy::BtlPrefixThenAtl,&metadata)),vec![0,3,2,1]);
+}
+
+#[test]
+fn test_pure_atl_fallback() {
+    let metadata = metadata(None);
+    // nothing below the line, so it is just an ATL vote.
+    assert_eq!(parties(interpret(&["1","2","","","",""],MixedBallotPolicy::BtlPrefixThenAtl,&metadata)),vec![0,1]);
+    // nothing usable below the line (no first preference).
+    assert_eq!(parties(interpret(&["1","","","2","3",""],MixedBallotPolicy::BtlPrefixThenAtl,&metadata)),vec![0]);
+    // no formal ATL vote, so the BTL vote is used on its own if formal.
+    assert_eq!(candidates(interpret(&["","","4","3","2","1"],MixedBallotPolicy::BtlPrefixThenAtl,&metadata)),vec![3,2,1,0]);
+    assert!(interpret(&["","","","","2","1"],MixedBallotPolicy::BtlPrefixThenAtl,&metadata).is_none());
+}
+
+#[test]
+fn test_parse_mixed_ballot_policy() {
+    assert_eq!(MixedBallotPolicy::default(),MixedBallotPolicy::BtlTakesPrecedence);
+    for policy in [MixedBallotPolicy::BtlTakesPrecedence,MixedBallotPolicy::BtlPrefixThenAtl] {
+        assert_eq!(policy.to_string().parse::<MixedBallotPolicy>(),Ok(policy));
+    }
+    assert!("Sometimes".parse::<MixedBallotPolicy>().is_err());
+}
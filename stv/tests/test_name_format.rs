@@ -0,0 +1,53 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test formatting of candidate names.
+
+use stv::ballot_metadata::{Candidate, NameFormat};
+
+fn formats(name:&str) -> (String,String,String) {
+    let candidate = Candidate::from_name(name);
+    (candidate.display_name(NameFormat::AsStored),candidate.display_name(NameFormat::LastFirst),candidate.display_name(NameFormat::FirstLast))
+}
+
+fn check(name:&str,last_first:&str,first_last:&str) {
+    assert_eq!(formats(name),(name.to_string(),last_first.to_string(),first_last.to_string()),"for name {}",name);
+}
+
+#[test]
+fn test_surname_comma_given() {
+    check("SMITH, John","SMITH, John","John SMITH");
+    check("SMITH,  John  Paul ","SMITH, John  Paul","John  Paul SMITH");
+    check("SMITH-JONES, Mary Anne","SMITH-JONES, Mary Anne","Mary Anne SMITH-JONES");
+}
+
+#[test]
+fn test_given_then_surname() {
+    check("John Paul SMITH","SMITH, John Paul","John Paul SMITH");
+    check("Mary Anne SMITH-JONES","SMITH-JONES, Mary Anne","Mary Anne SMITH-JONES");
+    // several upper case words at the end are all taken as the surname.
+    check("Anna VAN DER BERG","VAN DER BERG, Anna","Anna VAN DER BERG");
+    // no upper case surname, so the last word is used.
+    check("John Smith","Smith, John","John Smith");
+    // all upper case, so the last word is used.
+    check("JOHN SMITH","SMITH, JOHN","JOHN SMITH");
+}
+
+#[test]
+fn test_single_token() {
+    check("Cher","Cher","Cher");
+    check("O'BRIEN","O'BRIEN","O'BRIEN");
+    check("SMITH,","SMITH,","SMITH,");
+    assert_eq!(Candidate::from_name("Cher").surname_and_given_names(),None);
+}
+
+#[test]
+fn test_parse_name_format() {
+    assert_eq!("LastFirst".parse::<NameFormat>(),Ok(NameFormat::LastFirst));
+    assert_eq!("first_last".parse::<NameFormat>(),Ok(NameFormat::FirstLast));
+    assert_eq!("AsStored".parse::<NameFormat>(),Ok(NameFormat::AsStored));
+    assert!("Surname".parse::<NameFormat>().is_err());
+}
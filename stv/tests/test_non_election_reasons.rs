@@ -0,0 +1,54 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the explanation of why candidates were not elected.
+
+use stv::ballot_metadata::{CandidateIndex, ElectionMetadata};
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::non_election_reasons::WhyNotElected;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Five candidates for two vacancies, with E excluded before the count. A gets a quota on first preferences, with the surplus going to B.
+/// D is then excluded, electing C, and B is left continuing.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: ElectionMetadata {
+            excluded: vec![CandidateIndex(4)],
+            ..metadata(&["A","B","C","D","E"],2)
+        },
+        btl: vec![
+            btl(&[0,1],40),
+            btl(&[1],20),
+            btl(&[2],25),
+            btl(&[3,2],10),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_non_election_reasons() {
+    let data = make_data();
+    let transcript = data.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    // 95 votes, quota 32, so A's surplus of 8 goes to B, giving 28.
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,32);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(2)]);
+    let reasons = transcript.non_election_reasons(&data.metadata);
+    assert_eq!(reasons.len(),3);
+    assert_eq!(reasons[0].candidate,CandidateIndex(1));
+    assert_eq!(reasons[0].why,WhyNotElected::ContinuingWhenAllSeatsFilled);
+    assert_eq!(reasons[0].final_tally,28);
+    assert_eq!(reasons[1].candidate,CandidateIndex(3));
+    assert_eq!(reasons[1].why,WhyNotElected::Excluded(CountIndex(2)));
+    assert_eq!(reasons[1].final_tally,10);
+    assert_eq!(reasons[2].candidate,CandidateIndex(4));
+    assert_eq!(reasons[2].why,WhyNotElected::ExcludedBeforeCount);
+    assert_eq!(reasons[2].final_tally,0);
+}
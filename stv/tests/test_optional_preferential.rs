@@ -0,0 +1,82 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check modelling optional preferential voting by truncating full preferential votes.
+
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, MissingVacancies, NumberOfCandidates, Party, PartyIndex};
+use stv::ballot_paper::ATL;
+use stv::compare_transcripts::{DifferenceBetweenTranscripts, DifferentCandidateLists};
+use stv::election_data::ElectionData;
+use stv::optional_preferential::{model_optional_preferential, TruncationModel};
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, SimpleRules};
+
+fn party(name:&str,candidates:&[usize],atl_allowed:bool) -> Party {
+    Party{ column_id: name.to_string(), name: name.to_string(), abbreviation: None, atl_allowed, candidates: candidates.iter().map(|&c|CandidateIndex(c)).collect(), tickets: vec![], official_atl_votes: None }
+}
+
+fn candidate(name:&str,party:usize) -> Candidate {
+    Candidate{ party: Some(PartyIndex(party)), ..Candidate::from_name(name) }
+}
+
+/// Red (R1,R2), Blue (B1,B2) and Green (G1) parties, and an ungrouped candidate U, for two vacancies with quota 32.
+/// R1 is elected on first preferences, and the surplus goes to R2. With full preferences R2's votes then elect G1;
+/// if voters stopped after their own party, they exhaust instead and B1 is elected.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: ElectionMetadata {
+            candidates: vec![candidate("R1",0),candidate("R2",0),candidate("B1",1),candidate("B2",1),candidate("G1",2),candidate("U",3)],
+            parties: vec![party("Red",&[0,1],true),party("Blue",&[2,3],true),party("Green",&[4],true),party("Ungrouped",&[5],false)],
+            vacancies: Some(NumberOfCandidates(2)),
+            ..Default::default()
+        },
+        btl: vec![
+            btl(&[0,1,4,2,3,5],40),
+            btl(&[2,3,0,1,4,5],30),
+            btl(&[4,2,3,0,1,5],25),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_truncation_after_own_party_changes_outcome() {
+    let data = make_data();
+    let model = model_optional_preferential::<SimpleRules>(&data,TruncationModel::AfterOwnParty,&mut Randomness::ReverseDonkeyVote).unwrap();
+    let truncated = &model.truncated;
+    assert_eq!(truncated.btl.iter().map(|v|v.candidates.clone()).collect::<Vec<_>>(),vec![vec![CandidateIndex(0),CandidateIndex(1)],vec![CandidateIndex(2),CandidateIndex(3)],vec![CandidateIndex(4)]]);
+    assert_eq!(truncated.btl.iter().map(|v|v.n).sum::<usize>(),95);
+    assert_eq!(model.difference,DifferenceBetweenTranscripts::DifferentCandidatesElected(DifferentCandidateLists{list1:vec![CandidateIndex(0),CandidateIndex(4)],list2:vec![CandidateIndex(0),CandidateIndex(2)]}));
+    assert_eq!(model.exhausted_full,0);
+    // R1's surplus of 8, passed on to R2, exhausts when R2 is excluded, as do G1's 25 votes when G1 is excluded.
+    assert_eq!(model.exhausted_optional,33);
+}
+
+#[test]
+fn test_missing_vacancies_is_an_error() {
+    let mut data = make_data();
+    data.metadata.vacancies=None;
+    assert_eq!(model_optional_preferential::<SimpleRules>(&data,TruncationModel::AfterOwnParty,&mut Randomness::ReverseDonkeyVote).err(),Some(MissingVacancies));
+}
+
+#[test]
+fn test_truncation_models() {
+    let mut data = make_data();
+    data.btl.push(btl(&[5,0,1],3));
+    data.atl.push(ATL{ parties: vec![PartyIndex(1),PartyIndex(0),PartyIndex(2)], n: 7, ticket_index: None });
+    let truncated = TruncationModel::AfterOwnParty.apply(&data);
+    // an ungrouped candidate is not a party that can be voted for above the line, so only the first preference is kept.
+    assert_eq!(truncated.btl[3].candidates,vec![CandidateIndex(5)]);
+    assert_eq!(truncated.atl[0].parties,vec![PartyIndex(1)]);
+    assert_eq!(truncated.atl[0].n,7);
+    let truncated = TruncationModel::AfterPreferences(2).apply(&data);
+    assert_eq!(truncated.btl[2].candidates,vec![CandidateIndex(4),CandidateIndex(2)]);
+    assert_eq!(truncated.atl[0].parties,vec![PartyIndex(1),PartyIndex(0)]);
+    let model = model_optional_preferential::<SimpleRules>(&make_data(),TruncationModel::AfterPreferences(6),&mut Randomness::ReverseDonkeyVote).unwrap();
+    assert_eq!(model.difference,DifferenceBetweenTranscripts::Same);
+}
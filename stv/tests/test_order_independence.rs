@@ -0,0 +1,57 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that the result of a count does not depend on the order of ballots, or on arbitrary HashMap iteration order.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::{CountIndex, ReasonForCount};
+use stv::election_data::ElectionData;
+use stv::order_independence::{check_order_independent, shuffle_ballots};
+use stv::preference_distribution::distribute_preferences;
+use stv::random_util::Randomness;
+use stv::tie_resolution::TieResolutionsMadeByEC;
+use stv::transfer_value::TransferValue;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Candidates A, B, X, Y, Z for three vacancies, quota 21. A's surplus of 9 goes to X at 3/10, and B's surplus of 3 goes to X at 1/8.
+/// X is then excluded, with two parcels with different transfer values but no rule about which goes first.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","X","Y","Z"],3),
+        btl: vec![
+            btl(&[0,2,3],30),
+            btl(&[1,2,4],24),
+            btl(&[3],14),
+            btl(&[4],15),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_shuffle_keeps_votes() {
+    let data = make_data();
+    let shuffled = shuffle_ballots(&data,1);
+    assert_eq!(shuffled.num_votes(),data.num_votes());
+    assert_eq!(shuffled.btl.len(),data.btl.len());
+    for vote in &data.btl { assert!(shuffled.btl.iter().any(|v|v.candidates==vote.candidates && v.n==vote.n)); }
+}
+
+#[test]
+fn test_exclusion_parcel_order_is_deterministic() {
+    let data = make_data();
+    let seeds : Vec<u64> = (0..20).collect();
+    check_order_independent::<SimpleRules>(&data,&seeds).unwrap();
+    let transcript = distribute_preferences::<SimpleRules>(&data,NumberOfCandidates(3),&HashSet::new(),&TieResolutionsMadeByEC::default(),None,false,&mut Randomness::ReverseDonkeyVote);
+    // count 4 is the first part of the exclusion of X, which should be the parcel with the highest transfer value.
+    let count = transcript.count(CountIndex(3));
+    assert!(matches!(&count.reason,ReasonForCount::Elimination(c) if c==&vec![CandidateIndex(2)]));
+    assert_eq!(count.portion.transfer_value,Some(TransferValue::from_surplus(3,BallotPaperCount(10))));
+}
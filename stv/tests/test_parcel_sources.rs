@@ -0,0 +1,62 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the breakdown by first preference of the papers transferred in an exclusion.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::{ParcelSource, ReasonForCount, Transcript};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::distribute_preferences_with_extractors;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Five candidates for two vacancies, with E excluded before the count. A gets a quota on first preferences, with the surplus going to B.
+/// D is then excluded, electing C, and B is left continuing.
+fn count(include_list_of_votes_in_transcript:bool) -> Transcript<usize> {
+    let data = ElectionData {
+        metadata: metadata(&["A","B","C","D"],1),
+        btl: vec![
+            btl(&[0],40),
+            btl(&[1],30),
+            btl(&[2,1],15),
+            btl(&[3,2],10),
+            btl(&[3],4),
+        ],
+        ..Default::default()
+    };
+    distribute_preferences_with_extractors::<SimpleRules>(&data,NumberOfCandidates(1),&HashSet::new(),&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote,&[],include_list_of_votes_in_transcript)
+}
+
+#[test]
+fn test_parcel_sources() {
+    let transcript = count(true);
+    // D is excluded, giving C 10 papers. Then C is excluded, with a parcel of 15 of C's own papers and 10 from D.
+    assert!(matches!(&transcript.counts[1].reason,ReasonForCount::Elimination(excluded) if excluded==&[CandidateIndex(3)]));
+    assert_eq!(transcript.counts[1].parcel_sources,Some(vec![ParcelSource{ first_preference: CandidateIndex(3), papers: BallotPaperCount(14) }]));
+    assert!(matches!(&transcript.counts[2].reason,ReasonForCount::Elimination(excluded) if excluded==&[CandidateIndex(2)]));
+    let sources = transcript.counts[2].parcel_sources.clone().unwrap();
+    assert_eq!(sources,vec![ParcelSource{ first_preference: CandidateIndex(2), papers: BallotPaperCount(15) },ParcelSource{ first_preference: CandidateIndex(3), papers: BallotPaperCount(10) }]);
+    // the breakdown adds up to the papers transferred.
+    for (index,count) in transcript.counts.iter().enumerate() {
+        if let ReasonForCount::Elimination(excluded) = &count.reason {
+            let transferred : usize = excluded.iter().map(|c|transcript.counts[index-1].status.papers.candidate[c.0].0-count.status.papers.candidate[c.0].0).sum();
+            assert_eq!(count.parcel_sources.as_ref().unwrap().iter().map(|s|s.papers.0).sum::<usize>(),transferred);
+        } else {
+            assert!(count.parcel_sources.is_none());
+        }
+    }
+    assert_eq!(transcript.elected,vec![CandidateIndex(1)]);
+}
+
+#[test]
+fn test_parcel_sources_not_included_by_default() {
+    let transcript = count(false);
+    assert!(transcript.counts.iter().all(|c|c.parcel_sources.is_none()));
+}
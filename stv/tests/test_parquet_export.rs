@@ -0,0 +1,73 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check exporting a transcript as a Parquet file. Only compiled with the parquet feature.
+
+#![cfg(feature="parquet")]
+
+use std::collections::HashSet;
+use arrow_array::{Array, Float64Array, StringArray, UInt64Array};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::election_data::ElectionData;
+use stv::parquet_export::write_transcript_parquet;
+use stv::preference_distribution::distribute_preferences;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Three candidates for two vacancies. A is elected on first preferences, C is excluded, and B is elected.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C"],2),
+        btl: vec![
+            btl(&[0],40),
+            btl(&[1],25),
+            btl(&[2,1],20),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parquet_matches_json_transcript() {
+    let data = make_data();
+    let transcript = distribute_preferences::<SimpleRules>(&data,NumberOfCandidates(2),&HashSet::new(),&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+    let json = serde_json::to_value(&transcript).unwrap();
+    let path = std::env::temp_dir().join("concrete_stv_test_parquet_export.parquet");
+    write_transcript_parquet(&transcript,&data.metadata,std::fs::File::create(&path).unwrap()).unwrap();
+    let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap()).unwrap().build().unwrap();
+    let batches : Vec<_> = reader.map(|b|b.unwrap()).collect();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(batches.len(),1);
+    let batch = &batches[0];
+    assert_eq!(batch.num_rows(),3*transcript.counts.len());
+    let column = |name:&str| batch.column_by_name(name).unwrap().clone();
+    let count = column("count"); let count = count.as_any().downcast_ref::<UInt64Array>().unwrap();
+    let candidate = column("candidate"); let candidate = candidate.as_any().downcast_ref::<UInt64Array>().unwrap();
+    let candidate_name = column("candidate_name"); let candidate_name = candidate_name.as_any().downcast_ref::<StringArray>().unwrap();
+    let tally = column("tally"); let tally = tally.as_any().downcast_ref::<Float64Array>().unwrap();
+    let papers = column("papers"); let papers = papers.as_any().downcast_ref::<UInt64Array>().unwrap();
+    let status = column("status"); let status = status.as_any().downcast_ref::<StringArray>().unwrap();
+    for row in 0..batch.num_rows() {
+        let json_status = &json["counts"][count.value(row) as usize]["status"];
+        let who = candidate.value(row) as usize;
+        assert_eq!(candidate_name.value(row),data.metadata.candidates[who].name);
+        assert_eq!(tally.value(row),json_status["tallies"]["candidate"][who].as_f64().unwrap());
+        assert_eq!(papers.value(row),json_status["papers"]["candidate"][who].as_u64().unwrap());
+    }
+    // First preferences: A 40 (elected), B 25, C 20.
+    assert_eq!(tally.value(0),40.0);
+    assert_eq!(status.value(0),"Elected");
+    assert_eq!(status.value(1),"Continuing");
+    assert_eq!(papers.value(2),20);
+    // At the end C has been excluded and B elected.
+    let last = batch.num_rows()-3;
+    assert_eq!(status.value(last+1),"Elected");
+    assert_eq!(status.value(last+2),"Excluded");
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+}
@@ -0,0 +1,65 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that a custom [PreferenceOracle] can redirect a candidate's transfers.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::ballot_pile::BallotPaperCount;
+use stv::election_data::ElectionData;
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::preference_distribution::{distribute_preferences, distribute_preferences_with_options, CountOptions};
+use stv::preference_oracle::PreferenceOracle;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Toy oracle sending all of one candidate's transfers to another candidate.
+struct ForceTransfers {
+    from : CandidateIndex,
+    to : CandidateIndex,
+    /// the natural distributions that were overridden.
+    overridden : Vec<(CountIndex,Vec<BallotPaperCount>)>,
+}
+
+impl PreferenceOracle for ForceTransfers {
+    fn get_distribution_by_candidate(&mut self, current_count: CountIndex, from: &[CandidateIndex], natural: &[BallotPaperCount]) -> Option<Vec<BallotPaperCount>> {
+        if from!=[self.from] { return None }
+        self.overridden.push((current_count,natural.to_vec()));
+        let mut res = vec![BallotPaperCount(0);natural.len()];
+        res[self.to.0] = natural.iter().cloned().sum();
+        Some(res)
+    }
+}
+
+#[test]
+fn test_oracle_redirects_transfers() {
+    let vote_data = ElectionData {
+        metadata: metadata(&["A","B","C","D"],1),
+        btl: vec![
+            btl(&[0],35),
+            btl(&[1],33),
+            btl(&[2],22),
+            btl(&[3,1],10),
+        ],
+        ..Default::default()
+    };
+    let vacancies = NumberOfCandidates(1);
+    // Normally D's votes go to B, who then beats A.
+    let transcript = distribute_preferences::<SimpleRules>(&vote_data,vacancies,&HashSet::new(),&vote_data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(1)]);
+    assert_eq!(transcript.counts[1].not_continuing,vec![CandidateIndex(3)]);
+    // If D's votes are all forced to A, A wins instead.
+    let mut oracle = ForceTransfers{ from: CandidateIndex(3), to: CandidateIndex(0), overridden: vec![] };
+    let transcript = distribute_preferences_with_options::<SimpleRules>(&vote_data,vacancies,&HashSet::new(),&vote_data.metadata.tie_resolutions,&mut Randomness::ReverseDonkeyVote,CountOptions{ oracle: Some(&mut oracle), ..Default::default() });
+    assert_eq!(transcript.elected,vec![CandidateIndex(0)]);
+    assert_eq!(transcript.counts[1].not_continuing,vec![CandidateIndex(3)]);
+    assert_eq!(transcript.counts[1].status.tallies.candidate[0],45);
+    assert_eq!(transcript.counts[1].status.tallies.candidate[1],33);
+    // the oracle was consulted once for D's votes, which would naturally have gone to B.
+    assert_eq!(oracle.overridden,vec![(CountIndex(1),vec![BallotPaperCount(0),BallotPaperCount(10),BallotPaperCount(0),BallotPaperCount(0),BallotPaperCount(0)])]);
+}
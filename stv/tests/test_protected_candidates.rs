@@ -0,0 +1,38 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that protecting a candidate from exclusion can change who is elected.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{distribute_preferences, distribute_preferences_with_options, CountOptions};
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+#[test]
+fn test_protected_candidate_changes_elected_set() {
+    let vote_data = ElectionData {
+        metadata: metadata(&["A","B","C"],1),
+        btl: vec![
+            btl(&[0],40),
+            btl(&[1,2],35),
+            btl(&[2,1],25),
+        ],
+        ..Default::default()
+    };
+    let vacancies = NumberOfCandidates(1);
+    // Normally C is excluded, and their votes elect B.
+    let transcript = distribute_preferences::<SimpleRules>(&vote_data,vacancies,&HashSet::new(),&vote_data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(1)]);
+    assert_eq!(transcript.counts[1].not_continuing,vec![CandidateIndex(2)]);
+    // If C is protected, B is excluded instead, and their votes elect C.
+    let transcript = distribute_preferences_with_options::<SimpleRules>(&vote_data,vacancies,&HashSet::new(),&vote_data.metadata.tie_resolutions,&mut Randomness::ReverseDonkeyVote,CountOptions{ protected: &[CandidateIndex(2)], ..Default::default() });
+    assert_eq!(transcript.elected,vec![CandidateIndex(2)]);
+    assert_eq!(transcript.counts[1].not_continuing,vec![CandidateIndex(1)]);
+}
@@ -0,0 +1,61 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check counting with provisional ballots.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::ballot_pile::BallotPaperCount;
+use stv::election_data::{ElectionData, VoteTypeSpecification};
+use stv::provisional::count_with_provisional;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Three candidates for two vacancies. A is elected on first preferences. Without the 5 challenged declaration votes for C, C is
+/// then excluded and B elected; with them, B is excluded and C elected.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C"],2),
+        btl: vec![
+            btl(&[0],40),
+            btl(&[1],25),
+            btl(&[2],24),
+            btl(&[2],5),
+            btl(&[1],1),
+        ],
+        btl_types: vec![
+            VoteTypeSpecification{ vote_type: "Ordinary".to_string(), first_index_inclusive: 0, last_index_exclusive: 3 },
+            VoteTypeSpecification{ vote_type: "Declaration".to_string(), first_index_inclusive: 3, last_index_exclusive: 4 },
+            VoteTypeSpecification{ vote_type: "Postal".to_string(), first_index_inclusive: 4, last_index_exclusive: 5 },
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_provisional_ballots_determinative() {
+    let data = make_data();
+    let report = count_with_provisional::<SimpleRules>(&data,&["Declaration".to_string()],&mut Randomness::ReverseDonkeyVote).unwrap();
+    assert_eq!(report.provisional_ballots,BallotPaperCount(5));
+    assert_eq!(report.including_provisional.elected,vec![CandidateIndex(0),CandidateIndex(2)]);
+    // the postal vote is not provisional, so is still counted.
+    assert_eq!(report.excluding_provisional.quota.as_ref().unwrap().papers,BallotPaperCount(90));
+    assert_eq!(report.excluding_provisional.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+    assert!(report.is_outcome_determinative());
+    assert_eq!(report.elected_differences.common,vec![CandidateIndex(0)]);
+    assert_eq!(report.elected_differences.list1only,vec![CandidateIndex(2)]);
+    assert_eq!(report.elected_differences.list2only,vec![CandidateIndex(1)]);
+}
+
+#[test]
+fn test_provisional_ballots_not_determinative() {
+    let data = make_data();
+    let report = count_with_provisional::<SimpleRules>(&data,&["Postal".to_string()],&mut Randomness::ReverseDonkeyVote).unwrap();
+    assert_eq!(report.provisional_ballots,BallotPaperCount(1));
+    assert_eq!(report.including_provisional.elected,report.excluding_provisional.elected);
+    assert!(!report.is_outcome_determinative());
+}
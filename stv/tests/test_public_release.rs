@@ -0,0 +1,73 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check preparation of ballots for public release.
+
+use stv::election_data::{ElectionData, VoteTypeSpecification};
+use stv::public_release::{PublicReleasePolicy, PublicReleaseReport};
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// Candidates A, B, C, D for two vacancies, with a variety of common and rare preference sequences.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0,1,2],30),
+            btl(&[1],20),
+            btl(&[2,3],15),
+            btl(&[3,2,1],10),
+            btl(&[0,2,3,1],1),
+            btl(&[0,2,3],1),
+            btl(&[3,2,1],1),
+            btl(&[1,0],2),
+            btl(&[2,3,0],1),
+        ],
+        btl_types: vec![VoteTypeSpecification{ vote_type: "Ordinary".to_string(), first_index_inclusive: 0, last_index_exclusive: 9 }],
+        informal: 3,
+        ..Default::default()
+    }
+}
+
+fn btl_summary(data:&ElectionData) -> Vec<(Vec<usize>,usize)> {
+    data.btl.iter().map(|b|(b.candidates.iter().map(|c|c.0).collect(),b.n)).collect()
+}
+
+#[test]
+fn test_consolidation_only() {
+    let data = make_data();
+    let (released,report) = data.to_public_release_with_report(&PublicReleasePolicy::default());
+    assert_eq!(report,PublicReleaseReport{ ballots_truncated: 0, distinct_sequences_before: 8, distinct_sequences_after: 8 });
+    assert_eq!(btl_summary(&released)[..4],[(vec![0,1,2],30),(vec![1],20),(vec![2,3],15),(vec![3,2,1],11)]);
+    assert!(released.btl_types.is_empty());
+    assert_eq!(released.informal,3);
+    assert_eq!(released.num_votes(),data.num_votes());
+    assert_eq!(released.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote).counts.len(),data.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote).counts.len());
+}
+
+#[test]
+fn test_truncation() {
+    let data = make_data();
+    let policy = PublicReleasePolicy{ min_ballots_per_sequence: 3, min_preferences_kept: 1 };
+    let (released,report) = data.to_public_release_with_report(&policy);
+    // [0,2,3,1] and [0,2,3] are truncated to [0], [1,0] to [1], and [2,3,0] to [2,3].
+    assert_eq!(report,PublicReleaseReport{ ballots_truncated: 5, distinct_sequences_before: 8, distinct_sequences_after: 5 });
+    assert_eq!(btl_summary(&released),vec![(vec![0,1,2],30),(vec![1],22),(vec![2,3],16),(vec![3,2,1],11),(vec![0],2)]);
+    assert_eq!(released.num_votes(),data.num_votes());
+    assert_eq!(released.metadata.name.modifications.len(),1);
+    // the guarantee: every sequence longer than min_preferences_kept has at least k ballots.
+    for b in &released.btl { assert!(b.candidates.len()<=policy.min_preferences_kept || b.n>=policy.min_ballots_per_sequence); }
+    // the documented bound on the count: the first count is identical, and as fewer ballots were altered than the margin, the same candidates are elected.
+    let original_transcript = data.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    let released_transcript = released.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(released_transcript.counts[0].status.tallies.candidate,original_transcript.counts[0].status.tallies.candidate);
+    assert_eq!(released_transcript.elected,original_transcript.elected);
+    for (original,released) in original_transcript.counts.iter().zip(released_transcript.counts.iter()) {
+        for (o,r) in original.status.tallies.candidate.iter().zip(released.status.tallies.candidate.iter()) { assert!(o.abs_diff(*r)<=report.ballots_truncated); }
+    }
+}
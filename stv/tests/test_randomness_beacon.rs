@@ -0,0 +1,28 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that random tie resolution seeded from a randomness beacon is repeatable.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::random_util::{parse_hex_beacon, Randomness};
+
+/// The order of 10 tied candidates, and a random choice of 7 of 20, from the given randomness.
+fn draws(randomness:&mut Randomness) -> (Vec<CandidateIndex>,Vec<bool>) {
+    let mut tied : Vec<CandidateIndex> = (0..10).rev().map(CandidateIndex).collect();
+    randomness.resolve(&mut tied);
+    (tied,randomness.make_array_with_some_randomly_true(20,7))
+}
+
+#[test]
+fn test_same_beacon_same_draws() {
+    let beacon = parse_hex_beacon("8a6d2d1e1a1bbd8f3c8a9c5b2b7e0e6b1d1ad5f2c4a5d6e7f8091a2b3c4d5e6f").unwrap();
+    assert_eq!(beacon.len(),32);
+    let first = draws(&mut Randomness::from_beacon(&beacon));
+    assert_eq!(first,draws(&mut Randomness::from_beacon(&beacon)));
+    assert_eq!(first.1.iter().filter(|v|**v).count(),7);
+    let other = draws(&mut Randomness::from_beacon(&parse_hex_beacon("8a6d2d1e1a1bbd8f3c8a9c5b2b7e0e6b1d1ad5f2c4a5d6e7f8091a2b3c4d5e60").unwrap()));
+    assert_ne!(first,other);
+}
@@ -0,0 +1,74 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the option of recomputing the quota for the remaining vacancies each time candidates are elected.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::{ElectionReason, Transcript};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::WhenToDoElectCandidateClauseChecking;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Simple integer rules. If `RECOMPUTE` then the quota is recomputed after each election.
+type SimpleRules<const RECOMPUTE:bool> = SimpleRulesWith<RecomputeQuota<RECOMPUTE>>;
+
+struct RecomputeQuota<const RECOMPUTE:bool> {}
+
+impl <const RECOMPUTE:bool> Variation for RecomputeQuota<RECOMPUTE> {
+    fn when_to_check_if_just_two_standing_for_shortcut_election() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::AfterCheckingQuota }
+    fn recompute_quota_after_each_election() -> bool { RECOMPUTE }
+}
+
+/// Five candidates for three vacancies. Most of A's surplus exhausts. Quota 105/4+1 = 27.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D","E"],3),
+        btl: vec![
+            btl(&[0],30),
+            btl(&[0,1],10),
+            btl(&[1],25),
+            btl(&[2],21),
+            btl(&[3,4],9),
+            btl(&[4],10),
+        ],
+        ..Default::default()
+    }
+}
+
+fn elected(transcript:&Transcript<usize>,count:usize) -> Vec<(usize,ElectionReason)> {
+    transcript.counts[count].elected.iter().map(|e|(e.who.0,e.why)).collect()
+}
+
+#[test]
+fn test_fixed_quota() {
+    let transcript = make_data().distribute_preferences::<SimpleRules<false>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,27);
+    assert_eq!(transcript.counts.len(),4);
+    assert!(transcript.counts.iter().all(|c|c.new_quota.is_none()));
+    // C only wins as the higher of the last two after D is excluded.
+    assert_eq!(elected(&transcript,3),vec![(2,ElectionReason::HighestOfLastTwoStanding)]);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(2)]);
+}
+
+#[test]
+fn test_recomputed_quota() {
+    let transcript = make_data().distribute_preferences::<SimpleRules<true>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,27);
+    // after A is elected, V = 25+21+9+10 continuing + 13 surplus = 78, so the quota for 2 vacancies is 78/3+1 = 27.
+    assert_eq!(transcript.counts[0].new_quota,Some(27));
+    // after B is elected at count 2, 9 of A's surplus have exhausted. V = 21+9+10 continuing + 1 surplus = 41, so the quota for 1 vacancy is 41/2+1 = 21.
+    assert_eq!(elected(&transcript,1),vec![(1,ElectionReason::ReachedQuota)]);
+    assert_eq!(transcript.counts[1].new_quota,Some(21));
+    // B's surplus is still relative to the quota of 27 when B was elected.
+    assert_eq!(transcript.counts[2].status.tallies.candidate[1],27);
+    // C now has a quota without needing D to be excluded.
+    assert_eq!(transcript.counts.len(),3);
+    assert_eq!(elected(&transcript,2),vec![(2,ElectionReason::ReachedQuota)]);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(2)]);
+}
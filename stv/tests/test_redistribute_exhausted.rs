@@ -0,0 +1,48 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that the experimental proportional redistribution of exhausted votes can change who is elected.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Simple integer rules where exhausted votes in a surplus distribution carry value, optionally redistributing them.
+type SimpleRules<const REDISTRIBUTE:bool> = SimpleRulesWith<RedistributeExhausted<REDISTRIBUTE>>;
+
+struct RedistributeExhausted<const REDISTRIBUTE:bool> {}
+
+impl <const REDISTRIBUTE:bool> Variation for RedistributeExhausted<REDISTRIBUTE> {
+    fn redistribute_exhausted_proportionally() -> bool { REDISTRIBUTE }
+    fn name() -> String { format!("SimpleRules{}",if REDISTRIBUTE {"RedistributingExhausted"} else {""}) }
+}
+
+#[test]
+fn test_redistribute_exhausted_changes_elected_set() {
+    let vote_data = ElectionData {
+        metadata: metadata(&["B","C","E","F"],2),
+        btl: vec![
+            btl(&[0],100),
+            btl(&[1,2],28),
+            btl(&[2,1],25),
+            btl(&[3,2],4),
+            btl(&[3],20),
+        ],
+        ..Default::default()
+    };
+    // Quota is 179/3+1 = 60, so B has a surplus of 40, all of which exhausts.
+    // Normally F is excluded, giving E 4 votes and putting E (29) ahead of C (28).
+    let transcript = vote_data.distribute_preferences::<SimpleRules<false>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(2)]);
+    // With redistribution, B's surplus goes to C, E and F in proportion 28:25:24, widening C's lead enough that F's votes do not overcome it.
+    let transcript = vote_data.distribute_preferences::<SimpleRules<true>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+    assert_eq!(transcript.counts[1].status.tallies.exhausted,0);
+    assert_eq!(transcript.counts[1].status.tallies.candidate[1..],[42,37,36]);
+}
@@ -0,0 +1,65 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that representation constraints can change who is elected.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::distribution_of_preferences_transcript::{ElectionReason, ReasonForCount};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{distribute_preferences, distribute_preferences_with_options, CountOptions, RepresentationConstraint};
+use stv::random_util::Randomness;
+use stv::tie_resolution::TieResolutionsMadeByEC;
+
+mod common;
+use common::{btl, metadata, SortedExclusionRules};
+
+/// Candidates A, B, C, D for 2 vacancies, quota 34. A and B are both from the north.
+/// Unconstrained, A is elected, then D is excluded and its papers elect B.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0,1],40),
+            btl(&[1,2],25),
+            btl(&[2],20),
+            btl(&[3,1],15),
+        ],
+        ..Default::default()
+    }
+}
+
+fn north(maximum:usize) -> Vec<RepresentationConstraint> {
+    vec![RepresentationConstraint{ name: "North".to_string(), candidates: vec![CandidateIndex(0),CandidateIndex(1)], maximum }]
+}
+
+#[test]
+fn test_constraint_changes_last_seat() {
+    let data = make_data();
+    let unconstrained = distribute_preferences::<SortedExclusionRules>(&data,NumberOfCandidates(2),&HashSet::new(),&TieResolutionsMadeByEC::default(),None,false,&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(unconstrained.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+    let loose = distribute_preferences_with_options::<SortedExclusionRules>(&data,NumberOfCandidates(2),&HashSet::new(),&TieResolutionsMadeByEC::default(),&mut Randomness::ReverseDonkeyVote,CountOptions{ representation_constraints: &north(2), ..Default::default() });
+    assert_eq!(loose.elected,unconstrained.elected);
+    let constrained = distribute_preferences_with_options::<SortedExclusionRules>(&data,NumberOfCandidates(2),&HashSet::new(),&TieResolutionsMadeByEC::default(),&mut Randomness::ReverseDonkeyVote,CountOptions{ representation_constraints: &north(1), ..Default::default() });
+    assert_eq!(constrained.elected,vec![CandidateIndex(0),CandidateIndex(2)]);
+    // Once A is elected, B can no longer be elected, so is excluded ahead of D (who has fewer votes), and B's papers elect C.
+    let last = constrained.counts.last().unwrap();
+    assert!(matches!(&last.reason,ReasonForCount::Elimination(excluded) if excluded==&vec![CandidateIndex(1)]));
+    assert!(!constrained.counts.iter().any(|c|matches!(&c.reason,ReasonForCount::Elimination(excluded) if excluded.contains(&CandidateIndex(3)))));
+    assert_eq!(last.elected.len(),1);
+    assert_eq!(last.elected[0].who,CandidateIndex(2));
+    assert_eq!(last.elected[0].why,ElectionReason::ReachedQuota);
+}
+
+#[test]
+fn test_blocked_candidate_skipped_when_electing_remaining() {
+    // With 3 vacancies, once A is elected B is blocked, leaving C and D as the only candidates who can fill the 2 remaining seats.
+    let data = make_data();
+    let transcript = distribute_preferences_with_options::<SortedExclusionRules>(&data,NumberOfCandidates(3),&HashSet::new(),&TieResolutionsMadeByEC::default(),&mut Randomness::ReverseDonkeyVote,CountOptions{ representation_constraints: &north(1), ..Default::default() });
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(2),CandidateIndex(3)]);
+    assert_eq!(transcript.counts.len(),1);
+    assert!(transcript.counts[0].elected.iter().filter(|e|e.who!=CandidateIndex(0)).all(|e|e.why==ElectionReason::AllRemainingMustBeElected));
+}
@@ -0,0 +1,93 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the Robson rotation model preserves party first preferences but changes them within a party.
+
+use stv::ballot_metadata::{Candidate, CandidateIndex, ElectionMetadata, NumberOfCandidates, Party, PartyIndex};
+use stv::election_data::{ElectionData, VoteTypeSpecification};
+use stv::robson_rotation::RobsonRotationParams;
+
+mod common;
+use common::btl;
+
+fn party(column_id:&str,candidates:&[usize]) -> Party {
+    Party{
+        column_id: column_id.to_string(),
+        name: column_id.to_string(),
+        abbreviation: None,
+        atl_allowed: false,
+        candidates: candidates.iter().map(|&c|CandidateIndex(c)).collect(),
+        tickets: vec![],
+        official_atl_votes: None,
+    }
+}
+
+/// Column A has candidates 0,1,2, column B has 3,4.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: ElectionMetadata {
+            candidates: (0..5).map(|i|Candidate{ party: Some(PartyIndex(if i<3 {0} else {1})), ..Candidate::from_name(&format!("Candidate {}",i)) }).collect(),
+            parties: vec![party("A",&[0,1,2]),party("B",&[3,4])],
+            vacancies: Some(NumberOfCandidates(2)),
+            ..Default::default()
+        },
+        btl: vec![
+            btl(&[0,1,2,3],9), // follows column A's printed order.
+            btl(&[1,0],4), // doesn't.
+            btl(&[3,4],5), // follows column B's printed order.
+            btl(&[4,3],2), // doesn't.
+        ],
+        btl_types: vec![
+            VoteTypeSpecification{ vote_type: "Postal".to_string(), first_index_inclusive: 2, last_index_exclusive: 4 },
+        ],
+        ..Default::default()
+    }
+}
+
+fn first_preferences(data:&ElectionData) -> Vec<usize> {
+    let mut res = vec![0;data.metadata.candidates.len()];
+    for vote in &data.btl { res[vote.candidates[0].0]+=vote.n; }
+    res
+}
+
+fn party_first_preferences(data:&ElectionData) -> Vec<usize> {
+    let by_candidate = first_preferences(data);
+    data.metadata.parties.iter().map(|p|p.candidates.iter().map(|c|by_candidate[c.0]).sum()).collect()
+}
+
+#[test]
+fn test_robson_rotation_all_position_driven() {
+    let data = make_data();
+    let rotated = data.apply_robson_rotation_model(RobsonRotationParams::default());
+    assert_eq!(rotated.btl,vec![
+        btl(&[0,1,2,3],3),
+        btl(&[1,2,0,3],3),
+        btl(&[2,0,1,3],3),
+        btl(&[1,0],4),
+        btl(&[3,4],3),
+        btl(&[4,3],2),
+        btl(&[4,3],2),
+    ]);
+    assert_eq!(party_first_preferences(&rotated),party_first_preferences(&data));
+    assert_eq!(first_preferences(&data),vec![9,4,0,5,2]);
+    assert_eq!(first_preferences(&rotated),vec![3,7,3,3,4]);
+    assert_eq!(rotated.num_votes(),data.num_votes());
+    // the postal votes are still the ones that came from the postal votes.
+    assert_eq!(rotated.btl_types[0].first_index_inclusive,4);
+    assert_eq!(rotated.btl_types[0].last_index_exclusive,7);
+    assert!(!rotated.validate().has_fatal_problems(),"{}",rotated.validate());
+}
+
+#[test]
+fn test_robson_rotation_partially_position_driven() {
+    let data = make_data();
+    let rotated = data.apply_robson_rotation_model(RobsonRotationParams{ proportion_position_driven: 0.5 });
+    // 4 of the 9 are moved, 2 of the 5.
+    assert_eq!(first_preferences(&rotated),vec![5+2,4+1,1,3+1,2+1]);
+    assert_eq!(party_first_preferences(&rotated),party_first_preferences(&data));
+    let unchanged = data.apply_robson_rotation_model(RobsonRotationParams{ proportion_position_driven: 0.0 });
+    assert_eq!(unchanged.btl,data.btl);
+}
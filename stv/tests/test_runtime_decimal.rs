@@ -0,0 +1,85 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check counting with a number of decimal places chosen at run time.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::ballot_pile::{BallotPaperCount, DoNotSplitByCountNumber};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{BigRational, LastParcelUse, PreferenceDistributionRules, SurplusTransferMethod, TransferValueMethod, WhenToDoElectCandidateClauseChecking};
+use stv::random_util::Randomness;
+use stv::tie_resolution::MethodOfTieResolution;
+use stv::runtime_decimal::RuntimeDecimal;
+use stv::transfer_value::TransferValue;
+use num::BigInt;
+
+mod common;
+use common::{btl, metadata};
+
+/// Simple rules counting to `PLACES` decimal places, chosen at run time via [RuntimeDecimal].
+struct DecimalRules<const PLACES:u32> {}
+
+impl <const PLACES:u32> PreferenceDistributionRules for DecimalRules<PLACES> {
+    type Tally = RuntimeDecimal;
+    type SplitByNumber = DoNotSplitByCountNumber;
+
+    fn use_last_parcel_for_surplus_distribution() -> LastParcelUse { LastParcelUse::No }
+    fn transfer_value_method() -> TransferValueMethod { TransferValueMethod::SurplusOverBallots }
+    fn convert_tally_to_rational(tally: Self::Tally) -> BigRational { tally.to_rational() }
+    fn convert_rational_to_tally_after_applying_transfer_value(rational: BigRational) -> Self::Tally { RuntimeDecimal::from_rational_rounding_down(rational,PLACES) }
+    fn make_transfer_value(surplus: Self::Tally, ballots: BallotPaperCount) -> TransferValue {
+        let surplus = surplus.to_rational();
+        TransferValue::new(surplus.numer().clone(),surplus.denom()*BigInt::from(ballots.0))
+    }
+    fn use_transfer_value(transfer_value: &TransferValue, ballots: BallotPaperCount) -> Self::Tally { RuntimeDecimal::from_rational_rounding_down(transfer_value.mul(ballots),PLACES) }
+    fn surplus_distribution_subdivisions() -> SurplusTransferMethod { SurplusTransferMethod::JustOneTransferValue }
+    fn sort_exclusions_by_transfer_value() -> bool { false }
+    fn resolve_ties_elected_one_of_last_two() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_elected_by_quota() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_elected_all_remaining() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn resolve_ties_choose_lowest_candidate_for_exclusion() -> MethodOfTieResolution { MethodOfTieResolution::None }
+    fn check_elected_if_in_middle_of_surplus_distribution() -> bool { true }
+    fn check_elected_if_in_middle_of_exclusion() -> bool { true }
+    fn finish_all_counts_in_elimination_when_all_elected() -> bool { false }
+    fn finish_all_surplus_distributions_when_all_elected() -> bool { false }
+    fn when_to_check_if_just_two_standing_for_shortcut_election() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::Never }
+    fn when_to_check_if_all_remaining_should_get_elected() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::AfterCheckingQuota }
+    fn when_to_check_if_top_few_have_overwhelming_votes() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::Never }
+    fn name() -> String { format!("Decimal{}",PLACES) }
+}
+
+/// Four candidates for two vacancies. A gets a quota on first preferences, with a surplus of 26 over 60 papers going to B and C.
+/// D is then excluded, electing C.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0,1],40),
+            btl(&[0,2],20),
+            btl(&[1],10),
+            btl(&[2],15),
+            btl(&[3,2],15),
+        ],
+        ..Default::default()
+    }
+}
+
+fn check_places<const PLACES:u32>(b_tally_after_surplus:&str) {
+    let transcript = make_data().distribute_preferences::<DecimalRules<PLACES>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,BallotPaperCount(34).into());
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(2)]);
+    let b_tally = transcript.counts[1].status.tallies.candidate[1];
+    assert_eq!(b_tally.scale(),PLACES);
+    assert_eq!(b_tally,b_tally_after_surplus.parse().unwrap());
+    assert_eq!(b_tally.to_string(),b_tally_after_surplus);
+}
+
+#[test]
+fn test_5_places() { check_places::<5>("27.33333"); }
+#[test]
+fn test_6_places() { check_places::<6>("27.333333"); }
+#[test]
+fn test_8_places() { check_places::<8>("27.33333333"); }
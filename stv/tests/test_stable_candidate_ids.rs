@@ -0,0 +1,110 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that stable candidate ids let tie resolutions survive reordering of candidates.
+
+use stv::ballot_metadata::{CandidateIndex, StableCandidateId};
+use stv::election_data::ElectionData;
+use stv::file_format::load_str_with_migration;
+use stv::random_util::Randomness;
+use stv::tie_resolution::{TieResolutionAtom, TieResolutionExplicitDecision, TieResolutionExplicitDecisionInCount};
+
+mod common;
+use common::{btl, metadata, SimpleRules};
+
+/// One vacancy. YOUNG and BROWN are tied for exclusion at the first count; YOUNG's votes go to SMITH, BROWN's to JONES.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["SMITH","JONES","YOUNG","BROWN"],1),
+        btl: vec![
+            btl(&[0],20),
+            btl(&[1],18),
+            btl(&[2,0],5),
+            btl(&[3,1],5),
+        ],
+        ..Default::default()
+    }
+}
+
+/// The same election with the candidates in a different order; new candidate i is old candidate new_order[i].
+fn reorder(data:&ElectionData,new_order:&[usize]) -> ElectionData {
+    let mut new_index = vec![0;new_order.len()];
+    for (new,&old) in new_order.iter().enumerate() { new_index[old]=new; }
+    let mut res = data.clone();
+    res.metadata.candidates = new_order.iter().map(|&old|data.metadata.candidates[old].clone()).collect();
+    for b in &mut res.btl { for c in &mut b.candidates { *c=CandidateIndex(new_index[c.0]); } }
+    res
+}
+
+fn name_excluded_first(data:&ElectionData) -> String {
+    let transcript = data.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    data.metadata.candidate(transcript.counts[1].not_continuing[0]).name.clone()
+}
+
+#[test]
+fn test_ids_are_independent_of_order() {
+    let data = make_data();
+    let reordered = reorder(&data,&[2,3,0,1]);
+    let ids = data.metadata.stable_candidate_ids();
+    let reordered_ids = reordered.metadata.stable_candidate_ids();
+    assert_eq!(ids[2],reordered_ids[0]);
+    assert_eq!(ids[0],reordered_ids[2]);
+    assert_eq!(reordered.metadata.get_stable_candidate_id_lookup()[&ids[3]],CandidateIndex(1));
+    // a fixed algorithm, so it is the same between versions.
+    assert_eq!(ids[0],StableCandidateId("4f1c6daa809b8c5d".to_string()));
+    // identical names are distinguished.
+    let mut duplicated = data.metadata.clone();
+    duplicated.candidates[3].name="YOUNG".to_string();
+    let duplicated_ids = duplicated.stable_candidate_ids();
+    assert_eq!(duplicated_ids[2],ids[2]);
+    assert_eq!(duplicated_ids[3],StableCandidateId(ids[2].0.clone()+"-2"));
+}
+
+#[test]
+fn test_tie_resolution_survives_reordering() {
+    let mut data = make_data();
+    // left to randomness, the reverse donkey vote excludes YOUNG. The EC instead decided to exclude BROWN.
+    assert_eq!(name_excluded_first(&data),"YOUNG");
+    let mut decision = TieResolutionExplicitDecision::two_lists(vec![CandidateIndex(3)],vec![CandidateIndex(2)]);
+    let index_only = TieResolutionAtom::ExplicitDecision(TieResolutionExplicitDecisionInCount{ decision: decision.clone(), came_up_in: None });
+    decision.add_stable_candidate_ids(&data.metadata);
+    let with_ids = TieResolutionAtom::ExplicitDecision(TieResolutionExplicitDecisionInCount{ decision, came_up_in: None });
+    data.metadata.tie_resolutions.tie_resolutions=vec![index_only];
+    assert_eq!(name_excluded_first(&data),"BROWN");
+    // after reordering, the indices refer to the wrong candidates so the decision does not apply, and YOUNG, now first, is excluded by the reverse donkey vote.
+    assert_eq!(name_excluded_first(&reorder(&data,&[2,3,0,1])),"YOUNG");
+    // with ids, the decision still applies.
+    data.metadata.tie_resolutions.tie_resolutions=vec![with_ids];
+    assert_eq!(name_excluded_first(&data),"BROWN");
+    let mut reordered = reorder(&data,&[2,3,0,1]);
+    assert_eq!(name_excluded_first(&reordered),"BROWN");
+    // and the indices can be brought up to date.
+    reordered.metadata.tie_resolutions.update_indices_from_stable_ids(&reordered.metadata.clone()).unwrap();
+    match &reordered.metadata.tie_resolutions.tie_resolutions[..] {
+        [TieResolutionAtom::ExplicitDecision(decision)] => assert_eq!(decision.decision.increasing_favour,vec![vec![CandidateIndex(1)],vec![CandidateIndex(0)]]),
+        other => panic!("Unexpected tie resolutions {:?}",other),
+    }
+}
+
+#[test]
+fn test_transcript_decisions_get_ids() {
+    let data = make_data();
+    let mut transcript = data.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+    assert!(transcript.counts[1].decisions[0].candidate_ids.is_empty());
+    transcript.add_stable_candidate_ids(&data.metadata);
+    let ids = data.metadata.stable_candidate_ids();
+    assert_eq!(transcript.counts[1].decisions[0].candidate_ids,vec![vec![ids[2].clone()],vec![ids[3].clone()]]);
+}
+
+#[test]
+fn test_older_files_are_migrated() {
+    let upgraded : ElectionData = load_str_with_migration(include_str!("v1_format.stv")).unwrap();
+    let ids = upgraded.metadata.stable_candidate_ids();
+    match &upgraded.metadata.tie_resolutions.tie_resolutions[..] {
+        [TieResolutionAtom::ExplicitDecision(decision)] => assert_eq!(decision.decision.candidate_ids,vec![vec![ids[2].clone()],vec![ids[1].clone()]]),
+        other => panic!("Unexpected tie resolutions {:?}",other),
+    }
+}
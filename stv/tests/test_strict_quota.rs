@@ -0,0 +1,68 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the option of only electing candidates whose tally strictly exceeds the quota.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::{ElectionReason, ReasonForCount, Transcript};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::WhenToDoElectCandidateClauseChecking;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Simple integer rules. If `STRICT` then a candidate needs strictly more than the quota to be elected.
+type SimpleRules<const STRICT:bool> = SimpleRulesWith<StrictQuota<STRICT>>;
+
+struct StrictQuota<const STRICT:bool> {}
+
+impl <const STRICT:bool> Variation for StrictQuota<STRICT> {
+    fn when_to_check_if_just_two_standing_for_shortcut_election() -> WhenToDoElectCandidateClauseChecking { WhenToDoElectCandidateClauseChecking::AfterCheckingQuota }
+    fn quota_is_strict_greater() -> bool { STRICT }
+}
+
+/// Four candidates for two vacancies. Quota 90/3+1 = 31, which A gets exactly on first preferences.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0],31),
+            btl(&[1],30),
+            btl(&[2],20),
+            btl(&[3,0],9),
+        ],
+        ..Default::default()
+    }
+}
+
+fn elected(transcript:&Transcript<usize>,count:usize) -> Vec<(usize,ElectionReason)> {
+    transcript.counts[count].elected.iter().map(|e|(e.who.0,e.why)).collect()
+}
+
+#[test]
+fn test_quota_reached_when_equal() {
+    let transcript = make_data().distribute_preferences::<SimpleRules<false>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,31);
+    // A is elected on first preferences with no surplus.
+    assert_eq!(elected(&transcript,0),vec![(0,ElectionReason::ReachedQuota)]);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+}
+
+#[test]
+fn test_quota_must_be_exceeded() {
+    let transcript = make_data().distribute_preferences::<SimpleRules<true>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,31);
+    // A has exactly a quota, which is not enough.
+    assert_eq!(transcript.counts[0].status.tallies.candidate[0],31);
+    assert!(transcript.counts[0].elected.is_empty());
+    // D is excluded, and their votes take A over the quota.
+    assert!(matches!(&transcript.counts[1].reason,ReasonForCount::Elimination(e) if e==&vec![CandidateIndex(3)]));
+    assert_eq!(transcript.counts[1].status.tallies.candidate[0],40);
+    // B is then the higher of the last two standing for the remaining vacancy.
+    assert_eq!(elected(&transcript,1),vec![(0,ElectionReason::ReachedQuota),(1,ElectionReason::HighestOfLastTwoStanding)]);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+}
@@ -0,0 +1,102 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that a strict count stops at a tie the legislation provides no procedure for, unless the supplied tie resolutions resolve it,
+//! while ties the legislation resolves by countback or by lot are not errors.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::distribution_of_preferences_transcript::{CountIndex, CountStopped, Transcript};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{distribute_preferences_strict, distribute_preferences_with_options, CountOptions, PreferenceDistributionRules};
+use stv::random_util::Randomness;
+use stv::tie_resolution::{MethodOfTieResolution, TieResolutionsMadeByEC, UnresolvedTie};
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// The legislation provides no procedure for ties for exclusion.
+type NoProcedureRules = SimpleRulesWith<NoProcedure>;
+
+struct NoProcedure {}
+
+impl Variation for NoProcedure {
+    fn sort_exclusions_by_transfer_value() -> bool { true }
+    fn resolve_ties_choose_lowest_candidate_for_exclusion() -> MethodOfTieResolution { MethodOfTieResolution::None }
+}
+
+/// Ties for exclusion are resolved by countback, and if the candidates were always tied, by lot.
+type CountbackRules = SimpleRulesWith<Countback>;
+
+struct Countback {}
+
+impl Variation for Countback {
+    fn sort_exclusions_by_transfer_value() -> bool { true }
+    fn resolve_ties_choose_lowest_candidate_for_exclusion() -> MethodOfTieResolution { MethodOfTieResolution::RequireHistoricalCountsToBeAllDifferent }
+}
+
+/// One vacancy, with first preferences A `a`, B `b`, C 2, C's votes going to B `c_to_b` times.
+fn make_data(a:usize,b:usize,c_to_b:usize) -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C"],1),
+        btl: vec![
+            btl(&[0],a),
+            btl(&[1],b),
+            btl(&[2,1],c_to_b),
+            btl(&[2],2-c_to_b),
+        ],
+        ..Default::default()
+    }
+}
+
+fn strict_count<Rules:PreferenceDistributionRules<Tally=usize>>(data:&ElectionData,ec_resolutions:&TieResolutionsMadeByEC) -> Result<Transcript<usize>,UnresolvedTie> {
+    distribute_preferences_strict::<Rules>(data,NumberOfCandidates(1),&HashSet::new(),ec_resolutions,None,&mut Randomness::ReverseDonkeyVote)
+}
+
+#[test]
+fn test_tie_with_no_procedure_is_an_error() {
+    // A 5, B 5, C 2. C is excluded, then A and B are tied for exclusion.
+    let data = make_data(5,5,0);
+    let tie = strict_count::<NoProcedureRules>(&data,&TieResolutionsMadeByEC::default()).unwrap_err();
+    assert_eq!(tie.count,CountIndex(2));
+    let mut candidates = tie.candidates.clone();
+    candidates.sort_by_key(|c|c.0);
+    assert_eq!(candidates,vec![CandidateIndex(0),CandidateIndex(1)]);
+    assert_eq!(tie.candidate_names.len(),2);
+    let message = tie.to_string();
+    assert!(message.contains("count 2"),"{}",message);
+    assert!(message.contains("0 A") && message.contains("1 B"),"{}",message);
+    // the count stops in the count with the tie, and the transcript says why.
+    let transcript = distribute_preferences_with_options::<NoProcedureRules>(&data,NumberOfCandidates(1),&HashSet::new(),&TieResolutionsMadeByEC::default(),&mut Randomness::ReverseDonkeyVote,CountOptions{ strict_ties: true, ..Default::default() });
+    assert_eq!(transcript.stopped,Some(CountStopped::UnresolvedTie(tie)));
+    assert_eq!(transcript.counts.len(),3);
+    // once a resolution is supplied, the count succeeds using it.
+    let ec_resolutions = TieResolutionsMadeByEC::new(vec![vec![CandidateIndex(1),CandidateIndex(0)]]).unwrap();
+    let transcript = strict_count::<NoProcedureRules>(&data,&ec_resolutions).unwrap();
+    assert_eq!(transcript.elected,vec![CandidateIndex(0)]);
+    assert_eq!(transcript.stopped,None);
+}
+
+#[test]
+fn test_tie_with_no_procedure_is_an_error_even_if_countback_would_resolve_it() {
+    // A 5, B 4, C 2 with one vote going to B. After C is excluded A and B are tied on 5, although A was ahead on first preferences.
+    let tie = strict_count::<NoProcedureRules>(&make_data(5,4,1),&TieResolutionsMadeByEC::default()).unwrap_err();
+    assert_eq!(tie.count,CountIndex(2));
+}
+
+#[test]
+fn test_tie_resolved_by_countback_is_not_an_error() {
+    let transcript = strict_count::<CountbackRules>(&make_data(5,4,1),&TieResolutionsMadeByEC::default()).unwrap();
+    assert_eq!(transcript.elected,vec![CandidateIndex(0)]);
+}
+
+#[test]
+fn test_tie_resolved_by_lot_is_not_an_error() {
+    // A and B have been tied in every count, so countback fails and the legislation says to resolve it by lot.
+    let transcript = strict_count::<CountbackRules>(&make_data(5,5,0),&TieResolutionsMadeByEC::default()).unwrap();
+    assert_eq!(transcript.elected.len(),1);
+    assert_eq!(transcript.counts[2].decisions.len(),1);
+}
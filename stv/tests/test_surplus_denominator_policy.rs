@@ -0,0 +1,82 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check each [SurplusFractionDenominatorPolicy] on a surplus where no ballots are continuing.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::ballot_pile::BallotPaperCount;
+use stv::election_data::ElectionData;
+use stv::distribution_of_preferences_transcript::{CountIndex, CountStopped, Transcript};
+use stv::preference_distribution::{distribute_preferences, SurplusFractionDenominatorPolicy, TransferValueMethod};
+use stv::random_util::Randomness;
+use stv::transfer_value::TransferValue;
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Simple integer rules with the transfer value denominator being continuing ballots, and the given policy (0=ClampToOne, 1=Error, 2=AllowNegative).
+type SimpleRules<const POLICY:u8> = SimpleRulesWith<DenominatorPolicy<POLICY>>;
+
+struct DenominatorPolicy<const POLICY:u8> {}
+
+impl <const POLICY:u8> Variation for DenominatorPolicy<POLICY> {
+    fn transfer_value_method() -> TransferValueMethod { TransferValueMethod::SurplusOverContinuingBallots }
+    fn surplus_fraction_denominator_policy() -> SurplusFractionDenominatorPolicy {
+        match POLICY {
+            0 => SurplusFractionDenominatorPolicy::ClampToOne,
+            1 => SurplusFractionDenominatorPolicy::Error,
+            _ => SurplusFractionDenominatorPolicy::AllowNegative,
+        }
+    }
+}
+
+/// A's surplus is entirely on ballots with no further preferences, so there are no continuing ballots to divide it amongst.
+fn count<const POLICY:u8>() -> Transcript<usize> {
+    let vote_data = ElectionData {
+        metadata: metadata(&["A","B","C"],2),
+        btl: vec![
+            btl(&[0],50),
+            btl(&[1],30),
+            btl(&[2],20),
+        ],
+        ..Default::default()
+    };
+    distribute_preferences::<SimpleRules<POLICY>>(&vote_data,NumberOfCandidates(2),&HashSet::new(),&vote_data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote)
+}
+
+#[test]
+fn test_zero_denominator_clamp_to_one() {
+    let transcript = count::<0>();
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+    let created = transcript.counts[1].created_transfer_value.as_ref().unwrap();
+    assert_eq!(created.continuing_ballots,BallotPaperCount(0));
+    assert_eq!(created.transfer_value,TransferValue::one());
+    assert_eq!(created.denominator_policy_applied,Some(SurplusFractionDenominatorPolicy::ClampToOne));
+    assert_eq!(transcript.stopped,None);
+}
+
+#[test]
+fn test_zero_denominator_error() {
+    let transcript = count::<1>();
+    assert_eq!(transcript.stopped,Some(CountStopped::SurplusFractionDenominator{count:CountIndex(1),candidate:CandidateIndex(0)}));
+    // A was elected on first preferences, and the count stops when distributing A's surplus, before B is elected.
+    assert_eq!(transcript.elected,vec![CandidateIndex(0)]);
+    assert_eq!(transcript.counts.len(),2);
+    let created = transcript.counts[1].created_transfer_value.as_ref().unwrap();
+    assert_eq!(created.denominator_policy_applied,Some(SurplusFractionDenominatorPolicy::Error));
+    assert_eq!(transcript.counts[1].status.papers.set_aside,Some(BallotPaperCount(50)));
+}
+
+#[test]
+fn test_zero_denominator_allow_negative() {
+    let transcript = count::<2>();
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+    let created = transcript.counts[1].created_transfer_value.as_ref().unwrap();
+    assert_eq!(created.transfer_value,TransferValue::one());
+    assert_eq!(created.denominator_policy_applied,Some(SurplusFractionDenominatorPolicy::AllowNegative));
+    assert_eq!(transcript.stopped,None);
+}
@@ -0,0 +1,60 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the munge_surplus_fraction hook is applied to the scaled transfer value in surplus distributions.
+
+use stv::distribution_of_preferences_transcript::Transcript;
+use stv::election_data::ElectionData;
+use stv::preference_distribution::SurplusTransferMethod;
+use stv::random_util::Randomness;
+use stv::transfer_value::TransferValue;
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Simple integer rules scaling incoming transfer values, like NSW local government. If `ROUND` then the surplus fraction is rounded down to 1 decimal place.
+type ScalingRules<const ROUND:bool> = SimpleRulesWith<Scaling<ROUND>>;
+
+struct Scaling<const ROUND:bool> {}
+
+impl <const ROUND:bool> Variation for Scaling<ROUND> {
+    fn surplus_distribution_subdivisions() -> SurplusTransferMethod { SurplusTransferMethod::ScaleTransferValues }
+    fn munge_surplus_fraction(fraction: TransferValue) -> TransferValue { if ROUND { fraction.round_down_to_decimal_digits(1) } else { fraction } }
+    fn name() -> String { "ScalingRules".to_string() }
+}
+
+/// A gets 30 votes, all then going to C. B 10, C 9. Quota 49/3+1=17, so A's surplus is 13 and the surplus fraction 13/30.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C"],2),
+        btl: vec![
+            btl(&[0,2],30),
+            btl(&[1],10),
+            btl(&[2],9),
+        ],
+        ..Default::default()
+    }
+}
+
+/// The tally of candidate C and the lost to rounding after the distribution of A's surplus.
+fn after_surplus(transcript:&Transcript<usize>) -> (usize,String,String) {
+    let count = &transcript.counts[1];
+    (count.status.tallies.candidate[2],count.status.tallies.rounding.to_string(),count.created_transfer_value.as_ref().unwrap().transfer_value.to_string())
+}
+
+#[test]
+fn test_exact_surplus_fraction() {
+    let transcript = make_data().distribute_preferences::<ScalingRules<false>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.quota.as_ref().unwrap().quota,17);
+    assert_eq!(after_surplus(&transcript),(22,"0".to_string(),"13/30".to_string()));
+}
+
+#[test]
+fn test_rounded_surplus_fraction() {
+    let transcript = make_data().distribute_preferences::<ScalingRules<true>>(&mut Randomness::ReverseDonkeyVote);
+    // 13/30 = 0.4333... is rounded down to 0.4, so C only gets 12 of the 13 votes, and 1 is lost to rounding.
+    assert_eq!(after_surplus(&transcript),(21,"1".to_string(),"2/5".to_string()));
+}
@@ -0,0 +1,45 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that synthetic election data is reproducible, has the requested shape, and can be counted.
+
+use stv::ballot_metadata::NumberOfCandidates;
+use stv::random_util::Randomness;
+use stv::synthetic::{generate, SyntheticElectionParams};
+
+mod common;
+use common::SimpleRules;
+
+#[test]
+fn test_synthetic_election_counts() {
+    let params = SyntheticElectionParams{ parties: 5, candidates_per_party: 3, independents: 2, vacancies: 4, voters: 2000, ..Default::default() };
+    for seed in 1..=5 {
+        let data = generate(&params,seed);
+        assert_eq!(data.metadata.candidates.len(),17);
+        assert_eq!(data.metadata.parties.len(),5);
+        assert_eq!(data.metadata.vacancies,Some(NumberOfCandidates(4)));
+        assert_eq!(data.num_votes(),2000);
+        let transcript = data.distribute_preferences::<SimpleRules>(&mut Randomness::ReverseDonkeyVote);
+        assert_eq!(transcript.elected.len(),4);
+    }
+}
+
+#[test]
+fn test_synthetic_election_reproducible() {
+    let params = SyntheticElectionParams::default();
+    let json = |seed:u64| serde_json::to_string(&generate(&params,seed)).unwrap();
+    assert_eq!(json(7),json(7));
+    assert_ne!(json(7),json(8));
+}
+
+#[test]
+fn test_synthetic_election_exhaustion() {
+    // with no exhaustion and only BTL votes, every ballot ranks every candidate.
+    let params = SyntheticElectionParams{ exhaustion_rate: 0.0, atl_fraction: 0.0, ..Default::default() };
+    let data = generate(&params,3);
+    assert!(data.atl.is_empty());
+    assert!(data.btl.iter().all(|v|v.candidates.len()==data.metadata.candidates.len()));
+}
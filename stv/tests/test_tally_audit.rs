@@ -0,0 +1,76 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the optional tally audit accounts for every change to the tallies.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::CandidateIndex;
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{distribute_preferences, distribute_preferences_with_options, CountOptions};
+use stv::random_util::Randomness;
+use stv::signed_version::SignedVersion;
+use stv::tally_audit::{TallyAudit, TallyAuditTarget, TallyMutation};
+
+mod common;
+use common::{btl, metadata, SortedExclusionRules};
+
+/// Five candidates for two vacancies. A has a surplus that doesn't divide evenly, and some votes exhaust.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D","E"],2),
+        btl: vec![
+            btl(&[0,2],10),
+            btl(&[0,3],7),
+            btl(&[0],3),
+            btl(&[1],6),
+            btl(&[2,1],4),
+            btl(&[3,2],3),
+            btl(&[4],2),
+        ],
+        ..Default::default()
+    }
+}
+
+fn run() -> (stv::distribution_of_preferences_transcript::Transcript<usize>,TallyAudit<usize>) {
+    let data = make_data();
+    let mut audit = TallyAudit::default();
+    let transcript = distribute_preferences_with_options::<SortedExclusionRules>(&data,data.metadata.vacancies.unwrap(),&HashSet::new(),&data.metadata.tie_resolutions,&mut Randomness::ReverseDonkeyVote,CountOptions{ tally_audit: Some(&mut audit), ..Default::default() });
+    (transcript,audit)
+}
+
+#[test]
+fn test_tally_audit_sums_to_final_tallies() {
+    let (transcript,audit) = run();
+    let last = &transcript.counts.last().unwrap().status.tallies;
+    for (i,tally) in last.candidate.iter().enumerate() {
+        assert_eq!(audit.total(TallyAuditTarget::Candidate(CandidateIndex(i))),SignedVersion::from(*tally),"Candidate {}",i);
+    }
+    assert_eq!(audit.total(TallyAuditTarget::Exhausted),SignedVersion::from(last.exhausted));
+    assert_eq!(audit.total(TallyAuditTarget::Rounding),last.rounding);
+    assert_eq!(audit.total(TallyAuditTarget::SetAside),SignedVersion::from(last.set_aside.unwrap_or(0)));
+    // the surplus of A (20-12=8 over 20 papers) loses some value to rounding, and some votes exhaust.
+    assert!(audit.entries.iter().any(|e|e.why==TallyMutation::SurplusRemoved));
+    assert!(audit.entries.iter().any(|e|e.why==TallyMutation::ExcludedVotesRemoved));
+    assert!(audit.total(TallyAuditTarget::Rounding).value>0);
+    assert!(last.exhausted>0);
+}
+
+#[test]
+fn test_tally_audit_does_not_change_transcript() {
+    let data = make_data();
+    let plain = distribute_preferences::<SortedExclusionRules>(&data,data.metadata.vacancies.unwrap(),&HashSet::new(),&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote);
+    let (audited,_) = run();
+    assert_eq!(serde_json::to_string(&plain).unwrap(),serde_json::to_string(&audited).unwrap());
+}
+
+#[test]
+fn test_tally_audit_json_round_trip() {
+    let (_,audit) = run();
+    let json = serde_json::to_string(&audit).unwrap();
+    let back : TallyAudit<usize> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.entries.len(),audit.entries.len());
+    assert_eq!(back.total(TallyAuditTarget::Rounding),audit.total(TallyAuditTarget::Rounding));
+}
@@ -0,0 +1,73 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that counting every branch of a tie resolved by lot gives every possible outcome.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::election_data::ElectionData;
+use stv::preference_distribution::distribute_preferences;
+use stv::random_util::Randomness;
+use stv::tie_branches::{distribute_preferences_all_tie_branches, replay_transcript_with_tie_override};
+use stv::tie_resolution::TieResolutionsMadeByEC;
+
+mod common;
+use common::{btl, metadata, SortedExclusionRules};
+
+/// A 5, B 5, C 2. C is excluded, then A and B are tied for exclusion, so the winner is decided by lot.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C"],1),
+        btl: vec![
+            btl(&[0],5),
+            btl(&[1],5),
+            btl(&[2],2),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_one_tie_gives_two_outcomes() {
+    let branches = distribute_preferences_all_tie_branches::<SortedExclusionRules>(&make_data(),NumberOfCandidates(1),&HashSet::new(),&TieResolutionsMadeByEC::default(),None,10);
+    assert_eq!(branches.len(),2);
+    for (choices,_) in &branches {
+        assert_eq!(choices.len(),1);
+        assert_eq!(choices[0].count,CountIndex(2)); // first preferences, exclude C, then the tie.
+    }
+    let elected : Vec<Vec<CandidateIndex>> = branches.iter().map(|(_,transcript)|transcript.elected.clone()).collect();
+    assert_eq!(elected,vec![vec![CandidateIndex(1)],vec![CandidateIndex(0)]]);
+    // the bound on the number of branches is respected.
+    assert_eq!(distribute_preferences_all_tie_branches::<SortedExclusionRules>(&make_data(),NumberOfCandidates(1),&HashSet::new(),&TieResolutionsMadeByEC::default(),None,1).len(),1);
+}
+
+#[test]
+fn test_ec_decision_is_not_branched_on() {
+    let ec_resolutions = TieResolutionsMadeByEC::new(vec![vec![CandidateIndex(1),CandidateIndex(0)]]).unwrap();
+    let branches = distribute_preferences_all_tie_branches::<SortedExclusionRules>(&make_data(),NumberOfCandidates(1),&HashSet::new(),&ec_resolutions,None,10);
+    assert_eq!(branches.len(),1);
+    assert!(branches[0].0.is_empty());
+    assert_eq!(branches[0].1.elected,vec![CandidateIndex(0)]);
+}
+
+#[test]
+fn test_replay_with_tie_flipped() {
+    let data = make_data();
+    let original = distribute_preferences::<SortedExclusionRules>(&data,NumberOfCandidates(1),&HashSet::new(),&TieResolutionsMadeByEC::default(),None,false,&mut Randomness::ReverseDonkeyVote);
+    let recorded = &original.count(CountIndex(2)).decisions;
+    assert_eq!(recorded.len(),1);
+    let flipped : Vec<CandidateIndex> = recorded[0].increasing_favour.iter().flatten().rev().cloned().collect();
+    let replayed = replay_transcript_with_tie_override::<SortedExclusionRules>(&data,&original,CountIndex(2),&flipped).unwrap();
+    assert_eq!(replayed.elected.len(),1);
+    assert_ne!(replayed.elected,original.elected);
+    assert_eq!(replayed.elected[0],flipped[1]); // the more favoured candidate survives the exclusion.
+    // replaying with the same order reproduces the original.
+    let unflipped : Vec<CandidateIndex> = flipped.iter().rev().cloned().collect();
+    assert_eq!(replay_transcript_with_tie_override::<SortedExclusionRules>(&data,&original,CountIndex(2),&unflipped).unwrap().elected,original.elected);
+    // no tie in count 1.
+    assert!(replay_transcript_with_tie_override::<SortedExclusionRules>(&data,&original,CountIndex(1),&flipped).is_none());
+}
@@ -0,0 +1,110 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check general tie resolution policies for each usage, applying to ties between any candidates.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::distribution_of_preferences_transcript::CountIndex;
+use stv::election_data::ElectionData;
+use stv::random_util::Randomness;
+use stv::tie_resolution::{TieResolutionExplicitDecision, TieResolutionGranularityNeeded, TieResolutionPolicy, TieResolutionsMadeByEC, TieResolutionUsage};
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Simple integer rules, with no countback, so all ties go to the EC.
+type NoCountbackRules = SimpleRulesWith<NoCountback>;
+
+struct NoCountback {}
+
+impl Variation for NoCountback {
+    fn name() -> String { "NoCountbackRules".to_string() }
+}
+
+/// One vacancy. Candidates 2 and 3 are tied for exclusion at the first count; candidate 2's votes go to candidate 0, candidate 3's to candidate 1.
+fn make_data(names:[&str;4]) -> ElectionData {
+    ElectionData {
+        metadata: metadata(&names,1),
+        btl: vec![
+            btl(&[0],20),
+            btl(&[1],18),
+            btl(&[2,0],5),
+            btl(&[3,1],5),
+        ],
+        ..Default::default()
+    }
+}
+
+const ALL_USAGES : [TieResolutionUsage;5] = [TieResolutionUsage::Exclusion,TieResolutionUsage::OrderElected,TieResolutionUsage::ShortcutWinner,TieResolutionUsage::OrderSurplusDistributed,TieResolutionUsage::RoundingUp];
+
+fn resolve(resolutions:&TieResolutionsMadeByEC,usage:TieResolutionUsage,names:[&str;4]) -> Vec<CandidateIndex> {
+    let metadata = make_data(names).metadata;
+    let mut tied = vec![CandidateIndex(1),CandidateIndex(3),CandidateIndex(0)];
+    resolutions.resolve(&mut tied,TieResolutionGranularityNeeded::Total,usage,CountIndex(0),&metadata,&mut Randomness::ReverseDonkeyVote);
+    tied
+}
+
+#[test]
+fn test_policy_applies_only_to_its_usage() {
+    for usage in ALL_USAGES {
+        let mut resolutions = TieResolutionsMadeByEC::default();
+        resolutions.default_policy_for(usage,TieResolutionPolicy::FavourLowerCandidateIndex);
+        for other in ALL_USAGES {
+            let applies = other==usage || (usage==TieResolutionUsage::OrderElected && other==TieResolutionUsage::OrderSurplusDistributed);
+            // tied candidates are listed least favoured first.
+            let expected = if applies { vec![CandidateIndex(3),CandidateIndex(1),CandidateIndex(0)] } else { vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(3)] };
+            assert_eq!(resolve(&resolutions,other,["A","B","C","D"]),expected,"policy for {:?} used for {:?}",usage,other);
+        }
+    }
+}
+
+#[test]
+fn test_different_policies_for_different_usages() {
+    let mut resolutions = TieResolutionsMadeByEC::default();
+    resolutions.default_policy_for(TieResolutionUsage::Exclusion,TieResolutionPolicy::FavourHigherCandidateIndex);
+    resolutions.default_policy_for(TieResolutionUsage::OrderElected,TieResolutionPolicy::AlphabeticalByName);
+    resolutions.default_policy_for(TieResolutionUsage::OrderSurplusDistributed,TieResolutionPolicy::FavourLowerCandidateIndex);
+    let names = ["SMITH","JONES","YOUNG","BROWN"];
+    assert_eq!(resolve(&resolutions,TieResolutionUsage::Exclusion,names),vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(3)]);
+    assert_eq!(resolve(&resolutions,TieResolutionUsage::OrderElected,names),vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(3)]); // SMITH < JONES < BROWN
+    assert_eq!(resolve(&resolutions,TieResolutionUsage::OrderSurplusDistributed,names),vec![CandidateIndex(3),CandidateIndex(1),CandidateIndex(0)]);
+    let names = ["BROWN","JONES","YOUNG","SMITH"];
+    assert_eq!(resolve(&resolutions,TieResolutionUsage::OrderElected,names),vec![CandidateIndex(3),CandidateIndex(1),CandidateIndex(0)]); // SMITH < JONES < BROWN
+    // identical names are not resolved alphabetically, so fall back to randomness, and the decision is not determined by the EC.
+    let names = ["BROWN","SMITH","YOUNG","SMITH"];
+    assert_eq!(resolve(&resolutions,TieResolutionUsage::OrderElected,names),vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(3)]);
+    let decision = TieResolutionExplicitDecision::from_resolution(&[CandidateIndex(0),CandidateIndex(1),CandidateIndex(3)],TieResolutionGranularityNeeded::Total,TieResolutionUsage::OrderElected);
+    assert!(!resolutions.is_determined_by_ec(&decision,CountIndex(0),&make_data(names).metadata));
+    assert!(resolutions.is_determined_by_ec(&decision,CountIndex(0),&make_data(["BROWN","JONES","YOUNG","SMITH"]).metadata));
+    // replacing a policy
+    resolutions.default_policy_for(TieResolutionUsage::Exclusion,TieResolutionPolicy::FavourLowerCandidateIndex);
+    assert_eq!(resolutions.default_policies.len(),3);
+    assert_eq!(resolve(&resolutions,TieResolutionUsage::Exclusion,names),vec![CandidateIndex(3),CandidateIndex(1),CandidateIndex(0)]);
+}
+
+#[test]
+fn test_explicit_resolution_takes_precedence() {
+    let mut resolutions = TieResolutionsMadeByEC::new(vec![vec![CandidateIndex(0),CandidateIndex(3),CandidateIndex(1)]]).unwrap();
+    resolutions.default_policy_for(TieResolutionUsage::Exclusion,TieResolutionPolicy::FavourLowerCandidateIndex);
+    assert_eq!(resolve(&resolutions,TieResolutionUsage::Exclusion,["A","B","C","D"]),vec![CandidateIndex(0),CandidateIndex(3),CandidateIndex(1)]);
+}
+
+#[test]
+fn test_policy_in_count() {
+    // Candidates 2 and 3 are tied for exclusion. By default (reverse donkey vote) candidate 2 is excluded.
+    let transcript = make_data(["SMITH","JONES","YOUNG","BROWN"]).distribute_preferences::<NoCountbackRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.counts[1].not_continuing,vec![CandidateIndex(2)]);
+    assert_eq!(transcript.elected,vec![CandidateIndex(0)]);
+    let mut data = make_data(["SMITH","JONES","YOUNG","BROWN"]);
+    data.metadata.tie_resolutions.default_policy_for(TieResolutionUsage::Exclusion,TieResolutionPolicy::FavourLowerCandidateIndex);
+    // the policy survives a round trip through the file format.
+    let data : ElectionData = serde_json::from_str(&serde_json::to_string(&data).unwrap()).unwrap();
+    assert_eq!(data.metadata.tie_resolutions.policy_for(TieResolutionUsage::Exclusion),Some(TieResolutionPolicy::FavourLowerCandidateIndex));
+    let transcript = data.distribute_preferences::<NoCountbackRules>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.counts[1].not_continuing,vec![CandidateIndex(3)]);
+    assert_eq!(transcript.counts[1].decisions.len(),1);
+    assert_eq!(transcript.counts[1].status.tallies.candidate,vec![20,23,5,0]);
+}
@@ -0,0 +1,67 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check tracing the ballot papers that elected a candidate from a transcript.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::ballot_pile::BallotPaperCount;
+use stv::distribution_of_preferences_transcript::{CountIndex, TranscriptWithMetadata};
+use stv::election_data::ElectionData;
+use stv::preference_distribution::distribute_preferences_with_extractors;
+use stv::random_util::Randomness;
+use stv::tie_resolution::TieResolutionsMadeByEC;
+use stv::trace_votes::{count_elected, votes_electing, TraceVotesError};
+use stv::transfer_value::TransferValue;
+
+mod common;
+use common::{btl, metadata, SortedExclusionRules};
+
+/// Two vacancies, quota 34. A gets 60 votes all going to B, so A's surplus of 26 goes to B at a transfer value of 26/60, electing B.
+fn make_data() -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0,1],60),
+            btl(&[1],20),
+            btl(&[2],15),
+            btl(&[3,2],5),
+        ],
+        ..Default::default()
+    }
+}
+
+fn count(data:&ElectionData,include_list_of_votes_in_transcript:bool) -> TranscriptWithMetadata<usize> {
+    let transcript = distribute_preferences_with_extractors::<SortedExclusionRules>(data,NumberOfCandidates(2),&HashSet::new(),&TieResolutionsMadeByEC::default(),None,false,&mut Randomness::ReverseDonkeyVote,&[],include_list_of_votes_in_transcript);
+    TranscriptWithMetadata{ metadata: data.metadata.clone(), transcript }
+}
+
+#[test]
+fn test_trace_candidate_elected_on_transfers() {
+    let transcript = count(&make_data(),true);
+    let b = CandidateIndex(1);
+    assert_eq!(count_elected(&transcript,b),Some(CountIndex(1)));
+    let traced = votes_electing(&transcript,b).unwrap();
+    assert_eq!(traced.metadata.vacancies,Some(NumberOfCandidates(1)));
+    // The papers A received, transferred to B at 26/60, and B's own first preferences.
+    assert_eq!(traced.btl.len(),2);
+    assert_eq!(traced.btl[0].candidates,vec![CandidateIndex(1)]);
+    assert_eq!(traced.btl[0].n,20);
+    assert_eq!(traced.btl[1].candidates,vec![CandidateIndex(0),CandidateIndex(1)]);
+    assert_eq!(traced.btl[1].n,60);
+    assert_eq!(traced.btl_transfer_values.len(),2);
+    assert!(traced.btl_transfer_values[0].value.is_one());
+    assert_eq!((traced.btl_transfer_values[0].first_index_inclusive,traced.btl_transfer_values[0].last_index_exclusive),(0,1));
+    assert_eq!(traced.btl_transfer_values[1].value,TransferValue::from_surplus(26,BallotPaperCount(60)));
+    assert_eq!((traced.btl_transfer_values[1].first_index_inclusive,traced.btl_transfer_values[1].last_index_exclusive),(1,2));
+}
+
+#[test]
+fn test_trace_errors() {
+    let data = make_data();
+    assert!(matches!(votes_electing(&count(&data,true),CandidateIndex(2)),Err(TraceVotesError::NotElected(_))));
+    assert!(matches!(votes_electing(&count(&data,false),CandidateIndex(1)),Err(TraceVotesError::NoListOfVotes)));
+}
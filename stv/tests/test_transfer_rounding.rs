@@ -0,0 +1,60 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the difference between rounding each candidate's transfer down separately and apportioning whole votes by largest remainder.
+
+use stv::ballot_metadata::CandidateIndex;
+use stv::election_data::ElectionData;
+use stv::preference_distribution::{BigRational, TransferRounding};
+use stv::random_util::Randomness;
+use stv::signed_version::SignedVersion;
+use stv::transfer_value::apportion_largest_remainder;
+
+mod common;
+use common::{btl, metadata, SimpleRulesWith, Variation};
+
+/// Simple integer rules, optionally apportioning transfers by largest remainder.
+type SimpleRules<const LARGEST_REMAINDER:bool> = SimpleRulesWith<Rounding<LARGEST_REMAINDER>>;
+
+struct Rounding<const LARGEST_REMAINDER:bool> {}
+
+impl <const LARGEST_REMAINDER:bool> Variation for Rounding<LARGEST_REMAINDER> {
+    fn transfer_rounding() -> TransferRounding { if LARGEST_REMAINDER { TransferRounding::LargestRemainder } else { TransferRounding::PerCandidateDown } }
+    fn name() -> String { format!("SimpleRules{}",if LARGEST_REMAINDER {"LargestRemainder"} else {""}) }
+}
+
+#[test]
+fn test_apportion_largest_remainder() {
+    let r = |n:i64,d:i64| BigRational::new(n.into(),d.into());
+    assert_eq!(apportion_largest_remainder(&[r(1564,100),r(1518,100),r(1518,100)]),vec![16,15,15]);
+    assert_eq!(apportion_largest_remainder(&[r(1,2),r(1,2),r(1,1)]),vec![1,0,1]);
+    assert_eq!(apportion_largest_remainder(&[]),Vec::<usize>::new());
+}
+
+#[test]
+fn test_transfer_rounding_modes_differ() {
+    let vote_data = ElectionData {
+        metadata: metadata(&["A","B","C","D"],2),
+        btl: vec![
+            btl(&[0,1],34),
+            btl(&[0,2],33),
+            btl(&[0,3],33),
+            btl(&[1],20),
+            btl(&[2],20),
+            btl(&[3],20),
+        ],
+        ..Default::default()
+    };
+    // Quota is 160/3+1 = 54, so A has a surplus of 46 transferred at 46/100, worth 15.64, 15.18 and 15.18 votes to B, C and D.
+    let transcript = vote_data.distribute_preferences::<SimpleRules<false>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.counts[1].status.tallies.candidate[1..],[35,35,35]);
+    assert_eq!(transcript.counts[1].status.tallies.rounding,SignedVersion::from(1));
+    // The largest remainder goes to B, so the whole surplus is transferred.
+    let transcript = vote_data.distribute_preferences::<SimpleRules<true>>(&mut Randomness::ReverseDonkeyVote);
+    assert_eq!(transcript.counts[1].status.tallies.candidate[1..],[36,35,35]);
+    assert_eq!(transcript.counts[1].status.tallies.rounding,SignedVersion::from(0));
+    assert_eq!(transcript.elected,vec![CandidateIndex(0),CandidateIndex(1)]);
+}
@@ -0,0 +1,58 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check that a count with no more candidates than vacancies elects everyone on the first count.
+
+use std::collections::HashSet;
+use stv::ballot_metadata::{CandidateIndex, NumberOfCandidates};
+use stv::distribution_of_preferences_transcript::ElectionReason;
+use stv::election_data::ElectionData;
+use stv::preference_distribution::distribute_preferences;
+use stv::random_util::Randomness;
+use stv::tie_resolution::TieResolutionsMadeByEC;
+
+mod common;
+use common::{btl, metadata, SortedExclusionRules};
+
+/// Candidates A, B, C with first preferences 5, 30 and 10.
+fn make_data(vacancies:usize) -> ElectionData {
+    ElectionData {
+        metadata: metadata(&["A","B","C"],vacancies),
+        btl: vec![
+            btl(&[0,1],5),
+            btl(&[1],30),
+            btl(&[2,0],10),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_more_vacancies_than_candidates() {
+    let data = make_data(5);
+    let transcript = distribute_preferences::<SortedExclusionRules>(&data,NumberOfCandidates(5),&HashSet::new(),&TieResolutionsMadeByEC::default(),None,false,&mut Randomness::ReverseDonkeyVote);
+    assert!(transcript.undersubscribed);
+    assert_eq!(transcript.counts.len(),1);
+    assert_eq!(transcript.elected,vec![CandidateIndex(1),CandidateIndex(2),CandidateIndex(0)]);
+    assert!(transcript.counts[0].elected.iter().all(|e|e.why==ElectionReason::AllRemainingMustBeElected));
+    // survives a round trip, and is not written when false.
+    let json = serde_json::to_string(&transcript).unwrap();
+    assert!(json.contains("\"undersubscribed\":true"));
+    let normal = distribute_preferences::<SortedExclusionRules>(&make_data(1),NumberOfCandidates(1),&HashSet::new(),&TieResolutionsMadeByEC::default(),None,false,&mut Randomness::ReverseDonkeyVote);
+    assert!(!normal.undersubscribed);
+    assert!(!serde_json::to_string(&normal).unwrap().contains("undersubscribed"));
+}
+
+#[test]
+fn test_excluded_candidates_are_not_counted() {
+    // 3 candidates for 2 vacancies, but one is excluded, so the other two are elected.
+    let data = make_data(2);
+    let excluded : HashSet<CandidateIndex> = [CandidateIndex(1)].into_iter().collect();
+    let transcript = distribute_preferences::<SortedExclusionRules>(&data,NumberOfCandidates(2),&excluded,&TieResolutionsMadeByEC::default(),None,false,&mut Randomness::ReverseDonkeyVote);
+    assert!(transcript.undersubscribed);
+    assert_eq!(transcript.counts.len(),1);
+    assert_eq!(transcript.elected,vec![CandidateIndex(2),CandidateIndex(0)]);
+}
@@ -0,0 +1,118 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check validation of .stv files.
+
+use stv::ballot_metadata::{CandidateIndex, PartyIndex};
+use stv::ballot_pile::BallotPaperCount;
+use stv::election_data::ElectionData;
+use stv::validate_election_data::{AtlReconstructionWarning, ValidationProblem};
+
+/// Make a .stv file with two parties A (candidates 0,1) and B (candidate 2), and an ungrouped candidate 3,
+/// with the given vacancies, ATL and BTL json, and extra top level fields.
+fn stv_file(vacancies:&str,atl:&str,btl:&str,extra:&str) -> ElectionData {
+    let json = format!(r#"{{
+      "metadata" : {{
+        "name" : {{ "year":"2024","authority":"Test","name":"Test","electorate":"Test" }},
+        "candidates" : [
+          {{ "name":"A1","party":0,"position":1 }},
+          {{ "name":"A2","party":0,"position":2 }},
+          {{ "name":"B1","party":1,"position":1 }},
+          {{ "name":"U" }}
+        ],
+        "parties" : [
+          {{ "column_id":"A","name":"Party A","atl_allowed":true,"candidates":[0,1] }},
+          {{ "column_id":"B","name":"Party B","atl_allowed":true,"candidates":[2] }}
+        ],
+        "source" : [],
+        "vacancies" : {vacancies}
+      }},
+      "atl" : {atl},
+      "btl" : {btl},
+      "informal" : 0
+      {extra}
+    }}"#);
+    serde_json::from_str(&json).unwrap()
+}
+
+fn problems(data:&ElectionData) -> Vec<ValidationProblem> { data.validate().problems }
+
+#[test]
+fn test_valid_file() {
+    let data = stv_file("2",r#"[{"parties":[0,1],"n":10}]"#,r#"[{"candidates":[3,2,0],"n":5}]"#,"");
+    let report = data.validate();
+    assert!(report.is_ok());
+    assert!(!report.has_fatal_problems());
+}
+
+#[test]
+fn test_malformed_files() {
+    // vacancies missing
+    let data = stv_file("null","[]",r#"[{"candidates":[0],"n":5}]"#,"");
+    assert_eq!(problems(&data),vec![ValidationProblem::VacanciesNotSet]);
+    assert!(data.validate().has_fatal_problems());
+    // too many vacancies is only a warning
+    let data = stv_file("4","[]",r#"[{"candidates":[0],"n":5}]"#,"");
+    assert_eq!(problems(&data),vec![ValidationProblem::NotMoreCandidatesThanVacancies{vacancies:4,candidates:4}]);
+    assert!(!data.validate().has_fatal_problems());
+    // BTL candidate out of range, and duplicated.
+    let data = stv_file("2","[]",r#"[{"candidates":[0,7],"n":5},{"candidates":[1,2,1],"n":3}]"#,"");
+    assert_eq!(problems(&data),vec![ValidationProblem::BTLCandidateOutOfRange{vote:0,candidate:CandidateIndex(7)},ValidationProblem::BTLDuplicateCandidate{vote:1,candidate:CandidateIndex(1)}]);
+    // ATL party out of range, duplicated, and empty.
+    let data = stv_file("2",r#"[{"parties":[0,2],"n":5},{"parties":[1,1],"n":5},{"parties":[],"n":1}]"#,"[]","");
+    assert_eq!(problems(&data),vec![ValidationProblem::ATLPartyOutOfRange{vote:0,party:PartyIndex(2)},ValidationProblem::ATLDuplicateParty{vote:1,party:PartyIndex(1)},ValidationProblem::ATLEmpty{vote:2}]);
+    // ticket that does not exist
+    let data = stv_file("2",r#"[{"parties":[0],"n":5,"ticket_index":0}]"#,"[]","");
+    assert_eq!(problems(&data),vec![ValidationProblem::ATLTicketOutOfRange{vote:0,ticket:0}]);
+    // transfer value range past the end of the BTL votes.
+    let data = stv_file("2","[]",r#"[{"candidates":[0],"n":5}]"#,r#","btl_transfer_values":[{"value":"1/2","first_index_inclusive":0,"last_index_exclusive":2}]"#);
+    assert_eq!(problems(&data),vec![ValidationProblem::InvalidIndexRange{what:"btl_transfer_values".to_string(),first_index_inclusive:0,last_index_exclusive:2,len:1}]);
+    // vote with zero ballots is just a warning.
+    let data = stv_file("2","[]",r#"[{"candidates":[0],"n":0}]"#,"");
+    assert_eq!(problems(&data),vec![ValidationProblem::ZeroBallots{what:"BTL".to_string(),vote:0}]);
+    assert!(!data.validate().has_fatal_problems());
+}
+
+#[test]
+fn test_inconsistent_parties() {
+    let mut data = stv_file("2","[]",r#"[{"candidates":[0],"n":5}]"#,"");
+    data.metadata.parties[1].candidates.push(CandidateIndex(3)); // U is ungrouped, but listed in party B
+    data.metadata.candidates[1].party=Some(PartyIndex(1)); // A2 is listed in party A but claims to be in party B
+    data.metadata.parties[0].candidates.push(CandidateIndex(9));
+    assert_eq!(problems(&data),vec![
+        ValidationProblem::CandidateNotInOwnParty{candidate:CandidateIndex(1),party:PartyIndex(1)},
+        ValidationProblem::PartyCandidateInDifferentParty{party:PartyIndex(0),candidate:CandidateIndex(1)},
+        ValidationProblem::PartyCandidateOutOfRange{party:PartyIndex(0),candidate:CandidateIndex(9)},
+        ValidationProblem::PartyCandidateInDifferentParty{party:PartyIndex(1),candidate:CandidateIndex(3)},
+    ]);
+    assert!(data.validate().to_string().ends_with("4 errors, 0 warnings.\n"));
+}
+
+#[test]
+fn test_atl_reconstruction() {
+    // Officially 10 ATL votes for A and none for B, but the reconstruction has assigned 3 of them to B.
+    let mut data = stv_file("2",r#"[{"parties":[0,1],"n":7},{"parties":[1],"n":3}]"#,r#"[{"candidates":[3,2,0],"n":5}]"#,"");
+    assert_eq!(data.atl_votes_by_party(),vec![BallotPaperCount(7),BallotPaperCount(3)]);
+    assert!(data.validate_atl_reconstruction().is_empty()); // no official numbers to check against.
+    data.metadata.parties[0].official_atl_votes=Some(BallotPaperCount(10));
+    assert_eq!(data.validate_atl_reconstruction(),vec![
+        AtlReconstructionWarning::DifferentFromOfficial{party:PartyIndex(0),actual:BallotPaperCount(7),official:BallotPaperCount(10)},
+        AtlReconstructionWarning::NoOfficialNumber{party:PartyIndex(1),actual:BallotPaperCount(3)},
+    ]);
+    data.metadata.parties[1].official_atl_votes=Some(BallotPaperCount(0));
+    assert_eq!(data.validate_atl_reconstruction(),vec![
+        AtlReconstructionWarning::DifferentFromOfficial{party:PartyIndex(0),actual:BallotPaperCount(7),official:BallotPaperCount(10)},
+        AtlReconstructionWarning::DifferentFromOfficial{party:PartyIndex(1),actual:BallotPaperCount(3),official:BallotPaperCount(0)},
+    ]);
+    assert!(data.validate_atl_reconstruction_with_tolerance(BallotPaperCount(3)).is_empty());
+    assert_eq!(data.validate_atl_reconstruction_with_tolerance(BallotPaperCount(2)).len(),2);
+    // A correct reconstruction.
+    data.atl[1].parties=vec![PartyIndex(0)];
+    assert!(data.validate_atl_reconstruction().is_empty());
+    // The official numbers are kept in the file.
+    let reloaded : ElectionData = serde_json::from_str(&serde_json::to_string(&data).unwrap()).unwrap();
+    assert_eq!(reloaded.metadata.parties[0].official_atl_votes,Some(BallotPaperCount(10)));
+}
@@ -0,0 +1,82 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Contrast candidates withdrawn before the count with those excluded before the count, particularly for the quota.
+
+use stv::ballot_metadata::{CandidateIndex, ElectionMetadata};
+use stv::distribution_of_preferences_transcript::Transcript;
+use stv::election_data::ElectionData;
+use stv::non_election_reasons::WhyNotElected;
+use stv::preference_distribution::PreferenceDistributionRules;
+use stv::random_util::Randomness;
+
+mod common;
+use common::{btl, metadata, SimpleRules, SimpleRulesWith, Variation};
+
+/// Like [SimpleRules], but exhausted votes at the first count are part of the quota base.
+type ExhaustedCountForQuotaRules = SimpleRulesWith<ExhaustedCountForQuota>;
+
+struct ExhaustedCountForQuota {}
+
+impl Variation for ExhaustedCountForQuota {
+    fn should_exhausted_votes_count_for_quota_computation() -> bool { true }
+    fn name() -> String { "ExhaustedCountForQuotaRules".to_string() }
+}
+
+/// Candidates A, B, C, W for one vacancy, with W either excluded or withdrawn before the count.
+/// 10 votes are just for W, and 20 go from W to B.
+fn make_data(withdrawn:bool) -> ElectionData {
+    ElectionData {
+        metadata: ElectionMetadata {
+            excluded: if withdrawn { vec![] } else { vec![CandidateIndex(3)] },
+            withdrawn_before_count: if withdrawn { vec![CandidateIndex(3)] } else { vec![] },
+            ..metadata(&["A","B","C","W"],1)
+        },
+        btl: vec![
+            btl(&[0],40),
+            btl(&[1],30),
+            btl(&[3],10),
+            btl(&[3,1],20),
+            btl(&[2],5),
+        ],
+        ..Default::default()
+    }
+}
+
+fn count<Rules:PreferenceDistributionRules<Tally=usize>>(withdrawn:bool) -> Transcript<usize> {
+    make_data(withdrawn).distribute_preferences::<Rules>(&mut Randomness::ReverseDonkeyVote)
+}
+
+#[test]
+fn test_quota_excluded_versus_withdrawn() {
+    // When exhausted votes count for the quota, the 10 votes just for an excluded W are in the quota base: 105 votes, quota 53.
+    // B gets the 20 votes from W at the first count, with 50 not reaching a quota.
+    let excluded = count::<ExhaustedCountForQuotaRules>(false);
+    assert_eq!(excluded.quota.as_ref().unwrap().quota,53);
+    assert_eq!(excluded.counts[0].status.tallies.candidate,vec![40,50,5,0]);
+    assert_eq!(excluded.counts[0].status.tallies.exhausted,10);
+    assert!(excluded.counts[0].elected.is_empty());
+    // If W withdrew, those 10 votes are as if blank, and not counted: 95 votes, quota 48, so B is elected at the first count.
+    let withdrawn = count::<ExhaustedCountForQuotaRules>(true);
+    assert_eq!(withdrawn.quota.as_ref().unwrap().quota,48);
+    assert_eq!(withdrawn.counts[0].status.tallies.candidate,vec![40,50,5,0]);
+    assert_eq!(withdrawn.counts[0].status.tallies.exhausted,0);
+    assert_eq!(withdrawn.elected,vec![CandidateIndex(1)]);
+    assert_eq!(withdrawn.counts[0].elected.len(),1);
+}
+
+#[test]
+fn test_same_when_exhausted_votes_do_not_count_for_quota() {
+    let excluded = count::<SimpleRules>(false);
+    let withdrawn = count::<SimpleRules>(true);
+    assert_eq!(excluded.quota.as_ref().unwrap().quota,48);
+    assert_eq!(withdrawn.quota.as_ref().unwrap().quota,48);
+    assert_eq!(excluded.elected,withdrawn.elected);
+    assert_eq!(excluded.counts[0].status.tallies.candidate,withdrawn.counts[0].status.tallies.candidate);
+    // the withdrawn candidate is never continuing.
+    let reasons = withdrawn.non_election_reasons(&make_data(true).metadata);
+    assert!(reasons.iter().any(|r|r.candidate==CandidateIndex(3) && r.why==WhyNotElected::ExcludedBeforeCount));
+}
@@ -126,6 +126,8 @@ impl PreferenceDistributionRules for Vic2018LegislativeCouncil {
     /// candidates.
     /// ```
     fn check_elected_if_in_middle_of_exclusion() -> bool { true }
+    /// Subsection (15), quoted above: the surplus votes `are not to be transferred until the remaining votes of the excluded candidate have been transferred`.
+    fn defer_surplus_of_candidate_elected_mid_exclusion() -> bool { true }
 
     /// This is not at all clear. Section 12 says `all that candidate's votes are to be transferred` implying it should be finished.
     /// but sections (19) may shortcut this by `Despite any other provision of this section`.
@@ -18,7 +18,7 @@ use stv::parse_util::{CalamineLikeWrapper, FileFinder, KnowsAboutRawMarkings, Mi
 use stv::tie_resolution::TieResolutionsMadeByEC;
 use crate::Vic2018LegislativeCouncil;
 use calamine::{DataType, open_workbook_auto};
-use stv::ballot_paper::{ATL, BTL, parse_marking, RawBallotMarking, RawBallotMarkings, UniqueBTLBuilder};
+use stv::ballot_paper::{ATL, BTL, GapPolicy, parse_marking, RawBallotMarking, RawBallotMarkings, UniqueBTLBuilder};
 use stv::ballot_pile::BallotPaperCount;
 use stv::distribution_of_preferences_transcript::{CountIndex, PerCandidate, QuotaInfo};
 use stv::signed_version::SignedVersion;
@@ -59,6 +59,8 @@ pub struct VicDataLoader {
     archive_location : String,
     year : String,
     page_url : String,
+    /// How to interpret ballots with a gap in their numbering.
+    gap_policy : GapPolicy,
 }
 
 impl KnowsAboutRawMarkings for VicDataLoader {}
@@ -130,7 +132,7 @@ impl RawDataSource for VicDataLoader {
                     for i in 0..metadata.candidates.len() {
                         btl_markings[i]=parse_marking(&record[1+i]);
                     }
-                    if let Some(btl) = (RawBallotMarkings{ atl: &[], btl: &btl_markings, atl_parties: &[] }).interpret_vote_as_btl(min_btl_prefs_needed) {
+                    if let Some(btl) = (RawBallotMarkings{ atl: &[], btl: &btl_markings, atl_parties: &[] }).interpret_vote_as_btl_with_gap_policy(min_btl_prefs_needed,self.gap_policy) {
                         /* Code below used to debug an error in the VEC transcript for North Eastern Region, 2022, where on count 169 a paper was strangely transferred to DOLAN, Hugh.
                         if let Some(p1) = btl.candidates.iter().position(|c|c.0==32) {
                             if let Some(p2) = btl.candidates.iter().position(|c|c.0==25) {
@@ -162,10 +164,14 @@ impl RawDataSource for VicDataLoader {
             btl : builder.to_btls(),
             btl_types: vec![],
             btl_transfer_values: vec![],
+            atl_annotations: vec![],
+            btl_annotations: vec![],
             informal
         };
         if atl_votes.len()>0 { // the ATL votes are converted to BTL already. Deduce them (and tickets) from the BTL via the (ugly, unreliable) method of assuming the largest number of full length BTL votes starting with a given candidate is it.
             find_atl_votes_in_btl(&mut result,&atl_votes)?;
+            for (party,&votes) in result.metadata.parties.iter_mut().zip(atl_votes.iter()) { party.official_atl_votes=Some(votes); }
+            for warning in result.validate_atl_reconstruction() { println!("Warning for {} : {}",electorate,warning); }
         }
         if self.election_count_not_published_yet() { // in this case the file above is only partial results. Add an estimate of the remaining results from the count by first preference.
             let early_preference_votes = self.parse_results_by_region_intermediate_webpage(&mut result.metadata)?;
@@ -244,6 +250,11 @@ impl RawDataSource for VicDataLoader {
         let format = DOPFileFormat::new(&sheet1,metadata).context(path.to_string_lossy().to_string())?;
         format.parse_spreadsheet(&sheet1,metadata).context(path.to_string_lossy().to_string())
     }
+
+    fn set_gap_policy(&mut self,gap_policy:GapPolicy) -> anyhow::Result<()> {
+        self.gap_policy=gap_policy;
+        Ok(())
+    }
 }
 
 /// Deduce ATL votes and tickets from the BTL votes given the number of ATL votes, given that the ATL votes are already turned into BTL votes.
@@ -394,7 +405,8 @@ impl VicDataLoader {
                                     abbreviation: None,
                                     atl_allowed: had_atl,
                                     candidates: vec![],
-                                    tickets: vec![]
+                                    tickets: vec![],
+                                    official_atl_votes: None
                                 });
                                 if !had_atl {
                                     if atl_votes.len()+1!=parties.len() {
@@ -437,6 +449,7 @@ impl VicDataLoader {
             enrolment: None,
             secondary_vacancies: None,
             excluded: vec![],
+            withdrawn_before_count: vec![],
             tie_resolutions: Default::default()
         };
         self.reorder_candidates_in_metadata_by_official_dop_transcript(&mut metadata)?;
@@ -470,7 +483,8 @@ impl VicDataLoader {
                     abbreviation: None,
                     atl_allowed: name!="Ungrouped",
                     candidates: vec![],
-                    tickets: vec![]
+                    tickets: vec![],
+                    official_atl_votes: None
                 });
                 position_in_party=1;
             } else if let Some(candidate_name) = tr.select(&scraper::Selector::parse("tr.candidate-row td").unwrap()).next() {
@@ -500,6 +514,7 @@ impl VicDataLoader {
             enrolment: None,
             secondary_vacancies: None,
             excluded: vec![],
+            withdrawn_before_count: vec![],
             tie_resolutions: Default::default()
         };
         Ok(metadata)
@@ -870,6 +885,7 @@ impl VicDataLoader {
             archive_location,
             year: year.to_string(),
             page_url: page_url.to_string(),
+            gap_policy: GapPolicy::default(),
         })
     }
 
@@ -170,6 +170,8 @@ impl PreferenceDistributionRules for WALegislativeCouncil {
     /// transfer.
     ///
     fn check_elected_if_in_middle_of_exclusion() -> bool { true }
+    /// Clause 9, quoted above: `the surplus votes (if any) of the candidate so elected shall not be transferred until the remaining votes of the excluded candidate have been transferred`.
+    fn defer_surplus_of_candidate_elected_mid_exclusion() -> bool { true }
 
     /// There is no real mention of when the election ends, just things that should be done.
     /// So there is an argument that one should continue and do the things that are said to be done,
@@ -125,6 +125,7 @@ impl RawDataSource for WADataLoader {
             enrolment: None,
             secondary_vacancies: None,
             excluded: vec![],
+            withdrawn_before_count: vec![],
             tie_resolutions: Default::default()
         };
         if let Some((region,winners)) = parse_json_lc_elected_members(&path_winners)?.into_iter().find(|(region,_)|region.name==electorate) {
@@ -482,6 +483,7 @@ fn parse_json_lc_candidate_party(path:&PathBuf,region:&str) -> anyhow::Result<(V
                     atl_allowed: true,
                     candidates: vec![],
                     tickets: vec![],
+                    official_atl_votes: None,
                 });
                 last_column=Some(column);
             }
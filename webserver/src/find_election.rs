@@ -13,6 +13,7 @@ use federal::parse::FederalDataSource;
 use nsw::parse_lge::NSWLGEDataSource;
 use stv::datasource_description::{AssociatedRules, Copyright, ElectionDataSource};
 use serde::{Deserialize,Serialize};
+use utoipa::{IntoParams, ToSchema};
 use examples::example_datasource::ExampleDataSource;
 use nsw::parse_lc::NSWLCDataSource;
 use statistics::simple_statistics::SimpleStatistics;
@@ -39,7 +40,7 @@ fn get_all_elections_as_list() -> anyhow::Result<Vec<ElectionsOfOneType>> {
 }
 
 /// Information similar to that found in ElectionDataSource but usable outside.
-#[derive(Clone,Debug,Serialize,Deserialize)]
+#[derive(Clone,Debug,Serialize,Deserialize,ToSchema)]
 pub struct ElectionsOfOneType {
     pub name : String,
     /// the name of the electoral commission that administers it.
@@ -65,15 +66,17 @@ impl ElectionsOfOneType {
         })
     }
 }
-#[derive(Clone,Debug,Serialize,Deserialize)]
+#[derive(Clone,Debug,Serialize,Deserialize,ToSchema)]
 pub struct ElectionsOfOneTypeAndYear {
     pub year : String,
     pub electorates : Vec<String>,
 }
 
 
-#[derive(Debug,Serialize,Deserialize,Clone,Eq,PartialEq,Hash)]
+#[derive(Debug,Serialize,Deserialize,Clone,Eq,PartialEq,Hash,IntoParams)]
+#[into_params(parameter_in=Path)]
 pub struct TextElectionSpecification {
+    /// the name of the type of election, as listed in get_all_contests.json
     pub name : String,
     pub year : String,
     pub electorate : String,
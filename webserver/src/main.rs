@@ -6,29 +6,41 @@
 
 mod find_election;
 mod cache;
+mod openapi;
 
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use actix_files::NamedFile;
 use actix_web::{HttpServer, middleware, web};
 use actix_web::web::Json;
 use actix_web::{get, post};
 use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
-use main_app::rules::{PossibleTranscripts, Rules, RulesDetails};
+use main_app::seat_stability::{stability_report, StabilityReport};
+use main_app::rules::{PossibleExhaustionBreakdowns, PossibleExhaustionCurves, PossibleFinalSeatAnalyses, PossibleFirstPreferences, PossibleNonElectionReasons, PossibleTranscripts, PossibleTwoCandidatePreferred, Rules, RulesCountOptions, RulesDetails};
 use statistics::correlations::{CorrelationDendrogramsAndSVD, CorrelationOptions, SquareMatrix};
 use stv::find_vote::{FindMyVoteQuery, FindMyVoteResult};
 use statistics::intent_table::{IntentTable, IntentTableOptions};
 use statistics::mean_preference::MeanPreferences;
 use statistics::who_got_votes::WhoGotVotes;
+use statistics::effective_numbers::EffectiveNumbers;
+use statistics::viability_threshold::ViabilityThreshold;
+use statistics::ballot_weights::BallotWeights;
 use stv::ballot_metadata::{CandidateIndex, ElectionMetadata, NumberOfCandidates};
+use stv::election_data::ElectionData;
+use stv::distribution_of_preferences_transcript::CountIndex;
 use stv::errors_btl::ObviousErrorsInBTLVotes;
 use stv::tie_resolution::TieResolutionsMadeByEC;
-use crate::cache::cache_json;
-use crate::find_election::{ALL_ELECTIONS_AS_LIST, ElectionInfo, ElectionsOfOneType, FoundElection};
+use crate::cache::{cache_async, cache_json};
+use crate::find_election::{ALL_ELECTIONS_AS_LIST, ElectionInfo, ElectionsOfOneType, FoundElection, TextElectionSpecification};
 use serde::{Serialize,Deserialize};
+use utoipa::{IntoParams, ToSchema};
 use examples::example_datasource::ExampleDataSource;
 use stv::datasource_description::ElectionDataSource;
 use stv::random_util::Randomness;
 
+#[utoipa::path(responses((status=200,description="All the elections available",body=Vec<ElectionsOfOneType>)))]
 #[get("/get_all_contests.json")]
 async fn get_all_contests() -> Json<Result<Vec<ElectionsOfOneType>,String>> {
     let contests : &anyhow::Result<Vec<ElectionsOfOneType>> = &ALL_ELECTIONS_AS_LIST;
@@ -38,16 +50,19 @@ async fn get_all_contests() -> Json<Result<Vec<ElectionsOfOneType>,String>> {
     })
 }
 
+#[utoipa::path(params(TextElectionSpecification),responses((status=200,description="Candidates, parties and other metadata for the election",body=Object)))]
 #[get("/{name}/{year}/{electorate}/metadata.json")]
 async fn get_metadata(election : web::Path<FoundElection>) -> Json<Result<ElectionMetadata,String>> {
     cache_json("metadata.json",&election.spec,||election.metadata()).await
 }
 
+#[utoipa::path(params(TextElectionSpecification),responses((status=200,description="Simple statistics about the election and where the data came from",body=Object)))]
 #[get("/{name}/{year}/{electorate}/info.json")]
 async fn get_info(election : web::Path<FoundElection>) -> Json<Result<ElectionInfo,String>> {
     cache_json("simple.json",&election.spec,||election.get_info()).await
 }
 
+#[utoipa::path(params(TextElectionSpecification),responses((status=200,description="The mean preference given to each candidate",body=Object)))]
 #[get("/{name}/{year}/{electorate}/MeanPreferences.json")]
 async fn get_mean_preferences(election : web::Path<FoundElection>) -> Json<Result<MeanPreferences,String>> {
     async fn get_mean_preferences_uncached(election : &web::Path<FoundElection>) -> Result<MeanPreferences,String> {
@@ -57,23 +72,113 @@ async fn get_mean_preferences(election : web::Path<FoundElection>) -> Json<Resul
 }
 
 
+/// If `count` is given, weights for each ballot paper of its value at the end of that count of the election's recommended (or else official) rules.
+fn ballot_weights_at_count(election:&FoundElection,data:&ElectionData,count:Option<CountIndex>) -> Result<Option<BallotWeights>,String> {
+    let Some(count) = count else { return Ok(None) };
+    let rules = FirstPreferencesQuery{rules:None}.rules_for(election)?;
+    let vacancies = data.metadata.vacancies.ok_or_else(||"Need to specify number of vacancies".to_string())?;
+    let transcript = rules.count(data,vacancies,&data.metadata.excluded.iter().cloned().collect(),&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote,&[],true);
+    let weights = match &transcript {
+        PossibleTranscripts::Integers(t) => BallotWeights::value_at_count(data,&t.transcript,count),
+        PossibleTranscripts::SignedIntegers(t) => BallotWeights::value_at_count(data,&t.transcript,count),
+        PossibleTranscripts::SixDigitDecimals(t) => BallotWeights::value_at_count(data,&t.transcript,count),
+        PossibleTranscripts::RuntimeDecimals(t) => BallotWeights::value_at_count(data,&t.transcript,count),
+    };
+    weights.map(Some).map_err(|e|e.to_string())
+}
+
+#[utoipa::path(params(TextElectionSpecification,IntentTableOptions),responses((status=200,description="Where the votes of voters who put given candidates or groups first went",body=Object)))]
 #[get("/{name}/{year}/{electorate}/IntentTable.json")]
 async fn get_intent_table(election : web::Path<FoundElection>,options : web::Query<IntentTableOptions>) -> Json<Result<IntentTable,String>> {
     async fn get_intent_table_uncached(election : &web::Path<FoundElection>,options : &web::Query<IntentTableOptions>) -> Result<IntentTable,String> {
-        Ok(IntentTable::compute(&election.data().await?,options))
+        let data = election.data().await?;
+        let weights = ballot_weights_at_count(election,&data,options.weight_by_value_at_count)?;
+        Ok(IntentTable::compute_weighted(&data,options,weights.as_ref()))
     }
     cache_json("IntentTable.json",&(election.spec.clone(),options.0.clone()),||get_intent_table_uncached(&election,&options)).await
 }
 
+#[utoipa::path(params(TextElectionSpecification,CorrelationOptions),responses((status=200,description="Correlations between how candidates or groups are preferenced",body=Object)))]
 #[get("/{name}/{year}/{electorate}/Correlation.json")]
 async fn get_correlation(election : web::Path<FoundElection>,options : web::Query<CorrelationOptions>) -> Json<Result<CorrelationDendrogramsAndSVD,String>> {
     async fn get_correlation_uncached(election : &web::Path<FoundElection>,options : &web::Query<CorrelationOptions>) -> Result<CorrelationDendrogramsAndSVD,String> {
-        let correlation = SquareMatrix::compute_correlation_matrix(&election.data().await?,&options).to_distance_matrix();
+        let data = election.data().await?;
+        let weights = ballot_weights_at_count(election,&data,options.weight_by_value_at_count)?;
+        let correlation = SquareMatrix::compute_weighted_correlation_matrix(&data,&options,weights.as_ref()).to_distance_matrix();
         Ok(CorrelationDendrogramsAndSVD::new(correlation)?)
     }
     cache_json("Correlation.json",&(election.spec.clone(),options.0.clone()),||get_correlation_uncached(&election,&options)).await
 }
 
+#[derive(Serialize,Deserialize,Clone,IntoParams)]
+#[into_params(parameter_in=Query)]
+pub struct FirstPreferencesQuery {
+    /// The name of the rules to use, as listed in rules.json. If absent, the rules recommended for (or else used in) the election.
+    #[param(value_type=Option<String>)]
+    pub rules : Option<Rules>,
+}
+
+impl FirstPreferencesQuery {
+    /// The rules asked for, or else the rules recommended for (or else used in) the election.
+    fn rules_for(&self,election:&FoundElection) -> Result<Rules,String> {
+        match self.rules {
+            Some(rules) => Ok(rules),
+            None => {
+                let associated = election.loader.rules(election.electorate());
+                let name = associated.rules_recommended.or(associated.rules_used).ok_or_else(||"No rules known for this election".to_string())?;
+                Ok(Rules::from_str(&name)?)
+            }
+        }
+    }
+}
+
+#[utoipa::path(params(TextElectionSpecification,FirstPreferencesQuery),responses((status=200,description="The first preference tallies, the quota, and which candidates are already over quota, without doing a full count",body=Object)))]
+#[get("/{name}/{year}/{electorate}/FirstPreferences.json")]
+async fn get_first_preferences(election : web::Path<FoundElection>,query : web::Query<FirstPreferencesQuery>) -> Json<Result<PossibleFirstPreferences,String>> {
+    async fn get_first_preferences_uncached(election : &web::Path<FoundElection>,query : &FirstPreferencesQuery) -> Result<PossibleFirstPreferences,String> {
+        let rules = query.rules_for(election)?;
+        let data = election.data().await?;
+        let vacancies = data.metadata.vacancies.ok_or_else(||"Need to specify number of vacancies".to_string())?;
+        Ok(rules.first_preferences(&data,vacancies,&data.metadata.excluded.iter().cloned().collect(),&data.metadata.tie_resolutions,None,&mut Randomness::ReverseDonkeyVote))
+    }
+    cache_json("FirstPreferences.json",&(election.spec.clone(),query.0.clone()),||get_first_preferences_uncached(&election,&query)).await
+}
+
+#[utoipa::path(params(TextElectionSpecification,FirstPreferencesQuery),responses((status=200,description="The cumulative exhausted value, as a value and as a fraction of the formal vote, and separately the value lost to rounding, at the end of each count",body=Object)))]
+#[get("/{name}/{year}/{electorate}/ExhaustionCurve.json")]
+async fn get_exhaustion_curve(election : web::Path<FoundElection>,query : web::Query<FirstPreferencesQuery>) -> Json<Result<PossibleExhaustionCurves,String>> {
+    async fn get_exhaustion_curve_uncached(election : &web::Path<FoundElection>,query : &FirstPreferencesQuery) -> Result<PossibleExhaustionCurves,String> {
+        let rules = query.rules_for(election)?;
+        let data = election.data().await?;
+        let vacancies = data.metadata.vacancies.ok_or_else(||"Need to specify number of vacancies".to_string())?;
+        let transcript = rules.count(&data,vacancies,&data.metadata.excluded.iter().cloned().collect(),&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote,&[],false);
+        Ok(transcript.exhaustion_curve())
+    }
+    cache_json("ExhaustionCurve.json",&(election.spec.clone(),query.0.clone()),||get_exhaustion_curve_uncached(&election,&query)).await
+}
+
+#[utoipa::path(params(TextElectionSpecification,FirstPreferencesQuery),responses((status=200,description="For a single seat contest, the last two candidates standing and their tallies, highest first, as [candidate,tally,candidate,tally]",body=Object)))]
+#[get("/{name}/{year}/{electorate}/TwoCandidatePreferred.json")]
+async fn get_two_candidate_preferred(election : web::Path<FoundElection>,query : web::Query<FirstPreferencesQuery>) -> Json<Result<PossibleTwoCandidatePreferred,String>> {
+    async fn get_two_candidate_preferred_uncached(election : &web::Path<FoundElection>,query : &FirstPreferencesQuery) -> Result<PossibleTwoCandidatePreferred,String> {
+        let rules = query.rules_for(election)?;
+        let data = election.data().await?;
+        let vacancies = data.metadata.vacancies.ok_or_else(||"Need to specify number of vacancies".to_string())?;
+        if vacancies.0!=1 { return Err("Two candidate preferred is only defined for single seat contests".to_string()); }
+        let excluded = data.metadata.excluded.iter().cloned().collect();
+        let transcript = rules.count(&data,vacancies,&excluded,&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote,&[],false);
+        match transcript.final_two_candidate_preferred() {
+            Some(tcp) => Ok(tcp),
+            None => { // the count stopped before it got down to two candidates, e.g. someone had a majority. For a single seat this doesn't change who wins, so continue the count.
+                let transcript = Rules::IRVContinueToTwo.count(&data,vacancies,&excluded,&data.metadata.tie_resolutions,None,false,&mut Randomness::ReverseDonkeyVote,&[],false);
+                transcript.final_two_candidate_preferred().ok_or_else(||"The count did not get down to two candidates".to_string())
+            }
+        }
+    }
+    cache_json("TwoCandidatePreferred.json",&(election.spec.clone(),query.0.clone()),||get_two_candidate_preferred_uncached(&election,&query)).await
+}
+
+#[utoipa::path(params(TextElectionSpecification),responses((status=200,description="How many votes mentioned each candidate, and at what preference",body=Object)))]
 #[get("/{name}/{year}/{electorate}/WhoGotVotes.json")]
 async fn get_who_got_votes(election : web::Path<FoundElection>) -> Json<Result<WhoGotVotes,String>> {
     async fn get_who_got_votes_uncached(election : &web::Path<FoundElection>) -> Result<WhoGotVotes,String> {
@@ -82,6 +187,34 @@ async fn get_who_got_votes(election : web::Path<FoundElection>) -> Json<Result<W
     cache_json("WhoGotVotes.json",&election.spec,||get_who_got_votes_uncached(&election)).await
 }
 
+#[utoipa::path(params(TextElectionSpecification),responses((status=200,description="The Laakso-Taagepera effective number of candidates and parties by first preferences",body=Object)))]
+#[get("/{name}/{year}/{electorate}/EffectiveNumbers.json")]
+async fn get_effective_numbers(election : web::Path<FoundElection>) -> Json<Result<EffectiveNumbers,String>> {
+    async fn get_effective_numbers_uncached(election : &web::Path<FoundElection>) -> Result<EffectiveNumbers,String> {
+        Ok(EffectiveNumbers::compute(&election.data().await?))
+    }
+    cache_json("EffectiveNumbers.json",&election.spec,||get_effective_numbers_uncached(&election)).await
+}
+
+#[derive(Serialize,Deserialize,Clone,IntoParams)]
+#[into_params(parameter_in=Query)]
+pub struct ViabilityThresholdQuery {
+    /// The fraction of first preferences needed to be above the threshold. If absent, 0.04, the fraction needed for a deposit to be returned in federal elections.
+    pub threshold : Option<f64>,
+}
+
+#[utoipa::path(params(TextElectionSpecification,ViabilityThresholdQuery),responses((status=200,description="Which candidates and parties got at least a given fraction of the first preferences",body=Object)))]
+#[get("/{name}/{year}/{electorate}/ViabilityThreshold.json")]
+async fn get_viability_threshold(election : web::Path<FoundElection>,query : web::Query<ViabilityThresholdQuery>) -> Json<Result<ViabilityThreshold,String>> {
+    async fn get_viability_threshold_uncached(election : &web::Path<FoundElection>,query : &ViabilityThresholdQuery) -> Result<ViabilityThreshold,String> {
+        let threshold = query.threshold.unwrap_or(0.04);
+        if !(0.0..=1.0).contains(&threshold) { return Err("threshold should be between 0 and 1".to_string()); }
+        Ok(ViabilityThreshold::compute(&election.data().await?,threshold))
+    }
+    cache_json("ViabilityThreshold.json",&(election.spec.clone(),query.0.clone()),||get_viability_threshold_uncached(&election,&query)).await
+}
+
+#[utoipa::path(params(TextElectionSpecification),responses((status=200,description="Obvious errors in below the line votes, such as repeated numbers",body=Object)))]
 #[get("/{name}/{year}/{electorate}/RepeatedNumbers.json")]
 async fn get_find_btl_errors(election : web::Path<FoundElection>) -> Json<Result<ObviousErrorsInBTLVotes,String>> {
     async fn get_find_btl_errors_uncached(election : &web::Path<FoundElection>) -> Result<ObviousErrorsInBTLVotes,String> {
@@ -90,13 +223,33 @@ async fn get_find_btl_errors(election : web::Path<FoundElection>) -> Json<Result
     cache_json("WhoGotVotes.json",&election.spec,||get_find_btl_errors_uncached(&election)).await
 }
 
+#[derive(Serialize,Deserialize,Clone,IntoParams)]
+#[into_params(parameter_in=Query)]
+pub struct SeatStabilityQuery {
+    /// The names of the rules to compare, as listed in rules.json, separated by commas.
+    pub rules : String,
+}
+
+#[utoipa::path(params(TextElectionSpecification,SeatStabilityQuery),responses((status=200,description="Which seats are elected under all the given rules and not by a small margin (robust), and which are not (fragile)",body=Object)))]
+#[get("/{name}/{year}/{electorate}/SeatStability.json")]
+async fn get_seat_stability(election : web::Path<FoundElection>,query : web::Query<SeatStabilityQuery>) -> Json<Result<StabilityReport,String>> {
+    async fn get_seat_stability_uncached(election : &web::Path<FoundElection>,query : &SeatStabilityQuery) -> Result<StabilityReport,String> {
+        let rules = query.rules.split(',').map(Rules::from_str).collect::<Result<Vec<_>,_>>()?;
+        let data = election.data().await?;
+        web::block(move||stability_report(&data,&rules).map_err(|e|e.to_string())).await.map_err(|e|e.to_string())?
+    }
+    cache_json("SeatStability.json",&(election.spec.clone(),query.0.clone()),||get_seat_stability_uncached(&election,&query)).await
+}
+
 
 
+#[utoipa::path(params(TextElectionSpecification),request_body=FindMyVoteQuery,responses((status=200,description="The official ballots most similar to the query",body=FindMyVoteResult)))]
 #[post("/{name}/{year}/{electorate}/find_my_vote")]
 async fn find_my_vote(election : web::Path<FoundElection>,query:Json<FindMyVoteQuery>) -> Json<Result<FindMyVoteResult,String>> {
     Json(election.loader.find_my_vote(election.electorate(),&query).map_err(|e|e.to_string()))
 }
 
+#[utoipa::path(params(TextElectionSpecification),responses((status=200,description="The votes for the election, in ConcreteSTV's .stv format",content_type="application/octet-stream",body=Object)))]
 #[get("/{name}/{year}/{electorate}/data.stv")]
 async fn get_data(election : web::Path<FoundElection>) -> std::io::Result<NamedFile> {
     let cached_path = election.data().await.map_err(|e|std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?.metadata.name.cache_file_name();
@@ -110,19 +263,24 @@ async fn get_data(election : web::Path<FoundElection>) -> std::io::Result<NamedF
         }))
 }
 
+#[utoipa::path(responses((status=200,description="The counting rules supported by recount",body=Object)))]
 #[get("/rules.json")]
 async fn get_rules() -> Json<Vec<RulesDetails>> {
     Json(RulesDetails::list())
 }
 
-#[derive(Serialize,Deserialize,Clone)]
+#[derive(Serialize,Deserialize,Clone,ToSchema)]
 pub struct RecountQuery {
     /// Candidates who are usually excluded, e.g. if they died on the election day or were ruled ineligible to stand. Looking at you 2016.
     #[serde(skip_serializing_if = "Vec::is_empty",default)]
+    #[schema(value_type=Vec<usize>)]
     pub excluded : Vec<CandidateIndex>,
+    #[schema(value_type=usize)]
     pub candidates_to_be_elected : NumberOfCandidates,
     #[serde(flatten)]
     pub tie_resolutions : TieResolutionsMadeByEC,
+    /// The name of the rules to use, as listed in rules.json
+    #[schema(value_type=String)]
     pub rules : Rules,
     /// if none, use all votes. Otherwise only use ones specified in here. "" means votes not assigned a type.
     pub vote_types : Option<Vec<String>>,
@@ -130,18 +288,120 @@ pub struct RecountQuery {
     pub seed : Option<u64>,
 }
 
+#[utoipa::path(params(TextElectionSpecification),request_body=RecountQuery,responses((status=200,description="The transcript of the count",body=Object)))]
 #[post("/{name}/{year}/{electorate}/recount")]
 async fn recount(election : web::Path<FoundElection>,query:Json<RecountQuery>) -> Json<Result<PossibleTranscripts,String>> {
-    async fn recount_uncached(election : &web::Path<FoundElection>,query:&RecountQuery) -> Result<PossibleTranscripts,String> {
+    Json(recount_cached(&election,&query).await)
+}
+
+/// Do the count for [recount], or get it from the cache if it has already been done.
+async fn recount_cached(election : &FoundElection,query:&RecountQuery) -> Result<PossibleTranscripts,String> {
+    async fn recount_uncached(election : &FoundElection,query:&RecountQuery) -> Result<PossibleTranscripts,String> {
         let vote_types : Option<&[String]> = if let Some(vt) = &query.vote_types { Some(vt) } else { None };
         let mut randomness : Randomness = query.seed.into();
         let include_list_of_votes_in_transcript = election.spec.name.as_str()==ExampleDataSource{}.name().as_ref();
         Ok(query.rules.count(&election.data().await?,query.candidates_to_be_elected,&query.excluded.iter().cloned().collect(),&query.tie_resolutions,vote_types,false,&mut randomness,&[],include_list_of_votes_in_transcript))
     }
-    cache_json("recount",&(election.spec.clone(),query.clone()),||recount_uncached(&election,&query)).await
+    cache_async("recount",&(election.spec.clone(),query.clone()),||recount_uncached(election,query)).await
+}
+
+#[utoipa::path(params(TextElectionSpecification,FirstPreferencesQuery),responses((status=200,description="For each count and cumulatively, the value exhausted and separately the value lost to rounding",body=Object)))]
+#[get("/{name}/{year}/{electorate}/ExhaustionBreakdown.json")]
+async fn get_exhaustion_breakdown(election : web::Path<FoundElection>,query : web::Query<FirstPreferencesQuery>) -> Json<Result<PossibleExhaustionBreakdowns,String>> {
+    async fn get_exhaustion_breakdown_uncached(election : &FoundElection,query : &FirstPreferencesQuery) -> Result<PossibleExhaustionBreakdowns,String> {
+        let data = election.data().await?;
+        let candidates_to_be_elected = data.metadata.vacancies.ok_or_else(||"Need to specify number of vacancies".to_string())?;
+        let recount_query = RecountQuery{excluded:data.metadata.excluded.clone(),candidates_to_be_elected,tie_resolutions:data.metadata.tie_resolutions.clone(),rules:query.rules_for(election)?,vote_types:None,seed:None};
+        // the count is cached by recount_cached, and summarizing it is fast, so there is no need to cache the summary too.
+        Ok(recount_cached(election,&recount_query).await?.exhaustion_breakdown())
+    }
+    Json(get_exhaustion_breakdown_uncached(&election,&query).await)
 }
 
+#[utoipa::path(params(TextElectionSpecification,FirstPreferencesQuery),responses((status=200,description="The contest for the last seat awarded : the winner, the runner-up, the difference in their tallies, and any tie resolutions involving them",body=Object)))]
+#[get("/{name}/{year}/{electorate}/FinalSeat.json")]
+async fn get_final_seat(election : web::Path<FoundElection>,query : web::Query<FirstPreferencesQuery>) -> Json<Result<PossibleFinalSeatAnalyses,String>> {
+    async fn get_final_seat_uncached(election : &FoundElection,query : &FirstPreferencesQuery) -> Result<PossibleFinalSeatAnalyses,String> {
+        let data = election.data().await?;
+        let candidates_to_be_elected = data.metadata.vacancies.ok_or_else(||"Need to specify number of vacancies".to_string())?;
+        let recount_query = RecountQuery{excluded:data.metadata.excluded.clone(),candidates_to_be_elected,tie_resolutions:data.metadata.tie_resolutions.clone(),rules:query.rules_for(election)?,vote_types:None,seed:None};
+        recount_cached(election,&recount_query).await?.final_seat_analysis().ok_or_else(||"No one was elected".to_string())
+    }
+    Json(get_final_seat_uncached(&election,&query).await)
+}
 
+/// The largest body accepted by [count], in bytes. This is enough for the largest real elections.
+const MAX_COUNT_UPLOAD_BYTES : usize = 64*1024*1024;
+/// How long [count] will wait for a count to finish before giving up.
+const COUNT_TIME_BUDGET : std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(Serialize,Deserialize,Clone,ToSchema)]
+pub struct CountQuery {
+    /// The election data, as in a .stv file.
+    #[schema(value_type=Object)]
+    pub data : ElectionData,
+    /// How to do the count.
+    pub options : RecountQuery,
+}
+
+/// Count an uploaded election, rather than one from the catalog. The body is limited to [MAX_COUNT_UPLOAD_BYTES].
+/// If the count takes longer than [COUNT_TIME_BUDGET] an error is returned, and the count is cancelled at the start of its next count so it doesn't keep using the blocking thread pool.
+#[utoipa::path(post,path="/count",request_body=CountQuery,responses((status=200,description="The transcript of the count",body=Object)))]
+async fn count(query:Json<CountQuery>) -> Json<Result<PossibleTranscripts,String>> {
+    let CountQuery{data,options} = query.into_inner();
+    let cancel = Arc::new(AtomicBool::new(false));
+    let cancel_for_count = cancel.clone();
+    let work = web::block(move||{
+        let vote_types : Option<&[String]> = if let Some(vt) = &options.vote_types { Some(vt) } else { None };
+        let mut randomness : Randomness = options.seed.into();
+        options.rules.count_with_options(&data,options.candidates_to_be_elected,&options.excluded.iter().cloned().collect(),&options.tie_resolutions,&mut randomness,RulesCountOptions{ vote_types, cancel: Some(&cancel_for_count), ..Default::default() })
+    });
+    Json(match actix_web::rt::time::timeout(COUNT_TIME_BUDGET,work).await {
+        Ok(Ok(transcript)) => Ok(transcript),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => {
+            cancel.store(true,Ordering::Relaxed);
+            Err(format!("The count took longer than the limit of {} seconds.",COUNT_TIME_BUDGET.as_secs()))
+        }
+    })
+}
+
+
+#[derive(Serialize,Deserialize,Clone,ToSchema)]
+pub struct CertainLosersQuery {
+    /// A transcript, possibly only containing the counts done so far.
+    #[schema(value_type=Object)]
+    pub transcript : PossibleTranscripts,
+    /// The count to look at. If not given, the last count in the transcript.
+    #[serde(skip_serializing_if = "Option::is_none",default)]
+    #[schema(value_type=Option<usize>)]
+    pub count : Option<CountIndex>,
+}
+
+/// Find which continuing candidates are certain to lose at a given point in a (possibly partial) count. See [stv::certain_losers].
+#[utoipa::path(request_body=CertainLosersQuery,responses((status=200,description="The continuing candidates certain to lose",body=Vec<usize>)))]
+#[post("/certain_losers")]
+async fn certain_losers(query:Json<CertainLosersQuery>) -> Json<Result<Vec<CandidateIndex>,String>> {
+    let num_counts = query.transcript.num_counts();
+    Json(match query.count.or_else(||num_counts.checked_sub(1).map(CountIndex)) {
+        Some(count) if count.0<num_counts => Ok(query.transcript.certain_losers_at_count(count)),
+        _ => Err(format!("No such count. The transcript has {} counts.",num_counts)),
+    })
+}
+
+#[derive(Serialize,Deserialize,Clone,ToSchema)]
+pub struct NonElectionReasonsQuery {
+    /// A transcript of a count.
+    #[schema(value_type=Object)]
+    pub transcript : PossibleTranscripts,
+}
+
+/// For each candidate not elected in a transcript, whether they were excluded (and at which count) or were still continuing when all seats were filled, and their final tally. See [stv::non_election_reasons].
+#[utoipa::path(request_body=NonElectionReasonsQuery,responses((status=200,description="Why each candidate not elected was not elected",body=Object)))]
+#[post("/non_election_reasons")]
+async fn non_election_reasons(query:Json<NonElectionReasonsQuery>) -> Json<PossibleNonElectionReasons> {
+    Json(query.transcript.non_election_reasons())
+}
 
 /// find the path containing web resources, static web files that will be served.
 /// This is usually in the directory `WebResources` but the program may be run from
@@ -182,12 +442,24 @@ async fn main() -> anyhow::Result<()> {
             .service(get_mean_preferences)
             .service(get_intent_table)
             .service(get_correlation)
+            .service(get_first_preferences)
+            .service(get_exhaustion_curve)
+            .service(get_exhaustion_breakdown)
+            .service(get_final_seat)
+            .service(get_two_candidate_preferred)
             .service(get_who_got_votes)
+            .service(get_effective_numbers)
+            .service(get_viability_threshold)
             .service(get_find_btl_errors)
+            .service(get_seat_stability)
             .service(find_my_vote)
             .service(get_data)
             .service(get_rules)
             .service(recount)
+            .service(web::resource("/count").app_data(web::JsonConfig::default().limit(MAX_COUNT_UPLOAD_BYTES)).route(web::post().to(count)))
+            .service(certain_losers)
+            .service(non_election_reasons)
+            .service(openapi::get_openapi)
             .service(actix_files::Files::new("/{a}/{b}/{c}/", find_web_resources().join("ContestDirectory")).use_last_modified(true).use_etag(true).index_file("index.html"))
             .service(actix_files::Files::new("/Viewer/", find_viewer_resources()).use_last_modified(true).use_etag(true))
             .service(actix_files::Files::new("/", find_web_resources().join("RootDirectory")).use_last_modified(true).use_etag(true).index_file("index.html"))
@@ -196,4 +468,56 @@ async fn main() -> anyhow::Result<()> {
         .run()
         .await?;
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test;
+    use stv::ballot_metadata::{Candidate, ElectionName};
+    use stv::ballot_paper::BTL;
+
+    /// Four candidates for two vacancies. A gets a quota on first preferences, and D's preferences then elect B.
+    fn small_election() -> ElectionData {
+        ElectionData {
+            metadata: ElectionMetadata {
+                name: ElectionName { year: "2024".to_string(), authority: "Test".to_string(), name: "Upload".to_string(), electorate: "Here".to_string(), modifications: vec![], comment: None },
+                candidates: vec![Candidate::from_name("A"),Candidate::from_name("B"),Candidate::from_name("C"),Candidate::from_name("D")],
+                parties: vec![],
+                source: vec![],
+                results: None,
+                vacancies: Some(NumberOfCandidates(2)),
+                enrolment: None,
+                secondary_vacancies: None,
+                excluded: vec![],
+                withdrawn_before_count: vec![],
+                tie_resolutions: Default::default()
+            },
+            atl: vec![],
+            atl_types: vec![],
+            atl_transfer_values: vec![],
+            btl: vec![
+                BTL{ candidates: vec![CandidateIndex(0)], n: 40 },
+                BTL{ candidates: vec![CandidateIndex(1)], n: 30 },
+                BTL{ candidates: vec![CandidateIndex(2)], n: 20 },
+                BTL{ candidates: vec![CandidateIndex(3),CandidateIndex(1)], n: 10 },
+            ],
+            btl_types: vec![],
+            btl_transfer_values: vec![],
+            atl_annotations: vec![],
+            btl_annotations: vec![],
+            informal: 0
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_count_uploaded_election() {
+        let app = test::init_service(actix_web::App::new().service(web::resource("/count").app_data(web::JsonConfig::default().limit(MAX_COUNT_UPLOAD_BYTES)).route(web::post().to(count)))).await;
+        let query = CountQuery{
+            data: small_election(),
+            options: RecountQuery{ excluded: vec![], candidates_to_be_elected: NumberOfCandidates(2), tie_resolutions: Default::default(), rules: Rules::FederalPost2021, vote_types: None, seed: None },
+        };
+        let req = test::TestRequest::post().uri("/count").set_json(&query).to_request();
+        let transcript : Result<PossibleTranscripts,String> = test::call_and_read_body_json(&app,req).await;
+        assert_eq!(transcript.unwrap().elected(),&vec![CandidateIndex(0),CandidateIndex(1)]);
+    }
+}
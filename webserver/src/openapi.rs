@@ -0,0 +1,71 @@
+// Copyright 2024 Andrew Conway.
+// This file is part of ConcreteSTV.
+// ConcreteSTV is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// ConcreteSTV is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! An OpenAPI 3 description of the JSON interface provided by the webserver, served at `/openapi.json`.
+//!
+//! The routes are described by `#[utoipa::path]` attributes on the handlers in main.rs. Types from other
+//! crates that are too complex to usefully describe (e.g. transcripts) are just given as objects.
+
+use actix_web::get;
+use actix_web::web::Json;
+use utoipa::OpenApi;
+use stv::find_vote::{FindMyVoteQuery, FindMyVoteResult, FindVoteHit, SearchMatchesWithSameScore};
+use stv::tie_resolution::TieResolutionsMadeByEC;
+use crate::find_election::{ElectionsOfOneType, ElectionsOfOneTypeAndYear};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title="ConcreteSTV",description="Counts and statistics for Australian STV elections. Most JSON responses are wrapped as {\"Ok\":value} or {\"Err\":message}; the schema given is that of the value."),
+    paths(crate::get_all_contests,crate::get_metadata,crate::get_info,crate::get_mean_preferences,crate::get_intent_table,crate::get_correlation,crate::get_first_preferences,crate::get_exhaustion_curve,crate::get_exhaustion_breakdown,crate::get_final_seat,crate::get_two_candidate_preferred,crate::get_who_got_votes,crate::get_effective_numbers,crate::get_viability_threshold,crate::get_find_btl_errors,crate::get_seat_stability,crate::find_my_vote,crate::get_data,crate::get_rules,crate::recount,crate::count,crate::certain_losers,crate::non_election_reasons,get_openapi),
+    components(schemas(crate::RecountQuery,crate::CountQuery,crate::CertainLosersQuery,crate::NonElectionReasonsQuery,TieResolutionsMadeByEC,FindMyVoteQuery,FindMyVoteResult,SearchMatchesWithSameScore,FindVoteHit,ElectionsOfOneType,ElectionsOfOneTypeAndYear))
+)]
+pub struct ApiDoc;
+
+/// This description of the interface.
+#[utoipa::path(responses((status=200,description="An OpenAPI 3 description of the webserver",body=Object)))]
+#[get("/openapi.json")]
+pub async fn get_openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_lists_every_route() {
+        let json = serde_json::to_string(&ApiDoc::openapi()).unwrap();
+        let spec : utoipa::openapi::OpenApi = serde_json::from_str(&json).unwrap();
+        let paths : Vec<&str> = spec.paths.paths.keys().map(|s|s.as_str()).collect();
+        assert_eq!(paths,vec![
+            "/certain_losers",
+            "/count",
+            "/get_all_contests.json",
+            "/non_election_reasons",
+            "/openapi.json",
+            "/rules.json",
+            "/{name}/{year}/{electorate}/Correlation.json",
+            "/{name}/{year}/{electorate}/EffectiveNumbers.json",
+            "/{name}/{year}/{electorate}/ExhaustionBreakdown.json",
+            "/{name}/{year}/{electorate}/ExhaustionCurve.json",
+            "/{name}/{year}/{electorate}/FinalSeat.json",
+            "/{name}/{year}/{electorate}/FirstPreferences.json",
+            "/{name}/{year}/{electorate}/IntentTable.json",
+            "/{name}/{year}/{electorate}/MeanPreferences.json",
+            "/{name}/{year}/{electorate}/RepeatedNumbers.json",
+            "/{name}/{year}/{electorate}/SeatStability.json",
+            "/{name}/{year}/{electorate}/TwoCandidatePreferred.json",
+            "/{name}/{year}/{electorate}/ViabilityThreshold.json",
+            "/{name}/{year}/{electorate}/WhoGotVotes.json",
+            "/{name}/{year}/{electorate}/data.stv",
+            "/{name}/{year}/{electorate}/find_my_vote",
+            "/{name}/{year}/{electorate}/info.json",
+            "/{name}/{year}/{electorate}/metadata.json",
+            "/{name}/{year}/{electorate}/recount",
+        ]);
+        assert!(spec.components.unwrap().schemas.contains_key("RecountQuery"));
+    }
+}